@@ -0,0 +1,73 @@
+" Run vimlfmt on lines of the current buffer with --porcelain, which starts its output with a line
+" saying what happened. Settings:
+"
+" g:vimlfmt_command         the command to run, with any options (default: 'vimlfmt')
+" g:vimlfmt_formatexpr      set 'formatexpr' in Vim script buffers so `gq` uses vimlfmt
+"                           (default: 1)
+" g:vimlfmt_format_on_save  format Vim script buffers before they are written (default: 0)
+
+" The command line for formatting lines first to last of the current buffer. Lines from the middle
+" of a buffer are parsed as if they were in a function, and indented as the first of them is.
+function! vimlfmt#command(first, last) abort
+  let command = get(g:, 'vimlfmt_command', 'vimlfmt') . ' --porcelain'
+  if a:first > 1 || a:last < line('$')
+    let indent = indent(a:first) / 2
+    let command .= ' --context function --initial-indent ' . indent
+  endif
+  if !empty(expand('%'))
+    let command .= ' --stdin-filepath ' . shellescape(expand('%'))
+  endif
+  return command
+endfunction
+
+" Format lines first to last of the current buffer. Returns 0 if they were formatted, or already
+" were, and 1 if they couldn't be, after reporting why.
+function! vimlfmt#format(first, last) abort
+  let command = vimlfmt#command(a:first, a:last)
+  let output = systemlist(command, getline(a:first, a:last))
+  let header = get(output, 0, '')
+  if header ==# 'unchanged'
+    return 0
+  elseif header ==# 'formatted'
+    call s:replace(a:first, a:last, output[1:])
+    return 0
+  endif
+  let error = matchlist(header, '\v^error (\d+) (\d+) (.*)')
+  if !empty(error)
+    let line = a:first + str2nr(error[1]) - 1
+    call s:report(printf('line %d, column %s: %s', line, error[2], error[3]))
+  elseif header =~# '^failed '
+    call s:report(header[len('failed '):])
+  else
+    " e.g. the command isn't installed
+    call s:report(join(output, ' '))
+  endif
+  return 1
+endfunction
+
+" For 'formatexpr'. Falls back to Vim's own formatting for text typed in insert mode.
+function! vimlfmt#formatexpr() abort
+  if mode() =~# '^[iR]'
+    return 1
+  endif
+  call vimlfmt#format(v:lnum, v:lnum + v:count - 1)
+  return 0
+endfunction
+
+function! s:replace(first, last, lines) abort
+  let view = winsaveview()
+  let old = a:last - a:first + 1
+  if len(a:lines) < old
+    silent execute (a:first + len(a:lines)) . ',' . a:last . 'delete _'
+  elseif len(a:lines) > old
+    call append(a:last, repeat([''], len(a:lines) - old))
+  endif
+  call setline(a:first, a:lines)
+  call winrestview(view)
+endfunction
+
+function! s:report(message) abort
+  echohl ErrorMsg
+  echomsg 'vimlfmt: ' . a:message
+  echohl None
+endfunction
@@ -0,0 +1,31 @@
+-- Neovim interface to the plugin in plugin/vimlfmt.vim and autoload/vimlfmt.vim, which does the
+-- work, e.g.
+--
+--   require('vimlfmt').setup({ format_on_save = true })
+
+local M = {}
+
+-- Set the plugin's settings: `command`, `formatexpr` and `format_on_save` are
+-- g:vimlfmt_command, g:vimlfmt_formatexpr and g:vimlfmt_format_on_save.
+function M.setup(opts)
+  opts = opts or {}
+  if opts.command ~= nil then
+    vim.g.vimlfmt_command = opts.command
+  end
+  if opts.formatexpr ~= nil then
+    vim.g.vimlfmt_formatexpr = opts.formatexpr and 1 or 0
+  end
+  if opts.format_on_save ~= nil then
+    vim.g.vimlfmt_format_on_save = opts.format_on_save and 1 or 0
+  end
+end
+
+-- Format lines `first` to `last` of the current buffer, all of them by default. Returns whether
+-- they were formatted, or already were.
+function M.format(first, last)
+  first = first or 1
+  last = last or vim.fn.line('$')
+  return vim.fn['vimlfmt#format'](first, last) == 0
+end
+
+return M
@@ -0,0 +1,180 @@
+//! Generate the built-in command table from commands.csv, checking it as we go so mistakes in
+//! the data are build errors rather than commands that quietly parse wrong.
+
+use std::{collections::HashMap, env, fmt::Write as _, fs, path::Path};
+
+const TARGETS: &[&str] = &[
+    "after-vim74",
+    "after-vim82",
+    "vim-only",
+    "vim-only-after-vim74",
+    "vim9-only",
+    "neovim-only",
+];
+
+struct Row {
+    line: usize,
+    name: String,
+    minlen: usize,
+    parser: String,
+    flags: Vec<String>,
+    targets: String,
+}
+
+fn is_identifier(s: &str) -> bool {
+    s.chars().next().is_some_and(|c| c.is_ascii_uppercase())
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn parse_row(line: usize, text: &str) -> Result<Row, String> {
+    let fields = text.split(',').map(str::trim).collect::<Vec<&str>>();
+    if fields.len() != 5 {
+        return Err(format!("expected 5 fields, found {}", fields.len()));
+    }
+    let name = fields[0].to_string();
+    if name.is_empty() {
+        return Err("empty command name".to_string());
+    }
+    let minlen = fields[1]
+        .parse::<usize>()
+        .map_err(|_| format!("invalid minlen for {}: {}", name, fields[1]))?;
+    if minlen == 0 || minlen > name.len() {
+        return Err(format!(
+            "minlen for {} must be from 1 to {}",
+            name,
+            name.len()
+        ));
+    }
+    let parser = fields[2].to_string();
+    if !is_identifier(&parser) {
+        return Err(format!("invalid parser for {}: {}", name, parser));
+    }
+    let flags = if fields[3].is_empty() {
+        vec![]
+    } else {
+        fields[3]
+            .split('|')
+            .map(|f| f.trim().to_string())
+            .collect::<Vec<String>>()
+    };
+    if let Some(flag) = flags.iter().find(|f| !is_identifier(f)) {
+        return Err(format!("invalid flag for {}: {}", name, flag));
+    }
+    let targets = fields[4].to_string();
+    if !targets.is_empty() && !TARGETS.contains(&targets.as_str()) {
+        return Err(format!("invalid targets for {}: {}", name, targets));
+    }
+    Ok(Row {
+        line,
+        name,
+        minlen,
+        parser,
+        flags,
+        targets,
+    })
+}
+
+fn read_rows(source: &str) -> Result<Vec<Row>, String> {
+    let mut rows: Vec<Row> = vec![];
+    let mut header = false;
+    for (i, text) in source.lines().enumerate() {
+        let line = i + 1;
+        if text.trim().is_empty() || text.starts_with('#') {
+            continue;
+        }
+        if !header {
+            header = true;
+            continue;
+        }
+        let row = parse_row(line, text).map_err(|e| format!("commands.csv:{}: {}", line, e))?;
+        if let Some(other) = rows.iter().find(|r| r.name == row.name) {
+            return Err(format!(
+                "commands.csv:{}: {} is already defined on line {}",
+                line, row.name, other.line
+            ));
+        }
+        rows.push(row);
+    }
+    // Abbreviations go to the last command that accepts them, which must not take a command's
+    // full name away from it.
+    let mut owners = HashMap::new();
+    for row in &rows {
+        for len in row.minlen..=row.name.len() {
+            owners.insert(&row.name[..len], row);
+        }
+    }
+    for row in &rows {
+        let owner = owners[row.name.as_str()];
+        if owner.name != row.name {
+            return Err(format!(
+                "commands.csv:{}: {} can't be used, since it abbreviates {} on line {}",
+                row.line, row.name, owner.name, owner.line
+            ));
+        }
+    }
+    Ok(rows)
+}
+
+fn generate_commands(rows: &[Row]) -> String {
+    let mut rv = String::from("pub(crate) fn command_vec() -> Vec<Command> {\n    vec![\n");
+    for row in rows {
+        let flags = if row.flags.is_empty() {
+            "Flag::empty()".to_string()
+        } else {
+            row.flags
+                .iter()
+                .map(|f| format!("Flag::{}", f))
+                .collect::<Vec<String>>()
+                .join(" | ")
+        };
+        writeln!(
+            rv,
+            "        Command::new({:?}, {}, {}, ParserKind::{}),",
+            row.name, row.minlen, flags, row.parser
+        )
+        .unwrap();
+    }
+    rv.push_str("    ]\n}\n");
+    rv
+}
+
+fn generate_targets(rows: &[Row]) -> String {
+    let mut rv =
+        String::from("fn command_since(name: &str) -> Option<Since> {\n    match name {\n");
+    for target in TARGETS {
+        let names = rows
+            .iter()
+            .filter(|r| r.targets == *target)
+            .map(|r| format!("{:?}", r.name))
+            .collect::<Vec<String>>();
+        if !names.is_empty() {
+            writeln!(
+                rv,
+                "        {} => Some({}),",
+                names.join(" | "),
+                target.to_uppercase().replace('-', "_")
+            )
+            .unwrap();
+        }
+    }
+    rv.push_str("        _ => None,\n    }\n}\n");
+    rv
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=commands.csv");
+    let source = fs::read_to_string("commands.csv").expect("can't read commands.csv");
+    let rows = read_rows(&source).unwrap_or_else(|e| panic!("{}", e));
+    let out = env::var("OUT_DIR").unwrap();
+    fs::write(
+        Path::new(&out).join("commands.rs"),
+        generate_commands(&rows),
+    )
+    .unwrap();
+    fs::write(
+        Path::new(&out).join("command_since.rs"),
+        generate_targets(&rows),
+    )
+    .unwrap();
+}
@@ -0,0 +1,223 @@
+//! A syntax tree kept in one vector, as an alternative to the tree of boxed
+//! [Node](../enum.Node.html)s the parser returns. Every node of a boxed tree is its own
+//! allocation the size of the largest kind of node; an [Ast](struct.Ast.html) stores each node in
+//! a small fixed-size record, refers to nodes by [NodeId](struct.NodeId.html), and is freed all
+//! at once. [parse_lines_arena](../fn.parse_lines_arena.html) builds one a statement at a time,
+//! so the boxed tree of the whole script never exists.
+//!
+//! An arena node only has what is common to every kind of node: its kind, its position, its
+//! place in the tree and, for nodes without children, its text. Code that needs the fields of a
+//! particular kind of node can find the boxed node an arena node came from with
+//! [Ast::find](struct.Ast.html#method.find).
+
+use crate::{Node, Position, Trivia};
+use std::ops::Index;
+
+/// The index of a node in an [Ast](struct.Ast.html). Nodes are numbered in the order they appear
+/// in the source, parents before their children, so the root is always the first.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+pub struct NodeId(u32);
+
+impl NodeId {
+    /// The position of the node in [Ast::nodes](struct.Ast.html#method.nodes).
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// A node of an [Ast](struct.Ast.html).
+#[derive(Debug, PartialEq, Clone)]
+pub struct ArenaNode {
+    /// What kind of node this is, as [Node::kind](../enum.Node.html#method.kind) gives it, e.g.
+    /// `"Call"`.
+    pub kind: &'static str,
+    pub pos: Position,
+    /// For a node without children, how it displays, e.g. `x` for an identifier or `'a'` for a
+    /// string. `None` for nodes with children.
+    pub value: Option<Box<str>>,
+    parent: Option<NodeId>,
+    next_sibling: Option<NodeId>,
+    // the index after the last of the node's descendants
+    end: u32,
+}
+
+/// A syntax tree in one vector. See the [module documentation](index.html).
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Ast {
+    nodes: Vec<ArenaNode>,
+    trivia: Vec<Trivia>,
+}
+
+impl Ast {
+    /// An arena with only a [TopLevel](../enum.Node.html#variant.TopLevel) node at `pos`, for
+    /// statements to be added to with [push_statement](#method.push_statement).
+    pub(crate) fn top_level(pos: Position, trivia: Vec<Trivia>) -> Self {
+        let mut ast = Self {
+            nodes: vec![],
+            trivia,
+        };
+        ast.push(None, "TopLevel", pos, None);
+        ast
+    }
+
+    /// Add a statement, and everything in it, to the end of the body of the root.
+    pub(crate) fn push_statement(&mut self, node: &Node) {
+        // the last statement so far is the outermost ancestor of the last node below the root
+        let mut last = Some(NodeId(self.nodes.len() as u32 - 1)).filter(|&id| id != self.root());
+        while let Some(parent) = last.and_then(|id| self.parent(id)) {
+            if parent == self.root() {
+                break;
+            }
+            last = Some(parent);
+        }
+        self.push_tree(Some(self.root()), last, node);
+        self.nodes[0].end = self.nodes.len() as u32;
+    }
+
+    /// Copy a boxed tree into an arena.
+    pub fn from_node(node: &Node) -> Self {
+        let mut ast = Self::default();
+        if let Node::TopLevel { trivia, .. } = node {
+            ast.trivia = trivia.clone();
+        }
+        ast.push_tree(None, None, node);
+        ast
+    }
+
+    fn push(
+        &mut self,
+        parent: Option<NodeId>,
+        kind: &'static str,
+        pos: Position,
+        value: Option<Box<str>>,
+    ) -> NodeId {
+        let id = NodeId(self.nodes.len() as u32);
+        self.nodes.push(ArenaNode {
+            kind,
+            pos,
+            value,
+            parent,
+            next_sibling: None,
+            end: id.0 + 1,
+        });
+        id
+    }
+
+    fn push_tree(&mut self, parent: Option<NodeId>, previous: Option<NodeId>, node: &Node) {
+        let children = node.children();
+        let value = if children.is_empty() {
+            Some(node.to_string().into_boxed_str())
+        } else {
+            None
+        };
+        let id = self.push(parent, node.kind(), node.pos(), value);
+        if let Some(previous) = previous {
+            self.nodes[previous.index()].next_sibling = Some(id);
+        }
+        let mut previous = None;
+        for child in children {
+            let child_id = NodeId(self.nodes.len() as u32);
+            self.push_tree(Some(id), previous, child);
+            previous = Some(child_id);
+        }
+        self.nodes[id.index()].end = self.nodes.len() as u32;
+    }
+
+    /// The root of the tree, a [TopLevel](../enum.Node.html#variant.TopLevel) node for a parsed
+    /// script.
+    pub fn root(&self) -> NodeId {
+        NodeId(0)
+    }
+
+    /// Every node, in the order of their ids.
+    pub fn nodes(&self) -> &[ArenaNode] {
+        &self.nodes
+    }
+
+    /// The trivia of the script, as in its [TopLevel](../enum.Node.html#variant.TopLevel) node.
+    pub fn trivia(&self) -> &[Trivia] {
+        &self.trivia
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self[id].parent
+    }
+
+    /// The direct children of a node, in the order [Node::children](../enum.Node.html#method.children)
+    /// has them.
+    pub fn children(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        let first = Some(NodeId(id.0 + 1)).filter(|first| first.0 < self[id].end);
+        std::iter::successors(first, move |&child| self[child].next_sibling)
+    }
+
+    /// The nodes inside a node, at any depth, in order. They are the ids right after its own.
+    pub fn descendants(&self, id: NodeId) -> impl Iterator<Item = NodeId> {
+        (id.0 + 1..self[id].end).map(NodeId)
+    }
+
+    /// The boxed node that `id` was made from, given the boxed tree the arena was made from or
+    /// was parsed from the same source as. `None` if the trees don't match.
+    pub fn find<'a>(&self, id: NodeId, root: &'a Node) -> Option<&'a Node> {
+        let mut path = vec![];
+        let mut current = id;
+        while let Some(parent) = self.parent(current) {
+            path.push(self.children(parent).position(|c| c == current)?);
+            current = parent;
+        }
+        let mut node = root;
+        for &i in path.iter().rev() {
+            node = *node.children().get(i)?;
+        }
+        Some(node).filter(|node| node.kind() == self[id].kind && node.pos() == self[id].pos)
+    }
+}
+
+impl Index<NodeId> for Ast {
+    type Output = ArenaNode;
+
+    fn index(&self, id: NodeId) -> &ArenaNode {
+        &self.nodes[id.index()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_lines;
+
+    #[test]
+    fn test_ast() {
+        let source = [
+            "\" comment",
+            "function! s:F(x) abort",
+            "  return a:x + 1",
+            "endfunction",
+            "echo s:F(1)",
+        ];
+        let node = parse_lines(&source).unwrap();
+        let ast = Ast::from_node(&node);
+        assert_eq!(ast, crate::parse_lines_arena(&source).unwrap());
+        let root = ast.root();
+        assert_eq!(ast[root].kind, "TopLevel");
+        let statements = ast.children(root).collect::<Vec<_>>();
+        assert_eq!(
+            statements.iter().map(|&s| ast[s].kind).collect::<Vec<_>>(),
+            vec!["Comment", "Function", "Echo"]
+        );
+        assert_eq!(ast.descendants(root).count(), ast.nodes().len() - 1);
+        let plus = ast
+            .descendants(statements[1])
+            .find(|&id| ast[id].kind == "BinaryOp")
+            .unwrap();
+        assert_eq!(ast[plus].value, None);
+        assert_eq!(ast.parent(ast.parent(plus).unwrap()), Some(statements[1]));
+        let operands = ast.children(plus).collect::<Vec<_>>();
+        assert_eq!(ast[operands[0]].value.as_deref(), Some("a:x"));
+        assert_eq!(ast[operands[1]].value.as_deref(), Some("1"));
+        for id in ast.descendants(root) {
+            let boxed = ast.find(id, &node).unwrap();
+            assert_eq!(ast[id].pos, boxed.pos());
+        }
+        assert!(ast.find(plus, &parse_lines(&["echo 1"]).unwrap()).is_none());
+    }
+}
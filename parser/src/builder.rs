@@ -0,0 +1,749 @@
+use crate::{
+    command::{commands, CommandMap, ParserKind},
+    keys::split_keys,
+    parse_expression,
+    parser::Result,
+    range::Range,
+    ErrorCode, Node, ParseError, Position,
+};
+use std::sync::Arc;
+
+const LET_OPERATORS: &[&str] = &["=", "+=", "-=", "*=", "/=", "%=", ".=", "..="];
+const FUNCTION_ATTRS: &[&str] = &["abort", "closure", "dict", "range"];
+const MAPPING_ATTRS: &[&str] = &[
+    "buffer", "expr", "nowait", "script", "silent", "special", "unique",
+];
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn is_function_name(s: &str) -> bool {
+    let (name, script_local) = match s.strip_prefix("s:").or_else(|| s.strip_prefix("<SID>")) {
+        Some(name) => (name, true),
+        None => (s.strip_prefix("g:").unwrap_or(s), false),
+    };
+    (script_local || name.starts_with(|c: char| c.is_ascii_uppercase()) || name.contains('#'))
+        && !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '#')
+}
+
+fn end() -> Option<Box<Node>> {
+    Some(Box::new(Node::End {
+        pos: Position::default(),
+        mods: vec![],
+    }))
+}
+
+/// Builds a tree of [Nodes](enum.Node.html) in code rather than by parsing source, e.g. to
+/// generate a plugin's mappings from a table. The result of [build](#method.build) is a
+/// [TopLevel](enum.Node.html#variant.TopLevel) node just like one returned by
+/// [parse_lines](fn.parse_lines.html), so it can be handed to a formatter to produce VimL.
+///
+/// Expressions are given as strings and parsed with
+/// [parse_expression](fn.parse_expression.html). Every method checks what it is given against the
+/// same rules the parser enforces, e.g. that `return` is only used inside a function and `break`
+/// only inside a loop. The first problem found is kept and returned by `build`; once there is an
+/// error the remaining calls do nothing.
+///
+/// Statements that contain other statements take a closure which is passed a builder for the
+/// body:
+/// ```text
+/// NodeBuilder::new()
+///     .function("s:Greet", &["name"], &["abort"], |body| {
+///         body.if_("empty(a:name)", |then| then.return_(Some("'hello'")))
+///             .return_(Some("'hello ' .. a:name"))
+///     })
+///     .build()
+/// ```
+pub struct NodeBuilder {
+    body: Vec<Node>,
+    error: Option<ParseError>,
+    in_function: bool,
+    in_loop: bool,
+    commands: Arc<CommandMap>,
+}
+
+impl Default for NodeBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NodeBuilder {
+    /// A builder for the top level of a script.
+    pub fn new() -> Self {
+        Self {
+            body: vec![],
+            error: None,
+            in_function: false,
+            in_loop: false,
+            commands: commands(),
+        }
+    }
+
+    /// Finish building, returning a [TopLevel](enum.Node.html#variant.TopLevel) node, or the
+    /// first error found while building.
+    pub fn build(self) -> Result<Node> {
+        match self.error {
+            Some(e) => Err(e),
+            None => Ok(Node::TopLevel {
+                pos: Position::default(),
+                body: self.body,
+                trivia: vec![],
+            }),
+        }
+    }
+
+    fn fail(&mut self, code: ErrorCode, msg: String) {
+        if self.error.is_none() {
+            self.error = Some(ParseError::new(code, msg, Position::default()));
+        }
+    }
+
+    fn expr(&mut self, s: &str) -> Option<Node> {
+        match parse_expression(s) {
+            Ok(node) => Some(node),
+            Err(e) => {
+                if self.error.is_none() {
+                    self.error = Some(e);
+                }
+                None
+            }
+        }
+    }
+
+    fn exprs(&mut self, list: &[&str]) -> Option<Vec<Node>> {
+        list.iter().map(|s| self.expr(s)).collect()
+    }
+
+    fn block<F>(&mut self, in_function: bool, in_loop: bool, f: F) -> Vec<Node>
+    where
+        F: FnOnce(NodeBuilder) -> NodeBuilder,
+    {
+        let child = f(NodeBuilder {
+            body: vec![],
+            error: None,
+            in_function,
+            in_loop,
+            commands: Arc::clone(&self.commands),
+        });
+        if self.error.is_none() {
+            self.error = child.error;
+        }
+        child.body
+    }
+
+    fn push(mut self, node: Option<Node>) -> Self {
+        if let (None, Some(node)) = (&self.error, node) {
+            self.body.push(node);
+        }
+        self
+    }
+
+    /// Add a comment. `text` is everything after the `"`, so pass `" comment"` to get
+    /// `" comment`.
+    pub fn comment(self, text: &str) -> Self {
+        self.push(Some(Node::Comment {
+            pos: Position::default(),
+            value: text.to_string(),
+            trailing: false,
+        }))
+    }
+
+    /// Add an empty line.
+    pub fn blank_line(self) -> Self {
+        self.push(Some(Node::BlankLine {
+            pos: Position::default(),
+        }))
+    }
+
+    /// Add a `let` statement, e.g. `let_("g:foo", "+=", "1")`. A target that is a list, e.g.
+    /// `"[a, b]"`, unpacks the value into several variables.
+    pub fn let_(mut self, target: &str, op: &str, value: &str) -> Self {
+        if !LET_OPERATORS.contains(&op) {
+            self.fail(
+                ErrorCode::MissingEqualSign,
+                format!("E416: Missing equal sign: {}", op),
+            );
+        }
+        let (var, right) = (self.expr(target), self.expr(value));
+        let node = match (var, right) {
+            (Some(Node::List { items, .. }), Some(right)) => Some((None, items, right)),
+            (Some(var), Some(right)) => Some((Some(Box::new(var)), vec![], right)),
+            _ => None,
+        }
+        .map(|(var, list, right)| Node::Let {
+            pos: Position::default(),
+            mods: vec![],
+            cmd: "let".to_string(),
+            var,
+            list,
+            rest: None,
+            right: Box::new(right),
+            op: op.to_string(),
+        });
+        self.push(node)
+    }
+
+    /// Add a `call` statement. `expr` must be a function call, e.g. `"foo#bar(1)"`.
+    pub fn call(mut self, expr: &str) -> Self {
+        let node = match self.expr(expr) {
+            Some(left @ Node::Call { .. }) | Some(left @ Node::Method { .. }) => Some(left),
+            Some(_) => {
+                self.fail(
+                    ErrorCode::NotAFunctionCall,
+                    format!("Not a function call: {}", expr),
+                );
+                None
+            }
+            None => None,
+        };
+        self.push(node.map(|left| Node::ExCall {
+            pos: Position::default(),
+            mods: vec![],
+            range: Range::default(),
+            left: Box::new(left),
+        }))
+    }
+
+    /// Add an `echo`, `echon`, `echomsg` or `echoerr` statement.
+    pub fn echo(mut self, cmd: &str, list: &[&str]) -> Self {
+        if !["echo", "echon", "echomsg", "echoerr"].contains(&cmd) {
+            self.fail(
+                ErrorCode::NotAnEditorCommand,
+                format!("E492: Not an editor command: {}", cmd),
+            );
+        }
+        let node = self.exprs(list).map(|list| Node::Echo {
+            pos: Position::default(),
+            mods: vec![],
+            cmd: cmd.to_string(),
+            list,
+        });
+        self.push(node)
+    }
+
+    /// Add an `execute` statement.
+    pub fn execute(mut self, list: &[&str]) -> Self {
+        if list.is_empty() {
+            self.fail(
+                ErrorCode::ArgumentRequired,
+                "E471: Argument required".to_string(),
+            );
+        }
+        let node = self.exprs(list).map(|list| Node::Execute {
+            pos: Position::default(),
+            mods: vec![],
+            list,
+        });
+        self.push(node)
+    }
+
+    /// Add any other command, with its arguments written exactly as given. `command` must be the
+    /// full name of a built-in command, or a user command (starting with an uppercase letter).
+    pub fn command(mut self, command: &str, bang: bool, args: &str) -> Self {
+        if self.commands.get(command).is_none()
+            && !command.starts_with(|c: char| c.is_ascii_uppercase())
+        {
+            self.fail(
+                ErrorCode::NotAnEditorCommand,
+                format!("E492: Not an editor command: {}", command),
+            );
+        }
+        self.push(Some(Node::ExCmdRaw {
+            pos: Position::default(),
+            mods: vec![],
+            range: Range::default(),
+            command: command.to_string(),
+            bang,
+            args: args.to_string(),
+        }))
+    }
+
+    /// Add a mapping, e.g. `mapping("nnoremap", &["silent"], "<Leader>f", ":Files<CR>")`. If
+    /// `attrs` contains `"expr"`, `right` is parsed as an expression. A bang can be given with the
+    /// command, as in `map!`.
+    pub fn mapping(mut self, command: &str, attrs: &[&str], left: &str, right: &str) -> Self {
+        let (command, bang) = match command.strip_suffix('!') {
+            Some(command) => (command, true),
+            None => (command, false),
+        };
+        match self.commands.get(command) {
+            Some(c) if c.parser == ParserKind::Mapping => (),
+            _ => self.fail(
+                ErrorCode::NotAnEditorCommand,
+                format!("E492: Not a mapping command: {}", command),
+            ),
+        }
+        if let Some(attr) = attrs.iter().find(|a| !MAPPING_ATTRS.contains(a)) {
+            self.fail(
+                ErrorCode::InvalidArgument,
+                format!("E474: Invalid argument: <{}>", attr),
+            );
+        }
+        if left.is_empty() || left.contains(char::is_whitespace) {
+            self.fail(
+                ErrorCode::InvalidArgument,
+                format!("E474: Invalid argument: {}", left),
+            );
+        }
+        let right_expr = if attrs.contains(&"expr") {
+            match self.expr(right) {
+                Some(expr) => Some(Box::new(expr)),
+                None => return self,
+            }
+        } else {
+            None
+        };
+        self.push(Some(Node::Mapping {
+            pos: Position::default(),
+            mods: vec![],
+            command: command.to_string(),
+            bang,
+            left: left.to_string(),
+            right: if right_expr.is_some() {
+                String::new()
+            } else {
+                right.to_string()
+            },
+            right_keys: if right_expr.is_some() {
+                vec![]
+            } else {
+                split_keys(right)
+            },
+            right_expr,
+            attrs: attrs.iter().map(|a| a.to_string()).collect(),
+        }))
+    }
+
+    /// Add a function definition. `name` must be a valid function name, e.g. `"s:Foo"` or
+    /// `"plugin#foo"`; `args` are the parameter names (the last may be `"..."`); `attrs` may
+    /// contain any of `"abort"`, `"closure"`, `"dict"` and `"range"`.
+    pub fn function<F>(mut self, name: &str, args: &[&str], attrs: &[&str], body: F) -> Self
+    where
+        F: FnOnce(NodeBuilder) -> NodeBuilder,
+    {
+        let name_node = if name.contains('.') {
+            self.expr(name)
+        } else if is_function_name(name) {
+            Some(Node::Identifier {
+                pos: Position::default(),
+                value: name.to_string(),
+            })
+        } else {
+            self.fail(
+                ErrorCode::InvalidFunctionName,
+                format!(
+                    "E128: Function name must start with a capital or \"s:\": {}",
+                    name
+                ),
+            );
+            None
+        };
+        for (i, arg) in args.iter().enumerate() {
+            if args[..i].contains(arg) {
+                self.fail(
+                    ErrorCode::DuplicateArgument,
+                    format!("E853: Duplicate argument name: {}", arg),
+                );
+            } else if !is_identifier(arg) && (*arg != "..." || i + 1 < args.len()) {
+                self.fail(
+                    ErrorCode::IllegalArgument,
+                    format!("E125: Illegal argument: {}", arg),
+                );
+            }
+        }
+        if let Some(attr) = attrs.iter().find(|a| !FUNCTION_ATTRS.contains(a)) {
+            self.fail(
+                ErrorCode::InvalidArgument,
+                format!("E474: Invalid argument: {}", attr),
+            );
+        }
+        let body = self.block(true, false, body);
+        let node = name_node.map(|name| Node::Function {
+            pos: Position::default(),
+            mods: vec![],
+            bang: false,
+            name: Box::new(name),
+            args: args
+                .iter()
+                .map(|a| Node::Identifier {
+                    pos: Position::default(),
+                    value: a.to_string(),
+                })
+                .collect(),
+            body,
+            attrs: attrs.iter().map(|a| a.to_string()).collect(),
+            end: end(),
+        });
+        self.push(node)
+    }
+
+    /// Add a `return` statement. This is an error outside of a function.
+    pub fn return_(mut self, value: Option<&str>) -> Self {
+        if !self.in_function {
+            self.fail(
+                ErrorCode::ReturnOutsideFunction,
+                "E133: :return not inside a function".to_string(),
+            );
+        }
+        let left = match value.map(|v| self.expr(v)) {
+            Some(None) => return self,
+            Some(Some(left)) => Some(Box::new(left)),
+            None => None,
+        };
+        self.push(Some(Node::Return {
+            pos: Position::default(),
+            mods: vec![],
+            left,
+        }))
+    }
+
+    /// Add an `if` statement. Use [elseif](#method.elseif) and [else_](#method.else_) right
+    /// after this to add further clauses.
+    pub fn if_<F>(mut self, cond: &str, body: F) -> Self
+    where
+        F: FnOnce(NodeBuilder) -> NodeBuilder,
+    {
+        let cond = self.expr(cond);
+        let body = self.block(self.in_function, self.in_loop, body);
+        let node = cond.map(|cond| Node::If {
+            pos: Position::default(),
+            mods: vec![],
+            cond: Box::new(cond),
+            elseifs: vec![],
+            else_: None,
+            body,
+            end: end(),
+        });
+        self.push(node)
+    }
+
+    /// Add an `elseif` clause to the `if` statement that was added last.
+    pub fn elseif<F>(mut self, cond: &str, body: F) -> Self
+    where
+        F: FnOnce(NodeBuilder) -> NodeBuilder,
+    {
+        let cond = self.expr(cond);
+        let body = self.block(self.in_function, self.in_loop, body);
+        match (self.body.last_mut(), cond) {
+            (
+                Some(Node::If {
+                    elseifs,
+                    else_: None,
+                    ..
+                }),
+                Some(cond),
+            ) => elseifs.push(Node::ElseIf {
+                pos: Position::default(),
+                mods: vec![],
+                cond: Box::new(cond),
+                body,
+            }),
+            (_, Some(_)) => self.fail(
+                ErrorCode::ElseIfWithoutIf,
+                "E582: :elseif without :if".to_string(),
+            ),
+            _ => (),
+        }
+        self
+    }
+
+    /// Add an `else` clause to the `if` statement that was added last.
+    pub fn else_<F>(mut self, body: F) -> Self
+    where
+        F: FnOnce(NodeBuilder) -> NodeBuilder,
+    {
+        let body = self.block(self.in_function, self.in_loop, body);
+        match self.body.last_mut() {
+            Some(Node::If {
+                else_: e @ None, ..
+            }) => {
+                *e = Some(Box::new(Node::Else {
+                    pos: Position::default(),
+                    mods: vec![],
+                    body,
+                }))
+            }
+            _ => self.fail(
+                ErrorCode::ElseWithoutIf,
+                "E581: :else without :if".to_string(),
+            ),
+        }
+        self
+    }
+
+    /// Add a `for` loop. A variable that is a list, e.g. `"[key, value]"`, unpacks each item into
+    /// several variables.
+    pub fn for_<F>(mut self, var: &str, iterable: &str, body: F) -> Self
+    where
+        F: FnOnce(NodeBuilder) -> NodeBuilder,
+    {
+        let (var, right) = (self.expr(var), self.expr(iterable));
+        let body = self.block(self.in_function, true, body);
+        let node = match (var, right) {
+            (Some(Node::List { items, .. }), Some(right)) => Some((None, items, right)),
+            (Some(var), Some(right)) => Some((Some(Box::new(var)), vec![], right)),
+            _ => None,
+        }
+        .map(|(var, list, right)| Node::For {
+            pos: Position::default(),
+            mods: vec![],
+            var,
+            list,
+            rest: None,
+            right: Box::new(right),
+            body,
+            end: end(),
+        });
+        self.push(node)
+    }
+
+    /// Add a `while` loop.
+    pub fn while_<F>(mut self, cond: &str, body: F) -> Self
+    where
+        F: FnOnce(NodeBuilder) -> NodeBuilder,
+    {
+        let cond = self.expr(cond);
+        let body = self.block(self.in_function, true, body);
+        let node = cond.map(|cond| Node::While {
+            pos: Position::default(),
+            mods: vec![],
+            cond: Box::new(cond),
+            body,
+            end: end(),
+        });
+        self.push(node)
+    }
+
+    fn loop_command(mut self, command: &str) -> Self {
+        if !self.in_loop {
+            if command == "break" {
+                self.fail(
+                    ErrorCode::BreakWithoutLoop,
+                    "E587: :break without :while or :for".to_string(),
+                );
+            } else {
+                self.fail(
+                    ErrorCode::ContinueWithoutLoop,
+                    "E586: :continue without :while or :for".to_string(),
+                );
+            }
+        }
+        self.push(Some(Node::ExCmdRaw {
+            pos: Position::default(),
+            mods: vec![],
+            range: Range::default(),
+            command: command.to_string(),
+            bang: false,
+            args: String::new(),
+        }))
+    }
+
+    /// Add a `break` statement. This is an error outside of a loop.
+    pub fn break_(self) -> Self {
+        self.loop_command("break")
+    }
+
+    /// Add a `continue` statement. This is an error outside of a loop.
+    pub fn continue_(self) -> Self {
+        self.loop_command("continue")
+    }
+
+    /// Add a `try` statement. Use [catch](#method.catch) and [finally](#method.finally) right
+    /// after this to add further clauses.
+    pub fn try_<F>(mut self, body: F) -> Self
+    where
+        F: FnOnce(NodeBuilder) -> NodeBuilder,
+    {
+        let body = self.block(self.in_function, self.in_loop, body);
+        self.push(Some(Node::Try {
+            pos: Position::default(),
+            mods: vec![],
+            body,
+            catches: vec![],
+            finally: None,
+            end: end(),
+        }))
+    }
+
+    /// Add a `catch` clause to the `try` statement that was added last. `pattern` is written as
+    /// given, without the surrounding `/`s, e.g. `Some("^Vim:Interrupt$")`.
+    pub fn catch<F>(mut self, pattern: Option<&str>, body: F) -> Self
+    where
+        F: FnOnce(NodeBuilder) -> NodeBuilder,
+    {
+        let body = self.block(self.in_function, self.in_loop, body);
+        match self.body.last_mut() {
+            Some(Node::Try {
+                catches,
+                finally: None,
+                ..
+            }) => catches.push(Node::Catch {
+                pos: Position::default(),
+                mods: vec![],
+                pattern: pattern.map(|p| p.to_string()),
+                body,
+            }),
+            Some(Node::Try { .. }) => self.fail(
+                ErrorCode::CatchAfterFinally,
+                "E604: :catch after :finally".to_string(),
+            ),
+            _ => self.fail(
+                ErrorCode::CatchWithoutTry,
+                "E603: :catch without :try".to_string(),
+            ),
+        }
+        self
+    }
+
+    /// Add a `finally` clause to the `try` statement that was added last.
+    pub fn finally<F>(mut self, body: F) -> Self
+    where
+        F: FnOnce(NodeBuilder) -> NodeBuilder,
+    {
+        let body = self.block(self.in_function, self.in_loop, body);
+        match self.body.last_mut() {
+            Some(Node::Try {
+                finally: f @ None, ..
+            }) => {
+                *f = Some(Box::new(Node::Finally {
+                    pos: Position::default(),
+                    mods: vec![],
+                    body,
+                }))
+            }
+            _ => self.fail(
+                ErrorCode::FinallyWithoutTry,
+                "E606: :finally without :try".to_string(),
+            ),
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_lines;
+
+    #[test]
+    fn test_builder() {
+        let built = NodeBuilder::new()
+            .comment(" generated")
+            .let_("g:loaded_foo", "=", "1")
+            .mapping("nnoremap", &["silent"], "<Leader>f", ":Files<CR>")
+            .mapping(
+                "inoremap",
+                &["expr"],
+                "<Tab>",
+                "pumvisible() ? '<C-n>' : '<Tab>'",
+            )
+            .function("s:Foo", &["list", "..."], &["abort"], |body| {
+                body.for_("[i, x]", "items(a:list)", |body| {
+                    body.if_("x ==# ''", |body| body.continue_())
+                        .elseif("x ==# 'stop'", |body| body.break_())
+                        .else_(|body| body.call("add(l, x)"))
+                })
+                .try_(|body| body.echo("echo", &["1", "'two'"]))
+                .catch(Some("E123"), |body| body.command("nohlsearch", false, ""))
+                .finally(|body| body.execute(&["'normal! gg'"]))
+                .while_("v:true", |body| body.break_())
+                .return_(Some("l"))
+            })
+            .build()
+            .unwrap();
+        let parsed = parse_lines(&[
+            "\" generated",
+            "let g:loaded_foo = 1",
+            "nnoremap <silent> <Leader>f :Files<CR>",
+            "inoremap <expr> <Tab> pumvisible() ? '<C-n>' : '<Tab>'",
+            "function s:Foo(list, ...) abort",
+            "for [i, x] in items(a:list)",
+            "if x ==# ''",
+            "continue",
+            "elseif x ==# 'stop'",
+            "break",
+            "else",
+            "call add(l, x)",
+            "endif",
+            "endfor",
+            "try",
+            "echo 1 'two'",
+            "catch /E123/",
+            "nohlsearch",
+            "finally",
+            "execute 'normal! gg'",
+            "endtry",
+            "while v:true",
+            "break",
+            "endwhile",
+            "return l",
+            "endfunction",
+        ])
+        .unwrap();
+        assert_eq!(format!("{}", built), format!("{}", parsed));
+    }
+
+    #[test]
+    fn test_builder_errors() {
+        let code = |builder: NodeBuilder| builder.build().unwrap_err().code;
+        let b = NodeBuilder::new;
+        assert_eq!(code(b().return_(None)), ErrorCode::ReturnOutsideFunction);
+        assert_eq!(code(b().break_()), ErrorCode::BreakWithoutLoop);
+        assert_eq!(
+            code(b().while_("1", |b| b.function("F", &[], &[], |b| b.continue_()))),
+            ErrorCode::ContinueWithoutLoop
+        );
+        assert_eq!(code(b().else_(|b| b)), ErrorCode::ElseWithoutIf);
+        assert_eq!(
+            code(b().if_("1", |b| b).else_(|b| b).elseif("2", |b| b)),
+            ErrorCode::ElseIfWithoutIf
+        );
+        assert_eq!(code(b().catch(None, |b| b)), ErrorCode::CatchWithoutTry);
+        assert_eq!(
+            code(b().try_(|b| b).finally(|b| b).catch(None, |b| b)),
+            ErrorCode::CatchAfterFinally
+        );
+        assert_eq!(code(b().let_("x", "=", "1 +")), ErrorCode::UnexpectedToken);
+        assert_eq!(code(b().let_("x", "==", "1")), ErrorCode::MissingEqualSign);
+        assert_eq!(code(b().call("x")), ErrorCode::NotAFunctionCall);
+        assert_eq!(
+            code(b().command("frobnicate", false, "")),
+            ErrorCode::NotAnEditorCommand
+        );
+        assert_eq!(
+            code(b().mapping("set", &[], "a", "b")),
+            ErrorCode::NotAnEditorCommand
+        );
+        assert_eq!(
+            code(b().function("foo", &[], &[], |b| b)),
+            ErrorCode::InvalidFunctionName
+        );
+        assert_eq!(
+            code(b().function("Foo", &["a", "a"], &[], |b| b)),
+            ErrorCode::DuplicateArgument
+        );
+        assert_eq!(
+            code(b().function("Foo", &["...", "a"], &[], |b| b)),
+            ErrorCode::IllegalArgument
+        );
+        // the first error is the one reported
+        assert_eq!(
+            code(b().break_().return_(None)),
+            ErrorCode::BreakWithoutLoop
+        );
+        assert!(b()
+            .function("s:foo", &[], &["dict"], |b| b.return_(None))
+            .function("plugin#foo", &[], &[], |b| b)
+            .function("g:Foo", &[], &[], |b| b)
+            .function("<SID>foo", &[], &[], |b| b)
+            .build()
+            .is_ok());
+    }
+}
@@ -0,0 +1,39 @@
+//! Read-only access to the table of Vim's built-in commands the parser uses, e.g. to check how a
+//! command may be used or to complete command names.
+
+use crate::command::{self, Command};
+
+/// The built-in command `name` is the full name or an accepted abbreviation of, e.g. `echo` for
+/// `ec`.
+pub fn lookup(name: &str) -> Option<&'static Command> {
+    command::builtin(name)
+}
+
+/// Every built-in command, in alphabetical order of their full names.
+pub fn all() -> Vec<&'static Command> {
+    let mut commands = command::command_vec()
+        .iter()
+        .filter_map(|cmd| lookup(cmd.name()))
+        .collect::<Vec<&Command>>();
+    commands.sort_by(|a, b| a.name().cmp(b.name()));
+    commands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Flag, ParserKind};
+
+    #[test]
+    fn test_lookup() {
+        let echo = lookup("ec").unwrap();
+        assert_eq!(echo.name(), "echo");
+        assert_eq!(echo.minlen(), 2);
+        assert_eq!(echo.parser(), &ParserKind::Echo);
+        assert!(lookup("e").unwrap().flags().contains(Flag::BANG));
+        assert_eq!(lookup("e").unwrap().name(), "edit");
+        assert!(lookup("Foo").is_none());
+        assert!(all().iter().any(|cmd| cmd.name() == "echo"));
+        assert!(all().windows(2).all(|w| w[0].name() < w[1].name()));
+    }
+}
@@ -2,8 +2,9 @@ use super::Position;
 use crate::{
     command::{Command, Flag, ParserKind},
     modifier::Modifier,
+    range::Range,
 };
-use std::rc::Rc;
+use std::sync::Arc;
 
 #[derive(Debug, PartialEq, Clone)]
 pub(crate) struct ExArg {
@@ -12,9 +13,9 @@ pub(crate) struct ExArg {
     pub(crate) linepos: Position,
     pub(crate) cmdpos: Position,
     pub(crate) argpos: Position,
-    pub(crate) cmd: Rc<Command>,
+    pub(crate) cmd: Arc<Command>,
     pub(crate) modifiers: Vec<Modifier>,
-    pub(crate) range: Vec<String>,
+    pub(crate) range: Range,
 }
 
 impl Default for ExArg {
@@ -25,14 +26,14 @@ impl Default for ExArg {
             linepos: Position::empty(),
             cmdpos: Position::empty(),
             argpos: Position::empty(),
-            cmd: Rc::new(Command {
+            cmd: Arc::new(Command {
                 name: "Dummy".to_string(),
                 minlen: 0,
                 flags: Flag::empty(),
                 parser: ParserKind::UserCmd,
             }),
             modifiers: vec![],
-            range: vec![],
+            range: Range::default(),
         }
     }
 }
@@ -0,0 +1,75 @@
+/// A line of the table that follows `:loadkeymap`, as returned by
+/// [keymap_lines](fn.keymap_lines.html).
+#[derive(Debug, PartialEq, Clone)]
+pub enum KeymapLine {
+    /// The keys typed (`lhs`) and the text they insert (`rhs`), followed by whatever else is on
+    /// the line, which Vim ignores but keymap files use to name the character, e.g.
+    /// `a'  á  LATIN SMALL LETTER A WITH ACUTE`.
+    Entry {
+        lhs: String,
+        rhs: String,
+        comment: Option<String>,
+    },
+    /// A line starting with `"`. Holds everything after the `"`.
+    Comment(String),
+    Blank,
+}
+
+fn split_word(s: &str) -> (&str, &str) {
+    let end = s.find([' ', '\t']).unwrap_or(s.len());
+    (&s[..end], s[end..].trim_start_matches([' ', '\t']))
+}
+
+/// Split the lines that follow `:loadkeymap` (the arguments of its
+/// [ExCmdRaw](enum.Node.html#variant.ExCmdRaw) node, which are kept as they were written) into their
+/// columns. Returns `None` if a line has a left-hand side but no right-hand side, which Vim
+/// rejects with E791.
+pub fn keymap_lines(text: &str) -> Option<Vec<KeymapLine>> {
+    text.strip_prefix('\n')
+        .unwrap_or(text)
+        .split('\n')
+        .map(|line| {
+            let line = line.trim_start_matches([' ', '\t']);
+            if line.is_empty() {
+                return Some(KeymapLine::Blank);
+            }
+            if let Some(comment) = line.strip_prefix('"') {
+                return Some(KeymapLine::Comment(comment.to_string()));
+            }
+            let (lhs, rest) = split_word(line);
+            let (rhs, comment) = split_word(rest);
+            if rhs.is_empty() {
+                return None;
+            }
+            Some(KeymapLine::Entry {
+                lhs: lhs.to_string(),
+                rhs: rhs.to_string(),
+                comment: Some(comment.to_string()).filter(|c| !c.is_empty()),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keymap_lines() {
+        let entry = |lhs: &str, rhs: &str, comment: Option<&str>| KeymapLine::Entry {
+            lhs: lhs.to_string(),
+            rhs: rhs.to_string(),
+            comment: comment.map(|c| c.to_string()),
+        };
+        assert_eq!(
+            keymap_lines("\n\" vowels\na'\tá  LATIN SMALL LETTER A WITH ACUTE\n\n  <Space> _"),
+            Some(vec![
+                KeymapLine::Comment(" vowels".to_string()),
+                entry("a'", "á", Some("LATIN SMALL LETTER A WITH ACUTE")),
+                KeymapLine::Blank,
+                entry("<Space>", "_", None),
+            ])
+        );
+        assert_eq!(keymap_lines("\na A\nb"), None);
+    }
+}
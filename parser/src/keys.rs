@@ -0,0 +1,352 @@
+use std::{collections::HashMap, sync::LazyLock};
+
+/// A piece of a key sequence, such as the right-hand side of a mapping.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Key {
+    /// Keys typed as they are, e.g. `:Files` in `:Files<CR>`.
+    Text(String),
+    /// Key notation, e.g. `<CR>` or `<C-w>`. Holds what is between the angle brackets, as
+    /// written.
+    Special(String),
+    /// `<Plug>` and the name following it, e.g. `<Plug>(fzf-files)`. `notation` is what is
+    /// between the angle brackets of `<Plug>` as written, and `name` is `(fzf-files)`.
+    Plug { notation: String, name: String },
+    /// `<SID>`. Holds what is between the angle brackets, as written.
+    Sid(String),
+    /// A `<Cmd>` (or `<ScriptCmd>`) sequence, e.g. `<Cmd>update<CR>`. `notation` and `cr` are
+    /// what is between the angle brackets of the first and last keys as written, and `command`
+    /// is `update`.
+    Cmd {
+        notation: String,
+        command: String,
+        cr: String,
+    },
+}
+
+fn is_key_notation(inner: &str) -> bool {
+    let mut name = inner;
+    while name.len() > 2
+        && name.as_bytes()[1] == b'-'
+        && "CSMADTcsmadt".contains(name.as_bytes()[0] as char)
+    {
+        name = &name[2..];
+    }
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => !c.is_whitespace() && c != '<',
+        (Some(_), Some(_)) => name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+        _ => false,
+    }
+}
+
+/// The key notation at the start of `s`, if there is one, as `(inner, len)` where `inner` is
+/// what is between the angle brackets and `len` the length of the whole notation.
+pub fn key_notation(s: &str) -> Option<(&str, usize)> {
+    if !s.starts_with('<') {
+        return None;
+    }
+    let end = s.find('>')?;
+    let inner = &s[1..end];
+    if is_key_notation(inner) {
+        Some((inner, end + 1))
+    } else {
+        None
+    }
+}
+
+/// Split a key sequence (like the right-hand side of a mapping) into [Keys](enum.Key.html).
+/// Joining the pieces back together, writing each as it was written, gives the original string.
+pub fn split_keys(s: &str) -> Vec<Key> {
+    let mut keys = vec![];
+    let mut text = String::new();
+    let mut i = 0;
+    while i < s.len() {
+        let rest = &s[i..];
+        let (inner, len) = match key_notation(rest) {
+            Some(k) => k,
+            None => {
+                let c = rest.chars().next().unwrap();
+                text.push(c);
+                i += c.len_utf8();
+                continue;
+            }
+        };
+        if !text.is_empty() {
+            keys.push(Key::Text(text.split_off(0)));
+        }
+        i += len;
+        let lower = inner.to_lowercase();
+        if lower == "plug" {
+            let rest = &s[i..];
+            let name_len = if rest.starts_with('(') {
+                rest.find(')').map_or(rest.len(), |n| n + 1)
+            } else {
+                rest.find(|c: char| c == '<' || c.is_whitespace())
+                    .unwrap_or(rest.len())
+            };
+            keys.push(Key::Plug {
+                notation: inner.to_string(),
+                name: rest[..name_len].to_string(),
+            });
+            i += name_len;
+        } else if lower == "sid" {
+            keys.push(Key::Sid(inner.to_string()));
+        } else if let (true, Some(end)) = (
+            lower == "cmd" || lower == "scriptcmd",
+            s[i..].to_ascii_lowercase().find("<cr>"),
+        ) {
+            keys.push(Key::Cmd {
+                notation: inner.to_string(),
+                command: s[i..i + end].to_string(),
+                cr: s[i + end + 1..i + end + 3].to_string(),
+            });
+            i += end + 4;
+        } else {
+            keys.push(Key::Special(inner.to_string()));
+        }
+    }
+    if !text.is_empty() {
+        keys.push(Key::Text(text));
+    }
+    keys
+}
+
+/// The canonical spelling of a key name, the part of key notation after any modifiers (e.g.
+/// `CR` for `cr` or `PageUp` for `pageup`), or `None` if the key is not known. Key names are
+/// case-insensitive, so any casing is accepted.
+pub fn canonical_key(name: &str) -> Option<&'static str> {
+    static KEYS: LazyLock<HashMap<String, String>> = LazyLock::new(|| {
+        [
+            "BS",
+            "Bar",
+            "Bslash",
+            "CR",
+            "CSI",
+            "Cmd",
+            "CursorHold",
+            "Del",
+            "Delete",
+            "Down",
+            "EOL",
+            "End",
+            "Enter",
+            "Esc",
+            "FF",
+            "Help",
+            "Home",
+            "Ignore",
+            "Insert",
+            "Leader",
+            "Left",
+            "LeftDrag",
+            "LeftMouse",
+            "LeftRelease",
+            "LocalLeader",
+            "MiddleDrag",
+            "MiddleMouse",
+            "MiddleRelease",
+            "Mouse",
+            "NL",
+            "Nop",
+            "Nul",
+            "PageDown",
+            "PageUp",
+            "Plug",
+            "Return",
+            "Right",
+            "RightDrag",
+            "RightMouse",
+            "RightRelease",
+            "SID",
+            "ScriptCmd",
+            "ScrollWheelDown",
+            "ScrollWheelLeft",
+            "ScrollWheelRight",
+            "ScrollWheelUp",
+            "Space",
+            "Tab",
+            "Undo",
+            "Up",
+            "X1Drag",
+            "X1Mouse",
+            "X1Release",
+            "X2Drag",
+            "X2Mouse",
+            "X2Release",
+            "kComma",
+            "kDel",
+            "kDivide",
+            "kDown",
+            "kEnd",
+            "kEnter",
+            "kEqual",
+            "kHome",
+            "kInsert",
+            "kLeft",
+            "kMinus",
+            "kMultiply",
+            "kOrigin",
+            "kPageDown",
+            "kPageUp",
+            "kPlus",
+            "kPoint",
+            "kRight",
+            "kUp",
+            "lt",
+            "xCSI",
+            "xDown",
+            "xEnd",
+            "xHome",
+            "xLeft",
+            "xRight",
+            "xUp",
+            "zEnd",
+            "zHome",
+        ]
+        .iter()
+        .map(|k| k.to_string())
+        .chain((0..10).map(|n| format!("k{}", n)))
+        .chain((1..38).map(|n| format!("F{}", n)))
+        .chain((1..5).map(|n| format!("xF{}", n)))
+        .map(|k| (k.to_lowercase(), k))
+        .collect()
+    });
+    KEYS.get(name.to_lowercase().as_str()).map(|k| k.as_str())
+}
+
+/// Key notation with its modifiers and key name in their canonical spelling, e.g. `C-w` for
+/// `c-w` or `S-Tab` for `s-tab`. `inner` is what is between the angle brackets. Mouse keys may
+/// be prefixed with a click count (`2-LeftMouse`), which is kept as it is. Single-character key
+/// names are not changed, and neither is anything that isn't recognized.
+pub fn canonical_key_notation(inner: &str) -> String {
+    canonical_key_notation_with(inner, canonical_key)
+}
+
+/// As [canonical_key_notation](fn.canonical_key_notation.html), but the canonical spelling of key
+/// names is looked up with `lookup`.
+pub fn canonical_key_notation_with<'a, F>(inner: &str, lookup: F) -> String
+where
+    F: Fn(&str) -> Option<&'a str>,
+{
+    let mut rv = String::new();
+    let mut name = inner;
+    while name.len() > 2
+        && name.as_bytes()[1] == b'-'
+        && "CSMADTcsmadt".contains(name.as_bytes()[0] as char)
+    {
+        rv.push(name.as_bytes()[0].to_ascii_uppercase() as char);
+        rv.push('-');
+        name = &name[2..];
+    }
+    // a click count, e.g. the 2 in `2-LeftMouse`
+    let digits = name.len() - name.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if digits > 0 && name.len() > digits + 1 && name[digits..].starts_with('-') {
+        rv.push_str(&name[..=digits]);
+        name = &name[digits + 1..];
+    }
+    match lookup(name) {
+        Some(canonical) => rv.push_str(canonical),
+        None => rv.push_str(name),
+    }
+    rv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(keys: &[Key]) -> String {
+        keys.iter()
+            .map(|k| match k {
+                Key::Text(s) => s.clone(),
+                Key::Special(s) | Key::Sid(s) => format!("<{}>", s),
+                Key::Plug { notation, name } => format!("<{}>{}", notation, name),
+                Key::Cmd {
+                    notation,
+                    command,
+                    cr,
+                } => format!("<{}>{}<{}>", notation, command, cr),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_split_keys() {
+        let text = |s: &str| Key::Text(s.to_string());
+        let special = |s: &str| Key::Special(s.to_string());
+        assert_eq!(
+            split_keys(":Files<cr>"),
+            vec![text(":Files"), special("cr")]
+        );
+        assert_eq!(
+            split_keys("<Plug>(fzf-files)<c-w>x<plug>FooBar<SID>Baz"),
+            vec![
+                Key::Plug {
+                    notation: "Plug".to_string(),
+                    name: "(fzf-files)".to_string()
+                },
+                special("c-w"),
+                text("x"),
+                Key::Plug {
+                    notation: "plug".to_string(),
+                    name: "FooBar".to_string()
+                },
+                Key::Sid("SID".to_string()),
+                text("Baz"),
+            ]
+        );
+        assert_eq!(
+            split_keys("<cmd>call <SID>Foo()<Cr>j"),
+            vec![
+                Key::Cmd {
+                    notation: "cmd".to_string(),
+                    command: "call <SID>Foo()".to_string(),
+                    cr: "Cr".to_string(),
+                },
+                text("j"),
+            ]
+        );
+        assert_eq!(split_keys("<Cmd>echo"), vec![special("Cmd"), text("echo")]);
+        assert_eq!(
+            split_keys("a < b <> <lt>cr> <2-LeftMouse>"),
+            vec![
+                text("a < b <> "),
+                special("lt"),
+                text("cr> "),
+                special("2-LeftMouse"),
+            ]
+        );
+        for s in &[
+            "",
+            "<",
+            "<<",
+            "<>",
+            "x<",
+            "<Plug>",
+            "<Plug>(unclosed",
+            "ü<cr>ö",
+            ":s/<a b>/<c>/<CR>",
+        ] {
+            assert_eq!(&write(&split_keys(s)), s);
+        }
+    }
+
+    #[test]
+    fn test_canonical_key_notation() {
+        assert_eq!(canonical_key_notation("cr"), "CR");
+        assert_eq!(canonical_key_notation("c-w"), "C-w");
+        assert_eq!(canonical_key_notation("C-W"), "C-W");
+        assert_eq!(canonical_key_notation("s-tab"), "S-Tab");
+        assert_eq!(canonical_key_notation("m-s-f12"), "M-S-F12");
+        assert_eq!(canonical_key_notation("2-leftmouse"), "2-LeftMouse");
+        assert_eq!(canonical_key_notation("kpageup"), "kPageUp");
+        assert_eq!(canonical_key_notation("LEADER"), "Leader");
+        assert_eq!(canonical_key_notation("NotAKey"), "NotAKey");
+        assert_eq!(
+            canonical_key_notation_with("esc", |k| if k == "esc" { Some("ESC") } else { None }),
+            "ESC"
+        );
+    }
+}
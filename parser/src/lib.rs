@@ -1,18 +1,43 @@
 use crate::parser::Result;
 pub use crate::{
-    modifier::Modifier,
-    node::{BinaryOpKind, Node, UnaryOpKind},
+    arena::{ArenaNode, Ast, NodeId},
+    builder::NodeBuilder,
+    command::{valid_autocmds, AutocmdEvent, Command, Flag, ParserKind},
+    keymap::{keymap_lines, KeymapLine},
+    keys::{
+        canonical_key, canonical_key_notation, canonical_key_notation_with, key_notation,
+        split_keys, Key,
+    },
+    modifier::{sort_modifiers, Modifier, CANONICAL_ORDER},
+    node::{BinaryOpKind, LetTarget, Node, Trivia, UnaryOpKind},
+    option::{option_full_name, option_type, split_option, OptionScope, OptionType},
+    parser::MAX_EXPR_DEPTH,
+    range::{Address, LineSpec, Offset, Range},
+    target::Target,
+};
+use std::{
+    io::BufRead,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::Arc,
 };
-use lazy_static::lazy_static;
-use regex::Regex;
 
+pub mod arena;
+mod builder;
 mod command;
+pub mod commands;
 mod exarg;
+mod keymap;
+mod keys;
 mod modifier;
 mod node;
+mod option;
 mod parser;
+mod range;
 mod reader;
+mod target;
 mod token;
+pub mod transform;
 
 pub(crate) const EOF: char = '\x04';
 pub(crate) const EOL: char = '\n';
@@ -20,20 +45,326 @@ pub(crate) const EOL: char = '\n';
 /// Parse a list of lines, returning a Node upon success, or a [ParseError](struct.ParseError.html)
 /// upon failure. The node will be a [TopLevel](enum.Node.html#variant.TopLevel) variant.
 pub fn parse_lines(lines: &[&str]) -> Result<node::Node> {
-    let reader = reader::Reader::from_lines(lines);
-    let mut parser = parser::Parser::new(&reader);
-    parser.parse()
+    Parser::new().parse_lines(lines)
 }
 
 /// Parse a file, returning a Node upon success, or a [ParseError](struct.ParseError.html)
 /// upon failure. The node will be a [TopLevel](enum.Node.html#variant.TopLevel) variant.
 pub fn parse_file(path: &str) -> Result<node::Node> {
-    let reader = reader::Reader::from_file(path)?;
-    let mut parser = parser::Parser::new(&reader);
-    parser.parse()
+    Parser::new().parse_file(path)
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+/// Parse a file, as [parse_file](fn.parse_file.html), given any kind of path. Errors have the
+/// path in their [path](struct.ParseError.html#structfield.path).
+pub fn parse_path<P: AsRef<Path>>(path: P) -> Result<node::Node> {
+    Parser::new().parse_path(path)
+}
+
+/// Parse everything read from a reader, e.g. stdin or a file opened some other way, as
+/// [parse_file](fn.parse_file.html).
+pub fn parse_reader<R: BufRead>(reader: R) -> Result<node::Node> {
+    Parser::new().parse_reader(reader)
+}
+
+/// Parse a list of lines into an [Ast](arena/struct.Ast.html) rather than a tree of boxed nodes.
+/// This takes about as long as [parse_lines](fn.parse_lines.html) but needs much less memory for a
+/// large script; see the [arena](arena/index.html) module.
+pub fn parse_lines_arena(lines: &[&str]) -> Result<Ast> {
+    Parser::new().parse_lines_arena(lines)
+}
+
+/// Parse a list of lines one top-level statement at a time. See [ParseIter](struct.ParseIter.html).
+pub fn parse_iter(lines: &[&str]) -> ParseIter {
+    Parser::new().parse_iter(lines)
+}
+
+/// Parse a file one top-level statement at a time. See [ParseIter](struct.ParseIter.html).
+pub fn parse_file_iter(path: &str) -> Result<ParseIter> {
+    Parser::new().parse_file_iter(path)
+}
+
+/// Parse a single expression, such as the value of an `'statusline'` `%{}` item or the
+/// right-hand side of an `<expr>` mapping, returning a Node upon success, or a
+/// [ParseError](struct.ParseError.html) upon failure. The whole string must be one expression,
+/// optionally surrounded by whitespace; it may span several lines using line continuations.
+pub fn parse_expression(s: &str) -> Result<node::Node> {
+    let reader = reader::Reader::from_lines(&s.lines().collect::<Vec<&str>>());
+    let node = parser::ExprParser::new(&reader).parse()?;
+    reader.skip_white();
+    let c = reader.peek();
+    if c == EOF || (c == EOL && reader.peek_ahead(1) == EOF) {
+        Ok(node)
+    } else {
+        let rest = reader.peek_line();
+        Err(ParseError::new(
+            ErrorCode::TrailingCharacters,
+            format!("E488: Trailing characters: {}", rest),
+            reader.getpos(),
+        )
+        .with_found(rest))
+    }
+}
+
+/// A parser that can be taught about commands Vim itself doesn't have, e.g. ones defined by
+/// plugins. Without any extra commands it behaves exactly like [parse_lines](fn.parse_lines.html)
+/// and [parse_file](fn.parse_file.html), which treat unknown commands as user commands if they
+/// start with an uppercase letter and as errors otherwise.
+#[derive(Clone)]
+pub struct Parser {
+    commands: Arc<command::CommandMap>,
+    unknown_command: Option<Arc<parser::UnknownCommand>>,
+    target: Option<Target>,
+    context: Context,
+    cpoptions: String,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Parser {
+    /// A parser that knows about Vim's built-in commands.
+    pub fn new() -> Self {
+        Self {
+            commands: command::commands(),
+            unknown_command: None,
+            target: None,
+            context: Context::TopLevel,
+            cpoptions: String::new(),
+        }
+    }
+
+    /// Add commands to the parser's command table. These take precedence over built-in commands
+    /// with the same name or abbreviation.
+    pub fn with_commands(mut self, extra: Vec<Command>) -> Self {
+        command::extend_commands(Arc::make_mut(&mut self.commands), extra);
+        self
+    }
+
+    /// Only accept what the target editor has: commands it doesn't have are removed from the
+    /// command table, so they are errors like any other unknown command, and autocommand events
+    /// and expression syntax it doesn't have are errors too. Commands added with
+    /// [with_commands](#method.with_commands) after this are kept whatever the target.
+    pub fn with_target(mut self, target: Target) -> Self {
+        target.restrict_commands(Arc::make_mut(&mut self.commands));
+        self.target = Some(target);
+        self
+    }
+
+    /// Parse the input as if it were in the given context, rather than a whole script.
+    pub fn with_context(mut self, context: Context) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Read the input as if 'cpoptions' had this value when it started. This only matters if it
+    /// has the `C` flag, as Vim's does when it is started in Vi-compatible mode: lines starting
+    /// with a backslash are then not continuations of the line before them, until the input
+    /// itself changes 'cpoptions' (e.g. with `set cpo&vim`, as plugins do).
+    pub fn with_cpoptions(mut self, cpoptions: &str) -> Self {
+        self.cpoptions = cpoptions.to_string();
+        self
+    }
+
+    /// Set a callback to be consulted when a command is not in the command table. It is passed
+    /// the name of the command as written and returns how to parse it, or `None` to fall back to
+    /// the default behavior. Whatever it returns is remembered for the rest of the parse.
+    pub fn on_unknown_command<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&str) -> Option<Command> + Send + Sync + 'static,
+    {
+        self.unknown_command = Some(Arc::new(callback));
+        self
+    }
+
+    /// Parse a list of lines, as [parse_lines](fn.parse_lines.html).
+    pub fn parse_lines(&self, lines: &[&str]) -> Result<node::Node> {
+        let reader = reader::Reader::from_lines_with_cpoptions(lines, &self.cpoptions);
+        self.parse(reader)
+    }
+
+    /// Parse a file, as [parse_file](fn.parse_file.html).
+    pub fn parse_file(&self, path: &str) -> Result<node::Node> {
+        self.parse_path(path)
+    }
+
+    /// Parse a file, as [parse_path](fn.parse_path.html).
+    pub fn parse_path<P: AsRef<Path>>(&self, path: P) -> Result<node::Node> {
+        let path = path.as_ref();
+        reader::Reader::from_file(path, &self.cpoptions)
+            .map_err(ParseError::from)
+            .and_then(|reader| self.parse(reader))
+            .map_err(|e| e.with_path(path))
+    }
+
+    /// Parse everything read from a reader, as [parse_reader](fn.parse_reader.html).
+    pub fn parse_reader<R: BufRead>(&self, reader: R) -> Result<node::Node> {
+        self.parse(reader::Reader::from_reader(reader, &self.cpoptions)?)
+    }
+
+    /// Parse a list of lines into an arena, as [parse_lines_arena](fn.parse_lines_arena.html).
+    pub fn parse_lines_arena(&self, lines: &[&str]) -> Result<Ast> {
+        let statements = self.parse_iter(lines);
+        let mut ast = Ast::top_level(statements.parser.getpos(), statements.trivia().to_vec());
+        for statement in statements {
+            ast.push_statement(&statement?);
+        }
+        Ok(ast)
+    }
+
+    /// Parse a list of lines one top-level statement at a time, as
+    /// [parse_iter](fn.parse_iter.html).
+    pub fn parse_iter(&self, lines: &[&str]) -> ParseIter {
+        ParseIter {
+            parser: self.parser(reader::Reader::from_lines_with_cpoptions(
+                lines,
+                &self.cpoptions,
+            )),
+            target: self.target,
+            path: None,
+            failed: false,
+        }
+    }
+
+    /// Parse a file one top-level statement at a time, as
+    /// [parse_file_iter](fn.parse_file_iter.html).
+    pub fn parse_file_iter(&self, path: &str) -> Result<ParseIter> {
+        let reader = reader::Reader::from_file(path, &self.cpoptions)
+            .map_err(|e| ParseError::from(e).with_path(path))?;
+        Ok(ParseIter {
+            parser: self.parser(reader),
+            target: self.target,
+            path: Some(PathBuf::from(path)),
+            failed: false,
+        })
+    }
+
+    fn parser(&self, reader: reader::Reader) -> parser::Parser {
+        parser::Parser::with_commands(
+            Rc::new(reader),
+            Arc::clone(&self.commands),
+            self.unknown_command.clone(),
+        )
+        .in_function(self.context == Context::Function)
+    }
+
+    fn parse(&self, reader: reader::Reader) -> Result<node::Node> {
+        let node = self.parser(reader).parse()?;
+        if let Some(target) = self.target {
+            target.check(&node)?;
+        }
+        Ok(node)
+    }
+}
+
+/// The top-level statements of a script, parsed one at a time as the iterator is advanced, so a
+/// large script can be processed without holding its whole tree in memory. Each item is a
+/// complete statement - a function definition includes its whole body, an `if` all of its
+/// clauses - in the order they appear; together they are the `body` of the
+/// [TopLevel](enum.Node.html#variant.TopLevel) node [parse_lines](fn.parse_lines.html) would
+/// return. The iterator ends after the first error.
+pub struct ParseIter {
+    parser: parser::Parser,
+    target: Option<Target>,
+    path: Option<PathBuf>,
+    failed: bool,
+}
+
+impl ParseIter {
+    /// The trivia of the whole script, i.e. the `trivia` of the
+    /// [TopLevel](enum.Node.html#variant.TopLevel) node [parse_lines](fn.parse_lines.html) would
+    /// return. This is available before any statement is parsed.
+    pub fn trivia(&self) -> &[Trivia] {
+        self.parser.trivia()
+    }
+}
+
+impl Iterator for ParseIter {
+    type Item = Result<node::Node>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let item = match (self.parser.parse_next()?, self.target) {
+            (Ok(node), Some(target)) => target.check(&node).map(|_| node),
+            (item, _) => item,
+        };
+        let item = match (item, &self.path) {
+            (Err(e), Some(path)) => Err(e.with_path(path)),
+            (item, _) => item,
+        };
+        self.failed = item.is_err();
+        Some(item)
+    }
+}
+
+/// The canonical spelling of an autocommand event (e.g. `BufReadPost` for `bufreadpost`), or `None`
+/// if the event does not exist. Event names are case-insensitive, so any casing is accepted.
+pub fn canonical_autocmd_event(event: &str) -> Option<&'static str> {
+    command::valid_autocmds()
+        .get(event.to_lowercase().as_str())
+        .map(|e| e.name)
+}
+
+/// The canonical names of all the autocommand events the parser accepts, in alphabetical order.
+pub fn autocmd_events() -> Vec<&'static str> {
+    let mut events = command::valid_autocmds()
+        .values()
+        .map(|e| e.name)
+        .filter(|e| *e != "*")
+        .collect::<Vec<&str>>();
+    events.sort_unstable();
+    events
+}
+
+/// The keys that can be given values in a `:highlight` command, e.g. `ctermfg`.
+pub const HIGHLIGHT_KEYS: &[&str] = &[
+    "cterm", "ctermbg", "ctermfg", "font", "gui", "guibg", "guifg", "guisp", "start", "stop",
+    "term",
+];
+
+/// Where the statements given to a [Parser](struct.Parser.html) are, for parsing part of a
+/// script (e.g. lines selected in an editor) on its own.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Context {
+    /// At the top level of a script.
+    TopLevel,
+    /// In the body of a function, where e.g. `:return` is allowed.
+    Function,
+}
+
+/// The flavor of Vim script a file is written in.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Dialect {
+    Legacy,
+    Vim9,
+}
+
+impl Dialect {
+    /// Work out the dialect of a script from its first command: a script is vim9 script if it
+    /// starts with `:vim9script`. A leading `#!` line, blank lines and comments are skipped.
+    pub fn detect(lines: &[&str]) -> Self {
+        let mut lines = lines.iter().map(|l| l.trim());
+        let first = match lines.next() {
+            Some(l) if l.starts_with("#!") => lines.next(),
+            l => l,
+        };
+        let command = first
+            .into_iter()
+            .chain(lines)
+            .find(|l| !l.is_empty() && !l.starts_with('"'))
+            .and_then(|l| l.trim_start_matches(':').split_whitespace().next());
+        match command {
+            Some(c) if c.len() >= 5 && "vim9script".starts_with(c) => Dialect::Vim9,
+            _ => Dialect::Legacy,
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Hash, Copy, Clone)]
 pub struct Position {
     cursor: usize,
     line: usize,
@@ -54,6 +385,13 @@ impl Position {
         }
     }
 
+    /// The index of a given position in the parser's input, after continuation lines have been
+    /// joined. Unlike the line and column, this orders positions within a statement that spans
+    /// several lines.
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
     /// The column of a given position.
     pub fn column(&self) -> usize {
         self.col
@@ -63,6 +401,199 @@ impl Position {
     pub fn line(&self) -> usize {
         self.line
     }
+
+    /// The column of a given position in bytes, as Vim's `col()` counts it, given the source
+    /// lines that were parsed.
+    pub fn byte_column(&self, source: &[&str]) -> usize {
+        convert_column(source, self.line, self.col, char::len_utf8)
+    }
+
+    /// The column of a given position in UTF-16 code units, as the Language Server Protocol
+    /// counts it (but starting from 1, like the other columns), given the source lines that were
+    /// parsed.
+    pub fn utf16_column(&self, source: &[&str]) -> usize {
+        convert_column(source, self.line, self.col, char::len_utf16)
+    }
+
+    /// The offset in bytes of a given position from the start of the source lines that were
+    /// parsed, counting one byte for the end of each line (and any byte order mark).
+    pub fn byte_offset(&self, source: &[&str]) -> usize {
+        let lines = self.line.saturating_sub(1).min(source.len());
+        let mut before = source[..lines].iter().map(|l| l.len() + 1).sum::<usize>();
+        if lines == 0 && source.first().is_some_and(|l| l.starts_with('\u{feff}')) {
+            before += '\u{feff}'.len_utf8();
+        }
+        before + self.byte_column(source).saturating_sub(1)
+    }
+}
+
+// convert a 1-based column in characters on a 1-based line of the source to another unit.
+// columns past the end of the line (like that of the end of the line itself) count one unit
+// for each character, and a byte order mark at the start of the source isn't counted, since
+// the parser doesn't see it.
+fn convert_column(source: &[&str], line: usize, col: usize, len: fn(char) -> usize) -> usize {
+    let text = match source.get(line.wrapping_sub(1)) {
+        Some(text) if line == 1 => text.strip_prefix('\u{feff}').unwrap_or(text),
+        Some(text) => text,
+        None => "",
+    };
+    if col == 0 {
+        return 0;
+    }
+    let counted = text.chars().take(col - 1).map(len).sum::<usize>();
+    let past_end = (col - 1).saturating_sub(text.chars().count());
+    counted + past_end + 1
+}
+
+/// The kind of a [ParseError](struct.ParseError.html). Most of these correspond to one of Vim's
+/// own error messages, whose number is given by [number](#method.number); the rest are specific to
+/// this parser.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum ErrorCode {
+    /// E10: `\\` should be followed by `/`, `?` or `&`
+    InvalidBackslash,
+    /// E15: Invalid expression
+    InvalidExpression,
+    /// E39: Number expected
+    NumberExpected,
+    /// E112: Option name missing
+    OptionNameMissing,
+    /// E125: Illegal argument (to a function definition)
+    IllegalArgument,
+    /// E126: Missing `:endfunction`, `:endif`, `:endtry`, `:endwhile` or `:endfor`
+    MissingEnd,
+    /// E128: Function name must start with a capital or contain a colon
+    InvalidFunctionName,
+    /// E133: `:return` not inside a function
+    ReturnOutsideFunction,
+    /// E146: Regular expressions can't be delimited by letters
+    LetterDelimiter,
+    /// E160: Unknown sign command
+    UnknownSignCommand,
+    /// E193: `:endfunction` not inside a function
+    EndFunctionOutsideFunction,
+    /// E216: No such group or event
+    NoSuchGroupOrEvent,
+    /// E412: Not enough arguments
+    NotEnoughArguments,
+    /// E416: Missing equal sign
+    MissingEqualSign,
+    /// E423: Illegal argument (to `:highlight`)
+    IllegalHighlightArgument,
+    /// E461: Illegal variable name
+    IllegalVariableName,
+    /// E471: Argument required
+    ArgumentRequired,
+    /// E474: Invalid argument
+    InvalidArgument,
+    /// E475: Invalid argument, with the offending argument
+    InvalidArgumentValue,
+    /// E477: No ! allowed
+    NoBangAllowed,
+    /// E488: Trailing characters
+    TrailingCharacters,
+    /// E492: Not an editor command
+    NotAnEditorCommand,
+    /// E494: Use w or w>>
+    InvalidWriteAppend,
+    /// E580: `:endif` without `:if`
+    EndIfWithoutIf,
+    /// E581: `:else` without `:if`
+    ElseWithoutIf,
+    /// E582: `:elseif` without `:if`
+    ElseIfWithoutIf,
+    /// E586: `:continue` without `:while` or `:for`
+    ContinueWithoutLoop,
+    /// E587: `:break` without `:while` or `:for`
+    BreakWithoutLoop,
+    /// E588: `:endwhile` without `:while` or `:endfor` without `:for`
+    EndLoopWithoutLoop,
+    /// E602: `:endtry` without `:try`
+    EndTryWithoutTry,
+    /// E603: `:catch` without `:try`
+    CatchWithoutTry,
+    /// E604: `:catch` after `:finally`
+    CatchAfterFinally,
+    /// E606: `:finally` without `:try`
+    FinallyWithoutTry,
+    /// E682: Invalid search pattern or delimiter
+    InvalidSearchPattern,
+    /// E690: Missing "in" after `:for`
+    MissingIn,
+    /// E734: Wrong variable type, e.g. for `let @a += 1`
+    WrongVariableType,
+    /// E740: Too many arguments for function
+    TooManyArguments,
+    /// E853: Duplicate argument name
+    DuplicateArgument,
+    /// E1169: Expression too recursive, here nested more than
+    /// [MAX_EXPR_DEPTH](constant.MAX_EXPR_DEPTH.html) deep
+    ExpressionTooRecursive,
+    /// A character that can't start or continue a token.
+    UnexpectedCharacter,
+    /// A token that isn't valid where it was found.
+    UnexpectedToken,
+    /// The end of a line was reached in the middle of a token.
+    UnexpectedEol,
+    /// `:call` was used with something other than a function call.
+    NotAFunctionCall,
+    /// Something the [Target](enum.Target.html) given to the parser doesn't have.
+    UnsupportedByTarget,
+    /// The input couldn't be read.
+    Io,
+    /// A bug in the parser.
+    Internal,
+}
+
+impl ErrorCode {
+    /// The number of the equivalent Vim error, e.g. `Some(488)` for E488, or `None` if Vim has no
+    /// equivalent.
+    pub fn number(&self) -> Option<u32> {
+        use ErrorCode::*;
+        match self {
+            InvalidBackslash => Some(10),
+            InvalidExpression => Some(15),
+            NumberExpected => Some(39),
+            OptionNameMissing => Some(112),
+            IllegalArgument => Some(125),
+            MissingEnd => Some(126),
+            InvalidFunctionName => Some(128),
+            ReturnOutsideFunction => Some(133),
+            LetterDelimiter => Some(146),
+            UnknownSignCommand => Some(160),
+            EndFunctionOutsideFunction => Some(193),
+            NoSuchGroupOrEvent => Some(216),
+            NotEnoughArguments => Some(412),
+            MissingEqualSign => Some(416),
+            IllegalHighlightArgument => Some(423),
+            IllegalVariableName => Some(461),
+            ArgumentRequired => Some(471),
+            InvalidArgument => Some(474),
+            InvalidArgumentValue => Some(475),
+            NoBangAllowed => Some(477),
+            TrailingCharacters => Some(488),
+            NotAnEditorCommand => Some(492),
+            InvalidWriteAppend => Some(494),
+            EndIfWithoutIf => Some(580),
+            ElseWithoutIf => Some(581),
+            ElseIfWithoutIf => Some(582),
+            ContinueWithoutLoop => Some(586),
+            BreakWithoutLoop => Some(587),
+            EndLoopWithoutLoop => Some(588),
+            EndTryWithoutTry => Some(602),
+            CatchWithoutTry => Some(603),
+            CatchAfterFinally => Some(604),
+            FinallyWithoutTry => Some(606),
+            InvalidSearchPattern => Some(682),
+            MissingIn => Some(690),
+            WrongVariableType => Some(734),
+            TooManyArguments => Some(740),
+            DuplicateArgument => Some(853),
+            ExpressionTooRecursive => Some(1169),
+            UnexpectedCharacter | UnexpectedToken | UnexpectedEol | NotAFunctionCall
+            | UnsupportedByTarget | Io | Internal => None,
+        }
+    }
 }
 
 /// Any error encountered when parsing VimL.
@@ -71,6 +602,68 @@ pub struct ParseError {
     msg: String,
     /// The position of the error.
     pub pos: Position,
+    /// What kind of error this is.
+    pub code: ErrorCode,
+    /// The offending text, if the error is about a particular token or piece of text, e.g. the
+    /// unknown command for E492 or the characters after the command for E488. It starts at
+    /// `pos`.
+    pub found: Option<String>,
+    /// The file being parsed, if it was parsed with [parse_path](fn.parse_path.html),
+    /// [parse_file](fn.parse_file.html) or [parse_file_iter](fn.parse_file_iter.html).
+    pub path: Option<PathBuf>,
+}
+
+impl ParseError {
+    pub(crate) fn new(code: ErrorCode, msg: String, pos: Position) -> Self {
+        Self {
+            msg,
+            pos,
+            code,
+            found: None,
+            path: None,
+        }
+    }
+
+    pub(crate) fn with_found<T: ToString>(mut self, found: T) -> Self {
+        self.found = Some(found.to_string());
+        self
+    }
+
+    pub(crate) fn with_path<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// The error message, without the position, e.g. `"E488: Trailing characters: x"`.
+    pub fn message(&self) -> &str {
+        &self.msg
+    }
+
+    /// The line and column just past the offending text, if there is any and it fits on one line.
+    /// Together with `pos` this gives the span of text the error is about.
+    pub fn end(&self) -> Option<(usize, usize)> {
+        let found = self.found.as_ref()?;
+        if found.contains('\n') {
+            None
+        } else {
+            Some((self.pos.line, self.pos.col + found.chars().count()))
+        }
+    }
+
+    /// The line and byte column (see [Position::byte_column](struct.Position.html#method.byte_column))
+    /// just past the offending text, given the source lines that were parsed.
+    pub fn byte_end(&self, source: &[&str]) -> Option<(usize, usize)> {
+        let (line, col) = self.end()?;
+        Some((line, convert_column(source, line, col, char::len_utf8)))
+    }
+
+    /// The line and UTF-16 column (see
+    /// [Position::utf16_column](struct.Position.html#method.utf16_column)) just past the
+    /// offending text, given the source lines that were parsed.
+    pub fn utf16_end(&self, source: &[&str]) -> Option<(usize, usize)> {
+        let (line, col) = self.end()?;
+        Some((line, convert_column(source, line, col, char::len_utf16)))
+    }
 }
 
 impl std::error::Error for ParseError {
@@ -85,9 +678,14 @@ impl std::error::Error for ParseError {
 
 impl std::fmt::Display for ParseError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if let Some(path) = &self.path {
+            write!(f, "Parse error in {} ", path.display())?;
+        } else {
+            write!(f, "Parse error ")?;
+        }
         write!(
             f,
-            "Parse error at line {}, col {}: {}",
+            "at line {}, col {}: {}",
             self.pos.line, self.pos.col, self.msg
         )
     }
@@ -95,10 +693,7 @@ impl std::fmt::Display for ParseError {
 
 impl From<std::io::Error> for ParseError {
     fn from(err: std::io::Error) -> Self {
-        Self {
-            msg: format!("{}", err),
-            pos: Position::empty(),
-        }
+        Self::new(ErrorCode::Io, format!("{}", err), Position::empty())
     }
 }
 
@@ -127,25 +722,49 @@ impl CharClassification for char {
     }
 }
 
+/// Whether `s` is a letter or underscore followed by any number of letters, digits, underscores
+/// and `extra` characters.
+fn is_identifier(s: &str, extra: &[char]) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || extra.contains(&c))
+}
+
 fn isargname(s: &str) -> bool {
-    lazy_static! {
-        static ref RE: Regex = Regex::new("^[A-Za-z_][0-9A-Za-z_]*$").unwrap();
-    }
-    RE.is_match(s)
+    is_identifier(s, &[])
 }
 
 fn isvarname(s: &str) -> bool {
-    lazy_static! {
-        static ref RE: Regex =
-            Regex::new("^[vgslabwt]:$|^([vgslabwt]:)?[A-Za-z_][0-9A-Za-z_#]*$").unwrap();
-    }
-    RE.is_match(s)
+    // a scope on its own (e.g. `g:`), or a name with an optional scope
+    let name = match s.as_bytes() {
+        [scope, b':', ..] if b"vgslabwt".contains(scope) => &s[2..],
+        _ => s,
+    };
+    (name.is_empty() && name.len() < s.len()) || is_identifier(name, &['#'])
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_isargname_and_isvarname() {
+        assert!(isargname("foo_1"));
+        assert!(isargname("_"));
+        assert!(!isargname("1foo"));
+        assert!(!isargname("a:foo"));
+        assert!(!isargname(""));
+        assert!(isvarname("g:"));
+        assert!(isvarname("g:foo#bar"));
+        assert!(isvarname("foo"));
+        assert!(!isvarname("x:foo"));
+        assert!(!isvarname("g:1"));
+        assert!(!isvarname(":"));
+        assert!(!isvarname(""));
+    }
+
     #[test]
     fn test_is_word() {
         assert!('_'.is_word());
@@ -197,4 +816,384 @@ mod tests {
         assert!(!isvarname("x:foo"));
         assert!(!isvarname("fo|o"));
     }
+
+    #[test]
+    fn test_parser_is_shareable() {
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+        let parser = Parser::new().on_unknown_command(|name| {
+            (name == "foo").then(|| Command::new("Foo", 3, Flag::empty(), ParserKind::UserCmd))
+        });
+        assert_send_sync(&parser);
+        let node = std::thread::scope(|scope| {
+            scope
+                .spawn(|| parser.parse_lines(&["foo", "echo 1"]))
+                .join()
+                .unwrap()
+        })
+        .unwrap();
+        assert_send_sync(&node);
+        assert_eq!(node, parser.parse_lines(&["foo", "echo 1"]).unwrap());
+    }
+
+    #[test]
+    fn test_parser_with_cpoptions() {
+        let code = [
+            "let s:save_cpo = &cpo",
+            "set cpo&vim",
+            "let x = [",
+            "      \\ 1,",
+            "      \\ ]",
+            "let &cpo = s:save_cpo",
+            "unlet s:save_cpo",
+        ];
+        let compatible = Parser::new().with_cpoptions("aABceFsC");
+        assert_eq!(
+            compatible.parse_lines(&code).unwrap().to_string(),
+            Parser::new().parse_lines(&code).unwrap().to_string()
+        );
+        let mut code = code.to_vec();
+        code.extend(["echo 1", "      \\ + 2"]);
+        assert!(Parser::new().parse_lines(&code).is_ok());
+        let e = compatible.parse_lines(&code).unwrap_err();
+        assert_eq!(e.pos.line, 9);
+    }
+
+    #[test]
+    fn test_parser_with_commands() {
+        let parser = Parser::new().with_commands(vec![
+            Command::new(
+                "plugcmd",
+                5,
+                Flag::BANG | Flag::EXTRA | Flag::TRLBAR,
+                ParserKind::Common,
+            ),
+            Command::new(
+                "runexpr",
+                7,
+                Flag::EXTRA | Flag::NOTRLCOM,
+                ParserKind::Execute,
+            ),
+        ]);
+        assert_eq!(
+            format!(
+                "{}",
+                parser
+                    .parse_lines(&["plugc! foo | runexpr 'echo' . x"])
+                    .unwrap()
+            ),
+            "(excmd \"plugcmd foo\")\n(execute (concat 'echo' x))"
+        );
+        assert!(parse_lines(&["plugcmd foo"]).is_err());
+    }
+
+    #[test]
+    fn test_parser_with_target() {
+        let vim74 = Parser::new().with_target(Target::Vim74);
+        assert_eq!(
+            vim74.parse_lines(&["terminal"]).unwrap_err().code,
+            ErrorCode::NotAnEditorCommand
+        );
+        let e = vim74.parse_lines(&["echo 'a' .. 'b'"]).unwrap_err();
+        assert_eq!(e.code, ErrorCode::UnsupportedByTarget);
+        assert_eq!(e.message(), "`..` is not supported by Vim 7.4");
+        assert_eq!(e.pos.column(), 10);
+        assert!(vim74.parse_lines(&["call map(x, {k, v -> v})"]).is_err());
+        assert!(vim74.parse_lines(&["echo 'a' . 'b'"]).is_ok());
+        let neovim = Parser::new().with_target(Target::NeovimStable);
+        let e = neovim
+            .parse_lines(&["autocmd TerminalOpen * echo 1"])
+            .unwrap_err();
+        assert_eq!(e.code, ErrorCode::NoSuchGroupOrEvent);
+        assert_eq!(e.found, Some("TerminalOpen".to_string()));
+        assert!(neovim.parse_lines(&["autocmd TermOpen * echo 1"]).is_ok());
+        assert!(neovim.parse_lines(&["vim9script"]).is_err());
+        assert!(Parser::new()
+            .with_target(Target::Vim9)
+            .parse_lines(&["autocmd TermOpen * echo 1"])
+            .is_err());
+        let mut iter = vim74.parse_iter(&["echo 1", "echo x->len()", "echo 2"]);
+        assert!(iter.next().unwrap().is_ok());
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn test_parser_with_context() {
+        let code = ["if x", "return 1", "endif"];
+        assert_eq!(
+            Parser::new().parse_lines(&code).unwrap_err().code,
+            ErrorCode::ReturnOutsideFunction
+        );
+        let parser = Parser::new().with_context(Context::Function);
+        assert_eq!(
+            &format!("{}", parser.parse_lines(&code).unwrap()),
+            "(if x\n  (return 1))"
+        );
+    }
+
+    #[test]
+    fn test_parser_on_unknown_command() {
+        let parser = Parser::new().on_unknown_command(|name| {
+            if name.starts_with("my") {
+                Some(Command::new(
+                    name,
+                    name.len(),
+                    Flag::EXTRA,
+                    ParserKind::Common,
+                ))
+            } else {
+                None
+            }
+        });
+        assert_eq!(
+            format!("{}", parser.parse_lines(&["mycmd a b"]).unwrap()),
+            "(excmd \"mycmd a b\")"
+        );
+        assert_eq!(
+            format!("{}", parser.parse_lines(&["Foo"]).unwrap()),
+            "(excmd \"Foo\")"
+        );
+        assert!(parser.parse_lines(&["other"]).is_err());
+    }
+
+    #[test]
+    fn test_dialect_detect() {
+        assert_eq!(Dialect::detect(&["vim9script"]), Dialect::Vim9);
+        assert_eq!(
+            Dialect::detect(&[
+                "#!/usr/bin/env -S vim -S",
+                "",
+                "\" comment",
+                "vim9s noclear"
+            ]),
+            Dialect::Vim9
+        );
+        assert_eq!(
+            Dialect::detect(&["let x = 1", "vim9script"]),
+            Dialect::Legacy
+        );
+        assert_eq!(Dialect::detect(&["vim9"]), Dialect::Legacy);
+        assert_eq!(Dialect::detect(&[]), Dialect::Legacy);
+        assert_eq!(
+            format!("{}", parse_lines(&["vim9script noclear"]).unwrap()),
+            "(excmd \"vim9script noclear\")"
+        );
+    }
+
+    #[test]
+    fn test_error_codes() {
+        let tests = [
+            (
+                "echo 1 | endif",
+                ErrorCode::EndIfWithoutIf,
+                Some(580),
+                None,
+                None,
+            ),
+            (
+                "foobar baz",
+                ErrorCode::NotAnEditorCommand,
+                Some(492),
+                Some("foobar baz"),
+                Some((1, 11)),
+            ),
+            (
+                "autocmd BufRead,NotAnEvent * echo 1",
+                ErrorCode::NoSuchGroupOrEvent,
+                Some(216),
+                Some("NotAnEvent"),
+                Some((1, 27)),
+            ),
+            (
+                "let x = 1 +",
+                ErrorCode::UnexpectedToken,
+                None,
+                Some("\n"),
+                None,
+            ),
+        ];
+        for (code, kind, number, found, end) in tests.iter() {
+            let err = parse_lines(&[code]).unwrap_err();
+            assert_eq!(err.code, *kind, "{}", code);
+            assert_eq!(err.code.number(), *number);
+            assert_eq!(err.found.as_deref(), *found);
+            if end.is_some() {
+                assert_eq!(err.end(), *end);
+            }
+        }
+    }
+
+    #[test]
+    fn test_position_units() {
+        let code = ["\u{feff}\" é", "let s = 'é😀' | foo"];
+        let err = parse_lines(&code).unwrap_err();
+        assert_eq!(err.code, ErrorCode::NotAnEditorCommand);
+        assert_eq!((err.pos.line(), err.pos.column()), (2, 16));
+        assert_eq!(err.pos.byte_column(&code), 20);
+        assert_eq!(err.pos.utf16_column(&code), 17);
+        assert_eq!(err.pos.byte_offset(&code), 27);
+        assert_eq!(err.end(), Some((2, 19)));
+        assert_eq!(err.byte_end(&code), Some((2, 23)));
+        assert_eq!(err.utf16_end(&code), Some((2, 20)));
+        let pos = Position::new(0, 1, 3);
+        assert_eq!(pos.byte_column(&code), 3);
+        assert_eq!(pos.byte_offset(&code), 5);
+    }
+
+    #[test]
+    fn test_parse_iter() {
+        let code = [
+            "#!/usr/bin/vim -S",
+            "\" comment",
+            "",
+            "function! Foo()",
+            "  if 1",
+            "    let x = [",
+            "          \\ 1,",
+            "          \"\\ continuation comment",
+            "          \\ 2]",
+            "  endif",
+            "endfunction",
+            "echo 1 | echo 2",
+            "let y = 1 \" trailing",
+        ];
+        let iter = parse_iter(&code);
+        let (body, trivia) = match parse_lines(&code).unwrap() {
+            Node::TopLevel { body, trivia, .. } => (body, trivia),
+            _ => unreachable!(),
+        };
+        assert_eq!(iter.trivia(), trivia.as_slice());
+        assert_eq!(iter.map(|n| n.unwrap()).collect::<Vec<_>>(), body);
+        let mut iter = parse_iter(&["echo 1", "if 1", "echo 2", "echo 3"]);
+        assert_eq!(format!("{}", iter.next().unwrap().unwrap()), "(echo 1)");
+        assert_eq!(
+            iter.next().unwrap().unwrap_err().code,
+            ErrorCode::MissingEnd
+        );
+        assert!(iter.next().is_none());
+        let mut iter = parse_iter(&["echo 1", "endwhile", "echo 2"]);
+        assert_eq!(format!("{}", iter.next().unwrap().unwrap()), "(echo 1)");
+        assert_eq!(
+            iter.next().unwrap().unwrap_err().code,
+            ErrorCode::EndLoopWithoutLoop
+        );
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn test_parse_reader_and_path() {
+        let source = "\u{feff}echo 1\r\nif 1\n  echo 2\nendif\n";
+        let node = parse_reader(source.as_bytes()).unwrap();
+        assert_eq!(
+            node,
+            parse_lines(&source.lines().collect::<Vec<_>>()).unwrap()
+        );
+        let err = parse_reader("echo 1\nfoo".as_bytes()).unwrap_err();
+        assert_eq!(err.path, None);
+        let path = std::env::temp_dir().join(format!(
+            "viml-parser-test-parse-path-{}.vim",
+            std::process::id()
+        ));
+        std::fs::write(&path, "echo 1\nfoo").unwrap();
+        let err = parse_path(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(err.code, ErrorCode::NotAnEditorCommand);
+        assert_eq!(err.path.as_deref(), Some(path.as_path()));
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Parse error in {} at line 2, col 1: E492: Not an editor command: foo",
+                path.display()
+            )
+        );
+        let err = parse_path(&path).unwrap_err();
+        assert_eq!(err.code, ErrorCode::Io);
+        assert_eq!(err.path.as_deref(), Some(path.as_path()));
+    }
+
+    #[test]
+    fn test_parse_expression() {
+        assert_eq!(
+            format!("{}", parse_expression("  get(g:, 'x', 1) + 2 ").unwrap()),
+            "(+ (get g: 'x' 1) 2)"
+        );
+        assert_eq!(
+            format!("{}", parse_expression("[1,\n  \\ 2]").unwrap()),
+            "(list 1 2)"
+        );
+        let err = parse_expression("1 2").unwrap_err();
+        assert_eq!(err.code, ErrorCode::TrailingCharacters);
+        assert_eq!(err.found.as_deref(), Some("2"));
+        assert!(parse_expression("").is_err());
+    }
+
+    #[test]
+    fn test_expression_depth() {
+        // Unoptimized builds use several times the stack per level of nesting that release
+        // builds do, more than the test threads have.
+        let test = || {
+            let nested = |depth: usize, open: &str, close: &str| {
+                format!("{}1{}", open.repeat(depth), close.repeat(depth))
+            };
+            for (open, close) in [
+                ("(", ")"),
+                ("[", "]"),
+                ("{'a': ", "}"),
+                ("-", ""),
+                ("f(", ")"),
+            ] {
+                assert!(parse_expression(&nested(MAX_EXPR_DEPTH - 1, open, close)).is_ok());
+                let err = parse_expression(&nested(10_000, open, close)).unwrap_err();
+                assert_eq!(err.code, ErrorCode::ExpressionTooRecursive);
+                assert_eq!(err.code.number(), Some(1169));
+                let line = format!("echo {}", nested(10_000, open, close));
+                let err = parse_lines(&[&line]).unwrap_err();
+                assert_eq!(err.code, ErrorCode::ExpressionTooRecursive);
+            }
+            // a chain of operators nests each operation in the next, so it can't go on forever
+            // either
+            for link in [
+                " + 1", " .. 'x'", " || 1", " && 1", " * 2", "[0]", ".x", "->f()",
+            ] {
+                let chain = |links: usize| format!("x{}", link.repeat(links));
+                assert!(parse_expression(&chain(MAX_EXPR_DEPTH - 1)).is_ok());
+                let err = parse_expression(&chain(10_000)).unwrap_err();
+                assert_eq!(err.code, ErrorCode::ExpressionTooRecursive);
+                let line = format!("let y = {}", chain(10_000));
+                let err = parse_lines(&[&line]).unwrap_err();
+                assert_eq!(err.code, ErrorCode::ExpressionTooRecursive);
+            }
+        };
+        std::thread::Builder::new()
+            .stack_size(32 << 20)
+            .spawn(test)
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_canonical_autocmd_event() {
+        assert_eq!(canonical_autocmd_event("bufreadpost"), Some("BufReadPost"));
+        assert_eq!(canonical_autocmd_event("MODECHANGED"), Some("ModeChanged"));
+        assert_eq!(canonical_autocmd_event("UIEnter"), Some("UIEnter"));
+        assert_eq!(canonical_autocmd_event("NotAnEvent"), None);
+        let events = autocmd_events();
+        assert!(events.contains(&"BufReadPost"));
+        assert!(!events.contains(&"*"));
+        assert!(events.windows(2).all(|w| w[0] < w[1]));
+        let event = valid_autocmds()["termopen"];
+        assert_eq!(event.name, "TermOpen");
+        assert_eq!(
+            (event.vim, event.neovim),
+            (None, Some(Target::NeovimStable))
+        );
+        assert!(event.is_available(Target::NeovimNightly));
+        assert!(!event.is_available(Target::Vim9));
+        let event = valid_autocmds()["winscrolled"];
+        assert!(!event.is_available(Target::Vim82));
+        assert!(event.is_available(Target::Vim9));
+        assert!(valid_autocmds()["fileencoding"].deprecated);
+        assert!(!valid_autocmds()["bufenter"].deprecated);
+    }
 }
@@ -1,3 +1,34 @@
+use std::fmt;
+
+/// The full names of all modifiers, in canonical order: modifiers that change how messages are
+/// shown first, then those that change what the command may do or what it keeps, and those that
+/// change where windows are opened last. This is the order used by
+/// [sort_modifiers](fn.sort_modifiers.html).
+pub const CANONICAL_ORDER: &[&str] = &[
+    "silent",
+    "unsilent",
+    "verbose",
+    "noautocmd",
+    "sandbox",
+    "confirm",
+    "browse",
+    "keepalt",
+    "keepjumps",
+    "keepmarks",
+    "keeppatterns",
+    "lockmarks",
+    "noswapfile",
+    "hide",
+    "aboveleft",
+    "belowright",
+    "leftabove",
+    "rightbelow",
+    "topleft",
+    "botright",
+    "vertical",
+    "tab",
+];
+
 /// A command that modifies another command (e.g. `silent`). The following commands can be
 /// considered modifiers:
 ///
@@ -31,17 +62,121 @@ pub struct Modifier {
     /// Whether this modifier was invoked with a bang. This can only be true for `silent` - it will
     /// be false in all other cases.
     pub bang: bool,
-    /// The count argument to this modifier. Only `tab` and `silent` can have `Some`, all other
+    /// The count argument to this modifier. Only `tab` and `verbose` can have `Some`, all other
     /// variants will have `None`.
     pub count: Option<usize>,
 }
 
 impl Modifier {
-    pub(crate) fn new(name: &str) -> Self {
+    /// Create a modifier with no bang or count. `name` should be the full name of the modifier;
+    /// use [from_name](#method.from_name) to look up an abbreviated name.
+    pub fn new(name: &str) -> Self {
         Self {
             name: name.to_string(),
             bang: false,
             count: None,
         }
     }
+
+    /// The modifier that `name` (which may be abbreviated, e.g. `sil` for `silent`) refers to, or
+    /// `None` if it isn't a modifier.
+    pub fn from_name(name: &str) -> Option<Self> {
+        const MODIFIERS: &[(&str, usize)] = &[
+            ("aboveleft", 3),
+            ("belowright", 3),
+            ("browse", 3),
+            ("botright", 2),
+            ("confirm", 4),
+            ("keepmarks", 3),
+            ("keepalt", 5),
+            ("keepjumps", 5),
+            ("keeppatterns", 5),
+            ("hide", 3),
+            ("lockmarks", 3),
+            ("leftabove", 5),
+            ("noautocmd", 3),
+            ("noswapfile", 3),
+            ("rightbelow", 6),
+            ("sandbox", 3),
+            ("silent", 3),
+            ("tab", 3),
+            ("topleft", 2),
+            ("unsilent", 3),
+            ("vertical", 4),
+            ("verbose", 4),
+        ];
+        for (modifier, min_length) in MODIFIERS.iter() {
+            if modifier.starts_with(name) && name.len() >= *min_length {
+                return Some(Modifier::new(modifier));
+            }
+        }
+        None
+    }
+
+    /// The position of this modifier in [CANONICAL_ORDER](constant.CANONICAL_ORDER.html).
+    /// Unknown names sort last.
+    pub fn canonical_index(&self) -> usize {
+        CANONICAL_ORDER
+            .iter()
+            .position(|m| *m == self.name)
+            .unwrap_or_else(|| CANONICAL_ORDER.len())
+    }
+}
+
+impl fmt::Display for Modifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(count) = self.count {
+            write!(f, "{}", count)?;
+        }
+        write!(f, "{}{}", self.name, if self.bang { "!" } else { "" })
+    }
+}
+
+/// Sort modifiers into [canonical order](constant.CANONICAL_ORDER.html), e.g. `keepjumps
+/// silent!` into `silent! keepjumps`. The relative order of repeated modifiers is kept.
+pub fn sort_modifiers(mods: &mut [Modifier]) {
+    mods.sort_by_key(Modifier::canonical_index);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(Modifier::from_name("sil"), Some(Modifier::new("silent")));
+        assert_eq!(
+            Modifier::from_name("keepj"),
+            Some(Modifier::new("keepjumps"))
+        );
+        assert_eq!(Modifier::from_name("ke"), None);
+        assert_eq!(Modifier::from_name("bo"), Some(Modifier::new("botright")));
+        assert_eq!(Modifier::from_name("foo"), None);
+        for name in CANONICAL_ORDER {
+            assert_eq!(Modifier::from_name(name), Some(Modifier::new(name)));
+        }
+    }
+
+    #[test]
+    fn test_sort_modifiers() {
+        let mut silent = Modifier::new("silent");
+        silent.bang = true;
+        let mut tab = Modifier::new("tab");
+        tab.count = Some(2);
+        let mut mods = vec![
+            tab,
+            Modifier::new("keepjumps"),
+            Modifier::new("vertical"),
+            silent,
+            Modifier::new("noautocmd"),
+        ];
+        sort_modifiers(&mut mods);
+        assert_eq!(
+            mods.iter()
+                .map(|m| m.to_string())
+                .collect::<Vec<_>>()
+                .join(" "),
+            "silent! noautocmd keepjumps vertical 2tab"
+        );
+    }
 }
@@ -1,5 +1,10 @@
 use super::Position;
-use crate::modifier::Modifier;
+use crate::{
+    keys::Key,
+    modifier::Modifier,
+    option::{split_option, OptionScope},
+    range::Range,
+};
 use std::fmt;
 
 const INDENT: &str = "  ";
@@ -29,6 +34,212 @@ fn display_with_list<T: fmt::Display>(name: &str, list: &[T]) -> String {
     )
 }
 
+// the bodies of `Node::children` and `Node::children_mut` are identical apart from the mutability
+// of the references they collect, so they are generated from this.
+macro_rules! children {
+    ($node:expr, $ty:ty) => {{
+        let mut v: Vec<$ty> = vec![];
+        match $node {
+            Node::Autocmd { body, .. }
+            | Node::Catch { body, .. }
+            | Node::Else { body, .. }
+            | Node::Finally { body, .. }
+            | Node::Global { body, .. }
+            | Node::TopLevel { body, .. } => v.extend(body),
+            Node::BinaryOp { left, right, .. } | Node::Dot { left, right, .. } => {
+                v.push(left);
+                v.push(right);
+            }
+            Node::Call { name, args, .. } => {
+                v.push(name);
+                v.extend(args);
+            }
+            Node::CurlyName { pieces, .. } => v.extend(pieces),
+            Node::CurlyNameExpr { expr, .. } | Node::ParenExpr { expr, .. } => v.push(expr),
+            Node::Dict { items, .. } => {
+                for (key, value) in items {
+                    v.push(key);
+                    v.push(value);
+                }
+            }
+            Node::Echo { list, .. }
+            | Node::Execute { list, .. }
+            | Node::LockVar { list, .. }
+            | Node::Unlet { list, .. } => v.extend(list),
+            Node::ElseIf { cond, body, .. } => {
+                v.push(cond);
+                v.extend(body);
+            }
+            Node::Eval { expr, .. } => v.push(expr),
+            Node::ExCall { left, .. } => v.push(left),
+            Node::For {
+                var,
+                list,
+                rest,
+                right,
+                body,
+                end,
+                ..
+            } => {
+                if let Some(var) = var {
+                    v.push(var);
+                }
+                v.extend(list);
+                if let Some(rest) = rest {
+                    v.push(rest);
+                }
+                v.push(right);
+                v.extend(body);
+                if let Some(end) = end {
+                    v.push(end);
+                }
+            }
+            Node::Function {
+                name,
+                args,
+                body,
+                end,
+                ..
+            } => {
+                v.push(name);
+                v.extend(args);
+                v.extend(body);
+                if let Some(end) = end {
+                    v.push(end);
+                }
+            }
+            Node::If {
+                cond,
+                body,
+                elseifs,
+                else_,
+                end,
+                ..
+            } => {
+                v.push(cond);
+                v.extend(body);
+                v.extend(elseifs);
+                if let Some(else_) = else_ {
+                    v.push(else_);
+                }
+                if let Some(end) = end {
+                    v.push(end);
+                }
+            }
+            Node::Lambda { args, expr, .. } => {
+                v.extend(args);
+                v.push(expr);
+            }
+            Node::Let {
+                var,
+                list,
+                rest,
+                right,
+                ..
+            } => {
+                if let Some(var) = var {
+                    v.push(var);
+                }
+                v.extend(list);
+                if let Some(rest) = rest {
+                    v.push(rest);
+                }
+                v.push(right);
+            }
+            Node::List { items, .. } => v.extend(items),
+            Node::Abbreviation { right_expr, .. } | Node::Mapping { right_expr, .. } => {
+                if let Some(right_expr) = right_expr {
+                    v.push(right_expr);
+                }
+            }
+            Node::Method { left, right, .. } => {
+                v.push(left);
+                v.push(right);
+            }
+            Node::Return { left, .. } => {
+                if let Some(left) = left {
+                    v.push(left);
+                }
+            }
+            Node::Slice {
+                name, left, right, ..
+            } => {
+                v.push(name);
+                if let Some(left) = left {
+                    v.push(left);
+                }
+                if let Some(right) = right {
+                    v.push(right);
+                }
+            }
+            Node::Subscript { name, index, .. } => {
+                v.push(name);
+                v.push(index);
+            }
+            Node::Ternary {
+                cond, left, right, ..
+            } => {
+                v.push(cond);
+                v.push(left);
+                v.push(right);
+            }
+            Node::Throw { err, .. } => v.push(err),
+            Node::Try {
+                body,
+                catches,
+                finally,
+                end,
+                ..
+            } => {
+                v.extend(body);
+                v.extend(catches);
+                if let Some(finally) = finally {
+                    v.push(finally);
+                }
+                if let Some(end) = end {
+                    v.push(end);
+                }
+            }
+            Node::UnaryOp { right, .. } => v.push(right),
+            Node::While {
+                cond, body, end, ..
+            } => {
+                v.push(cond);
+                v.extend(body);
+                if let Some(end) = end {
+                    v.push(end);
+                }
+            }
+            Node::Bar { .. }
+            | Node::BlankLine { .. }
+            | Node::Colorscheme { .. }
+            | Node::Comment { .. }
+            | Node::CurlyNamePart { .. }
+            | Node::Digraphs { .. }
+            | Node::End { .. }
+            | Node::Env { .. }
+            | Node::ExCmdRaw { .. }
+            | Node::Highlight { .. }
+            | Node::Identifier { .. }
+            | Node::Lang { .. }
+            | Node::Match { .. }
+            | Node::Menu { .. }
+            | Node::Normal { .. }
+            | Node::Number { .. }
+            | Node::Option { .. }
+            | Node::Reg { .. }
+            | Node::Runtime { .. }
+            | Node::Set { .. }
+            | Node::Shebang { .. }
+            | Node::Sign { .. }
+            | Node::Source { .. }
+            | Node::String { .. }
+            | Node::Substitute { .. } => (),
+        }
+        v
+    }};
+}
+
 /// The operation kind in a Node::BinaryOp node.
 #[derive(Debug, PartialEq, Clone)]
 pub enum BinaryOpKind {
@@ -47,6 +258,8 @@ pub enum BinaryOpKind {
     EqEqCI,
     /// Case-sensitive equals (`==#`)
     EqEqCS,
+    /// Falsy operator (`??`) - the left side if it is truthy, otherwise the right side.
+    Falsy,
     /// Greater-than (`>`)
     GT,
     /// Case-insensitive greater-than (`>?`)
@@ -107,6 +320,8 @@ pub enum BinaryOpKind {
     Or,
     /// Modulo (`%`)
     Remainder,
+    /// Concatenation with `..`, which (unlike `.`) is never dictionary access.
+    StringConcat,
     /// Subtraction (`-`)
     Subtract,
 }
@@ -124,6 +339,7 @@ impl fmt::Display for BinaryOpKind {
                 BinaryOpKind::EqEq => "==",
                 BinaryOpKind::EqEqCI => "==?",
                 BinaryOpKind::EqEqCS => "==#",
+                BinaryOpKind::Falsy => "??",
                 BinaryOpKind::GT => ">",
                 BinaryOpKind::GTCI => ">?",
                 BinaryOpKind::GTCS => ">#",
@@ -154,6 +370,7 @@ impl fmt::Display for BinaryOpKind {
                 BinaryOpKind::NotEqCS => "!=#",
                 BinaryOpKind::Or => "||",
                 BinaryOpKind::Remainder => "%",
+                BinaryOpKind::StringConcat => "..",
                 BinaryOpKind::Subtract => "-",
             }
         )
@@ -185,12 +402,73 @@ impl fmt::Display for UnaryOpKind {
     }
 }
 
+/// Source text that is not part of any node but should be kept when the source is reproduced.
+/// Trivia is collected in the `trivia` member of the [TopLevel](enum.Node.html#variant.TopLevel)
+/// node; its position says which statement (and where in that statement) it belongs to.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Trivia {
+    /// A UTF-8 byte order mark at the start of the input. It is removed before parsing, so
+    /// positions on the first line don't count it.
+    ByteOrderMark { pos: Position },
+    /// A comment line inside a line continuation sequence, e.g. the third line of
+    /// ```text
+    /// let foo = [
+    ///       \ 'one',
+    ///       "\ this is a continuation comment
+    ///       \ 'two',
+    ///       \ ]
+    /// ```
+    /// The line and column of `pos` are those of the comment itself, while its
+    /// [cursor](struct.Position.html#method.cursor) places it inside the continued statement,
+    /// after `'one',` and before `'two'`.
+    ContinuationComment {
+        pos: Position,
+        /// Everything after the `"\`, so in the example above this is
+        /// `" this is a continuation comment"`.
+        value: String,
+    },
+    /// The start of a continuation line, i.e. a place where a statement was broken onto a new
+    /// line with a `\`. The line and column of `pos` are those of the backslash, while its
+    /// [cursor](struct.Position.html#method.cursor) is where the text after the backslash starts
+    /// in the continued statement.
+    LineContinuation { pos: Position },
+}
+
+impl Trivia {
+    /// The position of a piece of trivia.
+    pub fn pos(&self) -> Position {
+        match self {
+            Trivia::ByteOrderMark { pos }
+            | Trivia::ContinuationComment { pos, .. }
+            | Trivia::LineContinuation { pos } => *pos,
+        }
+    }
+}
+
 /// A single AST node. All variants have an inner struct containing data specific to the node.
 /// Every variant has a `pos` member (a [Position](struct.Position.html) struct) that represents
 /// the position of the node in the original source. Many variants have a `mods` vector which
 /// contains zero or more [Modifier](struct.Modifier.html)s.
 #[derive(Debug, PartialEq, Clone)]
 pub enum Node {
+    /// An abbreviation command, e.g. `iabbrev <buffer> teh the`. Abbreviations take the same
+    /// attributes and arguments as mappings; see [Mapping](#variant.Mapping).
+    Abbreviation {
+        pos: Position,
+        mods: Vec<Modifier>,
+        /// The specific abbreviation command used, e.g. `iabbrev` or `cnoreabbrev`.
+        command: String,
+        /// The abbreviated text.
+        left: String,
+        /// The text the abbreviation is replaced by, if it is not an expression abbreviation.
+        right: String,
+        /// `right` split into [Keys](enum.Key.html).
+        right_keys: Vec<Key>,
+        /// What the abbreviation is replaced by, if it is an expression abbreviation.
+        right_expr: Option<Box<Node>>,
+        /// Any attributes of the abbreviation, e.g. "buffer" or "expr".
+        attrs: Vec<String>,
+    },
     /// An autocommand
     Autocmd {
         pos: Position,
@@ -215,11 +493,13 @@ pub enum Node {
         /// this is `""`.
         group: String,
         /// A vector of the events that will cause this command to be executed. Only valid events
-        /// will be included (invalid events cause [ParseError](struct.ParseError.html)s). In
+        /// will be included (invalid events cause [ParseError](struct.ParseError.html)s). The
+        /// events are spelled as they were in the source, so in
         /// ```text
-        /// autocmd BufNewFile,BufReadPost * echo "foo"
+        /// autocmd BufNewFile,bufreadpost * echo "foo"
         /// ```
-        /// this is `["BufNewFile", "BufReadPost"]`.
+        /// this is `["BufNewFile", "bufreadpost"]`. Use
+        /// [canonical_autocmd_event](fn.canonical_autocmd_event.html) to normalize them.
         events: Vec<String>,
         /// A vector of patterns that must match for this command to be executed. In
         /// ```text
@@ -250,6 +530,10 @@ pub enum Node {
     /// An empty line. This kind of node can be ignored - it only exists for the VimL formatter
     /// which is the parent project of this parser.
     BlankLine { pos: Position },
+    /// A bar at the end of a line, with nothing after it but whitespace or a trailing comment,
+    /// e.g. in `echo 1 |` or `nnoremap a b | " note`. The bar separates the command from an empty
+    /// one, so it is redundant unless a comment follows.
+    Bar { pos: Position },
     /// A function call. Not to be confused with [ExCall](#variant.ExCall).
     Call {
         pos: Position,
@@ -268,6 +552,14 @@ pub enum Node {
         /// The commands in the body of the clause.
         body: Vec<Node>,
     },
+    /// A `colorscheme` command.
+    Colorscheme {
+        pos: Position,
+        mods: Vec<Modifier>,
+        /// The name of the colorscheme, or `None` if no name was given, which shows the current
+        /// one.
+        name: Option<String>,
+    },
     /// A comment
     Comment {
         pos: Position,
@@ -311,6 +603,18 @@ pub enum Node {
         /// [String](#variant.String)s or [Number](#variant.Number)s. (Vim allows either, though
         /// numbers will be coerced into strings.)
         items: Vec<(Box<Node>, Box<Node>)>,
+        /// Whether the last item is followed by a comma, as in `{'a': 1,}`, which Vim allows.
+        trailing_comma: bool,
+    },
+    /// A `digraphs` command that defines digraphs, e.g. `digraphs e: 235 a: 228`.
+    Digraphs {
+        pos: Position,
+        mods: Vec<Modifier>,
+        /// Whether this command was invoked with a bang (`!`).
+        bang: bool,
+        /// The digraphs defined, in `(characters, number)` form, e.g. `("e:", "235")`. Empty if
+        /// the command only lists digraphs.
+        digraphs: Vec<(String, String)>,
     },
     /// A dot operation - usually accessing an item in a dictionary. (Note that this parser cannot
     /// 100% distinguish between dictionary access and concatenation as VimL uses the dot token for
@@ -358,19 +662,33 @@ pub enum Node {
         /// The variable. The `$` is included.
         value: String,
     },
+    /// The `eval` command, which evaluates an expression for its side effects and discards the
+    /// result.
+    Eval {
+        pos: Position,
+        mods: Vec<Modifier>,
+        /// The expression that is evaluated.
+        expr: Box<Node>,
+    },
     /// The `call` command. Not to be confused with [Call](#variant.Call).
     ExCall {
         pos: Position,
         mods: Vec<Modifier>,
+        /// The range given to the call command, e.g. `'<,'>` in `'<,'>call Foo()`. Empty if no
+        /// range was given.
+        range: Range,
         /// The argument passed to the call command (probably a [Call](#variant.Call)).
         left: Box<Node>,
     },
     /// A general command which does not have a specific variant associated with it. This variant
     /// is kind of a "catch-all" for any commands that are not parsed specifically.
-    ExCmd {
+    ExCmdRaw {
         pos: Position,
         mods: Vec<Modifier>,
-        /// The command.
+        /// The range given to the command, e.g. `1,$` in `1,$delete`. Empty if no range was
+        /// given.
+        range: Range,
+        /// The command. Empty for a line with only a range, like `:5`.
         command: String,
         /// Whether this command was invoked with a bang (`!`).
         bang: bool,
@@ -441,6 +759,26 @@ pub enum Node {
         /// the body of the function before the `endfunction` is found.
         end: Option<Box<Node>>,
     },
+    /// A `global` or `vglobal` command, e.g. `g/^$/d`.
+    Global {
+        pos: Position,
+        mods: Vec<Modifier>,
+        /// The range given to the command, e.g. `1,10` in `1,10g/foo/d`. Empty if no range was
+        /// given.
+        range: Range,
+        /// Whether this command was invoked with a bang (`!`), which makes `global` act like
+        /// `vglobal`.
+        bang: bool,
+        /// The specific command - either `global` or `vglobal`.
+        cmd: String,
+        /// The character around the pattern, e.g. `/`.
+        delimiter: char,
+        /// The pattern, exactly as written. Empty to use the last search pattern.
+        pattern: String,
+        /// The commands run on the matching lines, which are separated by `|`. Empty if there are
+        /// none, in which case the lines are printed.
+        body: Vec<Node>,
+    },
     /// A highlight command, including highlight-link variants
     Highlight {
         pos: Position,
@@ -491,6 +829,25 @@ pub enum Node {
         /// body of the if before the `endif` is found.
         end: Option<Box<Node>>,
     },
+    /// A script in another language given to a command like `lua` or `python3` as a heredoc, e.g.
+    /// ```text
+    /// lua << trim EOF
+    ///   print('hello')
+    /// EOF
+    /// ```
+    /// A script given on the same line as the command is an [ExCmdRaw](#variant.ExCmdRaw).
+    Lang {
+        pos: Position,
+        mods: Vec<Modifier>,
+        /// The command, e.g. `lua`.
+        cmd: String,
+        /// Whether `trim` was given, allowing the script and end marker to be indented.
+        trim: bool,
+        /// The end marker, if one was given; otherwise the script ends with a `.` line.
+        marker: Option<String>,
+        /// The lines of the script, exactly as written and not including the end marker.
+        lines: Vec<String>,
+    },
     // A lambda function
     Lambda {
         pos: Position,
@@ -503,6 +860,8 @@ pub enum Node {
     Let {
         pos: Position,
         mods: Vec<Modifier>,
+        /// The specific command - `let`, `const` or `final`.
+        cmd: String,
         /// The variable being defined, e.g. in `let x = something`, this is `x`.
         var: Option<Box<Node>>,
         /// If there are multiple variables in the let statement, this is a list of those
@@ -522,6 +881,8 @@ pub enum Node {
         pos: Position,
         /// The items in the list.
         items: Vec<Node>,
+        /// Whether the last item is followed by a comma, as in `[1, 2,]`, which Vim allows.
+        trailing_comma: bool,
     },
     /// A lockvar or unlockvar command
     LockVar {
@@ -542,16 +903,88 @@ pub enum Node {
         mods: Vec<Modifier>,
         /// The specific mapping command used, e.g. `nnoremap` or `xmap`.
         command: String,
+        /// Whether the command was invoked with a bang (`!`), as in `map!`, which maps keys in
+        /// insert and command-line mode instead of normal, visual and operator-pending mode.
+        bang: bool,
         /// The left-hand side of the mapping (i.e. the key(s) to be mapped).
         left: String,
         /// The right-hand side of the mapping, if it is not an expression mapping.
         right: String,
+        /// `right` split into [Keys](enum.Key.html), so `<Plug>` and `<SID>` names, `<Cmd>`
+        /// sequences and key notation can be told apart from keys that are typed as they are.
+        right_keys: Vec<Key>,
         /// The right-hand side of the mapping, if it is an expression mapping.
         right_expr: Option<Box<Node>>,
         /// Any attributes of the mapping - could include "buffer", "nowait", "silent", "script",
         /// "unique" and/or "expr". (If it contains "expr", `right_expr` should be `Some`.
         attrs: Vec<String>,
     },
+    /// A `match` command, e.g. `match ErrorMsg /\s\+$/`. `2match` and `3match` are `match`
+    /// with a range of 2 or 3.
+    Match {
+        pos: Position,
+        mods: Vec<Modifier>,
+        /// The range given to the command, e.g. `2` in `2match`. Empty if no range was given.
+        range: Range,
+        /// The highlight group, e.g. `ErrorMsg`, or `none` to clear the match. Empty if no group
+        /// was given, which also clears the match.
+        group: String,
+        /// The character around the pattern, e.g. `/`, or `None` if there is no pattern.
+        delimiter: Option<char>,
+        /// The pattern, as written.
+        pattern: String,
+    },
+    /// A menu definition command, e.g. `nnoremenu <silent> 10.20 File.Save :w<CR>`.
+    Menu {
+        pos: Position,
+        mods: Vec<Modifier>,
+        /// The range given to the command. Empty if no range was given.
+        range: Range,
+        /// The specific menu command used, e.g. `amenu` or `vnoremenu`.
+        command: String,
+        /// Whether this command was invoked with a bang (`!`).
+        bang: bool,
+        /// Any attributes of the menu item - could include "silent", "special" and/or "script".
+        attrs: Vec<String>,
+        /// The icon file given with `icon=`, if there is one.
+        icon: Option<String>,
+        /// The priority of the menu item, e.g. `10.20`, if one was given.
+        priority: Option<String>,
+        /// `Some(true)` for `enable`, `Some(false)` for `disable` and `None` for neither.
+        enable: Option<bool>,
+        /// The menu path, with any `\ ` and `\.` escapes kept as written, e.g. `File.Save\ All`.
+        /// Empty if the command only lists menus.
+        path: String,
+        /// The right-hand side of the menu item, i.e. what it runs.
+        right: String,
+    },
+    /// A method call, e.g. `list->len()`.
+    Method {
+        pos: Position,
+        /// The expression the method is called on.
+        left: Box<Node>,
+        /// The function being called - a [Call](#variant.Call) node, whose arguments do not
+        /// include `left`.
+        right: Box<Node>,
+    },
+    /// A `normal` command
+    Normal {
+        pos: Position,
+        mods: Vec<Modifier>,
+        /// The range given to the command, e.g. `'<,'>` in `'<,'>normal! .`. Empty if no range
+        /// was given.
+        range: Range,
+        /// Whether this command was invoked with a bang (`!`).
+        bang: bool,
+        /// The argument of the command, exactly as it appeared in the source. Everything up to
+        /// the end of the line is part of it, including bars, quotes, trailing whitespace and any
+        /// control characters inserted literally (e.g. with `<C-v>`), so in
+        /// ```text
+        /// normal! A;^[
+        /// ```
+        /// (where `^[` is a literal escape character) this is `"A;\x1b"`.
+        raw: String,
+    },
     /// A number
     Number {
         pos: Position,
@@ -584,6 +1017,58 @@ pub enum Node {
         /// The value to return, if there is one.
         left: Option<Box<Node>>,
     },
+    /// A `runtime` command, e.g. `runtime! START plugin/**/*.vim`.
+    Runtime {
+        pos: Position,
+        mods: Vec<Modifier>,
+        /// Whether this command was invoked with a bang (`!`), which sources every matching file
+        /// rather than only the first.
+        bang: bool,
+        /// Where to look for the files if not in 'runtimepath', i.e. "START", "OPT", "PACK" or
+        /// "ALL".
+        location: Option<String>,
+        /// The file patterns, as written, including backslash escapes.
+        files: Vec<String>,
+    },
+    /// A `sign` command, e.g. `sign define Error text=>> texthl=ErrorMsg`.
+    Sign {
+        pos: Position,
+        mods: Vec<Modifier>,
+        /// The range given to the command. Empty if no range was given.
+        range: Range,
+        /// The subcommand, i.e. "define", "undefine", "list", "place", "unplace" or "jump".
+        subcommand: String,
+        /// The arguments that aren't `key=value` attributes, e.g. the sign name of a
+        /// `sign define` or the id of a `sign place`.
+        args: Vec<String>,
+        /// The `key=value` attributes, in `(key, value)` form, e.g. `("texthl", "ErrorMsg")`.
+        attrs: Vec<(String, String)>,
+    },
+    /// A `set`, `setlocal` or `setglobal` command.
+    Set {
+        pos: Position,
+        mods: Vec<Modifier>,
+        /// The command, i.e. "set", "setlocal" or "setglobal".
+        cmd: String,
+        /// Whether this command was invoked with a bang (`!`).
+        bang: bool,
+        /// The arguments, in `(option, operator, value)` form. The option includes any prefix or
+        /// suffix (`no`, `inv`, `!`, `&`, `?` etc), the operator is one of `=`, `:`, `+=`, `-=`
+        /// and `^=`, and the value is kept as written, including backslash escapes. Arguments
+        /// without a value have an empty operator and value, e.g. `set nowrap ts=4` gives
+        /// `[("nowrap", "", ""), ("ts", "=", "4")]`.
+        args: Vec<(String, String, String)>,
+    },
+    /// A `source` command, e.g. `source <sfile>:h/other.vim`.
+    Source {
+        pos: Position,
+        mods: Vec<Modifier>,
+        /// Whether this command was invoked with a bang (`!`), which reads the file as Normal
+        /// mode commands.
+        bang: bool,
+        /// The file, as written, or `None` if none was given.
+        file: Option<String>,
+    },
     /// A shebang (`#!`). Not common in VimL (it's a holdover from the Python library from which
     /// this parser was translated).
     Shebang {
@@ -609,6 +1094,31 @@ pub enum Node {
         /// The string. It includes the surrounding quotes.
         value: String,
     },
+    /// A `substitute`, `smagic` or `snomagic` command, e.g. `s/foo/bar/g`. The pattern and
+    /// replacement are kept exactly as written.
+    Substitute {
+        pos: Position,
+        mods: Vec<Modifier>,
+        /// The range given to the command, e.g. `%` in `%s/foo/bar/`. Empty if no range was given.
+        range: Range,
+        /// The specific command - `substitute`, `smagic` or `snomagic`.
+        cmd: String,
+        /// The command's name as written, e.g. `s` or `sm`.
+        name: String,
+        /// The character around the pattern and replacement, e.g. `/`, or `None` if there is no
+        /// pattern, e.g. in `s g`, which repeats the last substitution.
+        delimiter: Option<char>,
+        /// The pattern. Empty to use the last search pattern, e.g. in `s//bar/`.
+        pattern: String,
+        /// The replacement, or `None` if the pattern isn't followed by a delimiter, e.g. in
+        /// `s/foo`.
+        replacement: Option<String>,
+        /// The flags, e.g. `&g` or `gc`, or `None` if the replacement isn't followed by a
+        /// delimiter, e.g. in `s/foo/bar`.
+        flags: Option<String>,
+        /// The count, e.g. `3` in `s/foo/bar/ 3`.
+        count: Option<usize>,
+    },
     /// A subscripted expression (e.g. `foo[1]`)
     Subscript {
         pos: Position,
@@ -644,6 +1154,8 @@ pub enum Node {
         pos: Position,
         /// The statements of the input.
         body: Vec<Node>,
+        /// Any [Trivia](enum.Trivia.html) found in the input, in source order.
+        trivia: Vec<Trivia>,
     },
     /// A try statement
     Try {
@@ -692,51 +1204,97 @@ pub enum Node {
     },
 }
 
+/// Something a `let` assigns to, as given by [let_targets](enum.Node.html#method.let_targets).
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum LetTarget<'a> {
+    /// A variable, or part of one, e.g. `x`, `g:x`, `x[1]` or `x.y`.
+    Variable(&'a Node),
+    /// An option, e.g. `&l:sw`, with its name.
+    Option(OptionScope, &'a str),
+    /// A register, e.g. `@a`. `@@` is the unnamed register, `"`.
+    Register(char),
+    /// An environment variable, e.g. `$HOME`, without the `$`.
+    Env(&'a str),
+}
+
+impl<'a> From<&'a Node> for LetTarget<'a> {
+    fn from(node: &'a Node) -> Self {
+        match node {
+            Node::Option { value, .. } => {
+                let (scope, name) = split_option(value);
+                LetTarget::Option(scope, name)
+            }
+            Node::Reg { value, .. } => match value.chars().nth(1) {
+                Some('@') | None => LetTarget::Register('"'),
+                Some(c) => LetTarget::Register(c),
+            },
+            Node::Env { value, .. } => LetTarget::Env(&value[1..]),
+            _ => LetTarget::Variable(node),
+        }
+    }
+}
+
 impl Node {
     /// The position of a node. Also accessible directly through the `pos` member of each node's
     /// inner struct (every node variant has a `pos` member), but this method is provided for
     /// convenience to avoid having to destructure a variant just to get the position.
     pub fn pos(&self) -> Position {
         match self {
-            Node::Autocmd { pos, .. }
+            Node::Abbreviation { pos, .. }
+            | Node::Autocmd { pos, .. }
             | Node::BinaryOp { pos, .. }
+            | Node::Bar { pos, .. }
             | Node::BlankLine { pos, .. }
             | Node::Call { pos, .. }
             | Node::Catch { pos, .. }
+            | Node::Colorscheme { pos, .. }
             | Node::Comment { pos, .. }
             | Node::CurlyName { pos, .. }
             | Node::CurlyNameExpr { pos, .. }
             | Node::CurlyNamePart { pos, .. }
             | Node::Dict { pos, .. }
+            | Node::Digraphs { pos, .. }
             | Node::Dot { pos, .. }
             | Node::Echo { pos, .. }
             | Node::Else { pos, .. }
             | Node::ElseIf { pos, .. }
             | Node::End { pos, .. }
             | Node::Env { pos, .. }
+            | Node::Eval { pos, .. }
             | Node::ExCall { pos, .. }
-            | Node::ExCmd { pos, .. }
+            | Node::ExCmdRaw { pos, .. }
             | Node::Execute { pos, .. }
             | Node::Finally { pos, .. }
             | Node::For { pos, .. }
             | Node::Function { pos, .. }
+            | Node::Global { pos, .. }
             | Node::Highlight { pos, .. }
             | Node::Identifier { pos, .. }
             | Node::If { pos, .. }
             | Node::Lambda { pos, .. }
+            | Node::Lang { pos, .. }
             | Node::Let { pos, .. }
             | Node::List { pos, .. }
             | Node::LockVar { pos, .. }
             | Node::Mapping { pos, .. }
+            | Node::Match { pos, .. }
+            | Node::Menu { pos, .. }
+            | Node::Method { pos, .. }
+            | Node::Normal { pos, .. }
             | Node::Number { pos, .. }
             | Node::Option { pos, .. }
             | Node::ParenExpr { pos, .. }
             | Node::Reg { pos, .. }
             | Node::Return { pos, .. }
+            | Node::Runtime { pos, .. }
+            | Node::Set { pos, .. }
             | Node::Shebang { pos, .. }
+            | Node::Sign { pos, .. }
+            | Node::Source { pos, .. }
             | Node::Slice { pos, .. }
             | Node::String { pos, .. }
             | Node::Subscript { pos, .. }
+            | Node::Substitute { pos, .. }
             | Node::Ternary { pos, .. }
             | Node::Throw { pos, .. }
             | Node::TopLevel { pos, .. }
@@ -747,6 +1305,140 @@ impl Node {
         }
     }
 
+    /// The name of the variant of a node, e.g. `"ExCmdRaw"` for an [ExCmdRaw](#variant.ExCmdRaw).
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Node::Abbreviation { .. } => "Abbreviation",
+            Node::Autocmd { .. } => "Autocmd",
+            Node::BinaryOp { .. } => "BinaryOp",
+            Node::Bar { .. } => "Bar",
+            Node::BlankLine { .. } => "BlankLine",
+            Node::Call { .. } => "Call",
+            Node::Catch { .. } => "Catch",
+            Node::Colorscheme { .. } => "Colorscheme",
+            Node::Comment { .. } => "Comment",
+            Node::CurlyName { .. } => "CurlyName",
+            Node::CurlyNameExpr { .. } => "CurlyNameExpr",
+            Node::CurlyNamePart { .. } => "CurlyNamePart",
+            Node::Dict { .. } => "Dict",
+            Node::Digraphs { .. } => "Digraphs",
+            Node::Dot { .. } => "Dot",
+            Node::Echo { .. } => "Echo",
+            Node::Else { .. } => "Else",
+            Node::ElseIf { .. } => "ElseIf",
+            Node::End { .. } => "End",
+            Node::Env { .. } => "Env",
+            Node::Eval { .. } => "Eval",
+            Node::ExCall { .. } => "ExCall",
+            Node::ExCmdRaw { .. } => "ExCmdRaw",
+            Node::Execute { .. } => "Execute",
+            Node::Finally { .. } => "Finally",
+            Node::For { .. } => "For",
+            Node::Function { .. } => "Function",
+            Node::Global { .. } => "Global",
+            Node::Highlight { .. } => "Highlight",
+            Node::Identifier { .. } => "Identifier",
+            Node::If { .. } => "If",
+            Node::Lambda { .. } => "Lambda",
+            Node::Lang { .. } => "Lang",
+            Node::Let { .. } => "Let",
+            Node::List { .. } => "List",
+            Node::LockVar { .. } => "LockVar",
+            Node::Mapping { .. } => "Mapping",
+            Node::Match { .. } => "Match",
+            Node::Menu { .. } => "Menu",
+            Node::Method { .. } => "Method",
+            Node::Normal { .. } => "Normal",
+            Node::Number { .. } => "Number",
+            Node::Option { .. } => "Option",
+            Node::ParenExpr { .. } => "ParenExpr",
+            Node::Reg { .. } => "Reg",
+            Node::Return { .. } => "Return",
+            Node::Runtime { .. } => "Runtime",
+            Node::Set { .. } => "Set",
+            Node::Shebang { .. } => "Shebang",
+            Node::Sign { .. } => "Sign",
+            Node::Source { .. } => "Source",
+            Node::Slice { .. } => "Slice",
+            Node::String { .. } => "String",
+            Node::Subscript { .. } => "Subscript",
+            Node::Substitute { .. } => "Substitute",
+            Node::Ternary { .. } => "Ternary",
+            Node::Throw { .. } => "Throw",
+            Node::TopLevel { .. } => "TopLevel",
+            Node::Try { .. } => "Try",
+            Node::UnaryOp { .. } => "UnaryOp",
+            Node::Unlet { .. } => "Unlet",
+            Node::While { .. } => "While",
+        }
+    }
+
+    /// The direct children of a node, in the order they appear in the source. This includes
+    /// clauses like [ElseIf](#variant.ElseIf) and [End](#variant.End) nodes as well as the
+    /// statements in a body and the operands of an expression.
+    pub fn children(&self) -> Vec<&Node> {
+        children!(self, &Node)
+    }
+
+    /// Mutable version of [children](#method.children).
+    pub fn children_mut(&mut self) -> Vec<&mut Node> {
+        children!(self, &mut Node)
+    }
+
+    /// The statements in the body of a node that has one (see [has_body](#method.has_body)), or
+    /// nothing for any other node. The clauses of an if or a try have bodies of their own.
+    pub fn body(&self) -> &[Node] {
+        match self {
+            Node::Catch { body, .. }
+            | Node::Else { body, .. }
+            | Node::ElseIf { body, .. }
+            | Node::Finally { body, .. }
+            | Node::For { body, .. }
+            | Node::Function { body, .. }
+            | Node::If { body, .. }
+            | Node::TopLevel { body, .. }
+            | Node::Try { body, .. }
+            | Node::While { body, .. } => body,
+            _ => &[],
+        }
+    }
+
+    /// The statements in the body of a node (see [body](#method.body)) that have comments on the
+    /// lines just above them, each with those comments in source order. A blank line or a
+    /// trailing comment between a comment and the next statement ends the association.
+    pub fn leading_comments(&self) -> Vec<(&Node, Vec<&Node>)> {
+        let mut rv = vec![];
+        let mut comments = vec![];
+        for node in self.body() {
+            match node {
+                Node::Comment {
+                    trailing: false, ..
+                } => comments.push(node),
+                Node::Comment { .. } | Node::BlankLine { .. } => comments.clear(),
+                _ if !comments.is_empty() => rv.push((node, std::mem::take(&mut comments))),
+                _ => (),
+            }
+        }
+        rv
+    }
+
+    /// What a [Let](#variant.Let) node assigns to, in the order they appear in the source, or
+    /// nothing for any other node.
+    pub fn let_targets(&self) -> Vec<LetTarget<'_>> {
+        match self {
+            Node::Let {
+                var, list, rest, ..
+            } => var
+                .iter()
+                .map(|v| v.as_ref())
+                .chain(list)
+                .chain(rest.iter().map(|r| r.as_ref()))
+                .map(LetTarget::from)
+                .collect(),
+            _ => vec![],
+        }
+    }
+
     /// Whether a given node is a [For](#variant.For) node.
     pub fn is_for(node: &Node) -> bool {
         match node {
@@ -787,6 +1479,75 @@ impl Node {
             _ => false,
         }
     }
+
+    /// Whether a given block node ([If](#variant.If), [For](#variant.For),
+    /// [Function](#variant.Function), [Try](#variant.Try) or [While](#variant.While)) was opened
+    /// and closed on the same line of the source, with its statements separated by bars, e.g.
+    /// ```text
+    /// if has('nvim') | set inccommand=nosplit | endif
+    /// ```
+    /// Always `false` for any other kind of node.
+    pub fn is_one_line(node: &Node) -> bool {
+        match node {
+            Node::For { pos, end, .. }
+            | Node::Function { pos, end, .. }
+            | Node::If { pos, end, .. }
+            | Node::Try { pos, end, .. }
+            | Node::While { pos, end, .. } => match end {
+                Some(e) => e.pos().line == pos.line,
+                None => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// The full name of the command a statement is, e.g. `"nnoremap"` for a
+    /// [Mapping](#variant.Mapping) or `"delete"` for an [ExCmdRaw](#variant.ExCmdRaw), whichever
+    /// variant the command is parsed into. Code looking for particular commands should use this
+    /// rather than match on `ExCmdRaw`, since commands that are raw now may be given their own
+    /// variants later. `None` for nodes that aren't commands (expressions, comments and blank
+    /// lines), for the `End` of a block and for a line with only a range.
+    pub fn command_name(&self) -> Option<&str> {
+        let name = match self {
+            Node::Abbreviation { command, .. }
+            | Node::ExCmdRaw { command, .. }
+            | Node::Mapping { command, .. }
+            | Node::Menu { command, .. } => command.as_str(),
+            Node::Echo { cmd, .. }
+            | Node::Global { cmd, .. }
+            | Node::Lang { cmd, .. }
+            | Node::Let { cmd, .. }
+            | Node::LockVar { cmd, .. }
+            | Node::Set { cmd, .. }
+            | Node::Substitute { cmd, .. } => cmd.as_str(),
+            Node::Autocmd { .. } => "autocmd",
+            Node::Catch { .. } => "catch",
+            Node::Colorscheme { .. } => "colorscheme",
+            Node::Digraphs { .. } => "digraphs",
+            Node::Else { .. } => "else",
+            Node::ElseIf { .. } => "elseif",
+            Node::Eval { .. } => "eval",
+            Node::ExCall { .. } => "call",
+            Node::Execute { .. } => "execute",
+            Node::Finally { .. } => "finally",
+            Node::For { .. } => "for",
+            Node::Function { .. } => "function",
+            Node::Highlight { .. } => "highlight",
+            Node::If { .. } => "if",
+            Node::Match { .. } => "match",
+            Node::Normal { .. } => "normal",
+            Node::Return { .. } => "return",
+            Node::Runtime { .. } => "runtime",
+            Node::Sign { .. } => "sign",
+            Node::Source { .. } => "source",
+            Node::Throw { .. } => "throw",
+            Node::Try { .. } => "try",
+            Node::Unlet { .. } => "unlet",
+            Node::While { .. } => "while",
+            _ => return None,
+        };
+        Some(name).filter(|name| !name.is_empty())
+    }
 }
 
 fn format_body(body: &[Node]) -> String {
@@ -991,6 +1752,11 @@ impl fmt::Display for Node {
                         format!("({})", name)
                     }
                 }
+                Node::Bar { .. } => "(bar)".to_string(),
+                Node::Colorscheme { name, .. } => match name {
+                    Some(name) => format!("(colorscheme {})", name),
+                    None => "(colorscheme)".to_string(),
+                },
                 Node::Comment { value, .. } => format!(";{}", value),
                 Node::CurlyName { pieces, .. } => pieces
                     .iter()
@@ -1019,14 +1785,28 @@ impl fmt::Display for Node {
                         "(dict)".to_string()
                     }
                 }
+                Node::Digraphs { bang, digraphs, .. } => {
+                    let mut rv = format!("(digraphs{}", if *bang { "!" } else { "" });
+                    for (chars, number) in digraphs {
+                        rv.push_str(&format!(" {} {}", chars, number));
+                    }
+                    rv.push(')');
+                    rv
+                }
                 Node::Dot { left, right, .. } => display_lr("dot", left, right),
                 Node::Echo { cmd, list, .. } => display_with_list(&cmd, &list),
+                Node::Eval { expr, .. } => display_left("eval", expr),
                 Node::ExCall { left, .. } => display_left("call", left),
-                Node::ExCmd { command, args, .. } => {
+                Node::ExCmdRaw {
+                    range,
+                    command,
+                    args,
+                    ..
+                } => {
                     if command == "break" || command == "continue" {
                         format!("({})", command)
                     } else {
-                        let mut rv = format!("(excmd \"{}", command);
+                        let mut rv = format!("(excmd \"{}{}", range, command);
                         if !args.is_empty() {
                             rv.push_str(&format!(" {}", args));
                         }
@@ -1091,6 +1871,7 @@ impl fmt::Display for Node {
                     expr
                 ),
                 Node::Let {
+                    cmd,
                     var,
                     list,
                     rest,
@@ -1114,7 +1895,7 @@ impl fmt::Display for Node {
                         l.push_str(")");
                         l
                     };
-                    format!("(let {} {} {})", op, left, right)
+                    format!("({} {} {} {})", cmd, op, left, right)
                 }
                 Node::List { items, .. } => {
                     if items.is_empty() {
@@ -1132,14 +1913,22 @@ impl fmt::Display for Node {
                         display_with_list(&cmd, &list)
                     }
                 }
-                Node::Mapping {
+                Node::Abbreviation {
+                    command,
+                    left,
+                    right,
+                    right_expr,
+                    ..
+                }
+                | Node::Mapping {
                     command,
                     left,
                     right,
                     right_expr,
                     ..
                 } => {
-                    let mut rv = format!("({}", command);
+                    let bang = matches!(self, Node::Mapping { bang: true, .. });
+                    let mut rv = format!("({}{}", command, if bang { "!" } else { "" });
                     if !left.is_empty() {
                         rv.push_str(&format!(" {}", left));
                         if let Some(re) = right_expr {
@@ -1151,6 +1940,65 @@ impl fmt::Display for Node {
                     rv.push(')');
                     rv
                 }
+                Node::Match {
+                    range,
+                    group,
+                    delimiter,
+                    pattern,
+                    ..
+                } => {
+                    let mut rv = format!("({}match", range);
+                    if !group.is_empty() {
+                        rv.push_str(&format!(" {}", group));
+                    }
+                    if let Some(d) = delimiter {
+                        rv.push_str(&format!(" {}{}{}", d, pattern, d));
+                    }
+                    rv.push(')');
+                    rv
+                }
+                Node::Menu {
+                    command,
+                    path,
+                    right,
+                    ..
+                } => {
+                    let mut rv = format!("({}", command);
+                    if !path.is_empty() {
+                        rv.push_str(&format!(" {}", path));
+                        if !right.is_empty() {
+                            rv.push_str(&format!(" {}", right));
+                        }
+                    }
+                    rv.push(')');
+                    rv
+                }
+                Node::Method { left, right, .. } => format!("(method {} {})", left, right),
+                Node::Normal { bang, raw, .. } => format!(
+                    "(normal{} \"{}\")",
+                    if *bang { "!" } else { "" },
+                    escape(raw)
+                ),
+                Node::Lang {
+                    cmd,
+                    trim,
+                    marker,
+                    lines,
+                    ..
+                } => {
+                    let mut rv = format!("({} <<", cmd);
+                    if *trim {
+                        rv.push_str(" trim");
+                    }
+                    if let Some(marker) = marker {
+                        rv.push_str(&format!(" {}", marker));
+                    }
+                    for line in lines {
+                        rv.push_str(&format!("\n{}", line));
+                    }
+                    rv.push(')');
+                    rv
+                }
                 Node::ParenExpr { expr, .. } => format!("{}", expr),
                 Node::Return { left, .. } => {
                     if let Some(ref l) = left {
@@ -1159,7 +2007,48 @@ impl fmt::Display for Node {
                         "(return)".to_string()
                     }
                 }
+                Node::Set { cmd, args, .. } => {
+                    let mut rv = format!("({}", cmd);
+                    for (option, op, value) in args {
+                        rv.push_str(&format!(" {}{}{}", option, op, value));
+                    }
+                    rv.push(')');
+                    rv
+                }
+                Node::Runtime {
+                    bang,
+                    location,
+                    files,
+                    ..
+                } => {
+                    let mut rv = format!("(runtime{}", if *bang { "!" } else { "" });
+                    for word in location.iter().chain(files) {
+                        rv.push_str(&format!(" {}", word));
+                    }
+                    rv.push(')');
+                    rv
+                }
                 Node::Shebang { value, .. } => format!("(#! \"{}\")", escape(value)),
+                Node::Source { bang, file, .. } => match file {
+                    Some(file) => format!("(source{} {})", if *bang { "!" } else { "" }, file),
+                    None => format!("(source{})", if *bang { "!" } else { "" }),
+                },
+                Node::Sign {
+                    subcommand,
+                    args,
+                    attrs,
+                    ..
+                } => {
+                    let mut rv = format!("(sign {}", subcommand);
+                    for arg in args {
+                        rv.push_str(&format!(" {}", arg));
+                    }
+                    for (key, value) in attrs {
+                        rv.push_str(&format!(" {}={}", key, value));
+                    }
+                    rv.push(')');
+                    rv
+                }
                 Node::Slice {
                     name, left, right, ..
                 } => {
@@ -1173,7 +2062,55 @@ impl fmt::Display for Node {
                     };
                     format!("(slice {} {} {})", name, r0, r1)
                 }
+                Node::Global {
+                    range,
+                    bang,
+                    cmd,
+                    delimiter,
+                    pattern,
+                    body,
+                    ..
+                } => {
+                    let mut rv = format!(
+                        "({}{}{} \"{}\" \"{}\"",
+                        range,
+                        cmd,
+                        if *bang { "!" } else { "" },
+                        escape(&delimiter.to_string()),
+                        escape(pattern)
+                    );
+                    for node in body {
+                        rv.push_str(&format!(" {}", node));
+                    }
+                    rv.push(')');
+                    rv
+                }
                 Node::Subscript { name, index, .. } => display_lr("subscript", name, index),
+                Node::Substitute {
+                    range,
+                    cmd,
+                    delimiter,
+                    pattern,
+                    replacement,
+                    flags,
+                    count,
+                    ..
+                } => {
+                    let quote = |s: Option<&str>| match s {
+                        Some(s) => format!("\"{}\"", escape(s)),
+                        None => "nil".to_string(),
+                    };
+                    format!(
+                        "({}{} {} {} {} {} {})",
+                        range,
+                        cmd,
+                        quote(delimiter.map(|d| d.to_string()).as_deref()),
+                        quote(Some(pattern)),
+                        quote(replacement.as_deref()),
+                        quote(flags.as_deref()),
+                        count.map_or("nil".to_string(), |c| c.to_string())
+                    )
+                }
                 Node::Ternary {
                     cond, left, right, ..
                 } => display_lr(&format!("?: {}", cond), left, right),
@@ -1207,9 +2144,10 @@ mod tests {
     use super::*;
 
     fn dummy_node() -> Node {
-        Node::ExCmd {
+        Node::ExCmdRaw {
             pos: Position::empty(),
             mods: vec![],
+            range: Range::default(),
             bang: false,
             command: "break".to_string(),
             args: "".to_string(),
@@ -1303,4 +2241,78 @@ mod tests {
         assert!(Node::has_body(&while_node));
         assert!(!Node::has_body(&break_node));
     }
+
+    #[test]
+    fn test_children() {
+        let node = crate::parse_lines(&["if x", "echo 1 + 2", "endif"]).unwrap();
+        let children = node.children();
+        assert_eq!(children.len(), 1);
+        let children = children[0].children();
+        assert_eq!(children.len(), 3);
+        assert_eq!(format!("{}", children[0]), "x");
+        assert_eq!(format!("{}", children[1]), "(echo (+ 1 2))");
+        assert!(matches!(children[2], Node::End { .. }));
+        assert_eq!(children[2].kind(), "End");
+        let mut node = crate::parse_lines(&["echo foo bar"]).unwrap();
+        for child in node.children_mut()[0].children_mut() {
+            if let Node::Identifier { value, .. } = child {
+                value.make_ascii_uppercase();
+            }
+        }
+        assert_eq!(format!("{}", node), "(echo FOO BAR)");
+    }
+
+    #[test]
+    fn test_leading_comments() {
+        let node = crate::parse_lines(&[
+            "\" one",
+            "\" two",
+            "let x = 1 \" trailing",
+            "\" three",
+            "",
+            "let y = 2",
+            "function! F()",
+            "  \" four",
+            "  return 1",
+            "endfunction",
+        ])
+        .unwrap();
+        let value = |n: &Node| match n {
+            Node::Comment { value, .. } => value.clone(),
+            _ => String::new(),
+        };
+        let top = node.leading_comments();
+        assert_eq!(top.len(), 1);
+        assert_eq!(format!("{}", top[0].0), "(let = x 1)");
+        assert_eq!(
+            top[0].1.iter().map(|n| value(n)).collect::<Vec<_>>(),
+            vec![" one", " two"]
+        );
+        let function = node.body().last().unwrap();
+        let inner = function.leading_comments();
+        assert_eq!(inner.len(), 1);
+        assert_eq!(format!("{}", inner[0].0), "(return 1)");
+        assert!(inner[0].0.leading_comments().is_empty());
+    }
+
+    #[test]
+    fn test_is_one_line() {
+        let end = |line| {
+            Some(Box::new(Node::End {
+                pos: Position::new(0, line, 1),
+                mods: vec![],
+            }))
+        };
+        let while_node = |end| Node::While {
+            pos: Position::new(0, 1, 1),
+            mods: vec![],
+            body: vec![],
+            cond: Box::new(dummy_node()),
+            end,
+        };
+        assert!(Node::is_one_line(&while_node(end(1))));
+        assert!(!Node::is_one_line(&while_node(end(3))));
+        assert!(!Node::is_one_line(&while_node(None)));
+        assert!(!Node::is_one_line(&dummy_node()));
+    }
 }
@@ -0,0 +1,888 @@
+use std::fmt;
+
+/// How an option is scoped where it is set with `let` or read in an expression.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum OptionScope {
+    /// `&name`, the local value of an option that has one, otherwise the global value.
+    Default,
+    /// `&g:name`, the global value.
+    Global,
+    /// `&l:name`, the local value.
+    Local,
+}
+
+impl fmt::Display for OptionScope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OptionScope::Default => write!(f, "&"),
+            OptionScope::Global => write!(f, "&g:"),
+            OptionScope::Local => write!(f, "&l:"),
+        }
+    }
+}
+
+/// Split an option as it is written in Vim script, e.g. `&l:sw`, into its scope and name.
+pub fn split_option(option: &str) -> (OptionScope, &str) {
+    let name = option.strip_prefix('&').unwrap_or(option);
+    if let Some(name) = name.strip_prefix("g:") {
+        (OptionScope::Global, name)
+    } else if let Some(name) = name.strip_prefix("l:") {
+        (OptionScope::Local, name)
+    } else {
+        (OptionScope::Default, name)
+    }
+}
+
+// the short names of Vim's options, sorted, with their full names
+const SHORT_NAMES: &[(&str, &str)] = &[
+    ("acd", "autochdir"),
+    ("ai", "autoindent"),
+    ("akm", "altkeymap"),
+    ("al", "aleph"),
+    ("ambw", "ambiwidth"),
+    ("anti", "antialias"),
+    ("ar", "autoread"),
+    ("arab", "arabic"),
+    ("ari", "allowrevins"),
+    ("arshape", "arabicshape"),
+    ("asd", "autoshelldir"),
+    ("aw", "autowrite"),
+    ("awa", "autowriteall"),
+    ("bdir", "backupdir"),
+    ("bdlay", "balloondelay"),
+    ("beval", "ballooneval"),
+    ("bevalterm", "balloonevalterm"),
+    ("bex", "backupext"),
+    ("bexpr", "balloonexpr"),
+    ("bg", "background"),
+    ("bh", "bufhidden"),
+    ("bin", "binary"),
+    ("biosk", "bioskey"),
+    ("bk", "backup"),
+    ("bkc", "backupcopy"),
+    ("bl", "buflisted"),
+    ("bo", "belloff"),
+    ("bri", "breakindent"),
+    ("briopt", "breakindentopt"),
+    ("brk", "breakat"),
+    ("bs", "backspace"),
+    ("bsdir", "browsedir"),
+    ("bsk", "backupskip"),
+    ("bt", "buftype"),
+    ("cb", "clipboard"),
+    ("cc", "colorcolumn"),
+    ("ccv", "charconvert"),
+    ("cd", "cdpath"),
+    ("cdh", "cdhome"),
+    ("cf", "confirm"),
+    ("cfu", "completefunc"),
+    ("ch", "cmdheight"),
+    ("ci", "copyindent"),
+    ("cin", "cindent"),
+    ("cink", "cinkeys"),
+    ("cino", "cinoptions"),
+    ("cinsd", "cinscopedecls"),
+    ("cinw", "cinwords"),
+    ("cm", "cryptmethod"),
+    ("cmp", "casemap"),
+    ("cms", "commentstring"),
+    ("co", "columns"),
+    ("cocu", "concealcursor"),
+    ("cole", "conceallevel"),
+    ("com", "comments"),
+    ("consk", "conskey"),
+    ("cot", "completeopt"),
+    ("cp", "compatible"),
+    ("cpo", "cpoptions"),
+    ("cpp", "completepopup"),
+    ("cpt", "complete"),
+    ("crb", "cursorbind"),
+    ("csl", "completeslash"),
+    ("cspc", "cscopepathcomp"),
+    ("csprg", "cscopeprg"),
+    ("csqf", "cscopequickfix"),
+    ("csre", "cscoperelative"),
+    ("cst", "cscopetag"),
+    ("csto", "cscopetagorder"),
+    ("csverb", "cscopeverbose"),
+    ("cuc", "cursorcolumn"),
+    ("cul", "cursorline"),
+    ("culopt", "cursorlineopt"),
+    ("cwh", "cmdwinheight"),
+    ("deco", "delcombine"),
+    ("def", "define"),
+    ("dex", "diffexpr"),
+    ("dg", "digraph"),
+    ("dict", "dictionary"),
+    ("dip", "diffopt"),
+    ("dir", "directory"),
+    ("dy", "display"),
+    ("ea", "equalalways"),
+    ("ead", "eadirection"),
+    ("eb", "errorbells"),
+    ("ed", "edcompatible"),
+    ("ef", "errorfile"),
+    ("efm", "errorformat"),
+    ("ei", "eventignore"),
+    ("ek", "esckeys"),
+    ("emo", "emoji"),
+    ("enc", "encoding"),
+    ("eof", "endoffile"),
+    ("eol", "endofline"),
+    ("ep", "equalprg"),
+    ("et", "expandtab"),
+    ("ex", "exrc"),
+    ("fcl", "foldclose"),
+    ("fcs", "fillchars"),
+    ("fdc", "foldcolumn"),
+    ("fde", "foldexpr"),
+    ("fdi", "foldignore"),
+    ("fdl", "foldlevel"),
+    ("fdls", "foldlevelstart"),
+    ("fdm", "foldmethod"),
+    ("fdn", "foldnestmax"),
+    ("fdo", "foldopen"),
+    ("fdt", "foldtext"),
+    ("fen", "foldenable"),
+    ("fenc", "fileencoding"),
+    ("fencs", "fileencodings"),
+    ("fex", "formatexpr"),
+    ("ff", "fileformat"),
+    ("ffs", "fileformats"),
+    ("fic", "fileignorecase"),
+    ("fixeol", "fixendofline"),
+    ("fk", "fkmap"),
+    ("flp", "formatlistpat"),
+    ("fml", "foldminlines"),
+    ("fmr", "foldmarker"),
+    ("fo", "formatoptions"),
+    ("fp", "formatprg"),
+    ("fs", "fsync"),
+    ("ft", "filetype"),
+    ("gcr", "guicursor"),
+    ("gd", "gdefault"),
+    ("gfm", "grepformat"),
+    ("gfn", "guifont"),
+    ("gfs", "guifontset"),
+    ("gfw", "guifontwide"),
+    ("ghr", "guiheadroom"),
+    ("gli", "guiligatures"),
+    ("go", "guioptions"),
+    ("gp", "grepprg"),
+    ("gtl", "guitablabel"),
+    ("gtt", "guitabtooltip"),
+    ("hf", "helpfile"),
+    ("hh", "helpheight"),
+    ("hi", "history"),
+    ("hid", "hidden"),
+    ("hk", "hkmap"),
+    ("hkp", "hkmapp"),
+    ("hl", "highlight"),
+    ("hlg", "helplang"),
+    ("hls", "hlsearch"),
+    ("ic", "ignorecase"),
+    ("im", "insertmode"),
+    ("imaf", "imactivatefunc"),
+    ("imak", "imactivatekey"),
+    ("imc", "imcmdline"),
+    ("imd", "imdisable"),
+    ("imi", "iminsert"),
+    ("ims", "imsearch"),
+    ("imsf", "imstatusfunc"),
+    ("imst", "imstyle"),
+    ("inc", "include"),
+    ("inde", "indentexpr"),
+    ("indk", "indentkeys"),
+    ("inex", "includeexpr"),
+    ("inf", "infercase"),
+    ("is", "incsearch"),
+    ("isf", "isfname"),
+    ("isi", "isident"),
+    ("isk", "iskeyword"),
+    ("isp", "isprint"),
+    ("js", "joinspaces"),
+    ("km", "keymodel"),
+    ("kmp", "keymap"),
+    ("kp", "keywordprg"),
+    ("kpc", "keyprotocol"),
+    ("lbr", "linebreak"),
+    ("lcs", "listchars"),
+    ("lm", "langmenu"),
+    ("lmap", "langmap"),
+    ("lnr", "langnoremap"),
+    ("lop", "lispoptions"),
+    ("lpl", "loadplugins"),
+    ("lrm", "langremap"),
+    ("ls", "laststatus"),
+    ("lsp", "linespace"),
+    ("lw", "lispwords"),
+    ("lz", "lazyredraw"),
+    ("ma", "modifiable"),
+    ("mat", "matchtime"),
+    ("mco", "maxcombine"),
+    ("mef", "makeef"),
+    ("menc", "makeencoding"),
+    ("mfd", "maxfuncdepth"),
+    ("mh", "mousehide"),
+    ("mis", "menuitems"),
+    ("ml", "modeline"),
+    ("mle", "modelineexpr"),
+    ("mls", "modelines"),
+    ("mm", "maxmem"),
+    ("mmd", "maxmapdepth"),
+    ("mmp", "maxmempattern"),
+    ("mmt", "maxmemtot"),
+    ("mod", "modified"),
+    ("mousef", "mousefocus"),
+    ("mousem", "mousemodel"),
+    ("mousemev", "mousemoveevent"),
+    ("mouses", "mouseshape"),
+    ("mouset", "mousetime"),
+    ("mp", "makeprg"),
+    ("mps", "matchpairs"),
+    ("msm", "mkspellmem"),
+    ("mzq", "mzquantum"),
+    ("nf", "nrformats"),
+    ("nu", "number"),
+    ("nuw", "numberwidth"),
+    ("odev", "opendevice"),
+    ("oft", "osfiletype"),
+    ("ofu", "omnifunc"),
+    ("opfunc", "operatorfunc"),
+    ("pa", "path"),
+    ("para", "paragraphs"),
+    ("pdev", "printdevice"),
+    ("penc", "printencoding"),
+    ("pex", "patchexpr"),
+    ("pexpr", "printexpr"),
+    ("pfn", "printfont"),
+    ("ph", "pumheight"),
+    ("pheader", "printheader"),
+    ("pi", "preserveindent"),
+    ("pm", "patchmode"),
+    ("pmbcs", "printmbcharset"),
+    ("pmbfn", "printmbfont"),
+    ("popt", "printoptions"),
+    ("pp", "packpath"),
+    ("pt", "pastetoggle"),
+    ("pvh", "previewheight"),
+    ("pvp", "previewpopup"),
+    ("pvw", "previewwindow"),
+    ("pw", "pumwidth"),
+    ("pyx", "pyxversion"),
+    ("qe", "quoteescape"),
+    ("qftf", "quickfixtextfunc"),
+    ("rdt", "redrawtime"),
+    ("re", "regexpengine"),
+    ("ri", "revins"),
+    ("rl", "rightleft"),
+    ("rlc", "rightleftcmd"),
+    ("rnu", "relativenumber"),
+    ("ro", "readonly"),
+    ("rop", "renderoptions"),
+    ("rs", "restorescreen"),
+    ("rtp", "runtimepath"),
+    ("ru", "ruler"),
+    ("ruf", "rulerformat"),
+    ("sb", "splitbelow"),
+    ("sbo", "scrollopt"),
+    ("sbr", "showbreak"),
+    ("sc", "showcmd"),
+    ("scb", "scrollbind"),
+    ("scf", "scrollfocus"),
+    ("scl", "signcolumn"),
+    ("scr", "scroll"),
+    ("scs", "smartcase"),
+    ("sect", "sections"),
+    ("sel", "selection"),
+    ("sft", "showfulltag"),
+    ("sh", "shell"),
+    ("shcf", "shellcmdflag"),
+    ("shm", "shortmess"),
+    ("shq", "shellquote"),
+    ("si", "smartindent"),
+    ("siso", "sidescrolloff"),
+    ("sj", "scrolljump"),
+    ("slm", "selectmode"),
+    ("sloc", "showcmdloc"),
+    ("sm", "showmatch"),
+    ("smc", "synmaxcol"),
+    ("smd", "showmode"),
+    ("sms", "smoothscroll"),
+    ("sn", "shortname"),
+    ("so", "scrolloff"),
+    ("sol", "startofline"),
+    ("sp", "shellpipe"),
+    ("spc", "spellcapcheck"),
+    ("spf", "spellfile"),
+    ("spk", "splitkeep"),
+    ("spl", "spelllang"),
+    ("spo", "spelloptions"),
+    ("spr", "splitright"),
+    ("sps", "spellsuggest"),
+    ("sr", "shiftround"),
+    ("srr", "shellredir"),
+    ("ss", "sidescroll"),
+    ("ssl", "shellslash"),
+    ("ssop", "sessionoptions"),
+    ("st", "shelltype"),
+    ("sta", "smarttab"),
+    ("stal", "showtabline"),
+    ("stl", "statusline"),
+    ("stmp", "shelltemp"),
+    ("sts", "softtabstop"),
+    ("su", "suffixes"),
+    ("sua", "suffixesadd"),
+    ("sw", "shiftwidth"),
+    ("swb", "switchbuf"),
+    ("swf", "swapfile"),
+    ("sws", "swapsync"),
+    ("sxe", "shellxescape"),
+    ("sxq", "shellxquote"),
+    ("syn", "syntax"),
+    ("ta", "textauto"),
+    ("tag", "tags"),
+    ("tal", "tabline"),
+    ("tb", "toolbar"),
+    ("tbi", "ttybuiltin"),
+    ("tbidi", "termbidi"),
+    ("tbis", "toolbariconsize"),
+    ("tbs", "tagbsearch"),
+    ("tc", "tagcase"),
+    ("tenc", "termencoding"),
+    ("tf", "ttyfast"),
+    ("tfu", "tagfunc"),
+    ("tgc", "termguicolors"),
+    ("tgst", "tagstack"),
+    ("tl", "taglength"),
+    ("tm", "timeoutlen"),
+    ("to", "timeout"),
+    ("top", "tildeop"),
+    ("tpm", "tabpagemax"),
+    ("tr", "tagrelative"),
+    ("ts", "tabstop"),
+    ("tsl", "ttyscroll"),
+    ("tsr", "thesaurus"),
+    ("tsrfu", "thesaurusfunc"),
+    ("ttm", "ttimeoutlen"),
+    ("tty", "ttytype"),
+    ("ttym", "ttymouse"),
+    ("tw", "textwidth"),
+    ("twk", "termwinkey"),
+    ("tws", "termwinsize"),
+    ("twsl", "termwinscroll"),
+    ("twt", "termwintype"),
+    ("tx", "textmode"),
+    ("uc", "updatecount"),
+    ("udf", "undofile"),
+    ("udir", "undodir"),
+    ("ul", "undolevels"),
+    ("ur", "undoreload"),
+    ("ut", "updatetime"),
+    ("vb", "visualbell"),
+    ("vbs", "verbose"),
+    ("vdir", "viewdir"),
+    ("ve", "virtualedit"),
+    ("vfile", "verbosefile"),
+    ("vi", "viminfo"),
+    ("vif", "viminfofile"),
+    ("vop", "viewoptions"),
+    ("vsts", "varsofttabstop"),
+    ("vts", "vartabstop"),
+    ("wa", "writeany"),
+    ("wak", "winaltkeys"),
+    ("wb", "writebackup"),
+    ("wc", "wildchar"),
+    ("wcm", "wildcharm"),
+    ("wcr", "wincolor"),
+    ("wd", "writedelay"),
+    ("wfh", "winfixheight"),
+    ("wfw", "winfixwidth"),
+    ("wh", "winheight"),
+    ("wi", "window"),
+    ("wic", "wildignorecase"),
+    ("wig", "wildignore"),
+    ("wim", "wildmode"),
+    ("wiv", "weirdinvert"),
+    ("wiw", "winwidth"),
+    ("wm", "wrapmargin"),
+    ("wmh", "winminheight"),
+    ("wmnu", "wildmenu"),
+    ("wmw", "winminwidth"),
+    ("wop", "wildoptions"),
+    ("ws", "wrapscan"),
+    ("ww", "whichwrap"),
+];
+
+/// The full name of an option given its short name, e.g. `shiftwidth` for `sw`. A name that isn't
+/// the short name of one of Vim's options, including a full name, is returned as it is.
+pub fn option_full_name(name: &str) -> &str {
+    match SHORT_NAMES.binary_search_by(|(short, _)| (*short).cmp(name)) {
+        Ok(i) => SHORT_NAMES[i].1,
+        Err(_) => name,
+    }
+}
+
+/// The type of the value of an option.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum OptionType {
+    /// An option that is on or off, e.g. `wrap`, set with `set wrap` or `set nowrap`.
+    Boolean,
+    Number,
+    String,
+}
+
+// the full names of Vim's options, sorted, with their types
+const OPTION_TYPES: &[(&str, OptionType)] = &[
+    ("aleph", OptionType::Number),
+    ("allowrevins", OptionType::Boolean),
+    ("altkeymap", OptionType::Boolean),
+    ("ambiwidth", OptionType::String),
+    ("antialias", OptionType::Boolean),
+    ("arabic", OptionType::Boolean),
+    ("arabicshape", OptionType::Boolean),
+    ("autochdir", OptionType::Boolean),
+    ("autoindent", OptionType::Boolean),
+    ("autoread", OptionType::Boolean),
+    ("autoshelldir", OptionType::Boolean),
+    ("autowrite", OptionType::Boolean),
+    ("autowriteall", OptionType::Boolean),
+    ("background", OptionType::String),
+    ("backspace", OptionType::String),
+    ("backup", OptionType::Boolean),
+    ("backupcopy", OptionType::String),
+    ("backupdir", OptionType::String),
+    ("backupext", OptionType::String),
+    ("backupskip", OptionType::String),
+    ("balloondelay", OptionType::Number),
+    ("ballooneval", OptionType::Boolean),
+    ("balloonevalterm", OptionType::Boolean),
+    ("balloonexpr", OptionType::String),
+    ("belloff", OptionType::String),
+    ("binary", OptionType::Boolean),
+    ("bioskey", OptionType::Boolean),
+    ("bomb", OptionType::Boolean),
+    ("breakat", OptionType::String),
+    ("breakindent", OptionType::Boolean),
+    ("breakindentopt", OptionType::String),
+    ("browsedir", OptionType::String),
+    ("bufhidden", OptionType::String),
+    ("buflisted", OptionType::Boolean),
+    ("buftype", OptionType::String),
+    ("casemap", OptionType::String),
+    ("cdhome", OptionType::Boolean),
+    ("cdpath", OptionType::String),
+    ("cedit", OptionType::String),
+    ("charconvert", OptionType::String),
+    ("cindent", OptionType::Boolean),
+    ("cinkeys", OptionType::String),
+    ("cinoptions", OptionType::String),
+    ("cinscopedecls", OptionType::String),
+    ("cinwords", OptionType::String),
+    ("clipboard", OptionType::String),
+    ("cmdheight", OptionType::Number),
+    ("cmdwinheight", OptionType::Number),
+    ("colorcolumn", OptionType::String),
+    ("columns", OptionType::Number),
+    ("comments", OptionType::String),
+    ("commentstring", OptionType::String),
+    ("compatible", OptionType::Boolean),
+    ("complete", OptionType::String),
+    ("completefunc", OptionType::String),
+    ("completeopt", OptionType::String),
+    ("completepopup", OptionType::String),
+    ("completeslash", OptionType::String),
+    ("concealcursor", OptionType::String),
+    ("conceallevel", OptionType::Number),
+    ("confirm", OptionType::Boolean),
+    ("conskey", OptionType::Boolean),
+    ("copyindent", OptionType::Boolean),
+    ("cpoptions", OptionType::String),
+    ("cryptmethod", OptionType::String),
+    ("cscopepathcomp", OptionType::Number),
+    ("cscopeprg", OptionType::String),
+    ("cscopequickfix", OptionType::String),
+    ("cscoperelative", OptionType::Boolean),
+    ("cscopetag", OptionType::Boolean),
+    ("cscopetagorder", OptionType::Number),
+    ("cscopeverbose", OptionType::Boolean),
+    ("cursorbind", OptionType::Boolean),
+    ("cursorcolumn", OptionType::Boolean),
+    ("cursorline", OptionType::Boolean),
+    ("cursorlineopt", OptionType::String),
+    ("debug", OptionType::String),
+    ("define", OptionType::String),
+    ("delcombine", OptionType::Boolean),
+    ("dictionary", OptionType::String),
+    ("diff", OptionType::Boolean),
+    ("diffexpr", OptionType::String),
+    ("diffopt", OptionType::String),
+    ("digraph", OptionType::Boolean),
+    ("directory", OptionType::String),
+    ("display", OptionType::String),
+    ("eadirection", OptionType::String),
+    ("edcompatible", OptionType::Boolean),
+    ("emoji", OptionType::Boolean),
+    ("encoding", OptionType::String),
+    ("endoffile", OptionType::Boolean),
+    ("endofline", OptionType::Boolean),
+    ("equalalways", OptionType::Boolean),
+    ("equalprg", OptionType::String),
+    ("errorbells", OptionType::Boolean),
+    ("errorfile", OptionType::String),
+    ("errorformat", OptionType::String),
+    ("esckeys", OptionType::Boolean),
+    ("eventignore", OptionType::String),
+    ("expandtab", OptionType::Boolean),
+    ("exrc", OptionType::Boolean),
+    ("fileencoding", OptionType::String),
+    ("fileencodings", OptionType::String),
+    ("fileformat", OptionType::String),
+    ("fileformats", OptionType::String),
+    ("fileignorecase", OptionType::Boolean),
+    ("filetype", OptionType::String),
+    ("fillchars", OptionType::String),
+    ("fixendofline", OptionType::Boolean),
+    ("fkmap", OptionType::Boolean),
+    ("foldclose", OptionType::String),
+    ("foldcolumn", OptionType::Number),
+    ("foldenable", OptionType::Boolean),
+    ("foldexpr", OptionType::String),
+    ("foldignore", OptionType::String),
+    ("foldlevel", OptionType::Number),
+    ("foldlevelstart", OptionType::Number),
+    ("foldmarker", OptionType::String),
+    ("foldmethod", OptionType::String),
+    ("foldminlines", OptionType::Number),
+    ("foldnestmax", OptionType::Number),
+    ("foldopen", OptionType::String),
+    ("foldtext", OptionType::String),
+    ("formatexpr", OptionType::String),
+    ("formatlistpat", OptionType::String),
+    ("formatoptions", OptionType::String),
+    ("formatprg", OptionType::String),
+    ("fsync", OptionType::Boolean),
+    ("gdefault", OptionType::Boolean),
+    ("grepformat", OptionType::String),
+    ("grepprg", OptionType::String),
+    ("guicursor", OptionType::String),
+    ("guifont", OptionType::String),
+    ("guifontset", OptionType::String),
+    ("guifontwide", OptionType::String),
+    ("guiheadroom", OptionType::Number),
+    ("guiligatures", OptionType::String),
+    ("guioptions", OptionType::String),
+    ("guipty", OptionType::Boolean),
+    ("guitablabel", OptionType::String),
+    ("guitabtooltip", OptionType::String),
+    ("helpfile", OptionType::String),
+    ("helpheight", OptionType::Number),
+    ("helplang", OptionType::String),
+    ("hidden", OptionType::Boolean),
+    ("highlight", OptionType::String),
+    ("history", OptionType::Number),
+    ("hkmap", OptionType::Boolean),
+    ("hkmapp", OptionType::Boolean),
+    ("hlsearch", OptionType::Boolean),
+    ("icon", OptionType::Boolean),
+    ("iconstring", OptionType::String),
+    ("ignorecase", OptionType::Boolean),
+    ("imactivatefunc", OptionType::String),
+    ("imactivatekey", OptionType::String),
+    ("imcmdline", OptionType::Boolean),
+    ("imdisable", OptionType::Boolean),
+    ("iminsert", OptionType::Number),
+    ("imsearch", OptionType::Number),
+    ("imstatusfunc", OptionType::String),
+    ("imstyle", OptionType::Number),
+    ("include", OptionType::String),
+    ("includeexpr", OptionType::String),
+    ("incsearch", OptionType::Boolean),
+    ("indentexpr", OptionType::String),
+    ("indentkeys", OptionType::String),
+    ("infercase", OptionType::Boolean),
+    ("insertmode", OptionType::Boolean),
+    ("isfname", OptionType::String),
+    ("isident", OptionType::String),
+    ("iskeyword", OptionType::String),
+    ("isprint", OptionType::String),
+    ("joinspaces", OptionType::Boolean),
+    ("key", OptionType::String),
+    ("keymap", OptionType::String),
+    ("keymodel", OptionType::String),
+    ("keyprotocol", OptionType::String),
+    ("keywordprg", OptionType::String),
+    ("langmap", OptionType::String),
+    ("langmenu", OptionType::String),
+    ("langnoremap", OptionType::Boolean),
+    ("langremap", OptionType::Boolean),
+    ("laststatus", OptionType::Number),
+    ("lazyredraw", OptionType::Boolean),
+    ("linebreak", OptionType::Boolean),
+    ("lines", OptionType::Number),
+    ("linespace", OptionType::Number),
+    ("lisp", OptionType::Boolean),
+    ("lispoptions", OptionType::String),
+    ("lispwords", OptionType::String),
+    ("list", OptionType::Boolean),
+    ("listchars", OptionType::String),
+    ("loadplugins", OptionType::Boolean),
+    ("luadll", OptionType::String),
+    ("macatsui", OptionType::Boolean),
+    ("magic", OptionType::Boolean),
+    ("makeef", OptionType::String),
+    ("makeencoding", OptionType::String),
+    ("makeprg", OptionType::String),
+    ("matchpairs", OptionType::String),
+    ("matchtime", OptionType::Number),
+    ("maxcombine", OptionType::Number),
+    ("maxfuncdepth", OptionType::Number),
+    ("maxmapdepth", OptionType::Number),
+    ("maxmem", OptionType::Number),
+    ("maxmempattern", OptionType::Number),
+    ("maxmemtot", OptionType::Number),
+    ("menuitems", OptionType::Number),
+    ("mkspellmem", OptionType::String),
+    ("modeline", OptionType::Boolean),
+    ("modelineexpr", OptionType::Boolean),
+    ("modelines", OptionType::Number),
+    ("modifiable", OptionType::Boolean),
+    ("modified", OptionType::Boolean),
+    ("more", OptionType::Boolean),
+    ("mouse", OptionType::String),
+    ("mousefocus", OptionType::Boolean),
+    ("mousehide", OptionType::Boolean),
+    ("mousemodel", OptionType::String),
+    ("mousemoveevent", OptionType::Boolean),
+    ("mouseshape", OptionType::String),
+    ("mousetime", OptionType::Number),
+    ("mzquantum", OptionType::Number),
+    ("mzschemedll", OptionType::String),
+    ("mzschemegcdll", OptionType::String),
+    ("nrformats", OptionType::String),
+    ("number", OptionType::Boolean),
+    ("numberwidth", OptionType::Number),
+    ("omnifunc", OptionType::String),
+    ("opendevice", OptionType::Boolean),
+    ("operatorfunc", OptionType::String),
+    ("osfiletype", OptionType::String),
+    ("packpath", OptionType::String),
+    ("paragraphs", OptionType::String),
+    ("paste", OptionType::Boolean),
+    ("pastetoggle", OptionType::String),
+    ("patchexpr", OptionType::String),
+    ("patchmode", OptionType::String),
+    ("path", OptionType::String),
+    ("perldll", OptionType::String),
+    ("preserveindent", OptionType::Boolean),
+    ("previewheight", OptionType::Number),
+    ("previewpopup", OptionType::String),
+    ("previewwindow", OptionType::Boolean),
+    ("printdevice", OptionType::String),
+    ("printencoding", OptionType::String),
+    ("printexpr", OptionType::String),
+    ("printfont", OptionType::String),
+    ("printheader", OptionType::String),
+    ("printmbcharset", OptionType::String),
+    ("printmbfont", OptionType::String),
+    ("printoptions", OptionType::String),
+    ("prompt", OptionType::Boolean),
+    ("pumheight", OptionType::Number),
+    ("pumwidth", OptionType::Number),
+    ("pythondll", OptionType::String),
+    ("pythonhome", OptionType::String),
+    ("pythonthreedll", OptionType::String),
+    ("pythonthreehome", OptionType::String),
+    ("pyxversion", OptionType::Number),
+    ("quickfixtextfunc", OptionType::String),
+    ("quoteescape", OptionType::String),
+    ("readonly", OptionType::Boolean),
+    ("redrawtime", OptionType::Number),
+    ("regexpengine", OptionType::Number),
+    ("relativenumber", OptionType::Boolean),
+    ("remap", OptionType::Boolean),
+    ("renderoptions", OptionType::String),
+    ("report", OptionType::Number),
+    ("restorescreen", OptionType::Boolean),
+    ("revins", OptionType::Boolean),
+    ("rightleft", OptionType::Boolean),
+    ("rightleftcmd", OptionType::String),
+    ("rubydll", OptionType::String),
+    ("ruler", OptionType::Boolean),
+    ("rulerformat", OptionType::String),
+    ("runtimepath", OptionType::String),
+    ("scroll", OptionType::Number),
+    ("scrollbind", OptionType::Boolean),
+    ("scrollfocus", OptionType::Boolean),
+    ("scrolljump", OptionType::Number),
+    ("scrolloff", OptionType::Number),
+    ("scrollopt", OptionType::String),
+    ("sections", OptionType::String),
+    ("secure", OptionType::Boolean),
+    ("selection", OptionType::String),
+    ("selectmode", OptionType::String),
+    ("sessionoptions", OptionType::String),
+    ("shell", OptionType::String),
+    ("shellcmdflag", OptionType::String),
+    ("shellpipe", OptionType::String),
+    ("shellquote", OptionType::String),
+    ("shellredir", OptionType::String),
+    ("shellslash", OptionType::Boolean),
+    ("shelltemp", OptionType::Boolean),
+    ("shelltype", OptionType::Number),
+    ("shellxescape", OptionType::String),
+    ("shellxquote", OptionType::String),
+    ("shiftround", OptionType::Boolean),
+    ("shiftwidth", OptionType::Number),
+    ("shortmess", OptionType::String),
+    ("shortname", OptionType::Boolean),
+    ("showbreak", OptionType::String),
+    ("showcmd", OptionType::Boolean),
+    ("showcmdloc", OptionType::String),
+    ("showfulltag", OptionType::Boolean),
+    ("showmatch", OptionType::Boolean),
+    ("showmode", OptionType::Boolean),
+    ("showtabline", OptionType::Number),
+    ("sidescroll", OptionType::Number),
+    ("sidescrolloff", OptionType::Number),
+    ("signcolumn", OptionType::String),
+    ("smartcase", OptionType::Boolean),
+    ("smartindent", OptionType::Boolean),
+    ("smarttab", OptionType::Boolean),
+    ("smoothscroll", OptionType::Boolean),
+    ("softtabstop", OptionType::Number),
+    ("spell", OptionType::Boolean),
+    ("spellcapcheck", OptionType::String),
+    ("spellfile", OptionType::String),
+    ("spelllang", OptionType::String),
+    ("spelloptions", OptionType::String),
+    ("spellsuggest", OptionType::String),
+    ("splitbelow", OptionType::Boolean),
+    ("splitkeep", OptionType::String),
+    ("splitright", OptionType::Boolean),
+    ("startofline", OptionType::Boolean),
+    ("statusline", OptionType::String),
+    ("suffixes", OptionType::String),
+    ("suffixesadd", OptionType::String),
+    ("swapfile", OptionType::Boolean),
+    ("swapsync", OptionType::String),
+    ("switchbuf", OptionType::String),
+    ("synmaxcol", OptionType::Number),
+    ("syntax", OptionType::String),
+    ("tabline", OptionType::String),
+    ("tabpagemax", OptionType::Number),
+    ("tabstop", OptionType::Number),
+    ("tagbsearch", OptionType::Boolean),
+    ("tagcase", OptionType::String),
+    ("tagfunc", OptionType::String),
+    ("taglength", OptionType::Number),
+    ("tagrelative", OptionType::Boolean),
+    ("tags", OptionType::String),
+    ("tagstack", OptionType::Boolean),
+    ("tcldll", OptionType::String),
+    ("term", OptionType::String),
+    ("termbidi", OptionType::Boolean),
+    ("termencoding", OptionType::String),
+    ("termguicolors", OptionType::Boolean),
+    ("termwinkey", OptionType::String),
+    ("termwinscroll", OptionType::Number),
+    ("termwinsize", OptionType::String),
+    ("termwintype", OptionType::String),
+    ("terse", OptionType::Boolean),
+    ("textauto", OptionType::Boolean),
+    ("textmode", OptionType::Boolean),
+    ("textwidth", OptionType::Number),
+    ("thesaurus", OptionType::String),
+    ("thesaurusfunc", OptionType::String),
+    ("tildeop", OptionType::Boolean),
+    ("timeout", OptionType::Boolean),
+    ("timeoutlen", OptionType::Number),
+    ("title", OptionType::Boolean),
+    ("titlelen", OptionType::Number),
+    ("titleold", OptionType::String),
+    ("titlestring", OptionType::String),
+    ("toolbar", OptionType::String),
+    ("toolbariconsize", OptionType::String),
+    ("ttimeout", OptionType::Boolean),
+    ("ttimeoutlen", OptionType::Number),
+    ("ttybuiltin", OptionType::Boolean),
+    ("ttyfast", OptionType::Boolean),
+    ("ttymouse", OptionType::String),
+    ("ttyscroll", OptionType::Number),
+    ("ttytype", OptionType::String),
+    ("undodir", OptionType::String),
+    ("undofile", OptionType::Boolean),
+    ("undolevels", OptionType::Number),
+    ("undoreload", OptionType::Number),
+    ("updatecount", OptionType::Number),
+    ("updatetime", OptionType::Number),
+    ("varsofttabstop", OptionType::String),
+    ("vartabstop", OptionType::String),
+    ("verbose", OptionType::Number),
+    ("verbosefile", OptionType::String),
+    ("viewdir", OptionType::String),
+    ("viewoptions", OptionType::String),
+    ("viminfo", OptionType::String),
+    ("viminfofile", OptionType::String),
+    ("virtualedit", OptionType::String),
+    ("visualbell", OptionType::Boolean),
+    ("warn", OptionType::Boolean),
+    ("weirdinvert", OptionType::Boolean),
+    ("whichwrap", OptionType::String),
+    ("wildchar", OptionType::Number),
+    ("wildcharm", OptionType::Number),
+    ("wildignore", OptionType::String),
+    ("wildignorecase", OptionType::Boolean),
+    ("wildmenu", OptionType::Boolean),
+    ("wildmode", OptionType::String),
+    ("wildoptions", OptionType::String),
+    ("winaltkeys", OptionType::String),
+    ("wincolor", OptionType::String),
+    ("window", OptionType::Number),
+    ("winfixheight", OptionType::Boolean),
+    ("winfixwidth", OptionType::Boolean),
+    ("winheight", OptionType::Number),
+    ("winminheight", OptionType::Number),
+    ("winminwidth", OptionType::Number),
+    ("winptydll", OptionType::String),
+    ("winwidth", OptionType::Number),
+    ("wrap", OptionType::Boolean),
+    ("wrapmargin", OptionType::Number),
+    ("wrapscan", OptionType::Boolean),
+    ("write", OptionType::Boolean),
+    ("writeany", OptionType::Boolean),
+    ("writebackup", OptionType::Boolean),
+    ("writedelay", OptionType::Number),
+    ("xtermcodes", OptionType::Boolean),
+];
+
+/// The type of one of Vim's options, given its full name or its short name.
+pub fn option_type(name: &str) -> Option<OptionType> {
+    let name = option_full_name(name);
+    OPTION_TYPES
+        .binary_search_by(|(full, _)| (*full).cmp(name))
+        .ok()
+        .map(|i| OPTION_TYPES[i].1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_options() {
+        assert_eq!(split_option("&l:sw"), (OptionScope::Local, "sw"));
+        assert_eq!(split_option("&g:t_Co"), (OptionScope::Global, "t_Co"));
+        assert_eq!(split_option("&tw"), (OptionScope::Default, "tw"));
+        assert_eq!(option_full_name("sw"), "shiftwidth");
+        assert_eq!(option_full_name("ts"), "tabstop");
+        assert_eq!(option_full_name("shiftwidth"), "shiftwidth");
+        assert_eq!(option_full_name("t_Co"), "t_Co");
+        assert!(SHORT_NAMES.windows(2).all(|w| w[0].0 < w[1].0));
+        assert_eq!(option_type("cp"), Some(OptionType::Boolean));
+        assert_eq!(option_type("tabstop"), Some(OptionType::Number));
+        assert_eq!(option_type("ft"), Some(OptionType::String));
+        assert_eq!(option_type("t_Co"), None);
+        assert!(OPTION_TYPES.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+}
@@ -1,17 +1,25 @@
-use super::{isargname, isvarname, CharClassification, ParseError, Position, EOF, EOL};
+use super::{
+    isargname, isvarname, CharClassification, ErrorCode, ParseError, Position, EOF, EOL,
+    HIGHLIGHT_KEYS,
+};
 use crate::{
-    command::{commands, valid_autocmds, Command, Flag, ParserKind},
+    command::{valid_autocmds, Command, CommandMap, Flag, ParserKind},
     exarg::ExArg,
+    keys::split_keys,
     modifier::Modifier,
-    node::{BinaryOpKind, Node, UnaryOpKind},
+    node::{BinaryOpKind, LetTarget, Node, Trivia, UnaryOpKind},
+    range::{Address, LineSpec, Offset, Range},
     reader::Reader,
     token::{Token, TokenKind, Tokenizer},
 };
-use lazy_static::lazy_static;
-use regex::Regex;
-use std::{collections::HashMap, rc::Rc};
+use std::{rc::Rc, sync::Arc};
 
 const MAX_FUNC_ARGS: usize = 20;
+/// How deeply expressions can be nested in parentheses, lists, dicts, calls, unary operators and
+/// so on, beyond which parsing fails instead of overflowing the stack. Each operator after the
+/// first in a chain like `1 + 2 + 3`, and each subscript or call in one like `x[0].y()`, nests
+/// the expression before it a level deeper, so a long enough chain fails too.
+pub const MAX_EXPR_DEPTH: usize = 100;
 
 fn ends_excmds(c: char) -> bool {
     ['|', '"', EOF, EOL].contains(&c)
@@ -19,67 +27,161 @@ fn ends_excmds(c: char) -> bool {
 
 pub(crate) type Result<T> = std::result::Result<T, ParseError>;
 
-fn parse_piped_expressions(s: &str) -> Result<Vec<Node>> {
-    let reader = Reader::from_lines(&[s]);
-    let mut parser = Parser::new(&reader);
-    if let Node::TopLevel { body, .. } = parser.parse()? {
-        Ok(body)
-    } else {
-        Err(ParseError {
-            msg: "unknown sub-parser error: node returned was not a TopLevel node".to_string(),
-            pos: Position::empty(),
-        })
-    }
+/// The attributes, left-hand side, right-hand side and right-hand side expression of a mapping or
+/// abbreviation.
+type MappingArgs = (Vec<String>, String, String, Option<Box<Node>>);
+
+/// A callback consulted for commands that are not in the parser's command table.
+const LET_OPERATORS: &[&str] = &["..=", "+=", "-=", "*=", "/=", "%=", ".=", "="];
+
+pub type UnknownCommand = dyn Fn(&str) -> Option<Command> + Send + Sync;
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
 }
 
-fn make_modifier(k: &str) -> Option<Modifier> {
-    lazy_static! {
-        static ref MODIFIERS: &'static [(&'static str, usize)] = &[
-            ("aboveleft", 3),
-            ("belowright", 3),
-            ("browse", 3),
-            ("botright", 2),
-            ("confirm", 4),
-            ("keepmarks", 3),
-            ("keepalt", 5),
-            ("keepjumps", 5),
-            ("keeppatterns", 5),
-            ("hide", 3),
-            ("lockmarks", 3),
-            ("leftabove", 5),
-            ("noautocmd", 3),
-            ("noswapfile", 3),
-            ("rightbelow", 6),
-            ("sandbox", 3),
-            ("silent", 3),
-            ("tab", 3),
-            ("topleft", 2),
-            ("unsilent", 3),
-            ("vertical", 4),
-            ("verbose", 4),
-        ];
+/// Whether a word ends at the start of `s`, i.e. `s` doesn't start with a word character.
+fn ends_word(s: &str) -> bool {
+    !s.starts_with(is_word_char)
+}
+
+/// Whether `s` starts with one of the abbreviations of `:substitute` that take flags right after
+/// the `s`, like `:sg` or `:sIe`, rather than another command starting with `s`, like `:sil`.
+fn is_substitute_abbreviation(s: &str) -> bool {
+    let c = s.chars().collect::<Vec<char>>();
+    match c.get(..2) {
+        Some(['s', 'g']) | Some(['s', 'I']) => true,
+        Some(['s', 'c']) => {
+            matches!(c.get(2..5), Some([a, b, d]) if !"sr".contains(*a) && *b != 'i' && *d != 'p')
+        }
+        Some(['s', 'i']) => c.get(2).is_some_and(|c| !"mlg".contains(*c)),
+        Some(['s', 'r']) => c.get(2).is_some_and(|c| *c != 'e'),
+        _ => false,
     }
-    for (modifier, min_length) in MODIFIERS.iter() {
-        if modifier.starts_with(&k) && k.len() >= *min_length {
-            return Some(Modifier::new(modifier));
+}
+
+/// Whether `name` is an abbreviation of `:delete` followed by the `l` or `p` flag, e.g. `:dell`.
+fn is_delete_with_flag(name: &str) -> bool {
+    name.len() >= 3
+        && name.starts_with('d')
+        && name.ends_with(['l', 'p'])
+        && "elete".starts_with(&name[1..name.len() - 1])
+}
+
+/// Split the arguments of a `:set` command into `(option, operator, value)` triples. Arguments are
+/// separated by whitespace that is not escaped with a backslash; escapes are kept in the value.
+fn split_set_args(s: &str) -> Vec<(String, String, String)> {
+    split_words(s)
+        .into_iter()
+        .map(|word| match word.find(['=', ':']) {
+            Some(i) => {
+                let start = match word[..i].chars().last() {
+                    Some('+') | Some('-') | Some('^') => i - 1,
+                    _ => i,
+                };
+                (
+                    word[..start].to_string(),
+                    word[start..=i].to_string(),
+                    word[i + 1..].to_string(),
+                )
+            }
+            None => (word, String::new(), String::new()),
+        })
+        .collect()
+}
+
+/// Split a string into words separated by whitespace that is not escaped with a backslash,
+/// keeping the escapes.
+fn split_words(s: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut word = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            word.push(c);
+            if let Some(next) = chars.next() {
+                word.push(next);
+            }
+        } else if c.is_white() {
+            if !word.is_empty() {
+                words.push(word.split_off(0));
+            }
+        } else {
+            word.push(c);
         }
     }
-    None
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
 }
 
-#[derive(Debug)]
-pub struct Parser<'a> {
-    reader: &'a Reader,
+/// The attributes each `sign` subcommand accepts.
+const SIGN_ATTRS: &[(&str, &[&str])] = &[
+    (
+        "define",
+        &[
+            "icon", "linehl", "numhl", "text", "texthl", "culhl", "priority",
+        ],
+    ),
+    ("undefine", &[]),
+    ("list", &[]),
+    (
+        "place",
+        &[
+            "line", "lnum", "name", "group", "priority", "file", "buffer",
+        ],
+    ),
+    ("unplace", &["group", "file", "buffer"]),
+    ("jump", &["group", "file", "buffer"]),
+];
+
+pub struct Parser {
+    reader: Rc<Reader>,
     context: Vec<Node>,
-    commands: HashMap<String, Rc<Command>>,
+    finished: bool,
+    commands: Arc<CommandMap>,
+    unknown_command: Option<Arc<UnknownCommand>>,
+    in_function: bool, // the input is the body of a function that isn't part of it
 }
 
-impl<'a> Parser<'a> {
-    pub fn new(reader: &'a Reader) -> Self {
+impl Parser {
+    pub fn with_commands(
+        reader: Rc<Reader>,
+        commands: Arc<CommandMap>,
+        unknown_command: Option<Arc<UnknownCommand>>,
+    ) -> Self {
         Self {
             reader,
             context: vec![],
-            commands: commands(),
+            finished: false,
+            commands,
+            unknown_command,
+            in_function: false,
+        }
+    }
+
+    pub fn in_function(mut self, in_function: bool) -> Self {
+        self.in_function = in_function;
+        self
+    }
+
+    fn parse_piped_expressions(&self, s: &str) -> Result<Vec<Node>> {
+        let reader = Rc::new(Reader::from_lines(&[s]));
+        let mut parser = Parser::with_commands(
+            reader,
+            Arc::clone(&self.commands),
+            self.unknown_command.clone(),
+        )
+        .in_function(self.in_function || self.find_context(Node::is_function));
+        if let Node::TopLevel { body, .. } = parser.parse()? {
+            Ok(body)
+        } else {
+            Err(ParseError::new(
+                ErrorCode::Internal,
+                "unknown sub-parser error: node returned was not a TopLevel node".to_string(),
+                Position::empty(),
+            ))
         }
     }
 
@@ -181,10 +283,11 @@ impl<'a> Parser<'a> {
 
     fn check_missing_endfunction(&self, end: &str, pos: Position) -> Result<()> {
         if let Node::Function { .. } = self.current_context() {
-            Err(ParseError {
-                msg: format!("E126: Missing :endfunction:    {}", end),
+            Err(ParseError::new(
+                ErrorCode::MissingEnd,
+                format!("E126: Missing :endfunction:    {}", end),
                 pos,
-            })
+            ))
         } else {
             Ok(())
         }
@@ -192,30 +295,33 @@ impl<'a> Parser<'a> {
 
     fn check_missing_endif(&self, end: &str, pos: Position) -> Result<()> {
         match self.current_context() {
-            Node::If { .. } | Node::ElseIf { .. } | Node::Else { .. } => Err(ParseError {
-                msg: format!("E126: Missing :endif:    {}", end),
+            Node::If { .. } | Node::ElseIf { .. } | Node::Else { .. } => Err(ParseError::new(
+                ErrorCode::MissingEnd,
+                format!("E126: Missing :endif:    {}", end),
                 pos,
-            }),
+            )),
             _ => Ok(()),
         }
     }
 
     fn check_missing_endtry(&self, end: &str, pos: Position) -> Result<()> {
         match self.current_context() {
-            Node::Try { .. } | Node::Catch { .. } | Node::Finally { .. } => Err(ParseError {
-                msg: format!("E126: Missing :endtry:    {}", end),
+            Node::Try { .. } | Node::Catch { .. } | Node::Finally { .. } => Err(ParseError::new(
+                ErrorCode::MissingEnd,
+                format!("E126: Missing :endtry:    {}", end),
                 pos,
-            }),
+            )),
             _ => Ok(()),
         }
     }
 
     fn check_missing_endwhile(&self, end: &str, pos: Position) -> Result<()> {
         if let Node::While { .. } = self.current_context() {
-            Err(ParseError {
-                msg: format!("E126: Missing :endwhile:    {}", end),
+            Err(ParseError::new(
+                ErrorCode::MissingEnd,
+                format!("E126: Missing :endwhile:    {}", end),
                 pos,
-            })
+            ))
         } else {
             Ok(())
         }
@@ -223,43 +329,106 @@ impl<'a> Parser<'a> {
 
     fn check_missing_endfor(&self, end: &str, pos: Position) -> Result<()> {
         if let Node::For { .. } = self.current_context() {
-            Err(ParseError {
-                msg: format!("E126: Missing :endfor:    {}", end),
+            Err(ParseError::new(
+                ErrorCode::MissingEnd,
+                format!("E126: Missing :endfor:    {}", end),
                 pos,
-            })
+            ))
         } else {
             Ok(())
         }
     }
 
-    fn err<T>(&self, msg: &str) -> Result<T> {
-        Err(ParseError {
-            msg: msg.to_string(),
-            pos: self.reader.getpos(),
-        })
+    fn err<T>(&self, code: ErrorCode, msg: &str) -> Result<T> {
+        Err(ParseError::new(code, msg.to_string(), self.reader.getpos()))
+    }
+
+    fn err_found<T, F: ToString>(&self, code: ErrorCode, msg: &str, found: F) -> Result<T> {
+        // the offending text may already have been read, in which case the error should point at
+        // its start rather than after it.
+        let found = found.to_string();
+        let cursor = self.reader.tell();
+        let mut pos = self.reader.getpos();
+        let len = found.chars().count();
+        if len > 0 && cursor >= len {
+            self.reader.seek_set(cursor - len);
+            if self.reader.peekn(len) == found {
+                pos = self.reader.getpos();
+            }
+            self.reader.seek_set(cursor);
+        }
+        Err(ParseError::new(code, msg.to_string(), pos).with_found(found))
     }
 
     pub fn parse(&mut self) -> Result<Node> {
         let pos = self.reader.getpos();
-        self.push_context(Node::TopLevel { pos, body: vec![] });
-        while self.reader.peek() != EOF {
-            self.parse_one_cmd()?;
+        let mut body = vec![];
+        while let Some(node) = self.parse_next() {
+            body.push(node?);
+        }
+        Ok(Node::TopLevel {
+            pos,
+            body,
+            trivia: self.reader.trivia().to_vec(),
+        })
+    }
+
+    /// Parse until a top-level statement is complete and return it, or `None` once the input is
+    /// exhausted. Nothing is returned after an error.
+    pub fn parse_next(&mut self) -> Option<Result<Node>> {
+        if self.finished {
+            return None;
+        }
+        if self.context.is_empty() {
+            self.push_context(Node::TopLevel {
+                pos: self.reader.getpos(),
+                body: vec![],
+                trivia: vec![],
+            });
         }
+        let result = loop {
+            if let [Node::TopLevel { body, .. }] = self.context.as_mut_slice() {
+                if !body.is_empty() {
+                    return Some(Ok(body.remove(0)));
+                }
+            }
+            if self.reader.peek() == EOF {
+                break self.check_missing_ends();
+            }
+            if let Err(e) = self.parse_one_cmd() {
+                break Err(e);
+            }
+        };
+        self.finished = true;
+        self.context.clear();
+        result.err().map(Err)
+    }
+
+    fn check_missing_ends(&self) -> Result<()> {
         self.check_missing_endfunction("TOPLEVEL", self.reader.getpos())?;
         self.check_missing_endif("TOPLEVEL", self.reader.getpos())?;
         self.check_missing_endtry("TOPLEVEL", self.reader.getpos())?;
         self.check_missing_endwhile("TOPLEVEL", self.reader.getpos())?;
-        self.check_missing_endfor("TOPLEVEL", self.reader.getpos())?;
-        Ok(self.pop_context())
+        self.check_missing_endfor("TOPLEVEL", self.reader.getpos())
+    }
+
+    /// The trivia of the whole input.
+    pub fn trivia(&self) -> &[Trivia] {
+        self.reader.trivia()
+    }
+
+    pub fn getpos(&self) -> Position {
+        self.reader.getpos()
     }
 
     fn parse_expr(&mut self) -> Result<Node> {
-        ExprParser::new(self.reader).parse()
+        ExprParser::new(&self.reader).parse()
     }
 
     fn parse_one_cmd(&mut self) -> Result<()> {
         if self.reader.peekn(2) == "#!" {
             self.parse_shebang()?;
+            self.reader.get();
             return Ok(());
         }
         let pos = self.reader.getpos();
@@ -288,7 +457,11 @@ impl<'a> Parser<'a> {
     fn parse_shebang(&mut self) -> Result<()> {
         let sb = self.reader.getn(2);
         if sb != "#!" {
-            return self.err(&format!("unexpected characters: {}", sb));
+            return self.err_found(
+                ErrorCode::UnexpectedCharacter,
+                &format!("unexpected characters: {}", sb),
+                sb,
+            );
         }
         let pos = self.reader.getpos();
         let value = self.reader.get_line();
@@ -300,10 +473,12 @@ impl<'a> Parser<'a> {
         let pos = self.reader.getpos();
         let c = self.reader.get();
         if c != '"' {
-            return Err(ParseError {
-                msg: format!("unexpected character: {}", c),
+            return Err(ParseError::new(
+                ErrorCode::UnexpectedCharacter,
+                format!("unexpected character: {}", c),
                 pos,
-            });
+            )
+            .with_found(c));
         }
         self.add_node(Node::Comment {
             pos,
@@ -325,7 +500,7 @@ impl<'a> Parser<'a> {
             let k = self.reader.read_alpha();
             let c = self.reader.peek();
             self.reader.skip_white();
-            if let Some(mut modifier) = make_modifier(&k) {
+            if let Some(mut modifier) = Modifier::from_name(&k) {
                 match modifier.name.as_str() {
                     "hide" => {
                         if ends_excmds(c) {
@@ -354,37 +529,64 @@ impl<'a> Parser<'a> {
         Ok(modifiers)
     }
 
-    fn parse_range(&mut self) -> Result<Vec<String>> {
-        let mut tokens: Vec<String> = vec![];
+    fn parse_range(&mut self) -> Result<Range> {
+        let mut specs: Vec<LineSpec> = vec![];
+        let mut spec = LineSpec::default();
+        // consecutive addresses without a separator between them (like `/foo//bar/`) are
+        // separate specifiers
+        let push_address = |specs: &mut Vec<LineSpec>, spec: &mut LineSpec, address| {
+            if spec.address.is_some() || !spec.offsets.is_empty() {
+                specs.push(std::mem::take(spec));
+            }
+            spec.address = Some(address);
+        };
         loop {
             loop {
                 self.reader.skip_white();
                 let c = self.reader.peek();
-                match c {
-                    '.' | '$' => tokens.push(self.reader.get().to_string()),
+                let address = match c {
+                    '.' | '$' => {
+                        self.reader.get();
+                        Some(if c == '.' {
+                            Address::Current
+                        } else {
+                            Address::Last
+                        })
+                    }
                     '\'' => {
                         if self.reader.peek_ahead(1) == EOL {
                             break;
                         }
-                        tokens.push(self.reader.getn(2));
+                        self.reader.get();
+                        Some(Address::Mark(self.reader.get()))
                     }
                     '/' | '?' => {
                         self.reader.get();
                         let (pattern, _) = self.parse_pattern(&c.to_string())?;
-                        tokens.push(pattern);
+                        Some(Address::Search {
+                            pattern,
+                            backward: c == '?',
+                        })
                     }
                     '\\' => {
                         let m = self.reader.peek_ahead(1);
                         if m == '&' || m == '?' || m == '/' {
-                            tokens.push(self.reader.getn(2));
+                            self.reader.getn(2);
+                            Some(Address::PreviousSearch(m))
                         } else {
-                            return self.err("E10: \\\\ should be followed by /, ? or &");
+                            return self.err(
+                                ErrorCode::InvalidBackslash,
+                                "E10: \\\\ should be followed by /, ? or &",
+                            );
                         }
                     }
-                    _ if c.is_ascii_digit() => {
-                        tokens.push(self.reader.read_digit());
-                    }
-                    _ => (),
+                    _ if c.is_ascii_digit() => Some(Address::Line(
+                        self.reader.read_digit().parse().unwrap_or(usize::MAX),
+                    )),
+                    _ => None,
+                };
+                if let Some(address) = address {
+                    push_address(&mut specs, &mut spec, address);
                 }
                 loop {
                     self.reader.skip_white();
@@ -395,7 +597,12 @@ impl<'a> Parser<'a> {
                     if n == "" {
                         break;
                     }
-                    tokens.push(n);
+                    let count = n.trim_start_matches(['+', '-']).parse().ok();
+                    spec.offsets.push(if n.starts_with('-') {
+                        Offset::Minus(count)
+                    } else {
+                        Offset::Plus(count)
+                    });
                 }
                 if self.reader.peek() != '/' && self.reader.peek() != '?' {
                     break;
@@ -403,16 +610,26 @@ impl<'a> Parser<'a> {
             }
             let p = self.reader.peek();
             if p == '%' || p == '*' {
-                tokens.push(self.reader.get().to_string());
+                self.reader.get();
+                let address = if p == '%' {
+                    Address::All
+                } else {
+                    Address::Visual
+                };
+                push_address(&mut specs, &mut spec, address);
             }
             let p = self.reader.peek();
             if p == ';' || p == ',' {
-                tokens.push(self.reader.get().to_string());
+                spec.separator = Some(self.reader.get());
+                specs.push(std::mem::take(&mut spec));
                 continue;
             }
             break;
         }
-        Ok(tokens)
+        if spec != LineSpec::default() {
+            specs.push(spec);
+        }
+        Ok(Range { specs })
     }
 
     fn parse_pattern(&mut self, delimiter: &str) -> Result<(String, String)> {
@@ -432,7 +649,10 @@ impl<'a> Parser<'a> {
             if c == "\\" {
                 let c = self.reader.peek();
                 if c == EOL {
-                    return self.err("E682: Invalid search pattern or delimiter");
+                    return self.err(
+                        ErrorCode::InvalidSearchPattern,
+                        "E682: Invalid search pattern or delimiter",
+                    );
                 }
                 self.reader.getn(1);
                 pattern.push(c);
@@ -457,10 +677,12 @@ impl<'a> Parser<'a> {
         if let Some(c) = self.find_command() {
             ea.cmd = c;
         } else {
-            return self.err(&format!(
-                "E492: Not an editor command: {}",
-                self.reader.peek_line()
-            ));
+            self.reader.setpos(ea.cmdpos);
+            return self.err_found(
+                ErrorCode::NotAnEditorCommand,
+                &format!("E492: Not an editor command: {}", self.reader.peek_line()),
+                self.reader.peek_line(),
+            );
         }
         if self.reader.peek() == '!'
             && !["substitute", "smagic", "snomagic"].contains(&ea.cmd.name.as_str())
@@ -469,10 +691,11 @@ impl<'a> Parser<'a> {
             ea.bang = true;
         }
         if !ea.cmd.flags.contains(Flag::BANG) && ea.bang && !ea.cmd.flags.contains(Flag::USERCMD) {
-            return Err(ParseError {
-                msg: "E477: No ! allowed".to_string(),
-                pos: ea.cmdpos,
-            });
+            return Err(ParseError::new(
+                ErrorCode::NoBangAllowed,
+                "E477: No ! allowed".to_string(),
+                ea.cmdpos,
+            ));
         }
         if ea.cmd.name != "!" {
             self.reader.skip_white();
@@ -484,7 +707,7 @@ impl<'a> Parser<'a> {
         if ea.cmd.name == "write" || ea.cmd.name == "update" {
             if self.reader.peek() == '>' {
                 if self.reader.peek_ahead(1) == '>' {
-                    return self.err("E494: Use w or w>>");
+                    return self.err(ErrorCode::InvalidWriteAppend, "E494: Use w or w>>");
                 }
                 self.reader.seek_cur(2);
                 self.reader.skip_white();
@@ -515,7 +738,15 @@ impl<'a> Parser<'a> {
     }
 
     fn _parse_command(&mut self, ea: ExArg) -> Result<()> {
+        log::trace!(
+            "line {}, col {}: `{}` parsed by the {:?} parser",
+            ea.cmdpos.line(),
+            ea.cmdpos.column(),
+            ea.cmd.name,
+            ea.cmd.parser
+        );
         match ea.cmd.parser {
+            ParserKind::Abbreviation => self.parse_cmd_abbreviation(ea),
             ParserKind::Append | ParserKind::Insert => {
                 self.parse_cmd_append(ea);
                 Ok(())
@@ -524,8 +755,10 @@ impl<'a> Parser<'a> {
             ParserKind::Break => self.parse_cmd_break(ea),
             ParserKind::Call => self.parse_cmd_call(ea),
             ParserKind::Catch => self.parse_cmd_catch(ea),
+            ParserKind::Colorscheme => self.parse_cmd_colorscheme(ea),
             ParserKind::Common | ParserKind::UserCmd => self.parse_cmd_common(ea),
             ParserKind::Continue => self.parse_cmd_continue(ea),
+            ParserKind::Digraphs => self.parse_cmd_digraphs(ea),
             ParserKind::Echo => self.parse_cmd_echo(ea),
             ParserKind::Else => self.parse_cmd_else(ea),
             ParserKind::ElseIf => self.parse_cmd_elseif(ea),
@@ -534,11 +767,13 @@ impl<'a> Parser<'a> {
             ParserKind::EndIf => self.parse_cmd_endif(ea),
             ParserKind::EndTry => self.parse_cmd_endtry(ea),
             ParserKind::EndWhile => self.parse_cmd_endwhile(ea),
+            ParserKind::Eval => self.parse_cmd_eval(ea),
             ParserKind::Execute => self.parse_cmd_execute(ea),
             ParserKind::Finally => self.parse_cmd_finally(ea),
             ParserKind::Finish => self.parse_cmd_common(ea),
             ParserKind::For => self.parse_cmd_for(ea),
             ParserKind::Function => self.parse_cmd_function(ea),
+            ParserKind::Global => self.parse_cmd_global(ea),
             ParserKind::Highlight => self.parse_cmd_highlight(ea),
             ParserKind::If => self.parse_cmd_if(ea),
             ParserKind::Lang => self.parse_cmd_lang(ea),
@@ -546,7 +781,15 @@ impl<'a> Parser<'a> {
             ParserKind::LoadKeymap => self.parse_cmd_loadkeymap(ea),
             ParserKind::LockVar => self.parse_cmd_lockvar(ea),
             ParserKind::Mapping => self.parse_cmd_mapping(ea),
+            ParserKind::Match => self.parse_cmd_match(ea),
+            ParserKind::Menu => self.parse_cmd_menu(ea),
+            ParserKind::Normal => self.parse_cmd_normal(ea),
             ParserKind::Return => self.parse_cmd_return(ea),
+            ParserKind::Runtime => self.parse_cmd_runtime(ea),
+            ParserKind::Set => self.parse_cmd_set(ea),
+            ParserKind::Sign => self.parse_cmd_sign(ea),
+            ParserKind::Source => self.parse_cmd_source(ea),
+            ParserKind::Substitute => self.parse_cmd_substitute(ea),
             ParserKind::Syntax => self.parse_cmd_syntax(ea),
             ParserKind::Throw => self.parse_cmd_throw(ea),
             ParserKind::Try => self.parse_cmd_try(ea),
@@ -571,9 +814,10 @@ impl<'a> Parser<'a> {
             }
             self.reader.get();
         }
-        self.add_node(Node::ExCmd {
+        self.add_node(Node::ExCmdRaw {
             pos: ea.cmdpos,
             mods: ea.modifiers,
+            range: ea.range,
             command: ea.cmd.name.clone(),
             bang: ea.bang,
             args: if !lines.is_empty() {
@@ -585,10 +829,12 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_cmd_autocmd(&mut self, ea: ExArg) -> Result<()> {
-        // this is a mess because autocmd syntax is bonkers - almost everything is optional
+        // this is a mess because autocmd syntax is bonkers - almost everything is optional. a bar
+        // ends the command before the group, after the group or after the events, as in
+        // `augroup vimrc | autocmd! | augroup END`, but is part of the command after a pattern.
         let pos = ea.cmdpos;
         self.reader.skip_white();
-        if self.reader.peekn(1) == "" {
+        if self.reader.peekn(1) == "" || self.reader.peek() == '|' {
             self.add_node(Node::Autocmd {
                 pos,
                 mods: ea.modifiers,
@@ -601,14 +847,14 @@ impl<'a> Parser<'a> {
             });
             return Ok(());
         }
-        let maybe_group = self.reader.read_nonwhite();
+        let maybe_group = self.reader.read_nonwhitespace_or_bar();
         let (events_str, group) = if maybe_group
             .split(',')
             .all(|word| !valid_autocmds().contains_key(&word.to_lowercase().as_str()))
         {
             // maybe_group contains no autocmd names so assume it's a group
             self.reader.skip_white();
-            if self.reader.peekn(1) == "" {
+            if self.reader.peekn(1) == "" || self.reader.peek() == '|' {
                 self.add_node(Node::Autocmd {
                     pos,
                     mods: ea.modifiers,
@@ -621,20 +867,25 @@ impl<'a> Parser<'a> {
                 });
                 return Ok(());
             }
-            (self.reader.read_nonwhite(), maybe_group)
+            (self.reader.read_nonwhitespace_or_bar(), maybe_group)
         } else {
             // maybe_group contains at least one autocmd name so assume it's a list of events
             (maybe_group, String::new())
         };
         let mut events = vec![];
         for event in events_str.split(",") {
-            match valid_autocmds().get(&event.to_lowercase().as_str()) {
-                Some(e) => events.push(e.clone()),
-                None => return self.err(&format!("E216: No such group or event: {}", event)),
+            if valid_autocmds().contains_key(&event.to_lowercase().as_str()) {
+                events.push(event.to_string());
+            } else {
+                return self.err_found(
+                    ErrorCode::NoSuchGroupOrEvent,
+                    &format!("E216: No such group or event: {}", event),
+                    event,
+                );
             }
         }
         self.reader.skip_white();
-        if self.reader.peekn(1) == "" {
+        if self.reader.peekn(1) == "" || self.reader.peek() == '|' {
             self.add_node(Node::Autocmd {
                 pos,
                 mods: ea.modifiers,
@@ -649,7 +900,7 @@ impl<'a> Parser<'a> {
         }
         let patterns = self
             .reader
-            .read_nonwhite()
+            .read_nonwhitespace()
             .split(',')
             .map(|s| s.to_string())
             .collect::<Vec<String>>();
@@ -686,14 +937,14 @@ impl<'a> Parser<'a> {
             return Ok(());
         }
         let offset = self.reader.tell();
-        let result = parse_piped_expressions(&self.reader.get_line());
+        let result = self.parse_piped_expressions(&self.reader.get_line());
         let body = match result {
             Ok(body) => body,
             Err(e) => {
                 self.reader.seek_set(e.pos.cursor + offset);
                 return Err(ParseError {
-                    msg: e.msg,
                     pos: self.reader.getpos(),
+                    ..e
                 });
             }
         };
@@ -712,11 +963,15 @@ impl<'a> Parser<'a> {
 
     fn parse_cmd_break(&mut self, ea: ExArg) -> Result<()> {
         if !self.find_context(Node::is_while) && !self.find_context(Node::is_for) {
-            return self.err("E587: :break without :while or :for");
+            return self.err(
+                ErrorCode::BreakWithoutLoop,
+                "E587: :break without :while or :for",
+            );
         }
-        self.add_node(Node::ExCmd {
+        self.add_node(Node::ExCmdRaw {
             pos: ea.cmdpos,
             mods: ea.modifiers,
+            range: ea.range,
             bang: ea.bang,
             command: "break".to_string(),
             args: String::new(),
@@ -728,7 +983,7 @@ impl<'a> Parser<'a> {
         let pos = ea.cmdpos;
         self.reader.skip_white();
         if ends_excmds(self.reader.peek()) {
-            return self.err("E471: Argument required");
+            return self.err(ErrorCode::ArgumentRequired, "E471: Argument required");
         }
         let left = self.parse_expr()?;
         match left {
@@ -736,14 +991,16 @@ impl<'a> Parser<'a> {
                 self.add_node(Node::ExCall {
                     pos,
                     mods: ea.modifiers,
+                    range: ea.range,
                     left: Box::new(left),
                 });
                 Ok(())
             }
-            _ => Err(ParseError {
-                msg: "Not a function call".to_string(),
+            _ => Err(ParseError::new(
+                ErrorCode::NotAFunctionCall,
+                "Not a function call".to_string(),
                 pos,
-            }),
+            )),
         }
     }
 
@@ -754,16 +1011,18 @@ impl<'a> Parser<'a> {
                 self.collapse_context();
             }
             Node::Finally { .. } => {
-                return Err(ParseError {
-                    msg: "E604: :catch after :finally".to_string(),
-                    pos: ea.cmdpos,
-                });
+                return Err(ParseError::new(
+                    ErrorCode::CatchAfterFinally,
+                    "E604: :catch after :finally".to_string(),
+                    ea.cmdpos,
+                ));
             }
             _ => {
-                return Err(ParseError {
-                    msg: "E604: :catch without :try".to_string(),
-                    pos: ea.cmdpos,
-                });
+                return Err(ParseError::new(
+                    ErrorCode::CatchWithoutTry,
+                    "E603: :catch without :try".to_string(),
+                    ea.cmdpos,
+                ));
             }
         };
         let pattern = if !ends_excmds(self.reader.peek()) {
@@ -781,6 +1040,17 @@ impl<'a> Parser<'a> {
         Ok(())
     }
 
+    fn parse_cmd_colorscheme(&mut self, ea: ExArg) -> Result<()> {
+        let end = self.separate_nextcmd(&ea)?;
+        let name = self.reader.getstr(ea.argpos, end).trim_end().to_string();
+        self.add_node(Node::Colorscheme {
+            pos: ea.cmdpos,
+            mods: ea.modifiers,
+            name: Some(name).filter(|n| !n.is_empty()),
+        });
+        Ok(())
+    }
+
     fn parse_cmd_common(&mut self, ea: ExArg) -> Result<()> {
         let mut end;
         if ea.cmd.flags.contains(Flag::TRLBAR) && !ea.use_filter {
@@ -793,9 +1063,10 @@ impl<'a> Parser<'a> {
                 }
             }
         }
-        self.add_node(Node::ExCmd {
+        self.add_node(Node::ExCmdRaw {
             pos: ea.cmdpos,
             mods: ea.modifiers,
+            range: ea.range,
             command: ea.cmd.name.clone(),
             args: self.reader.getstr(ea.argpos, end),
             bang: ea.bang,
@@ -805,14 +1076,16 @@ impl<'a> Parser<'a> {
 
     fn parse_cmd_continue(&mut self, ea: ExArg) -> Result<()> {
         if !self.find_context(Node::is_while) && !self.find_context(Node::is_for) {
-            return Err(ParseError {
-                msg: "E586: :continue without :while or :for".to_string(),
-                pos: ea.cmdpos,
-            });
+            return Err(ParseError::new(
+                ErrorCode::ContinueWithoutLoop,
+                "E586: :continue without :while or :for".to_string(),
+                ea.cmdpos,
+            ));
         }
-        self.add_node(Node::ExCmd {
+        self.add_node(Node::ExCmdRaw {
             pos: ea.cmdpos,
             mods: ea.modifiers,
+            range: ea.range,
             bang: ea.bang,
             command: "continue".to_string(),
             args: String::new(),
@@ -831,6 +1104,21 @@ impl<'a> Parser<'a> {
         Ok(())
     }
 
+    fn parse_cmd_eval(&mut self, ea: ExArg) -> Result<()> {
+        self.reader.skip_white();
+        let c = self.reader.peek();
+        if c != '"' && ends_excmds(c) {
+            return self.err(ErrorCode::ArgumentRequired, "E471: Argument required");
+        }
+        let node = Node::Eval {
+            pos: ea.cmdpos,
+            mods: ea.modifiers,
+            expr: Box::new(self.parse_expr()?),
+        };
+        self.add_node(node);
+        Ok(())
+    }
+
     fn parse_cmd_execute(&mut self, ea: ExArg) -> Result<()> {
         let node = Node::Execute {
             pos: ea.cmdpos,
@@ -848,10 +1136,11 @@ impl<'a> Parser<'a> {
                 self.collapse_context();
             }
             _ => {
-                return Err(ParseError {
-                    msg: "E581: :else without :if".to_string(),
-                    pos: ea.cmdpos,
-                });
+                return Err(ParseError::new(
+                    ErrorCode::ElseWithoutIf,
+                    "E581: :else without :if".to_string(),
+                    ea.cmdpos,
+                ));
             }
         };
         self.push_context(Node::Else {
@@ -869,10 +1158,11 @@ impl<'a> Parser<'a> {
                 self.collapse_context();
             }
             _ => {
-                return Err(ParseError {
-                    msg: "E582: :elseif without :if".to_string(),
-                    pos: ea.cmdpos,
-                });
+                return Err(ParseError::new(
+                    ErrorCode::ElseIfWithoutIf,
+                    "E582: :elseif without :if".to_string(),
+                    ea.cmdpos,
+                ));
             }
         };
         let node = Node::ElseIf {
@@ -895,10 +1185,11 @@ impl<'a> Parser<'a> {
                 *end = Some(Box::new(node));
             }
             _ => {
-                return Err(ParseError {
-                    msg: "E588: :endfor without :for".to_string(),
-                    pos: ea.cmdpos,
-                });
+                return Err(ParseError::new(
+                    ErrorCode::EndLoopWithoutLoop,
+                    "E588: :endfor without :for".to_string(),
+                    ea.cmdpos,
+                ));
             }
         };
         self.collapse_context();
@@ -919,10 +1210,11 @@ impl<'a> Parser<'a> {
                 *end = Some(Box::new(node));
             }
             _ => {
-                return Err(ParseError {
-                    msg: "E193: :endfunction not inside a function".to_string(),
-                    pos: ea.cmdpos,
-                });
+                return Err(ParseError::new(
+                    ErrorCode::EndFunctionOutsideFunction,
+                    "E193: :endfunction not inside a function".to_string(),
+                    ea.cmdpos,
+                ));
             }
         };
         self.reader.get_line();
@@ -937,10 +1229,11 @@ impl<'a> Parser<'a> {
                 self.collapse_context();
             }
             _ => {
-                return Err(ParseError {
-                    msg: "E580: :endif without :if".to_string(),
-                    pos: ea.cmdpos,
-                });
+                return Err(ParseError::new(
+                    ErrorCode::EndIfWithoutIf,
+                    "E580: :endif without :if".to_string(),
+                    ea.cmdpos,
+                ));
             }
         };
         if let Node::If { ref mut end, .. } = self.current_context_mut() {
@@ -961,10 +1254,11 @@ impl<'a> Parser<'a> {
                 self.collapse_context();
             }
             _ => {
-                return Err(ParseError {
-                    msg: "E580: :endtry without :try".to_string(),
-                    pos: ea.cmdpos,
-                });
+                return Err(ParseError::new(
+                    ErrorCode::EndTryWithoutTry,
+                    "E602: :endtry without :try".to_string(),
+                    ea.cmdpos,
+                ));
             }
         };
         if let Node::Try { ref mut end, .. } = self.current_context_mut() {
@@ -991,10 +1285,11 @@ impl<'a> Parser<'a> {
                 self.collapse_context();
                 Ok(())
             }
-            _ => Err(ParseError {
-                msg: "E588: :endwhile without :while".to_string(),
-                pos: ea.cmdpos,
-            }),
+            _ => Err(ParseError::new(
+                ErrorCode::EndLoopWithoutLoop,
+                "E588: :endwhile without :while".to_string(),
+                ea.cmdpos,
+            )),
         }
     }
 
@@ -1005,10 +1300,11 @@ impl<'a> Parser<'a> {
                 self.collapse_context();
             }
             _ => {
-                return Err(ParseError {
-                    msg: "E606: :finally without :try".to_string(),
-                    pos: ea.cmdpos,
-                });
+                return Err(ParseError::new(
+                    ErrorCode::FinallyWithoutTry,
+                    "E606: :finally without :try".to_string(),
+                    ea.cmdpos,
+                ));
             }
         };
         self.push_context(Node::Finally {
@@ -1024,10 +1320,11 @@ impl<'a> Parser<'a> {
         self.reader.skip_white();
         let epos = self.reader.getpos();
         if self.reader.read_alpha() != "in" {
-            return Err(ParseError {
-                msg: "Missing \"in\" after :for".to_string(),
-                pos: epos,
-            });
+            return Err(ParseError::new(
+                ErrorCode::MissingIn,
+                "E690: Missing \"in\" after :for".to_string(),
+                epos,
+            ));
         }
         let right = Box::new(self.parse_expr()?);
         self.push_context(Node::For {
@@ -1058,38 +1355,42 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_cmd_lang(&mut self, ea: ExArg) -> Result<()> {
-        let mut lines = vec![];
         self.reader.skip_white();
-        if self.reader.peekn(2) == "<<" {
-            self.reader.getn(2);
-            self.reader.skip_white();
-            let mut m = self.reader.get_line();
-            if m == "" {
-                m = ".".to_string();
-            }
-            self.reader.setpos(ea.argpos);
-            lines.push(self.reader.get_line());
+        if self.reader.peekn(2) != "<<" {
+            return self.parse_cmd_common(ea);
+        }
+        self.reader.getn(2);
+        self.reader.skip_white();
+        let rest = self.reader.get_line();
+        let (trim, rest) = match rest.strip_prefix("trim") {
+            Some(r) if r.is_empty() || r.starts_with(char::is_whitespace) => (true, r.trim()),
+            _ => (false, rest.trim()),
+        };
+        let marker = if rest.is_empty() {
+            None
+        } else {
+            Some(rest.to_string())
+        };
+        let end = marker.as_deref().unwrap_or(".");
+        let mut lines = vec![];
+        while self.reader.peek() != EOF {
             self.reader.get();
-            loop {
-                if self.reader.peek() == EOF {
-                    break;
-                }
-                lines.push(self.reader.get_line());
-                if lines.last().unwrap() == &m {
-                    break;
-                }
-                self.reader.get();
+            if self.reader.peek() == EOF {
+                break;
             }
-        } else {
-            self.reader.setpos(ea.argpos);
-            lines.push(self.reader.get_line());
+            let line = self.reader.get_line();
+            if line == end || (trim && line.trim_start() == end) {
+                break;
+            }
+            lines.push(line);
         }
-        self.add_node(Node::ExCmd {
+        self.add_node(Node::Lang {
             pos: ea.cmdpos,
             mods: ea.modifiers,
-            bang: ea.bang,
-            command: ea.cmd.name.clone(),
-            args: lines.join("\n"),
+            cmd: ea.cmd.name.clone(),
+            trim,
+            marker,
+            lines,
         });
         Ok(())
     }
@@ -1103,24 +1404,41 @@ impl<'a> Parser<'a> {
         }
         let (var, list, rest) = self.parse_letlhs()?;
         self.reader.skip_white();
-        let s1 = self.reader.peek();
-        let s2 = self.reader.peekn(2);
-        if ends_excmds(s1) || s2 != "+=" && s2 != "-=" && s2 != ".=" && s1 != '=' {
-            self.reader.seek_set(pos);
-            return self.parse_cmd_common(ea);
-        }
-        let op = if s2 == "+=" || s2 == "-=" || s2 == ".=" {
-            self.reader.getn(2);
-            s2
-        } else if s1 == '=' {
-            self.reader.get();
-            s1.to_string()
-        } else {
-            return self.err("NOT REACHED");
+        let op = match LET_OPERATORS
+            .iter()
+            .find(|op| self.reader.peekn(op.len()) == **op)
+        {
+            Some(op) => op.to_string(),
+            None => {
+                self.reader.seek_set(pos);
+                return self.parse_cmd_common(ea);
+            }
         };
+        for target in var.iter().chain(&list).chain(&rest) {
+            match LetTarget::from(target) {
+                LetTarget::Option(_, "") => {
+                    return Err(ParseError::new(
+                        ErrorCode::OptionNameMissing,
+                        "E112: Option name missing: &".to_string(),
+                        target.pos(),
+                    ));
+                }
+                // registers only hold strings
+                LetTarget::Register(_) if !["=", ".=", "..="].contains(&op.as_str()) => {
+                    return Err(ParseError::new(
+                        ErrorCode::WrongVariableType,
+                        format!("E734: Wrong variable type for {}", op),
+                        target.pos(),
+                    ));
+                }
+                _ => (),
+            }
+        }
+        self.reader.getn(op.len());
         let node = Node::Let {
             pos: ea.cmdpos,
             mods: ea.modifiers,
+            cmd: ea.cmd.name.clone(),
             var: var.map(Box::new),
             list,
             rest: rest.map(Box::new),
@@ -1134,6 +1452,7 @@ impl<'a> Parser<'a> {
     fn parse_cmd_loadkeymap(&mut self, ea: ExArg) -> Result<()> {
         self.reader.setpos(ea.linepos);
         self.reader.get_line();
+        self.reader.get();
         let mut lines = vec![];
         loop {
             if self.reader.peek() == EOF {
@@ -1142,9 +1461,10 @@ impl<'a> Parser<'a> {
             lines.push(self.reader.get_line());
             self.reader.get();
         }
-        self.add_node(Node::ExCmd {
+        self.add_node(Node::ExCmdRaw {
             pos: ea.cmdpos,
             mods: ea.modifiers,
+            range: ea.range,
             bang: ea.bang,
             command: ea.cmd.name.clone(),
             args: if !lines.is_empty() {
@@ -1175,8 +1495,40 @@ impl<'a> Parser<'a> {
         Ok(())
     }
 
+    fn parse_cmd_abbreviation(&mut self, ea: ExArg) -> Result<()> {
+        let (attrs, left, right, right_expr) = self.parse_mapping_args()?;
+        self.add_node(Node::Abbreviation {
+            command: ea.cmd.name.clone(),
+            attrs,
+            left,
+            right_keys: split_keys(&right),
+            right,
+            right_expr,
+            pos: ea.cmdpos,
+            mods: ea.modifiers,
+        });
+        Ok(())
+    }
+
     fn parse_cmd_mapping(&mut self, ea: ExArg) -> Result<()> {
-        let command = ea.cmd.name.clone();
+        let (attrs, left, right, right_expr) = self.parse_mapping_args()?;
+        self.add_node(Node::Mapping {
+            command: ea.cmd.name.clone(),
+            bang: ea.bang,
+            attrs,
+            left,
+            right_keys: split_keys(&right),
+            right,
+            right_expr,
+            pos: ea.cmdpos,
+            mods: ea.modifiers,
+        });
+        Ok(())
+    }
+
+    /// Parse the arguments of a mapping or abbreviation command into its attributes, left-hand
+    /// side, right-hand side and, if it has the `<expr>` attribute, right-hand side expression.
+    fn parse_mapping_args(&mut self) -> Result<MappingArgs> {
         let mut attrs = vec![];
         let mut right_expr = None;
         loop {
@@ -1188,12 +1540,16 @@ impl<'a> Parser<'a> {
                 self.reader.get();
                 let attr = self.reader.read_alpha();
                 match attr.to_lowercase().as_str() {
-                    "buffer" | "nowait" | "silent" | "script" | "unique" | "expr" => {
+                    "buffer" | "nowait" | "silent" | "special" | "script" | "unique" | "expr" => {
                         attrs.push(attr.to_lowercase());
                         if self.reader.peek() == '>' {
                             self.reader.get();
                         } else {
-                            return self.err(&format!("unexpected token: {}", self.reader.peek()));
+                            return self.err_found(
+                                ErrorCode::UnexpectedToken,
+                                &format!("unexpected token: {}", self.reader.peek()),
+                                self.reader.peek(),
+                            );
                         }
                     }
                     _ => {
@@ -1208,72 +1564,419 @@ impl<'a> Parser<'a> {
         let left = if !["|", "", "\n", &EOF.to_string()].contains(&self.reader.peekn(1).as_str()) {
             self.reader.read_nonwhite()
         } else {
-            self.add_node(Node::Mapping {
-                command,
-                attrs,
-                left: String::new(),
-                right: String::new(),
-                right_expr,
-                pos: ea.cmdpos,
-                mods: ea.modifiers,
-            });
-            return Ok(());
+            return Ok((attrs, String::new(), String::new(), right_expr));
         };
         self.reader.skip_white();
         let right = if attrs.contains(&"expr".to_string()) {
             right_expr = Some(Box::new(self.parse_expr()?));
             String::new()
         } else {
-            let mut right = String::new();
-            loop {
-                let c = self.reader.peek();
-                let c2 = self.reader.peek_ahead(1);
-                if c == '\\' && c2 == '|' {
-                    self.reader.get();
-                    right.push(self.reader.get());
-                } else if c != '"' && ends_excmds(c) {
-                    break;
-                } else {
-                    right.push(self.reader.get());
-                }
-            }
-            right.trim_end().to_string()
+            self.read_mapping_rhs()
         };
-        self.add_node(Node::Mapping {
-            command,
-            attrs,
-            left,
-            right,
-            right_expr,
-            pos: ea.cmdpos,
-            mods: ea.modifiers,
-        });
-        Ok(())
+        Ok((attrs, left, right, right_expr))
     }
 
-    fn parse_cmd_return(&mut self, ea: ExArg) -> Result<()> {
-        if !self.find_context(Node::is_function) {
-            return Err(ParseError {
-                msg: "E133: :return not inside a function".to_string(),
-                pos: ea.cmdpos,
-            });
+    /// Read the right-hand side of a mapping or menu command, up to an unescaped bar or the end
+    /// of the line. Quotes don't start a comment, and `\|` is read as a literal bar.
+    fn read_mapping_rhs(&mut self) -> String {
+        let mut right = String::new();
+        loop {
+            let c = self.reader.peek();
+            let c2 = self.reader.peek_ahead(1);
+            if c == '\\' && c2 == '|' {
+                self.reader.get();
+                right.push(self.reader.get());
+            } else if c != '"' && ends_excmds(c) {
+                break;
+            } else {
+                right.push(self.reader.get());
+            }
+        }
+        right.trim_end().to_string()
+    }
+
+    fn parse_cmd_match(&mut self, ea: ExArg) -> Result<()> {
+        self.reader.skip_white();
+        let mut group = String::new();
+        while !self.reader.peek().is_white() && !ends_excmds(self.reader.peek()) {
+            group.push(self.reader.get());
         }
         self.reader.skip_white();
+        let mut delimiter = None;
+        let mut pattern = String::new();
         let c = self.reader.peek();
-        let left = if c == '"' || !ends_excmds(c) {
-            Some(Box::new(self.parse_expr()?))
-        } else {
-            None
-        };
-        self.add_node(Node::Return {
+        if !group.eq_ignore_ascii_case("none") && !group.is_empty() && ![EOL, EOF].contains(&c) {
+            self.reader.get();
+            let (pat, endc) = self.parse_pattern(&c.to_string())?;
+            if endc.is_empty() {
+                return self.err_found(
+                    ErrorCode::InvalidArgumentValue,
+                    &format!("E475: Invalid argument: {}{}", c, pat),
+                    format!("{}{}", c, pat),
+                );
+            }
+            delimiter = Some(c);
+            pattern = pat;
+        }
+        self.add_node(Node::Match {
             pos: ea.cmdpos,
             mods: ea.modifiers,
-            left,
+            range: ea.range,
+            group,
+            delimiter,
+            pattern,
         });
         Ok(())
     }
 
-    fn parse_cmd_syntax(&mut self, ea: ExArg) -> Result<()> {
+    fn parse_cmd_menu(&mut self, ea: ExArg) -> Result<()> {
+        let mut attrs = vec![];
+        loop {
+            self.reader.skip_white();
+            let pos = self.reader.getpos();
+            if self.reader.peek() != '<' {
+                break;
+            }
+            self.reader.get();
+            let attr = self.reader.read_alpha().to_lowercase();
+            if ["silent", "special", "script"].contains(&attr.as_str()) && self.reader.peek() == '>'
+            {
+                self.reader.get();
+                attrs.push(attr);
+            } else {
+                self.reader.setpos(pos);
+                break;
+            }
+        }
+        let mut icon = None;
+        if self.reader.peekn(5) == "icon=" {
+            self.reader.getn(5);
+            icon = Some(self.read_menu_word());
+            self.reader.skip_white();
+        }
+        let mut priority = None;
+        if self.reader.peek().is_ascii_digit() || self.reader.peek() == '.' {
+            priority = Some(self.reader.read_nonwhite());
+            self.reader.skip_white();
+        }
+        let mut enable = None;
+        for (word, value) in [("enable", true), ("disable", false)] {
+            if self.reader.peekn(word.len()) == word
+                && self.reader.peek_ahead(word.len()).is_white()
+            {
+                self.reader.getn(word.len());
+                self.reader.skip_white();
+                enable = Some(value);
+            }
+        }
+        let path = self.read_menu_word();
+        self.reader.skip_white();
+        let right = self.read_mapping_rhs();
+        self.add_node(Node::Menu {
+            pos: ea.cmdpos,
+            mods: ea.modifiers,
+            range: ea.range,
+            command: ea.cmd.name.clone(),
+            bang: ea.bang,
+            attrs,
+            icon,
+            priority,
+            enable,
+            path,
+            right,
+        });
+        Ok(())
+    }
+
+    /// Read a menu path or icon file name, which ends at whitespace that isn't escaped with a
+    /// backslash. Escapes are kept.
+    fn read_menu_word(&mut self) -> String {
+        let mut word = String::new();
+        loop {
+            let c = self.reader.peek();
+            if c == '\\' && ![EOF, EOL].contains(&self.reader.peek_ahead(1)) {
+                word.push(self.reader.get());
+                word.push(self.reader.get());
+            } else if c.is_white() || (c != '"' && ends_excmds(c)) {
+                break;
+            } else {
+                word.push(self.reader.get());
+            }
+        }
+        word
+    }
+
+    fn parse_cmd_normal(&mut self, ea: ExArg) -> Result<()> {
+        // everything up to the end of the line is passed to the command as-is, including bars,
+        // quotes, control characters and trailing whitespace.
+        let raw = self.reader.get_line();
+        self.add_node(Node::Normal {
+            pos: ea.cmdpos,
+            mods: ea.modifiers,
+            range: ea.range,
+            bang: ea.bang,
+            raw,
+        });
+        Ok(())
+    }
+
+    fn parse_cmd_runtime(&mut self, ea: ExArg) -> Result<()> {
+        let end = self.separate_nextcmd(&ea)?;
+        let mut files = split_words(&self.reader.getstr(ea.argpos, end));
+        let location = match files.first().map(String::as_str) {
+            Some("START" | "OPT" | "PACK" | "ALL") => Some(files.remove(0)),
+            _ => None,
+        };
+        if files.is_empty() {
+            return Err(ParseError::new(
+                ErrorCode::ArgumentRequired,
+                "E471: Argument required".to_string(),
+                ea.argpos,
+            ));
+        }
+        self.add_node(Node::Runtime {
+            pos: ea.cmdpos,
+            mods: ea.modifiers,
+            bang: ea.bang,
+            location,
+            files,
+        });
+        Ok(())
+    }
+
+    fn parse_cmd_set(&mut self, ea: ExArg) -> Result<()> {
+        let end = self.separate_nextcmd(&ea)?;
+        let args = split_set_args(&self.reader.getstr(ea.argpos, end));
+        self.add_node(Node::Set {
+            pos: ea.cmdpos,
+            mods: ea.modifiers,
+            cmd: ea.cmd.name.clone(),
+            bang: ea.bang,
+            args,
+        });
+        Ok(())
+    }
+
+    fn parse_cmd_sign(&mut self, ea: ExArg) -> Result<()> {
+        let mut end;
+        loop {
+            end = self.reader.getpos();
+            if self.reader.getn(1) == "" {
+                break;
+            }
+        }
+        let mut words = split_words(&self.reader.getstr(ea.argpos, end)).into_iter();
+        let subcommand = words.next().unwrap_or_default();
+        let keys = match SIGN_ATTRS.iter().find(|(name, _)| *name == subcommand) {
+            Some((_, keys)) => keys,
+            None => {
+                return Err(ParseError::new(
+                    ErrorCode::UnknownSignCommand,
+                    format!("E160: Unknown sign command: {}", subcommand),
+                    ea.argpos,
+                )
+                .with_found(&subcommand));
+            }
+        };
+        let mut args = vec![];
+        let mut attrs = vec![];
+        for word in words {
+            match word.split_once('=') {
+                Some((key, value)) => {
+                    if !keys.contains(&key) {
+                        return Err(ParseError::new(
+                            ErrorCode::InvalidArgumentValue,
+                            format!("E475: Invalid argument: {}", word),
+                            ea.argpos,
+                        )
+                        .with_found(&word));
+                    }
+                    attrs.push((key.to_string(), value.to_string()));
+                }
+                None => args.push(word),
+            }
+        }
+        self.add_node(Node::Sign {
+            pos: ea.cmdpos,
+            mods: ea.modifiers,
+            range: ea.range,
+            subcommand,
+            args,
+            attrs,
+        });
+        Ok(())
+    }
+
+    fn parse_cmd_digraphs(&mut self, ea: ExArg) -> Result<()> {
+        let end = self.separate_nextcmd(&ea)?;
+        let args = self.reader.getstr(ea.argpos, end);
+        let mut digraphs = vec![];
+        let mut chars = args.chars().peekable();
+        loop {
+            while chars.next_if(|c| c.is_white()).is_some() {}
+            let first = match chars.next() {
+                Some(c) => c,
+                None => break,
+            };
+            let pair = match chars.next() {
+                Some(second) if !second.is_white() => format!("{}{}", first, second),
+                _ => {
+                    return Err(ParseError::new(
+                        ErrorCode::NumberExpected,
+                        "E39: Number expected".to_string(),
+                        ea.argpos,
+                    ));
+                }
+            };
+            while chars.next_if(|c| c.is_white()).is_some() {}
+            let mut number = String::new();
+            while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+                number.push(c);
+            }
+            if number.is_empty() {
+                return Err(ParseError::new(
+                    ErrorCode::NumberExpected,
+                    "E39: Number expected".to_string(),
+                    ea.argpos,
+                ));
+            }
+            digraphs.push((pair, number));
+        }
+        self.add_node(Node::Digraphs {
+            pos: ea.cmdpos,
+            mods: ea.modifiers,
+            bang: ea.bang,
+            digraphs,
+        });
+        Ok(())
+    }
+
+    fn parse_cmd_return(&mut self, ea: ExArg) -> Result<()> {
+        if !self.in_function && !self.find_context(Node::is_function) {
+            return Err(ParseError::new(
+                ErrorCode::ReturnOutsideFunction,
+                "E133: :return not inside a function".to_string(),
+                ea.cmdpos,
+            ));
+        }
+        self.reader.skip_white();
+        let c = self.reader.peek();
+        let left = if c == '"' || !ends_excmds(c) {
+            Some(Box::new(self.parse_expr()?))
+        } else {
+            None
+        };
+        self.add_node(Node::Return {
+            pos: ea.cmdpos,
+            mods: ea.modifiers,
+            left,
+        });
+        Ok(())
+    }
+
+    fn parse_cmd_source(&mut self, ea: ExArg) -> Result<()> {
+        let end = self.separate_nextcmd(&ea)?;
+        let file = self.reader.getstr(ea.argpos, end).trim_end().to_string();
+        self.add_node(Node::Source {
+            pos: ea.cmdpos,
+            mods: ea.modifiers,
+            bang: ea.bang,
+            file: Some(file).filter(|f| !f.is_empty()),
+        });
+        Ok(())
+    }
+
+    fn parse_cmd_substitute(&mut self, ea: ExArg) -> Result<()> {
+        let start = self.reader.tell();
+        self.reader.skip_white();
+        let c = self.reader.peek();
+        if c == '\\' {
+            // `s\/`, `s\?` and `s\&` use the last search or substitute pattern, with the character
+            // after the backslash as the delimiter
+            self.reader.seek_set(start);
+            return self.parse_cmd_common(ea);
+        }
+        let mut delimiter = None;
+        let mut pattern = String::new();
+        let mut replacement = None;
+        let mut flags = Some(String::new());
+        if !ends_excmds(c) && !c.is_ascii_digit() && !"cegriIp".contains(c) {
+            if c.is_ascii_alphabetic() {
+                return self.err_found(
+                    ErrorCode::LetterDelimiter,
+                    "E146: Regular expressions can't be delimited by letters",
+                    c,
+                );
+            }
+            self.reader.get();
+            delimiter = Some(c);
+            let (pat, endc) = self.parse_pattern(&c.to_string())?;
+            pattern = pat;
+            flags = None;
+            if !endc.is_empty() {
+                let mut text = String::new();
+                loop {
+                    let c = self.reader.peek();
+                    if c == EOL || c == EOF {
+                        break;
+                    }
+                    self.reader.get();
+                    if delimiter == Some(c) {
+                        flags = Some(String::new());
+                        break;
+                    }
+                    text.push(c);
+                    if c == '\\' && ![EOL, EOF].contains(&self.reader.peek()) {
+                        text.push(self.reader.get());
+                    }
+                }
+                replacement = Some(text);
+            }
+        }
+        if let Some(flags) = flags.as_mut() {
+            if self.reader.peek() == '&' {
+                flags.push(self.reader.get());
+            }
+            while "cegiInp#lr".contains(self.reader.peek()) {
+                flags.push(self.reader.get());
+            }
+        }
+        self.reader.skip_white();
+        let count = if self.reader.peek().is_ascii_digit() {
+            let digits = self.reader.read_digit();
+            match digits.parse::<usize>() {
+                Ok(count) => Some(count),
+                Err(_) => {
+                    return self.err_found(
+                        ErrorCode::InvalidArgumentValue,
+                        &format!("E475: Invalid argument: {}", digits),
+                        digits.clone(),
+                    )
+                }
+            }
+        } else {
+            None
+        };
+        self.add_node(Node::Substitute {
+            pos: ea.cmdpos,
+            mods: ea.modifiers,
+            range: ea.range,
+            cmd: ea.cmd.name.clone(),
+            name: self
+                .reader
+                .getstr(ea.cmdpos, ea.argpos)
+                .trim_end()
+                .to_string(),
+            delimiter,
+            pattern,
+            replacement,
+            flags,
+            count,
+        });
+        Ok(())
+    }
+
+    fn parse_cmd_syntax(&mut self, ea: ExArg) -> Result<()> {
         let mut end;
         loop {
             end = self.reader.getpos();
@@ -1291,9 +1994,10 @@ impl<'a> Parser<'a> {
                 self.reader.getn(1);
             }
         }
-        self.add_node(Node::ExCmd {
+        self.add_node(Node::ExCmdRaw {
             pos: ea.cmdpos,
             mods: ea.modifiers,
+            range: ea.range,
             command: ea.cmd.name.clone(),
             args: self.reader.getstr(ea.argpos, end),
             bang: ea.bang,
@@ -1349,21 +2053,22 @@ impl<'a> Parser<'a> {
     fn parse_cmd_wincmd(&mut self, ea: ExArg) -> Result<()> {
         let c = self.reader.getn(1);
         if c == "" {
-            return self.err("E471: Argument required");
+            return self.err(ErrorCode::ArgumentRequired, "E471: Argument required");
         } else if c == "g" || c == "\x07" {
             let c2 = self.reader.getn(1);
             if ["", " ", "\t"].contains(&c2.as_str()) {
-                return self.err("E474: Invalid argument");
+                return self.err(ErrorCode::InvalidArgument, "E474: Invalid argument");
             }
         }
         let end = self.reader.getpos();
         self.reader.skip_white();
         if !ends_excmds(self.reader.peek()) {
-            return self.err("E474: Invalid argument");
+            return self.err(ErrorCode::InvalidArgument, "E474: Invalid argument");
         }
-        self.add_node(Node::ExCmd {
+        self.add_node(Node::ExCmdRaw {
             pos: ea.cmdpos,
             mods: ea.modifiers,
+            range: ea.range,
             command: ea.cmd.name.clone(),
             args: self.reader.getstr(ea.argpos, end),
             bang: ea.bang,
@@ -1372,7 +2077,8 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_letlhs(&mut self) -> Result<(Option<Node>, Vec<Node>, Option<Node>)> {
-        let mut tokenizer = Tokenizer::new(self.reader);
+        let reader = Rc::clone(&self.reader);
+        let mut tokenizer = Tokenizer::new(&reader);
         let mut nodes = vec![];
         let mut left = None;
         let mut rest = None;
@@ -1394,17 +2100,21 @@ impl<'a> Parser<'a> {
                         if token.kind == TokenKind::SqClose {
                             break;
                         } else {
-                            return Err(ParseError {
-                                msg: format!("E475: Invalid argument: {}", token.value),
-                                pos: token.pos,
-                            });
+                            return Err(ParseError::new(
+                                ErrorCode::InvalidArgumentValue,
+                                format!("E475: Invalid argument: {}", token.value),
+                                token.pos,
+                            )
+                            .with_found(token.value));
                         }
                     }
                     _ => {
-                        return Err(ParseError {
-                            msg: format!("E475: Invalid argument: {}", token.value),
-                            pos: token.pos,
-                        });
+                        return Err(ParseError::new(
+                            ErrorCode::InvalidArgumentValue,
+                            format!("E475: Invalid argument: {}", token.value),
+                            token.pos,
+                        )
+                        .with_found(token.value));
                     }
                 }
             }
@@ -1429,13 +2139,15 @@ impl<'a> Parser<'a> {
                 && !value.contains(':')
                 && !value.contains('#')
             {
-                return Err(ParseError {
-                    msg: format!(
+                return Err(ParseError::new(
+                    ErrorCode::InvalidFunctionName,
+                    format!(
                         "E128: Function name must start with a capital or contain a colon: {}",
                         value
                     ),
                     pos,
-                });
+                )
+                .with_found(value));
             }
         }
         if self.reader.peek() != '(' {
@@ -1444,7 +2156,7 @@ impl<'a> Parser<'a> {
         }
         let name = Box::new(left);
         self.reader.getn(1);
-        let mut tokenizer = Tokenizer::new(self.reader);
+        let mut tokenizer = Tokenizer::new(&self.reader);
         let mut args = vec![];
         if tokenizer.peek()?.kind == TokenKind::PClose {
             tokenizer.get()?;
@@ -1457,15 +2169,19 @@ impl<'a> Parser<'a> {
                         || token.value == "firstline"
                         || token.value == "lastline"
                     {
-                        return Err(ParseError {
-                            msg: format!("E125: Illegal argument: {}", token.value),
-                            pos: token.pos,
-                        });
+                        return Err(ParseError::new(
+                            ErrorCode::IllegalArgument,
+                            format!("E125: Illegal argument: {}", token.value),
+                            token.pos,
+                        )
+                        .with_found(token.value));
                     } else if named.contains(&token.value) {
-                        return Err(ParseError {
-                            msg: format!("E853: Duplicate argument name: {}", token.value),
-                            pos: token.pos,
-                        });
+                        return Err(ParseError::new(
+                            ErrorCode::DuplicateArgument,
+                            format!("E853: Duplicate argument name: {}", token.value),
+                            token.pos,
+                        )
+                        .with_found(token.value));
                     }
                     named.push(token.value.clone());
                     args.push(Node::Identifier {
@@ -1474,6 +2190,7 @@ impl<'a> Parser<'a> {
                     });
                     if self.reader.peek().is_white() && tokenizer.peek()?.kind == TokenKind::Comma {
                         return self.err(
+                            ErrorCode::InvalidArgumentValue,
                             "E475: Invalid argument: White space is not allowed before comma",
                         );
                     }
@@ -1486,10 +2203,12 @@ impl<'a> Parser<'a> {
                     } else if token.kind == TokenKind::PClose {
                         break;
                     } else {
-                        return Err(ParseError {
-                            msg: format!("unexpected token: {}", token.value),
-                            pos: token.pos,
-                        });
+                        return Err(ParseError::new(
+                            ErrorCode::UnexpectedToken,
+                            format!("unexpected token: {}", token.value),
+                            token.pos,
+                        )
+                        .with_found(token.value));
                     }
                 } else if token.kind == TokenKind::DotDotDot {
                     args.push(Node::Identifier {
@@ -1500,16 +2219,20 @@ impl<'a> Parser<'a> {
                     if token.kind == TokenKind::PClose {
                         break;
                     } else {
-                        return Err(ParseError {
-                            msg: format!("unexpected token: {}", token.value),
-                            pos: token.pos,
-                        });
+                        return Err(ParseError::new(
+                            ErrorCode::UnexpectedToken,
+                            format!("unexpected token: {}", token.value),
+                            token.pos,
+                        )
+                        .with_found(token.value));
                     }
                 } else {
-                    return Err(ParseError {
-                        msg: format!("unexpected token: {}", token.value),
-                        pos: token.pos,
-                    });
+                    return Err(ParseError::new(
+                        ErrorCode::UnexpectedToken,
+                        format!("unexpected token: {}", token.value),
+                        token.pos,
+                    )
+                    .with_found(token.value));
                 }
             }
         }
@@ -1524,10 +2247,12 @@ impl<'a> Parser<'a> {
                 }
                 "range" | "abort" | "dict" | "closure" => attrs.push(key),
                 _ => {
-                    return Err(ParseError {
-                        msg: format!("unexpected token: {}", key),
-                        pos: epos,
-                    });
+                    return Err(ParseError::new(
+                        ErrorCode::UnexpectedToken,
+                        format!("unexpected token: {}", key),
+                        epos,
+                    )
+                    .with_found(key));
                 }
             }
         }
@@ -1545,6 +2270,53 @@ impl<'a> Parser<'a> {
         Ok(())
     }
 
+    fn parse_cmd_global(&mut self, ea: ExArg) -> Result<()> {
+        let start = self.reader.tell();
+        self.reader.skip_white();
+        let delimiter = self.reader.peek();
+        if delimiter == '\\' || [EOL, EOF].contains(&delimiter) {
+            // `g\/`, `g\?` and `g\&` use the last search or substitute pattern
+            self.reader.seek_set(start);
+            return self.parse_cmd_common(ea);
+        }
+        if delimiter.is_ascii_alphabetic() {
+            return self.err_found(
+                ErrorCode::LetterDelimiter,
+                "E146: Regular expressions can't be delimited by letters",
+                delimiter,
+            );
+        }
+        self.reader.get();
+        let (pattern, _) = self.parse_pattern(&delimiter.to_string())?;
+        self.reader.skip_white();
+        let body = if self.reader.peekn(1) == "" {
+            vec![]
+        } else {
+            let offset = self.reader.tell();
+            match self.parse_piped_expressions(&self.reader.get_line()) {
+                Ok(body) => body,
+                Err(e) => {
+                    self.reader.seek_set(e.pos.cursor + offset);
+                    return Err(ParseError {
+                        pos: self.reader.getpos(),
+                        ..e
+                    });
+                }
+            }
+        };
+        self.add_node(Node::Global {
+            pos: ea.cmdpos,
+            mods: ea.modifiers,
+            range: ea.range,
+            bang: ea.bang,
+            cmd: ea.cmd.name.clone(),
+            delimiter,
+            pattern,
+            body,
+        });
+        Ok(())
+    }
+
     fn parse_cmd_highlight(&mut self, ea: ExArg) -> Result<()> {
         let (pos, mods, bang) = (ea.cmdpos, ea.modifiers, ea.bang);
         let mut attrs = vec![];
@@ -1606,10 +2378,11 @@ impl<'a> Parser<'a> {
             self.reader.skip_white();
             token = self.reader.read_nonwhitespace();
             if token == "" {
-                return Err(ParseError {
-                    msg: "E412: Not enough arguments: \":highlight link \"".to_string(),
+                return Err(ParseError::new(
+                    ErrorCode::NotEnoughArguments,
+                    "E412: Not enough arguments: \":highlight link \"".to_string(),
                     pos,
-                });
+                ));
             }
         }
         let group = Some(token);
@@ -1631,13 +2404,14 @@ impl<'a> Parser<'a> {
             return Ok(());
         } else if link {
             return if token == "" {
-                Err(ParseError {
-                    msg: format!(
+                Err(ParseError::new(
+                    ErrorCode::NotEnoughArguments,
+                    format!(
                         "E412: Not enough arguments: \":highlight link {}\"",
                         group.unwrap()
                     ),
                     pos,
-                })
+                ))
             } else {
                 self.add_node(Node::Highlight {
                     pos,
@@ -1654,22 +2428,24 @@ impl<'a> Parser<'a> {
                 return Ok(());
             };
         }
-        lazy_static! {
-            static ref VALID_HL_KEYS: &'static [&'static str] = &[
-                "cterm", "ctermbg", "ctermfg", "font", "gui", "guibg", "guifg", "guisp", "start",
-                "stop", "term",
-            ];
-        }
         while token != "" {
             if !token.contains('=') {
-                return self.err(&format!("E416: missing equal sign: {}", token));
+                return self.err_found(
+                    ErrorCode::MissingEqualSign,
+                    &format!("E416: missing equal sign: {}", token),
+                    token,
+                );
             }
             if token.contains("='") {
                 // have to account for e.g. `:highlight String font='Monospace 10'`
                 loop {
                     let c = self.reader.get();
                     if c == EOL || c == EOF {
-                        return self.err(&format!("E475: Invalid argument: {}", token));
+                        return self.err_found(
+                            ErrorCode::InvalidArgumentValue,
+                            &format!("E475: Invalid argument: {}", token),
+                            token,
+                        );
                     }
                     token.push(c);
                     if c == '\'' {
@@ -1679,11 +2455,13 @@ impl<'a> Parser<'a> {
             }
             let splits = token.splitn(2, '=').collect::<Vec<&str>>();
             let (key, value) = (splits[0], splits[1]);
-            if !VALID_HL_KEYS.contains(&key.to_lowercase().as_str()) {
-                return Err(ParseError {
-                    msg: format!("E423: Illegal argument: {}", token),
+            if !HIGHLIGHT_KEYS.contains(&key.to_lowercase().as_str()) {
+                return Err(ParseError::new(
+                    ErrorCode::IllegalHighlightArgument,
+                    format!("E423: Illegal argument: {}", token),
                     pos,
-                });
+                )
+                .with_found(token));
             }
             attrs.push((key.to_lowercase(), value.to_string()));
             self.reader.skip_white();
@@ -1719,15 +2497,17 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_lvalue(&mut self) -> Result<Node> {
-        let mut parser = ExprParser::new(self.reader);
+        let mut parser = ExprParser::new(&self.reader);
         let node = parser.parse_lv()?;
         match node {
             Node::Identifier { pos, ref value, .. } => {
                 if !isvarname(value) {
-                    Err(ParseError {
-                        msg: format!("E461: Illegal variable name: {}", value),
+                    Err(ParseError::new(
+                        ErrorCode::IllegalVariableName,
+                        format!("E461: Illegal variable name: {}", value),
                         pos,
-                    })
+                    )
+                    .with_found(value))
                 } else {
                     Ok(node.clone())
                 }
@@ -1739,10 +2519,11 @@ impl<'a> Parser<'a> {
             | Node::Option { .. }
             | Node::Env { .. }
             | Node::Reg { .. } => Ok(node),
-            _ => Err(ParseError {
-                msg: "Invalid expression".to_string(),
-                pos: self.reader.getpos(),
-            }),
+            _ => Err(ParseError::new(
+                ErrorCode::InvalidExpression,
+                "E15: Invalid expression".to_string(),
+                self.reader.getpos(),
+            )),
         }
     }
 
@@ -1760,7 +2541,7 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_lvalue_func(&mut self) -> Result<Node> {
-        let mut parser = ExprParser::new(self.reader);
+        let mut parser = ExprParser::new(&self.reader);
         let node = parser.parse_lv()?;
         match node {
             Node::Identifier { .. }
@@ -1770,10 +2551,11 @@ impl<'a> Parser<'a> {
             | Node::Option { .. }
             | Node::Env { .. }
             | Node::Reg { .. } => Ok(node),
-            _ => Err(ParseError {
-                msg: "Invalid expression".to_string(),
-                pos: self.reader.getpos(),
-            }),
+            _ => Err(ParseError::new(
+                ErrorCode::InvalidExpression,
+                "E15: Invalid expression".to_string(),
+                self.reader.getpos(),
+            )),
         }
     }
 
@@ -1782,11 +2564,14 @@ impl<'a> Parser<'a> {
             self.skip_vimgrep_pat()?;
         }
         let mut pc: char = EOF;
+        let mut ppc: char = EOF;
         let mut end = self.reader.getpos();
         let mut nospend = end;
         loop {
             end = self.reader.getpos();
-            if !pc.is_white() {
+            // trailing whitespace is dropped, unless it is escaped, like the space in
+            // `set fillchars=fold:\ `
+            if !pc.is_white() || ppc == '\\' || ppc == '\x16' {
                 nospend = end;
             }
             let mut c = self.reader.peek();
@@ -1811,7 +2596,11 @@ impl<'a> Parser<'a> {
                 self.parse_expr()?;
                 let peeked = self.reader.peekn(1);
                 if peeked != "`" {
-                    return self.err(&format!("unexpected character: {}", c));
+                    return self.err_found(
+                        ErrorCode::UnexpectedCharacter,
+                        &format!("unexpected character: {}", c),
+                        c,
+                    );
                 }
                 let gotten = self.reader.getn(1);
                 c = if gotten.is_empty() {
@@ -1834,6 +2623,7 @@ impl<'a> Parser<'a> {
             } else {
                 self.reader.get();
             }
+            ppc = pc;
             pc = c
         }
         if !ea.cmd.flags.contains(Flag::NOTRLCOM) {
@@ -1883,45 +2673,44 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_argopt(&mut self) -> Result<()> {
-        lazy_static! {
-            static ref BIN_RE: Regex = Regex::new("^\\+\\+bin\\b").unwrap();
-            static ref NOBIN_RE: Regex = Regex::new("^\\+\\+nobin\\b").unwrap();
-            static ref EDIT_RE: Regex = Regex::new("^\\+\\+edit\\b").unwrap();
-            static ref FF_RE: Regex = Regex::new("^\\+\\+ff=(dos|unix|mac)\\b").unwrap();
-            static ref FILEFORMAT_RE: Regex =
-                Regex::new("^\\+\\+fileformat=(dos|unix|mac)\\b").unwrap();
-            static ref ENC_RE: Regex = Regex::new("^\\+\\+enc=\\S").unwrap();
-            static ref ENCODING_RE: Regex = Regex::new("^\\+\\+encoding=\\S").unwrap();
-            static ref BAD_OUTER_RE: Regex = Regex::new("^\\+\\+bad=(keep|drop|.)\\b").unwrap();
-            static ref BAD_INNER_RE: Regex = Regex::new("^\\+\\+bad=(keep|drop)").unwrap();
-        }
         while self.reader.peekn(2) == "++" {
             let s = self.reader.peekn(20);
-            if BIN_RE.is_match(&s) {
+            let value = |name: &str, values: &[&str]| {
+                s.strip_prefix(name).and_then(|rest| {
+                    values
+                        .iter()
+                        .position(|v| rest.starts_with(*v) && ends_word(&rest[v.len()..]))
+                })
+            };
+            let nonwhite = |name: &str| {
+                s.strip_prefix(name)
+                    .is_some_and(|rest| rest.starts_with(|c: char| !c.is_whitespace()))
+            };
+            if value("++", &["bin"]).is_some() {
                 self.reader.getn(5);
-            } else if NOBIN_RE.is_match(&s) {
+            } else if value("++", &["nobin"]).is_some() {
                 self.reader.getn(7);
-            } else if EDIT_RE.is_match(&s) {
+            } else if value("++", &["edit"]).is_some() {
                 self.reader.getn(6);
-            } else if FF_RE.is_match(&s) {
+            } else if value("++ff=", &["dos", "unix", "mac"]).is_some() {
                 self.reader.getn(5);
-            } else if FILEFORMAT_RE.is_match(&s) {
+            } else if value("++fileformat=", &["dos", "unix", "mac"]).is_some() {
                 self.reader.getn(13);
-            } else if ENC_RE.is_match(&s) {
+            } else if nonwhite("++enc=") {
                 self.reader.getn(6);
                 self.reader.read_nonwhite();
-            } else if ENCODING_RE.is_match(&s) {
+            } else if nonwhite("++encoding=") {
                 self.reader.getn(11);
                 self.reader.read_nonwhite();
-            } else if BAD_OUTER_RE.is_match(&s) {
-                self.reader.getn(6);
-                if BAD_INNER_RE.is_match(&s) {
-                    self.reader.getn(4);
-                } else {
-                    self.reader.get();
-                }
+            } else if let Some(len) = value("++bad=", &["keep", "drop"]).map(|_| 4).or_else(|| {
+                // any single character, as long as a word boundary follows it
+                let c = s.strip_prefix("++bad=")?.chars().next()?;
+                let rest = &s[6 + c.len_utf8()..];
+                (is_word_char(c) != rest.starts_with(is_word_char)).then_some(1)
+            }) {
+                self.reader.getn(6 + len);
             } else if s.starts_with("++") {
-                return self.err("E474: Invalid Argument");
+                return self.err(ErrorCode::InvalidArgument, "E474: Invalid Argument");
             } else {
                 break;
             }
@@ -1930,26 +2719,22 @@ impl<'a> Parser<'a> {
         Ok(())
     }
 
-    fn find_command(&mut self) -> Option<Rc<Command>> {
+    fn find_command(&mut self) -> Option<Arc<Command>> {
         let c = self.reader.peek();
         let mut name = "".to_string();
-        lazy_static! {
-            static ref SUB_RE: Regex = Regex::new("^s(c[^sr][^i][^p]|g|i[^mlg]|I|r[^e])").unwrap();
-            static ref DEL_RE: Regex = Regex::new("^d(elete|elet|ele|el|e)[lp]$").unwrap();
-        }
         if c == 'k' {
             name.push(self.reader.get());
-        } else if c == 's' && SUB_RE.is_match(&self.reader.peekn(5)) {
+        } else if c == 's' && is_substitute_abbreviation(&self.reader.peekn(5)) {
             self.reader.get();
             name.push_str("substitute");
         } else if ['@', '*', '!', '=', '>', '<', '&', '~', '#'].contains(&c) {
             name.push(self.reader.get());
-        } else if self.reader.peekn(2) == "py" {
+        } else if self.reader.peekn(2) == "py" || self.reader.peekn(4) == "vim9" {
             name.push_str(&self.reader.read_alnum());
         } else {
             let pos = self.reader.tell();
             name.push_str(&self.reader.read_alpha());
-            if name != "del" && DEL_RE.is_match(&name) {
+            if name != "del" && is_delete_with_flag(&name) {
                 self.reader.seek_set(pos);
                 name = self.reader.getn(name.len() - 1);
             }
@@ -1958,16 +2743,22 @@ impl<'a> Parser<'a> {
             return None;
         }
         if let Some(cmd) = self.commands.get(&name) {
-            Some(Rc::clone(cmd))
+            Some(Arc::clone(cmd))
+        } else if let Some(cmd) = self.unknown_command.as_ref().and_then(|f| f(&name)) {
+            // the map is shared with the parser that made this one, so it is copied the first
+            // time a command is added to it
+            let cmd = Arc::new(cmd);
+            Arc::make_mut(&mut self.commands).insert(name, Arc::clone(&cmd));
+            Some(cmd)
         } else if name.starts_with(|c: char| c.is_uppercase()) {
             name.push_str(&self.reader.read_alnum());
-            let cmd = Rc::new(Command {
+            let cmd = Arc::new(Command {
                 name: name.clone(),
                 minlen: 0,
                 flags: Flag::USERCMD | Flag::TRLBAR,
                 parser: ParserKind::UserCmd,
             });
-            self.commands.insert(name, Rc::clone(&cmd));
+            Arc::make_mut(&mut self.commands).insert(name, Arc::clone(&cmd));
             Some(cmd)
         } else {
             None
@@ -1975,12 +2766,13 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_cmd_modifier_range(&mut self, ea: ExArg) {
-        let pos = self.reader.getpos();
-        self.add_node(Node::ExCmd {
+        // a line with only modifiers and a range, like `:5`, has no command
+        self.add_node(Node::ExCmdRaw {
             pos: ea.cmdpos,
             mods: ea.modifiers,
-            command: ea.cmd.name.clone(),
-            args: self.reader.getstr(ea.argpos, pos),
+            range: ea.range,
+            command: String::new(),
+            args: String::new(),
             bang: ea.bang,
         });
     }
@@ -1990,8 +2782,26 @@ impl<'a> Parser<'a> {
         let c = self.reader.peek();
         match c {
             EOF => Ok(()),
-            EOL | '|' => {
+            EOL => {
+                self.reader.get();
+                Ok(())
+            }
+            '|' => {
+                let pos = self.reader.getpos();
                 self.reader.get();
+                self.reader.skip_white();
+                match self.reader.peek() {
+                    EOF | EOL => {
+                        self.add_node(Node::Bar { pos });
+                        self.reader.get();
+                    }
+                    '"' => {
+                        self.add_node(Node::Bar { pos });
+                        self.parse_comment(true)?;
+                        self.reader.get();
+                    }
+                    _ => (),
+                }
                 Ok(())
             }
             '"' => {
@@ -1999,7 +2809,11 @@ impl<'a> Parser<'a> {
                 self.reader.get();
                 Ok(())
             }
-            _ => self.err(&format!("E488: Trailing characters: {}", c)),
+            _ => self.err_found(
+                ErrorCode::TrailingCharacters,
+                &format!("E488: Trailing characters: {}", c),
+                c,
+            ),
         }
     }
 }
@@ -2008,6 +2822,7 @@ impl<'a> Parser<'a> {
 pub struct ExprParser<'a> {
     reader: &'a Reader,
     tokenizer: Tokenizer<'a>,
+    depth: usize,
 }
 
 impl<'a> ExprParser<'a> {
@@ -2015,14 +2830,52 @@ impl<'a> ExprParser<'a> {
         Self {
             reader,
             tokenizer: Tokenizer::new(reader),
+            depth: 0,
+        }
+    }
+
+    /// Parse something nested in the current expression, failing if that would nest expressions
+    /// more than `MAX_EXPR_DEPTH` deep.
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Node>) -> Result<Node> {
+        let depth = self.depth;
+        self.deeper()?;
+        let rv = parse(self);
+        // this also undoes the levels added by the chains of operators in what was parsed
+        self.depth = depth;
+        rv
+    }
+
+    /// Count another link of a chain, e.g. an operator in `1 + 2 + 3` or a subscript in
+    /// `x[0][1]`. Each link after the first nests the chain a level deeper.
+    fn link(&mut self, links: &mut usize) -> Result<()> {
+        *links += 1;
+        if *links > 1 {
+            self.deeper()?;
+        }
+        Ok(())
+    }
+
+    /// Go a level deeper in the current expression, failing if that would be more than
+    /// `MAX_EXPR_DEPTH` deep.
+    fn deeper(&mut self) -> Result<()> {
+        if self.depth == MAX_EXPR_DEPTH {
+            return Err(ParseError::new(
+                ErrorCode::ExpressionTooRecursive,
+                "E1169: Expression too recursive".to_string(),
+                self.reader.getpos(),
+            ));
         }
+        self.depth += 1;
+        Ok(())
     }
 
     fn token_err<T>(&self, token: Token) -> Result<T> {
-        Err(ParseError {
-            msg: format!("unexpected token: {}", token.value),
-            pos: token.pos,
-        })
+        Err(ParseError::new(
+            ErrorCode::UnexpectedToken,
+            format!("unexpected token: {}", token.value),
+            token.pos,
+        )
+        .with_found(token.value))
     }
 
     pub fn parse(&mut self) -> Result<Node> {
@@ -2030,10 +2883,21 @@ impl<'a> ExprParser<'a> {
     }
 
     fn parse_expr1(&mut self) -> Result<Node> {
+        self.nested(Self::parse_ternary)
+    }
+
+    fn parse_ternary(&mut self) -> Result<Node> {
         let mut left = self.parse_expr2()?;
         let pos = self.reader.tell();
         let mut token = self.tokenizer.get()?;
-        if token.kind == TokenKind::Question {
+        if token.kind == TokenKind::QuestionQuestion {
+            left = Node::BinaryOp {
+                pos: token.pos,
+                op: BinaryOpKind::Falsy,
+                left: Box::new(left),
+                right: Box::new(self.parse_expr1()?),
+            };
+        } else if token.kind == TokenKind::Question {
             let pos = token.pos;
             let cond = Box::new(left);
             let left_side = Box::new(self.parse_expr1()?);
@@ -2057,10 +2921,12 @@ impl<'a> ExprParser<'a> {
 
     fn parse_expr2(&mut self) -> Result<Node> {
         let mut left = self.parse_expr3()?;
+        let mut links = 0;
         loop {
             let pos = self.reader.tell();
             let token = self.tokenizer.get()?;
             if token.kind == TokenKind::OrOr {
+                self.link(&mut links)?;
                 let node = Node::BinaryOp {
                     pos: token.pos,
                     op: BinaryOpKind::Or,
@@ -2078,10 +2944,12 @@ impl<'a> ExprParser<'a> {
 
     fn parse_expr3(&mut self) -> Result<Node> {
         let mut left = self.parse_expr4()?;
+        let mut links = 0;
         loop {
             let pos = self.reader.tell();
             let token = self.tokenizer.get()?;
             if token.kind == TokenKind::AndAnd {
+                self.link(&mut links)?;
                 let node = Node::BinaryOp {
                     pos: token.pos,
                     op: BinaryOpKind::And,
@@ -2102,7 +2970,6 @@ impl<'a> ExprParser<'a> {
         let cursor = self.reader.tell();
         let token = self.tokenizer.get()?;
         let pos = token.pos;
-        let left_side = Box::new(left.clone());
         let op = match token.kind {
             TokenKind::EqEq => BinaryOpKind::EqEq,
             TokenKind::EqEqCI => BinaryOpKind::EqEqCI,
@@ -2142,7 +3009,7 @@ impl<'a> ExprParser<'a> {
         let node = Node::BinaryOp {
             pos,
             op,
-            left: left_side,
+            left: Box::new(left),
             right: Box::new(self.parse_expr5()?),
         };
         left = node;
@@ -2151,24 +3018,26 @@ impl<'a> ExprParser<'a> {
 
     fn parse_expr5(&mut self) -> Result<Node> {
         let mut left = self.parse_expr6()?;
+        let mut links = 0;
         loop {
             let cursor = self.reader.tell();
             let token = self.tokenizer.get()?;
             let pos = token.pos;
-            let left_side = Box::new(left.clone());
             let op = match token.kind {
                 TokenKind::Plus => BinaryOpKind::Add,
                 TokenKind::Minus => BinaryOpKind::Subtract,
                 TokenKind::Dot => BinaryOpKind::Concat,
+                TokenKind::DotDot => BinaryOpKind::StringConcat,
                 _ => {
                     self.reader.seek_set(cursor);
                     break;
                 }
             };
+            self.link(&mut links)?;
             let node = Node::BinaryOp {
                 pos,
                 op,
-                left: left_side,
+                left: Box::new(left),
                 right: Box::new(self.parse_expr6()?),
             };
             left = node;
@@ -2178,11 +3047,11 @@ impl<'a> ExprParser<'a> {
 
     fn parse_expr6(&mut self) -> Result<Node> {
         let mut left = self.parse_expr7()?;
+        let mut links = 0;
         loop {
             let cursor = self.reader.tell();
             let token = self.tokenizer.get()?;
             let pos = token.pos;
-            let left_side = Box::new(left.clone());
             let op = match token.kind {
                 TokenKind::Star => BinaryOpKind::Multiply,
                 TokenKind::Slash => BinaryOpKind::Divide,
@@ -2192,10 +3061,11 @@ impl<'a> ExprParser<'a> {
                     break;
                 }
             };
+            self.link(&mut links)?;
             let node = Node::BinaryOp {
                 pos,
                 op,
-                left: left_side,
+                left: Box::new(left),
                 right: Box::new(self.parse_expr7()?),
             };
             left = node;
@@ -2219,51 +3089,48 @@ impl<'a> ExprParser<'a> {
         let node = Node::UnaryOp {
             pos,
             op,
-            right: Box::new(self.parse_expr7()?),
+            right: Box::new(self.nested(Self::parse_expr7)?),
         };
         Ok(node)
     }
 
     fn parse_expr8(&mut self) -> Result<Node> {
         let mut left = self.parse_expr9()?;
+        let mut links = 0;
         loop {
             let cursor = self.reader.tell();
             let c = self.reader.peek();
             let token = self.tokenizer.get()?;
             if !c.is_white() && token.kind == TokenKind::SqOpen {
+                self.link(&mut links)?;
                 left = self.parse_slice(left, token.pos)?;
             } else if token.kind == TokenKind::POpen {
+                self.link(&mut links)?;
                 let pos = token.pos;
                 let name = Box::new(left);
-                let mut args = vec![];
-                if self.tokenizer.peek()?.kind == TokenKind::PClose {
-                    self.tokenizer.get()?;
-                } else {
-                    loop {
-                        args.push(self.parse_expr1()?);
-                        let token = self.tokenizer.get()?;
-                        if token.kind == TokenKind::Comma {
-                            if self.tokenizer.peek()?.kind == TokenKind::PClose {
-                                self.tokenizer.get()?;
-                                break;
-                            }
-                        } else if token.kind == TokenKind::PClose {
-                            break;
-                        } else {
-                            return self.token_err(token);
-                        }
-                    }
+                let args = self.parse_call_args(pos)?;
+                let node = Node::Call { pos, name, args };
+                left = node;
+            } else if token.kind == TokenKind::Arrow {
+                self.link(&mut links)?;
+                let name = Box::new(self.parse_expr9()?);
+                let popen = self.tokenizer.get()?;
+                if popen.kind != TokenKind::POpen {
+                    return self.token_err(popen);
                 }
-                if args.len() > MAX_FUNC_ARGS {
-                    return Err(ParseError {
-                        msg: "E740: Too many arguments for function".to_string(),
-                        pos,
-                    });
-                }
-                let node = Node::Call { pos, name, args };
-                left = node;
+                let args = self.parse_call_args(popen.pos)?;
+                left = Node::Method {
+                    pos: token.pos,
+                    left: Box::new(left),
+                    right: Box::new(Node::Call {
+                        pos: popen.pos,
+                        name,
+                        args,
+                    }),
+                };
             } else if !c.is_white() && token.kind == TokenKind::Dot {
                 if let Some(node) = self.parse_dot(token, left.clone()) {
+                    self.link(&mut links)?;
                     left = node;
                 } else {
                     self.reader.seek_set(cursor);
@@ -2277,6 +3144,37 @@ impl<'a> ExprParser<'a> {
         Ok(left)
     }
 
+    fn parse_call_args(&mut self, pos: Position) -> Result<Vec<Node>> {
+        // the opening parenthesis has already been read
+        let mut args = vec![];
+        if self.tokenizer.peek()?.kind == TokenKind::PClose {
+            self.tokenizer.get()?;
+        } else {
+            loop {
+                args.push(self.parse_expr1()?);
+                let token = self.tokenizer.get()?;
+                if token.kind == TokenKind::Comma {
+                    if self.tokenizer.peek()?.kind == TokenKind::PClose {
+                        self.tokenizer.get()?;
+                        break;
+                    }
+                } else if token.kind == TokenKind::PClose {
+                    break;
+                } else {
+                    return self.token_err(token);
+                }
+            }
+        }
+        if args.len() > MAX_FUNC_ARGS {
+            return Err(ParseError::new(
+                ErrorCode::TooManyArguments,
+                "E740: Too many arguments for function".to_string(),
+                pos,
+            ));
+        }
+        Ok(args)
+    }
+
     fn parse_expr9(&mut self) -> Result<Node> {
         let cursor = self.reader.tell();
         let token = self.tokenizer.get()?;
@@ -2300,97 +3198,9 @@ impl<'a> ExprParser<'a> {
                     value: format!("\'{}\'", self.tokenizer.get_sstring()?),
                 }
             }
-            TokenKind::SqOpen => {
-                let token = self.tokenizer.peek()?;
-                let mut items = vec![];
-                if token.kind == TokenKind::SqClose {
-                    self.tokenizer.get()?;
-                } else {
-                    loop {
-                        items.push(self.parse_expr1()?);
-                        let token = self.tokenizer.peek()?;
-                        match token.kind {
-                            TokenKind::Comma => {
-                                self.tokenizer.get()?;
-                                if self.tokenizer.peek()?.kind == TokenKind::SqClose {
-                                    self.tokenizer.get()?;
-                                    break;
-                                }
-                            }
-                            TokenKind::SqClose => {
-                                self.tokenizer.get()?;
-                                break;
-                            }
-                            _ => {
-                                return self.token_err(token);
-                            }
-                        }
-                    }
-                }
-                Node::List { pos, items }
-            }
-            TokenKind::COpen => {
-                // dict or lambda
-                let savepos = self.reader.tell();
-                let mut token = self.tokenizer.get()?;
-                let mut is_lambda = token.kind == TokenKind::Arrow;
-                if !is_lambda && ![TokenKind::SQuote, TokenKind::DQuote].contains(&token.kind) {
-                    let token2 = self.tokenizer.peek()?;
-                    is_lambda = [TokenKind::Arrow, TokenKind::Comma].contains(&token2.kind);
-                }
-                if is_lambda {
-                    if let Some(node) = self.parse_lambda(token, pos)? {
-                        return Ok(node);
-                    }
-                }
-                let mut items = vec![];
-                self.reader.seek_set(savepos);
-                token = self.tokenizer.peek()?;
-                if token.kind == TokenKind::CClose {
-                    self.tokenizer.get()?;
-                    return Ok(Node::Dict { pos, items });
-                }
-                loop {
-                    let key = self.parse_expr1()?;
-                    token = self.tokenizer.get()?;
-                    if token.kind == TokenKind::CClose {
-                        // premature closing of dict, e.g. "let d = { 'foo': }"
-                        if !items.is_empty() {
-                            return self.token_err(token);
-                        }
-                        self.reader.seek_set(cursor);
-                        return self.parse_identifier();
-                    }
-                    if token.kind != TokenKind::Colon {
-                        return self.token_err(token);
-                    }
-                    let val = self.parse_expr1()?;
-                    items.push((Box::new(key), Box::new(val)));
-                    token = self.tokenizer.get()?;
-                    if token.kind == TokenKind::Comma {
-                        if self.tokenizer.peek()?.kind == TokenKind::CClose {
-                            self.tokenizer.get()?;
-                            break;
-                        }
-                    } else if token.kind == TokenKind::CClose {
-                        break;
-                    } else {
-                        return self.token_err(token);
-                    }
-                }
-                Node::Dict { pos, items }
-            }
-            TokenKind::POpen => {
-                let node = Node::ParenExpr {
-                    pos: token.pos,
-                    expr: Box::new(self.parse_expr1()?),
-                };
-                let token = self.tokenizer.get()?;
-                if token.kind != TokenKind::PClose {
-                    return self.token_err(token);
-                }
-                node
-            }
+            TokenKind::SqOpen => self.parse_list(pos)?,
+            TokenKind::COpen => self.parse_dict(cursor, pos)?,
+            TokenKind::POpen => self.parse_paren(pos)?,
             TokenKind::Option => Node::Option {
                 pos,
                 value: token.value,
@@ -2423,6 +3233,118 @@ impl<'a> ExprParser<'a> {
         })
     }
 
+    // The list, dict and parenthesized expression parsers are separate functions so that each
+    // level of nesting only pays for the stack space of the one it goes through.
+    fn parse_list(&mut self, pos: Position) -> Result<Node> {
+        let token = self.tokenizer.peek()?;
+        let mut items = vec![];
+        let mut trailing_comma = false;
+        if token.kind == TokenKind::SqClose {
+            self.tokenizer.get()?;
+        } else {
+            loop {
+                items.push(self.parse_expr1()?);
+                let token = self.tokenizer.peek()?;
+                match token.kind {
+                    TokenKind::Comma => {
+                        self.tokenizer.get()?;
+                        if self.tokenizer.peek()?.kind == TokenKind::SqClose {
+                            self.tokenizer.get()?;
+                            trailing_comma = true;
+                            break;
+                        }
+                    }
+                    TokenKind::SqClose => {
+                        self.tokenizer.get()?;
+                        break;
+                    }
+                    _ => {
+                        return self.token_err(token);
+                    }
+                }
+            }
+        }
+        Ok(Node::List {
+            pos,
+            items,
+            trailing_comma,
+        })
+    }
+
+    fn parse_dict(&mut self, cursor: usize, pos: Position) -> Result<Node> {
+        // dict or lambda
+        let savepos = self.reader.tell();
+        let mut token = self.tokenizer.get()?;
+        let mut is_lambda = token.kind == TokenKind::Arrow;
+        if !is_lambda && ![TokenKind::SQuote, TokenKind::DQuote].contains(&token.kind) {
+            let token2 = self.tokenizer.peek()?;
+            is_lambda = [TokenKind::Arrow, TokenKind::Comma].contains(&token2.kind);
+        }
+        if is_lambda {
+            if let Some(node) = self.parse_lambda(token, pos)? {
+                return Ok(node);
+            }
+        }
+        let mut items = vec![];
+        let mut trailing_comma = false;
+        self.reader.seek_set(savepos);
+        token = self.tokenizer.peek()?;
+        if token.kind == TokenKind::CClose {
+            self.tokenizer.get()?;
+            return Ok(Node::Dict {
+                pos,
+                items,
+                trailing_comma,
+            });
+        }
+        loop {
+            let key = self.parse_expr1()?;
+            token = self.tokenizer.get()?;
+            if token.kind == TokenKind::CClose {
+                // premature closing of dict, e.g. "let d = { 'foo': }"
+                if !items.is_empty() {
+                    return self.token_err(token);
+                }
+                self.reader.seek_set(cursor);
+                return self.parse_identifier();
+            }
+            if token.kind != TokenKind::Colon {
+                return self.token_err(token);
+            }
+            let val = self.parse_expr1()?;
+            items.push((Box::new(key), Box::new(val)));
+            token = self.tokenizer.get()?;
+            if token.kind == TokenKind::Comma {
+                if self.tokenizer.peek()?.kind == TokenKind::CClose {
+                    self.tokenizer.get()?;
+                    trailing_comma = true;
+                    break;
+                }
+            } else if token.kind == TokenKind::CClose {
+                break;
+            } else {
+                return self.token_err(token);
+            }
+        }
+        Ok(Node::Dict {
+            pos,
+            items,
+            trailing_comma,
+        })
+    }
+
+    fn parse_paren(&mut self, pos: Position) -> Result<Node> {
+        let node = Node::ParenExpr {
+            pos,
+            expr: Box::new(self.parse_expr1()?),
+        };
+        let token = self.tokenizer.get()?;
+        if token.kind != TokenKind::PClose {
+            return self.token_err(token);
+        }
+        Ok(node)
+    }
+
     fn parse_lambda(&mut self, mut token: Token, pos: Position) -> Result<Option<Node>> {
         let mut fallback = false;
         let mut args = vec![];
@@ -2434,15 +3356,19 @@ impl<'a> ExprParser<'a> {
                 }
                 TokenKind::Identifier => {
                     if !isargname(&token.value) {
-                        return Err(ParseError {
-                            msg: format!("E125: Illegal argument: {}", token.value),
-                            pos: token.pos,
-                        });
+                        return Err(ParseError::new(
+                            ErrorCode::IllegalArgument,
+                            format!("E125: Illegal argument: {}", token.value),
+                            token.pos,
+                        )
+                        .with_found(token.value));
                     } else if named.contains(&token.value.clone()) {
-                        return Err(ParseError {
-                            msg: format!("E853: Duplicate argument name: {}", token.value),
-                            pos: token.pos,
-                        });
+                        return Err(ParseError::new(
+                            ErrorCode::DuplicateArgument,
+                            format!("E853: Duplicate argument name: {}", token.value),
+                            token.pos,
+                        )
+                        .with_found(token.value));
                     }
                     named.push(token.value.clone());
                     let varnode = Node::Identifier {
@@ -2451,12 +3377,13 @@ impl<'a> ExprParser<'a> {
                     };
                     let maybe_comma = self.tokenizer.peek()?.kind;
                     if self.reader.peek().is_white() && maybe_comma == TokenKind::Comma {
-                        return Err(ParseError {
-                            msg: String::from(
+                        return Err(ParseError::new(
+                            ErrorCode::InvalidArgumentValue,
+                            String::from(
                                 "E475: invalid argument: White space is not allowed before comma",
                             ),
-                            pos: self.reader.getpos(),
-                        });
+                            self.reader.getpos(),
+                        ));
                     }
                     token = self.tokenizer.get()?;
                     args.push(varnode);
@@ -2469,13 +3396,11 @@ impl<'a> ExprParser<'a> {
                     } else if token.kind == TokenKind::Arrow {
                         break;
                     } else {
-                        return Err(ParseError {
-                            msg: format!(
-                                "unexpected token: {}, type: {:#?}",
-                                token.value, token.kind
-                            ),
-                            pos: token.pos,
-                        });
+                        return Err(ParseError::new(
+                            ErrorCode::UnexpectedToken,
+                            format!("unexpected token: {}, type: {:#?}", token.value, token.kind),
+                            token.pos,
+                        ));
                     }
                 }
                 TokenKind::DotDotDot => {
@@ -2557,10 +3482,12 @@ impl<'a> ExprParser<'a> {
                 self.reader.skip_white();
                 let c = self.reader.peek();
                 if c != '}' {
-                    return Err(ParseError {
-                        msg: format!("unexpected token: {}", c),
-                        pos: self.reader.getpos(),
-                    });
+                    return Err(ParseError::new(
+                        ErrorCode::UnexpectedToken,
+                        format!("unexpected token: {}", c),
+                        self.reader.getpos(),
+                    )
+                    .with_found(c));
                 }
                 self.reader.seek_cur(1);
             } else {
@@ -2660,15 +3587,22 @@ impl<'a> ExprParser<'a> {
         // is used for parsing the lhs of a `for` or `let` command, e.g. `let foo = bar`. in this
         // case a function call is not valid, e.g. `let foo() = bar` is not valid syntax, so we
         // should not parse it.
+        self.nested(Self::parse_lv8)
+    }
+
+    fn parse_lv8(&mut self) -> Result<Node> {
         let mut left = self.parse_lv9()?;
+        let mut links = 0;
         loop {
             let cursor = self.reader.tell();
             let c = self.reader.peek();
             let token = self.tokenizer.get()?;
             if !c.is_white() && token.kind == TokenKind::SqOpen {
+                self.link(&mut links)?;
                 left = self.parse_slice(left, token.pos)?;
             } else if !c.is_white() && token.kind == TokenKind::Dot {
                 if let Some(n) = self.parse_dot(token, left.clone()) {
+                    self.link(&mut links)?;
                     left = n;
                 } else {
                     self.reader.seek_set(cursor);
@@ -2726,7 +3660,11 @@ impl<'a> ExprParser<'a> {
 
 #[cfg(test)]
 mod tests {
-    use super::super::{parse_lines, Node, Position};
+    use super::super::{
+        parse_lines, Address, ErrorCode, LetTarget, LineSpec, Node, Offset, OptionScope, Position,
+        Range,
+    };
+    use super::{is_delete_with_flag, is_substitute_abbreviation};
 
     fn create_node(s: &str) -> Node {
         if let Node::TopLevel { body, .. } = parse_lines(&[s]).unwrap() {
@@ -2749,18 +3687,47 @@ mod tests {
         let code = ["python3 print('foo')"];
         let expected = "(excmd \"python3 print('foo')\")";
         assert_eq!(&format!("{}", parse_lines(&code).unwrap()), expected);
-        let code = ["python3 <<EOF", "print('foo')", "print('bar')", "EOF"];
-        let expected = "(excmd \"python3 <<EOF\nprint('foo')\nprint('bar')\nEOF\")";
+        let code = [
+            "python3 <<EOF",
+            "print('foo')",
+            "print('bar')",
+            "EOF",
+            "echo 1",
+        ];
+        let expected = "(python3 << EOF\nprint('foo')\nprint('bar'))\n(echo 1)";
+        assert_eq!(&format!("{}", parse_lines(&code).unwrap()), expected);
+        let code = ["if 1", "  lua << trim", "    print(1)", "  .", "endif"];
+        let expected = "(if 1\n  (lua << trim\n      print(1)))";
+        assert_eq!(&format!("{}", parse_lines(&code).unwrap()), expected);
+        let code = ["perl <<", "  .", "."];
+        let expected = "(perl <<\n  .)";
+        assert_eq!(&format!("{}", parse_lines(&code).unwrap()), expected);
+        let code = ["ruby << trimmed", "trimmed"];
+        let expected = "(ruby << trimmed)";
         assert_eq!(&format!("{}", parse_lines(&code).unwrap()), expected);
     }
 
     #[test]
     fn test_loadkeymap() {
         let code = ["loadkeymap", "a A", "b B comment"];
-        let expected = "(excmd \"loadkeymap \n\na A\nb B comment\")";
+        let expected = "(excmd \"loadkeymap \na A\nb B comment\")";
         assert_eq!(&format!("{}", parse_lines(&code).unwrap()), expected);
     }
 
+    #[test]
+    fn test_normal() {
+        let code = ["normal! gg=G", "silent normal A;\x1b | \"x\x04  "];
+        let expected = "(normal! \"gg=G\")\n(normal \"A;\x1b | \\\"x\x04  \")";
+        assert_eq!(&format!("{}", parse_lines(&code).unwrap()), expected);
+        if let Node::TopLevel { body, .. } = parse_lines(&code[1..]).unwrap() {
+            if let Node::Normal { raw, .. } = &body[0] {
+                assert_eq!(raw, "A;\x1b | \"x\x04  ");
+            } else {
+                panic!("not a normal node");
+            }
+        }
+    }
+
     #[test]
     fn test_augroup_and_autocmds() {
         let code = ["augroup foo", "autocmd VimEnter * Command", "augroup END"];
@@ -2770,6 +3737,24 @@ mod tests {
             "(excmd \"augroup END\")"
         );
         assert_eq!(&format!("{}", parse_lines(&code).unwrap()), expected);
+        let code = ["autocmd winclosed,UIEnter <buffer> Command"];
+        let expected = "(autocmd UIEnter,winclosed <buffer> (excmd \"Command\"))";
+        assert_eq!(&format!("{}", parse_lines(&code).unwrap()), expected);
+        let code = ["autocmd! * <buffer>", "autocmd! BufEnter", "echo 1"];
+        let expected = "(autocmd * <buffer>)\n(autocmd BufEnter)\n(echo 1)";
+        assert_eq!(&format!("{}", parse_lines(&code).unwrap()), expected);
+        let code = [
+            "augroup vimrc | autocmd! | augroup END",
+            "au! vimrc BufRead|echo 1",
+        ];
+        let expected = concat!(
+            "(excmd \"augroup vimrc\")\n",
+            "(autocmd)\n",
+            "(excmd \"augroup END\")\n",
+            "(autocmd vimrc BufRead)\n",
+            "(echo 1)"
+        );
+        assert_eq!(&format!("{}", parse_lines(&code).unwrap()), expected);
     }
 
     #[test]
@@ -2841,6 +3826,49 @@ mod tests {
         assert_eq!(&format!("{}", parse_lines(&code).unwrap()), expected);
     }
 
+    #[test]
+    fn test_let_targets() {
+        let node = parse_lines(&[
+            "let @a .= 'x'",
+            "let &l:sw *= 2",
+            "let [&g:ts, @@, $X; rest] = [1, 2, 3]",
+        ])
+        .unwrap();
+        let targets = match &node {
+            Node::TopLevel { body, .. } => body.iter().map(|n| n.let_targets()).collect::<Vec<_>>(),
+            _ => unreachable!(),
+        };
+        assert_eq!(targets[0], vec![LetTarget::Register('a')]);
+        assert_eq!(
+            targets[1],
+            vec![LetTarget::Option(OptionScope::Local, "sw")]
+        );
+        assert_eq!(
+            targets[2][..3],
+            [
+                LetTarget::Option(OptionScope::Global, "ts"),
+                LetTarget::Register('"'),
+                LetTarget::Env("X"),
+            ]
+        );
+        assert!(matches!(
+            targets[2][3],
+            LetTarget::Variable(Node::Identifier { .. })
+        ));
+        assert_eq!(
+            &format!("{}", node),
+            concat!(
+                "(let .= @a 'x')\n",
+                "(let *= &l:sw 2)\n",
+                "(let = (&g:ts @@ $X . rest) (list 1 2 3))",
+            )
+        );
+        let err = parse_lines(&["let @a += 1"]).unwrap_err();
+        assert_eq!(err.code, ErrorCode::WrongVariableType);
+        let err = parse_lines(&["let & = 1"]).unwrap_err();
+        assert_eq!(err.code, ErrorCode::OptionNameMissing);
+    }
+
     #[test]
     fn test_curly_name() {
         let code = ["let foo{bar}baz = 'something'"];
@@ -2855,6 +3883,36 @@ mod tests {
         assert_eq!(&format!("{}", parse_lines(&code).unwrap()), expected);
     }
 
+    #[test]
+    fn test_trailing_comma() {
+        let trailing_comma = |code: &str| match parse_lines(&[code]).unwrap() {
+            Node::TopLevel { body, .. } => match &body[0] {
+                Node::Echo { list, .. } => match &list[0] {
+                    Node::List { trailing_comma, .. } | Node::Dict { trailing_comma, .. } => {
+                        *trailing_comma
+                    }
+                    _ => panic!("not a list or dict"),
+                },
+                _ => panic!("not an echo"),
+            },
+            _ => unreachable!(),
+        };
+        assert!(trailing_comma("echo [1, 2,]"));
+        assert!(trailing_comma("echo [1 , ]"));
+        assert!(!trailing_comma("echo [1, 2]"));
+        assert!(!trailing_comma("echo []"));
+        assert!(trailing_comma("echo {'a': 1,}"));
+        assert!(!trailing_comma("echo {'a': 1}"));
+        assert!(!trailing_comma("echo {}"));
+        assert_eq!(
+            format!("{}", parse_lines(&["echo [1, 2,]"]).unwrap()),
+            format!("{}", parse_lines(&["echo [1, 2]"]).unwrap())
+        );
+        assert!(parse_lines(&["echo [,]"]).is_err());
+        assert!(parse_lines(&["echo [1,,]"]).is_err());
+        assert!(parse_lines(&["echo {,}"]).is_err());
+    }
+
     #[test]
     fn test_dot() {
         let code = ["echo foo.bar"];
@@ -2884,6 +3942,36 @@ mod tests {
         assert_eq!(&format!("{}", parse_lines(&code).unwrap()), expected);
     }
 
+    #[test]
+    fn test_one_line_blocks() {
+        let code = ["if has('nvim') | set inccommand=nosplit | endif"];
+        let expected = "(if (has 'nvim')\n  (set inccommand=nosplit))";
+        assert_eq!(&format!("{}", parse_lines(&code).unwrap()), expected);
+        for code in [
+            "if has('nvim') | set inccommand=nosplit | endif",
+            "if x | echo 1 | else | echo 2 | endif",
+            "while 1 | break | endwhile",
+            "try | call F() | catch | endtry",
+        ]
+        .iter()
+        {
+            assert!(Node::is_one_line(&create_node(code)));
+        }
+        let node = parse_lines(&["if x", "echo 1 | endif"]).unwrap();
+        if let Node::TopLevel { body, .. } = node {
+            assert!(!Node::is_one_line(&body[0]));
+        }
+        // `return` without a value ends at the bar
+        let code = [
+            "function F(x)",
+            "if a:x|return|endif",
+            "if a:x | return a:x | endif",
+            "endfunction",
+        ];
+        let expected = "(function (F x)\n  (if a:x\n    (return))\n  (if a:x\n    (return a:x)))";
+        assert_eq!(&format!("{}", parse_lines(&code).unwrap()), expected);
+    }
+
     #[test]
     fn test_execute() {
         let code = ["execute UserCmd"];
@@ -2891,6 +3979,87 @@ mod tests {
         assert_eq!(&format!("{}", parse_lines(&code).unwrap()), expected);
     }
 
+    #[test]
+    fn test_const_eval_and_vim9_commands() {
+        let code = [
+            "const [a, b] = [1, 2]",
+            "eval a->add(b)",
+            "final x = 3",
+            "class Foo extends Bar",
+            "endclass",
+        ];
+        let expected = concat!(
+            "(const = (a b) (list 1 2))\n",
+            "(eval (method a (add b)))\n",
+            "(final = x 3)\n",
+            "(excmd \"class Foo extends Bar\")\n",
+            "(excmd \"endclass\")",
+        );
+        assert_eq!(&format!("{}", parse_lines(&code).unwrap()), expected);
+        assert!(parse_lines(&["eval"]).is_err());
+    }
+
+    #[test]
+    fn test_argopt_and_command_matchers() {
+        assert!(is_substitute_abbreviation("sg"));
+        assert!(is_substitute_abbreviation("scen "));
+        assert!(is_substitute_abbreviation("sie"));
+        assert!(!is_substitute_abbreviation("sil"));
+        assert!(!is_substitute_abbreviation("scrip"));
+        assert!(!is_substitute_abbreviation("sre"));
+        assert!(is_delete_with_flag("dell"));
+        assert!(is_delete_with_flag("deletep"));
+        assert!(!is_delete_with_flag("dxl"));
+        assert!(!is_delete_with_flag("delete"));
+        for code in &[
+            "edit ++bin foo",
+            "edit ++nobin ++edit foo",
+            "edit ++ff=unix ++enc=utf-8 foo",
+            "edit ++bad=keep foo",
+            "edit ++bad=X foo",
+        ] {
+            assert!(parse_lines(&[code]).is_ok(), "{}", code);
+        }
+        assert!(parse_lines(&["edit ++binary foo"]).is_err());
+        assert!(parse_lines(&["edit ++ff=unixy foo"]).is_err());
+    }
+
+    #[test]
+    fn test_substitute() {
+        let code = [
+            r"%s/[/]a\/b/x|y/ge 3 | echo 1",
+            "sno#foo",
+            "s g",
+            "s\\/bar/",
+        ];
+        let expected = concat!(
+            r#"(%substitute "/" "[/]a\\/b" "x|y" "ge" 3)"#,
+            "\n(echo 1)\n",
+            r##"(snomagic "#" "foo" nil nil nil)"##,
+            "\n",
+            r#"(substitute nil "" nil "g" nil)"#,
+            "\n",
+            r#"(excmd "substitute \/bar/")"#,
+        );
+        assert_eq!(&format!("{}", parse_lines(&code).unwrap()), expected);
+        let err = parse_lines(&["s x/y/"]).unwrap_err();
+        assert_eq!(err.code, ErrorCode::LetterDelimiter);
+    }
+
+    #[test]
+    fn test_global() {
+        let code = [r"g!/a\/b/s//c/ | echo 1", "1,5v#x#", r"g\/d"];
+        let expected = concat!(
+            r#"(global! "/" "a\\/b" (substitute "/" "" "c" "" nil) (echo 1))"#,
+            "\n",
+            r##"(1,5vglobal "#" "x")"##,
+            "\n",
+            r#"(excmd "global \/d")"#,
+        );
+        assert_eq!(&format!("{}", parse_lines(&code).unwrap()), expected);
+        assert!(parse_lines(&["g/x/foo"]).is_err());
+    }
+
     #[test]
     fn test_excmd() {
         let code = ["UserCmd something 123"];
@@ -2898,6 +4067,69 @@ mod tests {
         assert_eq!(&format!("{}", parse_lines(&code).unwrap()), expected);
     }
 
+    #[test]
+    fn test_range() {
+        let code = [
+            "1 , $ delete",
+            ":5",
+            "'<,'>sort",
+            ".+2;/foo//bar/- yank",
+            "%!sort",
+            ":\\&,?x\\??+3 3print",
+        ];
+        let expected = [
+            "(excmd \"1,$delete\")",
+            "(excmd \"5\")",
+            "(excmd \"'<,'>sort\")",
+            "(excmd \".+2;/foo//bar/-yank\")",
+            "(excmd \"%! sort\")",
+            "(excmd \"\\&,?x\\??+3+3print\")",
+        ];
+        assert_eq!(
+            &format!("{}", parse_lines(&code).unwrap()),
+            &expected.join("\n")
+        );
+        let spec = |address, offsets, separator| LineSpec {
+            address,
+            offsets,
+            separator,
+        };
+        if let Node::TopLevel { body, .. } = parse_lines(&[".+2;/foo//bar/- yank"]).unwrap() {
+            if let Node::ExCmdRaw { range, .. } = &body[0] {
+                assert_eq!(
+                    range,
+                    &Range {
+                        specs: vec![
+                            spec(
+                                Some(Address::Current),
+                                vec![Offset::Plus(Some(2))],
+                                Some(';')
+                            ),
+                            spec(
+                                Some(Address::Search {
+                                    pattern: "foo".to_string(),
+                                    backward: false
+                                }),
+                                vec![],
+                                None
+                            ),
+                            spec(
+                                Some(Address::Search {
+                                    pattern: "bar".to_string(),
+                                    backward: false
+                                }),
+                                vec![Offset::Minus(None)],
+                                None
+                            ),
+                        ]
+                    }
+                );
+            } else {
+                panic!("not an excmd node");
+            }
+        }
+    }
+
     #[test]
     fn test_for() {
         let code = ["for [a, b; z] in something", "echo a b z", "endfor"];
@@ -2921,6 +4153,135 @@ mod tests {
         assert_eq!(&format!("{}", parse_lines(&code).unwrap()), expected);
     }
 
+    #[test]
+    fn test_abbreviations() {
+        let code = [
+            "iabbrev <buffer> teh the",
+            "cnoreabbrev <expr> W getcmdtype() == ':' ? 'w' : 'W'",
+            "iab",
+        ];
+        let expected = concat!(
+            "(iabbrev teh the)\n",
+            "(cnoreabbrev W (?: (== (getcmdtype) ':') 'w' 'W'))\n",
+            "(iabbrev)"
+        );
+        let node = parse_lines(&code).unwrap();
+        assert_eq!(&format!("{}", node), expected);
+        match &node.body()[0] {
+            Node::Abbreviation { attrs, .. } => assert_eq!(attrs, &["buffer"]),
+            node => panic!("expected an abbreviation, found {}", node.kind()),
+        }
+    }
+
+    #[test]
+    fn test_sign_menu_and_digraphs() {
+        let code = [
+            "sign define Err text=>> texthl=Error",
+            "sign place 2 line=23 name=Err file=a\\ b.vim",
+            "anoremenu <silent> 10.20 &File.Save\\ All :wall<CR>",
+            "menu",
+            "digraphs e: 235 a:228",
+        ];
+        let expected = concat!(
+            "(sign define Err text=>> texthl=Error)\n",
+            "(sign place 2 line=23 name=Err file=a\\ b.vim)\n",
+            "(anoremenu &File.Save\\ All :wall<CR>)\n",
+            "(menu)\n",
+            "(digraphs e: 235 a: 228)"
+        );
+        let node = parse_lines(&code).unwrap();
+        assert_eq!(&format!("{}", node), expected);
+        match &node.body()[2] {
+            Node::Menu {
+                attrs, priority, ..
+            } => {
+                assert_eq!(attrs, &["silent"]);
+                assert_eq!(priority.as_deref(), Some("10.20"));
+            }
+            node => panic!("expected a menu, found {}", node.kind()),
+        }
+        let err = parse_lines(&["sign frobnicate"]).unwrap_err();
+        assert_eq!(err.code, ErrorCode::UnknownSignCommand);
+        let err = parse_lines(&["sign define Err line=3"]).unwrap_err();
+        assert_eq!(err.code, ErrorCode::InvalidArgumentValue);
+        let err = parse_lines(&["digraphs ab"]).unwrap_err();
+        assert_eq!(err.code, ErrorCode::NumberExpected);
+    }
+
+    #[test]
+    fn test_trailing_bar() {
+        let code = ["echo 1 |  ", "map a b | \" note", "echo 2 | echo 3"];
+        let expected = concat!(
+            "(echo 1)\n",
+            "(bar)\n",
+            "(map a b)\n",
+            "(bar)\n",
+            "; note\n",
+            "(echo 2)\n",
+            "(echo 3)"
+        );
+        assert_eq!(&format!("{}", parse_lines(&code).unwrap()), expected);
+    }
+
+    #[test]
+    fn test_match() {
+        let tests = [
+            ("match ErrorMsg /\\s\\+$/", "(match ErrorMsg /\\s\\+$/)"),
+            (
+                "2mat Search #a/b# | echo 1",
+                "(2match Search #a/b#)\n(echo 1)",
+            ),
+            ("3match none", "(3match none)"),
+            ("match", "(match)"),
+            ("hi link Foo Bar", "(highlight link Foo Bar)"),
+        ];
+        for (code, expected) in tests.iter() {
+            assert_eq!(&format!("{}", parse_lines(&[code]).unwrap()), expected);
+        }
+        let err = parse_lines(&["match Error /foo"]).unwrap_err();
+        assert_eq!(err.code, ErrorCode::InvalidArgumentValue);
+    }
+
+    #[test]
+    fn test_source_runtime_and_colorscheme() {
+        let tests = [
+            ("so <sfile>:h/a\\ b.vim", "(source <sfile>:h/a\\ b.vim)"),
+            ("source! keys.txt | echo 1", "(source! keys.txt)\n(echo 1)"),
+            (
+                "runtime! START plugin/**/*.vim",
+                "(runtime! START plugin/**/*.vim)",
+            ),
+            (
+                "ru syntax/a.vim syntax/b.vim",
+                "(runtime syntax/a.vim syntax/b.vim)",
+            ),
+            ("colo desert", "(colorscheme desert)"),
+            ("colorscheme", "(colorscheme)"),
+        ];
+        for (code, expected) in tests.iter() {
+            assert_eq!(&format!("{}", parse_lines(&[code]).unwrap()), expected);
+        }
+        let err = parse_lines(&["runtime ALL"]).unwrap_err();
+        assert_eq!(err.code, ErrorCode::ArgumentRequired);
+        let names = ["source", "runtime", "colorscheme", "nnoremap", "delete"]
+            .iter()
+            .map(|c| {
+                create_node(&format!("{} x y", c))
+                    .command_name()
+                    .map(String::from)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            ["source", "runtime", "colorscheme", "nnoremap", "delete"]
+                .iter()
+                .map(|c| Some(c.to_string()))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(create_node("1,3").command_name(), None);
+        assert_eq!(create_node("\" note").command_name(), None);
+    }
+
     #[test]
     fn test_lockvar_mapping_and_unlockvar() {
         let code = [
@@ -2936,6 +4297,13 @@ mod tests {
         assert_eq!(&format!("{}", parse_lines(&code).unwrap()), expected);
     }
 
+    #[test]
+    fn test_mapping_bang() {
+        let code = ["noremap! <C-a> <Home>", "map <C-b> <Left>"];
+        let expected = "(noremap! <C-a> <Home>)\n(map <C-b> <Left>)";
+        assert_eq!(&format!("{}", parse_lines(&code).unwrap()), expected);
+    }
+
     #[test]
     fn test_parenexpr_and_bin_op() {
         let code = ["let x = ((a && b) || c * d)"];
@@ -3021,4 +4389,54 @@ mod tests {
             assert!(result.unwrap_err().msg.contains(err));
         }
     }
+
+    #[test]
+    fn test_newer_operators() {
+        let tests = [
+            ("echo a .. b . c", "(echo (concat (.. a b) c))"),
+            ("echo a ?? b ? c : d", "(echo (?? a (?: b c d)))"),
+            ("echo x->len()", "(echo (method x (len)))"),
+            (
+                "echo [1]->map({_, v -> v})->get(0, 2)",
+                "(echo (method (method (list 1) (map (lambda (_ v) v))) (get 0 2)))",
+            ),
+            ("let s ..= 'x'", "(let ..= s 'x')"),
+        ];
+        for (code, expected) in tests.iter() {
+            assert_eq!(&format!("{}", parse_lines(&[code]).unwrap()), expected);
+        }
+        assert!(parse_lines(&["echo x->len"]).is_err());
+    }
+
+    #[test]
+    fn test_set() {
+        let tests = [
+            ("set", "(set)"),
+            ("setl nowrap ts=4", "(setlocal nowrap ts=4)"),
+            ("setg fdm:marker", "(setglobal fdm:marker)"),
+            (
+                "set wig+=*.o,*.a path^=. rtp-=x all&",
+                "(set wig+=*.o,*.a path^=. rtp-=x all&)",
+            ),
+            ("set sbr=>\\ \\| | echo 1", "(set sbr=>\\ \\|)\n(echo 1)"),
+            ("set fcs=fold:\\   ", "(set fcs=fold:\\ )"),
+        ];
+        for (code, expected) in tests.iter() {
+            assert_eq!(&format!("{}", parse_lines(&[code]).unwrap()), expected);
+        }
+        if let Node::TopLevel { body, .. } = parse_lines(&["set nowrap ts=4 wig+=a\\ b"]).unwrap() {
+            if let Node::Set { args, .. } = &body[0] {
+                assert_eq!(
+                    args,
+                    &[
+                        ("nowrap".to_string(), "".to_string(), "".to_string()),
+                        ("ts".to_string(), "=".to_string(), "4".to_string()),
+                        ("wig".to_string(), "+=".to_string(), "a\\ b".to_string()),
+                    ]
+                );
+                return;
+            }
+        }
+        panic!("set did not parse to a Set node");
+    }
 }
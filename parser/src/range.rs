@@ -0,0 +1,167 @@
+use std::fmt;
+
+/// A line address in a [Range](struct.Range.html), without any offsets.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Address {
+    /// An absolute line number, e.g. `42`.
+    Line(usize),
+    /// The current line, `.`.
+    Current,
+    /// The last line, `$`.
+    Last,
+    /// The whole file, `%`.
+    All,
+    /// The last visual area, `*`.
+    Visual,
+    /// A mark, e.g. `'a` or `'<`. Holds the name of the mark.
+    Mark(char),
+    /// A search, e.g. `/pattern/` or `?pattern?`. `pattern` is what is between the delimiters, as
+    /// written.
+    Search { pattern: String, backward: bool },
+    /// The next (`\/`) or previous (`\?`) match of the last search pattern, or the next match of
+    /// the last substitute pattern (`\&`). Holds the character after the backslash.
+    PreviousSearch(char),
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Address::Line(n) => write!(f, "{}", n),
+            Address::Current => write!(f, "."),
+            Address::Last => write!(f, "$"),
+            Address::All => write!(f, "%"),
+            Address::Visual => write!(f, "*"),
+            Address::Mark(c) => write!(f, "'{}", c),
+            Address::Search { pattern, backward } => {
+                let delimiter = if *backward { '?' } else { '/' };
+                write!(f, "{}{}{}", delimiter, pattern, delimiter)
+            }
+            Address::PreviousSearch(c) => write!(f, "\\{}", c),
+        }
+    }
+}
+
+/// An offset added to or subtracted from an address, e.g. `+3` or `-`. A count of `None` means
+/// one.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Offset {
+    Plus(Option<usize>),
+    Minus(Option<usize>),
+}
+
+impl Offset {
+    /// The offset as a number of lines, e.g. `-2` for `--` or `-2`.
+    pub fn lines(&self) -> isize {
+        match self {
+            Offset::Plus(n) => n.unwrap_or(1) as isize,
+            Offset::Minus(n) => -(n.unwrap_or(1) as isize),
+        }
+    }
+}
+
+impl fmt::Display for Offset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (sign, count) = match self {
+            Offset::Plus(n) => ('+', n),
+            Offset::Minus(n) => ('-', n),
+        };
+        write!(f, "{}", sign)?;
+        if let Some(n) = count {
+            write!(f, "{}", n)?;
+        }
+        Ok(())
+    }
+}
+
+/// One line specifier of a [Range](struct.Range.html): an optional address, any number of
+/// offsets and the separator following it, if there is one. `.+2;` has an address of `.`, one
+/// offset of `+2` and a separator of `;`. A specifier with no address is relative to the current
+/// line, like `+3`.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct LineSpec {
+    pub address: Option<Address>,
+    pub offsets: Vec<Offset>,
+    /// `,` or `;`. Consecutive searches like `/foo//bar/` have no separator between them.
+    pub separator: Option<char>,
+}
+
+impl fmt::Display for LineSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(address) = &self.address {
+            write!(f, "{}", address)?;
+        }
+        for offset in &self.offsets {
+            write!(f, "{}", offset)?;
+        }
+        if let Some(separator) = self.separator {
+            write!(f, "{}", separator)?;
+        }
+        Ok(())
+    }
+}
+
+/// The range given to a command, e.g. `1,$` in `1,$delete` or `'<,'>` in `'<,'>sort`. Ranges
+/// are written without any whitespace, so `1 , $` is displayed as `1,$`.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Range {
+    pub specs: Vec<LineSpec>,
+}
+
+impl Range {
+    /// Whether no range was given.
+    pub fn is_empty(&self) -> bool {
+        self.specs.is_empty()
+    }
+}
+
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for spec in &self.specs {
+            write!(f, "{}", spec)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let range = Range {
+            specs: vec![
+                LineSpec {
+                    address: Some(Address::Search {
+                        pattern: "foo\\/".to_string(),
+                        backward: false,
+                    }),
+                    offsets: vec![],
+                    separator: None,
+                },
+                LineSpec {
+                    address: Some(Address::Search {
+                        pattern: "bar".to_string(),
+                        backward: true,
+                    }),
+                    offsets: vec![Offset::Plus(Some(2)), Offset::Minus(None)],
+                    separator: Some(';'),
+                },
+                LineSpec {
+                    address: None,
+                    offsets: vec![Offset::Plus(Some(3))],
+                    separator: Some(','),
+                },
+                LineSpec {
+                    address: Some(Address::Mark('>')),
+                    offsets: vec![],
+                    separator: None,
+                },
+            ],
+        };
+        assert_eq!(range.to_string(), "/foo\\//?bar?+2-;+3,'>");
+        assert_eq!(Offset::Minus(None).lines(), -1);
+        assert_eq!(Offset::Plus(Some(4)).lines(), 4);
+        assert!(Range::default().is_empty());
+    }
+}
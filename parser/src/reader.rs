@@ -1,11 +1,145 @@
-use super::{CharClassification, Position, EOF, EOL};
-use std::{cell::RefCell, cmp::min, fs::File, io::prelude::*};
+use super::{node::Trivia, CharClassification, Position, EOF, EOL};
+use std::{
+    cell::RefCell,
+    cmp::min,
+    collections::HashMap,
+    fs::File,
+    io::{prelude::*, BufReader},
+    path::Path,
+};
+
+const BYTE_ORDER_MARK: char = '\u{feff}';
+
+fn is_continuation(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.starts_with('\\') || trimmed.starts_with("\"\\ ")
+}
+
+/// Whether lines starting with a backslash continue the line before them, which they don't while
+/// 'cpoptions' has the `C` flag. Vim checks the flag as each line is read, so a script can change
+/// it part way through; the usual case is a plugin that saves 'cpoptions' and sets it to Vim's
+/// default while it is sourced, then restores it:
+///
+/// ```vim
+/// let s:save_cpo = &cpo
+/// set cpo&vim
+/// " ...
+/// let &cpo = s:save_cpo
+/// ```
+///
+/// The reader can't run the script, so it follows the commands that change 'cpoptions' in the
+/// order they are written, whether or not they would run: `set cpo...` (including `&vim` and
+/// `&vi`), `let &cpo ...` with a string or a variable it has seen a copy of 'cpoptions' saved in,
+/// and `vim9script`, which always allows continuation lines.
+#[derive(Debug, Clone)]
+struct Continuations {
+    enabled: bool,
+    initial: bool,
+    saved: HashMap<String, bool>,
+}
+
+impl Continuations {
+    fn new(cpoptions: &str) -> Self {
+        let enabled = !cpoptions.contains('C');
+        Self {
+            enabled,
+            initial: enabled,
+            saved: HashMap::new(),
+        }
+    }
+
+    /// Change whether continuation lines are allowed by a value given to 'cpoptions' with an
+    /// operator of `set` or `let`.
+    fn assign(&mut self, op: &str, value: &str) {
+        let has_c = value.contains('C');
+        match op {
+            "=" | ":" => self.enabled = !has_c,
+            "+=" | "^=" | ".=" | "..=" if has_c => self.enabled = false,
+            "-=" if has_c => self.enabled = true,
+            _ => (),
+        }
+    }
+
+    /// Follow the commands in a line (after joining any continuation lines) that change
+    /// 'cpoptions'.
+    fn update(&mut self, line: &str) {
+        for command in line.split('|') {
+            let command = command.trim_start_matches([' ', '\t', ':']).trim_end();
+            let (name, args) = command
+                .split_once(char::is_whitespace)
+                .unwrap_or((command, ""));
+            match name {
+                "vim9script" | "vim9s" => self.enabled = true,
+                "set" | "se" | "setglobal" | "setg" => {
+                    for arg in args.split_whitespace() {
+                        self.set(arg);
+                    }
+                }
+                "let" => self.let_(args),
+                _ => (),
+            }
+        }
+    }
+
+    fn set(&mut self, arg: &str) {
+        let rest = match ["cpoptions", "cpo"]
+            .iter()
+            .find_map(|o| arg.strip_prefix(o))
+        {
+            Some(rest) => rest,
+            None => return,
+        };
+        match rest {
+            "&" | "&vim" => self.enabled = true,
+            "&vi" => self.enabled = false,
+            _ => {
+                if let Some(op) = ["+=", "-=", "^=", "=", ":"]
+                    .iter()
+                    .find(|op| rest.starts_with(**op))
+                {
+                    self.assign(op, &rest[op.len()..]);
+                }
+            }
+        }
+    }
+
+    fn let_(&mut self, args: &str) {
+        let (target, op, value) = match ["..=", ".=", "+=", "-=", "="]
+            .iter()
+            .filter_map(|op| args.split_once(op).map(|(t, v)| (t.trim(), *op, v.trim())))
+            .min_by_key(|(target, _, _)| target.len())
+        {
+            Some(parts) => parts,
+            None => return,
+        };
+        let is_cpo = |s: &str| {
+            let name = s.strip_prefix('&').unwrap_or("");
+            let name = name.strip_prefix("g:").unwrap_or(name);
+            name == "cpo" || name == "cpoptions"
+        };
+        if is_cpo(target) {
+            match value.chars().next() {
+                Some(quote @ ('\'' | '"')) => {
+                    let value = value[1..].split(quote).next().unwrap_or("");
+                    self.assign(op, value);
+                }
+                _ if op == "=" => {
+                    self.enabled = self.saved.get(value).copied().unwrap_or(self.initial);
+                }
+                _ => (),
+            }
+        } else if op == "=" && is_cpo(value) {
+            self.saved.insert(target.to_string(), self.enabled);
+        }
+    }
+}
 
 #[derive(Debug, PartialEq)]
 pub struct Reader {
     buf: Vec<char>,
     pos: Vec<(usize, usize)>,
     cursor: RefCell<usize>,
+    trivia: Vec<Trivia>,
 }
 
 impl Reader {
@@ -14,6 +148,7 @@ impl Reader {
             buf: vec![],
             pos: vec![],
             cursor: RefCell::new(0),
+            trivia: vec![],
         }
     }
 
@@ -22,31 +157,82 @@ impl Reader {
     }
 
     pub fn from_lines(lines: &[&str]) -> Self {
+        Self::from_lines_with_cpoptions(lines, "")
+    }
+
+    /// Read lines as Vim would when sourcing them with 'cpoptions' starting as `cpoptions`,
+    /// which only matters if it has the `C` flag (see `Continuations`).
+    pub fn from_lines_with_cpoptions(lines: &[&str], cpoptions: &str) -> Self {
         let mut reader = Reader::new();
-        reader.set_lines(lines);
+        reader.set_lines(lines, Continuations::new(cpoptions));
         reader
     }
 
-    pub fn from_file(path: &str) -> std::io::Result<Self> {
-        let mut reader = Reader::new();
-        reader.read_file(path)?;
-        Ok(reader)
+    // a byte order mark at the start of the file is removed and kept as trivia, as it is at
+    // the start of the first line given to from_lines
+    pub fn from_file<P: AsRef<Path>>(path: P, cpoptions: &str) -> std::io::Result<Self> {
+        Self::from_reader(BufReader::new(File::open(path)?), cpoptions)
+    }
+
+    pub fn from_reader<R: BufRead>(reader: R, cpoptions: &str) -> std::io::Result<Self> {
+        let lines = reader.lines().collect::<std::io::Result<Vec<String>>>()?;
+        Ok(Self::from_lines_with_cpoptions(
+            &lines.iter().map(String::as_str).collect::<Vec<&str>>(),
+            cpoptions,
+        ))
     }
 
-    fn set_lines(&mut self, lines: &[&str]) {
+    fn set_lines(&mut self, lines: &[&str], mut continuations: Continuations) {
         let mut col;
         let mut lnum = 0;
         while lnum < lines.len() {
             col = 0;
-            for c in lines[lnum].chars() {
+            let mut line = lines[lnum];
+            if lnum == 0 {
+                if let Some(rest) = line.strip_prefix(BYTE_ORDER_MARK) {
+                    self.trivia.push(Trivia::ByteOrderMark {
+                        pos: Position {
+                            cursor: 0,
+                            line: 1,
+                            col: 1,
+                        },
+                    });
+                    line = rest;
+                }
+            }
+            let start = self.buf.len();
+            for c in line.chars() {
                 self.buf.push(c);
                 self.pos.push((lnum + 1, col + 1));
                 col += 1;
             }
-            while lnum + 1 < lines.len() && lines[lnum + 1].trim_start().starts_with('\\') {
+            while continuations.enabled
+                && lnum + 1 < lines.len()
+                && is_continuation(lines[lnum + 1])
+            {
                 let line = lines[lnum + 1];
                 let trimmed = line.trim_start();
                 col = line.len() - trimmed.len() + 1;
+                if trimmed.starts_with('"') {
+                    // a comment inside the continuation sequence - keep it out of the buffer
+                    self.trivia.push(Trivia::ContinuationComment {
+                        pos: Position {
+                            cursor: self.buf.len(),
+                            line: lnum + 2,
+                            col,
+                        },
+                        value: trimmed[2..].to_string(),
+                    });
+                    lnum += 1;
+                    continue;
+                }
+                self.trivia.push(Trivia::LineContinuation {
+                    pos: Position {
+                        cursor: self.buf.len(),
+                        line: lnum + 2,
+                        col,
+                    },
+                });
                 for c in trimmed[1..].chars() {
                     self.buf.push(c);
                     self.pos.push((lnum + 2, col + 1));
@@ -54,6 +240,7 @@ impl Reader {
                 }
                 lnum += 1;
             }
+            continuations.update(&self.buf[start..].iter().collect::<String>());
             self.buf.push(EOL);
             self.pos.push((lnum + 1, col + 1));
             lnum += 1;
@@ -61,12 +248,8 @@ impl Reader {
         self.pos.push((lnum + 1, 0)); // eof
     }
 
-    fn read_file(&mut self, path: &str) -> std::io::Result<()> {
-        let mut file = File::open(path)?;
-        let mut content = String::new();
-        file.read_to_string(&mut content)?;
-        self.set_lines(&content.lines().collect::<Vec<&str>>());
-        Ok(())
+    pub fn trivia(&self) -> &[Trivia] {
+        &self.trivia
     }
 
     pub fn seek_set(&self, i: usize) {
@@ -224,6 +407,11 @@ impl Reader {
         self.read_base(|c| !c.is_whitespace())
     }
 
+    /// Read up to whitespace or a bar, like an autocommand group name.
+    pub fn read_nonwhitespace_or_bar(&self) -> String {
+        self.read_base(|c| !c.is_whitespace() && c != '|')
+    }
+
     pub fn read_name(&self) -> String {
         self.read_base(|c| c.is_name())
     }
@@ -241,6 +429,37 @@ impl Reader {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_continuations() {
+        let mut continuations = Continuations::new("aABceFsC");
+        assert!(!continuations.enabled);
+        for (line, enabled) in &[
+            ("let s:save_cpo = &cpo | set cpo&vim", true),
+            ("let s:compat = &cpoptions", true),
+            ("set cpo+=C", false),
+            ("set nocompatible cpo-=C", true),
+            ("let &cpo .= 'C'", false),
+            ("let &g:cpoptions = 'aABceFs'", true),
+            ("let &cpo = s:save_cpo", false),
+            ("let &cpo = s:compat", true),
+            (":set cpo&vi", false),
+            ("let &cpo = unknown", false),
+            ("vim9script", true),
+            ("\" set cpo+=C", true),
+        ] {
+            continuations.update(line);
+            assert_eq!(continuations.enabled, *enabled, "{}", line);
+        }
+        let reader = Reader::from_lines_with_cpoptions(
+            &["echo 1", "\\ + 2", "set cpo-=C", "echo 1", "\\ + 2"],
+            "C",
+        );
+        assert_eq!(
+            reader.buf.iter().collect::<String>(),
+            "echo 1\n\\ + 2\nset cpo-=C\necho 1 + 2\n"
+        );
+    }
+
     #[test]
     fn test_peek_ahead() {
         let reader = Reader::from_lines(&["foo", "bar"]);
@@ -456,4 +675,72 @@ endfunction"#;
         println!("reader buf length -> {}", reader.buf.len());
         println!("reader pos length -> {}", reader.pos.len());
     }
+
+    #[test]
+    fn test_continuation_comments() {
+        let reader = Reader::from_lines(&[
+            "let foo = [",
+            "      \\ 'bar',",
+            "      \"\\ a comment",
+            "      \\ 'baz',",
+            "      \\ ]",
+            "\"\\this is not a continuation comment",
+        ]);
+        assert_eq!(reader.get_line(), "let foo = [ 'bar', 'baz', ]".to_string());
+        assert_eq!(
+            reader.trivia(),
+            &[
+                Trivia::LineContinuation {
+                    pos: Position {
+                        cursor: 11,
+                        line: 2,
+                        col: 7,
+                    },
+                },
+                Trivia::ContinuationComment {
+                    pos: Position {
+                        cursor: 18,
+                        line: 3,
+                        col: 7,
+                    },
+                    value: " a comment".to_string(),
+                },
+                Trivia::LineContinuation {
+                    pos: Position {
+                        cursor: 18,
+                        line: 4,
+                        col: 7,
+                    },
+                },
+                Trivia::LineContinuation {
+                    pos: Position {
+                        cursor: 25,
+                        line: 5,
+                        col: 7,
+                    },
+                },
+            ]
+        );
+        reader.get();
+        assert_eq!(reader.get_line(), "\"\\this is not a continuation comment");
+    }
+
+    #[test]
+    fn test_byte_order_mark() {
+        let reader = Reader::from_lines(&["\u{feff}let foo = 1", "\u{feff}"]);
+        assert_eq!(reader.get_line(), "let foo = 1");
+        assert_eq!(reader.getpos().col, 12);
+        reader.get();
+        assert_eq!(reader.get_line(), "\u{feff}");
+        assert_eq!(
+            reader.trivia(),
+            &[Trivia::ByteOrderMark {
+                pos: Position {
+                    cursor: 0,
+                    line: 1,
+                    col: 1,
+                },
+            }]
+        );
+    }
 }
@@ -0,0 +1,202 @@
+use crate::{
+    command::CommandMap, node::BinaryOpKind, parser::Result, Command, ErrorCode, Node, ParseError,
+    Position,
+};
+use std::fmt;
+
+/// The editor a script is meant to run in. A [Parser](struct.Parser.html) given a target with
+/// [with_target](struct.Parser.html#method.with_target) rejects commands, autocommand events and
+/// expression syntax the target doesn't have. Vim targets are releases without any later
+/// patches, so `Vim82` is Vim 8.2.0.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+pub enum Target {
+    Vim74,
+    Vim82,
+    Vim9,
+    NeovimStable,
+    /// The development version of Neovim, which has everything the stable version does.
+    NeovimNightly,
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Target::Vim74 => write!(f, "Vim 7.4"),
+            Target::Vim82 => write!(f, "Vim 8.2"),
+            Target::Vim9 => write!(f, "Vim 9"),
+            Target::NeovimStable => write!(f, "Neovim"),
+            Target::NeovimNightly => write!(f, "Neovim nightly"),
+        }
+    }
+}
+
+/// The first Vim and Neovim targets to have a feature, if any do.
+#[derive(Clone, Copy)]
+pub(crate) struct Since {
+    pub(crate) vim: Option<Target>,
+    pub(crate) neovim: Option<Target>,
+}
+
+const EVERY_TARGET: Since = Since {
+    vim: Some(Target::Vim74),
+    neovim: Some(Target::NeovimStable),
+};
+
+const AFTER_VIM74: Since = Since {
+    vim: Some(Target::Vim82),
+    neovim: Some(Target::NeovimStable),
+};
+const AFTER_VIM82: Since = Since {
+    vim: Some(Target::Vim9),
+    neovim: Some(Target::NeovimStable),
+};
+const VIM_ONLY: Since = Since {
+    vim: Some(Target::Vim74),
+    neovim: None,
+};
+const VIM_ONLY_AFTER_VIM74: Since = Since {
+    vim: Some(Target::Vim82),
+    neovim: None,
+};
+const VIM9_ONLY: Since = Since {
+    vim: Some(Target::Vim9),
+    neovim: None,
+};
+const NEOVIM_ONLY: Since = Since {
+    vim: None,
+    neovim: Some(Target::NeovimStable),
+};
+
+// `fn command_since(name: &str) -> Option<Since>`, the first targets to have the commands that
+// aren't in every target, by full name, generated by build.rs from commands.csv.
+include!(concat!(env!("OUT_DIR"), "/command_since.rs"));
+
+/// The first targets to have an autocommand event, by canonical name.
+pub(crate) fn event_since(name: &str) -> Since {
+    match name {
+        "CmdlineChanged" | "CmdlineEnter" | "CmdlineLeave" | "ColorSchemePre"
+        | "CompleteChanged" | "CompleteDonePre" | "DiffUpdated" | "DirChanged" | "ExitPre"
+        | "OptionSet" | "SourcePost" | "TabClosed" | "TabNew" | "TextChangedP" | "TextYankPost"
+        | "WinNew" => AFTER_VIM74,
+        "BufModifiedSet" | "DirChangedPre" | "InsertLeavePre" | "ModeChanged" | "TextChangedT"
+        | "WinClosed" | "WinResized" | "WinScrolled" => AFTER_VIM82,
+        "SafeState" | "SafeStateAgain" | "TerminalOpen" | "TerminalWinOpen" => VIM_ONLY_AFTER_VIM74,
+        "ChanInfo" | "ChanOpen" | "DiagnosticChanged" | "LspAttach" | "LspDetach" | "LspNotify"
+        | "LspProgress" | "LspRequest" | "LspTokenUpdate" | "RecordingEnter" | "RecordingLeave"
+        | "SearchWrapped" | "Signal" | "TabNewEntered" | "TermClose" | "TermEnter"
+        | "TermLeave" | "TermOpen" | "TermRequest" | "UIEnter" | "UILeave" => NEOVIM_ONLY,
+        _ => EVERY_TARGET,
+    }
+}
+
+impl Target {
+    pub(crate) fn has(self, since: Since) -> bool {
+        let first = match self {
+            Target::Vim74 | Target::Vim82 | Target::Vim9 => since.vim,
+            Target::NeovimStable | Target::NeovimNightly => since.neovim,
+        };
+        first.is_some_and(|first| first <= self)
+    }
+
+    /// Whether the target has a command, given its full name. Commands added by plugins or
+    /// with [with_commands](struct.Parser.html#method.with_commands) are not known to it and
+    /// count as present.
+    pub fn has_command(self, name: &str) -> bool {
+        command_since(name).is_none_or(|since| self.has(since))
+    }
+
+    /// Whether the target has an autocommand event, given its canonical name (see
+    /// [canonical_autocmd_event](fn.canonical_autocmd_event.html)).
+    pub fn has_event(self, name: &str) -> bool {
+        self.has(event_since(name))
+    }
+
+    /// The built-in commands the target has.
+    pub fn commands(self) -> Vec<Command> {
+        crate::command::command_vec()
+            .into_iter()
+            .filter(|command| self.has_command(&command.name))
+            .collect()
+    }
+
+    /// Remove the commands the target doesn't have from a command table.
+    pub(crate) fn restrict_commands(self, commands: &mut CommandMap) {
+        commands.retain(|command| self.has_command(&command.name));
+    }
+
+    fn unsupported(self, what: &str, pos: Position) -> ParseError {
+        ParseError::new(
+            ErrorCode::UnsupportedByTarget,
+            format!("{} not supported by {}", what, self),
+            pos,
+        )
+    }
+
+    /// Check a node, and everything in it, for autocommand events and expression syntax the
+    /// target doesn't have.
+    pub(crate) fn check(self, node: &Node) -> Result<()> {
+        match node {
+            Node::Autocmd { events, pos, .. } => {
+                for event in events {
+                    let canonical = crate::canonical_autocmd_event(event).unwrap_or(event);
+                    if !self.has_event(canonical) {
+                        return Err(ParseError::new(
+                            ErrorCode::NoSuchGroupOrEvent,
+                            format!("E216: No such group or event: {}", event),
+                            *pos,
+                        )
+                        .with_found(event));
+                    }
+                }
+            }
+            Node::Lambda { pos, .. } if !self.has(AFTER_VIM74) => {
+                return Err(self.unsupported("lambdas are", *pos));
+            }
+            Node::Method { pos, .. } if !self.has(AFTER_VIM74) => {
+                return Err(self.unsupported("method calls (`->`) are", *pos));
+            }
+            Node::BinaryOp { pos, op, .. } if !self.has(AFTER_VIM74) => match op {
+                BinaryOpKind::StringConcat => {
+                    return Err(self.unsupported("`..` is", *pos));
+                }
+                BinaryOpKind::Falsy => return Err(self.unsupported("`??` is", *pos)),
+                _ => (),
+            },
+            _ => (),
+        }
+        for child in node.children() {
+            self.check(child)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_has() {
+        assert!(Target::Vim82.has_command("terminal"));
+        assert!(!Target::Vim74.has_command("terminal"));
+        assert!(!Target::NeovimStable.has_command("vim9script"));
+        assert!(Target::NeovimNightly.has_command("wshada"));
+        assert!(!Target::Vim9.has_command("wshada"));
+        assert!(Target::Vim74.has_command("echo"));
+        assert!(Target::NeovimStable.has_event("TermOpen"));
+        assert!(!Target::Vim9.has_event("TermOpen"));
+        assert!(!Target::Vim82.has_event("WinScrolled"));
+        assert!(Target::Vim9.has_event("WinScrolled"));
+        assert!(Target::Vim74.has_event("BufEnter"));
+        let names = |target: Target| {
+            target
+                .commands()
+                .iter()
+                .map(|c| c.name().to_string())
+                .collect::<Vec<String>>()
+        };
+        assert!(names(Target::Vim9).contains(&"vim9script".to_string()));
+        assert!(!names(Target::NeovimStable).contains(&"vim9script".to_string()));
+        assert!(names(Target::NeovimStable).contains(&"echo".to_string()));
+    }
+}
@@ -1,4 +1,4 @@
-use super::{CharClassification, ParseError, Position, EOF, EOL};
+use super::{CharClassification, ErrorCode, ParseError, Position, EOF, EOL};
 use crate::{parser::Result, reader::Reader};
 use std::collections::HashMap;
 
@@ -13,6 +13,7 @@ pub enum TokenKind {
     Comma,
     DQuote,
     Dot,
+    DotDot,
     DotDotDot,
     EOF,
     EOL,
@@ -60,6 +61,7 @@ pub enum TokenKind {
     Percent,
     Plus,
     Question,
+    QuestionQuestion,
     Reg,
     SQuote,
     Semicolon,
@@ -261,6 +263,9 @@ impl<'a> Tokenizer<'a> {
                 if self.reader.peekn(3) == "..." {
                     return Ok(Token::new(TokenKind::DotDotDot, self.reader.getn(3), pos));
                 }
+                if self.reader.peekn(2) == ".." {
+                    return Ok(Token::new(TokenKind::DotDot, self.reader.getn(2), pos));
+                }
                 Ok(Token::new(
                     TokenKind::Dot,
                     self.reader.get().to_string(),
@@ -287,11 +292,20 @@ impl<'a> Tokenizer<'a> {
                 self.reader.get().to_string(),
                 pos,
             )),
-            '?' => Ok(Token::new(
-                TokenKind::Question,
-                self.reader.get().to_string(),
-                pos,
-            )),
+            '?' => {
+                if self.reader.peek_ahead(1) == '?' {
+                    return Ok(Token::new(
+                        TokenKind::QuestionQuestion,
+                        self.reader.getn(2),
+                        pos,
+                    ));
+                }
+                Ok(Token::new(
+                    TokenKind::Question,
+                    self.reader.get().to_string(),
+                    pos,
+                ))
+            }
             ':' => Ok(Token::new(
                 TokenKind::Colon,
                 self.reader.get().to_string(),
@@ -383,10 +397,12 @@ impl<'a> Tokenizer<'a> {
                 self.reader.get().to_string(),
                 pos,
             )),
-            _ => Err(ParseError {
-                msg: format!("unexpected character: {}", c),
+            _ => Err(ParseError::new(
+                ErrorCode::UnexpectedCharacter,
+                format!("unexpected character: {}", c),
                 pos,
-            }),
+            )
+            .with_found(c)),
         }
     }
 
@@ -394,20 +410,23 @@ impl<'a> Tokenizer<'a> {
         self.reader.skip_white();
         let c = self.reader.peek();
         if c != '\'' {
-            return Err(ParseError {
-                msg: format!("unexpected character: {}", c),
-                pos: self.reader.getpos(),
-            });
+            return Err(ParseError::new(
+                ErrorCode::UnexpectedCharacter,
+                format!("unexpected character: {}", c),
+                self.reader.getpos(),
+            )
+            .with_found(c));
         }
         self.reader.get();
         let mut value = String::new();
         loop {
             let c = self.reader.peek();
             if c == EOF || c == EOL {
-                return Err(ParseError {
-                    msg: "unexpected EOL".to_string(),
-                    pos: self.reader.getpos(),
-                });
+                return Err(ParseError::new(
+                    ErrorCode::UnexpectedEol,
+                    "unexpected EOL".to_string(),
+                    self.reader.getpos(),
+                ));
             }
             if c == '\'' {
                 self.reader.get();
@@ -428,20 +447,23 @@ impl<'a> Tokenizer<'a> {
         self.reader.skip_white();
         let c = self.reader.peek();
         if c != '"' {
-            return Err(ParseError {
-                msg: format!("unexpected character: {}", c),
-                pos: self.reader.getpos(),
-            });
+            return Err(ParseError::new(
+                ErrorCode::UnexpectedCharacter,
+                format!("unexpected character: {}", c),
+                self.reader.getpos(),
+            )
+            .with_found(c));
         }
         self.reader.get();
         let mut value = String::new();
         loop {
             let c = self.reader.peek();
             if c == EOF || c == EOL {
-                return Err(ParseError {
-                    msg: "unexpected EOL".to_string(),
-                    pos: self.reader.getpos(),
-                });
+                return Err(ParseError::new(
+                    ErrorCode::UnexpectedEol,
+                    "unexpected EOL".to_string(),
+                    self.reader.getpos(),
+                ));
             }
             if c == '"' {
                 self.reader.get();
@@ -450,10 +472,11 @@ impl<'a> Tokenizer<'a> {
                 value.push(self.reader.get());
                 let c = self.reader.peek();
                 if c == EOF || c == EOL {
-                    return Err(ParseError {
-                        msg: "unexpected EOL".to_string(),
-                        pos: self.reader.getpos(),
-                    });
+                    return Err(ParseError::new(
+                        ErrorCode::UnexpectedEol,
+                        "unexpected EOL".to_string(),
+                        self.reader.getpos(),
+                    ));
                 }
                 value.push(self.reader.get());
             } else {
@@ -954,7 +977,7 @@ mod tests {
 
     #[test]
     fn test_get_dot_or_ellipsis() {
-        let reader = Reader::from_lines(&["... ."]);
+        let reader = Reader::from_lines(&["... . .."]);
         let mut tokenizer = Tokenizer::new(&reader);
         assert_eq!(
             tokenizer.get(),
@@ -972,6 +995,36 @@ mod tests {
                 Position::new(4, 1, 5)
             ))
         );
+        assert_eq!(
+            tokenizer.get(),
+            Ok(Token::new(
+                TokenKind::DotDot,
+                "..".to_string(),
+                Position::new(6, 1, 7)
+            ))
+        );
+    }
+
+    #[test]
+    fn test_get_question_or_falsy() {
+        let reader = Reader::from_lines(&["?? ?"]);
+        let mut tokenizer = Tokenizer::new(&reader);
+        assert_eq!(
+            tokenizer.get(),
+            Ok(Token::new(
+                TokenKind::QuestionQuestion,
+                "??".to_string(),
+                Position::new(0, 1, 1)
+            ))
+        );
+        assert_eq!(
+            tokenizer.get(),
+            Ok(Token::new(
+                TokenKind::Question,
+                "?".to_string(),
+                Position::new(3, 1, 4)
+            ))
+        );
     }
 
     #[test]
@@ -1226,10 +1279,12 @@ mod tests {
         let mut tokenizer = Tokenizer::new(&reader);
         assert_eq!(
             tokenizer.get(),
-            Err(ParseError {
-                msg: "unexpected character: ^".to_string(),
-                pos: Position::new(0, 1, 1)
-            })
+            Err(ParseError::new(
+                ErrorCode::UnexpectedCharacter,
+                "unexpected character: ^".to_string(),
+                Position::new(0, 1, 1)
+            )
+            .with_found('^'))
         );
     }
 
@@ -0,0 +1,856 @@
+//! Optional passes that rewrite an AST into an equivalent, simpler one. None of these are applied
+//! by the parser itself; callers opt in by running them over the result of
+//! [parse_lines](../fn.parse_lines.html) or [parse_file](../fn.parse_file.html).
+
+use crate::{
+    node::{BinaryOpKind, UnaryOpKind},
+    option::{option_type, split_option, OptionScope, OptionType},
+    parse_lines, Node, Position,
+};
+
+/// Simplify [Execute](../enum.Node.html#variant.Execute) nodes whose arguments are made up of
+/// string literals. Adjacent literals in a concatenation are merged, so
+/// ```text
+/// execute 'set ' . 'ft=' . ft
+/// ```
+/// becomes
+/// ```text
+/// execute 'set ft=' . ft
+/// ```
+/// and if the whole argument is a single literal that parses as a command, the `execute` is
+/// replaced with that command, so `execute 'set ft=vim'` becomes `set ft=vim`. Only
+/// single-quoted strings and double-quoted strings without escape sequences are considered
+/// literals. The node passed in is consumed and the simplified node is returned.
+pub fn simplify_execute(node: Node) -> Node {
+    match node {
+        Node::TopLevel { pos, body, trivia } => Node::TopLevel {
+            pos,
+            body: simplify_body(body),
+            trivia,
+        },
+        Node::Autocmd {
+            pos,
+            mods,
+            bang,
+            group,
+            events,
+            patterns,
+            nested,
+            body,
+        } => Node::Autocmd {
+            pos,
+            mods,
+            bang,
+            group,
+            events,
+            patterns,
+            nested,
+            body: simplify_body(body),
+        },
+        Node::Catch {
+            pos,
+            mods,
+            pattern,
+            body,
+        } => Node::Catch {
+            pos,
+            mods,
+            pattern,
+            body: simplify_body(body),
+        },
+        Node::Else { pos, mods, body } => Node::Else {
+            pos,
+            mods,
+            body: simplify_body(body),
+        },
+        Node::ElseIf {
+            pos,
+            mods,
+            cond,
+            body,
+        } => Node::ElseIf {
+            pos,
+            mods,
+            cond,
+            body: simplify_body(body),
+        },
+        Node::Finally { pos, mods, body } => Node::Finally {
+            pos,
+            mods,
+            body: simplify_body(body),
+        },
+        Node::For {
+            pos,
+            mods,
+            var,
+            list,
+            rest,
+            right,
+            body,
+            end,
+        } => Node::For {
+            pos,
+            mods,
+            var,
+            list,
+            rest,
+            right,
+            body: simplify_body(body),
+            end,
+        },
+        Node::Function {
+            pos,
+            mods,
+            bang,
+            name,
+            args,
+            body,
+            attrs,
+            end,
+        } => Node::Function {
+            pos,
+            mods,
+            bang,
+            name,
+            args,
+            body: simplify_body(body),
+            attrs,
+            end,
+        },
+        Node::Global {
+            pos,
+            mods,
+            range,
+            bang,
+            cmd,
+            delimiter,
+            pattern,
+            body,
+        } => Node::Global {
+            pos,
+            mods,
+            range,
+            bang,
+            cmd,
+            delimiter,
+            pattern,
+            body: simplify_body(body),
+        },
+        Node::If {
+            pos,
+            mods,
+            cond,
+            elseifs,
+            else_,
+            body,
+            end,
+        } => Node::If {
+            pos,
+            mods,
+            cond,
+            elseifs: elseifs.into_iter().map(simplify_execute).collect(),
+            else_: else_.map(|e| Box::new(simplify_execute(*e))),
+            body: simplify_body(body),
+            end,
+        },
+        Node::Try {
+            pos,
+            mods,
+            body,
+            catches,
+            finally,
+            end,
+        } => Node::Try {
+            pos,
+            mods,
+            body: simplify_body(body),
+            catches: catches.into_iter().map(simplify_execute).collect(),
+            finally: finally.map(|f| Box::new(simplify_execute(*f))),
+            end,
+        },
+        Node::While {
+            pos,
+            mods,
+            body,
+            cond,
+            end,
+        } => Node::While {
+            pos,
+            mods,
+            body: simplify_body(body),
+            cond,
+            end,
+        },
+        _ => node,
+    }
+}
+
+fn simplify_body(body: Vec<Node>) -> Vec<Node> {
+    let mut ret = vec![];
+    for node in body {
+        match node {
+            Node::Execute { pos, mods, list } => {
+                let list = simplify_execute_list(list);
+                if mods.is_empty() {
+                    if let Some(nodes) = list_as_commands(&list) {
+                        ret.extend(nodes);
+                        continue;
+                    }
+                }
+                ret.push(Node::Execute { pos, mods, list });
+            }
+            _ => ret.push(simplify_execute(node)),
+        }
+    }
+    ret
+}
+
+/// A literal string, as it would be seen by Vim after evaluation.
+#[derive(Debug, PartialEq)]
+struct Literal {
+    value: String,
+    quote: char,
+}
+
+impl Literal {
+    fn from_node(node: &Node) -> Option<Self> {
+        match node {
+            Node::String { value, .. } => {
+                let quote = value.chars().next()?;
+                let inner = &value[1..value.len() - 1];
+                match quote {
+                    '\'' => Some(Self {
+                        value: inner.replace("''", "'"),
+                        quote,
+                    }),
+                    '"' if !inner.contains('\\') => Some(Self {
+                        value: inner.to_string(),
+                        quote,
+                    }),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Append another literal, keeping this one's quotes unless the result can't be written
+    /// with them.
+    fn join(&mut self, other: Literal) {
+        self.value.push_str(&other.value);
+        if self.value.contains('"') || self.value.contains('\\') {
+            self.quote = '\'';
+        }
+    }
+
+    fn to_node(&self, pos: Position) -> Node {
+        let value = if self.quote == '"' {
+            format!("\"{}\"", self.value)
+        } else {
+            format!("'{}'", self.value.replace('\'', "''"))
+        };
+        Node::String { pos, value }
+    }
+}
+
+enum Part {
+    Literal(Literal),
+    Expr(Node),
+}
+
+fn flatten_concat(node: Node, parts: &mut Vec<Part>) {
+    match node {
+        Node::BinaryOp {
+            op: BinaryOpKind::Concat,
+            left,
+            right,
+            ..
+        }
+        | Node::BinaryOp {
+            op: BinaryOpKind::StringConcat,
+            left,
+            right,
+            ..
+        } => {
+            flatten_concat(*left, parts);
+            flatten_concat(*right, parts);
+        }
+        _ => match Literal::from_node(&node) {
+            Some(lit) => match parts.last_mut() {
+                Some(Part::Literal(prev)) => prev.join(lit),
+                _ => parts.push(Part::Literal(lit)),
+            },
+            None => parts.push(Part::Expr(node)),
+        },
+    }
+}
+
+fn simplify_concat(node: Node) -> Node {
+    let pos = node.pos();
+    let op = match &node {
+        Node::BinaryOp {
+            op: BinaryOpKind::StringConcat,
+            ..
+        } => BinaryOpKind::StringConcat,
+        _ => BinaryOpKind::Concat,
+    };
+    let mut parts = vec![];
+    flatten_concat(node, &mut parts);
+    let mut nodes = parts.into_iter().map(|part| match part {
+        Part::Literal(lit) => lit.to_node(pos),
+        Part::Expr(node) => node,
+    });
+    let first = nodes.next().unwrap();
+    nodes.fold(first, |left, right| Node::BinaryOp {
+        pos,
+        op: op.clone(),
+        left: Box::new(left),
+        right: Box::new(right),
+    })
+}
+
+fn simplify_execute_list(list: Vec<Node>) -> Vec<Node> {
+    // `execute` joins its arguments with spaces, so adjacent literal arguments can be folded into
+    // one.
+    let mut ret: Vec<Node> = vec![];
+    for node in list.into_iter().map(simplify_concat) {
+        let prev = ret.last().and_then(Literal::from_node);
+        match (prev, Literal::from_node(&node)) {
+            (Some(mut prev), Some(lit)) => {
+                let pos = ret.pop().unwrap().pos();
+                prev.join(Literal {
+                    value: " ".to_string(),
+                    quote: prev.quote,
+                });
+                prev.join(lit);
+                ret.push(prev.to_node(pos));
+            }
+            _ => ret.push(node),
+        }
+    }
+    ret
+}
+
+fn list_as_commands(list: &[Node]) -> Option<Vec<Node>> {
+    if list.len() != 1 {
+        return None;
+    }
+    let lit = Literal::from_node(&list[0])?;
+    match parse_lines(&[&lit.value]) {
+        Ok(Node::TopLevel { body, .. }) => {
+            if body.is_empty()
+                || body
+                    .iter()
+                    .any(|n| matches!(n, Node::Comment { .. } | Node::BlankLine { .. }))
+            {
+                None
+            } else {
+                Some(body)
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Evaluate constant expressions: arithmetic on integer literals, e.g. `4 * 1024` becomes
+/// `4096`, and concatenation of string and integer literals, e.g. `'a' . 'b'` becomes `'ab'`.
+/// A folded string keeps the quotes of its first string operand where it can, and is
+/// single-quoted if there is none, e.g. `1 .. 2` becomes `'12'`.
+/// Anything whose value could depend on how Vim is built or which script syntax is used is left
+/// alone, namely floats, numbers with a leading zero (octal in legacy script, decimal in Vim9
+/// script), division or remainder by zero and results that don't fit in a 64-bit Number. Strings
+/// are only folded when they are literals as [simplify_execute](fn.simplify_execute.html)
+/// understands them. The node passed in is consumed and the folded node is returned.
+pub fn const_fold(mut node: Node) -> Node {
+    fold(&mut node);
+    node
+}
+
+/// Turn an `if` nested alone in the `else` clause of another `if` into `elseif` clauses of the
+/// outer one, so
+/// ```text
+/// if a
+///   echo 1
+/// else
+///   if b
+///     echo 2
+///   endif
+/// endif
+/// ```
+/// becomes
+/// ```text
+/// if a
+///   echo 1
+/// elseif b
+///   echo 2
+/// endif
+/// ```
+/// An `else` is only rewritten if the nested `if` is the only thing in it, with no other
+/// statements, comments or blank lines before or after it, since those would have nowhere to
+/// go. The node passed in is consumed and the rewritten node is returned.
+pub fn flatten_else_if(mut node: Node) -> Node {
+    flatten(&mut node);
+    node
+}
+
+fn flatten(node: &mut Node) {
+    for child in node.children_mut() {
+        flatten(child);
+    }
+    if let Node::If { elseifs, else_, .. } = node {
+        let nested = match else_.as_deref_mut() {
+            Some(Node::Else { mods, body, .. }) if mods.is_empty() && body.len() == 1 => {
+                match &body[0] {
+                    Node::If { mods, .. } if mods.is_empty() => body.pop(),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        if let Some(Node::If {
+            pos,
+            cond,
+            elseifs: nested_elseifs,
+            else_: nested_else,
+            body,
+            ..
+        }) = nested
+        {
+            elseifs.push(Node::ElseIf {
+                pos,
+                mods: vec![],
+                cond,
+                body,
+            });
+            elseifs.extend(nested_elseifs);
+            *else_ = nested_else;
+        }
+    }
+}
+
+/// Whether to set options with `set` or with `let &`, for
+/// [normalize_option_style](fn.normalize_option_style.html).
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum OptionStyle {
+    /// `set`, keeping `let` for values that are computed, so `let &l:ts = 4` becomes
+    /// `setlocal ts=4` and `let &wrap = 0` becomes `set nowrap`.
+    Set,
+    /// `let &`, so `setlocal ts=4` becomes `let &l:ts = 4` and `set nowrap` becomes
+    /// `let &wrap = 0`.
+    Let,
+}
+
+/// Rewrite statements that set one of Vim's options to a literal value in the given style. Only
+/// statements that mean the same thing either way are rewritten: a `let` of `0` or `1` to a
+/// boolean option, of a decimal number to a number option (also with `+=` and `-=`), or of a
+/// string without spaces, backslashes, `"` or `|` to a string option, and a `set` of one such
+/// option. The node passed in is consumed and the rewritten node is returned.
+pub fn normalize_option_style(mut node: Node, style: OptionStyle) -> Node {
+    restyle(&mut node, style);
+    node
+}
+
+fn restyle(node: &mut Node, style: OptionStyle) {
+    for child in node.children_mut() {
+        restyle(child, style);
+    }
+    let restyled = match style {
+        OptionStyle::Set => let_as_set(node),
+        OptionStyle::Let => set_as_let(node),
+    };
+    if let Some(restyled) = restyled {
+        *node = restyled;
+    }
+}
+
+fn is_decimal(value: &str) -> bool {
+    // a leading zero makes a number octal in legacy script
+    (value == "0" || !value.starts_with('0')) && value.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn let_as_set(node: &Node) -> Option<Node> {
+    let (pos, mods, var, op, right) = match node {
+        Node::Let {
+            pos,
+            mods,
+            cmd,
+            var: Some(var),
+            op,
+            right,
+            ..
+        } if cmd == "let" => (*pos, mods, var, op.as_str(), right.as_ref()),
+        _ => return None,
+    };
+    let (scope, name) = match var.as_ref() {
+        Node::Option { value, .. } => split_option(value),
+        _ => return None,
+    };
+    let arg = match (option_type(name)?, op, right) {
+        (OptionType::Boolean, "=", Node::Number { value, .. }) if value == "0" => {
+            (format!("no{}", name), String::new(), String::new())
+        }
+        (OptionType::Boolean, "=", Node::Number { value, .. }) if value == "1" => {
+            (name.to_string(), String::new(), String::new())
+        }
+        (OptionType::Number, "=" | "+=" | "-=", Node::Number { value, .. })
+            if is_decimal(value) =>
+        {
+            (name.to_string(), op.to_string(), value.to_string())
+        }
+        (OptionType::String, "=", Node::String { .. }) => {
+            let value = Literal::from_node(right)?.value;
+            if value.contains(|c: char| c.is_whitespace() || "\\\"|".contains(c)) {
+                return None;
+            }
+            (name.to_string(), op.to_string(), value)
+        }
+        _ => return None,
+    };
+    let cmd = match scope {
+        OptionScope::Default => "set",
+        OptionScope::Global => "setglobal",
+        OptionScope::Local => "setlocal",
+    };
+    Some(Node::Set {
+        pos,
+        mods: mods.clone(),
+        cmd: cmd.to_string(),
+        bang: false,
+        args: vec![arg],
+    })
+}
+
+fn set_as_let(node: &Node) -> Option<Node> {
+    let (pos, mods, cmd, (option, op, value)) = match node {
+        Node::Set {
+            pos,
+            mods,
+            cmd,
+            bang: false,
+            args,
+        } if args.len() == 1 => (*pos, mods, cmd, &args[0]),
+        _ => return None,
+    };
+    let scope = match cmd.as_str() {
+        "set" => "&",
+        "setglobal" => "&g:",
+        "setlocal" => "&l:",
+        _ => return None,
+    };
+    let (name, op, right) = match (op.as_str(), option_type(option)) {
+        ("", Some(OptionType::Boolean)) => (option.as_str(), "=", number_node(1, pos)),
+        ("", None) => match option.strip_prefix("no") {
+            Some(name) if option_type(name) == Some(OptionType::Boolean) => {
+                (name, "=", number_node(0, pos))
+            }
+            _ => return None,
+        },
+        ("=" | ":" | "+=" | "-=", Some(OptionType::Number)) if is_decimal(value) => (
+            option.as_str(),
+            if op == ":" { "=" } else { op },
+            Node::Number {
+                pos,
+                value: value.clone(),
+            },
+        ),
+        ("=" | ":", Some(OptionType::String)) if !value.contains('\\') => {
+            let literal = Literal {
+                value: value.clone(),
+                quote: '\'',
+            };
+            (option.as_str(), "=", literal.to_node(pos))
+        }
+        _ => return None,
+    };
+    Some(Node::Let {
+        pos,
+        mods: mods.clone(),
+        cmd: "let".to_string(),
+        var: Some(Box::new(Node::Option {
+            pos,
+            value: format!("{}{}", scope, name),
+        })),
+        list: vec![],
+        rest: None,
+        right: Box::new(right),
+        op: op.to_string(),
+    })
+}
+
+fn fold(node: &mut Node) {
+    for child in node.children_mut() {
+        fold(child);
+    }
+    if let Some(folded) = evaluate(node) {
+        *node = folded;
+    }
+}
+
+fn evaluate(node: &Node) -> Option<Node> {
+    let pos = node.pos();
+    match node {
+        // negative numbers keep their parentheses, since in e.g. `(-1)->abs()` they matter
+        Node::ParenExpr { expr, .. } => match **expr {
+            Node::Number { .. } | Node::String { .. } => Some((**expr).clone()),
+            _ => None,
+        },
+        Node::UnaryOp { op, right, .. } => {
+            let n = match right.as_ref() {
+                // a negated literal is as folded as it gets
+                Node::Number { .. } if *op == UnaryOpKind::Minus => return None,
+                right => number(right)?,
+            };
+            let value = match op {
+                UnaryOpKind::Minus => n.checked_neg()?,
+                UnaryOpKind::Plus => n,
+                UnaryOpKind::Not => (n == 0) as i64,
+            };
+            folded_number(value, pos)
+        }
+        Node::BinaryOp {
+            op, left, right, ..
+        } => match op {
+            BinaryOpKind::Concat | BinaryOpKind::StringConcat => {
+                let (mut left, right) = match (Literal::from_node(left), Literal::from_node(right))
+                {
+                    (Some(l), Some(r)) => (l, r),
+                    (Some(l), None) => {
+                        let r = number_literal(right, l.quote)?;
+                        (l, r)
+                    }
+                    (None, Some(r)) => (number_literal(left, r.quote)?, r),
+                    (None, None) => (number_literal(left, '\'')?, number_literal(right, '\'')?),
+                };
+                left.join(right);
+                Some(left.to_node(pos))
+            }
+            _ => {
+                let (l, r) = (number(left)?, number(right)?);
+                let value = match op {
+                    BinaryOpKind::Add => l.checked_add(r)?,
+                    BinaryOpKind::Subtract => l.checked_sub(r)?,
+                    BinaryOpKind::Multiply => l.checked_mul(r)?,
+                    // Vim gives a huge number rather than an error when dividing by zero
+                    BinaryOpKind::Divide if r != 0 => l.checked_div(r)?,
+                    BinaryOpKind::Remainder if r != 0 => l.checked_rem(r)?,
+                    _ => return None,
+                };
+                folded_number(value, pos)
+            }
+        },
+        _ => None,
+    }
+}
+
+/// The value of an integer literal, possibly negated or in parentheses.
+fn number(node: &Node) -> Option<i64> {
+    match node {
+        Node::Number { value, .. } => {
+            let lower = value.to_lowercase();
+            let (digits, radix) = if let Some(hex) = lower.strip_prefix("0x") {
+                (hex, 16)
+            } else if let Some(binary) = lower.strip_prefix("0b") {
+                (binary, 2)
+            } else if let Some(octal) = lower.strip_prefix("0o") {
+                (octal, 8)
+            } else if lower.len() > 1 && lower.starts_with('0') {
+                return None;
+            } else {
+                (lower.as_str(), 10)
+            };
+            i64::from_str_radix(digits, radix).ok()
+        }
+        Node::UnaryOp {
+            op: UnaryOpKind::Minus,
+            right,
+            ..
+        } => match right.as_ref() {
+            Node::Number { .. } => number(right)?.checked_neg(),
+            _ => None,
+        },
+        Node::ParenExpr { expr, .. } => number(expr),
+        _ => None,
+    }
+}
+
+fn number_literal(node: &Node, quote: char) -> Option<Literal> {
+    number(node).map(|n| Literal {
+        value: n.to_string(),
+        quote,
+    })
+}
+
+/// The node for a number a constant expression was folded to, or `None` if it is `i64::MIN`,
+/// which can't be written back: Vim reads the digits of `-9223372036854775808` as the largest
+/// number, so that is `-9223372036854775807`.
+fn folded_number(value: i64, pos: Position) -> Option<Node> {
+    (value != i64::MIN).then(|| number_node(value, pos))
+}
+
+fn number_node(value: i64, pos: Position) -> Node {
+    let number = Node::Number {
+        pos,
+        value: value.unsigned_abs().to_string(),
+    };
+    if value < 0 {
+        Node::UnaryOp {
+            pos,
+            op: UnaryOpKind::Minus,
+            right: Box::new(number),
+        }
+    } else {
+        number
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn simplified(lines: &[&str]) -> String {
+        format!("{}", simplify_execute(parse_lines(lines).unwrap()))
+    }
+
+    #[test]
+    fn test_simplify_execute_merges_literals() {
+        assert_eq!(
+            simplified(&["execute 'set ' . 'ft=' . ft . ' ' . \"x\""]),
+            "(execute (concat (concat 'set ft=' ft) ' x'))"
+        );
+        assert_eq!(
+            simplified(&["execute \"normal\" \"gg\" x"]),
+            "(execute \"normal gg\" x)"
+        );
+        assert_eq!(
+            simplified(&["silent execute 'set' 'ft=vim'"]),
+            "(execute 'set ft=vim')"
+        );
+    }
+
+    #[test]
+    fn test_simplify_execute_inlines_commands() {
+        assert_eq!(simplified(&["execute 'set ft=' . 'vim'"]), "(set ft=vim)");
+        assert_eq!(
+            simplified(&["if x", "  execute \"let foo = 1\" '| echo foo'", "endif"]),
+            "(if x\n  (let = foo 1)\n  (echo foo))"
+        );
+        assert_eq!(simplified(&["execute 'echo \"\\n\"'"]), "(echo \"\\n\")");
+    }
+
+    #[test]
+    fn test_const_fold() {
+        let folded = |expr: &str| {
+            format!(
+                "{}",
+                const_fold(parse_lines(&[&format!("let x = {}", expr)]).unwrap())
+            )
+        };
+        assert_eq!(folded("4 * 1024"), "(let = x 4096)");
+        assert_eq!(folded("'a' . 'b' .. 1"), "(let = x 'ab1')");
+        assert_eq!(folded("\"a\" . 1"), "(let = x \"a1\")");
+        assert_eq!(folded("\"a\" .. 1"), "(let = x \"a1\")");
+        assert_eq!(folded("1 . \"a\" . 'b'"), "(let = x \"1ab\")");
+        assert_eq!(folded("1 . 2"), "(let = x '12')");
+        assert_eq!(folded("1 .. 2"), "(let = x '12')");
+        assert_eq!(folded("\"a\" . '\\'"), "(let = x 'a\\')");
+        assert_eq!(folded("(1 + 2) * 0x10 - 0b1"), "(let = x 47)");
+        assert_eq!(folded("2 * (3 - 5)"), "(let = x (- 4))");
+        let method = const_fold(crate::parse_expression("(1 - 2)->abs()").unwrap());
+        assert!(
+            matches!(method, Node::Method { ref left, .. } if matches!(**left, Node::ParenExpr { .. }))
+        );
+        assert_eq!(folded("-7 / 2 + -7 % 2 + !0"), "(let = x (- 3))");
+        assert_eq!(folded("010 + 1"), "(let = x (+ 010 1))");
+        assert_eq!(folded("1 / 0"), "(let = x (/ 1 0))");
+        assert_eq!(folded("1.5 * 2"), "(let = x (* 1.5 2))");
+        assert_eq!(
+            folded("9223372036854775807 + 1"),
+            "(let = x (+ 9223372036854775807 1))"
+        );
+        assert_eq!(
+            folded("-9223372036854775807 - 1"),
+            "(let = x (- (- 9223372036854775807) 1))"
+        );
+        assert_eq!(
+            folded("-(-9223372036854775807 - 1)"),
+            "(let = x (- (- (- 9223372036854775807) 1)))"
+        );
+        assert_eq!(folded("y + 1 + 2"), "(let = x (+ (+ y 1) 2))");
+    }
+
+    #[test]
+    fn test_simplify_execute_leaves_non_literals() {
+        assert_eq!(
+            simplified(&["execute \"normal \\<C-w>\" . 'j'"]),
+            "(execute (concat \"normal \\<C-w>\" 'j'))"
+        );
+        assert_eq!(simplified(&["execute 'endif'"]), "(execute 'endif')");
+        assert_eq!(simplified(&["execute cmd"]), "(execute cmd)");
+    }
+
+    #[test]
+    fn test_flatten_else_if() {
+        let flattened =
+            |lines: &[&str]| format!("{}", flatten_else_if(parse_lines(lines).unwrap()));
+        assert_eq!(
+            flattened(&[
+                "if a",
+                "  echo 1",
+                "else",
+                "  if b",
+                "    echo 2",
+                "  elseif c",
+                "    echo 3",
+                "  else | if d | echo 4 | else | echo 5 | endif",
+                "  endif",
+                "endif",
+            ]),
+            flattened(&[
+                "if a", "  echo 1", "elseif b", "  echo 2", "elseif c", "  echo 3", "elseif d",
+                "  echo 4", "else", "  echo 5", "endif",
+            ])
+        );
+        let kept = ["if a", "else", "  if b", "  endif", "  echo 1", "endif"];
+        assert_eq!(flattened(&kept), format!("{}", parse_lines(&kept).unwrap()));
+        let commented = ["if a", "else", "  \" why", "  if b", "  endif", "endif"];
+        assert_eq!(
+            flattened(&commented),
+            format!("{}", parse_lines(&commented).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_normalize_option_style() {
+        let restyled = |style, lines: &[&str]| {
+            format!(
+                "{}",
+                normalize_option_style(parse_lines(lines).unwrap(), style)
+            )
+        };
+        let lets = [
+            "let &cp = 0",
+            "silent! let &l:wrap = 1",
+            "let &g:ts += 2",
+            "let &ft = 'vim'",
+            "let &sw = 010",
+            "let &tw = x",
+            "let &fo = 'a b'",
+            "let &t_Co = 256",
+        ];
+        let sets = [
+            "set nocp",
+            "silent! setlocal wrap",
+            "setglobal ts+=2",
+            "set ft=vim",
+            "let &sw = 010",
+            "let &tw = x",
+            "let &fo = 'a b'",
+            "let &t_Co = 256",
+        ];
+        let parsed = |lines: &[&str]| format!("{}", parse_lines(lines).unwrap());
+        assert_eq!(restyled(OptionStyle::Set, &lets), parsed(&sets));
+        assert_eq!(restyled(OptionStyle::Let, &sets), parsed(&lets));
+        let kept = ["set nocp ts=4", "set ts^=2", "set wrap!", "set fo=a\\ b"];
+        assert_eq!(restyled(OptionStyle::Let, &kept), parsed(&kept));
+    }
+}
@@ -0,0 +1,19 @@
+" Format Vim script with vimlfmt. See autoload/vimlfmt.vim for the settings.
+if exists('g:loaded_vimlfmt')
+  finish
+endif
+let g:loaded_vimlfmt = 1
+
+command! -range=% -bar VimlFmt call vimlfmt#format(<line1>, <line2>)
+
+augroup vimlfmt
+  autocmd!
+  autocmd FileType vim
+        \ if get(g:, 'vimlfmt_formatexpr', 1) |
+        \   setlocal formatexpr=vimlfmt#formatexpr() |
+        \ endif
+  autocmd BufWritePre *
+        \ if &filetype ==# 'vim' && get(g:, 'vimlfmt_format_on_save', 0) |
+        \   call vimlfmt#format(1, line('$')) |
+        \ endif
+augroup END
@@ -0,0 +1,1009 @@
+//! Static analysis of parsed VimL.
+use crate::{diagnostic::display_path, json};
+use std::{
+    collections::HashSet,
+    fmt,
+    path::{Path, PathBuf},
+};
+use viml_parser::{canonical_key_notation, split_keys, BinaryOpKind, Key, Node, Position};
+
+/// How a call site refers to the function it calls.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Reference {
+    /// A call, e.g. `Foo()`, `call Foo()` or `x->Foo()`.
+    Call,
+    /// A string naming the function, passed to `call()`, `function()` or `funcref()`, e.g.
+    /// `function('s:Foo')`.
+    Name,
+}
+
+/// A function defined in the script.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Definition {
+    pub name: String,
+    pub pos: Position,
+}
+
+/// A reference to a user-defined function.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CallSite {
+    /// The function the call is in, or `None` if it is at the script level.
+    pub caller: Option<String>,
+    pub callee: String,
+    pub pos: Position,
+    pub reference: Reference,
+}
+
+/// The functions a script defines and the calls made to user-defined functions, which are those
+/// whose names start with an uppercase letter or contain `:`, `#` or `.`. Calls to builtin functions
+/// aren't included. Names are normalized, so `<SID>Foo` is `s:Foo` and `g:Foo` is `Foo`.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct CallGraph {
+    pub definitions: Vec<Definition>,
+    pub calls: Vec<CallSite>,
+}
+
+impl CallGraph {
+    /// The functions that are never referred to, except by themselves. Global and autoload
+    /// functions may still be called from other scripts, mappings or commands, so only
+    /// script-local functions (`s:`) are certainly dead.
+    pub fn unreferenced(&self) -> Vec<&Definition> {
+        self.definitions
+            .iter()
+            .filter(|d| {
+                !self
+                    .calls
+                    .iter()
+                    .any(|c| c.callee == d.name && c.caller.as_ref() != Some(&d.name))
+            })
+            .collect()
+    }
+
+    /// The graph as JSON, e.g.
+    /// `{"functions":[{"name":"s:Foo","line":1,"column":1}],"calls":[{"caller":null,
+    /// "callee":"s:Foo","line":3,"column":6,"reference":"call"}]}`.
+    pub fn to_json(&self) -> String {
+        let functions = self
+            .definitions
+            .iter()
+            .map(|d| {
+                format!(
+                    "{{\"name\":{},\"line\":{},\"column\":{}}}",
+                    json::string(&d.name),
+                    d.pos.line(),
+                    d.pos.column()
+                )
+            })
+            .collect::<Vec<String>>();
+        let calls = self
+            .calls
+            .iter()
+            .map(|c| {
+                format!(
+                    "{{\"caller\":{},\"callee\":{},\"line\":{},\"column\":{},\"reference\":{}}}",
+                    c.caller
+                        .as_ref()
+                        .map_or("null".to_string(), |c| json::string(c)),
+                    json::string(&c.callee),
+                    c.pos.line(),
+                    c.pos.column(),
+                    json::string(match c.reference {
+                        Reference::Call => "call",
+                        Reference::Name => "name",
+                    })
+                )
+            })
+            .collect::<Vec<String>>();
+        format!(
+            "{{\"functions\":[{}],\"calls\":[{}]}}",
+            functions.join(","),
+            calls.join(",")
+        )
+    }
+
+    /// The graph in Graphviz's DOT language. Calls made at the script level come from a node
+    /// named `<script>`, and references by name are drawn dashed.
+    pub fn to_dot(&self) -> String {
+        let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+        let mut rv = String::from("digraph calls {\n");
+        for definition in &self.definitions {
+            rv.push_str(&format!("    {};\n", quote(&definition.name)));
+        }
+        let mut edges = vec![];
+        for call in &self.calls {
+            let edge = format!(
+                "    {} -> {}{};\n",
+                quote(call.caller.as_deref().unwrap_or("<script>")),
+                quote(&call.callee),
+                match call.reference {
+                    Reference::Call => "",
+                    Reference::Name => " [style=dashed]",
+                }
+            );
+            if !edges.contains(&edge) {
+                edges.push(edge);
+            }
+        }
+        rv.push_str(&edges.concat());
+        rv.push('}');
+        rv
+    }
+}
+
+/// Find the functions defined in a script and the calls made to user-defined functions.
+pub fn call_graph(node: &Node) -> CallGraph {
+    let mut graph = CallGraph::default();
+    walk(node, None, &mut graph);
+    graph
+}
+
+fn walk(node: &Node, caller: Option<&str>, graph: &mut CallGraph) {
+    match node {
+        Node::Function { name, body, .. } => {
+            if let Some(name) = function_name(name) {
+                graph.definitions.push(Definition {
+                    name: name.clone(),
+                    pos: node.pos(),
+                });
+                for child in body {
+                    walk(child, Some(&name), graph);
+                }
+                return;
+            }
+        }
+        Node::Call { name, args, pos } => {
+            if let Some(callee) = function_name(name) {
+                if is_user_function(&callee) {
+                    graph.calls.push(CallSite {
+                        caller: caller.map(str::to_string),
+                        callee: callee.clone(),
+                        pos: *pos,
+                        reference: Reference::Call,
+                    });
+                } else if ["call", "function", "funcref"].contains(&callee.as_str()) {
+                    if let Some(arg) = args.first() {
+                        if let Some(callee) = string_literal(arg).map(|s| normalize(&s)) {
+                            if is_user_function(&callee) {
+                                graph.calls.push(CallSite {
+                                    caller: caller.map(str::to_string),
+                                    callee,
+                                    pos: arg.pos(),
+                                    reference: Reference::Name,
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        _ => (),
+    }
+    for child in node.children() {
+        walk(child, caller, graph);
+    }
+}
+
+/// The name of a function being defined or called, if it is written literally, e.g. `s:Foo` or
+/// `s:dict.method`.
+fn function_name(node: &Node) -> Option<String> {
+    match node {
+        Node::Identifier { value, .. } => Some(normalize(value)),
+        Node::Dot { left, right, .. } => Some(format!(
+            "{}.{}",
+            function_name(left)?,
+            function_name(right)?
+        )),
+        _ => None,
+    }
+}
+
+fn normalize(name: &str) -> String {
+    if let Some(sid) = name.get(..5).filter(|p| p.eq_ignore_ascii_case("<sid>")) {
+        format!("s:{}", &name[sid.len()..])
+    } else if let Some(global) = name.strip_prefix("g:") {
+        global.to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+fn is_user_function(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_uppercase()) || name.contains([':', '#', '.'])
+}
+
+fn string_literal(node: &Node) -> Option<String> {
+    match node {
+        Node::String { value, .. } if value.len() >= 2 => {
+            let inner = &value[1..value.len() - 1];
+            match value.chars().next() {
+                Some('\'') => Some(inner.replace("''", "'")),
+                Some('"') if !inner.contains('\\') => Some(inner.to_string()),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// A variable of a function used by a function defined inside it, which can't see the variables
+/// of the function it is in without the `closure` attribute.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Capture {
+    /// The function using the variable.
+    pub function: String,
+    /// The variable as it is written, e.g. `x`, `l:x` or `a:arg`.
+    pub variable: String,
+    pub pos: Position,
+}
+
+/// Find the variables and arguments that functions defined in other functions, without the
+/// `closure` attribute, use from the functions they are in. Without the attribute these are the
+/// function's own variables, which are undefined unless it sets them.
+pub fn missing_closures(node: &Node) -> Vec<Capture> {
+    let mut rv = vec![];
+    find_captures(node, &HashSet::new(), &mut rv);
+    rv
+}
+
+fn find_captures(node: &Node, outer: &HashSet<String>, rv: &mut Vec<Capture>) {
+    if let Node::Function {
+        name,
+        args,
+        attrs,
+        body,
+        ..
+    } = node
+    {
+        let mut locals = HashSet::new();
+        for arg in args {
+            if let Node::Identifier { value, .. } = arg {
+                locals.insert(format!("a:{}", value));
+            }
+        }
+        for child in body {
+            local_variables(child, &mut locals);
+        }
+        if attrs.iter().any(|a| a == "closure") {
+            // a closure sees everything the function it is in does, and so do closures in it
+            locals.extend(outer.iter().cloned());
+        } else if !outer.is_empty() {
+            let mut used = vec![];
+            for child in body {
+                used_variables(child, &mut used);
+            }
+            for (variable, pos) in used {
+                let local = local_name(variable);
+                if outer.contains(&local) && !locals.contains(&local) {
+                    rv.push(Capture {
+                        function: function_name(name).unwrap_or_else(|| name.to_string()),
+                        variable: variable.to_string(),
+                        pos,
+                    });
+                }
+            }
+        }
+        for child in body {
+            find_captures(child, &locals, rv);
+        }
+        return;
+    }
+    for child in node.children() {
+        find_captures(child, outer, rv);
+    }
+}
+
+/// The name a function's variable has in its scope: `l:x` and `x` are both `x`, and the
+/// arguments of a function with `...` are all `a:...`. Other names are left as they are.
+fn local_name(variable: &str) -> String {
+    match variable.split_once(':') {
+        Some(("a", n)) if n.chars().all(|c| c.is_ascii_digit()) => "a:...".to_string(),
+        Some(("l", name)) => name.to_string(),
+        _ => variable.to_string(),
+    }
+}
+
+fn is_local(variable: &str) -> bool {
+    match variable.split_once(':') {
+        Some((scope, name)) => (scope == "l" || scope == "a") && !name.is_empty(),
+        None => true,
+    }
+}
+
+/// The variables a function sets, with `let` or `for`, or as the arguments of lambdas, without
+/// looking in the functions defined in it.
+fn local_variables(node: &Node, locals: &mut HashSet<String>) {
+    match node {
+        Node::Function { .. } => return,
+        Node::Let {
+            var, list, rest, ..
+        }
+        | Node::For {
+            var, list, rest, ..
+        } => {
+            for target in var
+                .iter()
+                .map(|v| &**v)
+                .chain(list)
+                .chain(rest.iter().map(|r| &**r))
+            {
+                if let Node::Identifier { value, .. } = target {
+                    if is_local(value) {
+                        locals.insert(local_name(value));
+                    }
+                }
+            }
+        }
+        Node::Lambda { args, .. } => {
+            for arg in args {
+                if let Node::Identifier { value, .. } = arg {
+                    locals.insert(value.clone());
+                }
+            }
+        }
+        _ => (),
+    }
+    for child in node.children() {
+        local_variables(child, locals);
+    }
+}
+
+/// The variables a function reads or sets, without looking in the functions defined in it.
+/// Names of functions and of dictionary keys after a dot aren't variables.
+fn used_variables<'a>(node: &'a Node, used: &mut Vec<(&'a str, Position)>) {
+    match node {
+        Node::Function { .. } => return,
+        Node::Identifier { value, pos } if is_local(value) => used.push((value, *pos)),
+        Node::Call { name, args, .. } => {
+            if !matches!(**name, Node::Identifier { .. }) {
+                used_variables(name, used);
+            }
+            for arg in args {
+                used_variables(arg, used);
+            }
+            return;
+        }
+        Node::Dot { left, .. } => {
+            used_variables(left, used);
+            return;
+        }
+        _ => (),
+    }
+    for child in node.children() {
+        used_variables(child, used);
+    }
+}
+
+/// Measurements of a function's size and complexity.
+#[derive(Debug, PartialEq, Clone)]
+pub struct FunctionMetrics {
+    pub name: String,
+    pub pos: Position,
+    /// The number of lines from the function's first line to its `endfunction`, inclusive.
+    pub lines: usize,
+    /// The cyclomatic complexity, which is one more than the number of branches: each `if`,
+    /// `elseif`, loop, `catch`, ternary and `&&`, `||` or `??` operator.
+    pub complexity: usize,
+    /// How deeply blocks (`if`, loops and `try`) are nested in the function. Zero if the body
+    /// has none.
+    pub depth: usize,
+}
+
+/// Measure every function defined in a script, including functions defined in other functions,
+/// which aren't counted towards the functions they are in.
+pub fn metrics(node: &Node) -> Vec<FunctionMetrics> {
+    let mut rv = vec![];
+    measure(node, &mut rv);
+    rv
+}
+
+fn measure(node: &Node, rv: &mut Vec<FunctionMetrics>) {
+    if let Node::Function {
+        name, body, end, ..
+    } = node
+    {
+        let pos = node.pos();
+        let mut function = FunctionMetrics {
+            name: function_name(name).unwrap_or_else(|| name.to_string()),
+            pos,
+            lines: end
+                .as_ref()
+                .map_or(1, |end| end.pos().line().saturating_sub(pos.line()) + 1),
+            complexity: 1,
+            depth: 0,
+        };
+        let index = rv.len();
+        rv.push(function.clone());
+        for child in body {
+            tally(child, 0, &mut function, rv);
+        }
+        rv[index] = function;
+        return;
+    }
+    for child in node.children() {
+        measure(child, rv);
+    }
+}
+
+fn tally(node: &Node, depth: usize, function: &mut FunctionMetrics, rv: &mut Vec<FunctionMetrics>) {
+    let depth = match node {
+        Node::Function { .. } => return measure(node, rv),
+        Node::If { .. } | Node::For { .. } | Node::While { .. } | Node::Try { .. } => depth + 1,
+        _ => depth,
+    };
+    function.depth = function.depth.max(depth);
+    match node {
+        Node::If { .. }
+        | Node::ElseIf { .. }
+        | Node::For { .. }
+        | Node::While { .. }
+        | Node::Catch { .. }
+        | Node::Ternary { .. }
+        | Node::BinaryOp {
+            op: BinaryOpKind::And | BinaryOpKind::Or | BinaryOpKind::Falsy,
+            ..
+        } => function.complexity += 1,
+        _ => (),
+    }
+    for child in node.children() {
+        tally(child, depth, function, rv);
+    }
+}
+
+/// Metrics as a table with a header row and a row for each function, e.g.
+///
+/// ```text
+/// FUNCTION  LINE  LINES  COMPLEXITY  DEPTH
+/// s:Foo        1     12           4      2
+/// ```
+pub fn metrics_table(metrics: &[FunctionMetrics]) -> String {
+    let header = ["FUNCTION", "LINE", "LINES", "COMPLEXITY", "DEPTH"];
+    let rows = metrics
+        .iter()
+        .map(|m| {
+            [
+                m.name.clone(),
+                m.pos.line().to_string(),
+                m.lines.to_string(),
+                m.complexity.to_string(),
+                m.depth.to_string(),
+            ]
+        })
+        .collect::<Vec<_>>();
+    let widths = (0..header.len())
+        .map(|i| {
+            rows.iter()
+                .map(|r| r[i].chars().count())
+                .chain(Some(header[i].len()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect::<Vec<_>>();
+    let mut rv = String::new();
+    let header = header.map(str::to_string);
+    for row in Some(&header).into_iter().chain(&rows) {
+        let mut line = format!("{:<1$}", row[0], widths[0]);
+        for i in 1..row.len() {
+            line.push_str(&format!("  {:>1$}", row[i], widths[i]));
+        }
+        rv.push_str(line.trim_end());
+        rv.push('\n');
+    }
+    rv
+}
+
+/// Metrics as a JSON array, e.g.
+/// `[{"name":"s:Foo","line":1,"lines":12,"complexity":4,"depth":2}]`.
+pub fn metrics_json(metrics: &[FunctionMetrics]) -> String {
+    let functions = metrics
+        .iter()
+        .map(|m| {
+            format!(
+                "{{\"name\":{},\"line\":{},\"lines\":{},\"complexity\":{},\"depth\":{}}}",
+                json::string(&m.name),
+                m.pos.line(),
+                m.lines,
+                m.complexity,
+                m.depth
+            )
+        })
+        .collect::<Vec<String>>();
+    format!("[{}]", functions.join(","))
+}
+
+/// The modes a mapping command maps keys in, as the letters `:map` lists them with: `n`
+/// (normal), `x` (visual), `s` (select), `o` (operator-pending), `i` (insert), `c`
+/// (command-line), `l` (language) and `t` (terminal). `command` is the command's full name, e.g.
+/// `nnoremap`, and `bang` whether it was given a `!`, as in `map!`.
+pub fn mapping_modes(command: &str, bang: bool) -> Vec<char> {
+    let prefix = command
+        .strip_suffix("noremap")
+        .or_else(|| command.strip_suffix("map"))
+        .unwrap_or(command);
+    match prefix {
+        "" if bang => vec!['i', 'c'],
+        "" => vec!['n', 'x', 's', 'o'],
+        "v" => vec!['x', 's'],
+        _ => prefix.chars().take(1).collect(),
+    }
+}
+
+/// A mapping defined in a script.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MappingSite {
+    pub path: PathBuf,
+    pub pos: Position,
+    /// The command, with its bang if it has one, e.g. `nnoremap` or `map!`.
+    pub command: String,
+    /// The keys mapped, with key notation in its canonical spelling, so `<c-w>` and `<C-W>` are
+    /// both `<C-w>`.
+    pub lhs: String,
+    /// What the keys are mapped to, with key notation in its canonical spelling. For an
+    /// `<expr>` mapping this is the expression as the syntax tree displays it, after `<expr>`.
+    pub rhs: String,
+    /// The modes the mapping is in, as [mapping_modes](fn.mapping_modes.html) gives them.
+    pub modes: Vec<char>,
+    /// Whether the mapping is local to a buffer (`<buffer>`).
+    pub buffer: bool,
+    // the `if` statements the mapping is in, and which of their clauses it is in
+    branches: Vec<(Position, usize)>,
+    // the function the mapping is defined in, if it is in one
+    function: Option<Position>,
+}
+
+impl MappingSite {
+    fn noremap(&self) -> bool {
+        self.command.contains("noremap")
+    }
+
+    /// Whether the two mappings can both be defined and map the same keys in some mode.
+    /// Mappings in different clauses of the same `if` can't both be defined. A mapping in a
+    /// function is only compared with the others in that function, as functions may be called
+    /// for different buffers or at different times, and buffer-local mappings are only compared
+    /// with those in the same file.
+    fn overlaps(&self, other: &MappingSite) -> bool {
+        let same_file = self.path == other.path;
+        if self.lhs != other.lhs
+            || self.buffer != other.buffer
+            || (self.buffer && !same_file)
+            || ((self.function.is_some() || other.function.is_some())
+                && (!same_file || self.function != other.function))
+            || !self.modes.iter().any(|m| other.modes.contains(m))
+        {
+            return false;
+        }
+        !same_file
+            || !self.branches.iter().any(|(pos, clause)| {
+                other
+                    .branches
+                    .iter()
+                    .any(|(other_pos, other_clause)| pos == other_pos && clause != other_clause)
+            })
+    }
+}
+
+/// Find every mapping a script defines. Commands that list mappings, e.g. `nmap <Leader>`, are
+/// left out.
+pub fn mapping_sites(path: &Path, node: &Node) -> Vec<MappingSite> {
+    let mut rv = vec![];
+    find_mappings(path, node, &mut vec![], None, &mut rv);
+    rv
+}
+
+fn find_mappings(
+    path: &Path,
+    node: &Node,
+    branches: &mut Vec<(Position, usize)>,
+    function: Option<Position>,
+    rv: &mut Vec<MappingSite>,
+) {
+    match node {
+        Node::Mapping {
+            pos,
+            command,
+            bang,
+            left,
+            right,
+            right_expr,
+            attrs,
+            ..
+        } => {
+            let rhs = match right_expr {
+                Some(expr) => format!("<expr> {}", expr),
+                None => canonical_keys(right),
+            };
+            if !left.is_empty() && !rhs.is_empty() {
+                rv.push(MappingSite {
+                    path: path.to_path_buf(),
+                    pos: *pos,
+                    command: format!("{}{}", command, if *bang { "!" } else { "" }),
+                    lhs: canonical_keys(left),
+                    rhs,
+                    modes: mapping_modes(command, *bang),
+                    buffer: attrs.iter().any(|a| a == "buffer"),
+                    branches: branches.clone(),
+                    function,
+                });
+            }
+        }
+        Node::If {
+            pos,
+            body,
+            elseifs,
+            else_,
+            ..
+        } => {
+            let clauses = std::iter::once(body)
+                .chain(elseifs.iter().filter_map(|elseif| match elseif {
+                    Node::ElseIf { body, .. } => Some(body),
+                    _ => None,
+                }))
+                .chain(else_.iter().filter_map(|else_| match &**else_ {
+                    Node::Else { body, .. } => Some(body),
+                    _ => None,
+                }));
+            for (clause, body) in clauses.enumerate() {
+                branches.push((*pos, clause));
+                for child in body {
+                    find_mappings(path, child, branches, function, rv);
+                }
+                branches.pop();
+            }
+        }
+        Node::Function { body, .. } => {
+            for child in body {
+                find_mappings(path, child, branches, Some(node.pos()), rv);
+            }
+        }
+        _ => {
+            for child in node.children() {
+                find_mappings(path, child, branches, function, rv);
+            }
+        }
+    }
+}
+
+/// Keys with key notation in its canonical spelling. Vim doesn't tell `<C-a>` from `<C-A>`, so
+/// the letter of a control key is made lowercase.
+fn canonical_keys(keys: &str) -> String {
+    split_keys(keys)
+        .into_iter()
+        .map(|key| match key {
+            Key::Text(text) => text,
+            Key::Special(inner) => {
+                let mut notation = canonical_key_notation(&inner);
+                if notation.len() == 3 && notation.starts_with("C-") {
+                    notation.make_ascii_lowercase();
+                    notation.replace_range(..1, "C");
+                }
+                format!("<{}>", notation)
+            }
+            Key::Plug { name, .. } => format!("<Plug>{}", name),
+            Key::Sid(_) => "<SID>".to_string(),
+            Key::Cmd { command, .. } => format!("<Cmd>{}<CR>", command),
+        })
+        .collect()
+}
+
+/// Whether a key has a built-in meaning in a mode, so that mapping it takes the meaning away.
+/// This is only checked for single keys: every printable character but `\` in normal, visual
+/// and operator-pending mode, and the control and special keys that do something in those modes
+/// and in insert and command-line mode.
+fn is_default_key(lhs: &str, mode: char) -> bool {
+    const NORMAL: &[&str] = &[
+        "<C-a>", "<C-b>", "<C-c>", "<C-d>", "<C-e>", "<C-f>", "<C-g>", "<C-h>", "<C-i>", "<C-j>",
+        "<C-l>", "<C-m>", "<C-n>", "<C-o>", "<C-p>", "<C-r>", "<C-t>", "<C-u>", "<C-v>", "<C-w>",
+        "<C-x>", "<C-y>", "<C-z>", "<C-]>", "<C-^>", "<CR>", "<BS>", "<Space>", "<Tab>", "<Del>",
+    ];
+    const INSERT: &[&str] = &[
+        "<C-a>", "<C-d>", "<C-e>", "<C-h>", "<C-k>", "<C-n>", "<C-o>", "<C-p>", "<C-r>", "<C-t>",
+        "<C-u>", "<C-v>", "<C-w>", "<C-x>", "<C-y>",
+    ];
+    const COMMAND_LINE: &[&str] = &[
+        "<C-a>", "<C-b>", "<C-d>", "<C-e>", "<C-h>", "<C-k>", "<C-n>", "<C-p>", "<C-r>", "<C-u>",
+        "<C-v>", "<C-w>",
+    ];
+    let mut chars = lhs.chars();
+    match (mode, chars.next(), chars.next()) {
+        ('n' | 'x' | 'o', Some(c), None) => c.is_ascii_graphic() && c != '\\',
+        ('n' | 'x' | 'o', ..) => NORMAL.contains(&lhs),
+        ('i', ..) => INSERT.contains(&lhs),
+        ('c', ..) => COMMAND_LINE.contains(&lhs),
+        _ => false,
+    }
+}
+
+/// What is wrong with a mapping.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum MappingIssueKind {
+    /// The same keys were already mapped to the same thing.
+    Duplicate,
+    /// The same keys were already mapped to something else, which this mapping replaces.
+    Conflict,
+    /// The keys have a built-in meaning, which the mapping takes away.
+    ShadowsDefault,
+}
+
+/// A problem with a mapping, found by [mapping_issues](fn.mapping_issues.html).
+#[derive(Debug, PartialEq, Clone)]
+pub struct MappingIssue {
+    pub kind: MappingIssueKind,
+    pub mapping: MappingSite,
+    /// The earlier mapping of the same keys, for a duplicate or conflict.
+    pub other: Option<MappingSite>,
+    /// The modes the problem is in.
+    pub modes: Vec<char>,
+}
+
+impl fmt::Display for MappingIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let site = |mapping: &MappingSite| {
+            format!(
+                "{}:{}:{}",
+                display_path(&mapping.path),
+                mapping.pos.line(),
+                mapping.pos.column()
+            )
+        };
+        let modes = self.modes.iter().collect::<String>();
+        write!(
+            f,
+            "{}: `{} {}` ",
+            site(&self.mapping),
+            self.mapping.command,
+            self.mapping.lhs
+        )?;
+        match (self.kind, &self.other) {
+            (MappingIssueKind::Duplicate, Some(other)) => write!(
+                f,
+                "repeats the mapping in mode {} at {}",
+                modes,
+                site(other)
+            ),
+            (MappingIssueKind::Conflict, Some(other)) => write!(
+                f,
+                "replaces the mapping to `{}` in mode {} at {}",
+                other.rhs,
+                modes,
+                site(other)
+            ),
+            _ => write!(f, "overrides a built-in command in mode {}", modes),
+        }
+    }
+}
+
+/// Compare the mappings of a project, from [mapping_sites](fn.mapping_sites.html) for each of
+/// its files, in the order the files are sourced. A mapping of keys that were already mapped in
+/// the same mode, and weren't in a different clause of the same `if`, is a duplicate if it maps
+/// them to the same thing the same way (with or without `noremap`) and a conflict if it doesn't;
+/// it is only compared with the first mapping it overlaps. Mappings of single keys that have a
+/// built-in meaning are reported too.
+pub fn mapping_issues(sites: &[MappingSite]) -> Vec<MappingIssue> {
+    let mut rv = vec![];
+    for (i, mapping) in sites.iter().enumerate() {
+        let shadowed = mapping
+            .modes
+            .iter()
+            .copied()
+            .filter(|&mode| is_default_key(&mapping.lhs, mode))
+            .collect::<Vec<char>>();
+        if !shadowed.is_empty() {
+            rv.push(MappingIssue {
+                kind: MappingIssueKind::ShadowsDefault,
+                mapping: mapping.clone(),
+                other: None,
+                modes: shadowed,
+            });
+        }
+        if let Some(other) = sites[..i].iter().find(|other| other.overlaps(mapping)) {
+            let kind = if other.rhs == mapping.rhs && other.noremap() == mapping.noremap() {
+                MappingIssueKind::Duplicate
+            } else {
+                MappingIssueKind::Conflict
+            };
+            rv.push(MappingIssue {
+                kind,
+                mapping: mapping.clone(),
+                other: Some(other.clone()),
+                modes: mapping
+                    .modes
+                    .iter()
+                    .copied()
+                    .filter(|m| other.modes.contains(m))
+                    .collect(),
+            });
+        }
+    }
+    rv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use viml_parser::parse_lines;
+
+    #[test]
+    fn test_call_graph() {
+        let node = parse_lines(&[
+            "function! s:Used() abort",
+            "  call s:Helper(len([]))",
+            "endfunction",
+            "function! s:Helper(n) abort",
+            "  return a:n ? s:Helper(a:n - 1) : foo#bar()",
+            "endfunction",
+            "function! s:Dead() abort",
+            "  return s:Dead()",
+            "endfunction",
+            "let s:F = function('<SID>Used')",
+        ])
+        .unwrap();
+        let graph = call_graph(&node);
+        assert_eq!(
+            graph
+                .calls
+                .iter()
+                .map(|c| (c.caller.as_deref(), c.callee.as_str(), c.reference))
+                .collect::<Vec<_>>(),
+            vec![
+                (Some("s:Used"), "s:Helper", Reference::Call),
+                (Some("s:Helper"), "s:Helper", Reference::Call),
+                (Some("s:Helper"), "foo#bar", Reference::Call),
+                (Some("s:Dead"), "s:Dead", Reference::Call),
+                (None, "s:Used", Reference::Name),
+            ]
+        );
+        assert_eq!(
+            graph
+                .unreferenced()
+                .iter()
+                .map(|d| d.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["s:Dead"]
+        );
+        assert_eq!(
+            graph.to_dot(),
+            concat!(
+                "digraph calls {\n",
+                "    \"s:Used\";\n",
+                "    \"s:Helper\";\n",
+                "    \"s:Dead\";\n",
+                "    \"s:Used\" -> \"s:Helper\";\n",
+                "    \"s:Helper\" -> \"s:Helper\";\n",
+                "    \"s:Helper\" -> \"foo#bar\";\n",
+                "    \"s:Dead\" -> \"s:Dead\";\n",
+                "    \"<script>\" -> \"s:Used\" [style=dashed];\n",
+                "}",
+            )
+        );
+        assert!(graph
+            .to_json()
+            .starts_with("{\"functions\":[{\"name\":\"s:Used\",\"line\":1,\"column\":1},"));
+    }
+
+    #[test]
+    fn test_mapping_modes() {
+        assert_eq!(mapping_modes("nnoremap", false), vec!['n']);
+        assert_eq!(mapping_modes("map", false), vec!['n', 'x', 's', 'o']);
+        assert_eq!(mapping_modes("noremap", true), vec!['i', 'c']);
+        assert_eq!(mapping_modes("vmap", false), vec!['x', 's']);
+        assert_eq!(mapping_modes("xnoremap", false), vec!['x']);
+        assert_eq!(mapping_modes("tmap", false), vec!['t']);
+    }
+
+    #[test]
+    fn test_mapping_issues() {
+        let plugin = parse_lines(&[
+            "nnoremap <silent> <Leader>f :Files<CR>",
+            "map <c-k> :Files<cr>",
+            "nnoremap <Leader>f :Files<cr>",
+            "if has('nvim')",
+            "  xnoremap <Leader>g :Grep<CR>",
+            "else",
+            "  xnoremap <Leader>g :Rg<CR>",
+            "endif",
+            "nnoremap Y y$",
+            "inoremap <C-a> <Home>",
+            "function! s:Setup() abort",
+            "  nnoremap <buffer> <Leader>f :Files<CR>",
+            "endfunction",
+        ])
+        .unwrap();
+        let ftplugin = parse_lines(&[
+            "nnoremap <buffer> <Leader>f :GFiles<CR>",
+            "nnoremap <C-K> :GFiles<CR>",
+        ])
+        .unwrap();
+        let mut sites = mapping_sites(Path::new("plugin/a.vim"), &plugin);
+        sites.extend(mapping_sites(Path::new("ftplugin/a.vim"), &ftplugin));
+        assert_eq!(sites.len(), 10);
+        assert_eq!(sites[1].lhs, "<C-k>");
+        assert_eq!(sites[1].rhs, ":Files<CR>");
+        let issues = mapping_issues(&sites);
+        assert_eq!(
+            issues.iter().map(|i| i.to_string()).collect::<Vec<_>>(),
+            vec![
+                "plugin/a.vim:3:1: `nnoremap <Leader>f` repeats the mapping in mode n at \
+                 plugin/a.vim:1:1",
+                "plugin/a.vim:9:1: `nnoremap Y` overrides a built-in command in mode n",
+                "plugin/a.vim:10:1: `inoremap <C-a>` overrides a built-in command in mode i",
+                "ftplugin/a.vim:2:1: `nnoremap <C-k>` replaces the mapping to `:Files<CR>` in \
+                 mode n at plugin/a.vim:2:1",
+            ]
+        );
+        assert_eq!(issues[3].kind, MappingIssueKind::Conflict);
+    }
+
+    #[test]
+    fn test_metrics() {
+        let node = parse_lines(&[
+            "function! s:Foo(x) abort",
+            "  if a:x && 1",
+            "    for i in range(3)",
+            "      echo i ? 1 : 2",
+            "    endfor",
+            "  elseif a:x",
+            "    function! s:Bar() abort",
+            "      return 1",
+            "    endfunction",
+            "  endif",
+            "endfunction",
+        ])
+        .unwrap();
+        let metrics = metrics(&node);
+        assert_eq!(
+            metrics
+                .iter()
+                .map(|m| (m.name.as_str(), m.lines, m.complexity, m.depth))
+                .collect::<Vec<_>>(),
+            vec![("s:Foo", 11, 6, 2), ("s:Bar", 3, 1, 0)]
+        );
+        assert_eq!(
+            metrics_table(&metrics),
+            concat!(
+                "FUNCTION  LINE  LINES  COMPLEXITY  DEPTH\n",
+                "s:Foo        1     11           6      2\n",
+                "s:Bar        7      3           1      0\n",
+            )
+        );
+        assert_eq!(
+            metrics_json(&metrics[1..]),
+            r#"[{"name":"s:Bar","line":7,"lines":3,"complexity":1,"depth":0}]"#
+        );
+    }
+
+    #[test]
+    fn test_missing_closures() {
+        let node = parse_lines(&[
+            "function! s:Outer(arg, ...) abort",
+            "  let count = 0",
+            "  let [first, second] = [1, 2]",
+            "  function! Inner(arg) abort",
+            "    let mine = 1",
+            "    return l:count + a:arg + mine + a:0 + map([], {first -> first})",
+            "  endfunction",
+            "  function! Closure() abort closure",
+            "    function! Deeper() abort closure",
+            "      return count + a:arg",
+            "    endfunction",
+            "    function! NotClosure() abort",
+            "      return second.key + len(g:second)",
+            "    endfunction",
+            "  endfunction",
+            "endfunction",
+            "function! Top() abort",
+            "  return count",
+            "endfunction",
+        ])
+        .unwrap();
+        assert_eq!(
+            missing_closures(&node)
+                .iter()
+                .map(|c| (c.function.as_str(), c.variable.as_str(), c.pos.line()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Inner", "l:count", 6),
+                ("Inner", "a:0", 6),
+                ("NotClosure", "second", 13),
+            ]
+        );
+    }
+}
@@ -0,0 +1,119 @@
+use crate::pipeline::Pipeline;
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// A record, kept on disk, of the files that are already formatted, so formatting them again can
+/// be skipped without even parsing them. Entries are keyed by a hash of a file's contents and a
+/// hash of the [Pipeline](../pipeline/struct.Pipeline.html) it was formatted with, so a file that
+/// changes, or a change to the parser options, transforms or configuration, is formatted again.
+/// Each entry is an empty file in the cache directory; the directory can be removed at any time
+/// to clear it.
+///
+/// Entries don't depend on a file's path, only on the pipeline given to [new](#method.new).
+/// `--write` formats every file with the configuration found from the current directory, not the
+/// one nearest each file, so neither the formatting nor the cache follows a configuration file in
+/// a subdirectory.
+pub struct Cache {
+    dir: PathBuf,
+    config_hash: u64,
+}
+
+/// The 64-bit FNV-1a hash of some text. Unlike the standard library's hashers, it doesn't change
+/// from one Rust release to the next, so the cache outlives rebuilding vimlfmt.
+fn hash(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+impl Cache {
+    /// A cache kept in `dir`, which is created when the first entry is added, for files
+    /// formatted with `pipeline`.
+    pub fn new(dir: &Path, pipeline: &Pipeline) -> Self {
+        // the maps in the configuration are sorted, so this doesn't depend on the order their
+        // entries were added in
+        let config_hash = hash(&format!("{} {:?}", env!("CARGO_PKG_VERSION"), pipeline));
+        Self {
+            dir: dir.to_path_buf(),
+            config_hash,
+        }
+    }
+
+    /// Where the cache is kept unless another directory is given: `vimlfmt` in
+    /// `$XDG_CACHE_HOME`, or in `~/.cache` if that isn't set.
+    pub fn default_dir() -> Option<PathBuf> {
+        env::var_os("XDG_CACHE_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+            .map(|dir| dir.join("vimlfmt"))
+    }
+
+    fn entry(&self, source: &str) -> PathBuf {
+        self.dir
+            .join(format!("{:016x}{:016x}", hash(source), self.config_hash))
+    }
+
+    /// Whether `source` is known to be formatted already.
+    pub fn is_formatted(&self, source: &str) -> bool {
+        self.entry(source).is_file()
+    }
+
+    /// Record that `source` is formatted, i.e. that formatting it doesn't change it.
+    pub fn mark_formatted(&self, source: &str) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.entry(source), "")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::FormatterConfig;
+    use std::process;
+    use viml_parser::Target;
+
+    #[test]
+    fn test_cache() {
+        // the hash mustn't change, or every cache would be invalidated
+        assert_eq!(hash(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hash("let x = 1\n"), 0xa73e_0c53_cfbe_bdc4);
+        let dir = env::temp_dir().join(format!("vimlfmt-test-cache-{}", process::id()));
+        let config = FormatterConfig::default();
+        let pipeline = Pipeline::new(config.clone());
+        let cache = Cache::new(&dir, &pipeline);
+        assert!(!cache.is_formatted("let x = 1\n"));
+        cache.mark_formatted("let x = 1\n").unwrap();
+        assert!(cache.is_formatted("let x = 1\n"));
+        assert!(!cache.is_formatted("let x = 2\n"));
+        assert!(Cache::new(&dir, &pipeline.clone()).is_formatted("let x = 1\n"));
+        let other = Pipeline::new(FormatterConfig {
+            space_in_braces: !config.space_in_braces,
+            ..config.clone()
+        });
+        assert!(!Cache::new(&dir, &other).is_formatted("let x = 1\n"));
+        let other = Pipeline {
+            target: Some(Target::Vim82),
+            ..pipeline.clone()
+        };
+        assert!(!Cache::new(&dir, &other).is_formatted("let x = 1\n"));
+        let keys = |keys: &[(&str, &str)]| {
+            Pipeline::new(FormatterConfig {
+                canonical_keys: keys
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+                ..config.clone()
+            })
+        };
+        let ordered = keys(&[("esc", "ESC"), ("cr", "CR")]);
+        Cache::new(&dir, &ordered)
+            .mark_formatted("let x = 1\n")
+            .unwrap();
+        let reversed = keys(&[("cr", "CR"), ("esc", "ESC")]);
+        assert!(Cache::new(&dir, &reversed).is_formatted("let x = 1\n"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,114 @@
+//! What the parser accepts, for editor plugins that complete command names, autocommand events
+//! and highlight keys and want to stay in sync with `vimlfmt`. The JSON form is an object like
+//!
+//! ```text
+//! {"commands": [...], "events": ["BufAdd", ...], "highlight_keys": ["cterm", ...]}
+//! ```
+//!
+//! where each command is an object like
+//!
+//! ```text
+//! {"name": "echo", "abbreviation": "ec", "bang": false, "range": false, "count": false,
+//!  "register": false, "argument": "optional"}
+//! ```
+//!
+//! and `argument` is one of `none`, `optional` or `required`.
+use crate::json;
+use viml_parser::{autocmd_events, commands, Command, Flag, HIGHLIGHT_KEYS};
+
+fn argument(command: &Command) -> &'static str {
+    if command.flags().contains(Flag::NEEDARG) {
+        "required"
+    } else if command.flags().contains(Flag::EXTRA) {
+        "optional"
+    } else {
+        "none"
+    }
+}
+
+fn command_json(command: &Command) -> String {
+    let flag = |flag| command.flags().contains(flag);
+    format!(
+        "{{\"name\":{},\"abbreviation\":{},\"bang\":{},\"range\":{},\"count\":{},\"register\":{},\
+         \"argument\":{}}}",
+        json::string(command.name()),
+        json::string(&command.name()[..command.minlen()]),
+        flag(Flag::BANG),
+        flag(Flag::RANGE),
+        flag(Flag::COUNT),
+        flag(Flag::REGSTR),
+        json::string(argument(command))
+    )
+}
+
+fn strings(list: &[&str]) -> String {
+    let list = list
+        .iter()
+        .map(|s| json::string(s))
+        .collect::<Vec<String>>();
+    format!("[{}]", list.join(","))
+}
+
+/// The built-in commands, autocommand events and highlight keys as JSON.
+pub fn to_json() -> String {
+    let commands = commands::all()
+        .into_iter()
+        .map(command_json)
+        .collect::<Vec<String>>();
+    format!(
+        "{{\"commands\":[{}],\"events\":{},\"highlight_keys\":{}}}",
+        commands.join(","),
+        strings(&autocmd_events()),
+        strings(HIGHLIGHT_KEYS)
+    )
+}
+
+/// The names of the built-in commands, one to a line, each followed by its shortest
+/// abbreviation if it has one.
+pub fn to_text() -> String {
+    commands::all()
+        .into_iter()
+        .map(|command| {
+            let abbreviation = &command.name()[..command.minlen()];
+            if abbreviation == command.name() {
+                format!("{}\n", command.name())
+            } else {
+                format!("{} {}\n", command.name(), abbreviation)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_json() {
+        let json = serde_json::from_str::<serde_json::Value>(&to_json()).unwrap();
+        let echo = json["commands"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|c| c["name"] == "echo")
+            .unwrap();
+        assert_eq!(echo["abbreviation"], "ec");
+        assert_eq!(echo["bang"], false);
+        let edit = &json["commands"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|c| c["name"] == "edit")
+            .unwrap();
+        assert_eq!(edit["bang"], true);
+        assert!(json["events"]
+            .as_array()
+            .unwrap()
+            .contains(&"BufReadPost".into()));
+        assert!(json["highlight_keys"]
+            .as_array()
+            .unwrap()
+            .contains(&"ctermfg".into()));
+        assert!(to_text().contains("echo ec\n"));
+    }
+}
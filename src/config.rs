@@ -0,0 +1,816 @@
+use crate::{
+    deprecated::{self, Deprecation, Target},
+    lint,
+};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// The name of the file that configures `vimlfmt` for the directory it is in and those below it.
+pub const CONFIG_FILE: &str = ".vimlfmt.toml";
+
+/// The configuration file that applies to files in `dir`: the nearest
+/// [CONFIG_FILE](constant.CONFIG_FILE.html) in it or one of the directories above it.
+pub fn find_config_file(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|d| d.join(CONFIG_FILE))
+        .find(|p| p.is_file())
+}
+
+/// What to do with blocks (`if`, `for`, `try`, `while`) that were written on a single line with
+/// bars in the source, e.g. `if has('nvim') | set inccommand=nosplit | endif`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum OneLineBlocks {
+    /// Expand the block so each statement is on its own line.
+    Expand,
+    /// Keep the block on one line, as long as it fits and none of its statements are blocks
+    /// themselves, e.g. `if x | return | endif` but not `if x | for y in z | endfor | endif`.
+    Preserve,
+}
+
+/// How to spell autocommand event names, which Vim treats case-insensitively.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum EventCase {
+    /// The spelling used in Vim's documentation, e.g. `BufReadPost`.
+    Canonical,
+    /// All lowercase, e.g. `bufreadpost`.
+    Lowercase,
+    /// However the event was spelled in the source.
+    Preserve,
+}
+
+/// Which flavor of Vim script to write.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Syntax {
+    /// Legacy Vim script, as understood by every version of Vim and Neovim.
+    Legacy,
+    /// Vim9 script: `def` instead of `function`, `var` declarations, `#` comments, `..` for
+    /// concatenation and `(x) => x` lambdas. Functions that can't be written with `def` are left
+    /// as legacy functions.
+    Vim9,
+}
+
+/// What to do with statements that were broken onto several lines with `\` continuations in the
+/// source.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Continuations {
+    /// Join the lines and wrap the statement again wherever it doesn't fit.
+    Rewrap,
+    /// Break the statement where it was broken in the source. Lines that still don't fit are
+    /// wrapped as well.
+    Preserve,
+    /// Join the lines if the whole statement fits on one line, otherwise break it where it was
+    /// broken in the source. Statements with bodies, like `if` or `function`, are never joined.
+    Join,
+}
+
+/// Where to start continuation lines, the lines starting with `\` that a wrapped statement is
+/// continued on.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ContinuationIndent {
+    /// This many spaces past the indent of the statement.
+    Spaces(usize),
+    /// Two indents past the indent of the statement.
+    Double,
+    /// Line the text of the continuation line up with the text after the innermost bracket
+    /// still open, e.g. the first argument of a call. Where no bracket is open, or the bracket
+    /// is too close to the start of the line, six spaces past the indent of the statement.
+    Aligned,
+}
+
+/// The order to write the attributes of `highlight` commands in.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum HighlightAttrOrder {
+    Alphabetical,
+    /// `term`, `cterm`, `ctermfg`, `ctermbg`, `gui`, `guifg`, `guibg` and `guisp`, followed by
+    /// any others in alphabetical order.
+    Canonical,
+    /// The order they were written in the source.
+    Preserve,
+}
+
+/// The order to write the attributes of mappings and abbreviations in, e.g. `<silent>`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum MappingAttrOrder {
+    Alphabetical,
+    /// The order Vim's documentation lists them in: `buffer`, `nowait`, `silent`, `special`,
+    /// `script`, `expr` and `unique`.
+    Canonical,
+    /// The order they were written in the source.
+    Preserve,
+}
+
+/// Where to put a comma after the last item of a list or dictionary, which Vim allows.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum TrailingCommas {
+    /// After the last item of those written one item per line, and not of those written on one
+    /// line.
+    Multiline,
+    Never,
+    /// Wherever there was one in the source.
+    Preserve,
+}
+
+/// Which operator to concatenate strings with in the arguments of `execute` commands. Vim9 script
+/// always uses `..`, since it doesn't allow `.` for concatenation.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Concatenation {
+    /// Whichever operator was used in the source.
+    Preserve,
+    /// `.`, which every version of Vim understands.
+    Dot,
+    /// `..`, which needs Vim 8.1.1114 or later, or Neovim.
+    DoubleDot,
+}
+
+/// Options controlling the output of the formatter.
+#[derive(Debug, PartialEq, Clone)]
+pub struct FormatterConfig {
+    pub one_line_blocks: OneLineBlocks,
+    pub continuations: Continuations,
+    pub continuation_indent: ContinuationIndent,
+    pub syntax: Syntax,
+    /// The script is Vim9 script, i.e. it starts with `vim9script`, so comments after that
+    /// command and outside legacy functions are written with `#`. Unlike
+    /// [syntax](#structfield.syntax), this doesn't rewrite any code, so it is what detecting Vim9
+    /// script turns on.
+    pub vim9script: bool,
+    pub event_case: EventCase,
+    /// Sort and deduplicate the values given to comma-separated list options in `set` commands,
+    /// e.g. `set wildignore+=b,a,b` becomes `set wildignore+=a,b`. Options whose values are
+    /// order-sensitive (like `runtimepath`) are only deduplicated.
+    pub sort_set_lists: bool,
+    /// Merge consecutive `set` commands into one, e.g. `set number` followed by `set list`
+    /// becomes `set number list`.
+    pub merge_set_lines: bool,
+    /// Remove trailing whitespace from comments. Whitespace the formatter would otherwise leave
+    /// at the end of a line is always removed, and the text of `append`, `insert` and
+    /// `loadkeymap` commands is always written exactly as it was in the source (unless the
+    /// columns of `loadkeymap` tables are aligned).
+    pub trim_trailing_whitespace: bool,
+    /// Copy the top-level statements that have parts the parser keeps as text (the arguments of
+    /// commands it doesn't parse, including user commands, and embedded scripts) exactly as they
+    /// are in the source, including whitespace, rather than risk reformatting them. Only takes
+    /// effect when the formatter is given the source, with `format_source` or `format_ranges`.
+    pub safe: bool,
+    /// Remove a bar at the end of a line that only separates a command from nothing, e.g.
+    /// `echo 1 |` becomes `echo 1`. A bar followed by a comment is kept, since some commands
+    /// (like mappings) would otherwise take the comment as an argument.
+    pub remove_trailing_bars: bool,
+    /// How many spaces to put before a comment at the end of a line, e.g. with 2,
+    /// `let x = 1   " note` becomes `let x = 1  " note`.
+    pub trailing_comment_gap: usize,
+    /// End the output with a newline.
+    pub ensure_final_newline: bool,
+    /// Start the output with a UTF-8 byte order mark if the source started with one. When this
+    /// is false the byte order mark is removed.
+    pub keep_byte_order_mark: bool,
+    /// Shell commands to format scripts embedded with e.g. `lua << EOF`, keyed by the command
+    /// that introduces them (e.g. `"lua"` to `"stylua -"`). The script is given on stdin and the
+    /// formatted script read from stdout; if the command fails the script is left as it was.
+    /// Scripts using `trim` are reindented to match the surrounding code whether or not they are
+    /// formatted.
+    pub heredoc_formatters: BTreeMap<String, String>,
+    /// Write key notation in canonical form (`<C-w>` for `<c-w>`, `<Esc>` for `<esc>`) in
+    /// mappings, unmap commands and `\<...>` escapes in double-quoted strings.
+    pub normalize_key_notation: bool,
+    /// Also normalize key notation in the argument of `normal`. Vim does not translate key
+    /// notation there, so `normal <c-w>` types the characters `<`, `c`, `-`, `w` and `>`, and
+    /// normalizing them changes what is typed.
+    pub normalize_keys_in_normal: bool,
+    /// Spellings of key names to use when normalizing key notation, keyed by the lowercase name,
+    /// e.g. `"esc"` to `"ESC"`. These replace or add to the built-in table, which spells each key
+    /// the way Vim's documentation does (`<Esc>`, `<CR>`, `<PageUp>`).
+    pub canonical_keys: BTreeMap<String, String>,
+    /// Commands whose arguments are written exactly as they were, without wrapping, trimming or
+    /// other changes, e.g. because they are passed to a shell. User commands (those starting
+    /// with an uppercase letter) are always treated this way. The names are the full names of
+    /// the commands, e.g. `terminal` rather than `ter`.
+    pub opaque_commands: Vec<String>,
+    /// Whether to sort command modifiers into canonical order, e.g. `keepjumps silent!` into
+    /// `silent! keepjumps`. See `viml_parser::CANONICAL_ORDER` for the order.
+    pub sort_modifiers: bool,
+    /// Keep `echohl Group | echo ... | echohl None` on one line, as long as it was on one line in
+    /// the source and still fits. Otherwise each command is put on its own line.
+    pub compact_echohl: bool,
+    /// Write the double-quoted strings in the arguments of `execute` commands with single quotes
+    /// where that doesn't change them, i.e. where they have no backslash escapes, e.g.
+    /// `execute "normal! " . count . "j"` becomes `execute 'normal! ' . count . 'j'`.
+    pub single_quote_execute: bool,
+    /// Write options set with `let` or read in expressions by their full names, e.g.
+    /// `let &l:sw = &ts` becomes `let &l:shiftwidth = &tabstop`.
+    pub expand_option_names: bool,
+    pub execute_concatenation: Concatenation,
+    /// Line up the `=` of the operators of consecutive `let` statements (a run of them with no
+    /// blank lines or comments in between), e.g. `let a = 1` and `let abc += 2` become
+    /// `let a    = 1` and `let abc += 2`.
+    pub align_assignments: bool,
+    /// The most spaces to add before an operator when aligning assignments. A statement that
+    /// would need more than this starts a new run, which is aligned separately.
+    pub max_alignment_padding: usize,
+    pub highlight_attr_order: HighlightAttrOrder,
+    pub mapping_attr_order: MappingAttrOrder,
+    /// Spell the names of Vim's and Neovim's built-in highlight groups in `highlight` commands
+    /// the way the documentation does, e.g. `LineNr` for `linenr`. Vim treats group names
+    /// case-insensitively.
+    pub canonical_highlight_groups: bool,
+    /// Line up the group names and attributes of consecutive `highlight` commands that set
+    /// attributes, as long as every line still fits, e.g.
+    /// ```text
+    /// highlight Normal  ctermbg=234 ctermfg=252
+    /// highlight Comment             ctermfg=244
+    /// ```
+    pub align_highlights: bool,
+    /// Line up the columns of the table after `loadkeymap`, as Vim's own keymap files do, e.g.
+    /// ```text
+    /// loadkeymap
+    /// a'   á  LATIN SMALL LETTER A WITH ACUTE
+    /// a<   â  LATIN SMALL LETTER A WITH CIRCUMFLEX
+    /// ```
+    /// A table with a line Vim would reject is written as it was.
+    pub align_keymaps: bool,
+    /// Line up the attributes of consecutive `sign define` commands, as long as every line still
+    /// fits, e.g.
+    /// ```text
+    /// sign define Error   text=>> texthl=ErrorMsg
+    /// sign define Warning text=!! texthl=WarningMsg
+    /// ```
+    pub align_signs: bool,
+    /// Put spaces inside the braces of dictionaries written on one line, e.g. `{ 'a': 1 }`
+    /// rather than `{'a': 1}`.
+    pub space_in_braces: bool,
+    /// Put spaces inside the brackets of lists written on one line, e.g. `[ 1, 2 ]` rather than
+    /// `[1, 2]`.
+    pub space_in_brackets: bool,
+    pub trailing_commas: TrailingCommas,
+    /// The indent level to start at, for formatting lines from inside a block (e.g. the body of a
+    /// function selected in an editor) on their own.
+    pub initial_indent: usize,
+    /// The width on screen to wrap lines at (counting wide characters as two columns and tabs
+    /// as going to the next multiple of 8), or `None` to never wrap them.
+    pub max_line_length: Option<usize>,
+    /// The most blank lines to keep in a row, or `None` to keep as many as there were in the
+    /// source. Blank lines at the start and end of the output are always removed.
+    pub max_blank_lines: Option<usize>,
+}
+
+impl Default for FormatterConfig {
+    fn default() -> Self {
+        Self {
+            one_line_blocks: OneLineBlocks::Expand,
+            continuations: Continuations::Rewrap,
+            continuation_indent: ContinuationIndent::Spaces(6),
+            syntax: Syntax::Legacy,
+            vim9script: false,
+            event_case: EventCase::Canonical,
+            sort_set_lists: false,
+            merge_set_lines: false,
+            trim_trailing_whitespace: true,
+            safe: false,
+            remove_trailing_bars: false,
+            trailing_comment_gap: 1,
+            ensure_final_newline: true,
+            keep_byte_order_mark: true,
+            heredoc_formatters: BTreeMap::new(),
+            normalize_key_notation: true,
+            normalize_keys_in_normal: false,
+            canonical_keys: BTreeMap::new(),
+            opaque_commands: vec!["!".to_string(), "terminal".to_string()],
+            sort_modifiers: false,
+            compact_echohl: false,
+            single_quote_execute: false,
+            expand_option_names: false,
+            execute_concatenation: Concatenation::Preserve,
+            align_assignments: false,
+            max_alignment_padding: 8,
+            highlight_attr_order: HighlightAttrOrder::Alphabetical,
+            mapping_attr_order: MappingAttrOrder::Alphabetical,
+            align_highlights: false,
+            align_keymaps: false,
+            align_signs: false,
+            space_in_braces: false,
+            space_in_brackets: false,
+            trailing_commas: TrailingCommas::Multiline,
+            canonical_highlight_groups: false,
+            initial_indent: 0,
+            max_line_length: Some(80),
+            max_blank_lines: Some(1),
+        }
+    }
+}
+
+impl FormatterConfig {
+    /// The settings of a built-in profile, in the first style edition.
+    pub fn with_profile(profile: Profile) -> Self {
+        Self::with_style(profile, StyleEdition::Edition2023)
+    }
+
+    /// The settings of a built-in profile in a style edition. The profile's own settings take
+    /// precedence over the edition's.
+    pub fn with_style(profile: Profile, edition: StyleEdition) -> Self {
+        let default = match edition {
+            StyleEdition::Edition2023 => Self::default(),
+            StyleEdition::Edition2024 => Self {
+                sort_modifiers: true,
+                remove_trailing_bars: true,
+                mapping_attr_order: MappingAttrOrder::Canonical,
+                ..Self::default()
+            },
+        };
+        match profile {
+            Profile::Default => default,
+            Profile::Colorscheme => Self {
+                event_case: EventCase::Canonical,
+                highlight_attr_order: HighlightAttrOrder::Canonical,
+                align_highlights: true,
+                canonical_highlight_groups: true,
+                max_line_length: None,
+                max_blank_lines: None,
+                ..default
+            },
+            Profile::Vimrc => Self {
+                one_line_blocks: OneLineBlocks::Preserve,
+                sort_set_lists: true,
+                merge_set_lines: true,
+                compact_echohl: true,
+                ..default
+            },
+        }
+    }
+
+    /// The names of the settings [set](#method.set) can change, which are the names of the
+    /// fields they are kept in.
+    pub const SETTINGS: &'static [&'static str] = &[
+        "one_line_blocks",
+        "continuations",
+        "continuation_indent",
+        "syntax",
+        "vim9script",
+        "event_case",
+        "sort_set_lists",
+        "merge_set_lines",
+        "trim_trailing_whitespace",
+        "safe",
+        "remove_trailing_bars",
+        "trailing_comment_gap",
+        "ensure_final_newline",
+        "keep_byte_order_mark",
+        "normalize_key_notation",
+        "normalize_keys_in_normal",
+        "sort_modifiers",
+        "compact_echohl",
+        "single_quote_execute",
+        "expand_option_names",
+        "execute_concatenation",
+        "align_assignments",
+        "max_alignment_padding",
+        "highlight_attr_order",
+        "mapping_attr_order",
+        "canonical_highlight_groups",
+        "align_highlights",
+        "align_keymaps",
+        "align_signs",
+        "space_in_braces",
+        "space_in_brackets",
+        "trailing_commas",
+        "initial_indent",
+        "max_line_length",
+        "max_blank_lines",
+    ];
+
+    /// Change a setting, given its name and a value written as it would be on the command line:
+    /// `true` or `false` for a flag, a number for a count, a number or `none` for a limit that
+    /// can be turned off, and the name of a choice in lowercase, e.g. `double-dot`, for the
+    /// others. Dashes in the name are taken as underscores, so `max-line-length` is
+    /// `max_line_length`. The maps and lists of the configuration can't be set this way.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let name = name.trim().replace('-', "_");
+        let value = value.trim();
+        let invalid = |expected: &str| {
+            format!(
+                "invalid value for {}: `{}` (expected {})",
+                name, value, expected
+            )
+        };
+        let flag = || match value {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            _ => Err(invalid("true or false")),
+        };
+        let count = || value.parse::<usize>().map_err(|_| invalid("a number"));
+        let limit = || match value {
+            "none" => Ok(None),
+            _ => value
+                .parse()
+                .map(Some)
+                .map_err(|_| invalid("a number or none")),
+        };
+        let choice = |choices: &[&str]| {
+            choices
+                .iter()
+                .position(|&c| c == value)
+                .ok_or_else(|| invalid(&format!("one of {}", choices.join(", "))))
+        };
+        match name.as_str() {
+            "one_line_blocks" => {
+                self.one_line_blocks = [OneLineBlocks::Expand, OneLineBlocks::Preserve]
+                    [choice(&["expand", "preserve"])?]
+            }
+            "continuations" => {
+                self.continuations = [
+                    Continuations::Rewrap,
+                    Continuations::Preserve,
+                    Continuations::Join,
+                ][choice(&["rewrap", "preserve", "join"])?]
+            }
+            "continuation_indent" => {
+                self.continuation_indent = match value {
+                    "double" => ContinuationIndent::Double,
+                    "aligned" => ContinuationIndent::Aligned,
+                    _ => ContinuationIndent::Spaces(
+                        value
+                            .parse()
+                            .map_err(|_| invalid("a number, double or aligned"))?,
+                    ),
+                }
+            }
+            "syntax" => self.syntax = [Syntax::Legacy, Syntax::Vim9][choice(&["legacy", "vim9"])?],
+            "vim9script" => self.vim9script = flag()?,
+            "event_case" => {
+                self.event_case = [
+                    EventCase::Canonical,
+                    EventCase::Lowercase,
+                    EventCase::Preserve,
+                ][choice(&["canonical", "lowercase", "preserve"])?]
+            }
+            "sort_set_lists" => self.sort_set_lists = flag()?,
+            "merge_set_lines" => self.merge_set_lines = flag()?,
+            "trim_trailing_whitespace" => self.trim_trailing_whitespace = flag()?,
+            "safe" => self.safe = flag()?,
+            "remove_trailing_bars" => self.remove_trailing_bars = flag()?,
+            "trailing_comment_gap" => self.trailing_comment_gap = count()?,
+            "ensure_final_newline" => self.ensure_final_newline = flag()?,
+            "keep_byte_order_mark" => self.keep_byte_order_mark = flag()?,
+            "normalize_key_notation" => self.normalize_key_notation = flag()?,
+            "normalize_keys_in_normal" => self.normalize_keys_in_normal = flag()?,
+            "sort_modifiers" => self.sort_modifiers = flag()?,
+            "compact_echohl" => self.compact_echohl = flag()?,
+            "single_quote_execute" => self.single_quote_execute = flag()?,
+            "expand_option_names" => self.expand_option_names = flag()?,
+            "execute_concatenation" => {
+                self.execute_concatenation = [
+                    Concatenation::Preserve,
+                    Concatenation::Dot,
+                    Concatenation::DoubleDot,
+                ][choice(&["preserve", "dot", "double-dot"])?]
+            }
+            "align_assignments" => self.align_assignments = flag()?,
+            "max_alignment_padding" => self.max_alignment_padding = count()?,
+            "highlight_attr_order" => {
+                self.highlight_attr_order = [
+                    HighlightAttrOrder::Alphabetical,
+                    HighlightAttrOrder::Canonical,
+                    HighlightAttrOrder::Preserve,
+                ][choice(&["alphabetical", "canonical", "preserve"])?]
+            }
+            "mapping_attr_order" => {
+                self.mapping_attr_order = [
+                    MappingAttrOrder::Alphabetical,
+                    MappingAttrOrder::Canonical,
+                    MappingAttrOrder::Preserve,
+                ][choice(&["alphabetical", "canonical", "preserve"])?]
+            }
+            "canonical_highlight_groups" => self.canonical_highlight_groups = flag()?,
+            "align_highlights" => self.align_highlights = flag()?,
+            "align_keymaps" => self.align_keymaps = flag()?,
+            "align_signs" => self.align_signs = flag()?,
+            "space_in_braces" => self.space_in_braces = flag()?,
+            "space_in_brackets" => self.space_in_brackets = flag()?,
+            "trailing_commas" => {
+                self.trailing_commas = [
+                    TrailingCommas::Multiline,
+                    TrailingCommas::Never,
+                    TrailingCommas::Preserve,
+                ][choice(&["multiline", "never", "preserve"])?]
+            }
+            "initial_indent" => self.initial_indent = count()?,
+            "max_line_length" => self.max_line_length = limit()?,
+            "max_blank_lines" => self.max_blank_lines = limit()?,
+            "heredoc_formatters" | "canonical_keys" | "opaque_commands" => {
+                return Err(format!(
+                    "{} can't be set with --config; use --{} instead",
+                    name,
+                    match name.as_str() {
+                        "heredoc_formatters" => "heredoc-formatter",
+                        "canonical_keys" => "canonical-key",
+                        _ => "opaque-command",
+                    }
+                ))
+            }
+            _ => {
+                return Err(format!(
+                    "unknown setting: {} (known settings: {})",
+                    name,
+                    Self::SETTINGS.join(", ")
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    /// Change the settings given as a comma-separated list of `name=value` pairs, e.g.
+    /// `max_line_length=100,space_in_braces=true`, with [set](#method.set). Stops at the first
+    /// pair that is invalid, returning an error saying what is wrong with it.
+    pub fn set_all(&mut self, settings: &str) -> Result<(), String> {
+        for setting in settings.split(',').filter(|s| !s.trim().is_empty()) {
+            match setting.split_once('=') {
+                Some((name, value)) => self.set(name, value)?,
+                None => {
+                    return Err(format!(
+                        "invalid setting: `{}` (expected name=value)",
+                        setting.trim()
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A named set of formatter settings to start from, which the settings given along with it
+/// change.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Profile {
+    /// The settings of `FormatterConfig::default()`.
+    Default,
+    /// For colorschemes: `highlight` commands aligned, with their attributes in canonical order
+    /// and built-in group names spelled canonically, no wrapping of long lines and blank lines
+    /// kept as they were.
+    Colorscheme,
+    /// For vimrcs: one-line blocks kept on one line, `set` commands merged and their lists
+    /// sorted, and `echohl` kept on one line with the `echo` it highlights.
+    Vimrc,
+}
+
+impl Profile {
+    pub const NAMES: &'static [&'static str] = &["default", "colorscheme", "vimrc"];
+}
+
+impl FromStr for Profile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "default" => Ok(Profile::Default),
+            "colorscheme" => Ok(Profile::Colorscheme),
+            "vimrc" => Ok(Profile::Vimrc),
+            _ => Err(format!("unknown profile: {}", s)),
+        }
+    }
+}
+
+/// A version of the formatter's default settings. New defaults only go into a new edition, so
+/// that code formatted in an edition keeps being formatted the same way after `vimlfmt` is
+/// upgraded; a project opts into the new defaults by choosing the new edition.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum StyleEdition {
+    /// The settings of `FormatterConfig::default()`.
+    Edition2023,
+    /// Command modifiers and the attributes of mappings sorted into canonical order, and bars at
+    /// the end of lines removed.
+    Edition2024,
+}
+
+impl StyleEdition {
+    pub const NAMES: &'static [&'static str] = &["2023", "2024"];
+
+    /// Read the `style_edition` of a configuration file, e.g. `style_edition = "2024"`, if it
+    /// has one.
+    pub fn from_toml(text: &str) -> Result<Option<Self>, String> {
+        let table = text.parse::<toml::Table>().map_err(|e| e.to_string())?;
+        match table.get("style_edition") {
+            Some(edition) => edition
+                .as_str()
+                .ok_or_else(|| "style_edition must be a string".to_string())?
+                .parse()
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+impl FromStr for StyleEdition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "2023" => Ok(StyleEdition::Edition2023),
+            "2024" => Ok(StyleEdition::Edition2024),
+            _ => Err(format!(
+                "unknown style edition: {} (known editions: {})",
+                s,
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+/// The level a lint rule reports its problems at, in place of the
+/// [Severity](../lint/enum.Severity.html) the rule gives them.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum RuleLevel {
+    /// Don't check the rule.
+    Off,
+    Warn,
+    Error,
+}
+
+impl FromStr for RuleLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(RuleLevel::Off),
+            "warn" => Ok(RuleLevel::Warn),
+            "error" => Ok(RuleLevel::Error),
+            _ => Err(format!("unknown rule level: {}", s)),
+        }
+    }
+}
+
+/// The header the `missing-header` rule requires scripts to start with.
+#[derive(Debug, PartialEq, Clone)]
+pub struct HeaderConfig {
+    /// The encoding scripts must declare with `scriptencoding`, or `None` not to require it.
+    pub encoding: Option<String>,
+    /// The load guard to add to scripts without one, with `{variable}` standing for the guard
+    /// variable, or `None` not to require a guard. Any `if exists(...)` checking the variable
+    /// and containing `finish` counts as a guard, however it is written. In a Vim9 script, `let`
+    /// is left off the start of the guard's lines.
+    pub guard: Option<String>,
+    /// The variable the guard must check, e.g. `g:loaded_foo`, usually from
+    /// [guard_variable](../lint/fn.guard_variable.html). Without one, a guard checking any
+    /// variable is accepted, and a missing guard isn't fixed.
+    pub guard_variable: Option<String>,
+}
+
+impl Default for HeaderConfig {
+    fn default() -> Self {
+        Self {
+            encoding: Some("utf-8".to_string()),
+            guard: Some("if exists('{variable}') | finish | endif\nlet {variable} = 1".to_string()),
+            guard_variable: None,
+        }
+    }
+}
+
+/// Which rules `vimlfmt lint` checks.
+#[derive(Debug, PartialEq, Clone)]
+pub struct LintConfig {
+    /// Require functions to be defined with the `abort` attribute, so they stop at the first
+    /// error instead of carrying on with the rest of the function.
+    pub require_abort: bool,
+    /// Require autocommands to be defined in an `augroup`, or with a group given in the `autocmd`
+    /// command, so they can be cleared with `autocmd!` when the script is sourced again.
+    pub require_augroup: bool,
+    /// Report script-local functions and variables that are defined but never used.
+    pub report_unused: bool,
+    /// Require functions and user commands to be defined with `!`, so that sourcing the script
+    /// again replaces them instead of failing because they already exist, as plugin scripts
+    /// should. This doesn't apply to `autocmd`, whose `!` removes autocommands rather than
+    /// allowing them to be redefined; see `require_augroup` for that.
+    pub require_bang: bool,
+    /// The name to give the groups made when fixing autocommands defined outside a group. If the
+    /// script already uses a group with this name, a number is added to it.
+    pub augroup_name: String,
+    /// The editor and version scripts are meant to run in, which decides which of the
+    /// `deprecations` are reported. Without one, only features that neither Vim nor Neovim
+    /// supports any more are.
+    pub target: Option<Target>,
+    /// Features to report the use of, which are Vim's and Neovim's deprecated and removed
+    /// features by default.
+    pub deprecations: Vec<Deprecation>,
+    /// The levels to report the problems found by rules at, by rule name, e.g. `"unused"`.
+    pub rule_levels: BTreeMap<String, RuleLevel>,
+    /// Require scripts to start with a header. Off by default, since only the scripts of plugins
+    /// need one.
+    pub header: Option<HeaderConfig>,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            require_abort: true,
+            require_augroup: true,
+            report_unused: true,
+            require_bang: false,
+            augroup_name: "vimrc".to_string(),
+            target: None,
+            deprecations: deprecated::builtin(),
+            rule_levels: BTreeMap::new(),
+            header: None,
+        }
+    }
+}
+
+impl LintConfig {
+    /// Set the levels of rules from the `[lint.rules]` table of a configuration file, and require
+    /// a header if it has a `[lint.header]` table, whose `encoding` and `guard` are those of
+    /// [HeaderConfig](struct.HeaderConfig.html) (or `false` for `None`), e.g.
+    /// ```toml
+    /// [lint.rules]
+    /// missing-abort = "off"
+    /// unused = "error"
+    ///
+    /// [lint.header]
+    /// guard = "if exists('{variable}')\n  finish\nendif\nlet {variable} = 1"
+    /// ```
+    pub fn apply_toml(&mut self, text: &str) -> Result<(), String> {
+        let table = text.parse::<toml::Table>().map_err(|e| e.to_string())?;
+        if let Some(header) = table.get("lint").and_then(|lint| lint.get("header")) {
+            let header = header
+                .as_table()
+                .ok_or_else(|| "lint.header must be a table".to_string())?;
+            let mut config = HeaderConfig {
+                guard_variable: self.header.take().and_then(|h| h.guard_variable),
+                ..HeaderConfig::default()
+            };
+            for (key, value) in header {
+                let value = match value {
+                    toml::Value::String(s) => Some(s.clone()),
+                    toml::Value::Boolean(false) => None,
+                    _ => return Err(format!("lint.header.{} must be a string or false", key)),
+                };
+                match key.as_str() {
+                    "encoding" => config.encoding = value,
+                    "guard" => config.guard = value,
+                    _ => return Err(format!("unknown lint.header key: {}", key)),
+                }
+            }
+            self.header = Some(config);
+        }
+        let rules = match table.get("lint").and_then(|lint| lint.get("rules")) {
+            Some(rules) => rules
+                .as_table()
+                .ok_or_else(|| "lint.rules must be a table".to_string())?,
+            None => return Ok(()),
+        };
+        for (rule, level) in rules {
+            if !lint::RULES.contains(&rule.as_str()) {
+                return Err(format!("unknown lint rule: {}", rule));
+            }
+            let level = level
+                .as_str()
+                .ok_or_else(|| format!("the level of {} must be a string", rule))?
+                .parse()?;
+            self.rule_levels.insert(rule.clone(), level);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_all() {
+        let mut config = FormatterConfig::default();
+        config
+            .set_all("max_line_length=none, space-in-braces=true,continuation_indent=4,")
+            .unwrap();
+        config.set_all("execute_concatenation=double-dot").unwrap();
+        assert_eq!(
+            config,
+            FormatterConfig {
+                max_line_length: None,
+                space_in_braces: true,
+                continuation_indent: ContinuationIndent::Spaces(4),
+                execute_concatenation: Concatenation::DoubleDot,
+                ..FormatterConfig::default()
+            }
+        );
+        let error = |settings| config.clone().set_all(settings).unwrap_err();
+        assert_eq!(
+            error("safe=yes"),
+            "invalid value for safe: `yes` (expected true or false)"
+        );
+        assert_eq!(
+            error("trailing_commas=always"),
+            "invalid value for trailing_commas: `always` (expected one of multiline, never, \
+             preserve)"
+        );
+        assert_eq!(
+            error("max_blank_lines=-1"),
+            "invalid value for max_blank_lines: `-1` (expected a number or none)"
+        );
+        assert_eq!(
+            error("max_line_length"),
+            "invalid setting: `max_line_length` (expected name=value)"
+        );
+        assert!(error("line_length=100").starts_with("unknown setting: line_length (known"));
+        assert!(error("canonical_keys=esc").contains("--canonical-key"));
+    }
+}
@@ -0,0 +1,482 @@
+use crate::{
+    config::{FormatterConfig, Syntax},
+    formatter::Formatter,
+};
+use std::{
+    collections::HashSet,
+    fmt,
+    io::{Error, ErrorKind},
+};
+use viml_parser::{BinaryOpKind, Node, Position, Range};
+
+/// Something in the source that could not be translated, or was translated in a way that might
+/// not behave exactly the same.
+#[derive(Debug, PartialEq)]
+pub struct Warning {
+    pub pos: Position,
+    pub msg: String,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}, col {}: {}",
+            self.pos.line(),
+            self.pos.column(),
+            self.msg
+        )
+    }
+}
+
+/// The result of converting a script.
+#[derive(Debug)]
+pub struct Conversion {
+    pub output: String,
+    pub warnings: Vec<Warning>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Context {
+    Script,
+    Def,
+    Legacy,
+}
+
+/// The reasons a [Function](../viml_parser/enum.Node.html#variant.Function) node can't be
+/// written as a `def` function. Returns an empty vector if it can be (or if the node is not a
+/// function at all).
+pub fn def_blockers(node: &Node) -> Vec<String> {
+    let mut blockers = vec![];
+    if let Node::Function {
+        name,
+        args,
+        attrs,
+        body,
+        ..
+    } = node
+    {
+        for attr in attrs {
+            if attr != "abort" {
+                blockers.push(format!("uses the `{}` attribute", attr));
+            }
+        }
+        if let Node::Identifier { .. } = **name {
+        } else {
+            blockers.push("is a dictionary function or has a computed name".to_string());
+        }
+        if args.iter().any(|a| format!("{}", a) == "...") {
+            blockers.push("takes variable arguments".to_string());
+        }
+        for node in body {
+            find_blockers(node, &mut blockers);
+        }
+    }
+    blockers.dedup();
+    blockers
+}
+
+fn find_blockers(node: &Node, blockers: &mut Vec<String>) {
+    match node {
+        Node::Function { .. } => blockers.push("defines a nested function".to_string()),
+        Node::CurlyName { .. } => blockers.push("uses a curly-braces name".to_string()),
+        Node::Identifier { value, .. } => {
+            let special = match value.as_str() {
+                "a:" | "l:" | "a:0" | "a:000" | "a:firstline" | "a:lastline" | "self" => true,
+                _ => value.starts_with("a:") && value[2..].chars().all(|c| c.is_ascii_digit()),
+            };
+            if special {
+                blockers.push(format!("uses `{}`", value));
+            }
+        }
+        _ => {
+            for child in node.children() {
+                find_blockers(child, blockers);
+            }
+        }
+    }
+}
+
+/// Convert a legacy script to vim9 script. Translates `function` to `def` where possible, `let`
+/// to `var` (or a plain assignment), drops the `a:`, `l:` and `s:` prefixes that vim9 script does
+/// not use, and makes variables that legacy script-level code implicitly made global explicitly
+/// global. Anything that can't be translated is left as it is and reported as a warning, though
+/// functions kept as legacy functions concatenate with `..`, since that's the only way they can
+/// in a vim9 script.
+pub fn to_vim9(ast: &Node) -> Result<Conversion, Error> {
+    let mut ast = ast.clone();
+    let mut converter = Converter {
+        globals: HashSet::new(),
+        shadowed: vec![],
+        warnings: vec![],
+    };
+    if let Node::TopLevel { body, .. } = &mut ast {
+        for node in body.iter() {
+            converter.collect_globals(node);
+        }
+        for node in body.iter_mut() {
+            converter.convert(node, Context::Script);
+        }
+        let pos = ast.pos();
+        let header = Node::ExCmdRaw {
+            pos,
+            mods: vec![],
+            range: Range::default(),
+            command: "vim9script".to_string(),
+            bang: false,
+            args: String::new(),
+        };
+        if let Node::TopLevel { body, .. } = &mut ast {
+            let index = match body.first() {
+                Some(Node::Shebang { .. }) => 1,
+                _ => 0,
+            };
+            body.insert(index, header);
+        }
+    } else {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "provided node is not a TopLevel node",
+        ));
+    }
+    let mut formatter = Formatter::with_config(FormatterConfig {
+        syntax: Syntax::Vim9,
+        ..Default::default()
+    });
+    let output = formatter.format(&ast)?;
+    Ok(Conversion {
+        output,
+        warnings: converter.warnings,
+    })
+}
+
+struct Converter {
+    // variables assigned without a scope at the script level, which legacy script makes global
+    globals: HashSet<String>,
+    // lambda arguments in scope, which must not be renamed
+    shadowed: Vec<String>,
+    warnings: Vec<Warning>,
+}
+
+impl Converter {
+    fn warn(&mut self, pos: Position, msg: String) {
+        self.warnings.push(Warning { pos, msg });
+    }
+
+    fn collect_globals(&mut self, node: &Node) {
+        match node {
+            Node::Function { .. } => (),
+            Node::Let {
+                var, list, rest, ..
+            } => {
+                for target in var
+                    .iter()
+                    .map(|v| &**v)
+                    .chain(list)
+                    .chain(rest.iter().map(|r| &**r))
+                {
+                    if let Node::Identifier { value, .. } = target {
+                        if !value.contains(':') && !value.contains('#') {
+                            self.globals.insert(value.clone());
+                        }
+                    }
+                }
+            }
+            _ => {
+                for child in node.children() {
+                    self.collect_globals(child);
+                }
+            }
+        }
+    }
+
+    fn rename(&self, name: &str, context: Context) -> Option<String> {
+        if self.shadowed.iter().any(|s| s == name) {
+            return None;
+        }
+        match context {
+            Context::Legacy => None,
+            Context::Script => {
+                if name.starts_with("s:") && name.len() > 2 {
+                    Some(name[2..].to_string())
+                } else if self.globals.contains(name) {
+                    Some(format!("g:{}", name))
+                } else {
+                    None
+                }
+            }
+            Context::Def => {
+                if (name.starts_with("a:") || name.starts_with("l:") || name.starts_with("s:"))
+                    && name.len() > 2
+                {
+                    Some(name[2..].to_string())
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    fn convert_function_name(&mut self, name: &mut Node, bang: &mut bool, context: Context) {
+        if context == Context::Legacy {
+            // a function defined in a legacy function is defined by legacy script
+            return;
+        }
+        if let Node::Identifier { value, .. } = name {
+            if value.starts_with("s:") {
+                *value = value[2..].to_string();
+            } else if value.starts_with("<SID>") {
+                *value = value[5..].to_string();
+            } else if !value.contains(':') && !value.contains('#') {
+                // legacy functions without a prefix are global, vim9 ones are script-local
+                *value = format!("g:{}", value);
+            }
+            if !value.starts_with("g:") {
+                *bang = false;
+            }
+        } else {
+            // e.g. `s:d.method`, which must use the same name for the dictionary as the rest of
+            // the script
+            self.convert(name, context);
+        }
+    }
+
+    fn convert(&mut self, node: &mut Node, context: Context) {
+        match node {
+            Node::Function { .. } => {
+                let blockers = def_blockers(node);
+                if let Node::Function {
+                    pos,
+                    name,
+                    bang,
+                    attrs,
+                    body,
+                    ..
+                } = node
+                {
+                    self.convert_function_name(name, bang, context);
+                    let inner = if context == Context::Legacy {
+                        Context::Legacy
+                    } else if blockers.is_empty() {
+                        attrs.clear();
+                        Context::Def
+                    } else {
+                        self.warn(
+                            *pos,
+                            format!("kept as a legacy function: it {}", blockers.join(", ")),
+                        );
+                        Context::Legacy
+                    };
+                    for child in body.iter_mut() {
+                        self.convert(child, inner);
+                    }
+                }
+            }
+            Node::Call { name, args, .. } => {
+                if let Node::Identifier { value, .. } = &mut **name {
+                    if context != Context::Legacy && value.starts_with("s:") {
+                        *value = value[2..].to_string();
+                    }
+                } else {
+                    self.convert(name, context);
+                }
+                for arg in args.iter_mut() {
+                    self.convert(arg, context);
+                }
+            }
+            Node::BinaryOp { op, .. } if context == Context::Legacy => {
+                // legacy functions in a vim9 script use the latest `scriptversion`, where `.`
+                // isn't concatenation
+                if *op == BinaryOpKind::Concat {
+                    *op = BinaryOpKind::StringConcat;
+                }
+                for child in node.children_mut() {
+                    self.convert(child, context);
+                }
+            }
+            Node::Let { op, .. } if context == Context::Legacy => {
+                if op == ".=" {
+                    *op = "..=".to_string();
+                }
+                for child in node.children_mut() {
+                    self.convert(child, context);
+                }
+            }
+            Node::Dot { left, .. } => self.convert(left, context),
+            Node::Identifier { value, .. } => {
+                if let Some(renamed) = self.rename(value, context) {
+                    *value = renamed;
+                }
+            }
+            Node::Lambda { args, expr, .. } => {
+                let count = args.len();
+                for arg in args.iter() {
+                    self.shadowed.push(format!("{}", arg));
+                }
+                self.convert(expr, context);
+                let len = self.shadowed.len();
+                self.shadowed.truncate(len - count);
+            }
+            Node::Execute { pos, .. } if context == Context::Def => {
+                self.warn(
+                    *pos,
+                    "`execute` in a def function can't see the function's local variables"
+                        .to_string(),
+                );
+                for child in node.children_mut() {
+                    self.convert(child, context);
+                }
+            }
+            Node::Unlet { pos, .. } if context == Context::Def => {
+                self.warn(
+                    *pos,
+                    "local variables can't be unlet in a def function".to_string(),
+                );
+                for child in node.children_mut() {
+                    self.convert(child, context);
+                }
+            }
+            _ => {
+                for child in node.children_mut() {
+                    self.convert(child, context);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use viml_parser::parse_lines;
+
+    #[test]
+    fn test_to_vim9() {
+        let node = parse_lines(&[
+            "\" counter",
+            "let s:count = 0",
+            "let loaded = 1",
+            "function! s:Inc(n) abort",
+            "  let l:x = a:n + 1",
+            "  let s:count += l:x",
+            "  let x = 3",
+            "  call s:Log('count: ' . s:count)",
+            "  return map([1, 2], {i, v -> v + x})",
+            "endfunction",
+            "if loaded",
+            "  call s:Inc(1)",
+            "endif",
+        ])
+        .unwrap();
+        let conversion = to_vim9(&node).unwrap();
+        let expected = concat!(
+            "vim9script\n",
+            "# counter\n",
+            "var count = 0\n",
+            "g:loaded = 1\n",
+            "\n",
+            "def Inc(n): any\n",
+            "  var x = n + 1\n",
+            "  count += x\n",
+            "  x = 3\n",
+            "  Log('count: ' .. count)\n",
+            "  return map([1, 2], (i, v) => v + x)\n",
+            "enddef\n",
+            "\n",
+            "if g:loaded\n",
+            "  Inc(1)\n",
+            "endif\n",
+        );
+        assert_eq!(expected, &conversion.output);
+        assert!(conversion.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_to_vim9_untranslatable() {
+        let node = parse_lines(&[
+            "function! Foo(...) dict",
+            "  let x = 1",
+            "  echo a:000",
+            "  return self",
+            "endfunction",
+        ])
+        .unwrap();
+        let conversion = to_vim9(&node).unwrap();
+        let expected = concat!(
+            "vim9script\n",
+            "\n",
+            "function! g:Foo(...) dict\n",
+            "  let x = 1\n",
+            "  echo a:000\n",
+            "  return self\n",
+            "endfunction\n",
+        );
+        assert_eq!(expected, &conversion.output);
+        assert_eq!(conversion.warnings.len(), 1);
+        assert_eq!(
+            conversion.warnings[0].msg,
+            "kept as a legacy function: it uses the `dict` attribute, takes variable arguments, \
+             uses `a:000`, uses `self`"
+        );
+    }
+
+    #[test]
+    fn test_to_vim9_legacy_function() {
+        let node = parse_lines(&[
+            "let s:d = {}",
+            "function! s:d.method() dict",
+            "  let l:sum = self.x . 'y'",
+            "  let l:sum .= 'z'",
+            "  function! s:Inner()",
+            "    return a:0",
+            "  endfunction",
+            "  return l:sum . s:d.x",
+            "endfunction",
+            "call s:d.method()",
+        ])
+        .unwrap();
+        let conversion = to_vim9(&node).unwrap();
+        let expected = concat!(
+            "vim9script\n",
+            "var d = {}\n",
+            "\n",
+            "function! d.method() dict\n",
+            "  let l:sum = self.x .. 'y'\n",
+            "  let l:sum ..= 'z'\n",
+            "\n",
+            "  function! s:Inner()\n",
+            "    return a:0\n",
+            "  endfunction\n",
+            "\n",
+            "  return l:sum .. s:d.x\n",
+            "endfunction\n",
+            "\n",
+            "d.method()\n",
+        );
+        assert_eq!(expected, &conversion.output);
+        for case in [
+            &conversion.output,
+            &to_vim9(
+                &parse_lines(&[
+                    "function! Foo(...) dict",
+                    "  return a:000 . self.x",
+                    "endfunction",
+                ])
+                .unwrap(),
+            )
+            .unwrap()
+            .output,
+        ] {
+            // the parser can't read vim9 script, so check for what Vim rejects: legacy functions
+            // in a vim9 script can't concatenate with `.`, and nothing at the script level can
+            // use `s:`
+            let lines = case.lines().collect::<Vec<_>>();
+            assert!(!lines
+                .iter()
+                .any(|l| l.contains(" . ") || l.contains(" .= ")));
+            assert!(!lines
+                .iter()
+                .any(|l| !l.starts_with(' ') && l.contains("s:")));
+        }
+    }
+}
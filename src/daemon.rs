@@ -0,0 +1,219 @@
+//! A long-running formatter for editor integrations, which saves starting a process for every
+//! file. Clients connect to a Unix socket and send requests as JSON objects, one per line:
+//!
+//! ```text
+//! {"path": "plugin/foo.vim", "content": "let x=1\n", "action": "format"}
+//! ```
+//!
+//! `path` is optional and is only echoed back. `action` is optional too, and is one of
+//! `"format"` (the default), `"check"` or `"ast"`. Each request gets one line in response:
+//!
+//! ```text
+//! {"path":"plugin/foo.vim","content":"let x = 1\n"}
+//! {"path":"plugin/foo.vim","changed":true}
+//! {"path":"plugin/foo.vim","ast":{"type":"TopLevel",...}}
+//! {"path":"plugin/foo.vim","error":{"message":"...","line":1,"column":1,"code":492}}
+//! ```
+//!
+//! for `format`, `check`, `ast` and errors respectively. Errors in the request itself have only a
+//! `message`.
+use crate::{json, pipeline::Pipeline};
+use serde_json::Value;
+use std::{
+    io::{self, BufRead, BufReader, ErrorKind, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::Path,
+    thread,
+};
+
+/// Respond to one request, given as a line of JSON. The response doesn't end with a newline.
+pub fn respond(request: &str, pipeline: &Pipeline) -> String {
+    let request = match serde_json::from_str::<Value>(request) {
+        Ok(Value::Object(request)) => request,
+        Ok(_) => return request_error(None, "request is not an object"),
+        Err(e) => return request_error(None, &format!("invalid request: {}", e)),
+    };
+    let path = request.get("path").and_then(Value::as_str);
+    let content = match request.get("content").and_then(Value::as_str) {
+        Some(content) => content,
+        None => return request_error(path, "request has no content"),
+    };
+    let action = request
+        .get("action")
+        .and_then(Value::as_str)
+        .unwrap_or("format");
+    if !["format", "check", "ast"].contains(&action) {
+        return request_error(path, &format!("unknown action: {}", action));
+    }
+    let lines = content.lines().collect::<Vec<&str>>();
+    let ast = match pipeline.parse(&lines) {
+        Ok(ast) => ast,
+        Err(e) => return response(path, &json::error(&e)),
+    };
+    if action == "ast" {
+        return response(path, &format!("{{\"ast\":{}}}", json::node(&ast)));
+    }
+    match pipeline.formatter(&lines).format_source(&ast, &lines) {
+        Ok(output) if action == "check" => {
+            response(path, &format!("{{\"changed\":{}}}", output != content))
+        }
+        Ok(output) => response(path, &format!("{{\"content\":{}}}", json::string(&output))),
+        Err(e) => request_error(path, &e.to_string()),
+    }
+}
+
+/// Add the path to the members of `body`, a JSON object.
+fn response(path: Option<&str>, body: &str) -> String {
+    match path {
+        Some(path) => format!("{{\"path\":{},{}", json::string(path), &body[1..]),
+        None => body.to_string(),
+    }
+}
+
+fn request_error(path: Option<&str>, message: &str) -> String {
+    response(
+        path,
+        &format!("{{\"error\":{{\"message\":{}}}}}", json::string(message)),
+    )
+}
+
+fn serve_client(stream: UnixStream, pipeline: &Pipeline) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        writeln!(writer, "{}", respond(&line, pipeline))?;
+    }
+    Ok(())
+}
+
+/// Listen for clients on a Unix socket at `path`, answering each client's requests on its own
+/// thread. A socket left behind by a daemon that is no longer running is replaced. `log` is
+/// called with any errors talking to clients.
+///
+/// This only returns if the socket can't be created or stops accepting connections.
+pub fn serve<F>(path: &Path, pipeline: &Pipeline, log: F) -> io::Result<()>
+where
+    F: Fn(io::Error) + Clone + Send + 'static,
+{
+    let listener = match UnixListener::bind(path) {
+        Err(e) if e.kind() == ErrorKind::AddrInUse && UnixStream::connect(path).is_err() => {
+            std::fs::remove_file(path)?;
+            UnixListener::bind(path)?
+        }
+        result => result?,
+    };
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let pipeline = pipeline.clone();
+        let log = log.clone();
+        thread::spawn(move || {
+            if let Err(e) = serve_client(stream, &pipeline) {
+                log(e);
+            }
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::FormatterConfig;
+    use std::{env, process};
+    use viml_parser::Context;
+
+    #[test]
+    fn test_respond() {
+        let pipeline = Pipeline::new(FormatterConfig::default());
+        assert_eq!(
+            respond(
+                r#"{"path": "foo.vim", "content": "let x=1\nif x|echo x|endif"}"#,
+                &pipeline
+            ),
+            r#"{"path":"foo.vim","content":"let x = 1\nif x\n  echo x\nendif\n"}"#
+        );
+        assert_eq!(
+            respond(
+                r#"{"content": "let x = 1\n", "action": "check"}"#,
+                &pipeline
+            ),
+            r#"{"changed":false}"#
+        );
+        assert_eq!(
+            respond(r#"{"content": "echo 1", "action": "ast"}"#, &pipeline),
+            concat!(
+                r#"{"ast":{"type":"TopLevel","line":1,"column":1,"children":["#,
+                r#"{"type":"Echo","line":1,"column":1,"children":["#,
+                r#"{"type":"Number","line":1,"column":6,"text":"1"}]}]}}"#
+            )
+        );
+        assert_eq!(
+            respond(r#"{"path": "foo.vim", "content": "foo"}"#, &pipeline),
+            concat!(
+                r#"{"path":"foo.vim","error":{"message":"E492: Not an editor command: foo","#,
+                r#""line":1,"column":1,"code":492}}"#
+            )
+        );
+        assert_eq!(
+            respond(r#"{"content": "", "action": "lint"}"#, &pipeline),
+            r#"{"error":{"message":"unknown action: lint"}}"#
+        );
+        assert_eq!(
+            respond("[]", &pipeline),
+            r#"{"error":{"message":"request is not an object"}}"#
+        );
+        assert_eq!(
+            respond(r#"{"path": "foo.vim"}"#, &pipeline),
+            r#"{"path":"foo.vim","error":{"message":"request has no content"}}"#
+        );
+        // requests are formatted with the parser options, transforms and safe mode
+        let mut pipeline = pipeline.clone();
+        pipeline.transforms.const_fold = true;
+        pipeline.config.safe = true;
+        assert_eq!(
+            respond(r#"{"content": "let x=2*3\nCustom   foo"}"#, &pipeline),
+            r#"{"content":"let x = 6\nCustom   foo\n"}"#
+        );
+        pipeline.context = Context::Function;
+        assert_eq!(
+            respond(r#"{"content": "return\n", "action": "check"}"#, &pipeline),
+            r#"{"changed":false}"#
+        );
+    }
+
+    #[test]
+    fn test_serve() {
+        let path = env::temp_dir().join(format!("vimlfmt-test-serve-{}.sock", process::id()));
+        let server_path = path.clone();
+        let pipeline = Pipeline::new(FormatterConfig::default());
+        thread::spawn(move || serve(&server_path, &pipeline, |_| ()));
+        let stream = loop {
+            match UnixStream::connect(&path) {
+                Ok(stream) => break stream,
+                Err(_) => thread::sleep(std::time::Duration::from_millis(10)),
+            }
+        };
+        let mut writer = stream.try_clone().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        for (request, response) in &[
+            (
+                r#"{"content": "let   x=1"}"#,
+                r#"{"content":"let x = 1\n"}"#,
+            ),
+            (
+                r#"{"content": "echo 1\n", "action": "check"}"#,
+                r#"{"changed":false}"#,
+            ),
+        ] {
+            writeln!(writer, "{}", request).unwrap();
+            line.clear();
+            reader.read_line(&mut line).unwrap();
+            assert_eq!(line, format!("{}\n", response));
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,333 @@
+use std::{fmt, str::FromStr};
+
+/// A version of Vim or Neovim, e.g. 8.2 or 0.10.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl Version {
+    pub const fn new(major: u32, minor: u32) -> Self {
+        Self { major, minor }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Editor {
+    Vim,
+    Neovim,
+}
+
+impl fmt::Display for Editor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Editor::Vim => write!(f, "Vim"),
+            Editor::Neovim => write!(f, "Neovim"),
+        }
+    }
+}
+
+/// The editor and version a script is meant to run in.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Target {
+    pub editor: Editor,
+    pub version: Version,
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    /// Parse a target written as `vim` or `nvim` followed by the major version and the minor
+    /// version, without a separator, e.g. `vim82`, `vim91`, `nvim09` or `nvim010`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (editor, version) = if let Some(version) = s.strip_prefix("nvim") {
+            (Editor::Neovim, version)
+        } else if let Some(version) = s.strip_prefix("vim") {
+            (Editor::Vim, version)
+        } else {
+            return Err(format!("unknown target: {}", s));
+        };
+        let mut digits = version.chars();
+        let major = digits.next().and_then(|c| c.to_digit(10));
+        let minor = match digits.as_str() {
+            "" => Some(0),
+            minor => minor.parse().ok(),
+        };
+        match (major, minor) {
+            (Some(major), Some(minor)) => Ok(Target {
+                editor,
+                version: Version::new(major, minor),
+            }),
+            _ => Err(format!("invalid version in target: {}", s)),
+        }
+    }
+}
+
+impl Target {
+    /// The parser's [Target](../../viml_parser/enum.Target.html) for this version, if it has one:
+    /// Vim 7.4 for versions before 8.0, Vim 8.2 for the versions from 8.2 to 9.0, Vim 9 for
+    /// later versions, and Neovim for any Neovim. Vim 8.0 and 8.1 have some of what 8.2 added
+    /// and not the rest, so they have none.
+    pub fn parser_target(self) -> Option<viml_parser::Target> {
+        match self.editor {
+            Editor::Neovim => Some(viml_parser::Target::NeovimStable),
+            Editor::Vim if self.version < Version::new(8, 0) => Some(viml_parser::Target::Vim74),
+            Editor::Vim if self.version < Version::new(8, 2) => None,
+            Editor::Vim if self.version < Version::new(9, 0) => Some(viml_parser::Target::Vim82),
+            Editor::Vim => Some(viml_parser::Target::Vim9),
+        }
+    }
+}
+
+/// Whether an editor still supports a feature.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Status {
+    Supported,
+    /// Still works, but is obsolete as of this version.
+    Deprecated(Version),
+    /// Doesn't work as of this version.
+    Removed(Version),
+}
+
+/// The kind of thing a [Deprecation](struct.Deprecation.html) is about.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Feature {
+    /// An Ex command, e.g. `:sniff`.
+    Command,
+    /// An option, set with `set` or read with `&`.
+    Option,
+    /// A variable, e.g. `v:none`.
+    Variable,
+    /// A builtin function, e.g. `buffer_name()`.
+    Function,
+}
+
+/// An entry in the database of deprecated and removed features.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Deprecation {
+    pub feature: Feature,
+    /// The full name of the feature, followed by any abbreviations, e.g. `["ttyfast", "tf"]`.
+    /// Commands are only matched by their full name, since the parser expands abbreviations.
+    pub names: Vec<String>,
+    /// For commands, only match uses with these arguments (compared case-insensitively), e.g.
+    /// `filetype off` but not `filetype on`.
+    pub args: Option<String>,
+    pub vim: Status,
+    pub neovim: Status,
+    /// What to do instead, if anything.
+    pub note: String,
+}
+
+impl Deprecation {
+    /// The status of the feature in the target editor. Without a target, a feature counts as
+    /// deprecated only if neither Vim nor Neovim supports it.
+    pub fn status(&self, target: Option<Target>) -> Status {
+        match target {
+            Some(target) => {
+                let status = match target.editor {
+                    Editor::Vim => self.vim,
+                    Editor::Neovim => self.neovim,
+                };
+                match status {
+                    Status::Deprecated(since) | Status::Removed(since)
+                        if since > target.version =>
+                    {
+                        Status::Supported
+                    }
+                    status => status,
+                }
+            }
+            None => match (self.vim, self.neovim) {
+                (Status::Supported, _) | (_, Status::Supported) => Status::Supported,
+                (Status::Removed(since), Status::Removed(_)) => Status::Removed(since),
+                (Status::Deprecated(since), _) | (_, Status::Deprecated(since)) => {
+                    Status::Deprecated(since)
+                }
+            },
+        }
+    }
+
+    /// Whether `name` is one of the names of this feature.
+    pub fn matches(&self, name: &str) -> bool {
+        self.names.iter().any(|n| n == name)
+    }
+}
+
+fn entry(feature: Feature, names: &str, vim: Status, neovim: Status, note: &str) -> Deprecation {
+    Deprecation {
+        feature,
+        names: names.split(',').map(|n| n.to_string()).collect(),
+        args: None,
+        vim,
+        neovim,
+        note: note.to_string(),
+    }
+}
+
+/// The built-in database of deprecated and removed features.
+pub fn builtin() -> Vec<Deprecation> {
+    use Feature::*;
+    use Status::*;
+    let removed_from_neovim = Removed(Version::new(0, 1));
+    let mut entries = vec![
+        entry(
+            Command,
+            "sniff",
+            Removed(Version::new(8, 0)),
+            removed_from_neovim,
+            "",
+        ),
+        entry(
+            Command,
+            "behave",
+            Deprecated(Version::new(9, 1)),
+            Removed(Version::new(0, 10)),
+            "set 'selection', 'selectmode', 'mousemodel' and 'keymodel' instead",
+        ),
+        entry(
+            Command,
+            "filetype",
+            Deprecated(Version::new(8, 0)),
+            Deprecated(Version::new(0, 1)),
+            "turning filetype detection off and on again is only needed by old plugin managers",
+        ),
+    ];
+    entries[2].args = Some("off".to_string());
+    for command in &[
+        "open",
+        "fixdel",
+        "helpfind",
+        "simalt",
+        "promptfind",
+        "promptrepl",
+        "mzscheme",
+        "mzfile",
+        "tcl",
+        "tcldo",
+        "tclfile",
+    ] {
+        entries.push(entry(Command, command, Supported, removed_from_neovim, ""));
+    }
+    for option in &[
+        "compatible,cp",
+        "edcompatible,ed",
+        "esckeys,ek",
+        "guipty",
+        "macatsui",
+        "mzquantum,mzq",
+        "restorescreen,rs",
+        "shelltype,st",
+        "terse",
+        "ttybuiltin,tbi",
+        "ttyfast,tf",
+        "ttymouse,ttym",
+        "ttyscroll,tsl",
+        "weirdinvert,wiv",
+        "antialias,anti",
+        "imactivatekey,imak",
+        "imactivatefunc,imaf",
+        "imstatusfunc,imsf",
+    ] {
+        entries.push(entry(Option, option, Supported, removed_from_neovim, ""));
+    }
+    entries.push(entry(
+        Option,
+        "insertmode,im",
+        Supported,
+        Removed(Version::new(0, 7)),
+        "",
+    ));
+    for option in &["textauto,ta", "textmode,tx"] {
+        entries.push(entry(
+            Option,
+            option,
+            Deprecated(Version::new(7, 0)),
+            removed_from_neovim,
+            "use 'fileformats' and 'fileformat' instead",
+        ));
+    }
+    entries.push(entry(
+        Variable,
+        "v:none",
+        Supported,
+        removed_from_neovim,
+        "use v:null instead",
+    ));
+    for (function, replacement) in &[
+        ("buffer_exists", "bufexists"),
+        ("buffer_name", "bufname"),
+        ("buffer_number", "bufnr"),
+        ("file_readable", "filereadable"),
+        ("highlight_exists", "hlexists"),
+        ("highlightID", "hlID"),
+        ("last_buffer_nr", "bufnr('$')"),
+    ] {
+        entries.push(entry(
+            Function,
+            function,
+            Deprecated(Version::new(7, 0)),
+            Deprecated(Version::new(0, 1)),
+            &format!("use {} instead", replacement),
+        ));
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_target() {
+        assert_eq!(
+            "vim82".parse(),
+            Ok(Target {
+                editor: Editor::Vim,
+                version: Version::new(8, 2),
+            })
+        );
+        assert_eq!(
+            "nvim010".parse(),
+            Ok(Target {
+                editor: Editor::Neovim,
+                version: Version::new(0, 10),
+            })
+        );
+        assert!("emacs29".parse::<Target>().is_err());
+        assert!("vimx".parse::<Target>().is_err());
+        let parser_target = |s: &str| s.parse::<Target>().unwrap().parser_target();
+        assert_eq!(parser_target("vim74"), Some(viml_parser::Target::Vim74));
+        assert_eq!(parser_target("vim81"), None);
+        assert_eq!(parser_target("vim82"), Some(viml_parser::Target::Vim82));
+        assert_eq!(parser_target("vim91"), Some(viml_parser::Target::Vim9));
+        assert_eq!(
+            parser_target("nvim010"),
+            Some(viml_parser::Target::NeovimStable)
+        );
+    }
+
+    #[test]
+    fn test_status() {
+        let entries = builtin();
+        let behave = entries.iter().find(|d| d.matches("behave")).unwrap();
+        assert_eq!(behave.status("vim82".parse().ok()), Status::Supported);
+        assert_eq!(
+            behave.status("vim91".parse().ok()),
+            Status::Deprecated(Version::new(9, 1))
+        );
+        assert_eq!(
+            behave.status("nvim010".parse().ok()),
+            Status::Removed(Version::new(0, 10))
+        );
+        assert_eq!(behave.status(None), Status::Deprecated(Version::new(9, 1)));
+        let ttyfast = entries.iter().find(|d| d.matches("tf")).unwrap();
+        assert_eq!(ttyfast.status(None), Status::Supported);
+    }
+}
@@ -0,0 +1,158 @@
+use std::path::{Path, MAIN_SEPARATOR};
+use viml_parser::ParseError;
+
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// When to color error messages.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Color {
+    /// Color them if they are written to a terminal and `NO_COLOR` isn't set.
+    Auto,
+    Always,
+    Never,
+}
+
+impl Color {
+    /// Whether to color output written to a stream, given whether it's a terminal.
+    pub fn enabled(self, is_terminal: bool) -> bool {
+        match self {
+            Color::Auto => is_terminal && std::env::var_os("NO_COLOR").is_none(),
+            Color::Always => true,
+            Color::Never => false,
+        }
+    }
+}
+
+/// A path as messages show it, with `/` between directories on every platform, so that output
+/// checked in CI is the same on Windows as elsewhere.
+pub fn display_path(path: &Path) -> String {
+    let path = path.display().to_string();
+    if MAIN_SEPARATOR == '/' {
+        path
+    } else {
+        path.replace(MAIN_SEPARATOR, "/")
+    }
+}
+
+/// Render a parse error along with the line of `source` it was found on, with the text it is about
+/// underlined, e.g.
+///
+/// ```text
+/// error: E492: Not an editor command: foo
+///  --> plugin/foo.vim:3:1
+///   |
+/// 3 | foo bar
+///   | ^^^
+/// ```
+///
+/// The file is shown as `path`, or else the error's own path, or else `<stdin>`. Errors at the end
+/// of the input, which have no line to show, are rendered without one. The result doesn't end
+/// with a newline.
+pub fn render(error: &ParseError, source: &[&str], path: Option<&str>, color: bool) -> String {
+    let paint = |style: &str, text: &str| {
+        if color {
+            format!("{}{}{}", style, text, RESET)
+        } else {
+            text.to_string()
+        }
+    };
+    let (line, column) = (error.pos.line(), error.pos.column().max(1));
+    let mut rv = format!(
+        "{}{}",
+        paint(RED, "error"),
+        paint(BOLD, &format!(": {}", error.message()))
+    );
+    let path = path.map(Path::new).or(error.path.as_deref());
+    let location = format!(
+        "{}:{}:{}",
+        path.map_or("<stdin>".to_string(), display_path),
+        line,
+        column
+    );
+    let text = match line.checked_sub(1).and_then(|i| source.get(i)) {
+        Some(text) => text,
+        None => {
+            rv.push_str(&format!("\n {} {}", paint(BLUE, "-->"), location));
+            return rv;
+        }
+    };
+    let gutter = " ".repeat(line.to_string().len());
+    let width = match error.end() {
+        Some((_, end)) if end > column => end - column,
+        _ => 1,
+    };
+    // Keep tabs before the error so the carets line up with the text above them.
+    let indent = text
+        .chars()
+        .take(column - 1)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect::<String>();
+    rv.push_str(&format!("\n{}{} {}", gutter, paint(BLUE, "-->"), location));
+    rv.push_str(&format!("\n{} {}", gutter, paint(BLUE, "|")));
+    rv.push_str(&format!(
+        "\n{} {}",
+        paint(BLUE, &format!("{} |", line)),
+        text
+    ));
+    rv.push_str(&format!(
+        "\n{} {} {}{}",
+        gutter,
+        paint(BLUE, "|"),
+        indent,
+        paint(RED, &"^".repeat(width))
+    ));
+    rv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use viml_parser::parse_lines;
+
+    #[test]
+    fn test_render() {
+        let source = ["let x = 1", "\tfoo bar"];
+        let error = parse_lines(&source).unwrap_err();
+        assert_eq!(
+            render(&error, &source, Some("foo.vim"), false),
+            concat!(
+                "error: E492: Not an editor command: foo bar\n",
+                " --> foo.vim:2:2\n",
+                "  |\n",
+                "2 | \tfoo bar\n",
+                "  | \t^^^^^^^",
+            )
+        );
+        assert_eq!(
+            render(&error, &source, None, true),
+            concat!(
+                "\x1b[1;31merror\x1b[0m\x1b[1m: E492: Not an editor command: foo bar\x1b[0m\n",
+                " \x1b[1;34m-->\x1b[0m <stdin>:2:2\n",
+                "  \x1b[1;34m|\x1b[0m\n",
+                "\x1b[1;34m2 |\x1b[0m \tfoo bar\n",
+                "  \x1b[1;34m|\x1b[0m \t\x1b[1;31m^^^^^^^\x1b[0m",
+            )
+        );
+        let source = ["if x"];
+        let error = parse_lines(&source).unwrap_err();
+        assert!(render(&error, &source, None, false).ends_with("\n --> <stdin>:2:1"));
+        assert!(Color::Always.enabled(false));
+        assert!(!Color::Never.enabled(true));
+        assert!(!Color::Auto.enabled(false));
+    }
+
+    #[test]
+    fn test_display_path() {
+        let path = Path::new("plugin").join("foo.vim");
+        assert_eq!(display_path(&path), "plugin/foo.vim");
+        let source = ["foo"];
+        let mut error = parse_lines(&source).unwrap_err();
+        error.path = Some(path.clone());
+        assert!(render(&error, &source, None, false).contains(" --> plugin/foo.vim:1:1\n"));
+        let path = path.to_str().unwrap();
+        assert!(render(&error, &source, Some(path), false).contains(" --> plugin/foo.vim:1:1\n"));
+    }
+}
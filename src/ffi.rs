@@ -0,0 +1,271 @@
+//! C bindings, for calling the formatter in-process from Vim's `libcall()` or from other editors.
+//! The functions are only exported when built with the `ffi` feature, e.g.
+//! `cargo build --release --features ffi` for `target/release/libvimlfmt.so`.
+//!
+//! Strings passed in are borrowed and must be NUL-terminated UTF-8. Strings returned by
+//! `vimlfmt_format` and `vimlfmt_parse_json` belong to the caller and must be released with
+//! `vimlfmt_free`. Strings returned by `vimlfmt_last_error` and `vimlfmt_libcall_format` belong
+//! to the library and are only valid until the next call from the same thread. A panic is never
+//! let out into the caller; the call fails instead, and `vimlfmt_last_error` says why.
+use crate::{
+    config::{FormatterConfig, OneLineBlocks, Syntax},
+    formatter::Formatter,
+    json,
+};
+use std::{
+    cell::RefCell,
+    ffi::{CStr, CString},
+    os::raw::c_char,
+    panic::{self, AssertUnwindSafe},
+    ptr,
+};
+use viml_parser::parse_lines;
+
+/// Formatting options for `vimlfmt_format`. Passing `NULL` uses the defaults, which are the same
+/// as the `vimlfmt` command's, e.g. the output ends with a newline.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Options {
+    pub preserve_one_line_blocks: bool,
+    pub sort_set_lists: bool,
+    pub merge_set_lines: bool,
+    pub keep_trailing_whitespace: bool,
+    pub final_newline: bool,
+    pub vim9: bool,
+}
+
+impl From<&Options> for FormatterConfig {
+    fn from(options: &Options) -> Self {
+        Self {
+            one_line_blocks: if options.preserve_one_line_blocks {
+                OneLineBlocks::Preserve
+            } else {
+                OneLineBlocks::Expand
+            },
+            syntax: if options.vim9 {
+                Syntax::Vim9
+            } else {
+                Syntax::Legacy
+            },
+            sort_set_lists: options.sort_set_lists,
+            merge_set_lines: options.merge_set_lines,
+            trim_trailing_whitespace: !options.keep_trailing_whitespace,
+            ensure_final_newline: options.final_newline,
+            ..FormatterConfig::default()
+        }
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+    static LIBCALL_RESULT: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(msg: String) {
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(to_c_string(msg)));
+}
+
+/// Interior NULs can't be represented in a C string, so they are dropped.
+fn to_c_string(s: String) -> CString {
+    CString::new(s.replace('\0', "")).unwrap_or_default()
+}
+
+unsafe fn from_c_str<'a>(source: *const c_char) -> Result<&'a str, String> {
+    if source.is_null() {
+        return Err("source is NULL".to_string());
+    }
+    CStr::from_ptr(source)
+        .to_str()
+        .map_err(|e| format!("source is not valid UTF-8: {}", e))
+}
+
+/// Run `f`, turning a panic into an error, since unwinding into C code is undefined behavior.
+fn catch_panic<T>(f: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        Err(format!("internal error: {}", message))
+    })
+}
+
+fn format(source: &str, config: FormatterConfig) -> Result<String, String> {
+    let lines = source.lines().collect::<Vec<&str>>();
+    let ast = parse_lines(&lines).map_err(|e| e.to_string())?;
+    Formatter::with_config(config)
+        .format(&ast)
+        .map_err(|e| e.to_string())
+}
+
+/// Format VimL source, returning the formatted code or `NULL` if it couldn't be formatted, in
+/// which case `vimlfmt_last_error` says why.
+///
+/// # Safety
+///
+/// `source` must be `NULL` or a NUL-terminated string, and `options` must be `NULL` or point to
+/// valid `Options`.
+#[no_mangle]
+pub unsafe extern "C" fn vimlfmt_format(
+    source: *const c_char,
+    options: *const Options,
+) -> *mut c_char {
+    let formatted = catch_panic(|| {
+        let config = options
+            .as_ref()
+            .map(FormatterConfig::from)
+            .unwrap_or_default();
+        from_c_str(source).and_then(|source| format(source, config))
+    });
+    match formatted {
+        Ok(output) => to_c_string(output).into_raw(),
+        Err(e) => {
+            set_last_error(e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Parse VimL source, returning the syntax tree as JSON (see the `json` module), or an object
+/// with an `error` member if it couldn't be parsed. Only returns `NULL` if `source` is `NULL` or
+/// not UTF-8, or if parsing panics, in which case `vimlfmt_last_error` says why.
+///
+/// # Safety
+///
+/// `source` must be `NULL` or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn vimlfmt_parse_json(source: *const c_char) -> *mut c_char {
+    let parsed = catch_panic(|| {
+        let lines = from_c_str(source)?.lines().collect::<Vec<&str>>();
+        Ok(match parse_lines(&lines) {
+            Ok(ast) => json::node(&ast),
+            Err(e) => json::error(&e),
+        })
+    });
+    match parsed {
+        Ok(output) => to_c_string(output).into_raw(),
+        Err(e) => {
+            set_last_error(e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Release a string returned by `vimlfmt_format` or `vimlfmt_parse_json`. Does nothing if `s` is
+/// `NULL`.
+///
+/// # Safety
+///
+/// `s` must be `NULL` or a string returned by one of those functions that hasn't been released.
+#[no_mangle]
+pub unsafe extern "C" fn vimlfmt_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// The reason the last call from this thread failed, or `NULL` if none has.
+#[no_mangle]
+pub extern "C" fn vimlfmt_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+}
+
+/// Format VimL source with the default options, for `libcall()`, which can't free what it is
+/// given. Returns the formatted code, or an empty string if it couldn't be formatted, e.g.
+///
+/// ```vim
+/// let formatted = libcall('libvimlfmt.so', 'vimlfmt_libcall_format', join(getline(1, '$'), "\n"))
+/// ```
+///
+/// # Safety
+///
+/// `source` must be `NULL` or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn vimlfmt_libcall_format(source: *const c_char) -> *const c_char {
+    let formatted = catch_panic(|| from_c_str(source).and_then(|s| format(s, Default::default())));
+    let output = match formatted {
+        Ok(output) => output,
+        Err(e) => {
+            set_last_error(e);
+            String::new()
+        }
+    };
+    LIBCALL_RESULT.with(|r| r.borrow_mut().insert(to_c_string(output)).as_ptr())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn take(s: *mut c_char) -> String {
+        let rv = CStr::from_ptr(s).to_str().unwrap().to_string();
+        vimlfmt_free(s);
+        rv
+    }
+
+    #[test]
+    fn test_format() {
+        let source = CString::new("let   x=1\nif x|echo x|endif").unwrap();
+        let options = Options {
+            preserve_one_line_blocks: true,
+            sort_set_lists: false,
+            merge_set_lines: false,
+            keep_trailing_whitespace: false,
+            final_newline: true,
+            vim9: false,
+        };
+        unsafe {
+            assert_eq!(
+                take(vimlfmt_format(source.as_ptr(), ptr::null())),
+                "let x = 1\nif x\n  echo x\nendif\n"
+            );
+            assert_eq!(
+                take(vimlfmt_format(source.as_ptr(), &options)),
+                "let x = 1\nif x | echo x | endif\n"
+            );
+            let bad = CString::new("if x").unwrap();
+            assert!(vimlfmt_format(bad.as_ptr(), ptr::null()).is_null());
+            assert!(CStr::from_ptr(vimlfmt_last_error())
+                .to_str()
+                .unwrap()
+                .contains("E126"));
+            assert!(vimlfmt_format(ptr::null(), ptr::null()).is_null());
+            vimlfmt_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let source = CString::new("echo 1").unwrap();
+        let bad = CString::new("foo").unwrap();
+        unsafe {
+            assert!(take(vimlfmt_parse_json(source.as_ptr())).starts_with(
+                r#"{"type":"TopLevel","line":1,"column":1,"children":[{"type":"Echo""#
+            ));
+            assert!(take(vimlfmt_parse_json(bad.as_ptr())).starts_with(r#"{"error":"#));
+        }
+    }
+
+    #[test]
+    fn test_catch_panic() {
+        assert_eq!(catch_panic(|| Ok(1)), Ok(1));
+        assert_eq!(
+            catch_panic::<()>(|| panic!("oops")),
+            Err("internal error: oops".to_string())
+        );
+        assert_eq!(
+            catch_panic::<()>(|| panic!("{} oops", 2)),
+            Err("internal error: 2 oops".to_string())
+        );
+    }
+
+    #[test]
+    fn test_libcall_format() {
+        let source = CString::new("let   x=1").unwrap();
+        unsafe {
+            let output = vimlfmt_libcall_format(source.as_ptr());
+            assert_eq!(CStr::from_ptr(output).to_str().unwrap(), "let x = 1\n");
+        }
+    }
+}
@@ -0,0 +1,133 @@
+/// A change to the source: the text from `start` up to (but not including) `end` is replaced
+/// with `text`. Positions are 1-based lines and columns, with columns counted in characters as
+/// they are in [Position](../../viml_parser/struct.Position.html). An edit whose start and end are
+/// the same inserts text.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Edit {
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+    pub text: String,
+}
+
+impl Edit {
+    pub fn insert<T: ToString>(at: (usize, usize), text: T) -> Self {
+        Self {
+            start: at,
+            end: at,
+            text: text.to_string(),
+        }
+    }
+
+    pub fn replace<T: ToString>(start: (usize, usize), end: (usize, usize), text: T) -> Self {
+        Self {
+            start,
+            end,
+            text: text.to_string(),
+        }
+    }
+
+    /// Whether two edits touch the same text, so applying both would be ambiguous. Insertions at
+    /// the same place conflict, since either could go first, but an insertion at either end of a
+    /// replacement doesn't.
+    pub fn conflicts(&self, other: &Edit) -> bool {
+        if self.start == self.end && other.start == other.end {
+            return self.start == other.start;
+        }
+        self.start < other.end && other.start < self.end
+    }
+}
+
+/// The changes that fix one problem, which are applied together or not at all.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Fix {
+    pub edits: Vec<Edit>,
+}
+
+impl Fix {
+    pub fn conflicts(&self, other: &Fix) -> bool {
+        self.edits
+            .iter()
+            .any(|a| other.edits.iter().any(|b| a.conflicts(b)))
+    }
+}
+
+/// Apply fixes to the lines of a file. Fixes are taken in order, and any fix that conflicts with
+/// one already taken is skipped, so it can be tried again on the result. Returns the new lines
+/// and the indexes of the fixes that were applied.
+pub fn apply(source: &[&str], fixes: &[&Fix]) -> (Vec<String>, Vec<usize>) {
+    let mut applied: Vec<usize> = vec![];
+    for (i, fix) in fixes.iter().enumerate() {
+        if !applied.iter().any(|&j| fixes[j].conflicts(fix)) {
+            applied.push(i);
+        }
+    }
+    let mut edits = applied
+        .iter()
+        .flat_map(|&i| fixes[i].edits.iter())
+        .collect::<Vec<&Edit>>();
+    // Apply the edits from the end of the file backward, so the positions of those still to be
+    // applied aren't moved by the ones that have been. A replacement goes before an insertion at
+    // the same place, so the insertion ends up in front of the replacement.
+    edits.sort_by(|a, b| b.start.cmp(&a.start).then(b.end.cmp(&a.end)));
+    let mut lines = source
+        .iter()
+        .map(|l| l.chars().collect::<Vec<char>>())
+        .collect::<Vec<Vec<char>>>();
+    if lines.is_empty() {
+        lines.push(vec![]);
+    }
+    for edit in edits {
+        let (start_line, start_col) = clamp(&lines, edit.start);
+        let (end_line, end_col) = clamp(&lines, edit.end);
+        let mut replaced = lines[start_line][..start_col].to_vec();
+        replaced.extend(edit.text.chars());
+        replaced.extend_from_slice(&lines[end_line][end_col..]);
+        let replaced = replaced
+            .split(|&c| c == '\n')
+            .map(|l| l.to_vec())
+            .collect::<Vec<Vec<char>>>();
+        lines.splice(start_line..=end_line, replaced);
+    }
+    let lines = lines.into_iter().map(|l| l.into_iter().collect()).collect();
+    (lines, applied)
+}
+
+/// A position as 0-based indexes into `lines`, kept within the lines that exist.
+fn clamp(lines: &[Vec<char>], (line, col): (usize, usize)) -> (usize, usize) {
+    let line = line.saturating_sub(1).min(lines.len().saturating_sub(1));
+    let col = col
+        .saturating_sub(1)
+        .min(lines.get(line).map_or(0, |l| l.len()));
+    (line, col)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let source = ["let x = 1", "echo x"];
+        let first = Fix {
+            edits: vec![
+                Edit::replace((1, 5), (1, 6), "y"),
+                Edit::replace((2, 6), (2, 7), "y"),
+            ],
+        };
+        let overlapping = Fix {
+            edits: vec![Edit::replace((1, 1), (1, 8), "const z =")],
+        };
+        let inserting = Fix {
+            edits: vec![
+                Edit::insert((1, 1), "\" comment\n"),
+                Edit::insert((2, 7), " | echo 2"),
+            ],
+        };
+        let (lines, applied) = apply(&source, &[&first, &overlapping, &inserting]);
+        assert_eq!(applied, vec![0, 2]);
+        assert_eq!(lines, vec!["\" comment", "let y = 1", "echo y | echo 2"]);
+        assert!(Edit::insert((1, 1), "a").conflicts(&Edit::insert((1, 1), "b")));
+        assert!(!Edit::insert((1, 5), "a").conflicts(&Edit::replace((1, 1), (1, 5), "b")));
+        assert!(Edit::insert((1, 3), "a").conflicts(&Edit::replace((1, 1), (1, 5), "b")));
+    }
+}
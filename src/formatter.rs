@@ -1,18 +1,62 @@
-use std::io::{Error, ErrorKind};
-use viml_parser::{Modifier, Node};
+use crate::{
+    config::{
+        Concatenation, ContinuationIndent, Continuations, EventCase, FormatterConfig,
+        HighlightAttrOrder, MappingAttrOrder, OneLineBlocks, Syntax, TrailingCommas,
+    },
+    convert,
+    report::{Report, Verbatim, VerbatimKind},
+    source_map::{Mapping, SourceMap},
+};
+use std::{
+    borrow::Cow,
+    cmp::min,
+    collections::HashSet,
+    io::{self, Error, ErrorKind, Write},
+    ops::Range,
+    process::{self, Stdio},
+    thread,
+};
+use unicode_width::UnicodeWidthChar;
+use viml_parser::{
+    canonical_autocmd_event, canonical_key, canonical_key_notation_with, key_notation,
+    keymap_lines, option_full_name, sort_modifiers, split_keys, split_option, BinaryOpKind, Key,
+    KeymapLine, Modifier, Node, ParseIter, Position, Trivia,
+};
 
 const INDENT: &str = "  ";
+const BYTE_ORDER_MARK: char = '\u{feff}';
+const TAB_WIDTH: usize = 8;
 const CONTINUATION: usize = 3;
-const MAX_LEN: usize = 80;
+
+// the column (counted from 0) that text starting at `column` ends at on screen, with tabs going
+// to the next multiple of 8 and wide characters (e.g. CJK ideographs) taking two columns
+fn end_column(column: usize, s: &str) -> usize {
+    s.chars().fold(column, |column, c| match c {
+        '\t' => column + TAB_WIDTH - column % TAB_WIDTH,
+        c => column + c.width().unwrap_or(0),
+    })
+}
+
+// the width of text on screen, when it starts a line
+fn display_width(s: &str) -> usize {
+    end_column(0, s)
+}
+
+// text followed by enough spaces to make it at least `width` columns wide on screen
+fn pad(s: &str, width: usize) -> String {
+    format!(
+        "{}{}",
+        s,
+        " ".repeat(width.saturating_sub(display_width(s)))
+    )
+}
 
 fn node_is_atom(node: &Node) -> bool {
     // not building this into the Node struct because this only has meaning in the context of the
     // formatter. in this case "atom" means a node that is a singular, i.e. whose value can only be
     // used as part of a more complex expression and is basically meaningless on its own.
     match node {
-        Node::CurlyName { .. }
-        | Node::CurlyNameExpr { .. }
-        | Node::CurlyNamePart { .. }
+        Node::CurlyNamePart { .. }
         | Node::Env { .. }
         | Node::Identifier { .. }
         | Node::Number { .. }
@@ -23,47 +67,614 @@ fn node_is_atom(node: &Node) -> bool {
     }
 }
 
+fn pos_is_operator(node: &Node) -> bool {
+    // the position of these nodes is that of an operator or bracket after their first operand,
+    // not of the start of the expression.
+    matches!(
+        node,
+        Node::BinaryOp { .. }
+            | Node::Call { .. }
+            | Node::Dot { .. }
+            | Node::Method { .. }
+            | Node::Slice { .. }
+            | Node::Subscript { .. }
+            | Node::Ternary { .. }
+    )
+}
+
+/// Run `command` with `sh -c`, passing it `script` on stdin, and return what it writes to
+/// stdout. `None` if the command fails, in which case the script is left as it was.
+fn run_heredoc_formatter(command: &str, script: &str) -> Option<String> {
+    let mut child = process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    let mut stdin = child.stdin.take()?;
+    let input = format!("{}\n", script);
+    // write from another thread so a command that starts writing before it has read everything
+    // can't fill the pipe and deadlock
+    let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child.wait_with_output().ok()?;
+    writer.join().ok()?.ok()?;
+    if output.status.success() {
+        String::from_utf8(output.stdout).ok()
+    } else {
+        None
+    }
+}
+
+fn end_cursor(end: &Option<Box<Node>>) -> usize {
+    end.as_ref().map_or(usize::MAX, |e| e.pos().cursor())
+}
+
+fn returns_value(node: &Node) -> bool {
+    match node {
+        Node::Return { left, .. } => left.is_some(),
+        Node::Function { .. } => false,
+        _ => node.children().into_iter().any(returns_value),
+    }
+}
+
+// comma-separated list options whose order doesn't matter, so their values can be sorted
+const UNORDERED_LIST_OPTIONS: &[&str] = &[
+    "backupskip",
+    "bsk",
+    "belloff",
+    "bo",
+    "completeopt",
+    "cot",
+    "foldclose",
+    "fcl",
+    "foldopen",
+    "fdo",
+    "nrformats",
+    "nf",
+    "sessionoptions",
+    "ssop",
+    "suffixes",
+    "su",
+    "switchbuf",
+    "swb",
+    "viewoptions",
+    "vop",
+    "virtualedit",
+    "ve",
+    "whichwrap",
+    "ww",
+    "wildignore",
+    "wig",
+    "wildoptions",
+    "wop",
+];
+
+// comma-separated list options where order matters, so values can only be deduplicated
+const ORDERED_LIST_OPTIONS: &[&str] = &[
+    "backupdir",
+    "bdir",
+    "cdpath",
+    "cd",
+    "complete",
+    "cpt",
+    "dictionary",
+    "dict",
+    "directory",
+    "dir",
+    "fileencodings",
+    "fencs",
+    "fileformats",
+    "ffs",
+    "packpath",
+    "pp",
+    "path",
+    "pa",
+    "runtimepath",
+    "rtp",
+    "suffixesadd",
+    "sua",
+    "tags",
+    "tag",
+    "thesaurus",
+    "tsr",
+    "undodir",
+    "udir",
+];
+
+fn sort_set_list(option: &str, op: &str, value: &str) -> String {
+    // `-=` removes its value as a single string, so its items can't be touched. values with
+    // escapes (e.g. `\,`) are left alone rather than risk splitting them in the wrong place.
+    let sort = UNORDERED_LIST_OPTIONS.contains(&option);
+    if (!sort && !ORDERED_LIST_OPTIONS.contains(&option))
+        || op == "-="
+        || op.is_empty()
+        || value.contains('\\')
+    {
+        return value.to_string();
+    }
+    let mut items: Vec<&str> = vec![];
+    for item in value.split(',') {
+        if item.is_empty() {
+            return value.to_string();
+        }
+        if !items.contains(&item) {
+            items.push(item);
+        }
+    }
+    if sort {
+        items.sort_unstable();
+    }
+    items.join(",")
+}
+
+fn mergeable_set(node: &Node) -> Option<&str> {
+    match node {
+        Node::Set {
+            mods,
+            cmd,
+            bang: false,
+            args,
+            ..
+        } if mods.is_empty() && !args.is_empty() => Some(cmd),
+        _ => None,
+    }
+}
+
+fn echohl_arg(node: &Node) -> Option<&str> {
+    match node {
+        Node::ExCmdRaw { command, args, .. } if command == "echohl" => Some(args.trim()),
+        _ => None,
+    }
+}
+
+fn starts_echohl_chain(node: &Node) -> bool {
+    matches!(echohl_arg(node), Some(group) if !group.eq_ignore_ascii_case("none"))
+}
+
+fn echohl_chain(body: &[Node]) -> (usize, bool) {
+    // the length of the `echohl Group | echo ... | echohl None` chain at the start of the body,
+    // all on one line in the source, and whether it is complete
+    let line = match body.first() {
+        Some(node) if starts_echohl_chain(node) => node.pos().line(),
+        _ => return (0, false),
+    };
+    for (i, node) in body.iter().enumerate().skip(1) {
+        if node.pos().line() != line {
+            return (i, false);
+        }
+        match echohl_arg(node) {
+            Some(g) if g.eq_ignore_ascii_case("none") && i > 1 => return (i + 1, true),
+            None if matches!(node, Node::Echo { .. }) => (),
+            _ => return (i, false),
+        }
+    }
+    (body.len(), false)
+}
+
+fn merge_set_lines(body: &[Node]) -> Cow<'_, [Node]> {
+    // consecutive set commands are merged into the first one, as long as they are the same
+    // command (so `set` and `setlocal` are kept apart) and have no modifiers.
+    if !body
+        .windows(2)
+        .any(|w| mergeable_set(&w[0]).is_some() && mergeable_set(&w[0]) == mergeable_set(&w[1]))
+    {
+        return Cow::Borrowed(body);
+    }
+    let mut merged: Vec<Node> = vec![];
+    for node in body {
+        if let Some(cmd) = mergeable_set(node) {
+            if let Some(Node::Set {
+                mods,
+                cmd: prev_cmd,
+                bang: false,
+                args: prev_args,
+                ..
+            }) = merged.last_mut()
+            {
+                if mods.is_empty() && !prev_args.is_empty() && prev_cmd == cmd {
+                    if let Node::Set { args, .. } = node {
+                        prev_args.extend(args.iter().cloned());
+                    }
+                    continue;
+                }
+            }
+        }
+        merged.push(node.clone());
+    }
+    Cow::Owned(merged)
+}
+
+const CANONICAL_MAPPING_ATTRS: &[&str] = &[
+    "buffer", "nowait", "silent", "special", "script", "expr", "unique",
+];
+
+const CANONICAL_HIGHLIGHT_ATTRS: &[&str] = &[
+    "term", "cterm", "ctermfg", "ctermbg", "gui", "guifg", "guibg", "guisp",
+];
+
+// Vim's and Neovim's built-in highlight groups, spelled the way the documentation does
+const HIGHLIGHT_GROUPS: &[&str] = &[
+    "Added",
+    "Boolean",
+    "Changed",
+    "Character",
+    "ColorColumn",
+    "Comment",
+    "Conceal",
+    "Conditional",
+    "Constant",
+    "CurSearch",
+    "Cursor",
+    "CursorColumn",
+    "CursorIM",
+    "CursorLine",
+    "CursorLineFold",
+    "CursorLineNr",
+    "CursorLineSign",
+    "Debug",
+    "Define",
+    "Delimiter",
+    "DiffAdd",
+    "DiffChange",
+    "DiffDelete",
+    "DiffText",
+    "Directory",
+    "EndOfBuffer",
+    "Error",
+    "ErrorMsg",
+    "Exception",
+    "Float",
+    "FloatBorder",
+    "FloatTitle",
+    "FoldColumn",
+    "Folded",
+    "Function",
+    "Identifier",
+    "Ignore",
+    "IncSearch",
+    "Include",
+    "Keyword",
+    "Label",
+    "LineNr",
+    "LineNrAbove",
+    "LineNrBelow",
+    "Macro",
+    "MatchParen",
+    "Menu",
+    "MessageWindow",
+    "ModeMsg",
+    "MoreMsg",
+    "MsgArea",
+    "MsgSeparator",
+    "NonText",
+    "Normal",
+    "NormalFloat",
+    "NormalNC",
+    "Number",
+    "Operator",
+    "Pmenu",
+    "PmenuExtra",
+    "PmenuExtraSel",
+    "PmenuKind",
+    "PmenuKindSel",
+    "PmenuSbar",
+    "PmenuSel",
+    "PmenuThumb",
+    "PopupNotification",
+    "PreCondit",
+    "PreProc",
+    "Question",
+    "QuickFixLine",
+    "Removed",
+    "Repeat",
+    "Scrollbar",
+    "Search",
+    "SignColumn",
+    "Special",
+    "SpecialChar",
+    "SpecialComment",
+    "SpecialKey",
+    "SpellBad",
+    "SpellCap",
+    "SpellLocal",
+    "SpellRare",
+    "Statement",
+    "StatusLine",
+    "StatusLineNC",
+    "StatusLineTerm",
+    "StatusLineTermNC",
+    "StorageClass",
+    "String",
+    "Structure",
+    "Substitute",
+    "TabLine",
+    "TabLineFill",
+    "TabLineSel",
+    "Tag",
+    "TermCursor",
+    "TermCursorNC",
+    "Terminal",
+    "Title",
+    "Todo",
+    "ToolbarButton",
+    "ToolbarLine",
+    "Tooltip",
+    "Type",
+    "Typedef",
+    "Underlined",
+    "User1",
+    "User2",
+    "User3",
+    "User4",
+    "User5",
+    "User6",
+    "User7",
+    "User8",
+    "User9",
+    "VertSplit",
+    "Visual",
+    "VisualNOS",
+    "WarningMsg",
+    "Whitespace",
+    "WildMenu",
+    "WinBar",
+    "WinBarNC",
+    "WinSeparator",
+    "lCursor",
+];
+
+/// Whether none of the statements of a block, or of its clauses, are blocks themselves, e.g.
+/// `if x | return | endif` but not `if x | for y in z | endfor | endif`.
+fn has_only_simple_statements(node: &Node) -> bool {
+    node.children().into_iter().all(|child| match child {
+        Node::ElseIf { .. } | Node::Else { .. } | Node::Catch { .. } | Node::Finally { .. } => {
+            has_only_simple_statements(child)
+        }
+        _ => !Node::has_body(child),
+    })
+}
+
+/// Remove the whitespace at the end of a line, except for a space or tab escaped with a backslash
+/// or CTRL-V, which Vim keeps as part of the command, e.g. in `set fillchars=fold:\ `.
+fn trim_line_end(line: &str) -> &str {
+    let trimmed = line.trim_end();
+    match line[trimmed.len()..].chars().next() {
+        Some(c) if trimmed.ends_with(['\\', '\x16']) => &line[..trimmed.len() + c.len_utf8()],
+        _ => trimmed,
+    }
+}
+
+/// Whether a statement has parts the parser keeps as text rather than parsing, i.e. the arguments
+/// of commands it doesn't parse (including user commands) and embedded scripts.
+fn has_raw_parts(node: &Node) -> bool {
+    match node {
+        Node::ExCmdRaw { args, .. } => !args.trim().is_empty(),
+        Node::Lang { .. } => true,
+        _ => node.children().into_iter().any(has_raw_parts),
+    }
+}
+
+/// Whether a comment is the `" vimlfmt: keep-sorted` directive, which sorts the lists in the
+/// statements after it, up to the next blank line or comment.
+fn is_keep_sorted(node: &Node) -> bool {
+    matches!(node, Node::Comment { value, trailing: false, .. } if value.trim() == "vimlfmt: keep-sorted")
+}
+
+/// The value of a number literal. A number with a leading zero is left alone, since legacy script
+/// reads `017` as octal and Vim9 script as decimal.
+fn number_value(value: &str) -> Option<f64> {
+    let lower = value.to_lowercase();
+    let (digits, radix) = if let Some(hex) = lower.strip_prefix("0x") {
+        (hex, 16)
+    } else if let Some(binary) = lower.strip_prefix("0b") {
+        (binary, 2)
+    } else if lower.len() > 1 && lower.starts_with('0') && !lower.contains(['.', 'e']) {
+        return None;
+    } else {
+        return lower.parse().ok();
+    };
+    i64::from_str_radix(digits, radix).ok().map(|n| n as f64)
+}
+
+/// The contents of a string literal, or `None` for a double-quoted string with escapes other than
+/// `\\` and `\"`.
+fn string_contents(value: &str) -> Option<String> {
+    let inner = &value[1..value.len() - 1];
+    if value.starts_with('\'') {
+        return Some(inner.replace("''", "'"));
+    }
+    let mut contents = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(c @ ('\\' | '"')) => contents.push(c),
+                _ => return None,
+            },
+            c => contents.push(c),
+        }
+    }
+    Some(contents)
+}
+
+fn sort_lists(node: &mut Node) {
+    // lists made up only of strings are sorted by their contents, and lists made up only of
+    // numbers by their values. other lists are left as they are, though lists in them are sorted.
+    for child in node.children_mut() {
+        sort_lists(child);
+    }
+    if let Node::List { items, .. } = node {
+        if let Some(mut strings) = items
+            .iter()
+            .map(|i| match i {
+                Node::String { value, .. } => string_contents(value).map(|s| (s, i.clone())),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()
+        {
+            strings.sort_by(|a, b| a.0.cmp(&b.0));
+            *items = strings.into_iter().map(|(_, i)| i).collect();
+        } else if let Some(mut numbers) = items
+            .iter()
+            .map(|i| match i {
+                Node::Number { value, .. } => number_value(value).map(|n| (n, i.clone())),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()
+        {
+            numbers.sort_by(|a, b| a.0.total_cmp(&b.0));
+            *items = numbers.into_iter().map(|(_, i)| i).collect();
+        }
+    }
+}
+
+/// Rewrite the strings concatenated to make an argument of `execute` with single quotes where that
+/// doesn't change them, and the operators concatenating them as `op`, if given.
+fn normalize_execute_arg(node: &mut Node, single_quote: bool, op: Option<&BinaryOpKind>) {
+    match node {
+        Node::BinaryOp {
+            op: node_op @ (BinaryOpKind::Concat | BinaryOpKind::StringConcat),
+            left,
+            right,
+            ..
+        } => {
+            if let Some(op) = op {
+                *node_op = op.clone();
+            }
+            normalize_execute_arg(left, single_quote, op);
+            normalize_execute_arg(right, single_quote, op);
+        }
+        Node::String { value, .. } if single_quote && value.starts_with('"') => {
+            let inner = &value[1..value.len() - 1];
+            if !inner.contains('\\') {
+                *value = format!("'{}'", inner.replace('\'', "''"));
+            }
+        }
+        _ => (),
+    }
+}
+
+// the width of the part of each of a run of highlight commands before the attributes, and the
+// name and width of each column of attributes
+type AttrLayout = (usize, Vec<(String, usize)>);
+
+// the source lines and output lines of a statement, and whether it counts as one in a report
+type StatementSpan = (Range<usize>, Range<usize>, bool);
+
 #[derive(Debug)]
 pub struct Formatter {
+    config: FormatterConfig,
     output: Vec<String>,
     current_indent: usize,
     line: String,
     last_line_was_blank: bool,
+    // the blank lines written in a row, and the blank lines in the source in a row up to the
+    // statement being formatted
+    blank_lines: usize,
+    source_blank_lines: usize,
     current_continuation_indent: usize, // indent beyond the next line backslash
+    declared: Vec<HashSet<String>>,     // vim9 variables declared in each enclosing scope
+    trivia: Vec<Trivia>,
+    next_trivia: usize, // index of the first trivia not yet written
+    breaks: Vec<usize>, // cursors of the line continuations in the source
+    next_break: usize,  // index of the first line continuation not yet reached
+    preserve_breaks: bool,
+    raw_line: bool,        // the current line ends with text that must not be trimmed
+    byte_order_mark: bool, // the output must start with a byte order mark
+    // the source line and first output line of each statement written, in order
+    statement_lines: Vec<(usize, usize, bool)>,
+    // when only some lines are formatted, the (1-based, inclusive) line ranges and the source
+    ranges: Option<Vec<(usize, usize)>>,
+    source: Vec<String>,
+    copied_to: usize, // the length of the output after source lines were last copied to it
+    // how many more statements are aligned with each other, and how: when aligning assignments,
+    // the column to pad the operator of a let to end at (and the column the last let's operator
+    // ended at), and when aligning highlight or sign commands, the width of the part up to the
+    // attributes and the width of each attribute
+    aligned_statements: usize,
+    let_column: Option<usize>,
+    op_column: Option<usize>,
+    attr_layout: Option<AttrLayout>,
+    // the column after each bracket still open on the current line or an earlier line of the
+    // statement, innermost last, or None for a list or dict written one item per line
+    delimiters: Vec<Option<usize>>,
+    keep_sorted: bool, // whether the statements being formatted follow a keep-sorted directive
+    bar_removed: bool, // whether a bar was left out of the end of the last line
+    safe_line: usize,  // in safe mode, the source line whose statements are being copied
+    // whether the vim9script command has been reached, before which comments are legacy script
+    after_vim9script: bool,
+    verbatim: Vec<Verbatim>,
+}
+
+impl Default for Formatter {
+    fn default() -> Self {
+        Self::with_config(FormatterConfig::default())
+    }
 }
 
 impl Formatter {
     pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_config(config: FormatterConfig) -> Self {
         Self {
+            config,
             output: vec![],
             current_indent: 0,
             line: String::new(),
             last_line_was_blank: false,
+            blank_lines: 0,
+            source_blank_lines: 0,
             current_continuation_indent: 0,
+            declared: vec![HashSet::new()],
+            trivia: vec![],
+            next_trivia: 0,
+            breaks: vec![],
+            next_break: 0,
+            preserve_breaks: false,
+            raw_line: false,
+            byte_order_mark: false,
+            statement_lines: vec![],
+            ranges: None,
+            source: vec![],
+            copied_to: 0,
+            aligned_statements: 0,
+            let_column: None,
+            op_column: None,
+            attr_layout: None,
+            delimiters: vec![],
+            keep_sorted: false,
+            bar_removed: false,
+            safe_line: 0,
+            after_vim9script: false,
+            verbatim: vec![],
         }
     }
 
     pub fn format(&mut self, ast: &Node) -> Result<String, Error> {
-        self.current_indent = 0;
-        self.output.clear();
-        self.line.clear();
-        self.last_line_was_blank = false;
-        if let Node::TopLevel { body, .. } = ast {
-            for node in body {
-                self.f(node);
-                self.next_line();
-            }
-            if !self.output.is_empty() {
-                while self.output[0].trim() == "" {
-                    self.output.remove(0);
-                }
-                let mut last = self.output.len() - 1;
-                while last > 0 && self.output[last].trim() == "" {
-                    self.output.remove(last);
-                    last = self.output.len() - 1;
+        if let Node::TopLevel { body, trivia, .. } = ast {
+            self.start(trivia);
+            self.f_top_level(body);
+            self.finish();
+            while self.ranges.is_none()
+                && matches!(self.output.first(), Some(l) if l.trim().is_empty())
+            {
+                self.output.remove(0);
+                for (_, output_line, _) in self.statement_lines.iter_mut() {
+                    *output_line = output_line.saturating_sub(1);
                 }
             }
-            Ok(self.output.join("\n"))
+            let mut output = self.output.join("\n");
+            if self.byte_order_mark && !output.is_empty() {
+                output.insert(0, BYTE_ORDER_MARK);
+            }
+            if self.config.ensure_final_newline && !output.is_empty() {
+                output.push('\n');
+            }
+            Ok(output)
         } else {
             Err(Error::new(
                 ErrorKind::InvalidData,
@@ -72,24 +683,434 @@ impl Formatter {
         }
     }
 
+    /// Format a tree like [format](#method.format), given `source`, the lines it was parsed from.
+    /// With the `safe` option, the top-level statements that have parts the parser keeps as text
+    /// are copied from the source exactly as they are, along with any other statements on the
+    /// same line.
+    pub fn format_source(&mut self, ast: &Node, source: &[&str]) -> Result<String, Error> {
+        // the byte order mark is written back (or not) like when the whole file is formatted
+        self.source = source.iter().map(|l| l.to_string()).collect();
+        if let Some(first) = self.source.first_mut() {
+            if let Some(rest) = first.strip_prefix(BYTE_ORDER_MARK) {
+                *first = rest.to_string();
+            }
+        }
+        let result = self.format(ast);
+        self.source.clear();
+        result
+    }
+
+    /// Format only the top-level statements that overlap any of the given line ranges, which are
+    /// 1-based and inclusive, like the hunks of a diff. The lines of all other statements are
+    /// copied from `source` (the lines `ast` was parsed from) exactly as they are, so a file can
+    /// be formatted a piece at a time. A statement with a body, like a function, is formatted as
+    /// a whole if any of its lines is in a range.
+    pub fn format_ranges(
+        &mut self,
+        ast: &Node,
+        source: &[&str],
+        ranges: &[(usize, usize)],
+    ) -> Result<String, Error> {
+        self.ranges = Some(ranges.to_vec());
+        let result = self.format_source(ast, source);
+        self.ranges = None;
+        result
+    }
+
+    /// Format a tree like [format](#method.format), but write each top-level statement to `out`
+    /// once it is complete instead of building the whole output in memory.
+    pub fn format_to<W: Write>(&mut self, ast: &Node, out: &mut W) -> io::Result<()> {
+        let (body, trivia) = match ast {
+            Node::TopLevel { body, trivia, .. } => (body, trivia),
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "provided node is not a TopLevel node",
+                ))
+            }
+        };
+        self.start(trivia);
+        let mut written = false;
+        let mut start = 0;
+        for end in 1..=body.len() {
+            if !self.held_back(&body[start..end]) {
+                written = self.write_statements(&body[start..end], written, out)?;
+                start = end;
+            }
+        }
+        self.write_last_statements(&body[start..], written, out)
+    }
+
+    /// Format statements as they are parsed, writing each to `out` once it is complete, so only
+    /// one top-level statement is in memory at a time. The output is the same as that of
+    /// [format](#method.format) for the whole tree; a parse error is returned as an
+    /// `InvalidData` error after the statements before it have been written.
+    pub fn format_stream(&mut self, statements: ParseIter, out: &mut dyn Write) -> io::Result<()> {
+        self.start(statements.trivia());
+        let mut written = false;
+        let mut pending = vec![];
+        for node in statements {
+            let node = match node {
+                Ok(node) => node,
+                Err(e) => {
+                    self.f_top_level(&pending);
+                    self.write_lines(self.output.len(), written, out)?;
+                    return Err(Error::new(ErrorKind::InvalidData, e));
+                }
+            };
+            pending.push(node);
+            if !self.held_back(&pending) {
+                written = self.write_statements(&pending, written, out)?;
+                pending.clear();
+            }
+        }
+        self.write_last_statements(&pending, written, out)
+    }
+
+    /// The output split into lines, and the spans of its statements in the order they were
+    /// written.
+    fn statement_spans<'s>(&'s self, source: &[&str]) -> (Vec<&'s str>, Vec<StatementSpan>) {
+        // a line of output can hold several lines of text (like the body of an append), so the
+        // output is split into lines again and its statements found by their first line
+        let mut first_lines = vec![];
+        let mut output = vec![];
+        for line in self.output.iter() {
+            first_lines.push(output.len());
+            output.extend(line.split('\n'));
+        }
+        first_lines.push(output.len());
+        // whatever comes before the first statement is compared too, but isn't a statement
+        let mut starts = vec![(0, 0, false)];
+        starts.extend(
+            self.statement_lines
+                .iter()
+                .map(|(line, output_line, counted)| {
+                    (
+                        min(line.saturating_sub(1), source.len()),
+                        first_lines[min(*output_line, first_lines.len() - 1)],
+                        *counted,
+                    )
+                }),
+        );
+        starts.push((source.len(), output.len(), false));
+        let spans = starts
+            .windows(2)
+            .map(|pair| {
+                let (line, output_line, counted) = pair[0];
+                let (next_line, next_output_line, _) = pair[1];
+                (
+                    line..next_line.max(line),
+                    output_line..next_output_line.max(output_line),
+                    counted,
+                )
+            })
+            .collect();
+        (output, spans)
+    }
+
+    /// Compare the output of the last call to [format](#method.format) with its source, given as
+    /// lines, and count the statements and lines that were changed. Each statement is compared
+    /// with the lines between its start and the start of the next statement, so a block's own
+    /// lines are those up to its first nested statement. The times in the report are zero.
+    pub fn report(&self, source: &[&str]) -> Report {
+        let (output, spans) = self.statement_spans(source);
+        let longest = |lines: &[&str]| lines.iter().map(|l| l.chars().count()).max();
+        let mut report = Report {
+            longest_line_before: longest(source).unwrap_or(0),
+            longest_line_after: longest(&output).unwrap_or(0),
+            ..Report::default()
+        };
+        for (before, after, counted) in spans {
+            let before = &source[before];
+            let after = &output[after];
+            if counted {
+                report.statements += 1;
+            }
+            if before != after {
+                report.lines_changed += before.len().max(after.len());
+                if counted {
+                    report.statements_changed += 1;
+                }
+            }
+        }
+        report
+    }
+
+    /// The constructs the last call to [format](#method.format) wrote exactly as they were in the
+    /// source, in the order they were written.
+    pub fn verbatim(&self) -> &[Verbatim] {
+        &self.verbatim
+    }
+
+    /// Map the lines of the output of the last call to [format](#method.format) back to the
+    /// source lines they were formatted from, a statement at a time.
+    pub fn source_map(&self, source: &[&str]) -> SourceMap {
+        let (_, spans) = self.statement_spans(source);
+        SourceMap {
+            mappings: spans
+                .into_iter()
+                .filter(|(before, after, _)| !before.is_empty() && !after.is_empty())
+                .map(|(before, after, _)| Mapping {
+                    output: after.start + 1..after.end + 1,
+                    input: before.start + 1..before.end + 1,
+                })
+                .collect(),
+        }
+    }
+
+    fn write_statements(
+        &mut self,
+        statements: &[Node],
+        written: bool,
+        out: &mut dyn Write,
+    ) -> io::Result<bool> {
+        self.f_top_level(statements);
+        // the last line is kept back in case a trailing comment is added to it
+        let last = self.output.len().saturating_sub(1);
+        self.write_lines(last, written, out)
+    }
+
+    fn write_last_statements(
+        &mut self,
+        statements: &[Node],
+        written: bool,
+        out: &mut dyn Write,
+    ) -> io::Result<()> {
+        self.f_top_level(statements);
+        self.finish();
+        let written = self.write_lines(self.output.len(), written, out)?;
+        if self.config.ensure_final_newline && written {
+            out.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    fn write_lines(
+        &mut self,
+        count: usize,
+        written: bool,
+        out: &mut dyn Write,
+    ) -> io::Result<bool> {
+        let mut written = written;
+        let byte_order_mark = self.byte_order_mark;
+        for line in self.output.drain(..count) {
+            if written {
+                out.write_all(b"\n")?;
+            } else if line.trim().is_empty() {
+                // leading blank lines are dropped
+                continue;
+            } else if byte_order_mark {
+                out.write_all(BYTE_ORDER_MARK.to_string().as_bytes())?;
+            }
+            out.write_all(line.as_bytes())?;
+            written = true;
+        }
+        Ok(written)
+    }
+
+    fn start(&mut self, trivia: &[Trivia]) {
+        self.current_indent = self.config.initial_indent;
+        self.output.clear();
+        self.line = self.indent();
+        self.last_line_was_blank = false;
+        self.blank_lines = 0;
+        self.source_blank_lines = 0;
+        self.declared = vec![HashSet::new()];
+        // line continuations are only needed to know where statements were broken, so they are
+        // kept apart from the comments
+        self.trivia = trivia
+            .iter()
+            .filter(|t| matches!(t, Trivia::ContinuationComment { .. }))
+            .cloned()
+            .collect();
+        self.next_trivia = 0;
+        self.breaks = trivia
+            .iter()
+            .filter(|t| matches!(t, Trivia::LineContinuation { .. }))
+            .map(|t| t.pos().cursor())
+            .collect();
+        self.next_break = 0;
+        self.preserve_breaks = self.config.continuations != Continuations::Rewrap;
+        self.statement_lines.clear();
+        self.copied_to = 0;
+        self.safe_line = 0;
+        self.after_vim9script = false;
+        self.verbatim.clear();
+        self.byte_order_mark = self.config.keep_byte_order_mark
+            && trivia
+                .iter()
+                .any(|t| matches!(t, Trivia::ByteOrderMark { .. }));
+    }
+
+    fn held_back(&self, pending: &[Node]) -> bool {
+        // consecutive set commands are held back in case they can be merged, consecutive lets,
+        // highlight and sign commands in case they are aligned, and an echohl chain until it is
+        // complete
+        let last = match pending.last() {
+            Some(node) => node,
+            None => return false,
+        };
+        if self.config.merge_set_lines && matches!(last, Node::Set { .. }) {
+            return true;
+        }
+        if self.config.align_assignments && matches!(last, Node::Let { .. }) {
+            return true;
+        }
+        if self.config.align_highlights && matches!(last, Node::Highlight { .. }) {
+            return true;
+        }
+        if self.config.align_signs && matches!(last, Node::Sign { .. }) {
+            return true;
+        }
+        if self.config.compact_echohl {
+            if let Some(start) = pending.iter().rposition(starts_echohl_chain) {
+                let (len, complete) = echohl_chain(&pending[start..]);
+                return start + len == pending.len() && !complete;
+            }
+        }
+        false
+    }
+
+    fn f_top_level(&mut self, body: &[Node]) {
+        let body = self.merged(body);
+        let mut i = 0;
+        while i < body.len() {
+            if body[i].command_name() == Some("vim9script") {
+                self.after_vim9script = true;
+            }
+            if self.copy_unformatted(&body[i..]) {
+                i += 1;
+                continue;
+            }
+            self.f_continuation_comments(body[i].pos().cursor());
+            i += self.f_statements(&body[i..]);
+            self.next_line();
+        }
+    }
+
+    fn copy_unformatted(&mut self, body: &[Node]) -> bool {
+        // when only some lines are formatted, copy the source of the first statement of the body
+        // to the output as it is if none of its lines are in a range, and in safe mode, if it
+        // has to be kept
+        if self.source.is_empty() || (self.ranges.is_none() && !self.config.safe) {
+            return false;
+        }
+        let start = body[0].pos().line();
+        let end = match body.get(1) {
+            Some(next) => next.pos().line(),
+            None => self.source.len() + 1,
+        };
+        let in_range = self.ranges.as_ref().is_none_or(|ranges| {
+            ranges
+                .iter()
+                .any(|(first, last)| *first < end.max(start + 1) && *last >= start)
+        });
+        if in_range && !self.keep_source(body) {
+            return false;
+        }
+        let source = &self.source;
+        let lines = source[min(start - 1, source.len())..min(end - 1, source.len()).max(start - 1)]
+            .to_vec();
+        for (i, line) in lines.into_iter().enumerate() {
+            let blank = line.trim().is_empty();
+            // a blank line the formatter put after the statement before stands in for the first
+            // blank line copied
+            if i == 0 && blank && self.last_line_was_blank && self.output.len() > self.copied_to {
+                continue;
+            }
+            self.last_line_was_blank = blank;
+            self.blank_lines = if blank { self.blank_lines + 1 } else { 0 };
+            self.output.push(line);
+        }
+        self.copied_to = self.output.len();
+        // skip over whatever was in the statement
+        let next = body.get(1).map_or(usize::MAX, |n| n.pos().cursor());
+        while self.next_trivia < self.trivia.len()
+            && self.trivia[self.next_trivia].pos().cursor() < next
+        {
+            self.next_trivia += 1;
+        }
+        while self.next_break < self.breaks.len() && self.breaks[self.next_break] < next {
+            self.next_break += 1;
+        }
+        true
+    }
+
+    fn keep_source(&mut self, body: &[Node]) -> bool {
+        // in safe mode, a statement with parts the parser keeps as text is copied, along with the
+        // other statements on the same line, since lines are copied whole
+        if !self.config.safe {
+            return false;
+        }
+        let line = body[0].pos().line();
+        if self.safe_line == line {
+            return true;
+        }
+        if body
+            .iter()
+            .take_while(|n| n.pos().line() == line)
+            .any(has_raw_parts)
+        {
+            self.safe_line = line;
+            return true;
+        }
+        false
+    }
+
+    fn finish(&mut self) {
+        self.f_continuation_comments(usize::MAX);
+        while matches!(self.output.last(), Some(l) if l.trim().is_empty()) {
+            self.output.pop();
+        }
+    }
+
+    fn vim9(&self) -> bool {
+        self.config.syntax == Syntax::Vim9
+    }
+
+    fn comment_char(&self) -> char {
+        if self.vim9() || (self.config.vim9script && self.after_vim9script) {
+            '#'
+        } else {
+            '"'
+        }
+    }
+
     fn indent(&self) -> String {
         INDENT.repeat(self.current_indent)
     }
 
     fn will_fit(&self, item: &str) -> bool {
-        self.line.len() + item.len() <= MAX_LEN
+        self.config
+            .max_line_length
+            .is_none_or(|max| end_column(display_width(&self.line), item) <= max)
     }
 
     fn next_line(&mut self) {
-        let current_line = self.line.split_off(0).trim_end().to_string();
+        let mut current_line = self.line.split_off(0);
+        if !self.raw_line {
+            current_line = trim_line_end(&current_line).to_string();
+        }
+        self.raw_line = false;
         if current_line == "" {
-            if self.last_line_was_blank {
-                // don't allow more than one blank line
+            // blank lines the formatter adds (e.g. between functions) stand in for those in the
+            // source, and more than one in a row are only kept if they were in the source
+            let allowed = self
+                .config
+                .max_blank_lines
+                .map_or(self.source_blank_lines, |max| {
+                    max.min(self.source_blank_lines)
+                })
+                .max(1);
+            if self.last_line_was_blank && self.blank_lines >= allowed {
+                self.line.push_str(&self.indent());
                 return;
             }
             self.last_line_was_blank = true;
+            self.blank_lines += 1;
         } else {
             self.last_line_was_blank = false;
+            self.blank_lines = 0;
         }
         self.output.push(current_line);
         self.line.push_str(&self.indent());
@@ -98,8 +1119,40 @@ impl Formatter {
     fn continue_line(&mut self) {
         self.output
             .push(self.line.split_off(0).trim_end().to_string());
-        self.line.push_str(&self.indent());
-        self.line.push_str(&INDENT.repeat(CONTINUATION));
+        self.start_continuation();
+    }
+
+    fn continuation_indent(&self) -> String {
+        match self.config.continuation_indent {
+            ContinuationIndent::Spaces(n) => " ".repeat(n),
+            ContinuationIndent::Double => INDENT.repeat(2),
+            ContinuationIndent::Aligned => INDENT.repeat(CONTINUATION),
+        }
+    }
+
+    fn open_delimiter(&mut self) {
+        let column = display_width(&self.line);
+        self.delimiters.push(Some(column));
+    }
+
+    fn close_delimiter(&mut self) {
+        self.delimiters.pop();
+    }
+
+    fn start_continuation(&mut self) {
+        let indent = self.indent();
+        if let (ContinuationIndent::Aligned, Some(Some(column))) =
+            (self.config.continuation_indent, self.delimiters.last())
+        {
+            // the backslash and the space after it go just before the column
+            if *column > indent.len() + 2 {
+                self.line.push_str(&" ".repeat(column - 2));
+                self.line.push_str("\\ ");
+                return;
+            }
+        }
+        self.line.push_str(&indent);
+        self.line.push_str(&self.continuation_indent());
         self.line.push_str("\\ ");
         if self.current_continuation_indent > 1 {
             self.line
@@ -118,82 +1171,409 @@ impl Formatter {
         self.add(s);
     }
 
-    fn f(&mut self, node: &Node) {
-        if node_is_atom(node) {
-            self.f_atom_node(node);
-        } else if Node::has_body(node) {
-            self.f_body_node(node);
-        } else {
-            self.f_node(node);
+    fn f_continuation_comments(&mut self, before: usize) {
+        // write any continuation comments that come before the given cursor position. in the
+        // middle of a statement the statement is broken onto a new line after the comments, in
+        // between statements the comments trail the previous statement.
+        while self.next_trivia < self.trivia.len()
+            && self.trivia[self.next_trivia].pos().cursor() <= before
+        {
+            let value = match self.trivia[self.next_trivia].clone() {
+                Trivia::ContinuationComment { value, .. } => value,
+                Trivia::ByteOrderMark { .. } | Trivia::LineContinuation { .. } => unreachable!(),
+            };
+            self.next_trivia += 1;
+            let code = self.line.split_off(0);
+            let in_statement = !code.trim().is_empty();
+            if in_statement && code.trim() != "\\" {
+                self.output.push(code.trim_end().to_string());
+            }
+            let value = if self.config.trim_trailing_whitespace {
+                value.trim_end()
+            } else {
+                &value
+            };
+            self.output.push(format!(
+                "{}{}\"\\{}",
+                self.indent(),
+                self.continuation_indent(),
+                value
+            ));
+            if in_statement {
+                self.start_continuation();
+            } else {
+                self.line = code;
+            }
         }
     }
 
-    fn f_atom_node(&mut self, node: &Node) {
-        // this method assumes there is some value in self.line already, and just adds the
-        // formatted node to that value, or continues it on the next line. for these nodes the
-        // Display output is what we want.
-        self.fit(&format!("{}", node));
-    }
-
-    fn f_letlhs(&mut self, node: &Node) {
-        match node {
-            Node::Let {
-                var, list, rest, ..
-            }
-            | Node::For {
-                var, list, rest, ..
-            } => {
-                if let Some(v) = var {
-                    self.f(v);
-                } else {
-                    self.add("[");
-                    let last = list.len() - 1;
-                    for (i, node) in list.iter().enumerate() {
-                        self.f(node);
-                        if i != last {
-                            self.add(", ");
-                        }
-                    }
-                    if let Some(r) = rest {
-                        self.add("; ");
-                        self.f(r);
-                    }
-                    self.add("]");
-                }
+    fn f_line_breaks(&mut self, before: usize) {
+        // when breaks are preserved, break the statement if it was broken in the source anywhere
+        // before the given cursor position
+        let mut broken = false;
+        while self.next_break < self.breaks.len() && self.breaks[self.next_break] <= before {
+            self.next_break += 1;
+            broken = true;
+        }
+        if broken && self.preserve_breaks {
+            let code = self.line.trim();
+            if !code.is_empty() && code != "\\" {
+                self.continue_line();
             }
-            _ => (),
         }
     }
 
-    fn f_list(&mut self, items: &[Node]) {
+    fn f_statements(&mut self, body: &[Node]) -> usize {
+        // format the first statement of the body, or the echohl chain it starts, and return how
+        // many statements were formatted
+        self.statement_lines.push((
+            body[0].pos().line(),
+            self.output.len(),
+            !matches!(body[0], Node::BlankLine { .. }),
+        ));
+        if !matches!(body[0], Node::Comment { trailing: true, .. }) {
+            self.bar_removed = false;
+        }
+        if matches!(body[0], Node::BlankLine { .. }) {
+            self.source_blank_lines += 1;
+        } else {
+            self.source_blank_lines = 0;
+        }
+        if self.config.compact_echohl {
+            if let (len, true) = echohl_chain(body) {
+                if self.f_echohl_chain(&body[..len]) {
+                    return len;
+                }
+            }
+        }
+        if self.config.align_assignments && self.aligned_statements == 0 {
+            (self.aligned_statements, self.let_column) = self.let_alignment(body);
+        }
+        if self.config.align_highlights && self.aligned_statements == 0 {
+            (self.aligned_statements, self.attr_layout) = self.highlight_alignment(body);
+        }
+        if self.config.align_signs && self.aligned_statements == 0 {
+            (self.aligned_statements, self.attr_layout) = self.sign_alignment(body);
+        }
+        let mut node = Cow::Borrowed(&body[0]);
+        if is_keep_sorted(&node) {
+            self.keep_sorted = true;
+        } else if matches!(*node, Node::BlankLine { .. } | Node::Comment { .. }) {
+            self.keep_sorted = false;
+        } else if self.keep_sorted {
+            sort_lists(node.to_mut());
+        }
+        self.f_statement(&node);
+        if self.aligned_statements > 0 {
+            self.aligned_statements -= 1;
+            if self.aligned_statements == 0 {
+                self.let_column = None;
+                self.attr_layout = None;
+            }
+        }
+        1
+    }
+
+    fn let_alignment(&mut self, body: &[Node]) -> (usize, Option<usize>) {
+        // how many of the lets at the start of the body to align, and the column to align the
+        // ends of their operators to. lets separated by bars in the source are aligned too, since
+        // they are written on lines of their own.
+        let mut columns: Vec<usize> = vec![];
+        for node in body {
+            if !matches!(node, Node::Let { .. }) {
+                break;
+            }
+            let column = match self.op_column(node) {
+                Some(column) => column,
+                None => break,
+            };
+            let min = columns.iter().copied().chain(Some(column)).min().unwrap();
+            let max = columns.iter().copied().chain(Some(column)).max().unwrap();
+            if max - min > self.config.max_alignment_padding {
+                break;
+            }
+            columns.push(column);
+        }
+        match columns.iter().max() {
+            Some(&max) if columns.len() > 1 => (columns.len(), Some(max)),
+            _ => (0, None),
+        }
+    }
+
+    fn op_column(&mut self, node: &Node) -> Option<usize> {
+        // format the let into a scratch buffer to find the column its operator ends at, which it
+        // doesn't have if the line was wrapped before it
+        let saved_output = self.output.split_off(0);
+        let saved_line = self.line.clone();
+        let saved_trivia = self.next_trivia;
+        let saved_break = self.next_break;
+        let saved_declared = self.declared.clone();
+        let saved_raw = self.raw_line;
+        self.op_column = None;
+        self.f(node);
+        let wrapped = !self.output.is_empty();
+        self.output = saved_output;
+        self.line = saved_line;
+        self.next_trivia = saved_trivia;
+        self.next_break = saved_break;
+        self.declared = saved_declared;
+        self.raw_line = saved_raw;
+        self.op_column.take().filter(|_| !wrapped)
+    }
+
+    fn f_let_operator(&mut self, op: &str) {
+        // the operator is padded so that its `=` lines up with those of the lets aligned with it
+        let column = display_width(&self.line) + op.len();
+        self.op_column = Some(column);
+        if let Some(target) = self.let_column {
+            for _ in column..target {
+                self.line.push(' ');
+            }
+        }
+        self.fit(&format!(" {} ", op));
+    }
+
+    fn f_echohl_chain(&mut self, chain: &[Node]) -> bool {
+        // keep the chain on one line, joined with bars, if it fits
+        let saved_output = self.output.split_off(0);
+        let saved_line = self.line.split_off(0);
+        let saved_trivia = self.next_trivia;
+        let saved_break = self.next_break;
+        let mut pieces = vec![];
+        for node in chain {
+            self.f(node);
+            pieces.push(self.line.split_off(0).trim().to_string());
+        }
+        let wrapped = !self.output.is_empty();
+        self.output = saved_output;
+        self.line = saved_line;
+        let joined = pieces.join(" | ");
+        if wrapped || self.next_trivia != saved_trivia || !self.will_fit(&joined) {
+            self.next_trivia = saved_trivia;
+            self.next_break = saved_break;
+            return false;
+        }
+        self.add(&joined);
+        true
+    }
+
+    fn f_statement(&mut self, node: &Node) {
+        // breaks before the statement (e.g. in the arguments of a command that are kept as a
+        // string) are not breaks in this statement
+        let saved_preserve = self.preserve_breaks;
+        self.preserve_breaks = false;
+        self.f_line_breaks(node.pos().cursor());
+        self.preserve_breaks = saved_preserve;
+        if self.config.continuations != Continuations::Join || Node::has_body(node) {
+            self.f(node);
+            return;
+        }
+        // try to fit the whole statement on one line first
+        let saved_line = self.line.clone();
+        let saved_trivia = self.next_trivia;
+        let saved_break = self.next_break;
+        let saved_declared = self.declared.clone();
+        let marker = self.output.len();
+        self.preserve_breaks = false;
+        self.f(node);
+        self.preserve_breaks = saved_preserve;
+        if self.output.len() != marker || !self.will_fit("") {
+            self.output.truncate(marker);
+            self.line = saved_line;
+            self.next_trivia = saved_trivia;
+            self.next_break = saved_break;
+            self.declared = saved_declared;
+            self.raw_line = false;
+            self.f(node);
+        }
+    }
+
+    fn f(&mut self, node: &Node) {
+        if !self.line.trim().is_empty() && !pos_is_operator(node) {
+            self.f_continuation_comments(node.pos().cursor());
+            self.f_line_breaks(node.pos().cursor());
+        }
+        if node_is_atom(node) {
+            self.f_atom_node(node);
+        } else if Node::has_body(node) {
+            self.f_body_node(node);
+        } else {
+            self.f_node(node);
+        }
+    }
+
+    fn f_atom_node(&mut self, node: &Node) {
+        // this method assumes there is some value in self.line already, and just adds the
+        // formatted node to that value, or continues it on the next line. for these nodes the
+        // Display output is what we want.
+        match node {
+            Node::String { value, .. } if value.starts_with('"') => {
+                self.fit(&self.string_keys(value))
+            }
+            Node::Option { value, .. } if self.config.expand_option_names => {
+                let (scope, name) = split_option(value);
+                self.fit(&format!("{}{}", scope, option_full_name(name)))
+            }
+            _ => self.fit(&format!("{}", node)),
+        }
+    }
+
+    /// A double-quoted string with the key notation in its `\<...>` escapes normalized.
+    fn string_keys(&self, s: &str) -> String {
+        let mut rv = String::new();
+        let mut chars = s.char_indices();
+        while let Some((i, c)) = chars.next() {
+            rv.push(c);
+            if c != '\\' {
+                continue;
+            }
+            match key_notation(&s[i + 1..]) {
+                Some((inner, len)) => {
+                    rv.push_str(&self.key_notation(inner));
+                    chars.nth(len - 1);
+                }
+                None => {
+                    // whatever is escaped can't start an escape itself
+                    if let Some((_, c)) = chars.next() {
+                        rv.push(c);
+                    }
+                }
+            }
+        }
+        rv
+    }
+
+    fn f_letlhs(&mut self, node: &Node) {
+        match node {
+            Node::Let {
+                var, list, rest, ..
+            }
+            | Node::For {
+                var, list, rest, ..
+            } => {
+                if let Some(v) = var {
+                    self.f(v);
+                } else {
+                    self.add("[");
+                    let last = list.len() - 1;
+                    for (i, node) in list.iter().enumerate() {
+                        self.f(node);
+                        if i != last {
+                            self.add(", ");
+                        }
+                    }
+                    if let Some(r) = rest {
+                        self.add("; ");
+                        self.f(r);
+                    }
+                    self.add("]");
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn f_vim9_assignment(&mut self, node: &Node) {
+        // in vim9 script a variable has to be declared with `var` the first time it is assigned
+        // in a scope, and assigned without any keyword after that. scoped variables (`g:foo`),
+        // options, registers, environment variables and items of lists or dicts are never
+        // declared.
+        if let Node::Let {
+            cmd,
+            var,
+            list,
+            rest,
+            op,
+            ..
+        } = node
+        {
+            let targets = var
+                .iter()
+                .map(|v| &**v)
+                .chain(list.iter())
+                .chain(rest.iter().map(|r| &**r));
+            let mut declares = false;
+            for target in targets {
+                if let Node::Identifier { value, .. } = target {
+                    if !value.contains(':')
+                        && !value.contains('#')
+                        && !self.declared.iter().any(|scope| scope.contains(value))
+                    {
+                        declares = true;
+                        self.declared.last_mut().unwrap().insert(value.clone());
+                    }
+                }
+            }
+            if cmd != "let" {
+                self.add(cmd);
+                self.add(" ");
+            } else if declares && op == "=" {
+                self.add("var ");
+            }
+            self.f_letlhs(node);
+            self.f_let_operator(if op == ".=" { "..=" } else { op });
+        }
+    }
+
+    /// Whether to write a comma after the last item of a list or dictionary, given whether it is
+    /// written one item per line and whether it had one in the source.
+    fn trailing_comma(&self, multiline: bool, in_source: bool) -> bool {
+        match self.config.trailing_commas {
+            TrailingCommas::Multiline => multiline,
+            TrailingCommas::Never => false,
+            TrailingCommas::Preserve => in_source,
+        }
+    }
+
+    fn f_list(&mut self, items: &[Node], trailing_comma: bool) {
         if items.is_empty() {
             self.fit("[]");
         } else {
             // try to fit this on one line first
             let saved_line = self.line.clone();
+            let saved_trivia = self.next_trivia;
+            let saved_break = self.next_break;
             let marker = self.output.len();
+            let space = if self.config.space_in_brackets {
+                " "
+            } else {
+                ""
+            };
             self.fit("[");
+            self.add(space);
+            self.open_delimiter();
             let last = items.len();
             for (i, item) in items.iter().enumerate() {
                 self.f(item);
                 if i != last - 1 {
                     self.add(", ");
+                } else if self.trailing_comma(false, trailing_comma) {
+                    self.add(",");
                 }
             }
+            self.close_delimiter();
+            self.add(space);
             self.fit("]");
-            // did it fit?
-            if self.output.len() != marker {
+            // did it fit? if it was broken where it was in the source, that's fine too.
+            let preserved = self.preserve_breaks && self.next_break != saved_break;
+            if self.output.len() != marker && !preserved {
                 // if we had to add lines to the output, it did not. delete the lines we added.
                 self.output.truncate(marker);
                 self.line = saved_line;
+                self.next_trivia = saved_trivia;
+                self.next_break = saved_break;
                 // now add a single item per line ("block" style)
                 self.fit("[");
                 self.current_continuation_indent += 1;
-                for item in items.iter() {
+                self.delimiters.push(None);
+                for (i, item) in items.iter().enumerate() {
                     self.continue_line();
                     self.f(item);
-                    self.add(",");
+                    if i != last - 1 || self.trailing_comma(true, trailing_comma) {
+                        self.add(",");
+                    }
                 }
+                self.close_delimiter();
                 self.current_continuation_indent -= 1;
                 self.continue_line();
                 self.add("]");
@@ -201,14 +1581,19 @@ impl Formatter {
         }
     }
 
-    fn f_dict(&mut self, items: &[(Box<Node>, Box<Node>)]) {
+    fn f_dict(&mut self, items: &[(Box<Node>, Box<Node>)], trailing_comma: bool) {
         if items.is_empty() {
             self.fit("{}");
         } else {
             // try to fit on one line first
             let saved_line = self.line.clone();
+            let saved_trivia = self.next_trivia;
+            let saved_break = self.next_break;
             let marker = self.output.len();
+            let space = if self.config.space_in_braces { " " } else { "" };
             self.fit("{");
+            self.add(space);
+            self.open_delimiter();
             let last = items.len();
             for (i, (k, v)) in items.iter().enumerate() {
                 self.f(k);
@@ -216,24 +1601,35 @@ impl Formatter {
                 self.f(v);
                 if i != last - 1 {
                     self.add(", ");
+                } else if self.trailing_comma(false, trailing_comma) {
+                    self.add(",");
                 }
             }
+            self.close_delimiter();
+            self.add(space);
             self.fit("}");
-            // did it fit?
-            if self.output.len() != marker {
+            // did it fit? if it was broken where it was in the source, that's fine too.
+            let preserved = self.preserve_breaks && self.next_break != saved_break;
+            if self.output.len() != marker && !preserved {
                 // if we had to add lines to the output, it did not. delete the lines we added.
                 self.output.truncate(marker);
                 self.line = saved_line;
+                self.next_trivia = saved_trivia;
+                self.next_break = saved_break;
                 // now add a single item per line ("block" style)
                 self.fit("{");
                 self.current_continuation_indent += 1;
-                for (k, v) in items.iter() {
+                self.delimiters.push(None);
+                for (i, (k, v)) in items.iter().enumerate() {
                     self.continue_line();
                     self.f(k);
                     self.add(": ");
                     self.f(v);
-                    self.add(",");
+                    if i != last - 1 || self.trailing_comma(true, trailing_comma) {
+                        self.add(",");
+                    }
                 }
+                self.close_delimiter();
                 self.current_continuation_indent -= 1;
                 self.continue_line();
                 self.add("}");
@@ -242,14 +1638,12 @@ impl Formatter {
     }
 
     fn f_mods(&mut self, mods: &[Modifier]) {
+        let mut mods = mods.to_vec();
+        if self.config.sort_modifiers {
+            sort_modifiers(&mut mods);
+        }
         for modifier in mods {
-            if let Some(count) = modifier.count {
-                self.add(&count.to_string());
-            }
-            self.add(&modifier.name);
-            if modifier.bang {
-                self.add("!");
-            }
+            self.add(&modifier.to_string());
             self.add(" ");
         }
     }
@@ -271,6 +1665,66 @@ impl Formatter {
         }
     }
 
+    /// Write the commands run by another command, e.g. an autocmd, after it, joined with bars.
+    fn f_piped_body(&mut self, body: &[Node]) {
+        let saved_output = self.output.split_off(0);
+        let saved_line = self.line.split_off(0);
+        // continuation comments can't be joined with bars, so they are left to trail the whole
+        // command
+        let saved_trivia = self.trivia.split_off(0);
+        // and the body is joined with bars, so it can't be broken either
+        let saved_preserve = self.preserve_breaks;
+        self.preserve_breaks = false;
+        let saved_statements = self.statement_lines.len();
+        let mut trimmed = vec![];
+        let mut raw = vec![];
+        for node in body {
+            self.output.clear();
+            self.line.clear();
+            self.f(node);
+            self.next_line();
+            trimmed.push(
+                self.output
+                    .iter()
+                    .map(|line| line.trim_start())
+                    .collect::<Vec<&str>>()
+                    .join(" | "),
+            );
+            raw.push(self.output.split_off(0));
+        }
+        self.output = saved_output;
+        self.line = saved_line;
+        self.trivia = saved_trivia;
+        self.preserve_breaks = saved_preserve;
+        self.statement_lines.truncate(saved_statements);
+        self.add(" ");
+        let last_raw = raw.len() - 1;
+        for i in 0..raw.len() {
+            if self.will_fit(&trimmed[i]) {
+                self.add(&trimmed[i]);
+            } else {
+                let pieces = raw[i].clone();
+                let last_piece = pieces.len() - 1;
+                let indent = self.indent().len();
+                for (j, piece) in pieces.iter().enumerate() {
+                    self.continue_line();
+                    if j == 0 {
+                        self.add(&piece);
+                    } else {
+                        self.add(piece.get(indent..).unwrap());
+                    }
+                    if j != last_piece {
+                        self.add(" | ");
+                    }
+                }
+            }
+            if i != last_raw {
+                self.add(" | ");
+            }
+        }
+        self.raw_line = matches!(body.last(), Some(Node::Normal { .. }));
+    }
+
     fn f_autocmd(&mut self, node: &Node) {
         if let Node::Autocmd {
             mods,
@@ -293,7 +1747,14 @@ impl Formatter {
                 self.fit(group);
             }
             if !events.is_empty() {
-                let mut events = events.clone();
+                let mut events = events
+                    .iter()
+                    .map(|e| match self.config.event_case {
+                        EventCase::Canonical => canonical_autocmd_event(e).unwrap_or(e).to_string(),
+                        EventCase::Lowercase => e.to_lowercase(),
+                        EventCase::Preserve => e.clone(),
+                    })
+                    .collect::<Vec<String>>();
                 events.sort_unstable();
                 self.fit(&format!(" {}", events.join(",")));
             }
@@ -306,58 +1767,265 @@ impl Formatter {
                 self.fit(" nested");
             }
             if !body.is_empty() {
-                let saved_output = self.output.split_off(0);
-                let saved_line = self.line.split_off(0);
-                let mut trimmed = vec![];
-                let mut raw = vec![];
-                for node in body {
-                    self.output.clear();
-                    self.line.clear();
-                    self.f(node);
-                    self.next_line();
-                    trimmed.push(
-                        self.output
-                            .iter()
-                            .map(|line| line.trim())
-                            .collect::<Vec<&str>>()
-                            .join(" | "),
-                    );
-                    raw.push(self.output.split_off(0));
-                }
-                self.output = saved_output;
-                self.line = saved_line;
-                self.add(" ");
-                let last_raw = raw.len() - 1;
-                for i in 0..raw.len() {
-                    if self.will_fit(&trimmed[i]) {
-                        self.add(&trimmed[i]);
-                    } else {
-                        let pieces = raw[i].clone();
-                        let last_piece = pieces.len() - 1;
-                        let indent = self.indent().len();
-                        for (j, piece) in pieces.iter().enumerate() {
-                            self.continue_line();
-                            if j == 0 {
-                                self.add(&piece);
-                            } else {
-                                self.add(piece.get(indent..).unwrap());
-                            }
-                            if j != last_piece {
-                                self.add(" | ");
-                            }
-                        }
+                self.f_piped_body(body);
+            }
+        } else {
+            panic!("node passed to f_autocmd is not an autocmd node");
+        }
+    }
+
+    fn mapping_attrs<'a>(&self, attrs: &'a [String]) -> Vec<&'a String> {
+        let mut attrs = attrs.iter().collect::<Vec<_>>();
+        match self.config.mapping_attr_order {
+            MappingAttrOrder::Alphabetical => attrs.sort_unstable(),
+            MappingAttrOrder::Canonical => attrs.sort_by_key(|a| {
+                CANONICAL_MAPPING_ATTRS
+                    .iter()
+                    .position(|c| c == a)
+                    .unwrap_or(CANONICAL_MAPPING_ATTRS.len())
+            }),
+            MappingAttrOrder::Preserve => (),
+        }
+        attrs
+    }
+
+    fn highlight_attrs<'a>(&self, attrs: &'a [(String, String)]) -> Vec<&'a (String, String)> {
+        let mut attrs = attrs.iter().collect::<Vec<_>>();
+        match self.config.highlight_attr_order {
+            HighlightAttrOrder::Alphabetical => attrs.sort_unstable(),
+            HighlightAttrOrder::Canonical => attrs.sort_by_key(|(k, _)| {
+                let key = k.to_lowercase();
+                (
+                    CANONICAL_HIGHLIGHT_ATTRS
+                        .iter()
+                        .position(|a| *a == key)
+                        .unwrap_or(CANONICAL_HIGHLIGHT_ATTRS.len()),
+                    key,
+                )
+            }),
+            HighlightAttrOrder::Preserve => (),
+        }
+        attrs
+    }
+
+    fn highlight_alignment(&self, body: &[Node]) -> (usize, Option<AttrLayout>) {
+        // how many of the highlight commands setting attributes at the start of the body to
+        // align, each on a line of its own, and how. the attributes are laid out in columns in
+        // the order of the first command to use each.
+        let mut run = vec![];
+        let mut last_line = 0;
+        for node in body {
+            match node {
+                Node::Highlight {
+                    mods,
+                    clear: false,
+                    link: false,
+                    none: false,
+                    group: Some(_),
+                    attrs,
+                    ..
+                } if mods.is_empty() && !attrs.is_empty() && node.pos().line() > last_line => {
+                    last_line = node.pos().line();
+                    run.push(node);
+                }
+                _ => break,
+            }
+        }
+        let mut prefix = 0;
+        let mut columns: Vec<(String, usize)> = vec![];
+        for node in &run {
+            if let Node::Highlight { attrs, .. } = node {
+                prefix = prefix.max(display_width(&self.highlight_prefix(node)));
+                for (k, v) in self.highlight_attrs(attrs) {
+                    let width = display_width(k) + display_width(v) + 1;
+                    match columns.iter_mut().find(|(key, _)| key == k) {
+                        Some(column) => column.1 = column.1.max(width),
+                        None => columns.push((k.clone(), width)),
                     }
-                    if i != last_raw {
-                        self.add(" | ");
+                }
+            }
+        }
+        if let HighlightAttrOrder::Alphabetical | HighlightAttrOrder::Canonical =
+            self.config.highlight_attr_order
+        {
+            let order = self
+                .highlight_attrs(
+                    &columns
+                        .iter()
+                        .map(|(k, _)| (k.clone(), String::new()))
+                        .collect::<Vec<_>>(),
+                )
+                .into_iter()
+                .map(|(k, _)| k.clone())
+                .collect::<Vec<_>>();
+            columns.sort_by_key(|(k, _)| order.iter().position(|o| o == k));
+        }
+        let width = self.indent().len()
+            + prefix
+            + columns.iter().map(|(_, width)| width + 1).sum::<usize>();
+        if run.len() < 2 {
+            (0, None)
+        } else if self
+            .config
+            .max_line_length
+            .is_some_and(|max| width > max + 1)
+        {
+            // none of the run is aligned, rather than only the part of it that would fit
+            (run.len(), None)
+        } else {
+            (run.len(), Some((prefix, columns)))
+        }
+    }
+
+    fn sign_alignment(&self, body: &[Node]) -> (usize, Option<AttrLayout>) {
+        // how many of the `sign define` commands at the start of the body to align, each on a
+        // line of its own, and how. the attributes are laid out in columns in the order of the
+        // first command to use each.
+        let mut run = vec![];
+        let mut last_line = 0;
+        for node in body {
+            match node {
+                Node::Sign {
+                    mods,
+                    range,
+                    subcommand,
+                    args,
+                    attrs,
+                    ..
+                } if mods.is_empty()
+                    && range.is_empty()
+                    && subcommand == "define"
+                    && args.len() == 1
+                    && !attrs.is_empty()
+                    && node.pos().line() > last_line =>
+                {
+                    last_line = node.pos().line();
+                    run.push(node);
+                }
+                _ => break,
+            }
+        }
+        let mut prefix = 0;
+        let mut columns: Vec<(String, usize)> = vec![];
+        for node in &run {
+            if let Node::Sign { args, attrs, .. } = node {
+                prefix = prefix.max(display_width(&format!("sign define {}", args[0])));
+                for (k, v) in attrs {
+                    let width = display_width(k) + display_width(v) + 1;
+                    match columns.iter_mut().find(|(key, _)| key == k) {
+                        Some(column) => column.1 = column.1.max(width),
+                        None => columns.push((k.clone(), width)),
                     }
                 }
             }
+        }
+        let width = self.indent().len()
+            + prefix
+            + columns.iter().map(|(_, width)| width + 1).sum::<usize>();
+        if run.len() < 2 {
+            (0, None)
+        } else if self
+            .config
+            .max_line_length
+            .is_some_and(|max| width > max + 1)
+        {
+            (run.len(), None)
         } else {
-            panic!("node passed to f_autocmd is not an autocmd node");
+            (run.len(), Some((prefix, columns)))
+        }
+    }
+
+    fn highlight_group<'a>(&self, group: &'a str) -> &'a str {
+        if !self.config.canonical_highlight_groups {
+            return group;
+        }
+        HIGHLIGHT_GROUPS
+            .iter()
+            .find(|g| g.eq_ignore_ascii_case(group))
+            .copied()
+            .unwrap_or(group)
+    }
+
+    /// The part of a highlight command setting attributes that comes before them, e.g.
+    /// `highlight default Comment`.
+    fn highlight_prefix(&self, node: &Node) -> String {
+        match node {
+            Node::Highlight { default, group, .. } => format!(
+                "highlight {}{}",
+                if *default { "default " } else { "" },
+                self.highlight_group(group.as_deref().unwrap_or(""))
+            ),
+            _ => String::new(),
+        }
+    }
+
+    /// The table after a `loadkeymap` command, with its columns lined up.
+    fn keymap(&self, lines: &[KeymapLine]) -> String {
+        let widths = |column: fn(&KeymapLine) -> Option<&String>| {
+            lines
+                .iter()
+                .filter_map(column)
+                .map(|s| display_width(s))
+                .max()
+                .unwrap_or(0)
+        };
+        let lhs_width = widths(|l| match l {
+            KeymapLine::Entry { lhs, .. } => Some(lhs),
+            _ => None,
+        });
+        let rhs_width = widths(|l| match l {
+            KeymapLine::Entry { rhs, .. } => Some(rhs),
+            _ => None,
+        });
+        let mut rv = String::new();
+        for line in lines {
+            rv.push('\n');
+            match line {
+                KeymapLine::Entry {
+                    lhs,
+                    rhs,
+                    comment: Some(comment),
+                } => {
+                    rv.push_str(&format!(
+                        "{} {} {}",
+                        pad(lhs, lhs_width),
+                        pad(rhs, rhs_width),
+                        self.keymap_comment(comment)
+                    ));
+                }
+                KeymapLine::Entry { lhs, rhs, .. } => {
+                    rv.push_str(&format!("{} {}", pad(lhs, lhs_width), rhs))
+                }
+                KeymapLine::Comment(comment) => {
+                    rv.push_str(&format!("\"{}", self.keymap_comment(comment)))
+                }
+                KeymapLine::Blank => (),
+            }
+        }
+        rv
+    }
+
+    fn keymap_comment<'a>(&self, comment: &'a str) -> &'a str {
+        if self.config.trim_trailing_whitespace {
+            comment.trim_end()
+        } else {
+            comment
         }
     }
 
     fn f_highlight(&mut self, node: &Node) {
+        if let (Some((prefix, columns)), Node::Highlight { attrs, .. }) =
+            (self.attr_layout.clone(), node)
+        {
+            self.add(&format!("{} ", pad(&self.highlight_prefix(node), prefix)));
+            for (key, width) in columns {
+                match attrs.iter().find(|(k, _)| *k == key) {
+                    Some((k, v)) => self.add(&format!("{} ", pad(&format!("{}={}", k, v), width))),
+                    None => self.add(&" ".repeat(width + 1)),
+                }
+            }
+            return;
+        }
         if let Node::Highlight {
             mods,
             clear,
@@ -386,99 +2054,389 @@ impl Formatter {
                 self.fit("link ");
             }
             if let Some(g) = group {
-                self.fit(&format!("{} ", g));
+                self.fit(&format!("{} ", self.highlight_group(g)));
             }
             if *none {
                 self.fit("NONE ");
             }
             if let Some(t) = to_group {
-                self.fit(&format!("{} ", t));
+                self.fit(&format!("{} ", self.highlight_group(t)));
             }
-            let mut attrs = attrs
-                .iter()
-                .map(|(k, v)| format!("{}={} ", k, v))
-                .collect::<Vec<String>>();
-            attrs.sort_unstable();
-            for attr in attrs.iter() {
-                self.fit(attr);
+            for (k, v) in self.highlight_attrs(attrs) {
+                self.fit(&format!("{}={} ", k, v));
             }
         } else {
             panic!("node passed to f_highlight is not a highlight node");
         }
     }
 
-    fn f_node(&mut self, node: &Node) {
-        // this method assumes there is not a value (besides the current indent) in self.line
-        // already. it will always put at least something onto the end of the current line before
-        // it checks length and possibly continues onto the next line.
-        match node {
-            Node::Autocmd { .. } => self.f_autocmd(node),
-            Node::BinaryOp {
-                left, right, op, ..
-            } => {
-                self.f(left);
-                self.fit(&format!(" {} ", op));
-                self.f(right);
-            }
-            Node::Call { name, args, .. } => {
-                self.f(name);
-                self.add("(");
-                let last = args.len();
-                for (i, arg) in args.iter().enumerate() {
-                    self.f(arg);
-                    if i != last - 1 {
-                        self.add(", ");
+    fn f_sign(&mut self, node: &Node) {
+        if let Node::Sign {
+            mods,
+            range,
+            subcommand,
+            args,
+            attrs,
+            ..
+        } = node
+        {
+            if let Some((prefix, columns)) = self.attr_layout.clone() {
+                self.add(&pad(
+                    &format!("sign {} {}", subcommand, args.join(" ")),
+                    prefix,
+                ));
+                for (key, width) in columns {
+                    match attrs.iter().find(|(k, _)| *k == key) {
+                        Some((k, v)) => {
+                            self.add(&format!(" {}", pad(&format!("{}={}", k, v), width)))
+                        }
+                        None => self.add(&" ".repeat(width + 1)),
                     }
                 }
-                self.add(")");
+                return;
             }
-            Node::Comment {
-                value, trailing, ..
-            } => {
-                let comment = if value.starts_with(char::is_whitespace) {
-                    value.to_string()
-                } else {
-                    format!(" {}", value)
-                };
-                if *trailing {
-                    let last = self.output.len() - 1;
-                    self.line = self.output.remove(last);
-                    self.add(&format!(" \"{}", comment));
-                } else {
-                    self.add(&format!("\"{}", comment));
-                }
+            self.f_mods(mods.as_slice());
+            self.add(&range.to_string());
+            self.add("sign ");
+            self.add(subcommand);
+            for arg in args {
+                self.fit(&format!(" {}", arg));
             }
-            Node::Dict { items, .. } => self.f_dict(items),
-            Node::Dot { left, right, .. } => {
-                self.f(left);
-                self.add(".");
-                self.f(right);
+            for (k, v) in attrs {
+                self.fit(&format!(" {}={}", k, v));
             }
-            Node::Echo {
-                mods, cmd, list, ..
-            } => {
-                self.f_mods(mods.as_slice());
-                self.add(cmd);
-                self.add(" ");
-                for item in list.iter() {
-                    self.f(item);
+        } else {
+            panic!("node passed to f_sign is not a sign node");
+        }
+    }
+
+    fn record_verbatim(&mut self, kind: VerbatimKind, command: &str, pos: Position) {
+        self.verbatim.push(Verbatim {
+            kind,
+            command: command.to_string(),
+            pos,
+        });
+    }
+
+    /// Whether a command's arguments are written exactly as they were: user commands and
+    /// commands in the `opaque_commands` config.
+    fn is_opaque(&self, command: &str) -> bool {
+        command.starts_with(|c: char| c.is_ascii_uppercase())
+            || self.config.opaque_commands.iter().any(|c| c == command)
+    }
+
+    /// Key notation, in canonical form if key notation is normalized. `inner` is what is between
+    /// the angle brackets.
+    fn key_notation(&self, inner: &str) -> String {
+        if !self.config.normalize_key_notation {
+            return format!("<{}>", inner);
+        }
+        let canonical = canonical_key_notation_with(inner, |name| {
+            match self.config.canonical_keys.get(&name.to_lowercase()) {
+                Some(name) => Some(name.as_str()),
+                None => canonical_key(name),
+            }
+        });
+        format!("<{}>", canonical)
+    }
+
+    /// Write keys, with their key notation normalized.
+    fn keys(&self, keys: &[Key]) -> String {
+        let notation = |inner: &str| self.key_notation(inner);
+        keys.iter()
+            .map(|key| match key {
+                Key::Text(s) => s.clone(),
+                Key::Special(inner) | Key::Sid(inner) => notation(inner),
+                Key::Plug { notation: n, name } => format!("{}{}", notation(n), name),
+                Key::Cmd {
+                    notation: n,
+                    command,
+                    cr,
+                } => format!(
+                    "{}{}{}",
+                    notation(n),
+                    self.keys(&split_keys(command)),
+                    notation(cr)
+                ),
+            })
+            .collect()
+    }
+
+    fn f_lang(&mut self, node: &Node) {
+        if let Node::Lang {
+            pos,
+            mods,
+            cmd,
+            trim,
+            marker,
+            lines,
+        } = node
+        {
+            self.f_mods(mods.as_slice());
+            self.add(cmd);
+            self.add(" <<");
+            if *trim {
+                self.add(" trim");
+            }
+            if let Some(marker) = marker {
+                self.add(" ");
+                self.add(marker);
+            }
+            // with trim, vim removes the indent of the first line from all of them, so the script
+            // can be moved to any indent without changing it as long as the other lines are
+            // indented at least as far
+            let first_indent = lines
+                .iter()
+                .find(|l| !l.trim().is_empty())
+                .map(|l| &l[..l.len() - l.trim_start().len()]);
+            let reindent = match first_indent {
+                Some(indent) if *trim => lines
+                    .iter()
+                    .all(|l| l.trim().is_empty() || l.starts_with(indent)),
+                _ => false,
+            };
+            let mut script = lines
+                .iter()
+                .map(|l| match first_indent {
+                    Some(indent) if reindent => l.get(indent.len()..).unwrap_or(""),
+                    _ => l,
+                })
+                .collect::<Vec<&str>>()
+                .join("\n");
+            match self
+                .config
+                .heredoc_formatters
+                .get(cmd)
+                .and_then(|command| run_heredoc_formatter(command, &script))
+            {
+                Some(formatted) => script = formatted,
+                None => self.record_verbatim(VerbatimKind::Script, cmd, *pos),
+            }
+            let indent = format!("{}{}", self.indent(), INDENT);
+            for line in script.lines() {
+                self.line.push('\n');
+                if reindent && !line.trim().is_empty() {
+                    self.line.push_str(&indent);
+                }
+                self.line.push_str(line);
+            }
+            self.line.push('\n');
+            if *trim {
+                self.line.push_str(&self.indent());
+            }
+            self.line.push_str(marker.as_deref().unwrap_or("."));
+            self.raw_line = true;
+        }
+    }
+
+    fn f_set(&mut self, node: &Node) {
+        if let Node::Set {
+            mods,
+            cmd,
+            bang,
+            args,
+            ..
+        } = node
+        {
+            self.f_mods(mods.as_slice());
+            self.add(cmd);
+            if *bang {
+                self.add("!");
+            }
+            self.add(" ");
+            for (option, op, value) in args.iter() {
+                let value = if self.config.sort_set_lists {
+                    sort_set_list(option, op, value)
+                } else {
+                    value.to_string()
+                };
+                self.fit(&format!("{}{}{} ", option, op, value));
+            }
+        } else {
+            panic!("node passed to f_set is not a set node");
+        }
+    }
+
+    fn f_node(&mut self, node: &Node) {
+        // this method assumes there is not a value (besides the current indent) in self.line
+        // already. it will always put at least something onto the end of the current line before
+        // it checks length and possibly continues onto the next line.
+        match node {
+            Node::Autocmd { .. } => self.f_autocmd(node),
+            Node::BinaryOp {
+                left, right, op, ..
+            } => {
+                self.f(left);
+                if *op == BinaryOpKind::Concat && self.vim9() {
+                    // `.` is not allowed for concatenation in vim9 script
+                    self.fit(" .. ");
+                } else {
+                    self.fit(&format!(" {} ", op));
+                }
+                self.f(right);
+            }
+            Node::Call { name, args, .. } => {
+                self.f(name);
+                self.add("(");
+                self.open_delimiter();
+                let last = args.len();
+                for (i, arg) in args.iter().enumerate() {
+                    self.f(arg);
+                    if i != last - 1 {
+                        self.add(", ");
+                    }
+                }
+                self.close_delimiter();
+                self.add(")");
+            }
+            Node::Comment {
+                value, trailing, ..
+            } => {
+                let comment = if value.starts_with(char::is_whitespace) {
+                    value.to_string()
+                } else {
+                    format!(" {}", value)
+                };
+                if *trailing {
+                    let last = self.output.len() - 1;
+                    self.line = self.output.remove(last);
+                    // a bar before a comment is needed, since some commands (like mappings) would
+                    // otherwise take the comment as an argument
+                    if std::mem::take(&mut self.bar_removed) {
+                        self.add(" |");
+                    }
+                    let gap = if self.line.ends_with('|') {
+                        1
+                    } else {
+                        self.config.trailing_comment_gap
+                    };
+                    self.add(&format!(
+                        "{}{}{}",
+                        " ".repeat(gap),
+                        self.comment_char(),
+                        comment
+                    ));
+                } else {
+                    self.add(&format!("{}{}", self.comment_char(), comment));
+                }
+                if !self.config.trim_trailing_whitespace {
+                    self.raw_line = true;
+                }
+            }
+            Node::Bar { .. } => {
+                let last = self.output.len() - 1;
+                self.line = self.output.remove(last);
+                if self.config.remove_trailing_bars {
+                    self.bar_removed = true;
+                } else {
+                    self.add(" |");
+                }
+            }
+            Node::Dict {
+                items,
+                trailing_comma,
+                ..
+            } => self.f_dict(items, *trailing_comma),
+            Node::Dot { left, right, .. } => {
+                self.f(left);
+                self.add(".");
+                self.f(right);
+            }
+            Node::Echo {
+                mods, cmd, list, ..
+            } => {
+                self.f_mods(mods.as_slice());
+                self.add(cmd);
+                self.add(" ");
+                for item in list.iter() {
+                    self.f(item);
                 }
             }
-            Node::ExCall { mods, left, .. } => {
+            Node::Eval { mods, expr, .. } => {
+                self.f_mods(mods.as_slice());
+                self.add("eval ");
+                self.f(expr);
+            }
+            Node::ExCall {
+                mods, range, left, ..
+            } => {
                 self.f_mods(mods.as_slice());
-                self.add("call ");
+                self.add(&range.to_string());
+                if !self.vim9() {
+                    self.add("call ");
+                }
                 self.f(left);
             }
-            Node::ExCmd {
+            Node::ExCmdRaw {
+                pos,
                 mods,
+                range,
                 command,
                 bang,
                 args,
-                ..
             } => match command.as_str() {
                 "augroup" => self.f_augroup(args),
+                "" => {
+                    self.f_mods(mods.as_slice());
+                    self.add(&range.to_string());
+                }
+                c if c.ends_with("unmap") => {
+                    self.f_mods(mods.as_slice());
+                    self.add(&range.to_string());
+                    self.add(command);
+                    if *bang {
+                        self.add("!");
+                    }
+                    self.add(" ");
+                    self.fit(&self.keys(&split_keys(args.trim_end())));
+                }
+                c if self.is_opaque(c) => {
+                    // arguments that may be passed to a shell or parsed in ways we know nothing
+                    // about are written exactly as they were, without wrapping or trimming
+                    self.f_mods(mods.as_slice());
+                    self.add(&range.to_string());
+                    self.add(command);
+                    if *bang {
+                        self.add("!");
+                    }
+                    if !args.is_empty() && command != "!" {
+                        self.add(" ");
+                    }
+                    self.line.push_str(args);
+                    self.raw_line = true;
+                    let kind = if c.starts_with(|c: char| c.is_ascii_uppercase()) {
+                        VerbatimKind::UserCommand
+                    } else {
+                        VerbatimKind::OpaqueCommand
+                    };
+                    self.record_verbatim(kind, command, *pos);
+                }
+                _ if args.starts_with('\n') => {
+                    // the lines after append, insert and loadkeymap are text, not commands, so
+                    // they are written exactly as they were
+                    self.f_mods(mods.as_slice());
+                    self.add(&range.to_string());
+                    self.add(command);
+                    if *bang {
+                        self.add("!");
+                    }
+                    let keymap = if command == "loadkeymap" && self.config.align_keymaps {
+                        keymap_lines(args)
+                    } else {
+                        None
+                    };
+                    match keymap {
+                        Some(lines) => self.line.push_str(&self.keymap(&lines)),
+                        None => {
+                            self.line.push_str(args);
+                            self.record_verbatim(VerbatimKind::Text, command, *pos);
+                        }
+                    }
+                    self.raw_line = true;
+                }
                 _ => {
                     self.f_mods(mods.as_slice());
+                    self.add(&range.to_string());
                     self.add(&command);
                     if *bang {
                         self.add("!");
@@ -490,12 +2448,60 @@ impl Formatter {
             Node::Execute { mods, list, .. } => {
                 self.f_mods(mods.as_slice());
                 self.add("execute ");
+                let op = match self.config.execute_concatenation {
+                    Concatenation::Preserve => None,
+                    Concatenation::Dot => Some(BinaryOpKind::Concat),
+                    Concatenation::DoubleDot => Some(BinaryOpKind::StringConcat),
+                };
                 for item in list.iter() {
-                    self.f(item);
+                    let mut item = Cow::Borrowed(item);
+                    if self.config.single_quote_execute || op.is_some() {
+                        normalize_execute_arg(
+                            item.to_mut(),
+                            self.config.single_quote_execute,
+                            op.as_ref(),
+                        );
+                    }
+                    self.f(&item);
                     self.add(" ");
                 }
             }
+            Node::Global {
+                mods,
+                range,
+                bang,
+                cmd,
+                delimiter,
+                pattern,
+                body,
+                ..
+            } => {
+                self.f_mods(mods.as_slice());
+                self.add(&range.to_string());
+                self.add(cmd);
+                if *bang {
+                    self.add("!");
+                }
+                self.add(&format!(" {}{}{}", delimiter, pattern, delimiter));
+                if !body.is_empty() {
+                    self.f_piped_body(body);
+                }
+            }
             Node::Highlight { .. } => self.f_highlight(node),
+            Node::Set { .. } => self.f_set(node),
+            Node::Sign { .. } => self.f_sign(node),
+            Node::Lang { .. } => self.f_lang(node),
+            Node::Lambda { args, expr, .. } if self.vim9() => {
+                self.add("(");
+                for (i, arg) in args.iter().enumerate() {
+                    self.f(arg);
+                    if i != args.len() - 1 {
+                        self.add(", ");
+                    }
+                }
+                self.add(") => ");
+                self.f(expr);
+            }
             Node::Lambda { args, expr, .. } => {
                 self.add("{");
                 for (i, arg) in args.iter().enumerate() {
@@ -510,15 +2516,28 @@ impl Formatter {
                 self.fit("}");
             }
             Node::Let {
-                mods, right, op, ..
+                mods,
+                cmd,
+                right,
+                op,
+                ..
             } => {
                 self.f_mods(mods.as_slice());
-                self.add("let ");
-                self.f_letlhs(node);
-                self.fit(&format!(" {} ", op));
+                if self.vim9() {
+                    self.f_vim9_assignment(node);
+                } else {
+                    self.add(cmd);
+                    self.add(" ");
+                    self.f_letlhs(node);
+                    self.f_let_operator(op);
+                }
                 self.f(right);
             }
-            Node::List { items, .. } => self.f_list(&items.as_slice()),
+            Node::List {
+                items,
+                trailing_comma,
+                ..
+            } => self.f_list(items, *trailing_comma),
             Node::LockVar {
                 mods,
                 cmd,
@@ -542,97 +2561,321 @@ impl Formatter {
                     self.add(" ");
                 }
             }
-            Node::Mapping {
+            Node::Abbreviation {
                 mods,
                 command,
                 attrs,
                 left,
-                right,
+                right_keys,
+                right_expr,
+                ..
+            }
+            | Node::Mapping {
+                mods,
+                command,
+                attrs,
+                left,
+                right_keys,
                 right_expr,
                 ..
             } => {
                 self.f_mods(mods.as_slice());
                 self.add(&command);
-                if !attrs.is_empty() {
-                    let mut attrs = attrs.clone();
-                    attrs.sort_unstable();
-                    for attr in attrs {
-                        self.fit(&format!(" <{}>", attr));
-                    }
+                if let Node::Mapping { bang: true, .. } = node {
+                    self.add("!");
+                }
+                for attr in self.mapping_attrs(attrs) {
+                    self.fit(&format!(" <{}>", attr));
                 }
                 if !left.is_empty() {
                     self.add(" ");
-                    self.fit(&left);
+                    self.fit(&self.keys(&split_keys(left)));
                     if let Some(re) = right_expr {
                         self.add(" ");
                         self.f(re);
-                    } else if !right.is_empty() {
+                    } else if !right_keys.is_empty() {
                         self.add(" ");
-                        self.fit(&right.replace("|", "\\|"));
+                        self.fit(&self.keys(right_keys).replace("|", "\\|"));
                     }
                 }
             }
-            Node::ParenExpr { expr, .. } => {
-                self.add("(");
-                self.f(expr);
-                self.fit(")");
-            }
-            Node::Return { mods, left, .. } => {
-                self.f_mods(mods.as_slice());
-                self.add("return");
-                if let Some(l) = left {
-                    self.add(" ");
-                    self.f(l);
-                }
-            }
-            Node::Shebang { value, .. } => self.add(&format!("#!{}", value)),
-            Node::Slice {
-                name, left, right, ..
+            Node::Match {
+                mods,
+                range,
+                group,
+                delimiter,
+                pattern,
+                ..
             } => {
-                self.f(name);
-                self.add("[");
-                if let Some(l) = left {
-                    self.f(l);
+                self.f_mods(mods.as_slice());
+                self.add(&range.to_string());
+                self.add("match");
+                if !group.is_empty() {
+                    self.add(&format!(" {}", group));
                 }
-                self.add(":");
-                if let Some(r) = right {
-                    self.f(r);
+                if let Some(d) = delimiter {
+                    self.add(&format!(" {}{}{}", d, pattern, d));
                 }
-                self.add("]");
-            }
-            Node::Subscript { name, index, .. } => {
-                self.f(name);
-                self.add("[");
-                self.f(index);
-                self.add("]");
             }
-            Node::Ternary {
-                cond, left, right, ..
+            Node::Menu {
+                mods,
+                range,
+                command,
+                bang,
+                attrs,
+                icon,
+                priority,
+                enable,
+                path,
+                right,
+                ..
             } => {
-                self.f(cond);
-                self.add(" ? ");
-                self.f(left);
-                self.add(" : ");
-                self.f(right);
-            }
-            Node::Throw { mods, err, .. } => {
                 self.f_mods(mods.as_slice());
-                self.add("throw ");
-                self.f(err);
-            }
-            Node::UnaryOp { op, right, .. } => {
-                self.add(&format!("{}", op));
-                self.f(right);
+                self.add(&range.to_string());
+                self.add(command);
+                if *bang {
+                    self.add("!");
+                }
+                let mut attrs = attrs.clone();
+                attrs.sort_unstable();
+                for attr in attrs {
+                    self.fit(&format!(" <{}>", attr));
+                }
+                if let Some(icon) = icon {
+                    self.fit(&format!(" icon={}", icon));
+                }
+                if let Some(priority) = priority {
+                    self.fit(&format!(" {}", priority));
+                }
+                match enable {
+                    Some(true) => self.fit(" enable"),
+                    Some(false) => self.fit(" disable"),
+                    None => (),
+                }
+                if !path.is_empty() {
+                    self.fit(&format!(" {}", path));
+                    if !right.is_empty() {
+                        self.add(" ");
+                        self.fit(&self.keys(&split_keys(right)).replace("|", "\\|"));
+                    }
+                }
             }
-            Node::Unlet {
-                mods, bang, list, ..
+            Node::Digraphs {
+                mods,
+                bang,
+                digraphs,
+                ..
             } => {
                 self.f_mods(mods.as_slice());
-                self.add("unlet");
+                self.add("digraphs");
                 if *bang {
                     self.add("!");
                 }
-                self.add(" ");
+                for (chars, number) in digraphs {
+                    self.fit(&format!(" {} {}", chars, number));
+                }
+            }
+            Node::Method { left, right, .. } => {
+                self.f(left);
+                self.add("->");
+                self.f(right);
+            }
+            Node::Normal {
+                mods,
+                range,
+                bang,
+                raw,
+                ..
+            } => {
+                // the argument is written exactly as it was read: it can't be wrapped, and any
+                // whitespace or control characters in it are significant.
+                self.f_mods(mods.as_slice());
+                self.add(&range.to_string());
+                self.add("normal");
+                if *bang {
+                    self.add("!");
+                }
+                if !raw.is_empty() {
+                    self.add(" ");
+                    if self.config.normalize_keys_in_normal {
+                        self.add(&self.keys(&split_keys(raw)));
+                    } else {
+                        self.add(raw);
+                    }
+                    self.raw_line = true;
+                }
+            }
+            Node::Colorscheme { mods, name, .. } => {
+                self.f_mods(mods.as_slice());
+                self.add("colorscheme");
+                if let Some(name) = name {
+                    self.add(" ");
+                    self.add(name);
+                }
+            }
+            Node::Runtime {
+                mods,
+                bang,
+                location,
+                files,
+                ..
+            } => {
+                self.f_mods(mods.as_slice());
+                self.add("runtime");
+                if *bang {
+                    self.add("!");
+                }
+                for word in location.iter().chain(files) {
+                    self.add(" ");
+                    self.add(word);
+                }
+            }
+            Node::Source {
+                mods, bang, file, ..
+            } => {
+                self.f_mods(mods.as_slice());
+                self.add("source");
+                if *bang {
+                    self.add("!");
+                }
+                if let Some(file) = file {
+                    self.add(" ");
+                    self.add(file);
+                }
+            }
+            Node::CurlyName { pieces, .. } => {
+                // the name isn't broken between its pieces
+                for piece in pieces {
+                    match piece {
+                        Node::CurlyNamePart { value, .. } => self.add(value),
+                        _ => self.f(piece),
+                    }
+                }
+            }
+            Node::CurlyNameExpr { expr, .. } => {
+                // the expression is written without spaces inside the braces, e.g. `s:{n + 1}`
+                self.add("{");
+                self.open_delimiter();
+                self.f(expr);
+                self.close_delimiter();
+                self.add("}");
+            }
+            Node::ParenExpr { expr, .. } => {
+                self.add("(");
+                self.open_delimiter();
+                self.f(expr);
+                self.close_delimiter();
+                self.fit(")");
+            }
+            Node::Return { mods, left, .. } => {
+                self.f_mods(mods.as_slice());
+                self.add("return");
+                if let Some(l) = left {
+                    self.add(" ");
+                    self.f(l);
+                }
+            }
+            Node::Shebang { value, .. } => {
+                // the shebang is for the shell, so it's written exactly as it was
+                self.add(&format!("#!{}", value));
+                self.raw_line = true;
+            }
+            Node::Slice {
+                name, left, right, ..
+            } => {
+                self.f(name);
+                self.add("[");
+                if let Some(l) = left {
+                    self.f(l);
+                }
+                self.add(":");
+                if let Some(r) = right {
+                    self.f(r);
+                }
+                self.add("]");
+            }
+            Node::Subscript { name, index, .. } => {
+                self.f(name);
+                self.add("[");
+                self.open_delimiter();
+                self.f(index);
+                self.close_delimiter();
+                self.add("]");
+            }
+            Node::Ternary {
+                cond, left, right, ..
+            } => {
+                self.f(cond);
+                self.add(" ? ");
+                self.f(left);
+                self.add(" : ");
+                self.f(right);
+            }
+            Node::Substitute {
+                mods,
+                range,
+                name,
+                delimiter,
+                pattern,
+                replacement,
+                flags,
+                count,
+                ..
+            } => {
+                // patterns and replacements are written exactly as they were, since even
+                // whitespace in them matters
+                self.f_mods(mods.as_slice());
+                self.add(&range.to_string());
+                self.add(name);
+                let mut args = String::new();
+                if let Some(d) = delimiter {
+                    args.push(*d);
+                    args.push_str(pattern);
+                    if let Some(replacement) = replacement {
+                        args.push(*d);
+                        args.push_str(replacement);
+                        if flags.is_some() {
+                            args.push(*d);
+                        }
+                    }
+                }
+                if let Some(flags) = flags.as_ref().filter(|f| !f.is_empty()) {
+                    if delimiter.is_none() {
+                        args.push(' ');
+                    }
+                    args.push_str(flags);
+                }
+                if let Some(count) = count {
+                    args.push_str(&format!(" {}", count));
+                }
+                if !args.is_empty() {
+                    // a delimiter can follow the name directly, but flags or a count can't, e.g.
+                    // `sg` isn't `s g`
+                    if delimiter.is_none() {
+                        self.add(" ");
+                    }
+                    self.line.push_str(args.trim_start());
+                    self.raw_line = true;
+                }
+            }
+            Node::Throw { mods, err, .. } => {
+                self.f_mods(mods.as_slice());
+                self.add("throw ");
+                self.f(err);
+            }
+            Node::UnaryOp { op, right, .. } => {
+                self.add(&format!("{}", op));
+                self.f(right);
+            }
+            Node::Unlet {
+                mods, bang, list, ..
+            } => {
+                self.f_mods(mods.as_slice());
+                self.add("unlet");
+                if *bang {
+                    self.add("!");
+                }
+                self.add(" ");
                 for item in list.iter() {
                     self.f(item);
                     self.add(" ");
@@ -642,17 +2885,150 @@ impl Formatter {
         };
     }
 
-    fn f_body(&mut self, body: &[Node]) {
+    fn merged<'a>(&self, body: &'a [Node]) -> Cow<'a, [Node]> {
+        if self.config.merge_set_lines {
+            merge_set_lines(body)
+        } else {
+            Cow::Borrowed(body)
+        }
+    }
+
+    fn f_body(&mut self, body: &[Node], end: usize) {
         self.current_indent += 1;
-        for node in body.iter() {
+        // a keep-sorted directive in the body only applies to the body
+        let saved_keep_sorted = self.keep_sorted;
+        self.keep_sorted = false;
+        let body = self.merged(body);
+        let mut i = 0;
+        while i < body.len() {
             self.next_line();
-            self.f(node);
+            self.f_continuation_comments(body[i].pos().cursor());
+            i += self.f_statements(&body[i..]);
         }
+        // a function at the end of a block has already ended its line, and isn't followed by a
+        // blank line before the end of the block
+        if !matches!(body.last(), Some(Node::Function { .. })) {
+            self.next_line();
+        }
+        self.f_continuation_comments(end);
+        self.keep_sorted = saved_keep_sorted;
         self.current_indent -= 1;
-        self.next_line();
+        self.line = self.indent();
     }
 
     fn f_body_node(&mut self, node: &Node) {
+        if self.config.one_line_blocks == OneLineBlocks::Preserve
+            && Node::is_one_line(node)
+            && has_only_simple_statements(node)
+        {
+            if let Some(line) = self.one_line_block(node) {
+                if self.will_fit(&line) {
+                    self.add(&line);
+                    return;
+                }
+            }
+        }
+        self.f_block(node);
+    }
+
+    fn one_line_block(&mut self, node: &Node) -> Option<String> {
+        // format the block as usual into a scratch buffer, then join its lines with bars. if any
+        // of the lines is a continuation the block can't be put back on one line.
+        if let Node::Function { .. } = node {
+            return None;
+        }
+        let saved_output = self.output.split_off(0);
+        let saved_line = self.line.split_off(0);
+        let saved_indent = self.current_indent;
+        let saved_trivia = self.next_trivia;
+        let saved_break = self.next_break;
+        let saved_statements = self.statement_lines.len();
+        self.current_indent = 0;
+        self.f_block(node);
+        self.statement_lines.truncate(saved_statements);
+        self.next_line();
+        let lines = self
+            .output
+            .split_off(0)
+            .into_iter()
+            .filter(|line| !line.trim().is_empty())
+            .collect::<Vec<String>>();
+        self.output = saved_output;
+        self.line = saved_line;
+        self.current_indent = saved_indent;
+        self.next_break = saved_break;
+        if self.next_trivia != saved_trivia {
+            self.next_trivia = saved_trivia;
+            None
+        } else if lines.iter().any(|line| line.trim_start().starts_with('\\')) {
+            None
+        } else {
+            Some(
+                lines
+                    .iter()
+                    .map(|line| line.trim())
+                    .collect::<Vec<&str>>()
+                    .join(" | "),
+            )
+        }
+    }
+
+    fn f_def(&mut self, node: &Node) {
+        if let Node::Function {
+            mods,
+            name,
+            bang,
+            args,
+            body,
+            end,
+            ..
+        } = node
+        {
+            self.f_mods(mods.as_slice());
+            self.add("def");
+            if *bang {
+                self.add("!");
+            }
+            self.add(" ");
+            self.f(name);
+            self.add("(");
+            let mut scope = HashSet::new();
+            for (i, arg) in args.iter().enumerate() {
+                self.f(arg);
+                if let Node::Identifier { value, .. } = arg {
+                    scope.insert(value.clone());
+                }
+                if i != args.len() - 1 {
+                    self.add(", ");
+                }
+            }
+            self.add(")");
+            if body.iter().any(returns_value) {
+                self.add(": any");
+            }
+            self.declared.push(scope);
+            self.f_body(body, end_cursor(end));
+            self.declared.pop();
+            self.add("enddef");
+            self.next_line(); // blank lines between functions
+        }
+    }
+
+    fn f_clauses(&mut self, body: &[Node], clauses: Vec<&Node>, end: usize) {
+        // the body of a block followed by its clauses, each of which ends where the next begins
+        let mut ends = clauses
+            .iter()
+            .map(|c| c.pos().cursor())
+            .collect::<Vec<usize>>();
+        ends.push(end);
+        self.f_body(body, ends[0]);
+        for (clause, end) in clauses.iter().zip(ends[1..].iter()) {
+            self.f_clause(clause, *end);
+        }
+    }
+
+    fn f_clause(&mut self, node: &Node, end: usize) {
+        // the else/elseif/catch/finally clauses of a block. `end` is where the clause ends.
         match node {
             Node::Catch {
                 mods,
@@ -666,12 +3042,12 @@ impl Formatter {
                     self.add(" ");
                     self.fit(&p);
                 }
-                self.f_body(body.as_slice());
+                self.f_body(body.as_slice(), end);
             }
             Node::Else { mods, body, .. } => {
                 self.f_mods(mods.as_slice());
                 self.add("else");
-                self.f_body(body);
+                self.f_body(body, end);
             }
             Node::ElseIf {
                 mods, cond, body, ..
@@ -679,22 +3055,32 @@ impl Formatter {
                 self.f_mods(mods.as_slice());
                 self.add("elseif ");
                 self.f(cond);
-                self.f_body(body);
+                self.f_body(body, end);
             }
             Node::Finally { mods, body, .. } => {
                 self.f_mods(mods.as_slice());
                 self.add("finally");
-                self.f_body(body);
+                self.f_body(body, end);
             }
+            _ => (),
+        }
+    }
+
+    fn f_block(&mut self, node: &Node) {
+        match node {
             Node::For {
-                mods, right, body, ..
+                mods,
+                right,
+                body,
+                end,
+                ..
             } => {
                 self.f_mods(mods.as_slice());
                 self.add("for ");
                 self.f_letlhs(node);
                 self.add(" in ");
                 self.f(right);
-                self.f_body(body);
+                self.f_body(body, end_cursor(end));
                 self.add("endfor");
             }
             Node::Function {
@@ -704,15 +3090,35 @@ impl Formatter {
                 args,
                 attrs,
                 body,
+                end,
                 ..
             } => {
-                if !self.output.is_empty() {
-                    // a function must be preceded by a blank line or a comment
-                    let last_line = self.output[self.output.len() - 1].trim().to_string();
-                    if last_line != "" && !last_line.starts_with('"') {
+                if let Some(last_line) = self.output.last() {
+                    // a function must be preceded by a blank line or a comment, unless it is the
+                    // first thing in a block, after a less indented line
+                    let starts_block =
+                        last_line.len() - last_line.trim_start().len() < self.indent().len();
+                    let last_line = last_line.trim();
+                    if !starts_block
+                        && last_line != ""
+                        && !last_line.starts_with(self.comment_char())
+                    {
                         self.next_line(); // blank lines between functions
                     }
                 }
+                if self.vim9() {
+                    if convert::def_blockers(node).is_empty() {
+                        self.f_def(node);
+                    } else {
+                        // a legacy function keeps legacy syntax in its body
+                        self.config.syntax = Syntax::Legacy;
+                        self.f_block(node);
+                        self.config.syntax = Syntax::Vim9;
+                    }
+                    return;
+                }
+                // the body of a legacy function is legacy script, even in a Vim9 script
+                let vim9script = std::mem::replace(&mut self.config.vim9script, false);
                 self.f_mods(mods.as_slice());
                 self.add("function");
                 if *bang {
@@ -732,9 +3138,10 @@ impl Formatter {
                 if !attrs.is_empty() {
                     self.add(&format!(" {}", attrs.join(" ")));
                 }
-                self.f_body(body);
+                self.f_body(body, end_cursor(end));
                 self.add("endfunction");
                 self.next_line(); // blank lines between functions
+                self.config.vim9script = vim9script;
             }
             Node::If {
                 mods,
@@ -742,18 +3149,14 @@ impl Formatter {
                 elseifs,
                 else_,
                 body,
+                end,
                 ..
             } => {
                 self.f_mods(mods.as_slice());
                 self.add("if ");
                 self.f(cond);
-                self.f_body(body);
-                for elseif in elseifs.iter() {
-                    self.f_body_node(elseif);
-                }
-                if let Some(e) = else_ {
-                    self.f_body_node(e);
-                }
+                let clauses = elseifs.iter().chain(else_.iter().map(|e| &**e));
+                self.f_clauses(body, clauses.collect(), end_cursor(end));
                 self.add("endif");
             }
             Node::Try {
@@ -761,26 +3164,26 @@ impl Formatter {
                 body,
                 catches,
                 finally,
+                end,
                 ..
             } => {
                 self.f_mods(mods.as_slice());
                 self.add("try");
-                self.f_body(body);
-                for catch in catches.iter() {
-                    self.f_body_node(catch);
-                }
-                if let Some(f) = finally {
-                    self.f_body_node(f);
-                }
+                let clauses = catches.iter().chain(finally.iter().map(|f| &**f));
+                self.f_clauses(body, clauses.collect(), end_cursor(end));
                 self.add("endtry");
             }
             Node::While {
-                mods, cond, body, ..
+                mods,
+                cond,
+                body,
+                end,
+                ..
             } => {
                 self.f_mods(mods.as_slice());
                 self.add("while ");
                 self.f(cond);
-                self.f_body(body);
+                self.f_body(body, end_cursor(end));
                 self.add("endwhile");
             }
             _ => (),
@@ -790,8 +3193,9 @@ impl Formatter {
 
 #[cfg(test)]
 mod tests {
-    use super::super::parse_lines;
     use super::*;
+    use crate::config::{Profile, StyleEdition};
+    use viml_parser::{parse_iter, parse_lines, NodeBuilder};
 
     #[test]
     fn test_augroup() {
@@ -802,7 +3206,7 @@ mod tests {
         let expected = concat!(
             "augroup foo\n",
             "  autocmd User Foo echo 'foo'\n",
-            "augroup END"
+            "augroup END\n"
         );
         assert_eq!(expected, &result);
     }
@@ -814,7 +3218,7 @@ mod tests {
             parse_lines(&["let foo = ['this list will fit', 'this list will fit']"]).unwrap();
         let mut formatter = Formatter::new();
         let result = formatter.format(&node).unwrap();
-        let expected = "let foo = ['this list will fit', 'this list will fit']";
+        let expected = "let foo = ['this list will fit', 'this list will fit']\n";
         assert_eq!(expected, &result);
         // "block formatting" - list won't fit on a single line, so format it as a block
         let node = parse_lines(
@@ -827,7 +3231,8 @@ mod tests {
       \ 'list is too long',
       \ 'list is too long',
       \ 'list is too long',
-      \ ]"#;
+      \ ]
+"#;
         assert_eq!(expected, &result);
     }
 
@@ -838,7 +3243,7 @@ mod tests {
             parse_lines(&["let foo = {'this': 'dict will fit', 'this dict': 'will fit'}"]).unwrap();
         let mut formatter = Formatter::new();
         let result = formatter.format(&node).unwrap();
-        let expected = "let foo = {'this': 'dict will fit', 'this dict': 'will fit'}";
+        let expected = "let foo = {'this': 'dict will fit', 'this dict': 'will fit'}\n";
         assert_eq!(expected, &result);
         // "block formatting" - dict won't fit on a single line, so format it as a block
         let node = parse_lines(
@@ -850,7 +3255,100 @@ mod tests {
       \ 'this': 'dict will not fit',
       \ 'this dict': 'will not fit',
       \ 'this dict will': 'not fit',
-      \ }"#;
+      \ }
+"#;
+        assert_eq!(expected, &result);
+    }
+
+    #[test]
+    fn test_curly_names() {
+        let code = [
+            "let my_{n}_var = 1",
+            "call F{n}()",
+            "echo s:{ n+1 }",
+            "let x = g:{'a'.b}_c",
+            "call s:{a ? 'x' : 'y'}#F{i}(1)",
+            "function! My_{x}_Func()",
+            "endfunction",
+        ];
+        let expected = concat!(
+            "let my_{n}_var = 1\n",
+            "call F{n}()\n",
+            "echo s:{n + 1}\n",
+            "let x = g:{'a' . b}_c\n",
+            "call s:{a ? 'x' : 'y'}#F{i}(1)\n",
+            "\n",
+            "function! My_{x}_Func()\n",
+            "endfunction\n",
+        );
+        let mut formatter = Formatter::default();
+        let result = formatter.format(&parse_lines(&code).unwrap()).unwrap();
+        assert_eq!(result, expected);
+        let lines = result.lines().collect::<Vec<&str>>();
+        assert_eq!(
+            formatter.format(&parse_lines(&lines).unwrap()).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_one_line_blocks() {
+        let code = [
+            "if has('nvim') | set inccommand=nosplit | endif",
+            "if x",
+            "echo 1 | endif",
+        ];
+        let node = parse_lines(&code).unwrap();
+        let mut formatter = Formatter::default();
+        let result = formatter.format(&node).unwrap();
+        let expected = concat!(
+            "if has('nvim')\n",
+            "  set inccommand=nosplit\n",
+            "endif\n",
+            "if x\n",
+            "  echo 1\n",
+            "endif\n"
+        );
+        assert_eq!(expected, &result);
+        let mut formatter = Formatter::with_config(FormatterConfig {
+            one_line_blocks: OneLineBlocks::Preserve,
+            ..Default::default()
+        });
+        let result = formatter.format(&node).unwrap();
+        let expected = concat!(
+            "if has('nvim') | set inccommand=nosplit | endif\n",
+            "if x\n",
+            "  echo 1\n",
+            "endif\n"
+        );
+        assert_eq!(expected, &result);
+        let node = parse_lines(&["if x | echo 1 | else | echo 2 | endif  \" why"]).unwrap();
+        let result = formatter.format(&node).unwrap();
+        assert_eq!("if x | echo 1 | else | echo 2 | endif \" why\n", &result);
+        let node = parse_lines(&[
+            "function! F(x) abort",
+            "if a:x|return|endif",
+            "if a:x | return 1 | else | for i in [] | endfor | endif",
+            "try | call G() | catch | return | endtry",
+            "while 1 | if a:x | break | endif | endwhile",
+            "endfunction",
+        ])
+        .unwrap();
+        let result = formatter.format(&node).unwrap();
+        let expected = concat!(
+            "function! F(x) abort\n",
+            "  if a:x | return | endif\n",
+            "  if a:x\n",
+            "    return 1\n",
+            "  else\n",
+            "    for i in [] | endfor\n",
+            "  endif\n",
+            "  try | call G() | catch | return | endtry\n",
+            "  while 1\n",
+            "    if a:x | break | endif\n",
+            "  endwhile\n",
+            "endfunction\n"
+        );
         assert_eq!(expected, &result);
     }
 
@@ -881,7 +3379,1578 @@ mod tests {
         for (input, expected) in tests.iter() {
             let node = parse_lines(&[input]).unwrap();
             let result = formatter.format(&node).unwrap();
-            assert_eq!(expected, &result);
+            assert_eq!(format!("{}\n", expected), result);
+        }
+    }
+
+    #[test]
+    fn test_continuation_comments() {
+        let node = parse_lines(&[
+            "let foo = [",
+            "      \\ 'bar',",
+            "      \"\\ a comment",
+            "      \\ 'baz',",
+            "      \\ ]",
+            "if x",
+            "  call Foo(1,",
+            "        \\ 2)",
+            "        \"\\ trailing",
+            "endif",
+        ])
+        .unwrap();
+        let mut formatter = Formatter::default();
+        let result = formatter.format(&node).unwrap();
+        let expected = concat!(
+            "let foo = [\n",
+            "      \\ 'bar',\n",
+            "      \"\\ a comment\n",
+            "      \\ 'baz',\n",
+            "      \\ ]\n",
+            "if x\n",
+            "  call Foo(1, 2)\n",
+            "        \"\\ trailing\n",
+            "endif\n",
+        );
+        assert_eq!(expected, &result);
+    }
+
+    #[test]
+    fn test_normal_is_verbatim() {
+        let raw = "A;\x16\x1b | \"x\x04\t ";
+        let node = parse_lines(&[
+            &format!("normal! {}", raw),
+            "if x",
+            &format!("silent normal {}", raw),
+            "endif",
+            &format!("autocmd BufEnter * normal {}", raw),
+        ])
+        .unwrap();
+        let mut formatter = Formatter::default();
+        let result = formatter.format(&node).unwrap();
+        let expected = format!(
+            "normal! {raw}\nif x\n  silent normal {raw}\nendif\nautocmd BufEnter * normal {raw}\n",
+            raw = raw
+        );
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn test_bare_normal() {
+        let node = parse_lines(&["normal", "if x", "normal!", "endif"]).unwrap();
+        let mut formatter = Formatter::default();
+        let result = formatter.format(&node).unwrap();
+        assert_eq!("normal\nif x\n  normal!\nendif\n", result);
+    }
+
+    #[test]
+    fn test_const_and_eval() {
+        let node = parse_lines(&["cons  x=[1,2]", "ev   x->add(3)"]).unwrap();
+        let mut formatter = Formatter::default();
+        let result = formatter.format(&node).unwrap();
+        assert_eq!("const x = [1, 2]\neval x->add(3)\n", result);
+    }
+
+    #[test]
+    fn test_substitute() {
+        let node = parse_lines(&[
+            "if x",
+            "%s/\\s\\+$//e",
+            "sm #[#] a#b#&g  3|echo 1",
+            "s  g",
+            "endif",
+        ])
+        .unwrap();
+        let mut formatter = Formatter::default();
+        let result = formatter.format(&node).unwrap();
+        assert_eq!(
+            concat!(
+                "if x\n",
+                "  %s/\\s\\+$//e\n",
+                "  sm#[#] a#b#&g 3\n",
+                "  echo 1\n",
+                "  s g\n",
+                "endif\n",
+            ),
+            result
+        );
+    }
+
+    #[test]
+    fn test_global() {
+        let node = parse_lines(&[
+            "g!/^\\s*$/d",
+            "v#foo#normal!   dd",
+            "%g/x/ s/a/b/ | let   y=1",
+            "g/[/]/",
+        ])
+        .unwrap();
+        let mut formatter = Formatter::default();
+        let result = formatter.format(&node).unwrap();
+        assert_eq!(
+            concat!(
+                "global! /^\\s*$/ delete\n",
+                "vglobal #foo# normal! dd\n",
+                "%global /x/ s/a/b/ | let y = 1\n",
+                "global /[/]/\n",
+            ),
+            result
+        );
+    }
+
+    #[test]
+    fn test_event_case() {
+        let node = parse_lines(&["autocmd bufreadpost,WinClosed,LSPATTACH * echo 'foo'"]).unwrap();
+        let format = |event_case| {
+            Formatter::with_config(FormatterConfig {
+                event_case,
+                ..Default::default()
+            })
+            .format(&node)
+            .unwrap()
+        };
+        assert_eq!(
+            format(EventCase::Canonical),
+            "autocmd BufReadPost,LspAttach,WinClosed * echo 'foo'\n"
+        );
+        assert_eq!(
+            format(EventCase::Lowercase),
+            "autocmd bufreadpost,lspattach,winclosed * echo 'foo'\n"
+        );
+        assert_eq!(
+            format(EventCase::Preserve),
+            "autocmd LSPATTACH,WinClosed,bufreadpost * echo 'foo'\n"
+        );
+    }
+
+    #[test]
+    fn test_set_lists() {
+        let node = parse_lines(&[
+            "set wig+=*.o,*.a,*.o rtp^=b,a,b path-=a,a",
+            "set nowrap",
+            "set tags=./tags,,tags",
+            "setlocal sw=2",
+            "setlocal et",
+            "silent set list",
+        ])
+        .unwrap();
+        let format = |sort_set_lists, merge_set_lines| {
+            Formatter::with_config(FormatterConfig {
+                sort_set_lists,
+                merge_set_lines,
+                ..Default::default()
+            })
+            .format(&node)
+            .unwrap()
+        };
+        assert_eq!(
+            format(false, false),
+            concat!(
+                "set wig+=*.o,*.a,*.o rtp^=b,a,b path-=a,a\n",
+                "set nowrap\n",
+                "set tags=./tags,,tags\n",
+                "setlocal sw=2\n",
+                "setlocal et\n",
+                "silent set list\n",
+            )
+        );
+        assert_eq!(
+            format(true, true),
+            concat!(
+                "set wig+=*.a,*.o rtp^=b,a path-=a,a nowrap tags=./tags,,tags\n",
+                "setlocal sw=2 et\n",
+                "silent set list\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_raw_bodies_are_untouched() {
+        let node = parse_lines(&[
+            "if x",
+            "append  ",
+            "  foo  ",
+            ".",
+            "insert!",
+            "\tbar \t",
+            ".",
+            "endif",
+            "loadkeymap",
+            "a  A  ",
+            "b B   \" comment  ",
+        ])
+        .unwrap();
+        let format = |trim_trailing_whitespace| {
+            Formatter::with_config(FormatterConfig {
+                trim_trailing_whitespace,
+                ..Default::default()
+            })
+            .format(&node)
+            .unwrap()
+        };
+        let expected = concat!(
+            "if x\n",
+            "  append\n",
+            "  foo  \n",
+            ".\n",
+            "  insert!\n",
+            "\tbar \t\n",
+            ".\n",
+            "endif\n",
+            "loadkeymap\n",
+            "a  A  \n",
+            "b B   \" comment  \n",
+        );
+        assert_eq!(format(true), expected);
+        assert_eq!(format(false), expected);
+    }
+
+    #[test]
+    fn test_align_keymaps() {
+        let format = |code: &[&str]| {
+            Formatter::with_config(FormatterConfig {
+                align_keymaps: true,
+                ..Default::default()
+            })
+            .format(&parse_lines(code).unwrap())
+            .unwrap()
+        };
+        assert_eq!(
+            format(&[
+                "loadkeymap",
+                "\" vowels  ",
+                "a'\tá LATIN SMALL LETTER A WITH ACUTE  ",
+                "",
+                "<Space>  _",
+                "e<  ê LATIN SMALL LETTER E WITH CIRCUMFLEX",
+            ]),
+            concat!(
+                "loadkeymap\n",
+                "\" vowels\n",
+                "a'      á LATIN SMALL LETTER A WITH ACUTE\n",
+                "\n",
+                "<Space> _\n",
+                "e<      ê LATIN SMALL LETTER E WITH CIRCUMFLEX\n",
+            )
+        );
+        // a table Vim would reject is left alone
+        assert_eq!(
+            format(&["loadkeymap", "a  A", "b"]),
+            "loadkeymap\na  A\nb\n"
+        );
+    }
+
+    #[test]
+    fn test_sign_menu_digraphs_and_match() {
+        let code = [
+            "sign  define Error text=>> texthl=ErrorMsg",
+            "sign define Warning  text=!! linehl=Search texthl=WarningMsg",
+            "sign place 2 line=3 name=Error",
+            "nnoremenu  <special> <silent> 10.20 File.Save\\ All :wall<cr>",
+            "digraphs  e: 235   a:228",
+            "2mat  Search   /a b/",
+        ];
+        let format = |align_signs| {
+            Formatter::with_config(FormatterConfig {
+                align_signs,
+                ..Default::default()
+            })
+            .format(&parse_lines(&code).unwrap())
+            .unwrap()
+        };
+        let rest = concat!(
+            "sign place 2 line=3 name=Error\n",
+            "nnoremenu <silent> <special> 10.20 File.Save\\ All :wall<CR>\n",
+            "digraphs e: 235 a: 228\n",
+            "2match Search /a b/\n"
+        );
+        assert_eq!(
+            format(false),
+            format!(
+                "{}{}",
+                concat!(
+                    "sign define Error text=>> texthl=ErrorMsg\n",
+                    "sign define Warning text=!! linehl=Search texthl=WarningMsg\n",
+                ),
+                rest
+            )
+        );
+        assert_eq!(
+            format(true),
+            format!(
+                "{}{}",
+                concat!(
+                    "sign define Error   text=>> texthl=ErrorMsg\n",
+                    "sign define Warning text=!! texthl=WarningMsg linehl=Search\n",
+                ),
+                rest
+            )
+        );
+    }
+
+    #[test]
+    fn test_trailing_bars_and_comments() {
+        let node =
+            parse_lines(&["echo 1 |", "map a b |   \" note", "let x = 1   \" note"]).unwrap();
+        let format = |remove_trailing_bars, trailing_comment_gap| {
+            Formatter::with_config(FormatterConfig {
+                remove_trailing_bars,
+                trailing_comment_gap,
+                ..Default::default()
+            })
+            .format(&node)
+            .unwrap()
+        };
+        assert_eq!(
+            format(false, 1),
+            "echo 1 |\nmap a b | \" note\nlet x = 1 \" note\n"
+        );
+        assert_eq!(
+            format(true, 2),
+            "echo 1\nmap a b | \" note\nlet x = 1  \" note\n"
+        );
+    }
+
+    #[test]
+    fn test_trailing_whitespace_and_final_newline() {
+        let node = parse_lines(&["\" comment  ", "let x = 1 \" trailing  "]).unwrap();
+        let format = |trim_trailing_whitespace, ensure_final_newline| {
+            Formatter::with_config(FormatterConfig {
+                trim_trailing_whitespace,
+                ensure_final_newline,
+                ..Default::default()
+            })
+            .format(&node)
+            .unwrap()
+        };
+        assert_eq!(format(true, false), "\" comment\nlet x = 1 \" trailing");
+        assert_eq!(format(true, true), "\" comment\nlet x = 1 \" trailing\n");
+        assert_eq!(
+            format(false, false),
+            "\" comment  \nlet x = 1 \" trailing  "
+        );
+        assert_eq!(
+            format(false, true),
+            "\" comment  \nlet x = 1 \" trailing  \n"
+        );
+    }
+
+    #[test]
+    fn test_built_nodes() {
+        let maps = [("<Leader>f", ":Files<CR>"), ("<Leader>b", ":Buffers<CR>")];
+        let node = maps
+            .iter()
+            .fold(
+                NodeBuilder::new().comment(" generated"),
+                |b, (left, right)| b.mapping("nnoremap", &["silent"], left, right),
+            )
+            .blank_line()
+            .function("s:Toggle", &["name"], &["abort"], |body| {
+                body.let_("l:key", "=", "'g:' .. a:name")
+                    .if_("get(g:, a:name)", |then| then.call("remove(g:, a:name)"))
+                    .else_(|body| body.let_("g:[a:name]", "=", "1"))
+            })
+            .build()
+            .unwrap();
+        let result = Formatter::default().format(&node).unwrap();
+        let expected = concat!(
+            "\" generated\n",
+            "nnoremap <silent> <Leader>f :Files<CR>\n",
+            "nnoremap <silent> <Leader>b :Buffers<CR>\n",
+            "\n",
+            "function s:Toggle(name) abort\n",
+            "  let l:key = 'g:' .. a:name\n",
+            "  if get(g:, a:name)\n",
+            "    call remove(g:, a:name)\n",
+            "  else\n",
+            "    let g:[a:name] = 1\n",
+            "  endif\n",
+            "endfunction\n",
+        );
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn test_formatter_is_shareable() {
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+        let node = parse_lines(&["let x=1"]).unwrap();
+        let mut formatter = Formatter::default();
+        assert_send_sync(&formatter);
+        let output = thread::scope(|scope| scope.spawn(|| formatter.format(&node)).join())
+            .unwrap()
+            .unwrap();
+        assert_eq!(output, "let x = 1\n");
+    }
+
+    #[test]
+    fn test_byte_order_mark_and_shebang() {
+        let code = ["\u{feff}#!/usr/bin/env -S vim -S ", "let x=1"];
+        let node = parse_lines(&code).unwrap();
+        let format = |keep_byte_order_mark| {
+            let config = FormatterConfig {
+                keep_byte_order_mark,
+                ..Default::default()
+            };
+            let mut streamed = vec![];
+            Formatter::with_config(config.clone())
+                .format_to(&node, &mut streamed)
+                .unwrap();
+            let output = Formatter::with_config(config).format(&node).unwrap();
+            assert_eq!(String::from_utf8(streamed).unwrap(), output);
+            output
+        };
+        assert_eq!(
+            format(true),
+            "\u{feff}#!/usr/bin/env -S vim -S \nlet x = 1\n"
+        );
+        assert_eq!(format(false), "#!/usr/bin/env -S vim -S \nlet x = 1\n");
+    }
+
+    #[test]
+    fn test_initial_indent() {
+        let code = ["if x", "call Foo()", "endif", "\" comment"];
+        let node = parse_lines(&code).unwrap();
+        let config = FormatterConfig {
+            initial_indent: 1,
+            ..FormatterConfig::default()
+        };
+        assert_eq!(
+            Formatter::with_config(config).format(&node).unwrap(),
+            "  if x\n    call Foo()\n  endif\n  \" comment\n"
+        );
+    }
+
+    #[test]
+    fn test_format_stream() {
+        let code = [
+            "",
+            "\" comment",
+            "set nowrap",
+            "set ts=4 \" trailing",
+            "set sw=4",
+            "",
+            "",
+            "function! Foo()",
+            "let x = [",
+            "      \\ 1,",
+            "      \"\\ continuation comment",
+            "      \\ 2]",
+            "endfunction",
+            "echo 1 | echo 2",
+            "hi Comment ctermfg=244",
+            "hi LineNr ctermfg=240 ctermbg=234",
+            "let y = 1 \" trailing",
+            "",
+        ];
+        let node = parse_lines(&code).unwrap();
+        for merge_set_lines in &[false, true] {
+            for ensure_final_newline in &[false, true] {
+                let config = FormatterConfig {
+                    merge_set_lines: *merge_set_lines,
+                    align_highlights: *merge_set_lines,
+                    ensure_final_newline: *ensure_final_newline,
+                    ..Default::default()
+                };
+                let expected = Formatter::with_config(config.clone())
+                    .format(&node)
+                    .unwrap();
+                let mut out = vec![];
+                Formatter::with_config(config)
+                    .format_stream(parse_iter(&code), &mut out)
+                    .unwrap();
+                assert_eq!(String::from_utf8(out).unwrap(), expected);
+            }
+        }
+        let mut out = vec![];
+        let err = Formatter::default()
+            .format_stream(parse_iter(&["echo 1", "echo 2", "endif"]), &mut out)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(String::from_utf8(out).unwrap(), "echo 1\necho 2");
+    }
+
+    #[test]
+    fn test_format_to() {
+        let node = parse_lines(&[
+            "\" comment",
+            "let a = 1",
+            "let abc = 2",
+            "hi Comment ctermfg=244",
+            "hi LineNr ctermfg=240 ctermbg=234",
+            "echo a | echo abc",
+            "let z = 3 \" trailing",
+        ])
+        .unwrap();
+        for align in &[false, true] {
+            let config = FormatterConfig {
+                align_assignments: *align,
+                align_highlights: *align,
+                ensure_final_newline: true,
+                ..Default::default()
+            };
+            let expected = Formatter::with_config(config.clone())
+                .format(&node)
+                .unwrap();
+            let mut out = vec![];
+            Formatter::with_config(config)
+                .format_to(&node, &mut out)
+                .unwrap();
+            assert_eq!(String::from_utf8(out).unwrap(), expected);
         }
+        let mut out = vec![];
+        let err = Formatter::default()
+            .format_to(parse_lines(&["echo 1"]).unwrap().children()[0], &mut out)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn test_heredocs() {
+        let node = parse_lines(&[
+            "function! Foo()",
+            "lua << trim EOF",
+            "      local x = 1",
+            "",
+            "        print(x)",
+            "      EOF",
+            "if 1",
+            "python3 <<",
+            "  print(1)",
+            ".",
+            "lua << trim",
+            "    print(1)",
+            "  print(2)",
+            ".",
+            "endif",
+            "endfunction",
+        ])
+        .unwrap();
+        let format = |formatters: &[(&str, &str)]| {
+            Formatter::with_config(FormatterConfig {
+                heredoc_formatters: formatters
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+                ..Default::default()
+            })
+            .format(&node)
+            .unwrap()
+        };
+        assert_eq!(
+            format(&[]),
+            concat!(
+                "function! Foo()\n",
+                "  lua << trim EOF\n",
+                "    local x = 1\n",
+                "\n",
+                "      print(x)\n",
+                "  EOF\n",
+                "  if 1\n",
+                "    python3 <<\n",
+                "  print(1)\n",
+                ".\n",
+                "    lua << trim\n",
+                "    print(1)\n",
+                "  print(2)\n",
+                "    .\n",
+                "  endif\n",
+                "endfunction\n",
+            )
+        );
+        assert_eq!(
+            format(&[("lua", "tr a-z A-Z"), ("python3", "exit 1")]),
+            concat!(
+                "function! Foo()\n",
+                "  lua << trim EOF\n",
+                "    LOCAL X = 1\n",
+                "\n",
+                "      PRINT(X)\n",
+                "  EOF\n",
+                "  if 1\n",
+                "    python3 <<\n",
+                "  print(1)\n",
+                ".\n",
+                "    lua << trim\n",
+                "    PRINT(1)\n",
+                "  PRINT(2)\n",
+                "    .\n",
+                "  endif\n",
+                "endfunction\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_abbreviations() {
+        let node = parse_lines(&[
+            "inoreabbrev <silent> <buffer> teh the<c-o>:echo<cr>",
+            "cabbrev <expr> W getcmdtype()==':'?'w':'W'",
+        ])
+        .unwrap();
+        assert_eq!(
+            Formatter::default().format(&node).unwrap(),
+            concat!(
+                "inoreabbrev <buffer> <silent> teh the<C-o>:echo<CR>\n",
+                "cabbrev <expr> W getcmdtype() == ':' ? 'w' : 'W'\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_space_in_braces_and_brackets() {
+        let node = parse_lines(&["let x = [{'a': [1, 2]}, [], {}]"]).unwrap();
+        let format = |space_in_braces, space_in_brackets| {
+            Formatter::with_config(FormatterConfig {
+                space_in_braces,
+                space_in_brackets,
+                ..Default::default()
+            })
+            .format(&node)
+            .unwrap()
+        };
+        assert_eq!(format(false, false), "let x = [{'a': [1, 2]}, [], {}]\n");
+        assert_eq!(format(true, false), "let x = [{ 'a': [1, 2] }, [], {}]\n");
+        assert_eq!(format(false, true), "let x = [ {'a': [ 1, 2 ]}, [], {} ]\n");
+        assert_eq!(
+            format(true, true),
+            "let x = [ { 'a': [ 1, 2 ] }, [], {} ]\n"
+        );
+    }
+
+    #[test]
+    fn test_trailing_commas() {
+        let node = parse_lines(&[
+            "let x = [1, {'a': 2,},]",
+            "let y = ['list is too long', 'list is too long', 'list is too long', 'list is too']",
+            "let z = {'this': 'dict will not fit', 'this dict': 'will not fit', 'this': 'not fit'}",
+        ])
+        .unwrap();
+        let format = |trailing_commas| {
+            Formatter::with_config(FormatterConfig {
+                trailing_commas,
+                ..Default::default()
+            })
+            .format(&node)
+            .unwrap()
+        };
+        let blocks = |comma| {
+            format!(
+                concat!(
+                    "let y = [\n",
+                    "      \\ 'list is too long',\n",
+                    "      \\ 'list is too long',\n",
+                    "      \\ 'list is too long',\n",
+                    "      \\ 'list is too'{}\n",
+                    "      \\ ]\n",
+                    "let z = {{\n",
+                    "      \\ 'this': 'dict will not fit',\n",
+                    "      \\ 'this dict': 'will not fit',\n",
+                    "      \\ 'this': 'not fit'{}\n",
+                    "      \\ }}\n",
+                ),
+                comma, comma
+            )
+        };
+        assert_eq!(
+            format(TrailingCommas::Multiline),
+            format!("let x = [1, {{'a': 2}}]\n{}", blocks(","))
+        );
+        assert_eq!(
+            format(TrailingCommas::Never),
+            format!("let x = [1, {{'a': 2}}]\n{}", blocks(""))
+        );
+        assert_eq!(
+            format(TrailingCommas::Preserve),
+            format!("let x = [1, {{'a': 2,}},]\n{}", blocks(""))
+        );
+    }
+
+    #[test]
+    fn test_mapping_keys() {
+        let node = parse_lines(&[
+            "nnoremap <silent> <leader>f :Files<cr>",
+            "nmap <c-w>x <plug>(foo-bar)<sid>Baz<bar>",
+            "nnoremap <m-s-f12> <cmd>call <sid>Foo('<lt>cr>')<cr><esc>",
+            "inoremap <NotAKey> <lt>esc> \\| <s-tab> <2-leftmouse>",
+            "noremap! <c-a> <home>",
+        ])
+        .unwrap();
+        let format = |canonical_keys: &[(&str, &str)]| {
+            Formatter::with_config(FormatterConfig {
+                canonical_keys: canonical_keys
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+                ..Default::default()
+            })
+            .format(&node)
+            .unwrap()
+        };
+        assert_eq!(
+            format(&[]),
+            concat!(
+                "nnoremap <silent> <Leader>f :Files<CR>\n",
+                "nmap <C-w>x <Plug>(foo-bar)<SID>Baz<Bar>\n",
+                "nnoremap <M-S-F12> <Cmd>call <SID>Foo('<lt>cr>')<CR><Esc>\n",
+                "inoremap <NotAKey> <lt>esc> \\| <S-Tab> <2-LeftMouse>\n",
+                "noremap! <C-a> <Home>\n",
+            )
+        );
+        assert_eq!(
+            format(&[("esc", "ESC"), ("cr", "Enter")]),
+            concat!(
+                "nnoremap <silent> <Leader>f :Files<Enter>\n",
+                "nmap <C-w>x <Plug>(foo-bar)<SID>Baz<Bar>\n",
+                "nnoremap <M-S-F12> <Cmd>call <SID>Foo('<lt>cr>')<Enter><ESC>\n",
+                "inoremap <NotAKey> <lt>esc> \\| <S-Tab> <2-LeftMouse>\n",
+                "noremap! <C-a> <Home>\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_key_notation() {
+        let node = parse_lines(&[
+            r#"call feedkeys("\<c-w>\\<c-w>\\\<esc>\<NotAKey>\<", 'n')"#,
+            r#"echo '\<cr>'"#,
+            "nunmap <buffer> <leader>f",
+            "normal! <c-w>",
+            "nnoremap <c-w> <esc>",
+        ])
+        .unwrap();
+        let format = |normalize_key_notation, normalize_keys_in_normal| {
+            Formatter::with_config(FormatterConfig {
+                normalize_key_notation,
+                normalize_keys_in_normal,
+                ..Default::default()
+            })
+            .format(&node)
+            .unwrap()
+        };
+        assert_eq!(
+            format(true, false),
+            concat!(
+                r#"call feedkeys("\<C-w>\\<c-w>\\\<Esc>\<NotAKey>\<", 'n')"#,
+                "\n",
+                r#"echo '\<cr>'"#,
+                "\n",
+                "nunmap <buffer> <Leader>f\n",
+                "normal! <c-w>\n",
+                "nnoremap <C-w> <Esc>\n",
+            )
+        );
+        assert_eq!(
+            format(true, true),
+            concat!(
+                r#"call feedkeys("\<C-w>\\<c-w>\\\<Esc>\<NotAKey>\<", 'n')"#,
+                "\n",
+                r#"echo '\<cr>'"#,
+                "\n",
+                "nunmap <buffer> <Leader>f\n",
+                "normal! <C-w>\n",
+                "nnoremap <C-w> <Esc>\n",
+            )
+        );
+        assert_eq!(
+            format(false, false),
+            concat!(
+                r#"call feedkeys("\<c-w>\\<c-w>\\\<esc>\<NotAKey>\<", 'n')"#,
+                "\n",
+                r#"echo '\<cr>'"#,
+                "\n",
+                "nunmap <buffer> <leader>f\n",
+                "normal! <c-w>\n",
+                "nnoremap <c-w> <esc>\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_verbatim() {
+        let node = parse_lines(&[
+            "Plug 'foo/bar'",
+            "if 1",
+            "  Plug 'baz/qux'",
+            "endif",
+            "terminal  ls",
+            "lua << EOF",
+            "print(1)",
+            "EOF",
+            "append",
+            "text",
+            ".",
+            "echo 1",
+        ])
+        .unwrap();
+        let mut formatter = Formatter::default();
+        formatter.format(&node).unwrap();
+        assert_eq!(
+            crate::report::verbatim_summary(formatter.verbatim()),
+            concat!(
+                "written verbatim: 5\n",
+                "  user command Plug: 2 (1:1, 3:3)\n",
+                "  opaque command terminal: 1 (5:1)\n",
+                "  script lua: 1 (6:1)\n",
+                "  text append: 1 (9:1)",
+            )
+        );
+        formatter
+            .format(&parse_lines(&["echo 1"]).unwrap())
+            .unwrap();
+        assert!(formatter.verbatim().is_empty());
+    }
+
+    #[test]
+    fn test_opaque_commands() {
+        let long = "x".repeat(90);
+        let node = parse_lines(&[
+            "MyCmd   a  b    c  ",
+            "!ls   -la  |  grep x",
+            "silent ! echo  hi",
+            "terminal   ++close  bash -c \"x  y\"  ",
+            "make  -j4  ",
+            &format!("MyCmd {}", long),
+            "if 1",
+            "Git! commit  -m 'x  y'",
+            "endif",
+        ])
+        .unwrap();
+        let format = |opaque_commands: &[&str]| {
+            let mut config = FormatterConfig::default();
+            config
+                .opaque_commands
+                .extend(opaque_commands.iter().map(|c| c.to_string()));
+            Formatter::with_config(config).format(&node).unwrap()
+        };
+        assert_eq!(
+            format(&[]),
+            format!(
+                concat!(
+                    "MyCmd a  b    c\n",
+                    "!ls   -la  |  grep x\n",
+                    "silent ! echo  hi\n",
+                    "terminal ++close  bash -c \"x  y\"  \n",
+                    "make -j4\n",
+                    "MyCmd {}\n",
+                    "if 1\n",
+                    "  Git! commit  -m 'x  y'\n",
+                    "endif\n",
+                ),
+                long
+            )
+        );
+        assert!(format(&["make"]).contains("\nmake -j4  \n"));
+    }
+
+    #[test]
+    fn test_sort_modifiers() {
+        let node =
+            parse_lines(&["keepj silent! normal! gg", "2tab vert sil keepalt split"]).unwrap();
+        assert_eq!(
+            Formatter::default().format(&node).unwrap(),
+            "keepjumps silent! normal! gg\n2tab vertical silent keepalt split\n"
+        );
+        let config = FormatterConfig {
+            sort_modifiers: true,
+            ..FormatterConfig::default()
+        };
+        assert_eq!(
+            Formatter::with_config(config).format(&node).unwrap(),
+            "silent! keepjumps normal! gg\nsilent keepalt vertical 2tab split\n"
+        );
+    }
+
+    #[test]
+    fn test_ranges() {
+        let node = parse_lines(&[
+            ":1 , $ delete",
+            ":5",
+            "silent! 'a,'b s/x/y/",
+            "'<,'>call Foo()",
+            "% normal! gg",
+            ":.,.+3!sort",
+        ])
+        .unwrap();
+        assert_eq!(
+            Formatter::default().format(&node).unwrap(),
+            concat!(
+                "1,$delete\n",
+                "5\n",
+                "silent! 'a,'bs/x/y/\n",
+                "'<,'>call Foo()\n",
+                "%normal! gg\n",
+                ".,.+3!sort\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_continuations() {
+        let node = parse_lines(&[
+            "let x = [",
+            "      \\ 1, 2,",
+            "      \\ 3]",
+            "call Foo(a,",
+            "      \\ b)",
+            "command! Foo",
+            "      \\ call Bar()",
+            "if a",
+            "      \\ && b",
+            "  let y = 1 +",
+            "        \\ 2",
+            &format!("  call Foo('{}',", "a".repeat(60)),
+            &format!("        \\ '{}')", "b".repeat(20)),
+            "endif",
+            "autocmd BufRead * call Foo(1,",
+            "      \\ 2)",
+        ])
+        .unwrap();
+        let long = format!(
+            "  call Foo('{}',\n        \\ '{}')\n",
+            "a".repeat(60),
+            "b".repeat(20)
+        );
+        let format = |continuations| {
+            let config = FormatterConfig {
+                continuations,
+                ..FormatterConfig::default()
+            };
+            Formatter::with_config(config).format(&node).unwrap()
+        };
+        assert_eq!(
+            format(Continuations::Rewrap),
+            concat!(
+                "let x = [1, 2, 3]\n",
+                "call Foo(a, b)\n",
+                "command! Foo call Bar()\n",
+                "if a && b\n",
+                "  let y = 1 + 2\n",
+                "{}",
+                "endif\n",
+                "autocmd BufRead * call Foo(1, 2)\n",
+            )
+            .replace("{}", &long)
+        );
+        assert_eq!(
+            format(Continuations::Preserve),
+            concat!(
+                "let x = [\n",
+                "      \\ 1, 2,\n",
+                "      \\ 3]\n",
+                "call Foo(a,\n",
+                "      \\ b)\n",
+                "command! Foo call Bar()\n",
+                "if a &&\n",
+                "      \\ b\n",
+                "  let y = 1 +\n",
+                "        \\ 2\n",
+                "{}",
+                "endif\n",
+                "autocmd BufRead * call Foo(1, 2)\n",
+            )
+            .replace("{}", &long)
+        );
+        assert_eq!(
+            format(Continuations::Join),
+            concat!(
+                "let x = [1, 2, 3]\n",
+                "call Foo(a, b)\n",
+                "command! Foo call Bar()\n",
+                "if a &&\n",
+                "      \\ b\n",
+                "  let y = 1 + 2\n",
+                "{}",
+                "endif\n",
+                "autocmd BufRead * call Foo(1, 2)\n",
+            )
+            .replace("{}", &long)
+        );
+    }
+
+    #[test]
+    fn test_continuation_indent() {
+        let node = parse_lines(&[
+            "if 1",
+            "  call s:Setup('a very long argument', [another_argument, 'and another'], \
+             'and a value')",
+            "endif",
+        ])
+        .unwrap();
+        let format = |continuation_indent| {
+            Formatter::with_config(FormatterConfig {
+                continuation_indent,
+                ..FormatterConfig::default()
+            })
+            .format(&node)
+            .unwrap()
+        };
+        assert_eq!(
+            format(ContinuationIndent::Spaces(6)),
+            concat!(
+                "if 1\n",
+                "  call s:Setup('a very long argument', [another_argument, 'and another'],\n",
+                "        \\ 'and a value')\n",
+                "endif\n",
+            )
+        );
+        assert_eq!(
+            format(ContinuationIndent::Double),
+            concat!(
+                "if 1\n",
+                "  call s:Setup('a very long argument', [another_argument, 'and another'],\n",
+                "      \\ 'and a value')\n",
+                "endif\n",
+            )
+        );
+        assert_eq!(
+            format(ContinuationIndent::Aligned),
+            concat!(
+                "if 1\n",
+                "  call s:Setup('a very long argument', [another_argument, 'and another'],\n",
+                "             \\ 'and a value')\n",
+                "endif\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_keep_sorted() {
+        let code = [
+            "let g:unsorted = ['b', 'a']",
+            "\" vimlfmt: keep-sorted",
+            "let g:plugins = ['tpope/vim-surround', \"junegunn/fzf\", 'airblade/vim-gitgutter']",
+            "let g:numbers = [10, 2, 0x1, [3, 1]] + [10, 2.5, 0x1]",
+            "if 1",
+            "  call add(g:x, ['z', 'y'])",
+            "  \" vimlfmt: keep-sorted",
+            "endif",
+            "let g:also = ['d', 'c']",
+            "",
+            "let g:not = ['f', 'e']",
+        ];
+        let node = parse_lines(&code).unwrap();
+        assert_eq!(
+            Formatter::default().format(&node).unwrap(),
+            concat!(
+                "let g:unsorted = ['b', 'a']\n",
+                "\" vimlfmt: keep-sorted\n",
+                "let g:plugins = ['airblade/vim-gitgutter', \"junegunn/fzf\", 'tpope/vim-surround']\n",
+                "let g:numbers = [10, 2, 0x1, [1, 3]] + [0x1, 2.5, 10]\n",
+                "if 1\n",
+                "  call add(g:x, ['y', 'z'])\n",
+                "  \" vimlfmt: keep-sorted\n",
+                "endif\n",
+                "let g:also = ['c', 'd']\n",
+                "\n",
+                "let g:not = ['f', 'e']\n",
+            )
+        );
+        let code = [
+            "\" vimlfmt: keep-sorted",
+            "let g:strings = [\"a\", 'a b', \"B\", 'ab', 'it''s', \"a\\\\b\"]",
+            "let g:numbers = [0x10, 0b11, 12, 1.5]",
+            "let g:octal = [017, 16]",
+            "let g:escaped = [\"b\", \"a\\tb\"]",
+        ];
+        let node = parse_lines(&code).unwrap();
+        assert_eq!(
+            Formatter::default().format(&node).unwrap(),
+            concat!(
+                "\" vimlfmt: keep-sorted\n",
+                "let g:strings = [\"B\", \"a\", 'a b', \"a\\\\b\", 'ab', 'it''s']\n",
+                "let g:numbers = [1.5, 0b11, 12, 0x10]\n",
+                "let g:octal = [017, 16]\n",
+                "let g:escaped = [\"b\", \"a\\tb\"]\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_align_highlights() {
+        let code = [
+            "hi Normal guifg=#d0d0d0 ctermfg=252 ctermbg=234",
+            "hi Comment guifg=#808080 ctermfg=244 cterm=italic",
+            "hi default LineNr ctermfg=240",
+            "hi link Foo Bar",
+            "hi Error ctermfg=1 guifg=#ff0000 guibg=#000000",
+            "hi Todo ctermfg=2 guifg=#00ff00 guibg=#000000 gui=bold,underline,italic",
+        ];
+        let node = parse_lines(&code).unwrap();
+        let format = |highlight_attr_order, align_highlights| {
+            Formatter::with_config(FormatterConfig {
+                highlight_attr_order,
+                align_highlights,
+                ..FormatterConfig::default()
+            })
+            .format(&node)
+            .unwrap()
+        };
+        assert_eq!(
+            format(HighlightAttrOrder::Canonical, false),
+            concat!(
+                "highlight Normal ctermfg=252 ctermbg=234 guifg=#d0d0d0\n",
+                "highlight Comment cterm=italic ctermfg=244 guifg=#808080\n",
+                "highlight default LineNr ctermfg=240\n",
+                "highlight link Foo Bar\n",
+                "highlight Error ctermfg=1 guifg=#ff0000 guibg=#000000\n",
+                "highlight Todo ctermfg=2 gui=bold,underline,italic guifg=#00ff00 guibg=#000000\n",
+            )
+        );
+        assert_eq!(
+            format(HighlightAttrOrder::Canonical, true),
+            concat!(
+                "highlight Normal                      ctermfg=252 ctermbg=234 guifg=#d0d0d0\n",
+                "highlight Comment        cterm=italic ctermfg=244             guifg=#808080\n",
+                "highlight default LineNr              ctermfg=240\n",
+                "highlight link Foo Bar\n",
+                "highlight Error ctermfg=1                           guifg=#ff0000 guibg=#000000\n",
+                "highlight Todo  ctermfg=2 gui=bold,underline,italic guifg=#00ff00 guibg=#000000\n",
+            )
+        );
+        assert_eq!(
+            format(HighlightAttrOrder::Preserve, false).lines().next(),
+            Some("highlight Normal guifg=#d0d0d0 ctermfg=252 ctermbg=234")
+        );
+    }
+
+    #[test]
+    fn test_mapping_attr_order() {
+        let code = [
+            "nnoremap <unique> <SILENT> <expr> <buffer> <leader>x Foo()",
+            "iabbrev <special> <buffer> <nowait> teh the",
+        ];
+        let node = parse_lines(&code).unwrap();
+        let format = |mapping_attr_order| {
+            Formatter::with_config(FormatterConfig {
+                mapping_attr_order,
+                ..FormatterConfig::default()
+            })
+            .format(&node)
+            .unwrap()
+        };
+        assert_eq!(
+            format(MappingAttrOrder::Canonical),
+            concat!(
+                "nnoremap <buffer> <silent> <expr> <unique> <Leader>x Foo()\n",
+                "iabbrev <buffer> <nowait> <special> teh the\n",
+            )
+        );
+        assert_eq!(
+            format(MappingAttrOrder::Alphabetical),
+            concat!(
+                "nnoremap <buffer> <expr> <silent> <unique> <Leader>x Foo()\n",
+                "iabbrev <buffer> <nowait> <special> teh the\n",
+            )
+        );
+        assert_eq!(
+            format(MappingAttrOrder::Preserve),
+            concat!(
+                "nnoremap <unique> <silent> <expr> <buffer> <Leader>x Foo()\n",
+                "iabbrev <special> <buffer> <nowait> teh the\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_expand_option_names() {
+        let node = parse_lines(&["let &l:sw = &ts", "let &g:t_Co += &shiftwidth"]).unwrap();
+        let formatter = |expand_option_names| {
+            Formatter::with_config(FormatterConfig {
+                expand_option_names,
+                ..FormatterConfig::default()
+            })
+        };
+        assert_eq!(
+            formatter(false).format(&node).unwrap(),
+            "let &l:sw = &ts\nlet &g:t_Co += &shiftwidth\n"
+        );
+        assert_eq!(
+            formatter(true).format(&node).unwrap(),
+            "let &l:shiftwidth = &tabstop\nlet &g:t_Co += &shiftwidth\n"
+        );
+    }
+
+    #[test]
+    fn test_normalize_execute() {
+        let code = [
+            r#"execute "normal! ".count."j""#,
+            r#"exe "echo 'it''s'" .. "\<CR>" . escape(x, "\\")"#,
+        ];
+        let node = parse_lines(&code).unwrap();
+        let format = |single_quote_execute, execute_concatenation| {
+            Formatter::with_config(FormatterConfig {
+                single_quote_execute,
+                execute_concatenation,
+                ..FormatterConfig::default()
+            })
+            .format(&node)
+            .unwrap()
+        };
+        assert_eq!(
+            format(false, Concatenation::Preserve),
+            concat!(
+                r#"execute "normal! " . count . "j""#,
+                "\n",
+                r#"execute "echo 'it''s'" .. "\<CR>" . escape(x, "\\")"#,
+                "\n",
+            )
+        );
+        assert_eq!(
+            format(true, Concatenation::DoubleDot),
+            concat!(
+                "execute 'normal! ' .. count .. 'j'\n",
+                r#"execute 'echo ''it''''s''' .. "\<CR>" .. escape(x, "\\")"#,
+                "\n",
+            )
+        );
+        assert_eq!(
+            format(false, Concatenation::Dot).lines().nth(1),
+            Some(r#"execute "echo 'it''s'" . "\<CR>" . escape(x, "\\")"#)
+        );
+    }
+
+    #[test]
+    fn test_colorscheme_profile() {
+        let long = format!(
+            "let g:terminal_ansi_colors = [{}]",
+            vec!["'#000000'"; 16].join(", ")
+        );
+        let code = [
+            "hi clear",
+            "",
+            "",
+            "hi normal guifg=#d0d0d0 ctermfg=252",
+            "hi comment ctermfg=244",
+            "hi link vimCommand statement",
+            "",
+            "",
+            "",
+            long.as_str(),
+        ];
+        let node = parse_lines(&code).unwrap();
+        let format = |profile| {
+            Formatter::with_config(FormatterConfig::with_profile(profile))
+                .format(&node)
+                .unwrap()
+        };
+        assert_eq!(
+            format(Profile::Colorscheme),
+            format!(
+                concat!(
+                    "highlight clear\n",
+                    "\n",
+                    "\n",
+                    "highlight Normal  ctermfg=252 guifg=#d0d0d0\n",
+                    "highlight Comment ctermfg=244\n",
+                    "highlight link vimCommand Statement\n",
+                    "\n",
+                    "\n",
+                    "\n",
+                    "{}\n",
+                ),
+                long
+            )
+        );
+        let default = format(Profile::Default);
+        assert!(default.starts_with("highlight clear\n\nhighlight normal ctermfg=252"));
+        assert!(default.contains("\n      \\ "));
+    }
+
+    #[test]
+    fn test_style_editions() {
+        let node = parse_lines(&["keepjumps silent! echo 1 |"]).unwrap();
+        let format = |profile, edition: &str| {
+            let edition = edition.parse().unwrap();
+            Formatter::with_config(FormatterConfig::with_style(profile, edition))
+                .format(&node)
+                .unwrap()
+        };
+        assert_eq!(
+            format(Profile::Default, "2023"),
+            "keepjumps silent! echo 1 |\n"
+        );
+        assert_eq!(format(Profile::Vimrc, "2024"), "silent! keepjumps echo 1\n");
+        assert_eq!(
+            StyleEdition::from_toml("style_edition = \"2024\"\n[lint.rules]\n"),
+            Ok(Some(StyleEdition::Edition2024))
+        );
+        assert_eq!(StyleEdition::from_toml(""), Ok(None));
+        assert!(StyleEdition::from_toml("style_edition = \"2019\"").is_err());
+        assert!(StyleEdition::from_toml("style_edition = 2024").is_err());
+    }
+
+    #[test]
+    fn test_alignment_uses_display_width() {
+        let code = [
+            "let d['名'] = 1",
+            "let d['abc'] = 2",
+            "hi A guibg=名 guifg=x",
+            "hi B guibg=#ff guifg=y",
+        ];
+        let node = parse_lines(&code).unwrap();
+        let config = FormatterConfig {
+            align_assignments: true,
+            align_highlights: true,
+            ..FormatterConfig::default()
+        };
+        let expected = concat!(
+            "let d['名']  = 1\n",
+            "let d['abc'] = 2\n",
+            "highlight A guibg=名  guifg=x\n",
+            "highlight B guibg=#ff guifg=y\n",
+        );
+        assert_eq!(
+            Formatter::with_config(config).format(&node).unwrap(),
+            expected
+        );
+        assert_eq!(display_width("a\t名"), 10);
+    }
+
+    #[test]
+    fn test_align_assignments() {
+        let code = [
+            "let a = 1",
+            "let abc += 2",
+            "let s:x[0] = 3",
+            "",
+            "let long_name_for_a_thing = 4",
+            "let b = 5",
+            "\" comment",
+            "let c = 6",
+            "let dd = 7 | let e = 8",
+            "let very_long_name_that_is_too_far = 9",
+        ];
+        let node = parse_lines(&code).unwrap();
+        let config = FormatterConfig {
+            align_assignments: true,
+            ..FormatterConfig::default()
+        };
+        let expected = concat!(
+            "let a      = 1\n",
+            "let abc   += 2\n",
+            "let s:x[0] = 3\n",
+            "\n",
+            "let long_name_for_a_thing = 4\n",
+            "let b = 5\n",
+            "\" comment\n",
+            "let c  = 6\n",
+            "let dd = 7\n",
+            "let e  = 8\n",
+            "let very_long_name_that_is_too_far = 9\n",
+        );
+        assert_eq!(
+            Formatter::with_config(config.clone())
+                .format(&node)
+                .unwrap(),
+            expected
+        );
+        // formatting the output again doesn't change it
+        let lines = expected.lines().collect::<Vec<_>>();
+        assert_eq!(
+            Formatter::with_config(config.clone())
+                .format(&parse_lines(&lines).unwrap())
+                .unwrap(),
+            expected
+        );
+        let mut streamed = vec![];
+        Formatter::with_config(config)
+            .format_stream(parse_iter(&code), &mut streamed)
+            .unwrap();
+        assert_eq!(String::from_utf8(streamed).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_compact_echohl() {
+        let code = [
+            "echohl WarningMsg | echo 'x' | echomsg 'y' | echohl None",
+            "if 1",
+            "  echohl ErrorMsg | echon 'z' | echohl NONE",
+            "endif",
+            "echohl Title",
+            "echo 'not on one line'",
+            "echohl None",
+            "echohl Title | echo 'incomplete'",
+            &format!("echohl Title | echo '{}' | echohl None", "x".repeat(60)),
+        ];
+        let node = parse_lines(&code).unwrap();
+        let expanded = Formatter::default().format(&node).unwrap();
+        assert_eq!(expanded.lines().count(), 17);
+        let config = FormatterConfig {
+            compact_echohl: true,
+            ..FormatterConfig::default()
+        };
+        let expected = format!(
+            concat!(
+                "echohl WarningMsg | echo 'x' | echomsg 'y' | echohl None\n",
+                "if 1\n",
+                "  echohl ErrorMsg | echon 'z' | echohl NONE\n",
+                "endif\n",
+                "echohl Title\n",
+                "echo 'not on one line'\n",
+                "echohl None\n",
+                "echohl Title\n",
+                "echo 'incomplete'\n",
+                "echohl Title\n",
+                "echo '{}'\n",
+                "echohl None\n",
+            ),
+            "x".repeat(60)
+        );
+        assert_eq!(
+            Formatter::with_config(config.clone())
+                .format(&node)
+                .unwrap(),
+            expected
+        );
+        let mut out = vec![];
+        Formatter::with_config(config)
+            .format_stream(parse_iter(&code), &mut out)
+            .unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_report() {
+        let code = [
+            "",
+            "let x =   1",
+            "\" a comment",
+            "if x",
+            "echo   'y'",
+            "  echo 'z'",
+            "endif",
+            "append",
+            "  text  ",
+            ".",
+            "call   Foo(1,",
+            "      \\ 2)",
+        ];
+        let node = parse_lines(&code).unwrap();
+        let mut formatter = Formatter::default();
+        let output = formatter.format(&node).unwrap();
+        assert_eq!(
+            output,
+            concat!(
+                "let x = 1\n",
+                "\" a comment\n",
+                "if x\n",
+                "  echo 'y'\n",
+                "  echo 'z'\n",
+                "endif\n",
+                "append\n",
+                "  text  \n",
+                ".\n",
+                "call Foo(1, 2)\n",
+            )
+        );
+        let report = formatter.report(&code);
+        assert_eq!(
+            report,
+            Report {
+                statements: 7,
+                statements_changed: 3,
+                lines_changed: 5,
+                longest_line_before: 13,
+                longest_line_after: 14,
+                ..Report::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_safe() {
+        let code = [
+            "let   x=1",
+            "Custom   foo  bar | let y=2",
+            "function!   Foo()",
+            "  syntax   on",
+            "endfunction",
+            "let   z=3   \" note",
+        ];
+        let node = parse_lines(&code).unwrap();
+        let mut formatter = Formatter::with_config(FormatterConfig {
+            safe: true,
+            ..Default::default()
+        });
+        assert_eq!(
+            formatter.format_source(&node, &code).unwrap(),
+            concat!(
+                "let x = 1\n",
+                "Custom   foo  bar | let y=2\n",
+                "function!   Foo()\n",
+                "  syntax   on\n",
+                "endfunction\n",
+                "let z = 3 \" note\n",
+            )
+        );
+        // without the source there is nothing to copy
+        assert!(formatter.format(&node).unwrap().contains("  syntax on\n"));
+    }
+
+    #[test]
+    fn test_format_ranges() {
+        let code = [
+            "let   x=1",
+            "function!   Foo()",
+            "echo   1",
+            "  \" comment",
+            "endfunction",
+            "",
+            "",
+            "let y = [",
+            "      \\ 1,",
+            "      \"\\ two",
+            "      \\ 2]",
+            "let z=3 \" trailing",
+        ];
+        let node = parse_lines(&code).unwrap();
+        let mut formatter = Formatter::default();
+        assert_eq!(
+            formatter.format_ranges(&node, &code, &[(3, 3)]).unwrap(),
+            concat!(
+                "let   x=1\n",
+                "\n",
+                "function! Foo()\n",
+                "  echo 1\n",
+                "  \" comment\n",
+                "endfunction\n",
+                "\n",
+                "\n",
+                "let y = [\n",
+                "      \\ 1,\n",
+                "      \"\\ two\n",
+                "      \\ 2]\n",
+                "let z=3 \" trailing\n",
+            )
+        );
+        assert_eq!(
+            formatter
+                .format_ranges(&node, &code, &[(1, 1), (12, 12)])
+                .unwrap(),
+            concat!(
+                "let x = 1\n",
+                "function!   Foo()\n",
+                "echo   1\n",
+                "  \" comment\n",
+                "endfunction\n",
+                "\n",
+                "\n",
+                "let y = [\n",
+                "      \\ 1,\n",
+                "      \"\\ two\n",
+                "      \\ 2]\n",
+                "let z = 3 \" trailing\n",
+            )
+        );
+        assert_eq!(
+            formatter.format_ranges(&node, &code, &[]).unwrap(),
+            format!("{}\n", code.join("\n"))
+        );
+        assert_eq!(
+            formatter
+                .format_ranges(&node, &code, &[(1, usize::MAX)])
+                .unwrap(),
+            formatter.format(&node).unwrap()
+        );
     }
 }
@@ -0,0 +1,94 @@
+use std::{
+    io::{self, Error},
+    path::Path,
+    process::Command,
+};
+
+/// The lines added or changed by a diff made with `git diff -U0`, as 1-based, inclusive ranges of
+/// lines in the new version of the file. Where lines were only removed, the line before the
+/// removal (or the first line) is counted as changed, so the statement around it is formatted.
+pub fn parse_hunks(diff: &str) -> Vec<(usize, usize)> {
+    let mut ranges = vec![];
+    for line in diff.lines() {
+        // e.g. `@@ -12,3 +14,5 @@ function! Foo()`
+        let new = match line
+            .strip_prefix("@@ ")
+            .and_then(|rest| rest.split_whitespace().nth(1))
+            .and_then(|new| new.strip_prefix('+'))
+        {
+            Some(new) => new,
+            None => continue,
+        };
+        let mut parts = new.splitn(2, ',').map(|n| n.parse::<usize>());
+        let start = match parts.next() {
+            Some(Ok(start)) => start,
+            _ => continue,
+        };
+        match parts.next() {
+            Some(Ok(0)) => ranges.push((start.max(1), start.max(1))),
+            Some(Ok(count)) => ranges.push((start, start + count - 1)),
+            Some(Err(_)) => (),
+            None => ranges.push((start, start)),
+        }
+    }
+    ranges
+}
+
+/// The lines of a file that git says have changed since `HEAD`, or only those changes that are
+/// staged if `staged` is true (in which case the line numbers are those of the staged version of
+/// the file). A file that git doesn't know about has changed entirely.
+pub fn changed_lines(path: &Path, staged: bool) -> io::Result<Vec<(usize, usize)>> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let file = path.file_name().unwrap_or(path.as_os_str());
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .current_dir(dir)
+            .args(args)
+            .arg("--")
+            .arg(file)
+            .output()
+    };
+    if !git(&["ls-files", "--error-unmatch"])?.status.success() {
+        return Ok(vec![(1, usize::MAX)]);
+    }
+    let mut args = vec!["diff", "--no-color", "--no-ext-diff", "-U0"];
+    args.push(if staged { "--cached" } else { "HEAD" });
+    let output = git(&args)?;
+    if !output.status.success() {
+        return Err(Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(parse_hunks(&String::from_utf8_lossy(&output.stdout)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hunks() {
+        let diff = concat!(
+            "diff --git a/foo.vim b/foo.vim\n",
+            "index 1234567..89abcde 100644\n",
+            "--- a/foo.vim\n",
+            "+++ b/foo.vim\n",
+            "@@ -3 +3 @@ let x = 1\n",
+            "-let y = 2\n",
+            "+let y = 3\n",
+            "@@ -10,0 +11,2 @@ function! Foo()\n",
+            "+  echo 1\n",
+            "+  echo 2\n",
+            "@@ -20,2 +21,0 @@\n",
+            "-echo 3\n",
+            "-echo 4\n",
+            "@@ -1 +0,0 @@\n",
+            "-\" first line\n",
+        );
+        assert_eq!(parse_hunks(diff), vec![(3, 3), (11, 12), (21, 21), (1, 1)]);
+        assert_eq!(parse_hunks(""), vec![]);
+    }
+}
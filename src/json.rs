@@ -0,0 +1,114 @@
+//! A JSON representation of parsed VimL, for programs that want the syntax tree but can't link
+//! against Rust. Each node is an object like
+//!
+//! ```text
+//! {"type": "Let", "line": 1, "column": 1, "children": [...]}
+//! ```
+//!
+//! where `type` is the [kind](../../viml_parser/enum.Node.html#method.kind) of the node and
+//! `children` are its [children](../../viml_parser/enum.Node.html#method.children). Nodes without
+//! children (identifiers, numbers, strings, comments, commands kept as text and so on) have a
+//! `text` member holding the node as it is displayed instead.
+use crate::diagnostic::display_path;
+use viml_parser::{Node, ParseError};
+
+/// Escape a string for use as a JSON string, including the quotes.
+pub fn string(s: &str) -> String {
+    let mut rv = String::with_capacity(s.len() + 2);
+    rv.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => rv.push_str("\\\""),
+            '\\' => rv.push_str("\\\\"),
+            '\n' => rv.push_str("\\n"),
+            '\r' => rv.push_str("\\r"),
+            '\t' => rv.push_str("\\t"),
+            c if (c as u32) < 0x20 => rv.push_str(&format!("\\u{:04x}", c as u32)),
+            c => rv.push(c),
+        }
+    }
+    rv.push('"');
+    rv
+}
+
+/// A node and all of its children as JSON.
+pub fn node(node: &Node) -> String {
+    let mut rv = String::new();
+    write_node(node, &mut rv);
+    rv
+}
+
+fn write_node(node: &Node, out: &mut String) {
+    let pos = node.pos();
+    out.push_str(&format!(
+        "{{\"type\":{},\"line\":{},\"column\":{}",
+        string(node.kind()),
+        pos.line(),
+        pos.column()
+    ));
+    let children = node.children();
+    if children.is_empty() {
+        out.push_str(&format!(",\"text\":{}", string(&node.to_string())));
+    } else {
+        out.push_str(",\"children\":[");
+        for (i, child) in children.into_iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            write_node(child, out);
+        }
+        out.push(']');
+    }
+    out.push('}');
+}
+
+/// A parse error as JSON, e.g.
+/// `{"error":{"message":"E492: Not an editor command: foo","line":1,"column":1,"code":492}}`.
+/// `code` is `null` for errors Vim has no number for. Errors with a path also have a `path` key.
+pub fn error(error: &ParseError) -> String {
+    let path = error.path.as_ref().map_or(String::new(), |p| {
+        format!("\"path\":{},", string(&display_path(p)))
+    });
+    format!(
+        "{{\"error\":{{{}\"message\":{},\"line\":{},\"column\":{},\"code\":{}}}}}",
+        path,
+        string(error.message()),
+        error.pos.line(),
+        error.pos.column(),
+        error
+            .code
+            .number()
+            .map_or("null".to_string(), |n| n.to_string())
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use viml_parser::parse_lines;
+
+    #[test]
+    fn test_node() {
+        let ast = parse_lines(&["let x = \"a\\tb\"", "\" c"]).unwrap();
+        assert_eq!(
+            node(&ast),
+            concat!(
+                r#"{"type":"TopLevel","line":1,"column":1,"children":["#,
+                r#"{"type":"Let","line":1,"column":1,"children":["#,
+                r#"{"type":"Identifier","line":1,"column":5,"text":"x"},"#,
+                r#"{"type":"String","line":1,"column":9,"text":"\"a\\tb\""}]},"#,
+                r#"{"type":"Comment","line":2,"column":1,"text":"; c"}]}"#,
+            )
+        );
+        assert_eq!(string("\u{1}\n"), r#""\u0001\n""#);
+    }
+
+    #[test]
+    fn test_error() {
+        let e = parse_lines(&["foo"]).unwrap_err();
+        assert_eq!(
+            error(&e),
+            r#"{"error":{"message":"E492: Not an editor command: foo","line":1,"column":1,"code":492}}"#
+        );
+    }
+}
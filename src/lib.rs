@@ -0,0 +1,25 @@
+//! The formatter behind the `vimlfmt` command, for programs that produce VimL from a parsed or
+//! built [Node](../viml_parser/enum.Node.html) tree.
+pub mod analysis;
+pub mod cache;
+pub mod completion;
+pub mod config;
+pub mod convert;
+#[cfg(unix)]
+pub mod daemon;
+pub mod deprecated;
+pub mod diagnostic;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fix;
+pub mod formatter;
+pub mod git;
+pub mod json;
+pub mod lint;
+pub mod pipeline;
+pub mod porcelain;
+pub mod project;
+pub mod report;
+pub mod source_map;
+pub mod tree;
+pub mod watch;
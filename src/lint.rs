@@ -0,0 +1,1320 @@
+use crate::{
+    analysis,
+    config::{HeaderConfig, LintConfig, RuleLevel},
+    deprecated::{Feature, Status},
+    diagnostic::display_path,
+    fix::{self, Edit, Fix},
+    project::Project,
+};
+use std::{collections::HashSet, fmt, ops::Range, path::Path};
+use viml_parser::{parse_lines, valid_autocmds, Node, ParseError, Position};
+
+/// The name of the rule requiring functions to have the `abort` attribute.
+pub const MISSING_ABORT: &str = "missing-abort";
+/// The name of the rule requiring autocommands to be defined in a group.
+pub const AUTOCMD_OUTSIDE_AUGROUP: &str = "autocmd-outside-augroup";
+/// The name of the rule reporting the use of deprecated or removed features.
+pub const DEPRECATED: &str = "deprecated";
+/// The name of the rule reporting script-local functions and variables that are never used.
+pub const UNUSED: &str = "unused";
+/// The name of the rule requiring functions and user commands to be defined with `!`.
+pub const MISSING_BANG: &str = "missing-bang";
+/// The name of the rule reporting autocommand events the target editor doesn't have.
+pub const UNAVAILABLE_EVENT: &str = "unavailable-event";
+/// The name of the rule reporting functions that use the variables of the function they are
+/// defined in without the `closure` attribute.
+pub const MISSING_CLOSURE: &str = "missing-closure";
+/// The name of the rule requiring scripts to start with `scriptencoding` and a load guard.
+pub const MISSING_HEADER: &str = "missing-header";
+/// The name of the rule reporting calls to a project's autoload functions that aren't defined.
+pub const MISSING_AUTOLOAD_FUNCTION: &str = "missing-autoload-function";
+
+/// The names of all the rules.
+pub const RULES: &[&str] = &[
+    MISSING_ABORT,
+    AUTOCMD_OUTSIDE_AUGROUP,
+    DEPRECATED,
+    UNUSED,
+    MISSING_BANG,
+    UNAVAILABLE_EVENT,
+    MISSING_CLOSURE,
+    MISSING_HEADER,
+    MISSING_AUTOLOAD_FUNCTION,
+];
+
+/// A comment that stops the rules named after it (or every rule, if none are) reporting problems
+/// in the statement below it, e.g. `" vimlfmt-disable-next-line unused, missing-abort`.
+pub const DISABLE_NEXT_LINE: &str = "vimlfmt-disable-next-line";
+
+/// How many times [fix](fn.fix.html) lints and fixes a file before giving up on fixes that keep
+/// conflicting with each other.
+const MAX_FIX_PASSES: usize = 10;
+
+/// How sure a rule is that it has found a problem.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Severity {
+    Error,
+    /// Something that might be a problem, which the rule can't be sure of.
+    Warning,
+}
+
+/// A problem found by one of the lint rules.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Diagnostic {
+    /// The name of the rule that found the problem, e.g. `"missing-abort"`.
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub pos: Position,
+    pub msg: String,
+    /// How to fix the problem, if the rule knows how.
+    pub fix: Option<Fix>,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}, col {}: {}{} [{}]",
+            self.pos.line(),
+            self.pos.column(),
+            match self.severity {
+                Severity::Error => "",
+                Severity::Warning => "warning: ",
+            },
+            self.msg,
+            self.rule
+        )
+    }
+}
+
+/// The result of [fix](fn.fix.html).
+#[derive(Debug, PartialEq)]
+pub struct Fixed {
+    /// The fixed lines, which haven't been formatted.
+    pub lines: Vec<String>,
+    /// The problems that were fixed, in the order they were fixed. Positions are those in the
+    /// source as it was when each was found.
+    pub fixed: Vec<Diagnostic>,
+}
+
+/// Check a syntax tree, parsed from `source`, against the rules enabled in `config`, returning
+/// the problems found in the order they appear in the source.
+pub fn lint(ast: &Node, source: &[&str], config: &LintConfig) -> Vec<Diagnostic> {
+    lint_with(ast, source, config, None)
+}
+
+/// As [lint](fn.lint.html), for a script of a project at `path`, which is also checked for calls
+/// to the project's autoload functions that aren't defined.
+pub fn lint_in_project(
+    ast: &Node,
+    source: &[&str],
+    config: &LintConfig,
+    project: &Project,
+    path: &Path,
+) -> Vec<Diagnostic> {
+    lint_with(ast, source, config, Some((project, path)))
+}
+
+fn lint_with(
+    ast: &Node,
+    source: &[&str],
+    config: &LintConfig,
+    project: Option<(&Project, &Path)>,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    walk(ast, &mut |node| {
+        check(node, source, config, &mut diagnostics)
+    });
+    if config.require_augroup {
+        check_augroups(ast, source, config, &mut diagnostics);
+    }
+    if config.report_unused {
+        check_unused(ast, &mut diagnostics);
+    }
+    if let Some(header) = &config.header {
+        check_header(ast, source, header, &mut diagnostics);
+    }
+    for capture in analysis::missing_closures(ast) {
+        diagnostics.push(Diagnostic {
+            rule: MISSING_CLOSURE,
+            // the function might set the variable some way other than `let` or `for`
+            severity: Severity::Warning,
+            pos: capture.pos,
+            msg: format!(
+                "function {} uses {} of the function it is defined in, but isn't defined with \
+                 `closure`",
+                capture.function, capture.variable
+            ),
+            fix: None,
+        });
+    }
+    if let Some((project, path)) = project {
+        for call in project.missing_autoload_calls(path, ast) {
+            let script = project.autoload_path(&call.callee).unwrap_or_default();
+            let exists = script == path || project.files().iter().any(|f| f.path == script);
+            let script = display_path(script.strip_prefix(project.root()).unwrap_or(&script));
+            let msg = if exists {
+                format!("{} isn't defined in {}", call.callee, script)
+            } else {
+                format!("{} isn't defined: there is no {}", call.callee, script)
+            };
+            diagnostics.push(Diagnostic {
+                rule: MISSING_AUTOLOAD_FUNCTION,
+                severity: Severity::Error,
+                pos: call.pos,
+                msg,
+                fix: None,
+            });
+        }
+    }
+    diagnostics.retain_mut(|d| match config.rule_levels.get(d.rule) {
+        Some(RuleLevel::Off) => false,
+        Some(RuleLevel::Warn) => {
+            d.severity = Severity::Warning;
+            true
+        }
+        Some(RuleLevel::Error) => {
+            d.severity = Severity::Error;
+            true
+        }
+        None => true,
+    });
+    let suppressions = suppressions(ast);
+    diagnostics.retain(|d| {
+        !suppressions.iter().any(|(lines, rules)| {
+            lines.contains(&d.pos.line()) && (rules.is_empty() || rules.contains(&d.rule))
+        })
+    });
+    diagnostics.sort_by_key(|d| (d.pos.line(), d.pos.column()));
+    diagnostics
+}
+
+/// Fix the problems [lint](fn.lint.html) finds in `source`, where the rule that found them knows
+/// how. Fixes that conflict with one another are applied one at a time, linting again in between,
+/// and a pass of fixes that leaves the source unparseable is thrown away. Returns an error only
+/// if `source` itself can't be parsed.
+pub fn fix(source: &[&str], config: &LintConfig) -> Result<Fixed, ParseError> {
+    let mut lines = source.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+    let mut fixed = vec![];
+    let mut ast = parse_lines(source)?;
+    for _ in 0..MAX_FIX_PASSES {
+        let current = lines.iter().map(String::as_str).collect::<Vec<_>>();
+        let diagnostics = lint(&ast, &current, config)
+            .into_iter()
+            .filter(|d| d.fix.is_some())
+            .collect::<Vec<_>>();
+        let fixes = diagnostics
+            .iter()
+            .filter_map(|d| d.fix.as_ref())
+            .collect::<Vec<_>>();
+        let (new_lines, applied) = fix::apply(&current, &fixes);
+        if applied.is_empty() {
+            break;
+        }
+        let new = new_lines.iter().map(String::as_str).collect::<Vec<_>>();
+        ast = match parse_lines(&new) {
+            Ok(ast) => ast,
+            Err(_) => break,
+        };
+        fixed.extend(applied.into_iter().map(|i| diagnostics[i].clone()));
+        lines = new_lines;
+    }
+    Ok(Fixed { lines, fixed })
+}
+
+/// The lines of each statement below a [DISABLE_NEXT_LINE](constant.DISABLE_NEXT_LINE.html)
+/// comment, with the rules the comment names.
+fn suppressions(ast: &Node) -> Vec<(Range<usize>, Vec<&str>)> {
+    // a statement's lines run up to whatever comes after it: the next statement, the next clause
+    // of the block it's in, or the end of the block
+    let mut starts = vec![];
+    walk(ast, &mut |node| {
+        starts.extend(node.body().iter().map(|n| n.pos().line()));
+        if let Node::Catch { .. }
+        | Node::Else { .. }
+        | Node::ElseIf { .. }
+        | Node::End { .. }
+        | Node::Finally { .. } = node
+        {
+            starts.push(node.pos().line());
+        }
+    });
+    let mut rv = vec![];
+    walk(ast, &mut |node| {
+        for (statement, comments) in node.leading_comments() {
+            for comment in comments {
+                let rules = match comment {
+                    Node::Comment { value, .. } => value.trim().strip_prefix(DISABLE_NEXT_LINE),
+                    _ => None,
+                };
+                let rules = match rules {
+                    Some(rules) if rules.is_empty() || rules.starts_with([' ', '\t']) => rules,
+                    _ => continue,
+                };
+                let start = statement.pos().line();
+                let end = starts
+                    .iter()
+                    .filter(|&&line| line > start)
+                    .min()
+                    .map_or(usize::MAX, |&line| line);
+                rv.push((
+                    start..end,
+                    rules
+                        .split([',', ' ', '\t'])
+                        .filter(|r| !r.is_empty())
+                        .collect(),
+                ));
+            }
+        }
+    });
+    rv
+}
+
+fn walk<'a, F: FnMut(&'a Node)>(node: &'a Node, f: &mut F) {
+    f(node);
+    for child in node.children() {
+        walk(child, f);
+    }
+}
+
+fn check(node: &Node, source: &[&str], config: &LintConfig, diagnostics: &mut Vec<Diagnostic>) {
+    check_deprecated(node, config, diagnostics);
+    check_events(node, config, diagnostics);
+    if config.require_bang {
+        check_bang(node, source, diagnostics);
+    }
+    if let Node::Function {
+        pos, name, attrs, ..
+    } = node
+    {
+        if config.require_abort && !attrs.iter().any(|a| a == "abort") {
+            diagnostics.push(Diagnostic {
+                rule: MISSING_ABORT,
+                severity: Severity::Error,
+                pos: *pos,
+                msg: format!("function {} is not defined with `abort`", name),
+                fix: command_end(source, *pos).map(|end| Fix {
+                    edits: vec![Edit::insert(end, " abort")],
+                }),
+            });
+        }
+    }
+}
+
+fn check_bang(node: &Node, source: &[&str], diagnostics: &mut Vec<Diagnostic>) {
+    let (pos, what) = match node {
+        Node::Function {
+            pos,
+            name,
+            bang: false,
+            ..
+        } => (*pos, format!("function {}", name)),
+        Node::ExCmdRaw {
+            pos,
+            command,
+            args,
+            bang: false,
+            ..
+        } if command == "command" => {
+            // only definitions, which have a replacement after the name; `command Foo` lists
+            // the commands starting with Foo
+            let mut words = args.split_whitespace().skip_while(|w| w.starts_with('-'));
+            match (words.next(), words.next()) {
+                (Some(name), Some(_)) => (*pos, format!("command {}", name)),
+                _ => return,
+            }
+        }
+        _ => return,
+    };
+    diagnostics.push(Diagnostic {
+        rule: MISSING_BANG,
+        severity: Severity::Error,
+        pos,
+        msg: format!("{} is not defined with `!`", what),
+        fix: command_name_end(source, pos).map(|end| Fix {
+            edits: vec![Edit::insert(end, "!")],
+        }),
+    });
+}
+
+/// The names an option set with `set` might have, e.g. `nocp` might be `cp` turned off or an
+/// option called `nocp`.
+fn set_option_names(arg: &str) -> Vec<&str> {
+    let name = arg
+        .trim_end_matches("&vim")
+        .trim_end_matches("&vi")
+        .trim_end_matches(['&', '!', '?']);
+    let mut names = vec![name];
+    names.extend(name.strip_prefix("no"));
+    names.extend(name.strip_prefix("inv"));
+    names
+}
+
+fn check_deprecated(node: &Node, config: &LintConfig, diagnostics: &mut Vec<Diagnostic>) {
+    let (feature, names, args) = match node {
+        Node::ExCmdRaw { command, args, .. } => (Feature::Command, vec![command.as_str()], args),
+        Node::Set { args, .. } => (
+            Feature::Option,
+            args.iter()
+                .flat_map(|(option, _, _)| set_option_names(option))
+                .collect(),
+            &String::new(),
+        ),
+        Node::Option { value, .. } => {
+            let name = value.trim_start_matches('&');
+            let name = name
+                .strip_prefix("l:")
+                .or_else(|| name.strip_prefix("g:"))
+                .unwrap_or(name);
+            (Feature::Option, vec![name], &String::new())
+        }
+        Node::Identifier { value, .. } => (Feature::Variable, vec![value.as_str()], &String::new()),
+        Node::Call { name, .. } => match &**name {
+            Node::Identifier { value, .. } => {
+                (Feature::Function, vec![value.as_str()], &String::new())
+            }
+            _ => return,
+        },
+        // commands with their own variants, whose arguments are no longer kept as text
+        node => match node.command_name() {
+            Some(name) => (Feature::Command, vec![name], &String::new()),
+            None => return,
+        },
+    };
+    for deprecation in &config.deprecations {
+        if deprecation.feature != feature || !names.iter().any(|n| deprecation.matches(n)) {
+            continue;
+        }
+        if let Some(expected) = &deprecation.args {
+            if !args.trim().eq_ignore_ascii_case(expected) {
+                continue;
+            }
+        }
+        let name = match feature {
+            Feature::Command => format!("`:{}`", deprecation.names[0]),
+            Feature::Option => format!("'{}'", deprecation.names[0]),
+            Feature::Variable => format!("`{}`", deprecation.names[0]),
+            Feature::Function => format!("`{}()`", deprecation.names[0]),
+        };
+        let editor = match config.target {
+            Some(target) => target.editor.to_string(),
+            None => "Vim and Neovim".to_string(),
+        };
+        let mut msg = match deprecation.status(config.target) {
+            Status::Supported => continue,
+            Status::Deprecated(_) if config.target.is_none() => {
+                format!("{} is deprecated in {}", name, editor)
+            }
+            Status::Removed(_) if config.target.is_none() => {
+                format!("{} has been removed from {}", name, editor)
+            }
+            Status::Deprecated(since) => {
+                format!("{} is deprecated as of {} {}", name, editor, since)
+            }
+            Status::Removed(since) => format!("{} was removed in {} {}", name, editor, since),
+        };
+        if !deprecation.note.is_empty() {
+            msg.push_str("; ");
+            msg.push_str(&deprecation.note);
+        }
+        diagnostics.push(Diagnostic {
+            rule: DEPRECATED,
+            severity: Severity::Error,
+            pos: node.pos(),
+            msg,
+            fix: None,
+        });
+    }
+}
+
+fn check_events(node: &Node, config: &LintConfig, diagnostics: &mut Vec<Diagnostic>) {
+    let Node::Autocmd { pos, events, .. } = node else {
+        return;
+    };
+    let events = events
+        .iter()
+        .filter_map(|e| valid_autocmds().get(e.to_lowercase().as_str()))
+        .collect::<Vec<_>>();
+    for event in events.iter().filter(|e| e.deprecated) {
+        diagnostics.push(Diagnostic {
+            rule: DEPRECATED,
+            severity: Severity::Error,
+            pos: *pos,
+            msg: format!("autocommand event {} is deprecated", event.name),
+            fix: None,
+        });
+    }
+    let Some(target) = config.target else {
+        return;
+    };
+    let Some(parser_target) = target.parser_target() else {
+        return;
+    };
+    for event in events.iter().filter(|e| !e.is_available(parser_target)) {
+        diagnostics.push(Diagnostic {
+            rule: UNAVAILABLE_EVENT,
+            severity: Severity::Warning,
+            pos: *pos,
+            msg: format!(
+                "autocommand event {} is not available in {} {}",
+                event.name, target.editor, target.version
+            ),
+            fix: None,
+        });
+    }
+}
+
+/// Whether a node is an `augroup` command, and if so the group it starts, or `None` for
+/// `augroup END`. `augroup!`, which deletes a group, doesn't count.
+fn augroup(node: &Node) -> Option<Option<&str>> {
+    match node {
+        Node::ExCmdRaw {
+            command,
+            bang: false,
+            args,
+            ..
+        } if command == "augroup" => {
+            let name = args.trim();
+            Some(if name.eq_ignore_ascii_case("end") {
+                None
+            } else {
+                Some(name)
+            })
+        }
+        _ => None,
+    }
+}
+
+/// The names of the groups used by `augroup` and `autocmd` commands anywhere in a script.
+fn augroup_names<'a>(node: &'a Node, names: &mut HashSet<&'a str>) {
+    match node {
+        Node::Autocmd { group, .. } if !group.is_empty() => {
+            names.insert(group);
+        }
+        _ => {
+            if let Some(Some(name)) = augroup(node) {
+                names.insert(name);
+            }
+        }
+    }
+    for child in node.children() {
+        augroup_names(child, names);
+    }
+}
+
+/// Find runs of consecutive autocommands defined outside a group, which may have comments
+/// between them. Whether there is a current group is followed in source order, and starts afresh
+/// in each function, whose commands run at some later time.
+fn ungrouped_autocmds<'a>(node: &'a Node, in_group: &mut bool, runs: &mut Vec<Vec<&'a Node>>) {
+    let mut run = vec![];
+    for child in node.children() {
+        match child {
+            Node::Autocmd { group, body, .. } if !*in_group && group.is_empty() => {
+                if !body.is_empty() {
+                    run.push(child);
+                }
+                continue;
+            }
+            Node::Comment { .. } if !run.is_empty() => continue,
+            _ => (),
+        }
+        if !run.is_empty() {
+            runs.push(run);
+            run = vec![];
+        }
+        if let Some(group) = augroup(child) {
+            *in_group = group.is_some();
+        } else if let Node::Function { .. } = child {
+            ungrouped_autocmds(child, &mut false, runs);
+        } else {
+            ungrouped_autocmds(child, in_group, runs);
+        }
+    }
+    if !run.is_empty() {
+        runs.push(run);
+    }
+}
+
+/// The variable the load guard of a script should check, from its path, as plugins
+/// conventionally name them: `g:loaded_foo` for `plugin/foo.vim`, and `g:autoloaded_foo_bar` for
+/// `autoload/foo/bar.vim`.
+pub fn guard_variable(path: &Path) -> String {
+    let components = path
+        .with_extension("")
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    let (prefix, name) = match components.iter().rposition(|c| c == "autoload") {
+        Some(i) if i + 1 < components.len() => ("autoloaded", components[i + 1..].join("_")),
+        _ => ("loaded", components.last().cloned().unwrap_or_default()),
+    };
+    let name = name
+        .trim_start_matches(['.', '_'])
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    format!("g:{}_{}", prefix, name)
+}
+
+fn is_scriptencoding(node: &Node, encoding: &str) -> bool {
+    let normalize = |e: &str| e.trim().to_lowercase().replace('-', "");
+    match node {
+        Node::ExCmdRaw { args, .. } => {
+            node.command_name() == Some("scriptencoding") && normalize(args) == normalize(encoding)
+        }
+        _ => false,
+    }
+}
+
+/// Whether a statement is a load guard, `if exists(...)` with `finish` in its body, checking
+/// `variable` if one is given.
+fn is_guard(node: &Node, variable: Option<&str>) -> bool {
+    match node {
+        Node::If { cond, body, .. } => {
+            let checked = match &**cond {
+                Node::Call { name, args, .. } if args.len() == 1 => match (&**name, &args[0]) {
+                    (Node::Identifier { value: f, .. }, Node::String { value, .. })
+                        if f == "exists" =>
+                    {
+                        Some(value.trim_matches(['\'', '"']))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            };
+            checked.is_some_and(|checked| variable.is_none_or(|v| v == checked))
+                && body.iter().any(|n| n.command_name() == Some("finish"))
+        }
+        _ => false,
+    }
+}
+
+fn check_header(
+    ast: &Node,
+    source: &[&str],
+    header: &HeaderConfig,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let body = match ast {
+        Node::TopLevel { body, .. } => body,
+        _ => return,
+    };
+    // in a Vim9 script, the header goes after `vim9script`, which must be the first command
+    let vim9script = body
+        .iter()
+        .position(|node| node.command_name() == Some("vim9script"));
+    let mut missing = vec![];
+    let mut lines = vec![];
+    if let Some(encoding) = &header.encoding {
+        if !body.iter().any(|node| is_scriptencoding(node, encoding)) {
+            missing.push(format!("`scriptencoding {}`", encoding));
+            lines.push(format!("scriptencoding {}", encoding));
+        }
+    }
+    if let Some(guard) = &header.guard {
+        let variable = header.guard_variable.as_deref();
+        if !body.iter().any(|node| is_guard(node, variable)) {
+            match variable {
+                Some(variable) => {
+                    missing.push(format!("a load guard checking {}", variable));
+                    let guard = guard.replace("{variable}", variable);
+                    if vim9script.is_some() {
+                        // Vim9 script assigns without `let`
+                        lines.extend(
+                            guard.lines().map(|l| {
+                                l.strip_prefix("let ").map_or(l.to_string(), str::to_string)
+                            }),
+                        );
+                    } else {
+                        lines.push(guard);
+                    }
+                }
+                None => missing.push("a load guard".to_string()),
+            }
+        }
+    }
+    if missing.is_empty() {
+        return;
+    }
+    // the header goes after any comments at the top of the script, and after its
+    // `scriptencoding` if it has one
+    let first = body
+        .iter()
+        .skip(vim9script.map_or(0, |i| i + 1))
+        .find(|node| {
+            !matches!(node, Node::Comment { .. } | Node::BlankLine { .. })
+                && node.command_name() != Some("scriptencoding")
+        });
+    let text = lines.join("\n");
+    let edit = match (first, source.last()) {
+        _ if lines.is_empty() => None,
+        (Some(node), _) => Some(Edit::insert((node.pos().line(), 1), text + "\n")),
+        (None, Some(last)) => Some(Edit::insert(
+            (source.len(), last.chars().count() + 1),
+            format!("\n{}", text),
+        )),
+        (None, None) => Some(Edit::insert((1, 1), text)),
+    };
+    diagnostics.push(Diagnostic {
+        rule: MISSING_HEADER,
+        severity: Severity::Error,
+        pos: body.first().map_or(ast.pos(), Node::pos),
+        msg: format!("script header is missing {}", missing.join(" and ")),
+        fix: edit.map(|edit| Fix { edits: vec![edit] }),
+    });
+}
+
+fn check_augroups(
+    ast: &Node,
+    source: &[&str],
+    config: &LintConfig,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let mut runs = vec![];
+    ungrouped_autocmds(ast, &mut false, &mut runs);
+    let mut names = HashSet::new();
+    augroup_names(ast, &mut names);
+    let mut suffix = 1;
+    for run in runs {
+        // Each run gets a group of its own, so that clearing one group when it is defined can't
+        // remove the autocommands of another that might have been defined before it.
+        let name = loop {
+            let name = match suffix {
+                1 => config.augroup_name.clone(),
+                n => format!("{}{}", config.augroup_name, n),
+            };
+            suffix += 1;
+            if !names.contains(name.as_str()) {
+                break name;
+            }
+        };
+        let first = run[0].pos();
+        let fix = match (
+            source.get(first.line().wrapping_sub(1)),
+            command_end(source, run[run.len() - 1].pos()),
+        ) {
+            (Some(line), Some(end)) => {
+                let indent = &line[..line.len() - line.trim_start().len()];
+                Some(Fix {
+                    edits: vec![
+                        Edit::insert(
+                            (first.line(), 1),
+                            format!("{0}augroup {1}\n{0}autocmd!\n", indent, name),
+                        ),
+                        Edit::insert(end, format!("\n{}augroup END", indent)),
+                    ],
+                })
+            }
+            _ => None,
+        };
+        for (i, node) in run.into_iter().enumerate() {
+            diagnostics.push(Diagnostic {
+                rule: AUTOCMD_OUTSIDE_AUGROUP,
+                severity: Severity::Error,
+                pos: node.pos(),
+                msg: "autocommand is not defined in a group".to_string(),
+                fix: if i == 0 { fix.clone() } else { None },
+            });
+        }
+    }
+}
+
+/// Text that may refer to a script-local function or variable without the parser seeing it,
+/// e.g. strings passed to `execute` and the arguments of commands it doesn't parse.
+fn opaque_text<'a>(node: &'a Node, texts: &mut Vec<&'a str>) {
+    match node {
+        Node::String { value, .. } => texts.push(value),
+        Node::ExCmdRaw { args, .. } => texts.push(args),
+        _ => (),
+    }
+    for child in node.children() {
+        opaque_text(child, texts);
+    }
+}
+
+/// The `s:` variables defined with `let`, and those that are read anywhere. Returns `None` for
+/// the variables read if the script uses `s:` as a dictionary, which could read any of them.
+fn script_variables<'a>(
+    node: &'a Node,
+    defined: &mut Vec<(&'a str, Position)>,
+    used: &mut Option<HashSet<&'a str>>,
+) {
+    match node {
+        Node::Let {
+            var,
+            list,
+            rest,
+            right,
+            ..
+        } => {
+            for target in var
+                .iter()
+                .map(|v| &**v)
+                .chain(list)
+                .chain(rest.iter().map(|r| &**r))
+            {
+                match target {
+                    Node::Identifier { value, pos } if value.starts_with("s:") => {
+                        if !defined.iter().any(|(name, _)| name == value) {
+                            defined.push((value, *pos));
+                        }
+                    }
+                    _ => script_variables(target, defined, used),
+                }
+            }
+            script_variables(right, defined, used);
+            return;
+        }
+        Node::Identifier { value, .. } if value == "s:" => *used = None,
+        Node::Identifier { value, .. } if value.starts_with("s:") => {
+            if let Some(used) = used {
+                used.insert(value);
+            }
+        }
+        _ => (),
+    }
+    for child in node.children() {
+        script_variables(child, defined, used);
+    }
+}
+
+fn check_unused(ast: &Node, diagnostics: &mut Vec<Diagnostic>) {
+    let mut texts = vec![];
+    opaque_text(ast, &mut texts);
+    let mut mappings = vec![];
+    walk(ast, &mut |node| {
+        if let Node::Abbreviation { right, .. } | Node::Mapping { right, .. } = node {
+            mappings.push(right.to_lowercase());
+        }
+    });
+    let mut report = |kind: &str, name: &str, pos: Position, certain: bool| {
+        diagnostics.push(Diagnostic {
+            rule: UNUSED,
+            severity: if certain {
+                Severity::Error
+            } else {
+                Severity::Warning
+            },
+            pos,
+            msg: if certain {
+                format!("{} {} is never used", kind, name)
+            } else {
+                format!(
+                    "{} {} is never used, unless by a string that is run as a command",
+                    kind, name
+                )
+            },
+            fix: None,
+        });
+    };
+    let graph = analysis::call_graph(ast);
+    for definition in graph.unreferenced() {
+        let name = &definition.name;
+        let Some(bare) = name.strip_prefix("s:").filter(|n| !n.contains('.')) else {
+            continue;
+        };
+        let sid = format!("<sid>{}", bare.to_lowercase());
+        if mappings.iter().any(|m| m.contains(&sid)) {
+            continue;
+        }
+        let in_text = texts
+            .iter()
+            .any(|t| t.contains(name.as_str()) || t.to_lowercase().contains(&sid));
+        report("function", name, definition.pos, !in_text);
+    }
+    let mut defined = vec![];
+    let mut used = Some(HashSet::new());
+    script_variables(ast, &mut defined, &mut used);
+    if let Some(used) = used {
+        for (name, pos) in defined {
+            if !used.contains(name) {
+                report(
+                    "variable",
+                    name,
+                    pos,
+                    !texts.iter().any(|t| t.contains(name)),
+                );
+            }
+        }
+    }
+}
+
+/// The position just past the name of the command starting at `pos`, however it is abbreviated.
+fn command_name_end(source: &[&str], pos: Position) -> Option<(usize, usize)> {
+    let line = source.get(pos.line().checked_sub(1)?)?;
+    let name = line
+        .chars()
+        .skip(pos.column().checked_sub(1)?)
+        .take_while(char::is_ascii_alphabetic)
+        .count();
+    Some((pos.line(), pos.column() + name))
+}
+
+/// The position just past the last non-blank character of the command starting at `pos`,
+/// following any continuation lines.
+fn command_end(source: &[&str], pos: Position) -> Option<(usize, usize)> {
+    let mut line = pos.line();
+    source.get(line.checked_sub(1)?)?;
+    while source
+        .get(line)
+        .is_some_and(|l| l.trim_start().starts_with('\\'))
+    {
+        line += 1;
+    }
+    Some((line, source[line - 1].trim_end().chars().count() + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_abort() {
+        let source = [
+            "function! Foo()",
+            "      \\ range",
+            "  function! s:bar() abort dict",
+            "  endfunction",
+            "endfunction",
+            "function Baz()  ",
+            "endfunction",
+        ];
+        let ast = parse_lines(&source).unwrap();
+        let config = LintConfig {
+            report_unused: false,
+            ..LintConfig::default()
+        };
+        let diagnostics = lint(&ast, &source, &config);
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(|d| d.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "line 1, col 1: function Foo is not defined with `abort` [missing-abort]",
+                "line 6, col 1: function Baz is not defined with `abort` [missing-abort]",
+            ]
+        );
+        assert!(lint(
+            &ast,
+            &source,
+            &LintConfig {
+                require_abort: false,
+                ..config.clone()
+            }
+        )
+        .is_empty());
+        let fixed = fix(&source, &config).unwrap();
+        assert_eq!(fixed.fixed, diagnostics);
+        assert_eq!(
+            fixed.lines,
+            vec![
+                "function! Foo()",
+                "      \\ range abort",
+                "  function! s:bar() abort dict",
+                "  endfunction",
+                "endfunction",
+                "function Baz() abort  ",
+                "endfunction",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_missing_bang() {
+        let source = [
+            "function Foo() abort",
+            "endfunction",
+            "silent! fu! Bar() abort",
+            "endfunction",
+            "com -nargs=1 Baz echo <args>",
+            "command! Qux echo 1",
+            "command Baz",
+        ];
+        let ast = parse_lines(&source).unwrap();
+        let config = LintConfig {
+            require_bang: true,
+            report_unused: false,
+            ..LintConfig::default()
+        };
+        assert_eq!(
+            lint(&ast, &source, &config)
+                .iter()
+                .map(|d| d.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "line 1, col 1: function Foo is not defined with `!` [missing-bang]",
+                "line 5, col 1: command Baz is not defined with `!` [missing-bang]",
+            ]
+        );
+        assert_eq!(
+            fix(&source, &config).unwrap().lines[..5],
+            [
+                "function! Foo() abort",
+                "endfunction",
+                "silent! fu! Bar() abort",
+                "endfunction",
+                "com! -nargs=1 Baz echo <args>",
+            ]
+        );
+        assert!(lint(&ast, &source, &LintConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn test_autocmd_outside_augroup() {
+        let source = [
+            "autocmd BufRead * echo 1",
+            "\" comment",
+            "autocmd BufRead * echo 2",
+            "augroup vimrc",
+            "  autocmd!",
+            "  autocmd BufRead * echo 3",
+            "  if 1",
+            "    autocmd BufRead * echo 4",
+            "  endif",
+            "augroup END",
+            "autocmd other BufRead * echo 5",
+            "autocmd! BufRead",
+            "if 1",
+            "  autocmd BufRead * echo 6",
+            "endif",
+            "function! Foo() abort",
+            "  autocmd BufRead * echo 7",
+            "endfunction",
+        ];
+        let ast = parse_lines(&source).unwrap();
+        let config = LintConfig::default();
+        let diagnostics = lint(&ast, &source, &config);
+        assert_eq!(
+            diagnostics.iter().map(|d| d.pos.line()).collect::<Vec<_>>(),
+            vec![1, 3, 14, 17]
+        );
+        assert!(diagnostics[1].fix.is_none());
+        let fixed = fix(&source, &config).unwrap();
+        assert_eq!(fixed.fixed.len(), 3);
+        assert_eq!(
+            fixed.lines[..5],
+            [
+                "augroup vimrc2",
+                "autocmd!",
+                "autocmd BufRead * echo 1",
+                "\" comment",
+                "autocmd BufRead * echo 2",
+            ]
+        );
+        assert_eq!(
+            fixed.lines[16..22],
+            [
+                "  augroup vimrc3",
+                "  autocmd!",
+                "  autocmd BufRead * echo 6",
+                "  augroup END",
+                "endif",
+                "function! Foo() abort",
+            ]
+        );
+        let fixed_lines = fixed.lines.iter().map(String::as_str).collect::<Vec<_>>();
+        let ast = parse_lines(&fixed_lines).unwrap();
+        assert!(lint(&ast, &fixed_lines, &config).is_empty());
+    }
+
+    #[test]
+    fn test_deprecated() {
+        let source = [
+            "behave mswin",
+            "sniff request",
+            "filetype off",
+            "filetype on",
+            "set nocp tf? ts=4",
+            "let x = v:none",
+            "echo &l:insertmode buffer_name(1)",
+        ];
+        let ast = parse_lines(&source).unwrap();
+        let messages = |target: &str| {
+            let config = LintConfig {
+                target: target.parse().ok(),
+                ..LintConfig::default()
+            };
+            lint(&ast, &source, &config)
+                .into_iter()
+                .map(|d| format!("{}: {}", d.pos.line(), d.msg))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            messages(""),
+            vec![
+                "1: `:behave` is deprecated in Vim and Neovim; set 'selection', 'selectmode', \
+                 'mousemodel' and 'keymodel' instead",
+                "2: `:sniff` has been removed from Vim and Neovim",
+                "3: `:filetype` is deprecated in Vim and Neovim; turning filetype detection off \
+                 and on again is only needed by old plugin managers",
+                "7: `buffer_name()` is deprecated in Vim and Neovim; use bufname instead",
+            ]
+        );
+        assert_eq!(messages("vim82").len(), 3);
+        assert_eq!(
+            messages("nvim010"),
+            vec![
+                "1: `:behave` was removed in Neovim 0.10; set 'selection', 'selectmode', \
+                 'mousemodel' and 'keymodel' instead",
+                "2: `:sniff` was removed in Neovim 0.1",
+                "3: `:filetype` is deprecated as of Neovim 0.1; turning filetype detection off \
+                 and on again is only needed by old plugin managers",
+                "5: 'compatible' was removed in Neovim 0.1",
+                "5: 'ttyfast' was removed in Neovim 0.1",
+                "6: `v:none` was removed in Neovim 0.1; use v:null instead",
+                "7: 'insertmode' was removed in Neovim 0.7",
+                "7: `buffer_name()` is deprecated as of Neovim 0.1; use bufname instead",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unavailable_event() {
+        let source = [
+            "augroup test",
+            "  autocmd TermOpen,BufEnter * echo 1",
+            "  autocmd winscrolled * echo 2",
+            "augroup END",
+        ];
+        let ast = parse_lines(&source).unwrap();
+        let messages = |target: &str| {
+            let config = LintConfig {
+                target: target.parse().ok(),
+                ..LintConfig::default()
+            };
+            lint(&ast, &source, &config)
+                .into_iter()
+                .map(|d| format!("{}: {}", d.pos.line(), d))
+                .collect::<Vec<_>>()
+        };
+        assert!(messages("").is_empty());
+        assert!(messages("nvim09").is_empty());
+        assert!(messages("vim81").is_empty());
+        assert_eq!(
+            messages("vim82"),
+            vec![
+                "2: line 2, col 3: warning: autocommand event TermOpen is not available in Vim \
+                 8.2 [unavailable-event]",
+                "3: line 3, col 3: warning: autocommand event WinScrolled is not available in Vim \
+                 8.2 [unavailable-event]",
+            ]
+        );
+        assert_eq!(messages("vim91").len(), 1);
+        let source = ["autocmd! fileencoding * echo 1"];
+        assert_eq!(
+            lint(
+                &parse_lines(&source).unwrap(),
+                &source,
+                &LintConfig {
+                    require_augroup: false,
+                    ..LintConfig::default()
+                }
+            )
+            .iter()
+            .map(|d| d.to_string())
+            .collect::<Vec<_>>(),
+            vec!["line 1, col 1: autocommand event FileEncoding is deprecated [deprecated]"]
+        );
+    }
+
+    #[test]
+    fn test_unused() {
+        let source = [
+            "let s:count = 0",
+            "let s:unused = 1",
+            "let s:maybe = 2",
+            "function! s:Used() abort",
+            "  return s:count",
+            "endfunction",
+            "function! s:Dead() abort",
+            "  return s:Dead()",
+            "endfunction",
+            "function! s:Mapped() abort",
+            "endfunction",
+            "function! s:Executed() abort",
+            "endfunction",
+            "nnoremap <silent> x :call <SID>Mapped()<CR>",
+            "execute 'call s:Executed() | echo s:maybe'",
+            "echo s:Used()",
+        ];
+        let ast = parse_lines(&source).unwrap();
+        assert_eq!(
+            lint(&ast, &source, &LintConfig::default())
+                .into_iter()
+                .map(|d| d.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "line 2, col 5: variable s:unused is never used [unused]",
+                "line 3, col 5: warning: variable s:maybe is never used, unless by a string that \
+                 is run as a command [unused]",
+                "line 7, col 1: function s:Dead is never used [unused]",
+                "line 12, col 1: warning: function s:Executed is never used, unless by a string \
+                 that is run as a command [unused]",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rule_levels_and_suppressions() {
+        let source = [
+            "\" vimlfmt-disable-next-line",
+            "function! Foo()",
+            "  autocmd BufRead * echo 1",
+            "endfunction",
+            "\" vimlfmt-disable-next-line unused",
+            "function! Bar()",
+            "endfunction",
+            "\" vimlfmt-disable-next-lines",
+            "function! Baz()",
+            "endfunction",
+        ];
+        let ast = parse_lines(&source).unwrap();
+        let mut config = LintConfig::default();
+        config
+            .apply_toml(
+                "[lint.rules]\nmissing-abort = \"warn\"\nautocmd-outside-augroup = \"off\"\n",
+            )
+            .unwrap();
+        assert_eq!(
+            lint(&ast, &source, &config)
+                .iter()
+                .map(|d| d.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "line 6, col 1: warning: function Bar is not defined with `abort` [missing-abort]",
+                "line 9, col 1: warning: function Baz is not defined with `abort` [missing-abort]",
+            ]
+        );
+        assert!(config
+            .apply_toml("[lint.rules]\nmissing = \"off\"")
+            .is_err());
+        assert!(config
+            .apply_toml("[lint.rules]\nunused = \"loud\"")
+            .is_err());
+    }
+
+    #[test]
+    fn test_missing_header() {
+        assert_eq!(
+            guard_variable(Path::new("plugin/foo-bar.vim")),
+            "g:loaded_foo_bar"
+        );
+        assert_eq!(
+            guard_variable(Path::new("/x/autoload/foo/bar.vim")),
+            "g:autoloaded_foo_bar"
+        );
+        let source = ["\" Foo: does foo", "", "command! Foo echo 1"];
+        let ast = parse_lines(&source).unwrap();
+        let mut config = LintConfig {
+            header: Some(HeaderConfig {
+                guard_variable: Some("g:loaded_foo".to_string()),
+                ..HeaderConfig::default()
+            }),
+            ..LintConfig::default()
+        };
+        assert_eq!(
+            lint(&ast, &source, &config)
+                .iter()
+                .map(|d| d.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "line 1, col 1: script header is missing `scriptencoding utf-8` and a load guard \
+                 checking g:loaded_foo [missing-header]"
+            ]
+        );
+        assert_eq!(
+            fix(&source, &config).unwrap().lines,
+            vec![
+                "\" Foo: does foo",
+                "",
+                "scriptencoding utf-8",
+                "if exists('g:loaded_foo') | finish | endif",
+                "let g:loaded_foo = 1",
+                "command! Foo echo 1",
+            ]
+        );
+        let source = ["\" Foo: does foo", "vim9script", "command! Foo echo 1"];
+        assert_eq!(
+            fix(&source, &config).unwrap().lines,
+            vec![
+                "\" Foo: does foo",
+                "vim9script",
+                "scriptencoding utf-8",
+                "if exists('g:loaded_foo') | finish | endif",
+                "g:loaded_foo = 1",
+                "command! Foo echo 1",
+            ]
+        );
+        assert_eq!(
+            fix(&["vim9script"], &config).unwrap().lines,
+            vec![
+                "vim9script",
+                "scriptencoding utf-8",
+                "if exists('g:loaded_foo') | finish | endif",
+                "g:loaded_foo = 1",
+            ]
+        );
+        let source = [
+            "scriptencoding utf8",
+            "if exists(\"g:loaded_foo\")",
+            "  finish",
+            "endif",
+        ];
+        let ast = parse_lines(&source).unwrap();
+        assert!(lint(&ast, &source, &config).is_empty());
+        config.header.as_mut().unwrap().guard_variable = Some("g:loaded_bar".to_string());
+        assert_eq!(lint(&ast, &source, &config).len(), 1);
+        config
+            .apply_toml("[lint.header]\nencoding = \"latin1\"\nguard = false")
+            .unwrap();
+        let header = config.header.as_ref().unwrap();
+        assert_eq!(header.encoding.as_deref(), Some("latin1"));
+        assert_eq!(header.guard, None);
+        assert_eq!(header.guard_variable.as_deref(), Some("g:loaded_bar"));
+        assert!(lint(
+            &parse_lines(&["scriptencoding latin1"]).unwrap(),
+            &[],
+            &config
+        )
+        .is_empty());
+        assert!(config.apply_toml("[lint.header]\nguard = 1").is_err());
+        assert!(lint(&ast, &source, &LintConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn test_missing_autoload_function() {
+        let mut project = Project::new(Path::new("root"));
+        project.add_file(
+            Path::new("root/autoload/foo.vim").to_path_buf(),
+            parse_lines(&["function! foo#bar() abort", "endfunction"]).unwrap(),
+        );
+        let source = [
+            "call foo#bar()",
+            "call foo#baz()",
+            "call foo#qux#quux()",
+            "call other#plugin()",
+        ];
+        let ast = parse_lines(&source).unwrap();
+        let config = LintConfig::default();
+        assert_eq!(
+            lint_in_project(
+                &ast,
+                &source,
+                &config,
+                &project,
+                Path::new("root/plugin/foo.vim")
+            )
+            .iter()
+            .map(|d| d.to_string())
+            .collect::<Vec<_>>(),
+            vec![
+                "line 2, col 13: foo#baz isn't defined in autoload/foo.vim \
+                 [missing-autoload-function]",
+                "line 3, col 18: foo#qux#quux isn't defined: there is no autoload/foo/qux.vim \
+                 [missing-autoload-function]",
+            ]
+        );
+        assert!(lint(&ast, &source, &config).is_empty());
+    }
+}
@@ -1,47 +1,1363 @@
-mod formatter;
+use clap::{
+    crate_authors, crate_description, crate_name, crate_version, App, Arg, ArgMatches, SubCommand,
+    Values,
+};
+use log::{debug, info, Level, LevelFilter, Log, Metadata, Record};
+use std::{
+    env,
+    fmt::Display,
+    fs,
+    io::{self, IsTerminal, Read},
+    path::{Path, PathBuf},
+    process,
+    time::{Duration, Instant},
+};
+use viml_parser::{transform::OptionStyle, Context, Dialect, Target};
+use vimlfmt::{
+    analysis,
+    cache::Cache,
+    completion,
+    config::{
+        self, Concatenation, ContinuationIndent, Continuations, EventCase, FormatterConfig,
+        HeaderConfig, HighlightAttrOrder, LintConfig, MappingAttrOrder, OneLineBlocks, Profile,
+        StyleEdition, TrailingCommas,
+    },
+    convert,
+    diagnostic::{self, display_path, Color},
+    git,
+    lint::{self, Severity},
+    pipeline::{Pipeline, Transforms},
+    porcelain,
+    project::Project,
+    report,
+    tree::{self, TreeOptions},
+    watch,
+};
 
-use crate::formatter::Formatter;
-use clap::{crate_authors, crate_description, crate_name, crate_version, App, Arg};
-use std::io::{self, BufRead};
-use viml_parser::parse_lines;
+#[cfg(unix)]
+fn serve(socket: &str, pipeline: &Pipeline) {
+    if let Err(e) = vimlfmt::daemon::serve(Path::new(socket), pipeline, |e| eprintln!("{}", e)) {
+        eprintln!("{}: {}", socket, e);
+    }
+}
 
-fn main() {
-    let matches = App::new(crate_name!())
+#[cfg(not(unix))]
+fn serve(_: &str, _: &Pipeline) {
+    eprintln!("--daemon is only supported on Unix");
+}
+
+/// The options given to a subcommand followed by those given before it, so options that come
+/// before the subcommand, or without one, still count.
+struct Options<'a> {
+    matches: Vec<&'a ArgMatches<'a>>,
+}
+
+impl<'a> Options<'a> {
+    fn is_present(&self, name: &str) -> bool {
+        self.matches.iter().any(|m| m.is_present(name))
+    }
+
+    fn value_of(&self, name: &str) -> Option<&'a str> {
+        self.matches.iter().find_map(|m| m.value_of(name))
+    }
+
+    fn values_of(&self, name: &str) -> Option<Values<'a>> {
+        self.matches.iter().find_map(|m| m.values_of(name))
+    }
+}
+
+/// Writes log messages to stderr, up to the level `--quiet` or `--verbose` chose. What the
+/// parser logs is only written with `--debug-parser`, whatever the level.
+struct Logger {
+    level: LevelFilter,
+    debug_parser: bool,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        if metadata.target().starts_with("viml_parser") {
+            self.debug_parser
+        } else {
+            metadata.level() <= self.level
+        }
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        match record.level() {
+            Level::Error | Level::Info => eprintln!("{}", record.args()),
+            level => eprintln!("{}: {}", level.as_str().to_lowercase(), record.args()),
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+fn init_logger(args: &Options) {
+    let level = if args.is_present("quiet") {
+        LevelFilter::Error
+    } else if args.is_present("verbose") {
+        LevelFilter::Debug
+    } else {
+        LevelFilter::Info
+    };
+    let debug_parser = args.is_present("debug-parser");
+    let logger = Box::leak(Box::new(Logger {
+        level,
+        debug_parser,
+    }));
+    if log::set_logger(logger).is_ok() {
+        log::set_max_level(if debug_parser {
+            LevelFilter::Trace
+        } else {
+            level
+        });
+    }
+}
+
+/// Options for reading and parsing the input, which every subcommand that reads it has.
+fn input_args() -> Vec<Arg<'static, 'static>> {
+    vec![
+        Arg::with_name("stdin-filepath")
+            .long("stdin-filepath")
+            .takes_value(true)
+            .value_name("PATH")
+            .help("The path of the file being formatted, used in error messages"),
+        Arg::with_name("editor")
+            .long("editor")
+            .takes_value(true)
+            .possible_values(&["vim74", "vim82", "vim9", "nvim", "nvim-nightly"])
+            .help(
+                "Reject commands, autocommand events and expression syntax this editor \
+                         doesn't have",
+            ),
+        Arg::with_name("context")
+            .long("context")
+            .takes_value(true)
+            .possible_values(&["toplevel", "function"])
+            .help("Parse the input as if it were in the body of a function [default: toplevel]"),
+        Arg::with_name("cpoptions")
+            .long("cpoptions")
+            .takes_value(true)
+            .value_name("FLAGS")
+            .help(
+                "The value of 'cpoptions' the input starts with; with the C flag, lines \
+                         starting with a backslash aren't continuations until the input changes it",
+            ),
+        Arg::with_name("color")
+            .long("color")
+            .takes_value(true)
+            .possible_values(&["auto", "always", "never"])
+            .help("When to color error messages [default: auto]"),
+    ]
+}
+
+/// Options that change how code is formatted.
+fn style_args() -> Vec<Arg<'static, 'static>> {
+    vec![
+        Arg::with_name("profile")
+            .long("profile")
+            .takes_value(true)
+            .possible_values(Profile::NAMES)
+            .help(
+                "The built-in settings to start from, which the other options change \
+                         (default: default)",
+            ),
+        Arg::with_name("style-edition")
+            .long("style-edition")
+            .takes_value(true)
+            .possible_values(StyleEdition::NAMES)
+            .help(
+                "The version of the default settings to use, so that upgrading doesn't \
+                         change the output (default: the style_edition of .vimlfmt.toml, or 2023)",
+            ),
+        Arg::with_name("config")
+            .long("config")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .value_name("NAME=VALUE,...")
+            .help(
+                "Change settings by the names of the fields of FormatterConfig, after all the \
+                         other options, e.g. `max_line_length=100,space_in_braces=true`",
+            ),
+        Arg::with_name("preserve-one-line-blocks")
+            .long("preserve-one-line-blocks")
+            .help(
+                "Keep blocks of simple statements written on one line with bars (e.g. \
+                         `if x | return | endif`) as they are, if they fit",
+            ),
+        Arg::with_name("continuations")
+            .long("continuations")
+            .takes_value(true)
+            .possible_values(&["rewrap", "preserve", "join"])
+            .help(
+                "What to do with lines continued with `\\`: join and rewrap them, keep the \
+                         breaks, or join them if they fit on one line (default: rewrap)",
+            ),
+        Arg::with_name("continuation-indent")
+            .long("continuation-indent")
+            .takes_value(true)
+            .value_name("N|double|aligned")
+            .help(
+                "Where to start continuation lines: N spaces past the statement, two indents \
+                         past it, or aligned with the innermost open bracket (default: 6)",
+            ),
+        Arg::with_name("event-case")
+            .long("event-case")
+            .takes_value(true)
+            .possible_values(&["canonical", "lowercase", "preserve"])
+            .help("How to spell autocommand events (default: canonical)"),
+        Arg::with_name("sort-set-lists")
+            .long("sort-set-lists")
+            .help("Sort and deduplicate comma-separated option values in `set` commands"),
+        Arg::with_name("merge-set-lines")
+            .long("merge-set-lines")
+            .help("Merge consecutive `set` commands into one"),
+        Arg::with_name("keep-trailing-whitespace")
+            .long("keep-trailing-whitespace")
+            .help("Keep trailing whitespace in comments"),
+        Arg::with_name("no-final-newline")
+            .long("no-final-newline")
+            .help("Don't end the output with a newline"),
+        Arg::with_name("strip-bom")
+            .long("strip-bom")
+            .help("Remove a UTF-8 byte order mark from the start of the file"),
+        Arg::with_name("heredoc-formatter")
+            .long("heredoc-formatter")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .value_name("CMD=COMMAND")
+            .help(
+                "Format scripts embedded with e.g. `lua << EOF` by piping them through a \
+                         shell command, e.g. `lua=stylua -`",
+            ),
+        Arg::with_name("keep-key-notation")
+            .long("keep-key-notation")
+            .help("Don't normalize the spelling of key notation like `<c-w>` or `<esc>`"),
+        Arg::with_name("normalize-normal-keys")
+            .long("normalize-normal-keys")
+            .conflicts_with("keep-key-notation")
+            .help(
+                "Also normalize key notation in `normal` commands, where Vim types it \
+                         literally",
+            ),
+        Arg::with_name("canonical-key")
+            .long("canonical-key")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .value_name("NAME=SPELLING")
+            .help("Spell a key name this way when normalizing key notation, e.g. `esc=ESC`"),
+        Arg::with_name("compact-echohl")
+            .long("compact-echohl")
+            .help("Keep `echohl Group | echo ... | echohl None` on one line if it fits"),
+        Arg::with_name("single-quote-execute")
+            .long("single-quote-execute")
+            .help(
+                "Write strings in `execute` arguments with single quotes where they have no \
+                         escapes",
+            ),
+        Arg::with_name("expand-option-names")
+            .long("expand-option-names")
+            .help("Write options used with `&` by their full names, e.g. `&ts` as `&tabstop`"),
+        Arg::with_name("execute-concatenation")
+            .long("execute-concatenation")
+            .takes_value(true)
+            .possible_values(&["preserve", "dot", "double-dot"])
+            .help(
+                "The operator to concatenate `execute` arguments with, `.` or `..` (default: \
+                         preserve)",
+            ),
+        Arg::with_name("align-assignments")
+            .long("align-assignments")
+            .help("Line up the operators of consecutive `let` statements"),
+        Arg::with_name("highlight-attr-order")
+            .long("highlight-attr-order")
+            .takes_value(true)
+            .possible_values(&["alphabetical", "canonical", "preserve"])
+            .help(
+                "The order to write the attributes of `highlight` commands in; canonical is \
+                         term, cterm, ctermfg, ctermbg, gui, guifg, guibg, guisp (default: \
+                         alphabetical)",
+            ),
+        Arg::with_name("mapping-attr-order")
+            .long("mapping-attr-order")
+            .takes_value(true)
+            .possible_values(&["alphabetical", "canonical", "preserve"])
+            .help(
+                "The order to write the attributes of mappings and abbreviations in; \
+                         canonical is buffer, nowait, silent, special, script, expr, unique \
+                         (default: alphabetical, or canonical in style edition 2024)",
+            ),
+        Arg::with_name("align-highlights")
+            .long("align-highlights")
+            .help("Line up the attributes of consecutive `highlight` commands"),
+        Arg::with_name("align-keymaps")
+            .long("align-keymaps")
+            .help("Line up the columns of the table after `loadkeymap`"),
+        Arg::with_name("align-signs")
+            .long("align-signs")
+            .help("Line up the attributes of consecutive `sign define` commands"),
+        Arg::with_name("space-in-braces")
+            .long("space-in-braces")
+            .help("Put spaces inside the braces of one-line dictionaries, e.g. `{ 'a': 1 }`"),
+        Arg::with_name("space-in-brackets")
+            .long("space-in-brackets")
+            .help("Put spaces inside the brackets of one-line lists, e.g. `[ 1, 2 ]`"),
+        Arg::with_name("trailing-commas")
+            .long("trailing-commas")
+            .takes_value(true)
+            .possible_values(&["multiline", "never", "preserve"])
+            .help(
+                "Where to put a comma after the last item of a list or dictionary; multiline \
+                         is after those written one item per line (default: multiline)",
+            ),
+        Arg::with_name("canonical-highlight-groups")
+            .long("canonical-highlight-groups")
+            .help("Spell built-in highlight group names as Vim's documentation does"),
+        Arg::with_name("safe").long("safe").help(
+            "Copy statements with parts the parser keeps as text (like the arguments of \
+                         user commands) exactly as they are",
+        ),
+        Arg::with_name("remove-trailing-bars")
+            .long("remove-trailing-bars")
+            .help("Remove a bar at the end of a line that isn't followed by a comment"),
+        Arg::with_name("trailing-comment-gap")
+            .long("trailing-comment-gap")
+            .takes_value(true)
+            .value_name("N")
+            .help("The number of spaces before a comment at the end of a line [default: 1]"),
+        Arg::with_name("max-alignment-padding")
+            .long("max-alignment-padding")
+            .takes_value(true)
+            .value_name("N")
+            .requires("align-assignments")
+            .help("The most spaces to add before an aligned operator [default: 8]"),
+        Arg::with_name("sort-modifiers")
+            .long("sort-modifiers")
+            .help("Sort command modifiers like `silent!` and `keepjumps` into canonical order"),
+        Arg::with_name("opaque-command")
+            .long("opaque-command")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .value_name("COMMAND")
+            .help(
+                "Write the arguments of this command exactly as they are (user commands, `!` \
+                         and `terminal` always are)",
+            ),
+        Arg::with_name("initial-indent")
+            .long("initial-indent")
+            .takes_value(true)
+            .value_name("N")
+            .help("Indent the output by N levels, for lines from inside a block"),
+        Arg::with_name("const-fold")
+            .long("const-fold")
+            .help("Evaluate arithmetic and concatenation of literals, e.g. `4 * 1024`"),
+        Arg::with_name("flatten-else-if")
+            .long("flatten-else-if")
+            .help("Turn an `if` nested alone in an `else` into `elseif` clauses"),
+        Arg::with_name("option-style")
+            .long("option-style")
+            .takes_value(true)
+            .possible_values(&["set", "let"])
+            .help(
+                "Set options to literal values with `set` or with `let &`, where both mean \
+                         the same",
+            ),
+        Arg::with_name("simplify-execute")
+            .long("simplify-execute")
+            .help("Merge string literals in `execute` commands, inlining them where possible"),
+    ]
+}
+
+/// Options for formatting files other than standard input, and for only formatting some of it.
+fn fmt_args() -> Vec<Arg<'static, 'static>> {
+    vec![
+        Arg::with_name("watch")
+            .long("watch")
+            .takes_value(true)
+            .value_name("DIR")
+            .help("Format Vim script files in DIR in place whenever they change"),
+        Arg::with_name("write")
+            .long("write")
+            .takes_value(true)
+            .multiple(true)
+            .value_name("PATH")
+            .conflicts_with("watch")
+            .help(
+                "Format files in place, and the Vim script files in directories and the \
+                         directories below them. Every file is formatted with the configuration \
+                         file found from the current directory",
+            ),
+        Arg::with_name("porcelain")
+            .long("porcelain")
+            .conflicts_with_all(&["watch", "write", "socket"])
+            .help(
+                "Start the output with a line saying whether the input was formatted, unchanged \
+                         or couldn't be parsed, for editor integrations",
+            ),
+        Arg::with_name("no-cache")
+            .long("no-cache")
+            .requires("write")
+            .help("With --write, format every file even if the cache says it is formatted"),
+        Arg::with_name("cache-dir")
+            .long("cache-dir")
+            .takes_value(true)
+            .value_name("DIR")
+            .requires("write")
+            .conflicts_with("no-cache")
+            .help(
+                "Where --write keeps track of the files that are already formatted \
+                         (default: $XDG_CACHE_HOME/vimlfmt or ~/.cache/vimlfmt)",
+            ),
+        Arg::with_name("daemon")
+            .long("daemon")
+            .requires("socket")
+            .conflicts_with("watch")
+            .help("Answer formatting requests sent as JSON lines to --socket"),
+        Arg::with_name("socket")
+            .long("socket")
+            .takes_value(true)
+            .value_name("PATH")
+            .requires("daemon")
+            .help("The Unix socket to listen on with --daemon"),
+        Arg::with_name("changed-lines")
+            .long("changed-lines")
+            .requires("stdin-filepath")
+            .help(
+                "Only format the statements that git says have changed since the last commit \
+                         (needs --stdin-filepath)",
+            ),
+        Arg::with_name("staged")
+            .long("staged")
+            .requires("changed-lines")
+            .help("With --changed-lines, only format the changes that are staged"),
+        Arg::with_name("report")
+            .long("report")
+            .help("Write statistics like the number of lines changed to stderr"),
+        Arg::with_name("report-verbatim")
+            .long("report-verbatim")
+            .help(
+                "List the user commands, embedded scripts and other text written exactly as \
+                         it was to stderr",
+            ),
+        Arg::with_name("source-map")
+            .long("source-map")
+            .takes_value(true)
+            .value_name("FILE")
+            .help("Write a JSON map from the lines of the output to the lines they came from"),
+    ]
+}
+
+fn app() -> App<'static, 'static> {
+    App::new(crate_name!())
         .version(crate_version!())
         .author(crate_authors!())
         .about(crate_description!())
+        // without a subcommand, the input is formatted as with `fmt`, and these options, from
+        // before there were subcommands, are still accepted
         .arg(
             Arg::with_name("ast")
                 .long("ast")
                 .help("Output AST instead of formatted code"),
         )
+        .arg(
+            Arg::with_name("ast-tree")
+                .long("ast-tree")
+                .help("Output AST as an indented tree, with positions and excerpts of the source"),
+        )
+        .arg(
+            Arg::with_name("ast-depth")
+                .long("ast-depth")
+                .takes_value(true)
+                .value_name("N")
+                .requires("ast-tree")
+                .help("With --ast-tree, only show nodes up to N levels deep"),
+        )
+        .arg(
+            Arg::with_name("ast-filter")
+                .long("ast-filter")
+                .takes_value(true)
+                .value_name("KIND")
+                .requires("ast-tree")
+                .help("With --ast-tree, only show nodes of this kind (e.g. `Call`)"),
+        )
+        .arg(
+            Arg::with_name("call-graph")
+                .long("call-graph")
+                .takes_value(true)
+                .possible_values(&["json", "dot"])
+                .help("Print the functions defined and called, as JSON or a Graphviz graph"),
+        )
         .arg(
             Arg::with_name("debug")
                 .long("debug")
                 .help("Output formatted Rust debug output (using '{:#?}')"),
         )
-        .get_matches();
-    let mut formatter = Formatter::new();
-    let lines: Vec<String> = io::stdin().lock().lines().filter_map(|l| l.ok()).collect();
-    match parse_lines(
-        lines
-            .iter()
-            .map(|l| l.as_str())
-            .collect::<Vec<&str>>()
-            .as_slice(),
-    ) {
+        .arg(
+            Arg::with_name("quiet")
+                .short("q")
+                .long("quiet")
+                .global(true)
+                .conflicts_with("verbose")
+                .help("Only write errors to standard error, not the files formatted or to format"),
+        )
+        .arg(
+            Arg::with_name("verbose")
+                .short("v")
+                .long("verbose")
+                .global(true)
+                .help(
+                    "Also write the configuration file used, the files already formatted and \
+                     how long parsing takes",
+                ),
+        )
+        .arg(
+            Arg::with_name("debug-parser")
+                .long("debug-parser")
+                .global(true)
+                .help("Write which command parser handled each statement, to diagnose misparses"),
+        )
+        .args(&input_args())
+        .args(&style_args())
+        .args(&fmt_args())
+        .subcommand(
+            SubCommand::with_name("fmt")
+                .about(
+                    "Format code from standard input, or files with --write (what happens when no \
+                     subcommand is given)",
+                )
+                .args(&input_args())
+                .args(&style_args())
+                .args(&fmt_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("check")
+                .about(
+                    "Check that code is formatted, listing what isn't and exiting with status 1 \
+                     if anything isn't",
+                )
+                .args(&input_args())
+                .args(&style_args())
+                .arg(
+                    Arg::with_name("path")
+                        .multiple(true)
+                        .value_name("PATH")
+                        .help(
+                            "Files, and directories of Vim script files, to check instead of \
+                             standard input",
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("ast")
+                .about("Output the syntax tree instead of formatted code")
+                .args(&input_args())
+                .arg(
+                    Arg::with_name("ast-tree")
+                        .long("tree")
+                        .help("Output an indented tree, with positions and excerpts of the source"),
+                )
+                .arg(
+                    Arg::with_name("ast-depth")
+                        .long("depth")
+                        .takes_value(true)
+                        .value_name("N")
+                        .requires("ast-tree")
+                        .help("With --tree, only show nodes up to N levels deep"),
+                )
+                .arg(
+                    Arg::with_name("ast-filter")
+                        .long("filter")
+                        .takes_value(true)
+                        .value_name("KIND")
+                        .requires("ast-tree")
+                        .help("With --tree, only show nodes of this kind (e.g. `Call`)"),
+                )
+                .arg(
+                    Arg::with_name("debug")
+                        .long("debug")
+                        .conflicts_with("ast-tree")
+                        .help("Output formatted Rust debug output (using '{:#?}')"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("dump-commands")
+                .about(
+                    "List the built-in commands, and with --json the autocommand events and \
+                     highlight keys too, for editor completion",
+                )
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .help("Output everything as JSON"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("convert")
+                .about("Convert legacy Vim script to another dialect")
+                .args(&input_args())
+                .arg(
+                    Arg::with_name("to")
+                        .long("to")
+                        .takes_value(true)
+                        .possible_values(&["vim9"])
+                        .required(true)
+                        .help("The dialect to convert to"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("metrics")
+                .about("Report the size and complexity of each function")
+                .args(&input_args())
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["table", "json"])
+                        .default_value("table")
+                        .help("How to output the metrics"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("mappings")
+                .about(
+                    "Report mappings that repeat or replace others, across all the files given, \
+                     or that override built-in commands",
+                )
+                .arg(
+                    Arg::with_name("file")
+                        .multiple(true)
+                        .required(true)
+                        .value_name("PATH")
+                        .help("Files, and directories of Vim script files, to check together"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("lint")
+                .about("Report likely problems in Vim script")
+                .args(&input_args())
+                .args(&style_args())
+                .arg(
+                    Arg::with_name("fix")
+                        .long("fix")
+                        .help("Fix what can be fixed and output the formatted result"),
+                )
+                .arg(
+                    Arg::with_name("max-warnings")
+                        .long("max-warnings")
+                        .takes_value(true)
+                        .value_name("N")
+                        .help(
+                            "Exit with status 1 if there are more than N warnings, as it always \
+                             does if there are errors",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("allow-missing-abort")
+                        .long("allow-missing-abort")
+                        .help("Don't require functions to be defined with `abort`"),
+                )
+                .arg(
+                    Arg::with_name("allow-ungrouped-autocmds")
+                        .long("allow-ungrouped-autocmds")
+                        .help("Don't require autocommands to be defined in an augroup"),
+                )
+                .arg(
+                    Arg::with_name("allow-unused")
+                        .long("allow-unused")
+                        .help("Don't report unused script-local functions and variables"),
+                )
+                .arg(
+                    Arg::with_name("require-bang")
+                        .long("require-bang")
+                        .help("Require functions and user commands to be defined with `!`"),
+                )
+                .arg(
+                    Arg::with_name("require-header")
+                        .long("require-header")
+                        .help(
+                            "Require scripts to start with `scriptencoding utf-8` and a load \
+                             guard named after --stdin-filepath, e.g. g:loaded_foo",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("project")
+                        .long("project")
+                        .takes_value(true)
+                        .value_name("DIR")
+                        .help(
+                            "The plugin the input is a script of, whose autoload functions calls \
+                             to them are checked against",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("target")
+                        .long("target")
+                        .takes_value(true)
+                        .value_name("EDITOR")
+                        .help(
+                            "The editor and version to check for deprecated features, e.g. vim82 \
+                             or nvim010",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("augroup")
+                        .long("augroup")
+                        .takes_value(true)
+                        .value_name("NAME")
+                        .help(
+                            "The group to put ungrouped autocommands in when fixing them \
+                             (defaults to the name of the file given by --stdin-filepath)",
+                        ),
+                ),
+        )
+}
+
+fn formatter_config(
+    args: &Options,
+    config_file: Option<&(PathBuf, Result<String, String>)>,
+) -> Result<FormatterConfig, String> {
+    let edition = match (args.value_of("style-edition"), config_file) {
+        (Some(edition), _) => edition.parse(),
+        (None, Some((file, text))) => text
+            .clone()
+            .and_then(|text| StyleEdition::from_toml(&text))
+            .map(|edition| edition.unwrap_or(StyleEdition::Edition2023))
+            .map_err(|e| format!("{}: {}", display_path(file), e)),
+        (None, None) => Ok(StyleEdition::Edition2023),
+    }?;
+    let mut config = FormatterConfig::with_style(
+        args.value_of("profile")
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(Profile::Default),
+        edition,
+    );
+    if args.is_present("preserve-one-line-blocks") {
+        config.one_line_blocks = OneLineBlocks::Preserve;
+    }
+    match args.value_of("continuations") {
+        Some("rewrap") => config.continuations = Continuations::Rewrap,
+        Some("preserve") => config.continuations = Continuations::Preserve,
+        Some("join") => config.continuations = Continuations::Join,
+        _ => (),
+    }
+    match args.value_of("event-case") {
+        Some("canonical") => config.event_case = EventCase::Canonical,
+        Some("lowercase") => config.event_case = EventCase::Lowercase,
+        Some("preserve") => config.event_case = EventCase::Preserve,
+        _ => (),
+    }
+    // flags only turn settings on or off, leaving those the profile chose alone otherwise
+    let flag = |name, setting: &mut bool, value| {
+        if args.is_present(name) {
+            *setting = value;
+        }
+    };
+    flag("sort-set-lists", &mut config.sort_set_lists, true);
+    flag("merge-set-lines", &mut config.merge_set_lines, true);
+    flag(
+        "keep-trailing-whitespace",
+        &mut config.trim_trailing_whitespace,
+        false,
+    );
+    flag("no-final-newline", &mut config.ensure_final_newline, false);
+    flag("strip-bom", &mut config.keep_byte_order_mark, false);
+    flag(
+        "keep-key-notation",
+        &mut config.normalize_key_notation,
+        false,
+    );
+    flag(
+        "normalize-normal-keys",
+        &mut config.normalize_keys_in_normal,
+        true,
+    );
+    flag("sort-modifiers", &mut config.sort_modifiers, true);
+    flag("compact-echohl", &mut config.compact_echohl, true);
+    flag(
+        "single-quote-execute",
+        &mut config.single_quote_execute,
+        true,
+    );
+    flag("expand-option-names", &mut config.expand_option_names, true);
+    flag("align-assignments", &mut config.align_assignments, true);
+    flag("align-highlights", &mut config.align_highlights, true);
+    flag("align-keymaps", &mut config.align_keymaps, true);
+    flag("align-signs", &mut config.align_signs, true);
+    flag("safe", &mut config.safe, true);
+    flag(
+        "remove-trailing-bars",
+        &mut config.remove_trailing_bars,
+        true,
+    );
+    flag("space-in-braces", &mut config.space_in_braces, true);
+    flag("space-in-brackets", &mut config.space_in_brackets, true);
+    flag(
+        "canonical-highlight-groups",
+        &mut config.canonical_highlight_groups,
+        true,
+    );
+    match args.value_of("execute-concatenation") {
+        Some("preserve") => config.execute_concatenation = Concatenation::Preserve,
+        Some("dot") => config.execute_concatenation = Concatenation::Dot,
+        Some("double-dot") => config.execute_concatenation = Concatenation::DoubleDot,
+        _ => (),
+    }
+    match args.value_of("highlight-attr-order") {
+        Some("alphabetical") => config.highlight_attr_order = HighlightAttrOrder::Alphabetical,
+        Some("canonical") => config.highlight_attr_order = HighlightAttrOrder::Canonical,
+        Some("preserve") => config.highlight_attr_order = HighlightAttrOrder::Preserve,
+        _ => (),
+    }
+    match args.value_of("trailing-commas") {
+        Some("multiline") => config.trailing_commas = TrailingCommas::Multiline,
+        Some("never") => config.trailing_commas = TrailingCommas::Never,
+        Some("preserve") => config.trailing_commas = TrailingCommas::Preserve,
+        _ => (),
+    }
+    match args.value_of("mapping-attr-order") {
+        Some("alphabetical") => config.mapping_attr_order = MappingAttrOrder::Alphabetical,
+        Some("canonical") => config.mapping_attr_order = MappingAttrOrder::Canonical,
+        Some("preserve") => config.mapping_attr_order = MappingAttrOrder::Preserve,
+        _ => (),
+    }
+    config.opaque_commands.extend(
+        args.values_of("opaque-command")
+            .into_iter()
+            .flatten()
+            .map(|c| c.to_string()),
+    );
+    if let Some(padding) = args.value_of("max-alignment-padding") {
+        match padding.parse() {
+            Ok(padding) => config.max_alignment_padding = padding,
+            Err(e) => return Err(format!("invalid --max-alignment-padding value: {}", e)),
+        }
+    }
+    if let Some(gap) = args.value_of("trailing-comment-gap") {
+        match gap.parse() {
+            Ok(gap) => config.trailing_comment_gap = gap,
+            Err(e) => return Err(format!("invalid --trailing-comment-gap value: {}", e)),
+        }
+    }
+    if let Some(indent) = args.value_of("initial-indent") {
+        match indent.parse() {
+            Ok(indent) => config.initial_indent = indent,
+            Err(e) => return Err(format!("invalid --initial-indent value: {}", e)),
+        }
+    }
+    match args.value_of("continuation-indent") {
+        Some("double") => config.continuation_indent = ContinuationIndent::Double,
+        Some("aligned") => config.continuation_indent = ContinuationIndent::Aligned,
+        Some(spaces) => match spaces.parse() {
+            Ok(spaces) => config.continuation_indent = ContinuationIndent::Spaces(spaces),
+            Err(_) => return Err(format!("invalid --continuation-indent value: {}", spaces)),
+        },
+        None => (),
+    }
+    for value in args.values_of("heredoc-formatter").into_iter().flatten() {
+        match value.split_once('=') {
+            Some((cmd, command)) => {
+                config
+                    .heredoc_formatters
+                    .insert(cmd.trim().to_string(), command.to_string());
+            }
+            None => return Err(format!("invalid --heredoc-formatter value: {}", value)),
+        }
+    }
+    for value in args.values_of("canonical-key").into_iter().flatten() {
+        match value.split_once('=') {
+            Some((name, spelling)) => {
+                config
+                    .canonical_keys
+                    .insert(name.to_lowercase(), spelling.to_string());
+            }
+            None => return Err(format!("invalid --canonical-key value: {}", value)),
+        }
+    }
+    for settings in args.values_of("config").into_iter().flatten() {
+        config.set_all(settings)?;
+    }
+    Ok(config)
+}
+
+fn pipeline(args: &Options, config: FormatterConfig) -> Pipeline {
+    let mut pipeline = Pipeline::new(config);
+    pipeline.target = match args.value_of("editor") {
+        Some("vim74") => Some(Target::Vim74),
+        Some("vim82") => Some(Target::Vim82),
+        Some("vim9") => Some(Target::Vim9),
+        Some("nvim") => Some(Target::NeovimStable),
+        Some("nvim-nightly") => Some(Target::NeovimNightly),
+        _ => None,
+    };
+    if let Some(cpoptions) = args.value_of("cpoptions") {
+        pipeline.cpoptions = cpoptions.to_string();
+    }
+    if args.value_of("context") == Some("function") {
+        pipeline.context = Context::Function;
+    }
+    pipeline.transforms = Transforms {
+        const_fold: args.is_present("const-fold"),
+        flatten_else_if: args.is_present("flatten-else-if"),
+        option_style: match args.value_of("option-style") {
+            Some("set") => Some(OptionStyle::Set),
+            Some("let") => Some(OptionStyle::Let),
+            _ => None,
+        },
+        simplify_execute: args.is_present("simplify-execute"),
+    };
+    pipeline
+}
+
+/// The files given, with directories replaced by the Vim script files in them and the
+/// directories below them. Directories that can't be read are reported and left out.
+fn vim_files<'a>(paths: impl Iterator<Item = &'a str>) -> Vec<PathBuf> {
+    let mut files = vec![];
+    for path in paths.map(Path::new) {
+        if path.is_dir() {
+            match watch::vim_files(path) {
+                Ok(found) => files.extend(found),
+                Err(e) => eprintln!("{}: {}", display_path(path), e),
+            }
+        } else {
+            files.push(path.to_path_buf());
+        }
+    }
+    files
+}
+
+/// What vimlfmt exits with, so scripts can tell what happened. When there are several files,
+/// it exits with the highest status any of them has.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Status {
+    /// Nothing was wrong.
+    Clean = 0,
+    /// Code isn't formatted, with `check`, or has problems, with `lint` or `mappings`.
+    Reformat = 1,
+    /// Code couldn't be parsed.
+    ParseError = 2,
+    /// Something else went wrong, e.g. a file couldn't be read or an option was invalid.
+    Internal = 3,
+}
+
+impl Status {
+    /// The status for an error from formatting a file, which is invalid data if the file
+    /// couldn't be parsed.
+    fn of(e: &io::Error) -> Self {
+        if e.kind() == io::ErrorKind::InvalidData {
+            Status::ParseError
+        } else {
+            Status::Internal
+        }
+    }
+}
+
+fn main() {
+    process::exit(run() as i32);
+}
+
+fn run() -> Status {
+    let matches = app().get_matches();
+    let (command, command_matches) = matches.subcommand();
+    if let Some(dump_matches) = matches.subcommand_matches("dump-commands") {
+        if dump_matches.is_present("json") {
+            println!("{}", completion::to_json());
+        } else {
+            print!("{}", completion::to_text());
+        }
+        return Status::Clean;
+    }
+    let args = Options {
+        matches: command_matches.into_iter().chain(Some(&matches)).collect(),
+    };
+    init_logger(&args);
+    if command == "mappings" {
+        let parser = pipeline(&args, FormatterConfig::default()).parser();
+        let mut status = Status::Clean;
+        let mut sites = vec![];
+        for file in vim_files(args.values_of("file").into_iter().flatten()) {
+            let parsed = fs::read_to_string(&file)
+                .map(|source| parser.parse_lines(&source.lines().collect::<Vec<&str>>()));
+            match parsed {
+                Ok(Ok(node)) => sites.extend(analysis::mapping_sites(&file, &node)),
+                Ok(Err(e)) => {
+                    eprintln!("{}: {}", display_path(&file), e);
+                    status = status.max(Status::ParseError);
+                }
+                Err(e) => {
+                    eprintln!("{}: {}", display_path(&file), e);
+                    status = status.max(Status::Internal);
+                }
+            }
+        }
+        for issue in analysis::mapping_issues(&sites) {
+            println!("{}", issue);
+            status = status.max(Status::Reformat);
+        }
+        return status;
+    }
+    let path = args.value_of("stdin-filepath");
+    let report = |e: &dyn Display| match path {
+        Some(path) => eprintln!("{}: {}", display_path(Path::new(path)), e),
+        None => eprintln!("{}", e),
+    };
+    // the configuration file is looked for from the directory of the file being formatted, or
+    // the current directory for stdin
+    let dir = env::current_dir().unwrap_or_default().join(
+        path.and_then(|p| Path::new(p).parent())
+            .unwrap_or(Path::new("")),
+    );
+    let config_file = config::find_config_file(&dir).map(|file| {
+        debug!("using configuration file {}", display_path(&file));
+        let text = fs::read_to_string(&file).map_err(|e| e.to_string());
+        (file, text)
+    });
+    let pipeline = match formatter_config(&args, config_file.as_ref()) {
+        Ok(config) => pipeline(&args, config),
+        Err(e) => {
+            report(&e);
+            return Status::Internal;
+        }
+    };
+    if let Some(dir) = args.value_of("watch") {
+        let result = watch::watch(
+            Path::new(dir),
+            &pipeline,
+            Duration::from_millis(500),
+            |path, result| match result {
+                Ok(true) => info!("formatted {}", display_path(path)),
+                Ok(false) => (),
+                Err(e) => eprintln!("{}: {}", display_path(path), e),
+            },
+        );
+        if let Err(e) = result {
+            eprintln!("{}: {}", dir, e);
+            return Status::Internal;
+        }
+        return Status::Clean;
+    }
+    if let Some(paths) = args.values_of("write") {
+        let cache = if args.is_present("no-cache") {
+            None
+        } else {
+            args.value_of("cache-dir")
+                .map(PathBuf::from)
+                .or_else(Cache::default_dir)
+                .map(|dir| Cache::new(&dir, &pipeline))
+        };
+        let mut status = Status::Clean;
+        for file in vim_files(paths) {
+            match watch::format_file_cached(&file, &pipeline, cache.as_ref()) {
+                Ok(true) => info!("formatted {}", display_path(&file)),
+                Ok(false) => debug!("{} is already formatted", display_path(&file)),
+                Err(e) => {
+                    eprintln!("{}: {}", display_path(&file), e);
+                    status = status.max(Status::of(&e));
+                }
+            }
+        }
+        return status;
+    }
+    if let Some(paths) = args.values_of("path") {
+        let mut status = Status::Clean;
+        for file in vim_files(paths) {
+            match watch::check_file(&file, &pipeline) {
+                Ok(true) => {
+                    info!("would reformat {}", display_path(&file));
+                    status = status.max(Status::Reformat);
+                }
+                Ok(false) => debug!("{} is already formatted", display_path(&file)),
+                Err(e) => {
+                    eprintln!("{}: {}", display_path(&file), e);
+                    status = status.max(Status::of(&e));
+                }
+            }
+        }
+        return status;
+    }
+    if let Some(socket) = args.value_of("socket") {
+        serve(socket, &pipeline);
+        return Status::Clean;
+    }
+    let mut input = String::new();
+    if let Err(e) = io::stdin().read_to_string(&mut input) {
+        report(&e);
+        return Status::Internal;
+    }
+    let lines = input.lines().collect::<Vec<&str>>();
+    let dialect = Dialect::detect(&lines);
+    let mut formatter = pipeline.formatter(&lines);
+    let parse_start = Instant::now();
+    let parsed = pipeline.parse(&lines);
+    let parse_time = parse_start.elapsed();
+    debug!("parsing took {:?} ({} lines)", parse_time, lines.len());
+    let mut status = Status::Clean;
+    match parsed {
         Ok(output) => {
-            if matches.is_present("debug") {
+            if command == "convert" {
+                match args.value_of("to") {
+                    Some("vim9") if dialect == Dialect::Vim9 => {
+                        report(&"input is already vim9 script")
+                    }
+                    Some("vim9") => match convert::to_vim9(&output) {
+                        Ok(conversion) => {
+                            print!("{}", conversion.output);
+                            for warning in conversion.warnings {
+                                report(&warning);
+                            }
+                        }
+                        Err(e) => {
+                            report(&e);
+                            return Status::Internal;
+                        }
+                    },
+                    _ => unreachable!(),
+                }
+            } else if command == "metrics" {
+                let metrics = analysis::metrics(&output);
+                if args.value_of("format") == Some("json") {
+                    println!("{}", analysis::metrics_json(&metrics));
+                } else {
+                    print!("{}", analysis::metrics_table(&metrics));
+                }
+            } else if command == "lint" {
+                let mut lint_config = LintConfig {
+                    require_abort: !args.is_present("allow-missing-abort"),
+                    require_augroup: !args.is_present("allow-ungrouped-autocmds"),
+                    report_unused: !args.is_present("allow-unused"),
+                    require_bang: args.is_present("require-bang"),
+                    ..LintConfig::default()
+                };
+                if let Some((file, text)) = &config_file {
+                    let applied = text.clone().and_then(|text| lint_config.apply_toml(&text));
+                    if let Err(e) = applied {
+                        report(&format!("{}: {}", display_path(file), e));
+                        return Status::Internal;
+                    }
+                }
+                let max_warnings = match args.value_of("max-warnings").map(str::parse) {
+                    Some(Ok(max)) => Some(max),
+                    Some(Err(e)) => {
+                        report(&format!("invalid --max-warnings value: {}", e));
+                        return Status::Internal;
+                    }
+                    None => None,
+                };
+                if let Some(target) = args.value_of("target") {
+                    match target.parse() {
+                        Ok(target) => lint_config.target = Some(target),
+                        Err(e) => {
+                            report(&e);
+                            return Status::Internal;
+                        }
+                    }
+                }
+                let file_stem = path
+                    .and_then(|p| Path::new(p).file_stem())
+                    .and_then(|s| s.to_str())
+                    .map(|s| s.trim_start_matches(['.', '_']))
+                    .filter(|s| !s.is_empty());
+                if let Some(name) = args.value_of("augroup").or(file_stem) {
+                    lint_config.augroup_name = name
+                        .chars()
+                        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+                        .collect();
+                }
+                if args.is_present("require-header") && lint_config.header.is_none() {
+                    lint_config.header = Some(HeaderConfig::default());
+                }
+                if let (Some(header), Some(path)) = (&mut lint_config.header, path) {
+                    header.guard_variable = Some(lint::guard_variable(Path::new(path)));
+                }
+                let (lines, output) = if args.is_present("fix") {
+                    let fixed = match lint::fix(&lines, &lint_config) {
+                        Ok(fixed) => fixed,
+                        Err(e) => {
+                            report(&e);
+                            return Status::Internal;
+                        }
+                    };
+                    for diagnostic in &fixed.fixed {
+                        report(&format!("fixed: {}", diagnostic));
+                    }
+                    let lines = fixed.lines;
+                    let fixed_lines = lines.iter().map(String::as_str).collect::<Vec<_>>();
+                    let output = match pipeline.parser().parse_lines(&fixed_lines) {
+                        Ok(output) => output,
+                        Err(e) => {
+                            report(&e);
+                            return Status::ParseError;
+                        }
+                    };
+                    match formatter.format(&output) {
+                        Ok(o) => print!("{}", o),
+                        Err(e) => {
+                            report(&e);
+                            return Status::Internal;
+                        }
+                    }
+                    (lines, output)
+                } else {
+                    (lines.iter().map(|l| l.to_string()).collect(), output)
+                };
+                let lines = lines.iter().map(String::as_str).collect::<Vec<_>>();
+                let diagnostics = match args.value_of("project").map(Path::new) {
+                    Some(dir) => match Project::load(dir) {
+                        Ok(project) => lint::lint_in_project(
+                            &output,
+                            &lines,
+                            &lint_config,
+                            &project,
+                            Path::new(path.unwrap_or_default()),
+                        ),
+                        Err(e) => {
+                            report(&format!("{}: {}", display_path(dir), e));
+                            return Status::Internal;
+                        }
+                    },
+                    None => lint::lint(&output, &lines, &lint_config),
+                };
+                let mut warnings = 0;
+                for diagnostic in diagnostics {
+                    report(&diagnostic);
+                    match diagnostic.severity {
+                        Severity::Error => status = Status::Reformat,
+                        Severity::Warning => warnings += 1,
+                    }
+                }
+                if let Some(max) = max_warnings.filter(|&max| warnings > max) {
+                    report(&format!(
+                        "too many warnings: {} (--max-warnings {})",
+                        warnings, max
+                    ));
+                    status = Status::Reformat;
+                }
+            } else if command == "check" {
+                match formatter.format_source(&output, &lines) {
+                    Ok(o) if o == input => (),
+                    Ok(_) => {
+                        info!("would reformat {}", path.unwrap_or("<stdin>"));
+                        status = Status::Reformat;
+                    }
+                    Err(e) => {
+                        report(&e);
+                        status = Status::Internal;
+                    }
+                }
+            } else if let Some(format) = args.value_of("call-graph") {
+                let graph = analysis::call_graph(&output);
+                if format == "dot" {
+                    println!("{}", graph.to_dot());
+                } else {
+                    println!("{}", graph.to_json());
+                }
+            } else if args.is_present("debug") {
                 println!("{:#?}", output);
-            } else if matches.is_present("ast") {
+            } else if args.is_present("ast-tree") {
+                let depth = match args.value_of("ast-depth").map(str::parse) {
+                    Some(Ok(depth)) => Some(depth),
+                    Some(Err(e)) => {
+                        report(&format!("invalid --ast-depth value: {}", e));
+                        return Status::Internal;
+                    }
+                    None => None,
+                };
+                let options = TreeOptions {
+                    depth,
+                    kind: args.value_of("ast-filter").map(|k| k.to_string()),
+                };
+                print!("{}", tree::render(&output, &lines, &options));
+            } else if command == "ast" || args.is_present("ast") {
                 println!("{}", output);
             } else {
-                match formatter.format(&output) {
-                    Ok(o) => println!("{}", o),
-                    Err(e) => eprintln!("{}", e),
+                let changed = match path {
+                    Some(path) if args.is_present("changed-lines") => {
+                        match git::changed_lines(Path::new(path), args.is_present("staged")) {
+                            Ok(changed) => Some(changed),
+                            Err(e) => {
+                                report(&e);
+                                return Status::Internal;
+                            }
+                        }
+                    }
+                    _ => None,
+                };
+                let format_start = Instant::now();
+                let formatted = match &changed {
+                    Some(changed) => formatter.format_ranges(&output, &lines, changed),
+                    None if pipeline.config.safe => formatter.format_source(&output, &lines),
+                    None if !args.is_present("report")
+                        && !args.is_present("report-verbatim")
+                        && !args.is_present("source-map")
+                        && !args.is_present("porcelain") =>
+                    {
+                        // nothing needs the output afterwards, so it isn't kept in memory
+                        if let Err(e) = formatter.format_to(&output, &mut io::stdout().lock()) {
+                            report(&e);
+                            return Status::Internal;
+                        }
+                        return Status::Clean;
+                    }
+                    None => formatter.format(&output),
+                };
+                match formatted {
+                    Ok(o) => {
+                        let format_time = format_start.elapsed();
+                        if args.is_present("porcelain") {
+                            print!("{}", porcelain::formatted(&input, &o));
+                        } else {
+                            print!("{}", o);
+                        }
+                        if args.is_present("report") {
+                            let mut stats = formatter.report(&lines);
+                            stats.parse_time = parse_time;
+                            stats.format_time = format_time;
+                            eprintln!("{}:\n{}", path.unwrap_or("<stdin>"), stats);
+                        }
+                        if args.is_present("report-verbatim") {
+                            eprintln!(
+                                "{}:\n{}",
+                                path.unwrap_or("<stdin>"),
+                                report::verbatim_summary(formatter.verbatim())
+                            );
+                        }
+                        if let Some(map_path) = args.value_of("source-map") {
+                            let map = formatter.source_map(&lines);
+                            if let Err(e) = fs::write(map_path, map.to_json() + "\n") {
+                                report(&format!("can't write {}: {}", map_path, e));
+                                status = Status::Internal;
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        if args.is_present("porcelain") {
+                            print!("{}", porcelain::failed(&e));
+                        }
+                        report(&e);
+                        status = Status::Internal;
+                    }
                 }
             }
         }
-        Err(e) => eprintln!("{}", e),
+        Err(e) => {
+            if args.is_present("porcelain") {
+                print!("{}", porcelain::error(&e));
+            }
+            let color = match args.value_of("color") {
+                Some("always") => Color::Always,
+                Some("never") => Color::Never,
+                _ => Color::Auto,
+            };
+            let color = color.enabled(io::stderr().is_terminal());
+            eprintln!("{}", diagnostic::render(&e, &lines, path, color));
+            status = Status::ParseError;
+        }
     }
+    status
 }
@@ -0,0 +1,146 @@
+//! The steps from source to formatted code: parsing, the transforms from
+//! [viml_parser::transform](../../viml_parser/transform/index.html), and formatting. Standard
+//! input, files formatted in place and the daemon all go through a
+//! [Pipeline](struct.Pipeline.html), so the same options format code the same way everywhere.
+use crate::{config::FormatterConfig, formatter::Formatter};
+use std::io::{self, Error, ErrorKind};
+use viml_parser::{
+    transform::{self, OptionStyle},
+    Context, Dialect, Node, ParseError, Parser, Target,
+};
+
+/// The transforms to apply to code after it is parsed and before it is formatted, which are
+/// applied in the order of the fields.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Transforms {
+    /// Evaluate constant expressions, with
+    /// [const_fold](../../viml_parser/transform/fn.const_fold.html).
+    pub const_fold: bool,
+    /// Turn an `if` alone in an `else` clause into `elseif` clauses, with
+    /// [flatten_else_if](../../viml_parser/transform/fn.flatten_else_if.html).
+    pub flatten_else_if: bool,
+    /// Write every option assignment with `set` or with `let`, with
+    /// [normalize_option_style](../../viml_parser/transform/fn.normalize_option_style.html), or
+    /// `None` to leave them as they are.
+    pub option_style: Option<OptionStyle>,
+    /// Fold the literal arguments of `execute` into the command they make, with
+    /// [simplify_execute](../../viml_parser/transform/fn.simplify_execute.html).
+    pub simplify_execute: bool,
+}
+
+/// How to parse, transform and format code.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Pipeline {
+    /// The editor to parse for, or `None` to accept what any editor does.
+    pub target: Option<Target>,
+    /// The value of `'cpoptions'` to parse with.
+    pub cpoptions: String,
+    /// Where the code is, e.g. in the body of a function.
+    pub context: Context,
+    pub transforms: Transforms,
+    pub config: FormatterConfig,
+}
+
+impl Pipeline {
+    /// A pipeline that parses like [parse_lines](../../viml_parser/fn.parse_lines.html), applies
+    /// no transforms and formats with `config`.
+    pub fn new(config: FormatterConfig) -> Self {
+        Self {
+            target: None,
+            cpoptions: String::new(),
+            context: Context::TopLevel,
+            transforms: Transforms::default(),
+            config,
+        }
+    }
+
+    pub fn parser(&self) -> Parser {
+        let parser = Parser::new()
+            .with_context(self.context)
+            .with_cpoptions(&self.cpoptions);
+        match self.target {
+            Some(target) => parser.with_target(target),
+            None => parser,
+        }
+    }
+
+    /// Parse a script and apply the transforms to it.
+    pub fn parse(&self, lines: &[&str]) -> Result<Node, ParseError> {
+        let mut ast = self.parser().parse_lines(lines)?;
+        if self.transforms.const_fold {
+            ast = transform::const_fold(ast);
+        }
+        if self.transforms.flatten_else_if {
+            ast = transform::flatten_else_if(ast);
+        }
+        if let Some(style) = self.transforms.option_style {
+            ast = transform::normalize_option_style(ast, style);
+        }
+        if self.transforms.simplify_execute {
+            ast = transform::simplify_execute(ast);
+        }
+        Ok(ast)
+    }
+
+    /// A formatter for a script, which writes Vim9 script comments if the script is Vim9
+    /// script.
+    pub fn formatter(&self, lines: &[&str]) -> Formatter {
+        let mut config = self.config.clone();
+        if Dialect::detect(lines) == Dialect::Vim9 {
+            config.vim9script = true;
+        }
+        Formatter::with_config(config)
+    }
+
+    /// Parse, transform and format a script. The error is invalid data if the script can't be
+    /// parsed.
+    pub fn format(&self, source: &str) -> io::Result<String> {
+        let lines = source.lines().collect::<Vec<&str>>();
+        let ast = self
+            .parse(&lines)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        self.formatter(&lines).format_source(&ast, &lines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pipeline() {
+        let mut pipeline = Pipeline::new(FormatterConfig::default());
+        assert_eq!(pipeline.format("let x=2*3").unwrap(), "let x = 2 * 3\n");
+        pipeline.transforms.const_fold = true;
+        assert_eq!(pipeline.format("let x=2*3").unwrap(), "let x = 6\n");
+        pipeline.transforms.option_style = Some(OptionStyle::Set);
+        assert_eq!(pipeline.format("let &tw=79").unwrap(), "set tw=79\n");
+        assert_eq!(
+            pipeline.format("return 1").unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+        pipeline.context = Context::Function;
+        assert_eq!(pipeline.format("return 1").unwrap(), "return 1\n");
+        pipeline.target = Some(Target::Vim9);
+        assert_eq!(
+            pipeline.format("wshada").unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+        pipeline.config.safe = true;
+        assert_eq!(
+            pipeline.format("let x=1\nCustom   foo  bar").unwrap(),
+            "let x = 1\nCustom   foo  bar\n"
+        );
+        assert_eq!(
+            pipeline.format("vim9script\n\" comment").unwrap(),
+            "vim9script\n# comment\n"
+        );
+        // the lines before `vim9script` are legacy script
+        assert_eq!(
+            pipeline
+                .format("\" Header comment\nvim9script\n\" comment")
+                .unwrap(),
+            "\" Header comment\nvim9script\n# comment\n"
+        );
+    }
+}
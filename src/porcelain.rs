@@ -0,0 +1,64 @@
+//! The output of `--porcelain`, for editor integrations that read it as a list of lines, e.g.
+//! with Vim's `systemlist()`. The first line is a header saying what happened, so the caller
+//! doesn't have to guess from the exit status or stderr:
+//!
+//! ```text
+//! formatted
+//! unchanged
+//! error LINE COLUMN MESSAGE
+//! failed MESSAGE
+//! ```
+//!
+//! Only `formatted` is followed by anything: the formatted text, which replaces the input. After
+//! `unchanged` the input should be left as it is, `error` is a parse error at a position in the
+//! input, and `failed` is any other error.
+
+use std::fmt::Display;
+use viml_parser::ParseError;
+
+// messages go on the header line, so they can't span more than one
+fn one_line(message: &str) -> String {
+    message.lines().collect::<Vec<&str>>().join(" ")
+}
+
+/// The output for formatting `input` into `output`.
+pub fn formatted(input: &str, output: &str) -> String {
+    if input == output {
+        "unchanged\n".to_string()
+    } else {
+        format!("formatted\n{}", output)
+    }
+}
+
+/// The output for input that couldn't be parsed.
+pub fn error(error: &ParseError) -> String {
+    format!(
+        "error {} {} {}\n",
+        error.pos.line(),
+        error.pos.column(),
+        one_line(error.message())
+    )
+}
+
+/// The output for any other error.
+pub fn failed(error: &dyn Display) -> String {
+    format!("failed {}\n", one_line(&error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use viml_parser::parse_lines;
+
+    #[test]
+    fn test_porcelain() {
+        assert_eq!(formatted("let x = 1\n", "let x = 1\n"), "unchanged\n");
+        assert_eq!(
+            formatted("let x=1\n", "let x = 1\n"),
+            "formatted\nlet x = 1\n"
+        );
+        let e = parse_lines(&["let x = 1", "foo"]).unwrap_err();
+        assert_eq!(error(&e), "error 2 1 E492: Not an editor command: foo\n");
+        assert_eq!(failed(&"no\nway"), "failed no way\n");
+    }
+}
@@ -0,0 +1,257 @@
+//! A plugin's scripts, loaded together so that code in one can be checked against the others.
+//! Vim finds an autoload function from its name: `foo#bar#baz()` is looked for in
+//! `autoload/foo/bar.vim`, so a [Project](struct.Project.html) indexes the functions each
+//! autoload script defines to resolve calls made from any script.
+use crate::{
+    analysis::{self, CallSite},
+    watch,
+};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs, io,
+    path::{Component, Path, PathBuf},
+};
+use viml_parser::{parse_lines, Node, ParseError, Position};
+
+/// A script of a project.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ProjectFile {
+    pub path: PathBuf,
+    pub ast: Node,
+}
+
+/// Where a function is defined.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Location {
+    pub path: PathBuf,
+    pub pos: Position,
+}
+
+/// The scripts of a plugin, or of any directory laid out like a runtime directory, with an
+/// index of the autoload functions they define.
+#[derive(Debug, Default)]
+pub struct Project {
+    root: PathBuf,
+    files: Vec<ProjectFile>,
+    errors: Vec<(PathBuf, ParseError)>,
+    autoload: BTreeMap<String, Location>,
+}
+
+/// The part of an autoload function's name that says which script defines it, e.g. `foo#bar#`
+/// for `foo#bar#baz`.
+fn autoload_prefix_of(name: &str) -> Option<&str> {
+    name.rfind('#').map(|i| &name[..=i])
+}
+
+impl Project {
+    /// A project with no scripts, for scripts under `root` to be added to with
+    /// [add_file](#method.add_file).
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            ..Self::default()
+        }
+    }
+
+    /// Load every Vim script file in `root` and the directories below it. Scripts that can't be
+    /// parsed are kept in [errors](#method.errors) instead of being added. Returns an error if a
+    /// directory or file can't be read.
+    pub fn load(root: &Path) -> io::Result<Self> {
+        let mut project = Self::new(root);
+        for path in watch::vim_files(root)? {
+            let source = fs::read_to_string(&path)?;
+            match parse_lines(&source.lines().collect::<Vec<&str>>()) {
+                Ok(ast) => project.add_file(path, ast),
+                Err(e) => project.errors.push((path, e)),
+            }
+        }
+        Ok(project)
+    }
+
+    /// Add a script, and the autoload functions it defines, to the project.
+    pub fn add_file(&mut self, path: PathBuf, ast: Node) {
+        if let Some(prefix) = self.autoload_prefix(&path) {
+            for definition in analysis::call_graph(&ast).definitions {
+                // a function can only be autoloaded from the script its name points to
+                if autoload_prefix_of(&definition.name) == Some(prefix.as_str()) {
+                    let pos = definition.pos;
+                    self.autoload
+                        .entry(definition.name)
+                        .or_insert_with(|| Location {
+                            path: path.clone(),
+                            pos,
+                        });
+                }
+            }
+        }
+        self.files.push(ProjectFile { path, ast });
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The scripts of the project, in the order they were added.
+    pub fn files(&self) -> &[ProjectFile] {
+        &self.files
+    }
+
+    /// The scripts that couldn't be parsed, with the errors parsing them.
+    pub fn errors(&self) -> &[(PathBuf, ParseError)] {
+        &self.errors
+    }
+
+    /// The names of functions a script in `autoload` defines must start with, e.g. `foo#bar#`
+    /// for `autoload/foo/bar.vim` under the project's root. `None` for other scripts.
+    pub fn autoload_prefix(&self, path: &Path) -> Option<String> {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        let mut names = relative
+            .with_extension("")
+            .components()
+            .map(|c| match c {
+                Component::Normal(name) => name.to_str().map(str::to_string),
+                _ => None,
+            })
+            .collect::<Option<Vec<String>>>()?;
+        if names.len() < 2 || names.remove(0) != "autoload" {
+            return None;
+        }
+        Some(format!("{}#", names.join("#")))
+    }
+
+    /// The script Vim would source to define an autoload function, e.g.
+    /// `autoload/foo/bar.vim` under the project's root for `foo#bar#baz`. `None` if `function`
+    /// isn't the name of an autoload function.
+    pub fn autoload_path(&self, function: &str) -> Option<PathBuf> {
+        let prefix = autoload_prefix_of(function)?.trim_end_matches('#');
+        let mut path = self.root.join("autoload");
+        path.extend(prefix.split('#'));
+        Some(path.with_extension("vim"))
+    }
+
+    /// Where an autoload function, e.g. `foo#bar#baz`, is defined, if the script it would be
+    /// autoloaded from defines it.
+    pub fn definition(&self, function: &str) -> Option<&Location> {
+        self.autoload.get(function)
+    }
+
+    /// The calls a script makes to autoload functions of the project that aren't defined. A
+    /// function belongs to the project if its name starts with the name of a file or directory
+    /// in the project's `autoload` directory, so calls to the functions of other plugins aren't
+    /// reported; calls whose script couldn't be parsed aren't either. `path` is where the script
+    /// is, which may be one of the project's, in which case it is `ast` rather than the script
+    /// as it was loaded that is checked against.
+    pub fn missing_autoload_calls(&self, path: &Path, ast: &Node) -> Vec<CallSite> {
+        let graph = analysis::call_graph(ast);
+        let own_prefix = self.autoload_prefix(path);
+        let own = graph
+            .definitions
+            .iter()
+            .map(|d| d.name.as_str())
+            .collect::<HashSet<&str>>();
+        let namespaces = self
+            .files
+            .iter()
+            .map(|f| f.path.as_path())
+            .chain(self.errors.iter().map(|(path, _)| path.as_path()))
+            .filter_map(|path| self.autoload_prefix(path))
+            .filter_map(|prefix| prefix.split('#').next().map(str::to_string))
+            .collect::<HashSet<String>>();
+        let unparsed = self
+            .errors
+            .iter()
+            .map(|(path, _)| path.as_path())
+            .collect::<Vec<&Path>>();
+        graph
+            .calls
+            .into_iter()
+            .filter(|call| {
+                let prefix = match autoload_prefix_of(&call.callee) {
+                    Some(prefix) => prefix,
+                    None => return false,
+                };
+                let defined = if own_prefix.as_deref() == Some(prefix) {
+                    own.contains(call.callee.as_str())
+                } else {
+                    self.definition(&call.callee).is_some()
+                };
+                !defined
+                    && namespaces.contains(prefix.split('#').next().unwrap_or_default())
+                    && !self
+                        .autoload_path(&call.callee)
+                        .is_some_and(|path| unparsed.contains(&path.as_path()))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project() {
+        let mut project = Project::new(Path::new("plugin-root"));
+        let parse = |lines: &[&str]| parse_lines(lines).unwrap();
+        project.add_file(
+            PathBuf::from("plugin-root/autoload/foo/bar.vim"),
+            parse(&[
+                "function! foo#bar#baz() abort",
+                "  return foo#bar#missing() + foo#qux() + fzf#run()",
+                "endfunction",
+                "function! foo#elsewhere() abort",
+                "endfunction",
+            ]),
+        );
+        let plugin = parse(&["call foo#bar#baz()", "call foo#elsewhere()"]);
+        project.add_file(PathBuf::from("plugin-root/plugin/foo.vim"), plugin.clone());
+        assert_eq!(
+            project.autoload_prefix(Path::new("plugin-root/autoload/foo/bar.vim")),
+            Some("foo#bar#".to_string())
+        );
+        assert_eq!(
+            project.autoload_prefix(Path::new("plugin-root/plugin/foo.vim")),
+            None
+        );
+        assert_eq!(
+            project.autoload_path("foo#bar#baz"),
+            Some(PathBuf::from("plugin-root/autoload/foo/bar.vim"))
+        );
+        assert_eq!(project.autoload_path("Foo"), None);
+        assert_eq!(
+            project.definition("foo#bar#baz"),
+            Some(&Location {
+                path: PathBuf::from("plugin-root/autoload/foo/bar.vim"),
+                pos: project.files()[0].ast.children()[0].pos(),
+            })
+        );
+        // defined, but not where it would be autoloaded from
+        assert_eq!(project.definition("foo#elsewhere"), None);
+        let missing = |path: &str, ast: &Node| {
+            project
+                .missing_autoload_calls(Path::new(path), ast)
+                .into_iter()
+                .map(|c| c.callee)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            missing("plugin-root/plugin/foo.vim", &plugin),
+            vec!["foo#elsewhere"]
+        );
+        let ast = project.files()[0].ast.clone();
+        assert_eq!(
+            missing("plugin-root/autoload/foo/bar.vim", &ast),
+            vec!["foo#bar#missing", "foo#qux"]
+        );
+        // the script being checked is used instead of the one that was loaded
+        let edited = parse(&[
+            "function! foo#bar#missing() abort",
+            "endfunction",
+            "echo foo#bar#baz()",
+        ]);
+        assert_eq!(
+            missing("plugin-root/autoload/foo/bar.vim", &edited),
+            vec!["foo#bar#baz"]
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use std::{fmt, time::Duration};
+use viml_parser::Position;
+
+/// Statistics about formatting one file, as returned by
+/// [Formatter::report](../formatter/struct.Formatter.html#method.report). The formatter only
+/// knows about the statements and lines, so the times are left for the caller to fill in.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Report {
+    /// The number of statements in the file, including comments but not blank lines. Statements
+    /// in the bodies of blocks (like `if` or `function`) are counted separately from the blocks.
+    pub statements: usize,
+    /// The number of statements whose lines were changed by formatting.
+    pub statements_changed: usize,
+    /// The number of lines changed, added or removed by formatting.
+    pub lines_changed: usize,
+    /// The length of the longest line before formatting, in characters.
+    pub longest_line_before: usize,
+    /// The length of the longest line after formatting, in characters.
+    pub longest_line_after: usize,
+    /// How long the file took to parse.
+    pub parse_time: Duration,
+    /// How long the file took to format.
+    pub format_time: Duration,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "statements reformatted: {}/{}",
+            self.statements_changed, self.statements
+        )?;
+        writeln!(f, "lines changed: {}", self.lines_changed)?;
+        writeln!(
+            f,
+            "longest line: {} -> {}",
+            self.longest_line_before, self.longest_line_after
+        )?;
+        writeln!(f, "parse time: {:?}", self.parse_time)?;
+        write!(f, "format time: {:?}", self.format_time)
+    }
+}
+
+/// The kinds of construct the formatter writes exactly as they were in the source.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum VerbatimKind {
+    /// A user command, e.g. one defined by a plugin, whose arguments the formatter knows
+    /// nothing about.
+    UserCommand,
+    /// A command listed in `opaque_commands`, e.g. `terminal`.
+    OpaqueCommand,
+    /// A script in another language, e.g. `lua << EOF`, that no heredoc formatter formatted.
+    Script,
+    /// The text after `append`, `insert` or `loadkeymap`.
+    Text,
+}
+
+impl fmt::Display for VerbatimKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VerbatimKind::UserCommand => write!(f, "user command"),
+            VerbatimKind::OpaqueCommand => write!(f, "opaque command"),
+            VerbatimKind::Script => write!(f, "script"),
+            VerbatimKind::Text => write!(f, "text"),
+        }
+    }
+}
+
+/// A construct the formatter wrote exactly as it was, as returned by
+/// [Formatter::verbatim](../formatter/struct.Formatter.html#method.verbatim).
+#[derive(Debug, PartialEq, Clone)]
+pub struct Verbatim {
+    pub kind: VerbatimKind,
+    /// The full name of the command, e.g. `Plug` or `lua`.
+    pub command: String,
+    pub pos: Position,
+}
+
+/// A summary of the constructs written verbatim, with a line for each kind and command giving
+/// how many there were and where, e.g.
+///
+/// ```text
+/// written verbatim: 3
+///   user command Plug: 2 (1:1, 2:1)
+///   script lua: 1 (4:1)
+/// ```
+pub fn verbatim_summary(verbatim: &[Verbatim]) -> String {
+    let mut groups: Vec<(VerbatimKind, &str, Vec<Position>)> = vec![];
+    for v in verbatim {
+        match groups
+            .iter_mut()
+            .find(|(kind, command, _)| *kind == v.kind && *command == v.command)
+        {
+            Some((_, _, positions)) => positions.push(v.pos),
+            None => groups.push((v.kind, &v.command, vec![v.pos])),
+        }
+    }
+    let mut rv = format!("written verbatim: {}", verbatim.len());
+    for (kind, command, positions) in groups {
+        let positions = positions
+            .iter()
+            .map(|p| format!("{}:{}", p.line(), p.column()))
+            .collect::<Vec<String>>();
+        rv.push_str(&format!(
+            "\n  {} {}: {} ({})",
+            kind,
+            command,
+            positions.len(),
+            positions.join(", ")
+        ));
+    }
+    rv
+}
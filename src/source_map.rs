@@ -0,0 +1,104 @@
+use crate::json;
+use std::ops::Range;
+
+/// The source lines a run of output lines was formatted from. Lines are numbered from 1 and the
+/// ranges don't include their ends, like Rust ranges.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Mapping {
+    pub output: Range<usize>,
+    pub input: Range<usize>,
+}
+
+/// A map from the lines of formatted output to the source lines they came from, as returned by
+/// [Formatter::source_map](../formatter/struct.Formatter.html#method.source_map), for tools that
+/// check a formatted copy of a file but report on the original. Statements are mapped as a whole,
+/// so every line of a statement maps to all of its source lines.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct SourceMap {
+    /// The mappings in output order. Output lines that came from no source lines (and source
+    /// lines that produced no output) aren't in any mapping.
+    pub mappings: Vec<Mapping>,
+}
+
+impl SourceMap {
+    /// The source lines an output line was formatted from.
+    pub fn input_lines(&self, output_line: usize) -> Option<Range<usize>> {
+        self.mappings
+            .iter()
+            .find(|m| m.output.contains(&output_line))
+            .map(|m| m.input.clone())
+    }
+
+    /// The output lines a source line was formatted into.
+    pub fn output_lines(&self, input_line: usize) -> Option<Range<usize>> {
+        self.mappings
+            .iter()
+            .find(|m| m.input.contains(&input_line))
+            .map(|m| m.output.clone())
+    }
+
+    /// The map as JSON, e.g.
+    /// `{"version":1,"mappings":[{"output":{"start":1,"end":3},"input":{"start":1,"end":2}}]}`.
+    pub fn to_json(&self) -> String {
+        let range = |r: &Range<usize>| {
+            format!(
+                "{{{}:{},{}:{}}}",
+                json::string("start"),
+                r.start,
+                json::string("end"),
+                r.end
+            )
+        };
+        let mappings = self
+            .mappings
+            .iter()
+            .map(|m| {
+                format!(
+                    "{{{}:{},{}:{}}}",
+                    json::string("output"),
+                    range(&m.output),
+                    json::string("input"),
+                    range(&m.input)
+                )
+            })
+            .collect::<Vec<String>>();
+        format!(
+            "{{{}:1,{}:[{}]}}",
+            json::string("version"),
+            json::string("mappings"),
+            mappings.join(",")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{config::FormatterConfig, formatter::Formatter};
+    use viml_parser::parse_lines;
+
+    #[test]
+    fn test_source_map() {
+        let source = [
+            "let foo = 1",
+            "call bar(1,",
+            "      \\ 2)",
+            "",
+            "",
+            "echo foo",
+        ];
+        let ast = parse_lines(&source).unwrap();
+        let mut formatter = Formatter::with_config(FormatterConfig::default());
+        assert_eq!(
+            formatter.format(&ast).unwrap(),
+            "let foo = 1\ncall bar(1, 2)\n\necho foo\n"
+        );
+        let map = formatter.source_map(&source);
+        assert_eq!(map.input_lines(2), Some(2..4));
+        assert_eq!(map.input_lines(4), Some(6..7));
+        assert_eq!(map.output_lines(3), Some(2..3));
+        assert_eq!(map.output_lines(1), Some(1..2));
+        assert!(map.to_json().starts_with(
+            r#"{"version":1,"mappings":[{"output":{"start":1,"end":2},"input":{"start":1,"end":2}},"#
+        ));
+    }
+}
@@ -0,0 +1,166 @@
+use viml_parser::Node;
+
+/// The longest source excerpt shown for a node, in characters.
+const EXCERPT_LENGTH: usize = 40;
+
+/// Which parts of the syntax tree [render](fn.render.html) shows.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct TreeOptions {
+    /// Don't show nodes more than this many levels below the nodes being shown, so a depth of 0
+    /// shows only the nodes themselves.
+    pub depth: Option<usize>,
+    /// Only show nodes of this [kind](../../viml_parser/enum.Node.html#method.kind) (compared
+    /// case-insensitively), and their children, instead of the whole tree. Nodes of this kind
+    /// within another are shown as part of it rather than again on their own.
+    pub kind: Option<String>,
+}
+
+/// Render a syntax tree for reading, one node per line, with the children of each node indented
+/// beneath it. Each line has the kind of the node, where it starts (`line:column`), the last line
+/// it covers if that is a different line, and an excerpt of the source from where it starts:
+///
+/// ```text
+/// TopLevel 1:1-3  if x
+/// └── If 1:1-3  if x
+///     ├── Identifier 1:4  x
+///     ├── Echo 2:3  echo x
+///     │   └── Identifier 2:8  x
+///     └── End 3:1  endif
+/// ```
+pub fn render(node: &Node, source: &[&str], options: &TreeOptions) -> String {
+    let mut out = String::new();
+    for root in roots(node, options.kind.as_deref()) {
+        write_node(root, source, options.depth, "", "", &mut out);
+    }
+    out
+}
+
+fn roots<'a>(node: &'a Node, kind: Option<&str>) -> Vec<&'a Node> {
+    match kind {
+        None => vec![node],
+        Some(kind) if node.kind().eq_ignore_ascii_case(kind) => vec![node],
+        Some(kind) => node
+            .children()
+            .into_iter()
+            .flat_map(|child| roots(child, Some(kind)))
+            .collect(),
+    }
+}
+
+fn last_line(node: &Node) -> usize {
+    node.children()
+        .into_iter()
+        .map(last_line)
+        .fold(node.pos().line(), usize::max)
+}
+
+fn excerpt(node: &Node, source: &[&str]) -> String {
+    let pos = node.pos();
+    let line = match pos.line().checked_sub(1).and_then(|i| source.get(i)) {
+        Some(line) => line,
+        None => return String::new(),
+    };
+    let text = line
+        .chars()
+        .skip(pos.column().saturating_sub(1))
+        .collect::<String>();
+    let text = text.trim_end();
+    if text.chars().count() > EXCERPT_LENGTH {
+        format!(
+            "{}...",
+            text.chars().take(EXCERPT_LENGTH - 3).collect::<String>()
+        )
+    } else {
+        text.to_string()
+    }
+}
+
+fn write_node(
+    node: &Node,
+    source: &[&str],
+    depth: Option<usize>,
+    prefix: &str,
+    child_prefix: &str,
+    out: &mut String,
+) {
+    let pos = node.pos();
+    out.push_str(prefix);
+    out.push_str(&format!("{} {}:{}", node.kind(), pos.line(), pos.column()));
+    let last = last_line(node);
+    if last != pos.line() {
+        out.push_str(&format!("-{}", last));
+    }
+    let excerpt = excerpt(node, source);
+    if !excerpt.is_empty() {
+        out.push_str("  ");
+        out.push_str(&excerpt);
+    }
+    out.push('\n');
+    let children = node.children();
+    if children.is_empty() {
+        return;
+    }
+    if depth == Some(0) {
+        out.push_str(&format!("{}└── ...\n", child_prefix));
+        return;
+    }
+    let depth = depth.map(|d| d - 1);
+    let count = children.len();
+    for (i, child) in children.into_iter().enumerate() {
+        let (branch, indent) = if i + 1 == count {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+        write_node(
+            child,
+            source,
+            depth,
+            &format!("{}{}", child_prefix, branch),
+            &format!("{}{}", child_prefix, indent),
+            out,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use viml_parser::parse_lines;
+
+    #[test]
+    fn test_render() {
+        let source = [
+            "if x",
+            "  echo x",
+            "endif",
+            "call some#really#long#function#name('with', 'several', 'arguments')",
+        ];
+        let ast = parse_lines(&source).unwrap();
+        assert_eq!(
+            render(&ast, &source, &TreeOptions::default()),
+            concat!(
+                "TopLevel 1:1-4  if x\n",
+                "├── If 1:1-3  if x\n",
+                "│   ├── Identifier 1:4  x\n",
+                "│   ├── Echo 2:3  echo x\n",
+                "│   │   └── Identifier 2:8  x\n",
+                "│   └── End 3:1  endif\n",
+                "└── ExCall 4:1  call some#really#long#function#name('...\n",
+                "    └── Call 4:36  ('with', 'several', 'arguments')\n",
+                "        ├── Identifier 4:6  some#really#long#function#name('with'...\n",
+                "        ├── String 4:37  'with', 'several', 'arguments')\n",
+                "        ├── String 4:45  'several', 'arguments')\n",
+                "        └── String 4:56  'arguments')\n",
+            )
+        );
+        let options = TreeOptions {
+            depth: Some(0),
+            kind: Some("echo".to_string()),
+        };
+        assert_eq!(
+            render(&ast, &source, &options),
+            "Echo 2:3  echo x\n└── ...\n"
+        );
+    }
+}
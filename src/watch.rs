@@ -0,0 +1,222 @@
+use crate::{cache::Cache, pipeline::Pipeline};
+use notify::{DebouncedEvent, RecursiveMode, Watcher};
+use std::{
+    ffi::OsStr,
+    fs,
+    io::{self, Error},
+    path::{Path, PathBuf},
+    sync::mpsc::channel,
+    time::Duration,
+};
+
+/// Whether a path looks like a Vim script: a `.vim` file or a vimrc (`.vimrc`, `_vimrc`,
+/// `.gvimrc` and so on).
+pub fn is_vim_file(path: &Path) -> bool {
+    if path.extension() == Some(OsStr::new("vim")) {
+        return true;
+    }
+    match path.file_name().and_then(|n| n.to_str()) {
+        Some(name) => {
+            let name = name.trim_start_matches(['.', '_']);
+            name == "vimrc" || name == "gvimrc" || name == "exrc"
+        }
+        None => false,
+    }
+}
+
+/// The Vim script files in a directory and the directories below it, sorted by path.
+pub fn vim_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(vim_files(&path)?);
+        } else if is_vim_file(&path) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Format a file in place, returning whether it was changed. The file is only written if the
+/// formatted code is different from what was there.
+pub fn format_file(path: &Path, pipeline: &Pipeline) -> io::Result<bool> {
+    format_file_cached(path, pipeline, None)
+}
+
+/// Like [format_file](fn.format_file.html), but a file the cache says is already formatted is
+/// left alone without being parsed, and files that are formatted afterwards are added to the
+/// cache.
+pub fn format_file_cached(
+    path: &Path,
+    pipeline: &Pipeline,
+    cache: Option<&Cache>,
+) -> io::Result<bool> {
+    let source = fs::read_to_string(path)?;
+    if cache.is_some_and(|cache| cache.is_formatted(&source)) {
+        return Ok(false);
+    }
+    let output = pipeline.format(&source)?;
+    let changed = output != source;
+    if changed {
+        fs::write(path, &output)?;
+    }
+    if let Some(cache) = cache {
+        cache.mark_formatted(&output)?;
+    }
+    Ok(changed)
+}
+
+/// Whether formatting a file would change it, without writing it.
+pub fn check_file(path: &Path, pipeline: &Pipeline) -> io::Result<bool> {
+    let source = fs::read_to_string(path)?;
+    Ok(pipeline.format(&source)? != source)
+}
+
+/// Watch a directory, and everything in it, for Vim script files being created or written, and
+/// format each one in place once it hasn't changed for `delay`. `log` is called with the path and
+/// the result of [format_file](fn.format_file.html) every time a file is formatted, and with any
+/// errors from watching the directory. Writing a file that is already formatted does not change
+/// it, so the formatter's own writes don't cause it to be formatted again and again.
+///
+/// This only returns if the directory can't be watched.
+pub fn watch<F>(dir: &Path, pipeline: &Pipeline, delay: Duration, mut log: F) -> io::Result<()>
+where
+    F: FnMut(&Path, io::Result<bool>),
+{
+    let to_io_error = Error::other;
+    let (tx, rx) = channel();
+    let mut watcher = notify::watcher(tx, delay).map_err(to_io_error)?;
+    watcher
+        .watch(dir, RecursiveMode::Recursive)
+        .map_err(to_io_error)?;
+    for event in rx {
+        match event {
+            DebouncedEvent::Create(path)
+            | DebouncedEvent::Write(path)
+            | DebouncedEvent::Rename(_, path)
+                if is_vim_file(&path) && path.is_file() =>
+            {
+                log(&path, format_file(&path, pipeline));
+            }
+            DebouncedEvent::Error(e, path) => {
+                log(path.as_deref().unwrap_or(dir), Err(to_io_error(e)));
+            }
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::FormatterConfig;
+    use std::{env, io::ErrorKind, path::PathBuf, process};
+
+    #[test]
+    fn test_is_vim_file() {
+        for path in &[
+            "plugin/foo.vim",
+            ".vimrc",
+            "_vimrc",
+            "/home/me/.gvimrc",
+            "vimrc",
+        ] {
+            assert!(is_vim_file(Path::new(path)), "{}", path);
+        }
+        for path in &["foo.vim.swp", "README.md", "vim", ".vim"] {
+            assert!(!is_vim_file(Path::new(path)), "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_format_file() {
+        let path: PathBuf =
+            env::temp_dir().join(format!("vimlfmt-test-format-file-{}.vim", process::id()));
+        let pipeline = Pipeline::new(FormatterConfig::default());
+        fs::write(&path, "let   x=1\nif x|echo x|endif\n").unwrap();
+        assert!(check_file(&path, &pipeline).unwrap());
+        assert!(format_file(&path, &pipeline).unwrap());
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "let x = 1\nif x\n  echo x\nendif\n"
+        );
+        assert!(!format_file(&path, &pipeline).unwrap());
+        assert!(!check_file(&path, &pipeline).unwrap());
+        // checking applies the transforms and safe mode like formatting does
+        let mut folding = pipeline.clone();
+        folding.transforms.const_fold = true;
+        fs::write(&path, "let x = 2 * 3\n").unwrap();
+        assert!(!check_file(&path, &pipeline).unwrap());
+        assert!(check_file(&path, &folding).unwrap());
+        let mut safe = pipeline.clone();
+        safe.config.safe = true;
+        fs::write(&path, "Custom   foo\n").unwrap();
+        assert!(check_file(&path, &pipeline).unwrap());
+        assert!(!check_file(&path, &safe).unwrap());
+        fs::write(&path, "if x\n").unwrap();
+        assert_eq!(
+            format_file(&path, &pipeline).unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "if x\n");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_format_vim9script() {
+        let path: PathBuf =
+            env::temp_dir().join(format!("vimlfmt-test-format-vim9-{}.vim", process::id()));
+        // a legacy function is still a legacy function in Vim9 script
+        let source = concat!(
+            "vim9script\n",
+            "\n",
+            "function F(a) abort\n",
+            "  \" body\n",
+            "  let l:x = a:a\n",
+            "endfunction\n",
+        );
+        let pipeline = Pipeline::new(FormatterConfig::default());
+        fs::write(&path, source).unwrap();
+        assert!(!check_file(&path, &pipeline).unwrap());
+        assert!(!format_file(&path, &pipeline).unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), source);
+        fs::write(&path, "vim9script\n\" comment\nfunction F()\nendfunction\n").unwrap();
+        assert!(format_file(&path, &pipeline).unwrap());
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "vim9script\n# comment\nfunction F()\nendfunction\n"
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_format_file_cached() {
+        let dir = env::temp_dir().join(format!("vimlfmt-test-format-cached-{}", process::id()));
+        let path = dir.join("plugin").join("foo.vim");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(dir.join("README.md"), "").unwrap();
+        fs::write(&path, "let   x=1").unwrap();
+        assert_eq!(vim_files(&dir).unwrap(), vec![path.clone()]);
+        let mut pipeline = Pipeline::new(FormatterConfig::default());
+        let cache = Cache::new(&dir.join("cache"), &pipeline);
+        assert!(format_file_cached(&path, &pipeline, Some(&cache)).unwrap());
+        assert!(cache.is_formatted("let x = 1\n"));
+        // the transforms are part of what the cache is for
+        pipeline.transforms.const_fold = true;
+        let folding = Cache::new(&dir.join("cache"), &pipeline);
+        assert!(!folding.is_formatted("let x = 1\n"));
+        fs::write(&path, "let x = 2 * 3").unwrap();
+        assert!(format_file_cached(&path, &pipeline, Some(&folding)).unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "let x = 6\n");
+        // the cache is trusted, so a file it knows about isn't even parsed
+        let cache = Cache::new(&dir.join("cache"), &pipeline);
+        cache.mark_formatted("if x").unwrap();
+        fs::write(&path, "if x").unwrap();
+        assert!(!format_file_cached(&path, &pipeline, Some(&cache)).unwrap());
+        assert!(format_file(&path, &pipeline).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
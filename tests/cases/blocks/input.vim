@@ -0,0 +1,15 @@
+" Blocks are indented by two spaces, and one-line blocks are expanded.
+function!   s:Toggle(name)   abort
+if exists('g:'.a:name)|unlet g:{a:name}|else|let g:{a:name}=1|endif
+for [k,v] in items({'a':1,'b':2})
+try
+echo k
+catch
+echoerr v:exception
+finally
+echo 'done'
+endtry
+endfor
+while 0
+endwhile
+endfunction
@@ -0,0 +1,5 @@
+let s:options = {
+      \ 'first': 1,
+      \ 'second': [1, 2, 3],
+      \ }
+call s:Setup('a very long argument that goes on', 'another very long argument', 'and one more')
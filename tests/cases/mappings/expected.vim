@@ -0,0 +1,6 @@
+nnoremap <buffer> <silent> <Leader>f :call <SID>Format()<CR>
+inoremap <expr> <Tab> pumvisible() ? "\<C-n>" : "\<Tab>"
+augroup vimlfmt
+  autocmd!
+  autocmd BufWritePre *.vim call s:Format()
+augroup END
@@ -0,0 +1,41 @@
+if has('nvim')
+  function! s:Open() abort
+    echo 'nvim'
+  endfunction
+else
+  function! s:Open() abort
+    echo 'vim'
+  endfunction
+
+  " the end of the else
+endif
+
+function! s:Outer(list) abort
+  let total = 0
+
+  function! Add(item) closure
+    let total += a:item
+  endfunction
+
+  call map(copy(a:list), {_, item -> Add(item)})
+  try
+    function! s:Inner() abort
+      return 1
+    endfunction
+  catch
+  endtry
+
+  function! s:Last() dict
+    function! s:Deepest()
+      return 0
+    endfunction
+  endfunction
+endfunction
+
+function! s:Next()
+  if 1
+    function! s:InIf()
+    endfunction
+
+  endif
+endfunction
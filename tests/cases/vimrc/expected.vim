@@ -0,0 +1,46 @@
+" vimrc
+set nocompatible
+filetype plugin indent on
+syntax enable
+if has('win32')
+  set shell=cmd.exe
+endif " windows
+if has('persistent_undo')
+  set undodir=~/.vim/undo
+  set undofile
+endif
+silent! colorscheme gruvbox
+runtime! plugin/**/*.vim
+runtime! macros/matchit.vim
+set fillchars=vert:\ ,fold:\ 
+set statusline=%f\ %m%r%=%l:%c
+let &t_SI = "\<Esc>[6 q"
+execute 'set rtp+=' . expand('~/.vim')
+call plug#begin('~/.vim/plugged')
+Plug 'tpope/vim-sensible'
+Plug 'junegunn/fzf', { 'do': { -> fzf#install() } }
+call plug#end()
+let mapleader = "\<Space>"
+nnoremap <silent> <Leader>w :w<CR>
+nnoremap <Leader>ev :e $MYVIMRC<CR>
+augroup vimrc
+  autocmd!
+augroup END
+augroup vimrc
+  autocmd!
+  autocmd FileType python setlocal ts=4 sw=4 | setlocal et
+  autocmd BufWritePre * %s/\s\+$//e
+augroup END
+command! -nargs=0 Trim %s/\s\+$//e
+if exists('+colorcolumn')
+  set colorcolumn=+1
+endif
+let g:netrw_banner = 0
+let g:netrw_liststyle = 3
+set wildignore+=*.o,*.obj,.git
+set guifont=Source\ Code\ Pro:h12
+highlight! link SignColumn LineNr
+cnoreabbrev W w
+if &term =~ '256color'
+  set t_ut=
+endif
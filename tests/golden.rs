@@ -0,0 +1,147 @@
+//! Golden tests for the formatter. Each directory in `tests/cases` holds an `input.vim`, which is
+//! formatted and compared with the `expected.vim` next to it, which must also come out of the
+//! formatter unchanged. Pass `--bless` to write the output of every case to its `expected.vim`
+//! instead, e.g. `cargo test --test golden -- --bless`, and a case name to run only the cases
+//! whose names contain it.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+};
+use vimlfmt::{config::FormatterConfig, pipeline::Pipeline};
+
+fn format(input: &str) -> Result<String, String> {
+    Pipeline::new(FormatterConfig::default())
+        .format(input)
+        .map_err(|e| e.to_string())
+}
+
+/// A line-by-line diff of the expected and actual output, marking lines only in the expected
+/// output with `-` and lines only in the actual output with `+`.
+fn diff(expected: &str, actual: &str) -> String {
+    let expected = expected.lines().collect::<Vec<_>>();
+    let actual = actual.lines().collect::<Vec<_>>();
+    // Longest common subsequence, so that an added or removed line doesn't show every line
+    // after it as changed.
+    let mut lcs = vec![vec![0; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            lcs[i][j] = if expected[i] == actual[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let mut rv = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            rv.push_str(&format!("  {}\n", expected[i]));
+            i += 1;
+            j += 1;
+        } else if i < expected.len() && (j == actual.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            rv.push_str(&format!("- {}\n", expected[i]));
+            i += 1;
+        } else {
+            rv.push_str(&format!("+ {}\n", actual[j]));
+            j += 1;
+        }
+    }
+    rv
+}
+
+fn cases(filter: Option<&str>) -> Vec<PathBuf> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("cases");
+    let mut cases = fs::read_dir(&root)
+        .unwrap_or_else(|e| panic!("can't read {}: {}", root.display(), e))
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.join("input.vim").is_file())
+        .filter(|path| {
+            filter.is_none_or(|f| path.file_name().unwrap().to_string_lossy().contains(f))
+        })
+        .collect::<Vec<_>>();
+    cases.sort();
+    cases
+}
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    let bless = args.iter().any(|a| a == "--bless");
+    // The test harness passes flags like `--nocapture` along; anything else is a filter.
+    let filter = args
+        .iter()
+        .find(|a| !a.starts_with('-'))
+        .map(String::as_str);
+    let cases = cases(filter);
+    let mut failed = vec![];
+    for case in &cases {
+        let name = case.file_name().unwrap().to_string_lossy();
+        let input = fs::read_to_string(case.join("input.vim")).unwrap();
+        let expected_path = case.join("expected.vim");
+        let actual = match format(&input) {
+            Ok(actual) => actual,
+            Err(e) => {
+                println!("case {} ... FAILED\n  input doesn't parse: {}", name, e);
+                failed.push(name);
+                continue;
+            }
+        };
+        if bless {
+            fs::write(&expected_path, &actual).unwrap();
+            println!("case {} ... blessed", name);
+            continue;
+        }
+        match fs::read_to_string(&expected_path) {
+            Ok(expected) if expected == actual => match format(&expected) {
+                // the expected output is already formatted, so formatting it again shouldn't
+                // change it
+                Ok(again) if again == expected => println!("case {} ... ok", name),
+                Ok(again) => {
+                    println!(
+                        "case {} ... FAILED\n  expected.vim changes when formatted again:\n{}",
+                        name,
+                        diff(&expected, &again)
+                    );
+                    failed.push(name);
+                }
+                Err(e) => {
+                    println!(
+                        "case {} ... FAILED\n  expected.vim doesn't parse: {}",
+                        name, e
+                    );
+                    failed.push(name);
+                }
+            },
+            Ok(expected) => {
+                println!("case {} ... FAILED\n{}", name, diff(&expected, &actual));
+                failed.push(name);
+            }
+            Err(e) => {
+                println!(
+                    "case {} ... FAILED\n  can't read {}: {}",
+                    name,
+                    expected_path.display(),
+                    e
+                );
+                failed.push(name);
+            }
+        }
+    }
+    println!(
+        "\ngolden test result: {} passed; {} failed",
+        cases.len() - failed.len(),
+        failed.len()
+    );
+    if !failed.is_empty() {
+        println!("\nfailures:");
+        for name in failed {
+            println!("    {}", name);
+        }
+        println!("\nrun `cargo test --test golden -- --bless` to accept the new output");
+        process::exit(1);
+    }
+}
@@ -0,0 +1,121 @@
+//! Tests for the editor plugin in `plugin/` and `autoload/`, which runs the binary with
+//! `--porcelain`. Its scripts must be formatted as the binary formats them, and where Vim is
+//! installed, formatting a buffer with `:VimlFmt` must give what the binary gives.
+
+use std::{
+    env, fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{self, Command, Stdio},
+};
+
+const BINARY: &str = env!("CARGO_BIN_EXE_vimlfmt");
+
+fn root() -> &'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+}
+
+fn vimlfmt(input: &str, args: &[&str]) -> String {
+    let mut child = Command::new(BINARY)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    String::from_utf8(child.wait_with_output().unwrap().stdout).unwrap()
+}
+
+// Run Vim without any configuration but the plugin on a copy of `input`, and return what the
+// file holds afterwards and the messages Vim gave. `None` if Vim isn't installed.
+fn vim(name: &str, input: &str, commands: &[&str]) -> Option<(String, String)> {
+    let dir = env::temp_dir().join(format!("vimlfmt-test-plugin-{}-{}", name, process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("test.vim");
+    let messages = dir.join("messages");
+    fs::write(&file, input).unwrap();
+    let mut args = vec![
+        "-Nu".to_string(),
+        "NONE".to_string(),
+        "-i".to_string(),
+        "NONE".to_string(),
+        "-es".to_string(),
+        "--cmd".to_string(),
+        format!("set runtimepath^={}", root().display()),
+        "-c".to_string(),
+        "runtime plugin/vimlfmt.vim".to_string(),
+        "-c".to_string(),
+        format!("let g:vimlfmt_command = '{}'", BINARY),
+    ];
+    for command in commands {
+        args.push("-c".to_string());
+        args.push(command.to_string());
+    }
+    args.push("-c".to_string());
+    args.push(format!(
+        "call writefile(split(execute('messages'), \"\\n\"), '{}') | write | quit!",
+        messages.display()
+    ));
+    args.push(file.display().to_string());
+    let status = Command::new("vim")
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .ok()?;
+    assert!(status.success(), "vim exited with {}", status);
+    let output = (
+        fs::read_to_string(&file).unwrap(),
+        fs::read_to_string(&messages).unwrap_or_default(),
+    );
+    fs::remove_dir_all(&dir).unwrap();
+    Some(output)
+}
+
+#[test]
+fn test_scripts_are_formatted() {
+    for script in ["plugin/vimlfmt.vim", "autoload/vimlfmt.vim"] {
+        let path: PathBuf = root().join(script);
+        let source = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            vimlfmt(&source, &["--porcelain"]),
+            "unchanged\n",
+            "{}",
+            script
+        );
+    }
+}
+
+#[test]
+fn test_vimlfmt_command() {
+    let input = "function! F(x)\nif a:x\nreturn   1\nendif\nendfunction\n";
+    let Some((output, messages)) = vim("format", input, &["VimlFmt"]) else {
+        return;
+    };
+    assert_eq!(output, vimlfmt(input, &[]));
+    assert!(!messages.contains("vimlfmt:"), "{}", messages);
+    let input = "let x = 1\nfoo\n";
+    let (output, messages) = vim("error", input, &["VimlFmt"]).unwrap();
+    assert_eq!(output, input);
+    assert!(
+        messages.contains("vimlfmt: line 2, column 1: E492: Not an editor command: foo"),
+        "{}",
+        messages
+    );
+}
+
+#[test]
+fn test_formatexpr() {
+    let input = "function! F(x)\n  if a:x\n    return   1\n  endif\nendfunction\n";
+    let Some((output, _)) = vim("formatexpr", input, &["set filetype=vim", "normal! 3Ggqq"]) else {
+        return;
+    };
+    assert_eq!(output, input.replace("return   1", "return 1"));
+}
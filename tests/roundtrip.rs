@@ -0,0 +1,233 @@
+//! Property tests for the expression parser and formatter: random expressions are built as
+//! syntax trees, formatted, and parsed again, and the result must be the same tree. Positions
+//! aren't compared, since the trees are compared by their s-expression form, which doesn't
+//! include them.
+
+use proptest::prelude::*;
+use viml_parser::{parse_lines, BinaryOpKind, Node, Position, UnaryOpKind};
+use vimlfmt::formatter::Formatter;
+
+// How tightly each kind of expression binds, loosest first, as in `:help expression-syntax`.
+const TERNARY: u8 = 0;
+const OR: u8 = 1;
+const AND: u8 = 2;
+const COMPARISON: u8 = 3;
+const ADDITIVE: u8 = 4;
+const MULTIPLICATIVE: u8 = 5;
+const UNARY: u8 = 6;
+const POSTFIX: u8 = 7;
+const ATOM: u8 = 8;
+
+/// An expression, and how tightly it binds.
+type Expr = (Node, u8);
+
+fn pos() -> Position {
+    Position::default()
+}
+
+/// An expression that binds at least as tightly as `min`, wrapping it in parentheses if needed.
+fn operand((node, level): Expr, min: u8) -> Box<Node> {
+    if level >= min {
+        Box::new(node)
+    } else {
+        Box::new(Node::ParenExpr {
+            pos: pos(),
+            expr: Box::new(node),
+        })
+    }
+}
+
+fn atom() -> impl Strategy<Value = Expr> {
+    prop_oneof![
+        (0u32..1000).prop_map(|n| n.to_string()),
+        Just("0x1F".to_string()),
+        Just("0b101".to_string()),
+        Just("1.5".to_string()),
+    ]
+    .prop_map(|value| Node::Number { pos: pos(), value })
+    .boxed()
+    .prop_union(
+        prop_oneof![
+            "'[a-z ]{0,6}'",
+            "\"[a-z ]{0,6}\"",
+            Just("'it''s'".to_string()),
+            Just("\"\\t\\\"\"".to_string()),
+        ]
+        .prop_map(|value| Node::String { pos: pos(), value })
+        .boxed(),
+    )
+    .or(
+        prop::sample::select(vec!["x", "g:y", "s:z", "l:count", "a:000", "v:true"])
+            .prop_map(|value| Node::Identifier {
+                pos: pos(),
+                value: value.to_string(),
+            })
+            .boxed(),
+    )
+    .prop_map(|node| (node, ATOM))
+}
+
+fn binary_op() -> impl Strategy<Value = (BinaryOpKind, u8)> {
+    use BinaryOpKind::*;
+    prop::sample::select(vec![
+        (Or, OR),
+        (And, AND),
+        (EqEq, COMPARISON),
+        (NotEqCI, COMPARISON),
+        (GTEq, COMPARISON),
+        (LTCS, COMPARISON),
+        (Is, COMPARISON),
+        (IsNot, COMPARISON),
+        (Match, COMPARISON),
+        (NoMatchCS, COMPARISON),
+        (Add, ADDITIVE),
+        (Subtract, ADDITIVE),
+        (StringConcat, ADDITIVE),
+        (Multiply, MULTIPLICATIVE),
+        (Divide, MULTIPLICATIVE),
+        (Remainder, MULTIPLICATIVE),
+    ])
+}
+
+fn expr() -> impl Strategy<Value = Expr> {
+    atom().prop_recursive(4, 32, 3, |inner| {
+        prop_oneof![
+            (binary_op(), inner.clone(), inner.clone()).prop_map(|((op, level), left, right)| {
+                // Comparisons don't chain; everything else is left-associative.
+                let left_min = if level == COMPARISON {
+                    level + 1
+                } else {
+                    level
+                };
+                (
+                    Node::BinaryOp {
+                        pos: pos(),
+                        op,
+                        left: operand(left, left_min),
+                        right: operand(right, level + 1),
+                    },
+                    level,
+                )
+            }),
+            (inner.clone(), inner.clone(), inner.clone()).prop_map(|(cond, left, right)| {
+                (
+                    Node::Ternary {
+                        pos: pos(),
+                        cond: operand(cond, OR),
+                        left: operand(left, TERNARY),
+                        right: operand(right, TERNARY),
+                    },
+                    TERNARY,
+                )
+            }),
+            (
+                prop::sample::select(vec![
+                    UnaryOpKind::Not,
+                    UnaryOpKind::Minus,
+                    UnaryOpKind::Plus
+                ]),
+                inner.clone()
+            )
+                .prop_map(|(op, right)| {
+                    (
+                        Node::UnaryOp {
+                            pos: pos(),
+                            op,
+                            right: operand(right, UNARY),
+                        },
+                        UNARY,
+                    )
+                }),
+            (inner.clone(), inner.clone()).prop_map(|(name, index)| {
+                (
+                    Node::Subscript {
+                        pos: pos(),
+                        name: operand(name, POSTFIX),
+                        index: operand(index, TERNARY),
+                    },
+                    POSTFIX,
+                )
+            }),
+            (
+                prop::sample::select(vec!["len", "s:Foo", "foo#bar", "Baz"]),
+                prop::collection::vec(inner.clone(), 0..3)
+            )
+                .prop_map(|(name, args)| {
+                    (
+                        Node::Call {
+                            pos: pos(),
+                            name: Box::new(Node::Identifier {
+                                pos: pos(),
+                                value: name.to_string(),
+                            }),
+                            args: args.into_iter().map(|a| *operand(a, TERNARY)).collect(),
+                        },
+                        POSTFIX,
+                    )
+                }),
+            prop::collection::vec(inner.clone(), 0..3).prop_map(|items| {
+                (
+                    Node::List {
+                        pos: pos(),
+                        items: items.into_iter().map(|i| *operand(i, TERNARY)).collect(),
+                        trailing_comma: false,
+                    },
+                    ATOM,
+                )
+            }),
+            prop::collection::vec(("'[a-z]{1,4}'", inner), 0..3).prop_map(|items| {
+                (
+                    Node::Dict {
+                        pos: pos(),
+                        items: items
+                            .into_iter()
+                            .map(|(key, value)| {
+                                (
+                                    Box::new(Node::String {
+                                        pos: pos(),
+                                        value: key,
+                                    }),
+                                    operand(value, TERNARY),
+                                )
+                            })
+                            .collect(),
+                        trailing_comma: false,
+                    },
+                    ATOM,
+                )
+            }),
+        ]
+    })
+}
+
+/// Format `echo {expr}` and parse it again, returning the expression that was parsed.
+fn roundtrip(expr: &Node) -> (String, Node) {
+    let ast = Node::TopLevel {
+        pos: pos(),
+        body: vec![Node::Echo {
+            pos: pos(),
+            mods: vec![],
+            cmd: "echo".to_string(),
+            list: vec![expr.clone()],
+        }],
+        trivia: vec![],
+    };
+    let source = Formatter::default().format(&ast).unwrap();
+    let lines = source.lines().collect::<Vec<_>>();
+    let parsed = parse_lines(&lines).unwrap_or_else(|e| panic!("{}\n{}", source, e));
+    match parsed {
+        Node::TopLevel { mut body, .. } => match body.remove(0) {
+            Node::Echo { mut list, .. } => (source, list.remove(0)),
+            node => panic!("{}\nparsed as {}", source, node),
+        },
+        node => panic!("{}\nparsed as {}", source, node),
+    }
+}
+
+proptest! {
+    #[test]
+    fn expressions_survive_formatting((expr, _) in expr()) {
+        let (source, parsed) = roundtrip(&expr);
+        prop_assert_eq!(expr.to_string(), parsed.to_string(), "formatted as {}", source);
+    }
+}
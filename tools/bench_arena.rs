@@ -0,0 +1,96 @@
+//! Compare parsing a script into a tree of boxed nodes with parsing it into an arena: how long
+//! each takes, and the most memory allocated at once while doing it. The script is given on the
+//! command line; without one, a script of about 1MB is generated. Build with optimizations for
+//! meaningful numbers.
+//!
+//! ```text
+//! cargo run --release --example bench_arena
+//! cargo run --release --example bench_arena -- path/to/script.vim
+//! ```
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    env, fs,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+use viml_parser::{parse_lines, parse_lines_arena};
+
+// the system allocator, keeping track of how much is allocated and the most that has been
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let now = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        PEAK.fetch_max(now, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn generate() -> String {
+    let mut source = String::new();
+    let mut i = 0;
+    while source.len() < 1 << 20 {
+        source.push_str(&format!(
+            "\" function {i}\n\
+             function! s:F{i}(x, ...) abort\n  \
+               let l:list = [a:x, {i}, 'item', a:0 > 0 ? a:1 : v:null]\n  \
+               for l:item in l:list\n    \
+                 if type(l:item) == v:t_number && l:item > {i}\n      \
+                   call add(g:results, {{'value': l:item, 'name': 's:F{i}'}})\n    \
+                 endif\n  \
+               endfor\n  \
+               return map(copy(l:list), {{_, v -> string(v) . '{i}'}})\n\
+             endfunction\n\
+             augroup group{i}\n  \
+               autocmd!\n  \
+               autocmd BufRead *.{i} call s:F{i}(expand('<afile>'))\n\
+             augroup END\n\
+             nnoremap <silent> <leader>{i} :<C-u>call s:F{i}(v:count)<CR>\n"
+        ));
+        i += 1;
+    }
+    source
+}
+
+fn measure<T>(what: &str, f: impl FnOnce() -> T) -> T {
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    PEAK.store(before, Ordering::Relaxed);
+    let start = Instant::now();
+    let result = f();
+    let elapsed = start.elapsed();
+    let peak = PEAK.load(Ordering::Relaxed) - before;
+    let kept = ALLOCATED.load(Ordering::Relaxed) - before;
+    println!(
+        "{:<10} {:>10.3?} {:>10.1}MB peak {:>10.1}MB kept",
+        what,
+        elapsed,
+        peak as f64 / 1e6,
+        kept as f64 / 1e6
+    );
+    result
+}
+
+fn main() {
+    let source = match env::args().nth(1) {
+        Some(path) => fs::read_to_string(path).expect("couldn't read the script"),
+        None => generate(),
+    };
+    let lines = source.lines().collect::<Vec<&str>>();
+    println!("{} lines, {:.1}MB", lines.len(), source.len() as f64 / 1e6);
+    let node = measure("boxed", || parse_lines(&lines).unwrap());
+    drop(node);
+    let ast = measure("arena", || parse_lines_arena(&lines).unwrap());
+    println!("{} nodes", ast.nodes().len());
+}
@@ -0,0 +1,48 @@
+//! Time how long it takes to set up a parser and to look commands up in its command table. Each
+//! measurement is repeated a number of times (10000 unless given on the command line) and the
+//! average is printed. Build with optimizations for meaningful numbers.
+//!
+//! ```text
+//! cargo run --release --example bench_commands
+//! cargo run --release --example bench_commands -- 100000
+//! ```
+
+use std::{env, time::Instant};
+use viml_parser::{commands, Command, Flag, Parser, ParserKind};
+
+fn time<T>(what: &str, runs: u32, mut f: impl FnMut() -> T) {
+    let start = Instant::now();
+    for _ in 0..runs {
+        std::hint::black_box(f());
+    }
+    let each = start.elapsed() / runs;
+    println!("{:<40} {:>10.3?}", what, each);
+}
+
+fn main() {
+    let runs = env::args()
+        .nth(1)
+        .map(|n| n.parse().expect("the number of runs must be a number"))
+        .unwrap_or(10000);
+    // every accepted name of every built-in command
+    let names = commands::all()
+        .iter()
+        .flat_map(|cmd| (cmd.minlen()..=cmd.name().len()).map(move |len| &cmd.name()[..len]))
+        .collect::<Vec<&str>>();
+    let extra = || {
+        (0..100)
+            .map(|i| Command::new(&format!("Plugin{}", i), 7, Flag::BANG, ParserKind::Common))
+            .collect::<Vec<Command>>()
+    };
+
+    time("Parser::new", runs, Parser::new);
+    time("Parser::new with 100 commands", runs, || {
+        Parser::new().with_commands(extra())
+    });
+    time(&format!("lookup of {} names", names.len()), runs, || {
+        names
+            .iter()
+            .filter(|name| commands::lookup(name).is_some())
+            .count()
+    });
+}
@@ -0,0 +1,181 @@
+//! Check the parser and formatter against real plugins. Each plugin listed in
+//! `tools/corpus.txt` is cloned (or, if it already has been, used as it is) into
+//! `target/corpus`, and every Vim script in it is parsed, formatted, and formatted again to check
+//! that formatting is idempotent. Directories given on the command line are checked instead of
+//! the list. Failures are reported in aggregate, and the exit status is non-zero if there are any.
+//!
+//! ```text
+//! cargo run --example corpus
+//! cargo run --example corpus -- ~/src/my-plugin
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+    process::{self, Command},
+};
+use vimlfmt::{config::FormatterConfig, pipeline::Pipeline, watch::is_vim_file};
+
+/// What went wrong with a file.
+enum Failure {
+    /// The file doesn't parse.
+    Parse(String),
+    /// The formatted file doesn't parse.
+    Output(String),
+    /// Formatting the formatted file changes it, starting at this line.
+    Idempotency(usize),
+    Format(String),
+}
+
+fn format(source: &str) -> Result<String, Failure> {
+    let lines = source.lines().collect::<Vec<&str>>();
+    let pipeline = Pipeline::new(FormatterConfig::default());
+    let ast = pipeline
+        .parse(&lines)
+        .map_err(|e| Failure::Parse(e.to_string()))?;
+    pipeline
+        .formatter(&lines)
+        .format(&ast)
+        .map_err(|e| Failure::Format(e.to_string()))
+}
+
+fn check(source: &str) -> Result<(), Failure> {
+    let once = format(source)?;
+    let twice = format(&once).map_err(|e| match e {
+        Failure::Parse(e) => Failure::Output(e),
+        e => e,
+    })?;
+    match once.lines().zip(twice.lines()).position(|(a, b)| a != b) {
+        Some(i) => Err(Failure::Idempotency(i + 1)),
+        None if once.lines().count() != twice.lines().count() => Err(Failure::Idempotency(
+            once.lines().count().min(twice.lines().count()) + 1,
+        )),
+        None => Ok(()),
+    }
+}
+
+fn vim_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let mut entries = match fs::read_dir(dir) {
+        Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
+        Err(_) => vec![],
+    };
+    entries.sort();
+    for path in entries {
+        if path.is_dir() && path.file_name().is_some_and(|n| n != ".git") {
+            vim_files(&path, files);
+        } else if is_vim_file(&path) {
+            files.push(path);
+        }
+    }
+}
+
+/// The directory a plugin is cloned into, cloning it if it hasn't been.
+fn clone(url: &str, corpus: &Path) -> Option<PathBuf> {
+    let name = url.trim_end_matches('/').rsplit('/').next()?;
+    let dir = corpus.join(name.trim_end_matches(".git"));
+    if dir.is_dir() {
+        return Some(dir);
+    }
+    eprintln!("cloning {}", url);
+    let status = Command::new("git")
+        .args(["clone", "--quiet", "--depth", "1", url])
+        .arg(&dir)
+        .status();
+    match status {
+        Ok(status) if status.success() => Some(dir),
+        _ => {
+            eprintln!("can't clone {}", url);
+            None
+        }
+    }
+}
+
+fn main() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    let plugins = if args.is_empty() {
+        let corpus = root.join("target").join("corpus");
+        fs::create_dir_all(&corpus).expect("can't create target/corpus");
+        fs::read_to_string(root.join("tools").join("corpus.txt"))
+            .expect("can't read tools/corpus.txt")
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .filter_map(|url| clone(url, &corpus))
+            .collect::<Vec<_>>()
+    } else {
+        args.iter().map(PathBuf::from).collect()
+    };
+    let mut total = 0;
+    let mut parse_errors = BTreeMap::<String, Vec<String>>::new();
+    let mut failures = vec![];
+    for plugin in &plugins {
+        let mut files = vec![];
+        vim_files(plugin, &mut files);
+        let mut failed = 0;
+        for path in &files {
+            let source = match fs::read(path) {
+                Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+                Err(_) => continue,
+            };
+            let location = path
+                .strip_prefix(plugin.parent().unwrap_or(plugin))
+                .unwrap_or(path);
+            let location = location.display().to_string();
+            match check(&source) {
+                Ok(()) => continue,
+                Err(Failure::Parse(e)) => {
+                    // Group parse errors by message, without the position, so one missing
+                    // feature shows up as one entry.
+                    let message = e.split_once(": ").map_or(e.as_str(), |(_, m)| m);
+                    let message = message.split(": ").take(2).collect::<Vec<_>>().join(": ");
+                    parse_errors.entry(message).or_default().push(location);
+                }
+                Err(Failure::Output(e)) => failures.push(format!(
+                    "{}: formatted output doesn't parse: {}",
+                    location, e
+                )),
+                Err(Failure::Idempotency(line)) => failures.push(format!(
+                    "{}: formatting isn't idempotent, from line {} of the output",
+                    location, line
+                )),
+                Err(Failure::Format(e)) => {
+                    failures.push(format!("{}: can't format: {}", location, e))
+                }
+            }
+            failed += 1;
+        }
+        total += files.len();
+        println!(
+            "{}: {} files, {} failed",
+            plugin.display(),
+            files.len(),
+            failed
+        );
+    }
+    let parse_failures = parse_errors.values().map(Vec::len).sum::<usize>();
+    if !parse_errors.is_empty() {
+        println!("\nparse errors:");
+        let mut parse_errors = parse_errors.into_iter().collect::<Vec<_>>();
+        parse_errors.sort_by_key(|(_, files)| std::cmp::Reverse(files.len()));
+        for (message, files) in parse_errors {
+            println!("  {} ({} files, e.g. {})", message, files.len(), files[0]);
+        }
+    }
+    if !failures.is_empty() {
+        println!("\nformatting errors:");
+        for failure in &failures {
+            println!("  {}", failure);
+        }
+    }
+    println!(
+        "\ncorpus result: {} files; {} don't parse; {} don't format cleanly",
+        total,
+        parse_failures,
+        failures.len()
+    );
+    if parse_failures + failures.len() > 0 {
+        process::exit(1);
+    }
+}